@@ -0,0 +1,260 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compact proofs that a chain had a certain balance at a certain height.
+//!
+//! A [`BalanceProof`] can be checked against a trusted committee without any storage or
+//! execution: it contains a confirmed block, the validators' signatures of that block and
+//! the hashes needed to recompute the block's execution state hash from the chain balance.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use custom_debug_derive::Debug;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest as _, Sha3_256};
+use thiserror::Error;
+
+use crate::{
+    crypto::{BcsHashable, CryptoError, CryptoHash, PublicKey, Signature},
+    data_types::{Amount, BlockHeight, CertificateKind, Round, Timestamp, VoteValue},
+    hex_debug,
+    identifiers::{ChainId, Owner},
+};
+
+/// The position of the chain balance among the fields of the system execution state.
+///
+/// The verifier needs this without access to the view, so it is fixed here. The prover
+/// derives the position from the view's field list, and a test in `linera-execution` checks
+/// that both agree.
+pub const BALANCE_FIELD_INDEX: usize = 6;
+
+/// A proof that a chain had a given balance after the block at a given height.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceProof {
+    /// The header of the confirmed block.
+    pub header: CertifiedBlockHeader,
+    /// The BCS serialization of the block's body.
+    #[debug(with = "hex_debug")]
+    #[serde(with = "serde_bytes")]
+    pub body: Vec<u8>,
+    /// The round in which the block was confirmed.
+    pub round: Round,
+    /// The validators' signatures of the confirmed block.
+    pub signatures: Vec<(PublicKey, Signature)>,
+    /// The path from the chain balance to the execution state hash in the header.
+    pub path: BalancePath,
+}
+
+/// The fields of a block header that are certified directly, in the order in which they
+/// are hashed. The other header fields are hashes of the body.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertifiedBlockHeader {
+    /// The chain to which the block belongs.
+    pub chain_id: ChainId,
+    /// The epoch of the committee that certified the block.
+    pub epoch: u32,
+    /// The block height.
+    pub height: BlockHeight,
+    /// The timestamp when the block was created.
+    pub timestamp: Timestamp,
+    /// The hash of the chain's execution state after the block.
+    pub state_hash: CryptoHash,
+    /// Certified hash of the previous block in the chain, if any.
+    #[debug(skip_if = Option::is_none)]
+    pub previous_block_hash: Option<CryptoHash>,
+    /// The user signing for the operations in the block, if any.
+    #[debug(skip_if = Option::is_none)]
+    pub authenticated_signer: Option<Owner>,
+}
+
+/// The chain balance, together with the hashes of the rest of the execution state.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalancePath {
+    /// The chain balance.
+    pub balance: Amount,
+    /// The hashes of the other fields of the system execution state, in order.
+    pub other_system_field_hashes: Vec<[u8; 32]>,
+    /// The hash of the user applications' execution states.
+    pub users_hash: [u8; 32],
+}
+
+/// An error found when verifying a [`BalanceProof`].
+#[derive(Debug, Error)]
+pub enum BalanceProofError {
+    #[error("Signature from a validator that is not in the committee")]
+    UnknownValidator,
+    #[error("Signatures from the same validator appear more than once")]
+    ValidatorReuse,
+    #[error("The signatures do not form a quorum of the committee")]
+    MissingQuorum,
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(#[from] CryptoError),
+    #[error("The balance path does not match the state hash of the block")]
+    StateHashMismatch,
+}
+
+impl BalanceProof {
+    /// Verifies the proof against the voting weights of a trusted committee, and returns the
+    /// chain, the height of the block and the chain balance after that block.
+    pub fn verify(
+        &self,
+        committee: &BTreeMap<PublicKey, u64>,
+    ) -> Result<(ChainId, BlockHeight, Amount), BalanceProofError> {
+        let total_votes = committee.values().sum::<u64>();
+        let mut validators = BTreeSet::new();
+        let mut votes = 0;
+        for (validator, _) in &self.signatures {
+            let weight = committee
+                .get(validator)
+                .ok_or(BalanceProofError::UnknownValidator)?;
+            if !validators.insert(validator) {
+                return Err(BalanceProofError::ValidatorReuse);
+            }
+            votes += weight;
+        }
+        if votes < 2 * total_votes / 3 + 1 {
+            return Err(BalanceProofError::MissingQuorum);
+        }
+        let value = VoteValue(self.block_hash(), self.round, CertificateKind::Confirmed);
+        Signature::verify_batch(&value, self.signatures.iter().map(|(v, s)| (v, s)))?;
+        if self.path.state_hash() != self.header.state_hash {
+            return Err(BalanceProofError::StateHashMismatch);
+        }
+        Ok((self.header.chain_id, self.header.height, self.path.balance))
+    }
+
+    /// Returns the hash of the confirmed block, as signed by the validators.
+    pub fn block_hash(&self) -> CryptoHash {
+        let mut bytes = bcs::to_bytes(&self.header).expect("Serialization should not fail");
+        bytes.extend_from_slice(&self.body);
+        CryptoHash::from_bcs_bytes("Block", &bytes)
+    }
+}
+
+impl BalancePath {
+    /// Returns the execution state hash that this path leads to.
+    pub fn state_hash(&self) -> CryptoHash {
+        let balance_bytes = bcs::to_bytes(&self.balance).expect("Serialization should not fail");
+        let balance_hash: [u8; 32] = Sha3_256::digest(balance_bytes).into();
+        let mut field_hashes = self.other_system_field_hashes.iter();
+        let mut system_hasher = Sha3_256::new();
+        for hash in field_hashes.by_ref().take(BALANCE_FIELD_INDEX) {
+            system_hasher.update(hash);
+        }
+        system_hasher.update(balance_hash);
+        for hash in field_hashes {
+            system_hasher.update(hash);
+        }
+        let mut state_hasher = Sha3_256::new();
+        state_hasher.update(system_hasher.finalize());
+        state_hasher.update(self.users_hash);
+        CryptoHash::new(&ExecutionStateViewHash(state_hasher.finalize().into()))
+    }
+}
+
+/// The hash of an execution state view. This must match the hash type generated for
+/// `ExecutionStateView` in `linera-execution`.
+#[derive(Serialize, Deserialize)]
+struct ExecutionStateViewHash([u8; 32]);
+
+impl<'de> BcsHashable<'de> for ExecutionStateViewHash {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    /// Returns a proof of a balance of 7 tokens, signed by the first three of the given
+    /// validators.
+    fn make_proof(key_pairs: &[KeyPair]) -> BalanceProof {
+        let path = BalancePath {
+            balance: Amount::from_tokens(7),
+            other_system_field_hashes: (0..14).map(|i| [i; 32]).collect(),
+            users_hash: [42; 32],
+        };
+        let mut proof = BalanceProof {
+            header: CertifiedBlockHeader {
+                chain_id: ChainId::root(1),
+                epoch: 0,
+                height: BlockHeight(3),
+                timestamp: Timestamp::from(1000),
+                state_hash: path.state_hash(),
+                previous_block_hash: Some(CryptoHash::test_hash("previous block")),
+                authenticated_signer: None,
+            },
+            body: b"body".to_vec(),
+            round: Round::MultiLeader(1),
+            signatures: Vec::new(),
+            path,
+        };
+        let value = VoteValue(proof.block_hash(), proof.round, CertificateKind::Confirmed);
+        proof.signatures = key_pairs[..3]
+            .iter()
+            .map(|key_pair| (key_pair.public(), Signature::new(&value, key_pair)))
+            .collect();
+        proof
+    }
+
+    fn make_committee(key_pairs: &[KeyPair]) -> BTreeMap<PublicKey, u64> {
+        key_pairs
+            .iter()
+            .map(|key_pair| (key_pair.public(), 1))
+            .collect()
+    }
+
+    #[test]
+    fn verify_balance_proof() {
+        let key_pairs = (0..4).map(|_| KeyPair::generate()).collect::<Vec<_>>();
+        let proof = make_proof(&key_pairs);
+        let committee = make_committee(&key_pairs);
+        assert_eq!(
+            proof.verify(&committee).unwrap(),
+            (ChainId::root(1), BlockHeight(3), Amount::from_tokens(7))
+        );
+        let bytes = bcs::to_bytes(&proof).unwrap();
+        let proof = bcs::from_bytes::<BalanceProof>(&bytes).unwrap();
+        assert!(proof.verify(&committee).is_ok());
+    }
+
+    #[test]
+    fn reject_tampered_balance_proof() {
+        let key_pairs = (0..4).map(|_| KeyPair::generate()).collect::<Vec<_>>();
+        let committee = make_committee(&key_pairs);
+
+        let mut proof = make_proof(&key_pairs);
+        proof.path.balance = Amount::from_tokens(8);
+        assert!(matches!(
+            proof.verify(&committee),
+            Err(BalanceProofError::StateHashMismatch)
+        ));
+
+        let mut proof = make_proof(&key_pairs);
+        proof.header.height = BlockHeight(4);
+        assert!(matches!(
+            proof.verify(&committee),
+            Err(BalanceProofError::InvalidSignature(_))
+        ));
+
+        let mut proof = make_proof(&key_pairs);
+        proof.signatures.pop();
+        assert!(matches!(
+            proof.verify(&committee),
+            Err(BalanceProofError::MissingQuorum)
+        ));
+
+        let mut proof = make_proof(&key_pairs);
+        let duplicate = proof.signatures[0];
+        proof.signatures[2] = duplicate;
+        assert!(matches!(
+            proof.verify(&committee),
+            Err(BalanceProofError::ValidatorReuse)
+        ));
+
+        let proof = make_proof(&key_pairs);
+        let other_committee = make_committee(&key_pairs[1..]);
+        assert!(matches!(
+            proof.verify(&other_committee),
+            Err(BalanceProofError::UnknownValidator)
+        ));
+    }
+}
@@ -4,6 +4,9 @@
 
 //! Define the cryptographic primitives used by the Linera protocol.
 
+mod derivation;
+pub mod secp256k1;
+
 use std::{borrow::Cow, fmt, io, num::ParseIntError, str::FromStr};
 
 use alloy_primitives::{FixedBytes, Keccak256, B256};
@@ -24,6 +27,10 @@ use {
     std::ops::RangeInclusive,
 };
 
+pub use self::{
+    derivation::{Seed, MAX_SEED_LENGTH, MIN_SEED_LENGTH},
+    secp256k1::{Secp256k1KeyPair, Secp256k1PublicKey, Secp256k1Signature},
+};
 use crate::doc_scalar;
 
 /// A signature key-pair.
@@ -52,6 +59,37 @@ impl<'de> BcsHashable<'de> for CryptoHashVec {}
 #[derive(Eq, PartialEq, Copy, Clone)]
 pub struct Signature(pub dalek::Signature);
 
+/// The secret key of a chain owner, for any of the supported signature schemes.
+///
+/// Validators always sign with an ed25519 [`KeyPair`].
+pub enum AccountSecretKey {
+    /// An ed25519 key-pair.
+    Ed25519(KeyPair),
+    /// A secp256k1 key-pair.
+    Secp256k1(Secp256k1KeyPair),
+}
+
+/// The public key of a chain owner, for any of the supported signature schemes.
+///
+/// In human-readable formats, ed25519 keys are written exactly like a [`PublicKey`], and
+/// secp256k1 keys are told apart by their length.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Hash)]
+pub enum AccountPublicKey {
+    /// An ed25519 public key.
+    Ed25519(PublicKey),
+    /// A secp256k1 public key.
+    Secp256k1(Secp256k1PublicKey),
+}
+
+/// A signature of a chain owner, for any of the supported signature schemes.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum AccountSignature {
+    /// An ed25519 signature.
+    Ed25519(Signature),
+    /// A secp256k1 signature.
+    Secp256k1(Secp256k1Signature),
+}
+
 /// Error type for cryptographic errors.
 #[derive(Error, Debug)]
 #[allow(missing_docs)]
@@ -72,6 +110,11 @@ pub enum CryptoError {
         expected = dalek::PUBLIC_KEY_LENGTH,
     )]
     IncorrectPublicKeySize(usize),
+    #[error(
+        "Byte slice has length {0} but a `Seed` requires between {MIN_SEED_LENGTH} and \
+        {MAX_SEED_LENGTH} bytes"
+    )]
+    IncorrectSeedSize(usize),
     #[error("Could not parse integer: {0}")]
     ParseIntError(#[from] ParseIntError),
 }
@@ -133,6 +176,184 @@ impl KeyPair {
     }
 }
 
+impl AccountSecretKey {
+    /// Obtains the public key of this secret key.
+    pub fn public(&self) -> AccountPublicKey {
+        match self {
+            AccountSecretKey::Ed25519(key_pair) => AccountPublicKey::Ed25519(key_pair.public()),
+            AccountSecretKey::Secp256k1(key_pair) => AccountPublicKey::Secp256k1(key_pair.public()),
+        }
+    }
+
+    /// Copies the secret key.
+    ///
+    /// The `Clone` and `Copy` traits are deliberately not implemented for `AccountSecretKey` to
+    /// prevent accidental copies of secret keys.
+    pub fn copy(&self) -> AccountSecretKey {
+        match self {
+            AccountSecretKey::Ed25519(key_pair) => AccountSecretKey::Ed25519(key_pair.copy()),
+            AccountSecretKey::Secp256k1(key_pair) => AccountSecretKey::Secp256k1(key_pair.copy()),
+        }
+    }
+}
+
+impl From<KeyPair> for AccountSecretKey {
+    fn from(key_pair: KeyPair) -> Self {
+        AccountSecretKey::Ed25519(key_pair)
+    }
+}
+
+impl From<Secp256k1KeyPair> for AccountSecretKey {
+    fn from(key_pair: Secp256k1KeyPair) -> Self {
+        AccountSecretKey::Secp256k1(key_pair)
+    }
+}
+
+impl From<PublicKey> for AccountPublicKey {
+    fn from(public_key: PublicKey) -> Self {
+        AccountPublicKey::Ed25519(public_key)
+    }
+}
+
+impl From<Secp256k1PublicKey> for AccountPublicKey {
+    fn from(public_key: Secp256k1PublicKey) -> Self {
+        AccountPublicKey::Secp256k1(public_key)
+    }
+}
+
+impl AccountSignature {
+    /// Computes a signature.
+    pub fn new<'de, T>(value: &T, secret: &AccountSecretKey) -> Self
+    where
+        T: BcsSignable<'de>,
+    {
+        match secret {
+            AccountSecretKey::Ed25519(key_pair) => {
+                AccountSignature::Ed25519(Signature::new(value, key_pair))
+            }
+            AccountSecretKey::Secp256k1(key_pair) => {
+                AccountSignature::Secp256k1(Secp256k1Signature::new(value, key_pair))
+            }
+        }
+    }
+
+    /// Checks a signature. Fails if the author's key is for a different signature scheme.
+    pub fn check<'de, T>(&self, value: &T, author: AccountPublicKey) -> Result<(), CryptoError>
+    where
+        T: BcsSignable<'de> + fmt::Debug,
+    {
+        match (self, author) {
+            (AccountSignature::Ed25519(signature), AccountPublicKey::Ed25519(author)) => {
+                signature.check(value, author)
+            }
+            (AccountSignature::Secp256k1(signature), AccountPublicKey::Secp256k1(author)) => {
+                signature.check(value, author)
+            }
+            _ => Err(CryptoError::InvalidSignature {
+                error: "signature scheme does not match the public key".to_string(),
+                type_name: T::type_name().to_string(),
+            }),
+        }
+    }
+}
+
+impl Serialize for AccountPublicKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            #[derive(Serialize)]
+            #[serde(rename = "AccountPublicKey")]
+            enum Foo<'a> {
+                Ed25519(&'a PublicKey),
+                Secp256k1(&'a Secp256k1PublicKey),
+            }
+
+            match self {
+                AccountPublicKey::Ed25519(public_key) => Foo::Ed25519(public_key),
+                AccountPublicKey::Secp256k1(public_key) => Foo::Secp256k1(public_key),
+            }
+            .serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AccountPublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            Self::from_str(&s).map_err(serde::de::Error::custom)
+        } else {
+            #[derive(Deserialize)]
+            #[serde(rename = "AccountPublicKey")]
+            enum Foo {
+                Ed25519(PublicKey),
+                Secp256k1(Secp256k1PublicKey),
+            }
+
+            Ok(match Foo::deserialize(deserializer)? {
+                Foo::Ed25519(public_key) => AccountPublicKey::Ed25519(public_key),
+                Foo::Secp256k1(public_key) => AccountPublicKey::Secp256k1(public_key),
+            })
+        }
+    }
+}
+
+impl FromStr for AccountPublicKey {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = hex::decode(s)?;
+        (value.as_slice()).try_into()
+    }
+}
+
+impl TryFrom<&[u8]> for AccountPublicKey {
+    type Error = CryptoError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() == secp256k1::SECP256K1_PUBLIC_KEY_LENGTH {
+            Ok(AccountPublicKey::Secp256k1(value.try_into()?))
+        } else {
+            Ok(AccountPublicKey::Ed25519(value.try_into()?))
+        }
+    }
+}
+
+impl AccountPublicKey {
+    /// Returns the bytes of the public key. The signature scheme is determined by their length.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            AccountPublicKey::Ed25519(public_key) => &public_key.0,
+            AccountPublicKey::Secp256k1(public_key) => &public_key.0,
+        }
+    }
+}
+
+impl fmt::Display for AccountPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountPublicKey::Ed25519(public_key) => public_key.fmt(f),
+            AccountPublicKey::Secp256k1(public_key) => public_key.fmt(f),
+        }
+    }
+}
+
+impl fmt::Debug for AccountPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountPublicKey::Ed25519(public_key) => fmt::Debug::fmt(public_key, f),
+            AccountPublicKey::Secp256k1(public_key) => fmt::Debug::fmt(public_key, f),
+        }
+    }
+}
+
 impl Serialize for PublicKey {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -379,7 +600,21 @@ pub trait HasTypeName {
 /// Activate the blanket implementation of `Hashable` based on serde and BCS.
 /// * We use `serde_name` to extract a seed from the name of structs and enums.
 /// * We use `BCS` to generate canonical bytes suitable for hashing.
-pub trait BcsHashable<'de>: Serialize + Deserialize<'de> {}
+pub trait BcsHashable<'de>: Serialize + Deserialize<'de> {
+    /// Returns the canonical BCS serialization of `self`. These are the bytes that are
+    /// hashed and signed.
+    fn to_bytes(&self) -> Vec<u8> {
+        bcs::to_bytes(self).expect("Message serialization should not fail")
+    }
+
+    /// Reads a value from its canonical BCS serialization.
+    fn from_bytes(bytes: &'de [u8]) -> Result<Self, bcs::Error>
+    where
+        Self: Sized,
+    {
+        bcs::from_bytes(bytes)
+    }
+}
 
 /// Activate the blanket implementation of `Signable` based on serde and BCS.
 /// * We use `serde_name` to extract a seed from the name of structs and enums.
@@ -397,7 +632,9 @@ where
         let name = <Self as HasTypeName>::type_name();
         // Note: This assumes that names never contain the separator `::`.
         write!(hasher, "{}::", name).expect("Hasher should not fail");
-        bcs::serialize_into(hasher, &self).expect("Message serialization should not fail");
+        hasher
+            .write_all(&self.to_bytes())
+            .expect("Hasher should not fail");
     }
 }
 
@@ -441,6 +678,17 @@ impl CryptoHash {
         CryptoHash(hasher.0.finalize())
     }
 
+    /// Computes the hash of a value of the type named `type_name`, given its BCS
+    /// serialization. This is the same as [`CryptoHash::new`], for values that can't be
+    /// deserialized here.
+    pub fn from_bcs_bytes(type_name: &str, bcs_bytes: &[u8]) -> Self {
+        let mut hasher = Keccak256::new();
+        hasher.update(type_name.as_bytes());
+        hasher.update(b"::");
+        hasher.update(bcs_bytes);
+        CryptoHash(hasher.finalize())
+    }
+
     /// Reads the bytes of the hash value.
     pub fn as_bytes(&self) -> &B256 {
         &self.0
@@ -542,6 +790,39 @@ impl Signature {
             }
         })
     }
+
+    fn verify_batch_of_values_internal<'a, 'de, T, I>(votes: I) -> Result<(), dalek::SignatureError>
+    where
+        T: BcsSignable<'de> + 'a,
+        I: IntoIterator<Item = (&'a T, &'a PublicKey, &'a Signature)>,
+    {
+        let mut msgs = Vec::new();
+        let mut signatures = Vec::new();
+        let mut public_keys = Vec::new();
+        for (value, addr, sig) in votes.into_iter() {
+            let mut msg = Vec::new();
+            value.write(&mut msg);
+            msgs.push(msg);
+            signatures.push(sig.0);
+            public_keys.push(dalek::VerifyingKey::from_bytes(&addr.0)?);
+        }
+        let messages = msgs.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        dalek::verify_batch(&messages[..], &signatures[..], &public_keys[..])
+    }
+
+    /// Verifies a batch of signatures, each one over its own value.
+    pub fn verify_batch_of_values<'a, 'de, T, I>(votes: I) -> Result<(), CryptoError>
+    where
+        T: BcsSignable<'de> + 'a,
+        I: IntoIterator<Item = (&'a T, &'a PublicKey, &'a Signature)>,
+    {
+        Signature::verify_batch_of_values_internal(votes).map_err(|error| {
+            CryptoError::InvalidSignature {
+                error: format!("batched {}", error),
+                type_name: T::type_name().to_string(),
+            }
+        })
+    }
 }
 
 impl WitType for CryptoHash {
@@ -710,6 +991,10 @@ impl<'de> BcsHashable<'de> for PublicKey {}
 doc_scalar!(CryptoHash, "A Keccak256 value");
 doc_scalar!(PublicKey, "A signature public key");
 doc_scalar!(Signature, "A signature value");
+doc_scalar!(
+    AccountPublicKey,
+    "The public key of a chain owner, for any of the supported signature schemes"
+);
 
 /// A BCS-signable struct for testing.
 #[cfg(with_testing)]
@@ -750,6 +1035,65 @@ fn test_signatures() {
     assert!(s.check(&foo, addr1).is_err());
 }
 
+#[test]
+fn test_account_signatures() {
+    let ed25519_key = AccountSecretKey::from(KeyPair::generate());
+    let secp256k1_key = AccountSecretKey::from(Secp256k1KeyPair::generate());
+    let ts = TestString("hello".into());
+
+    for (key, other_key) in [
+        (&ed25519_key, &secp256k1_key),
+        (&secp256k1_key, &ed25519_key),
+    ] {
+        let s = AccountSignature::new(&ts, key);
+        assert!(s.check(&ts, key.public()).is_ok());
+        assert!(s.check(&ts, other_key.public()).is_err());
+    }
+}
+
+#[test]
+fn test_canonical_bytes() {
+    let ts = TestString::new("hello");
+    let bytes = ts.to_bytes();
+    assert_eq!(bytes, b"\x05hello");
+    assert_eq!(TestString::from_bytes(&bytes).unwrap().0, "hello");
+    assert!(TestString::from_bytes(b"\x06hello").is_err());
+    assert!(TestString::from_bytes(b"\x05hello!").is_err());
+    assert_eq!(
+        CryptoHash::new(&ts),
+        CryptoHash::from_bcs_bytes("TestString", &bytes)
+    );
+}
+
+#[test]
+fn test_account_public_key_serialization() {
+    let ed25519_key = KeyPair::generate().public();
+    let secp256k1_key = Secp256k1KeyPair::generate().public();
+
+    // Ed25519 keys keep the same human-readable form as a `PublicKey`.
+    let json = serde_json::to_string(&ed25519_key).unwrap();
+    assert_eq!(
+        serde_json::from_str::<AccountPublicKey>(&json).unwrap(),
+        AccountPublicKey::Ed25519(ed25519_key)
+    );
+
+    for public_key in [
+        AccountPublicKey::from(ed25519_key),
+        AccountPublicKey::from(secp256k1_key),
+    ] {
+        let json = serde_json::to_string(&public_key).unwrap();
+        assert_eq!(
+            serde_json::from_str::<AccountPublicKey>(&json).unwrap(),
+            public_key
+        );
+        let bytes = bcs::to_bytes(&public_key).unwrap();
+        assert_eq!(
+            bcs::from_bytes::<AccountPublicKey>(&bytes).unwrap(),
+            public_key
+        );
+    }
+}
+
 /// Reads the `bytes` as four little-endian unsigned 64-bit integers and returns them.
 fn le_bytes_to_u64_array(bytes: &[u8]) -> [u64; 4] {
     let mut integers = [0u64; 4];
@@ -0,0 +1,238 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hierarchical deterministic derivation of ed25519 keys, following
+//! [SLIP-0010](https://github.com/satoshilabs/slips/blob/master/slip-0010.md).
+
+use std::fmt;
+
+use ed25519_dalek as dalek;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha512;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[cfg(with_getrandom)]
+use super::CryptoRng;
+use super::{CryptoError, KeyPair};
+
+/// The minimum length in bytes of a [`Seed`].
+pub const MIN_SEED_LENGTH: usize = 16;
+
+/// The maximum length in bytes of a [`Seed`].
+pub const MAX_SEED_LENGTH: usize = 64;
+
+/// The bit marking an index of a derivation path as hardened.
+const HARDENED_BIT: u32 = 1 << 31;
+
+/// The key of the HMAC used to compute the master key from a seed.
+const ED25519_CURVE_KEY: &[u8] = b"ed25519 seed";
+
+/// The secret seed from which a tree of key-pairs is derived. Its bytes are erased from memory
+/// when it is dropped.
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct Seed(Vec<u8>);
+
+impl Seed {
+    /// Creates a [`Seed`] from its bytes, which must be between [`MIN_SEED_LENGTH`] and
+    /// [`MAX_SEED_LENGTH`] bytes long.
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Result<Self, CryptoError> {
+        let seed = Seed(bytes.into());
+        if !(MIN_SEED_LENGTH..=MAX_SEED_LENGTH).contains(&seed.0.len()) {
+            return Err(CryptoError::IncorrectSeedSize(seed.0.len()));
+        }
+        Ok(seed)
+    }
+
+    #[cfg(with_getrandom)]
+    /// Generates a new 32-byte seed from the given RNG. Use with care.
+    pub fn generate_from<R: CryptoRng>(rng: &mut R) -> Self {
+        let mut bytes = vec![0; 32];
+        rng.fill_bytes(&mut bytes);
+        Seed(bytes)
+    }
+
+    /// Returns the bytes of the seed.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl KeyPair {
+    /// Derives the key-pair at the given `path` of the tree generated by `seed`.
+    ///
+    /// Ed25519 only supports hardened derivation, so every index of the path is hardened: the
+    /// path `[0, 1]` denotes `m/0'/1'`. The result is stable across releases.
+    pub fn derive_from_seed(seed: &Seed, path: &[u32]) -> KeyPair {
+        let (mut key, mut chain_code) = hmac_sha512(ED25519_CURVE_KEY, &[&seed.0]);
+        for index in path {
+            let index = (index | HARDENED_BIT).to_be_bytes();
+            (key, chain_code) = hmac_sha512(&chain_code, &[&[0], &key, &index]);
+        }
+        let key_pair = KeyPair(dalek::SigningKey::from_bytes(&key));
+        key.zeroize();
+        chain_code.zeroize();
+        key_pair
+    }
+}
+
+/// Computes the HMAC-SHA512 of the concatenated `data` and splits it into a secret key and a
+/// chain code.
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    for bytes in data {
+        mac.update(bytes);
+    }
+    let output = mac.finalize().into_bytes();
+    let mut secret_key = [0; 32];
+    let mut chain_code = [0; 32];
+    secret_key.copy_from_slice(&output[..32]);
+    chain_code.copy_from_slice(&output[32..]);
+    (secret_key, chain_code)
+}
+
+impl Serialize for Seed {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        // This is only used for JSON configuration.
+        assert!(serializer.is_human_readable());
+        serializer.serialize_str(&hex::encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Seed {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        // This is only used for JSON configuration.
+        assert!(deserializer.is_human_readable());
+        let s = String::deserialize(deserializer)?;
+        let bytes = hex::decode(s).map_err(serde::de::Error::custom)?;
+        Seed::new(bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Debug for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the secret seed.
+        f.write_str("Seed(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks the key-pair derived from `seed` at `path` against the expected secret and public
+    /// keys.
+    fn check_derivation(seed: &str, path: &[u32], secret_key: &str, public_key: &str) {
+        let seed = Seed::new(hex::decode(seed).unwrap()).unwrap();
+        let key_pair = KeyPair::derive_from_seed(&seed, path);
+        assert_eq!(hex::encode(key_pair.0.to_bytes()), secret_key);
+        assert_eq!(key_pair.public().to_string(), public_key);
+    }
+
+    /// Test vector 1 for ed25519 from SLIP-0010.
+    #[test]
+    fn test_slip10_vector_1() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+        check_derivation(
+            seed,
+            &[],
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+            "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed",
+        );
+        check_derivation(
+            seed,
+            &[0],
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+            "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c",
+        );
+        check_derivation(
+            seed,
+            &[0, 1],
+            "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2",
+            "1932a5270f335bed617d5b935c80aedb1a35bd9fc1e31acafd5372c30f5c1187",
+        );
+        check_derivation(
+            seed,
+            &[0, 1, 2],
+            "92a5b23c0b8a99e37d07df3fb9966917f5d06e02ddbd909c7e184371463e9fc9",
+            "ae98736566d30ed0e9d2f4486a64bc95740d89c7db33f52121f8ea8f76ff0fc1",
+        );
+        check_derivation(
+            seed,
+            &[0, 1, 2, 2],
+            "30d1dc7e5fc04c31219ab25a27ae00b50f6fd66622f6e9c913253d6511d1e662",
+            "8abae2d66361c879b900d204ad2cc4984fa2aa344dd7ddc46007329ac76c429c",
+        );
+        check_derivation(
+            seed,
+            &[0, 1, 2, 2, 1_000_000_000],
+            "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+            "3c24da049451555d51a7014a37337aa4e12d41e485abccfa46b47dfb2af54b7a",
+        );
+    }
+
+    /// Test vector 2 for ed25519 from SLIP-0010.
+    #[test]
+    fn test_slip10_vector_2() {
+        let seed = "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542";
+        check_derivation(
+            seed,
+            &[],
+            "171cb88b1b3c1db25add599712e36245d75bc65a1a5c9e18d76f9f2b1eab4012",
+            "8fe9693f8fa62a4305a140b9764c5ee01e455963744fe18204b4fb948249308a",
+        );
+        check_derivation(
+            seed,
+            &[0],
+            "1559eb2bbec5790b0c65d8693e4d0875b1747f4970ae8b650486ed7470845635",
+            "86fab68dcb57aa196c77c5f264f215a112c22a912c10d123b0d03c3c28ef1037",
+        );
+        check_derivation(
+            seed,
+            &[0, 2_147_483_647],
+            "ea4f5bfe8694d8bb74b7b59404632fd5968b774ed545e810de9c32a4fb4192f4",
+            "5ba3b9ac6e90e83effcd25ac4e58a1365a9e35a3d3ae5eb07b9e4d90bcf7506d",
+        );
+        check_derivation(
+            seed,
+            &[0, 2_147_483_647, 1],
+            "3757c7577170179c7868353ada796c839135b3d30554bbb74a4b1e4a5a58505c",
+            "2e66aa57069c86cc18249aecf5cb5a9cebbfd6fadeab056254763874a9352b45",
+        );
+    }
+
+    #[test]
+    fn test_hardened_indices() {
+        let seed = Seed::new([7; 32]).unwrap();
+        assert_eq!(
+            KeyPair::derive_from_seed(&seed, &[3]).public(),
+            KeyPair::derive_from_seed(&seed, &[3 | HARDENED_BIT]).public()
+        );
+    }
+
+    #[test]
+    fn test_seed_size() {
+        assert!(Seed::new([0; MIN_SEED_LENGTH - 1]).is_err());
+        assert!(Seed::new([0; MIN_SEED_LENGTH]).is_ok());
+        assert!(Seed::new([0; MAX_SEED_LENGTH]).is_ok());
+        assert!(Seed::new([0; MAX_SEED_LENGTH + 1]).is_err());
+
+        let seed = Seed::new([42; 32]).unwrap();
+        let json = serde_json::to_string(&seed).unwrap();
+        let seed_copy = serde_json::from_str::<Seed>(&json).unwrap();
+        assert_eq!(seed_copy.as_bytes(), seed.as_bytes());
+    }
+
+    #[test]
+    fn test_seed_zeroize() {
+        let mut seed = Seed::new([42; 32]).unwrap();
+        seed.zeroize();
+        assert!(seed.as_bytes().is_empty());
+    }
+}
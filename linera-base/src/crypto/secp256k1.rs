@@ -0,0 +1,312 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Defines the secp256k1 signature scheme, which can be used by chain owners.
+
+use std::{fmt, str::FromStr};
+
+use k256::ecdsa::{
+    self,
+    signature::{Signer, Verifier},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(with_getrandom)]
+use super::CryptoRng;
+use super::{BcsHashable, BcsSignable, CryptoError, HasTypeName, Hashable};
+
+/// The length in bytes of a compressed secp256k1 public key.
+pub const SECP256K1_PUBLIC_KEY_LENGTH: usize = 33;
+
+/// A secp256k1 key-pair.
+pub struct Secp256k1KeyPair(ecdsa::SigningKey);
+
+/// A secp256k1 public key, in compressed SEC1 form.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Hash)]
+pub struct Secp256k1PublicKey(pub [u8; SECP256K1_PUBLIC_KEY_LENGTH]);
+
+/// A secp256k1 signature value.
+#[derive(Eq, PartialEq, Copy, Clone)]
+pub struct Secp256k1Signature(pub ecdsa::Signature);
+
+impl Secp256k1KeyPair {
+    #[cfg(all(with_getrandom, with_testing))]
+    /// Generates a new key-pair.
+    pub fn generate() -> Self {
+        let mut rng = rand::rngs::OsRng;
+        Self::generate_from(&mut rng)
+    }
+
+    #[cfg(with_getrandom)]
+    /// Generates a new key-pair from the given RNG. Use with care.
+    pub fn generate_from<R: CryptoRng>(rng: &mut R) -> Self {
+        Secp256k1KeyPair(ecdsa::SigningKey::random(rng))
+    }
+
+    /// Obtains the public key of a key-pair.
+    pub fn public(&self) -> Secp256k1PublicKey {
+        let point = self.0.verifying_key().to_encoded_point(true);
+        let mut bytes = [0u8; SECP256K1_PUBLIC_KEY_LENGTH];
+        bytes.copy_from_slice(point.as_bytes());
+        Secp256k1PublicKey(bytes)
+    }
+
+    /// Copies the key-pair, **including the secret key**.
+    ///
+    /// The `Clone` and `Copy` traits are deliberately not implemented for `Secp256k1KeyPair` to
+    /// prevent accidental copies of secret keys.
+    pub fn copy(&self) -> Secp256k1KeyPair {
+        Secp256k1KeyPair(self.0.clone())
+    }
+}
+
+impl Secp256k1Signature {
+    /// Computes a signature.
+    pub fn new<'de, T>(value: &T, secret: &Secp256k1KeyPair) -> Self
+    where
+        T: BcsSignable<'de>,
+    {
+        let mut message = Vec::new();
+        value.write(&mut message);
+        Secp256k1Signature(secret.0.sign(&message))
+    }
+
+    /// Returns the bytes of the signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes().to_vec()
+    }
+
+    fn check_internal<'de, T>(
+        &self,
+        value: &T,
+        author: Secp256k1PublicKey,
+    ) -> Result<(), ecdsa::Error>
+    where
+        T: BcsSignable<'de>,
+    {
+        let mut message = Vec::new();
+        value.write(&mut message);
+        let public_key = ecdsa::VerifyingKey::from_sec1_bytes(&author.0)?;
+        public_key.verify(&message, &self.0)
+    }
+
+    /// Checks a signature.
+    pub fn check<'de, T>(&self, value: &T, author: Secp256k1PublicKey) -> Result<(), CryptoError>
+    where
+        T: BcsSignable<'de> + fmt::Debug,
+    {
+        self.check_internal(value, author)
+            .map_err(|error| CryptoError::InvalidSignature {
+                error: error.to_string(),
+                type_name: T::type_name().to_string(),
+            })
+    }
+}
+
+impl TryFrom<&[u8]> for Secp256k1Signature {
+    type Error = CryptoError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let signature =
+            ecdsa::Signature::from_slice(value).map_err(|error| CryptoError::InvalidSignature {
+                error: error.to_string(),
+                type_name: "Secp256k1Signature".to_string(),
+            })?;
+        Ok(Secp256k1Signature(signature))
+    }
+}
+
+impl Serialize for Secp256k1KeyPair {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        // This is only used for JSON configuration.
+        assert!(serializer.is_human_readable());
+        serializer.serialize_str(&hex::encode(self.0.to_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Secp256k1KeyPair {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        // This is only used for JSON configuration.
+        assert!(deserializer.is_human_readable());
+        let s = String::deserialize(deserializer)?;
+        let value = hex::decode(s).map_err(serde::de::Error::custom)?;
+        let key = ecdsa::SigningKey::from_slice(&value).map_err(serde::de::Error::custom)?;
+        Ok(Secp256k1KeyPair(key))
+    }
+}
+
+impl Serialize for Secp256k1PublicKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            serializer
+                .serialize_newtype_struct("Secp256k1PublicKey", serde_bytes::Bytes::new(&self.0))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Secp256k1PublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            Self::from_str(&s).map_err(serde::de::Error::custom)
+        } else {
+            #[derive(Deserialize)]
+            #[serde(rename = "Secp256k1PublicKey")]
+            struct Foo(#[serde(with = "serde_bytes")] Vec<u8>);
+
+            let value = Foo::deserialize(deserializer)?;
+            Self::try_from(value.0.as_slice()).map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+impl Serialize for Secp256k1Signature {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            let bytes = self.to_bytes();
+            serializer
+                .serialize_newtype_struct("Secp256k1Signature", serde_bytes::Bytes::new(&bytes))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Secp256k1Signature {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let bytes = if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            hex::decode(s).map_err(serde::de::Error::custom)?
+        } else {
+            #[derive(Deserialize)]
+            #[serde(rename = "Secp256k1Signature")]
+            struct Foo(#[serde(with = "serde_bytes")] Vec<u8>);
+
+            Foo::deserialize(deserializer)?.0
+        };
+        Self::try_from(bytes.as_slice()).map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for Secp256k1PublicKey {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = hex::decode(s)?;
+        (value.as_slice()).try_into()
+    }
+}
+
+impl TryFrom<&[u8]> for Secp256k1PublicKey {
+    type Error = CryptoError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != SECP256K1_PUBLIC_KEY_LENGTH {
+            return Err(CryptoError::IncorrectPublicKeySize(value.len()));
+        }
+        let mut public_key = [0u8; SECP256K1_PUBLIC_KEY_LENGTH];
+        public_key.copy_from_slice(value);
+        Ok(Secp256k1PublicKey(public_key))
+    }
+}
+
+impl fmt::Display for Secp256k1PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0[..]))
+    }
+}
+
+impl fmt::Debug for Secp256k1PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0[..8]))
+    }
+}
+
+impl fmt::Display for Secp256k1Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0.to_bytes()))
+    }
+}
+
+impl fmt::Debug for Secp256k1Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0.to_bytes()[0..8]))
+    }
+}
+
+impl<'de> BcsHashable<'de> for Secp256k1PublicKey {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::TestString;
+
+    #[test]
+    fn test_secp256k1_signatures() {
+        let key1 = Secp256k1KeyPair::generate();
+        let addr1 = key1.public();
+        let key2 = Secp256k1KeyPair::generate();
+        let addr2 = key2.public();
+
+        let ts = TestString("hello".into());
+        let tsx = TestString("hellox".into());
+
+        let s = Secp256k1Signature::new(&ts, &key1);
+        assert!(s.check(&ts, addr1).is_ok());
+        assert!(s.check(&ts, addr2).is_err());
+        assert!(s.check(&tsx, addr1).is_err());
+    }
+
+    #[test]
+    fn test_secp256k1_serialization() {
+        let key = Secp256k1KeyPair::generate();
+        let public_key = key.public();
+        let signature = Secp256k1Signature::new(&TestString::new("hello"), &key);
+
+        let bytes = bcs::to_bytes(&public_key).unwrap();
+        assert_eq!(
+            bcs::from_bytes::<Secp256k1PublicKey>(&bytes).unwrap(),
+            public_key
+        );
+        let json = serde_json::to_string(&public_key).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Secp256k1PublicKey>(&json).unwrap(),
+            public_key
+        );
+
+        let bytes = bcs::to_bytes(&signature).unwrap();
+        assert_eq!(
+            bcs::from_bytes::<Secp256k1Signature>(&bytes).unwrap(),
+            signature
+        );
+        let json = serde_json::to_string(&signature).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Secp256k1Signature>(&json).unwrap(),
+            signature
+        );
+
+        let json = serde_json::to_string(&key).unwrap();
+        let key_copy = serde_json::from_str::<Secp256k1KeyPair>(&json).unwrap();
+        assert_eq!(key_copy.public(), public_key);
+    }
+}
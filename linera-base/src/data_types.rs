@@ -31,7 +31,7 @@ use thiserror::Error;
 #[cfg(with_metrics)]
 use crate::prometheus_util::{bucket_latencies, register_histogram_vec, MeasureLatency};
 use crate::{
-    crypto::{BcsHashable, CryptoHash},
+    crypto::{BcsHashable, BcsSignable, CryptoHash},
     doc_scalar, hex_debug,
     identifiers::{
         ApplicationId, BlobId, BlobType, BytecodeId, Destination, GenericApplicationId, MessageId,
@@ -119,6 +119,60 @@ pub enum Round {
     Validator(u32),
 }
 
+/// The kinds of certificates that validators can vote for.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Hash, Eq, PartialEq)]
+#[repr(u8)]
+pub enum CertificateKind {
+    /// A certificate that the current round timed out.
+    Timeout = 0,
+    /// A certificate that a block is valid.
+    Validated = 1,
+    /// A certificate that a block is confirmed.
+    Confirmed = 2,
+}
+
+/// The value that validators sign when voting: the hash of the certified value, the round
+/// and the kind of certificate.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct VoteValue(pub CryptoHash, pub Round, pub CertificateKind);
+
+impl<'de> BcsSignable<'de> for VoteValue {}
+
+/// The version of the protocol spoken between clients and validators.
+///
+/// Peers with the same major version can talk to each other. A newer minor version only
+/// adds features that older peers can ignore.
+///
+/// The version is sent in optional fields of the gRPC messages, next to the signed data
+/// rather than inside it, so that it is not part of the binary format of proposals,
+/// certificates and chain information. Messages over the simple transport don't carry it.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Hash, Debug, Serialize, Deserialize)]
+#[cfg_attr(with_testing, derive(test_strategy::Arbitrary))]
+pub struct ProtocolVersion {
+    /// Incremented for changes that older peers cannot handle.
+    pub major: u32,
+    /// Incremented for changes that older peers can ignore.
+    pub minor: u32,
+}
+
+impl ProtocolVersion {
+    /// The version implemented by this code.
+    ///
+    /// Version 2 changed the binary format of block proposals: their owner keys and
+    /// signatures can use other schemes than ed25519, and they carry the other owners'
+    /// approvals. It also added the block's effects to the certified blocks.
+    pub const CURRENT: ProtocolVersion = ProtocolVersion { major: 2, minor: 0 };
+
+    /// The version of peers that predate version negotiation and don't send one.
+    pub const UNVERSIONED: ProtocolVersion = ProtocolVersion { major: 1, minor: 0 };
+
+    /// Returns whether a peer with this version can handle messages from a peer with the
+    /// `other` version.
+    pub fn is_compatible_with(&self, other: &ProtocolVersion) -> bool {
+        self.major == other.major
+    }
+}
+
 /// A duration in microseconds.
 #[derive(
     Eq,
@@ -544,6 +598,7 @@ impl FromStr for Amount {
     fn from_str(src: &str) -> Result<Self, Self::Err> {
         let mut result: u128 = 0;
         let mut decimals: Option<u8> = None;
+        let mut has_digits = false;
         let mut chars = src.trim().chars().peekable();
         if chars.peek() == Some(&'+') {
             chars.next();
@@ -555,8 +610,16 @@ impl FromStr for Amount {
                 '.' => decimals = Some(Amount::DECIMAL_PLACES),
                 char => {
                     let digit = u128::from(char.to_digit(10).ok_or(ParseAmountError::Parse)?);
+                    has_digits = true;
                     if let Some(d) = &mut decimals {
-                        *d = d.checked_sub(1).ok_or(ParseAmountError::TooManyDigits)?;
+                        if *d == 0 {
+                            // Trailing zeros beyond the precision don't change the amount.
+                            if digit == 0 {
+                                continue;
+                            }
+                            return Err(ParseAmountError::TooManyDigits);
+                        }
+                        *d -= 1;
                     }
                     result = result
                         .checked_mul(10)
@@ -565,6 +628,9 @@ impl FromStr for Amount {
                 }
             }
         }
+        if !has_digits {
+            return Err(ParseAmountError::Parse);
+        }
         result = result
             .checked_mul(10u128.pow(decimals.unwrap_or(Amount::DECIMAL_PLACES) as u32))
             .ok_or(ParseAmountError::TooHigh)?;
@@ -586,6 +652,12 @@ impl FromStr for BlockHeight {
     }
 }
 
+impl Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
 impl Display for Round {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -668,6 +740,16 @@ impl Amount {
         Amount(10u128.pow(Amount::DECIMAL_PLACES as u32 - 9)).saturating_mul(nanotokens)
     }
 
+    /// Returns an `Amount` corresponding to that many tokens, or an error if it overflows.
+    pub fn try_from_tokens(tokens: u128) -> Result<Amount, ArithmeticError> {
+        Self::ONE.try_mul(tokens)
+    }
+
+    /// Returns an `Amount` corresponding to that many millitokens, or an error if it overflows.
+    pub fn try_from_millis(millitokens: u128) -> Result<Amount, ArithmeticError> {
+        Amount(10u128.pow(Amount::DECIMAL_PLACES as u32 - 3)).try_mul(millitokens)
+    }
+
     /// Returns an `Amount` corresponding to that many attotokens.
     pub fn from_attos(attotokens: u128) -> Amount {
         Amount(attotokens)
@@ -683,6 +765,16 @@ impl Amount {
         self.0 as u64
     }
 
+    /// Returns the sum of the `amounts`, or an error if it exceeds [`Amount::MAX`].
+    ///
+    /// Unlike summing with [`Iterator::sum`], which saturates, this must be used wherever the
+    /// total is checked against a balance.
+    pub fn try_sum(amounts: impl IntoIterator<Item = Amount>) -> Result<Amount, ArithmeticError> {
+        amounts
+            .into_iter()
+            .try_fold(Amount::ZERO, |sum, amount| sum.try_add(amount))
+    }
+
     /// Divides this by the other amount. If the other is 0, it returns `u128::MAX`.
     pub fn saturating_div(self, other: Amount) -> u128 {
         self.0.checked_div(other.0).unwrap_or(u128::MAX)
@@ -891,6 +983,8 @@ impl AsRef<[u8]> for Bytecode {
     }
 }
 
+impl<'de> BcsHashable<'de> for Bytecode {}
+
 /// A type for errors happening during decompression.
 #[derive(Error, Debug)]
 pub enum DecompressionError {
@@ -1205,7 +1299,10 @@ static BYTECODE_DECOMPRESSION_LATENCY: LazyLock<HistogramVec> = LazyLock::new(||
 mod tests {
     use std::str::FromStr;
 
-    use super::Amount;
+    use assert_matches::assert_matches;
+    use test_strategy::proptest;
+
+    use super::{Amount, ArithmeticError, ParseAmountError};
 
     #[test]
     fn display_amount() {
@@ -1232,4 +1329,120 @@ mod tests {
             format!("{:~^+9.1}", Amount::from_str("12.34").unwrap())
         );
     }
+
+    #[test]
+    fn checked_amount_arithmetic_near_limits() {
+        let max_u64 = Amount::from_attos(u64::MAX.into());
+        assert_eq!(max_u64.upper_half(), 0);
+        assert_eq!(max_u64.lower_half(), u64::MAX);
+        let above_u64 = max_u64.try_add_one().unwrap();
+        assert_eq!(above_u64.upper_half(), 1);
+        assert_eq!(above_u64.lower_half(), 0);
+        assert_eq!(above_u64.try_sub(max_u64).unwrap(), Amount::from_attos(1));
+        assert_matches!(max_u64.try_sub(above_u64), Err(ArithmeticError::Underflow));
+        assert_eq!(max_u64.saturating_sub(above_u64), Amount::ZERO);
+        assert_eq!(
+            u128::from(max_u64.try_mul(u64::MAX.into()).unwrap()),
+            u128::from(u64::MAX).pow(2)
+        );
+        assert_matches!(
+            above_u64.try_mul(u128::from(u64::MAX) + 1),
+            Err(ArithmeticError::Overflow)
+        );
+
+        let almost_max = Amount::MAX.try_sub_one().unwrap();
+        assert_eq!(almost_max.try_add_one().unwrap(), Amount::MAX);
+        assert_matches!(
+            almost_max.try_add(Amount::from_attos(2)),
+            Err(ArithmeticError::Overflow)
+        );
+        assert_matches!(Amount::MAX.try_add_one(), Err(ArithmeticError::Overflow));
+        assert_eq!(almost_max.saturating_add(Amount::ONE), Amount::MAX);
+        assert_eq!(Amount::try_sum([]).unwrap(), Amount::ZERO);
+        assert_eq!(
+            Amount::try_sum([almost_max, Amount::from_attos(1)]).unwrap(),
+            Amount::MAX
+        );
+        assert_matches!(
+            Amount::try_sum([almost_max, Amount::ZERO, Amount::from_attos(2)]),
+            Err(ArithmeticError::Overflow)
+        );
+        assert_eq!(
+            [almost_max, Amount::from_attos(2)].iter().sum::<Amount>(),
+            Amount::MAX
+        );
+
+        let max_tokens = u128::MAX / u128::from(Amount::ONE);
+        assert_eq!(
+            Amount::try_from_tokens(max_tokens).unwrap(),
+            Amount::from_tokens(max_tokens)
+        );
+        assert_matches!(
+            Amount::try_from_tokens(max_tokens + 1),
+            Err(ArithmeticError::Overflow)
+        );
+        assert_eq!(Amount::from_tokens(max_tokens + 1), Amount::MAX);
+        assert_eq!(
+            Amount::try_from_millis(u64::MAX.into()).unwrap(),
+            Amount::from_millis(u64::MAX.into())
+        );
+        assert_matches!(
+            Amount::try_from_millis(u128::MAX),
+            Err(ArithmeticError::Overflow)
+        );
+    }
+
+    #[test]
+    fn parse_amount() {
+        assert_eq!(Amount::from_str("1.5").unwrap(), Amount::from_millis(1_500));
+        assert_eq!(
+            Amount::from_str("0.000001").unwrap(),
+            Amount::from_micros(1)
+        );
+        assert_eq!(Amount::from_str(".5").unwrap(), Amount::from_millis(500));
+        assert_eq!(Amount::from_str("+2.").unwrap(), Amount::from_tokens(2));
+        assert_eq!(
+            Amount::from_str("0.000000000000000001").unwrap(),
+            Amount::from_attos(1)
+        );
+        assert_eq!(
+            Amount::from_str("0.0000000000000000010000").unwrap(),
+            Amount::from_attos(1)
+        );
+        assert_eq!(
+            Amount::from_str(&Amount::MAX.to_string()).unwrap(),
+            Amount::MAX
+        );
+
+        for invalid in ["", " ", ".", "_", "+", "1.2.3", "-1", "1e3", "0x10", "one"] {
+            assert_matches!(
+                Amount::from_str(invalid),
+                Err(ParseAmountError::Parse),
+                "{invalid:?} should not be parsed"
+            );
+        }
+        assert_matches!(
+            Amount::from_str("0.0000000000000000001"),
+            Err(ParseAmountError::TooManyDigits)
+        );
+        assert_matches!(
+            Amount::from_str("1.0000000000000000000000000000000000000001"),
+            Err(ParseAmountError::TooManyDigits)
+        );
+        assert_matches!(
+            Amount::from_str("340282366920938463464"),
+            Err(ParseAmountError::TooHigh)
+        );
+    }
+
+    #[proptest]
+    fn amount_round_trips_through_its_string_representation(amount: Amount) {
+        assert_eq!(Amount::from_str(&amount.to_string()).unwrap(), amount);
+        assert_eq!(Amount::from_str(&format!("{amount:.30}")).unwrap(), amount);
+    }
+
+    #[proptest]
+    fn parsing_arbitrary_strings_does_not_panic(string: String) {
+        let _ = Amount::from_str(&string);
+    }
 }
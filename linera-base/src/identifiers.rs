@@ -18,7 +18,9 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
     bcs_scalar,
-    crypto::{BcsHashable, CryptoError, CryptoHash, PublicKey},
+    crypto::{
+        AccountPublicKey, BcsHashable, CryptoError, CryptoHash, PublicKey, Secp256k1PublicKey,
+    },
     data_types::BlockHeight,
     doc_scalar, hex_debug,
 };
@@ -805,6 +807,27 @@ impl From<&PublicKey> for Owner {
     }
 }
 
+impl From<Secp256k1PublicKey> for Owner {
+    fn from(value: Secp256k1PublicKey) -> Self {
+        Self(CryptoHash::new(&value))
+    }
+}
+
+impl From<AccountPublicKey> for Owner {
+    fn from(value: AccountPublicKey) -> Self {
+        Owner::from(&value)
+    }
+}
+
+impl From<&AccountPublicKey> for Owner {
+    fn from(value: &AccountPublicKey) -> Self {
+        match value {
+            AccountPublicKey::Ed25519(public_key) => Owner::from(public_key),
+            AccountPublicKey::Secp256k1(public_key) => Owner::from(*public_key),
+        }
+    }
+}
+
 impl std::str::FromStr for Owner {
     type Err = CryptoError;
 
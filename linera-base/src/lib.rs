@@ -14,6 +14,7 @@ use std::fmt;
 pub use async_trait::async_trait;
 
 pub mod abi;
+pub mod balance_proof;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod command;
 pub mod crypto;
@@ -0,0 +1,76 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use linera_base::{
+    crypto::{CryptoHash, KeyPair},
+    data_types::{Amount, BlockHeight, Round},
+    hashed::Hashed,
+    identifiers::ChainId,
+};
+use linera_chain::{
+    data_types::{BlockExecutionOutcome, LiteValue, LiteVote, SignatureAggregator},
+    test::{make_first_block, BlockTestExt},
+    types::{ConfirmedBlock, ConfirmedBlockCertificate},
+};
+use linera_execution::committee::{Committee, ValidatorName};
+
+/// The number of certificates verified in each iteration, as during the sync of a long chain.
+const CERTIFICATE_COUNT: u64 = 1000;
+
+/// Creates a committee of four validators and `CERTIFICATE_COUNT` certificates signed by it.
+fn setup() -> (Committee, Vec<ConfirmedBlockCertificate>) {
+    let keys = (0..4).map(|_| KeyPair::generate()).collect::<Vec<_>>();
+    let committee =
+        Committee::make_simple(keys.iter().map(|key| ValidatorName(key.public())).collect());
+    let certificates = (0..CERTIFICATE_COUNT)
+        .map(|height| {
+            let mut block = make_first_block(ChainId::root(0))
+                .with_simple_transfer(ChainId::root(1), Amount::ONE);
+            block.height = BlockHeight(height);
+            let executed_block = BlockExecutionOutcome {
+                messages: vec![Vec::new()],
+                state_hash: CryptoHash::test_hash("state"),
+                oracle_responses: vec![Vec::new()],
+                events: vec![Vec::new()],
+            }
+            .with(block);
+            let value = Hashed::new(ConfirmedBlock::new(executed_block));
+            let mut builder = SignatureAggregator::new(value.clone(), Round::Fast, &committee);
+            keys.iter()
+                .find_map(|key| {
+                    let vote = LiteVote::new(LiteValue::new(&value), Round::Fast, key);
+                    builder.append(vote.validator, vote.signature).unwrap()
+                })
+                .unwrap()
+        })
+        .collect();
+    (committee, certificates)
+}
+
+fn verify_certificates_individually(c: &mut Criterion) {
+    let (committee, certificates) = setup();
+    c.bench_function("verify_certificates_individually", |b| {
+        b.iter(|| {
+            for certificate in black_box(&certificates) {
+                certificate.check(&committee).unwrap();
+            }
+        })
+    });
+}
+
+fn verify_certificates_in_batch(c: &mut Criterion) {
+    let (committee, certificates) = setup();
+    c.bench_function("verify_certificates_in_batch", |b| {
+        b.iter(|| {
+            ConfirmedBlockCertificate::verify_batch(black_box(&certificates), &committee).unwrap()
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    verify_certificates_individually,
+    verify_certificates_in_batch
+);
+criterion_main!(benches);
@@ -6,19 +6,24 @@ use std::{collections::BTreeSet, fmt::Debug};
 
 use async_graphql::SimpleObject;
 use linera_base::{
-    crypto::{BcsHashable, CryptoHash},
-    data_types::{BlockHeight, OracleResponse, Timestamp},
+    crypto::{BcsHashable, CryptoHash, HasTypeName},
+    data_types::{Amount, BlockHeight, OracleResponse, Timestamp},
+    doc_scalar,
     hashed::Hashed,
-    identifiers::{BlobId, BlobType, ChainId, MessageId, Owner},
+    identifiers::{AccountOwner, BlobId, BlobType, ChainId, Destination, MessageId, Owner},
+};
+use linera_execution::{
+    committee::Epoch,
+    system::{AdminOperation, Recipient},
+    Message, MessageKind, Operation, SystemMessage, SystemOperation,
 };
-use linera_execution::{committee::Epoch, Operation, SystemOperation};
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
     data_types::{
-        BlockExecutionOutcome, EventRecord, ExecutedBlock, IncomingBundle, Medium, MessageBundle,
-        OutgoingMessage, ProposedBlock,
+        BlockExecutionOutcome, EventRecord, ExecutedBlock, IncomingBundle, Medium, MessageAction,
+        MessageBundle, OutgoingMessage, ProposedBlock,
     },
     types::CertificateValue,
     ChainError,
@@ -130,6 +135,35 @@ impl ConfirmedBlock {
         "confirmed_block"
     }
 
+    /// Reads a confirmed block in the format of protocol version 1, before the effects were
+    /// added to the certified blocks. Its effects are computed from its contents.
+    ///
+    /// The block can only be used with the hash it was certified with, see
+    /// [`Self::with_hash_unchecked`]: hashing it again would use the current format.
+    pub fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, bcs::Error> {
+        let LegacyBlock { header, body } = bcs::from_bytes(bytes)?;
+        let block = ProposedBlock {
+            chain_id: header.chain_id,
+            epoch: header.epoch,
+            incoming_bundles: body.incoming_bundles,
+            operations: body.operations,
+            height: header.height,
+            timestamp: header.timestamp,
+            authenticated_signer: header.authenticated_signer,
+            previous_block_hash: header.previous_block_hash,
+        };
+        let outcome = BlockExecutionOutcome {
+            messages: body.messages,
+            state_hash: header.state_hash,
+            oracle_responses: body.oracle_responses,
+            events: body.events,
+        };
+        Ok(Self(Hashed::unchecked_new(
+            Block::new(block, outcome),
+            CryptoHash::from_bcs_bytes(Block::type_name(), bytes),
+        )))
+    }
+
     /// Creates a `HashedCertificateValue` without checking that this is the correct hash!
     pub fn with_hash_unchecked(self, hash: CryptoHash) -> Hashed<ConfirmedBlock> {
         Hashed::unchecked_new(self, hash)
@@ -275,6 +309,7 @@ impl<'de> Deserialize<'de> for Block {
         let operations_hash = hashing::hash_vec(&inner.body.operations);
         let oracle_responses_hash = hashing::hash_vec_vec(&inner.body.oracle_responses);
         let events_hash = hashing::hash_vec_vec(&inner.body.events);
+        let effects_hash = hashing::hash_vec(&inner.body.effects);
 
         let header = BlockHeader {
             chain_id: inner.header.chain_id,
@@ -289,6 +324,7 @@ impl<'de> Deserialize<'de> for Block {
             messages_hash,
             oracle_responses_hash,
             events_hash,
+            effects_hash,
         };
 
         Ok(Self {
@@ -334,6 +370,8 @@ pub struct BlockHeader {
     pub oracle_responses_hash: CryptoHash,
     /// Cryptographic hash of all the events in the block.
     pub events_hash: CryptoHash,
+    /// Cryptographic hash of all the effects of the block.
+    pub effects_hash: CryptoHash,
 }
 
 /// The body of a block containing all the data included in the block.
@@ -350,6 +388,9 @@ pub struct BlockBody {
     pub oracle_responses: Vec<Vec<OracleResponse>>,
     /// The list of events produced by each transaction.
     pub events: Vec<Vec<EventRecord>>,
+    /// The effects of the block on balances, ownership and committees, computed by the
+    /// validators when they execute it.
+    pub effects: Vec<Effect>,
 }
 
 impl Block {
@@ -359,6 +400,13 @@ impl Block {
         let operations_hash = hashing::hash_vec(&block.operations);
         let oracle_responses_hash = hashing::hash_vec_vec(&outcome.oracle_responses);
         let events_hash = hashing::hash_vec_vec(&outcome.events);
+        let effects = compute_effects(
+            block.chain_id,
+            &block.incoming_bundles,
+            &block.operations,
+            &outcome.messages,
+        );
+        let effects_hash = hashing::hash_vec(&effects);
 
         let header = BlockHeader {
             chain_id: block.chain_id,
@@ -373,6 +421,7 @@ impl Block {
             messages_hash,
             oracle_responses_hash,
             events_hash,
+            effects_hash,
         };
 
         let body = BlockBody {
@@ -381,6 +430,7 @@ impl Block {
             messages: outcome.messages,
             oracle_responses: outcome.oracle_responses,
             events: outcome.events,
+            effects,
         };
 
         Self { header, body }
@@ -477,6 +527,18 @@ impl Block {
         None
     }
 
+    /// Returns the ID of the chain opened by the message with the specified id, or `None` if
+    /// that message doesn't open a chain.
+    pub fn opened_chain_id(&self, message_id: &MessageId) -> Option<ChainId> {
+        let message = self.message_by_id(message_id)?;
+        match (&message.message, &message.destination) {
+            (Message::System(SystemMessage::OpenChain(_)), Destination::Recipient(chain_id)) => {
+                Some(*chain_id)
+            }
+            _ => None,
+        }
+    }
+
     /// Returns all the blob IDs required by this block.
     /// Either as oracle responses or as published blobs.
     pub fn required_blob_ids(&self) -> BTreeSet<BlobId> {
@@ -526,6 +588,171 @@ impl Block {
     pub fn messages(&self) -> &Vec<Vec<OutgoingMessage>> {
         &self.body.messages
     }
+
+    /// Returns the effects of this block on balances, ownership and committees, as computed
+    /// by the validators and certified with the block.
+    pub fn effects(&self) -> &[Effect] {
+        &self.body.effects
+    }
+}
+
+/// An effect of a block, computed by the validators and certified with the block, so that it
+/// can be read from a certificate without executing the block again.
+///
+/// New variants must be added at the end, so that the effects of older blocks can still be
+/// read.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum Effect {
+    /// `amount` was sent to the `target` account on another chain.
+    Credit {
+        recipient: ChainId,
+        target: Option<AccountOwner>,
+        amount: Amount,
+    },
+    /// A new chain was opened with `balance` from this chain.
+    OpenChain { chain_id: ChainId, balance: Amount },
+    /// `amount` was claimed from the account of `owner`, or from the chain balance, on the
+    /// `target_id` chain, to be sent to `recipient`. This only takes effect if the other chain
+    /// accepts the claim.
+    Claim {
+        target_id: ChainId,
+        owner: Option<AccountOwner>,
+        amount: Amount,
+        recipient: Recipient,
+    },
+    /// `amount` was burned from the account of `owner`, or from the chain balance.
+    Burn {
+        owner: Option<AccountOwner>,
+        amount: Amount,
+    },
+    /// The owners of the chain were replaced.
+    OwnershipChange {
+        super_owners: Vec<Owner>,
+        owners: Vec<(Owner, u64)>,
+    },
+    /// The admin chain created the committee for a new epoch.
+    CreateCommittee { epoch: Epoch },
+    /// The admin chain removed the committee of an epoch.
+    RemoveCommittee { epoch: Epoch },
+}
+
+/// Computes the effects of a block on balances, ownership and committees: first the burns
+/// requested by incoming messages, then the effects of the operations, then those of the
+/// outgoing messages.
+///
+/// Tokens leaving the chain are reported wherever they come from, including user
+/// applications. However, a block does not record changes that stay within the chain, so
+/// burns by user applications and transfers between accounts of this chain are not
+/// reported.
+fn compute_effects(
+    chain_id: ChainId,
+    incoming_bundles: &[IncomingBundle],
+    operations: &[Operation],
+    messages: &[Vec<OutgoingMessage>],
+) -> Vec<Effect> {
+    let mut effects = Vec::new();
+    let incoming_messages = incoming_bundles
+        .iter()
+        .filter(|bundle| bundle.action == MessageAction::Accept)
+        .flat_map(|bundle| &bundle.bundle.messages);
+    for posted_message in incoming_messages {
+        let Message::System(message) = &posted_message.message else {
+            continue;
+        };
+        match message {
+            SystemMessage::Withdraw {
+                owner,
+                amount,
+                recipient: Recipient::Burn,
+            } => effects.push(Effect::Burn {
+                owner: Some(*owner),
+                amount: *amount,
+            }),
+            SystemMessage::WithdrawChainBalance {
+                amount,
+                recipient: Recipient::Burn,
+                ..
+            } => effects.push(Effect::Burn {
+                owner: None,
+                amount: *amount,
+            }),
+            _ => {}
+        }
+    }
+    for operation in operations {
+        let Operation::System(operation) = operation else {
+            continue;
+        };
+        match operation {
+            SystemOperation::Transfer {
+                owner,
+                recipient: Recipient::Burn,
+                amount,
+            } => effects.push(Effect::Burn {
+                owner: owner.map(AccountOwner::User),
+                amount: *amount,
+            }),
+            SystemOperation::ChangeOwnership {
+                super_owners,
+                owners,
+                ..
+            } => effects.push(Effect::OwnershipChange {
+                super_owners: super_owners.clone(),
+                owners: owners.clone(),
+            }),
+            SystemOperation::Admin(AdminOperation::CreateCommittee { epoch, .. }) => {
+                effects.push(Effect::CreateCommittee { epoch: *epoch })
+            }
+            SystemOperation::Admin(AdminOperation::RemoveCommittee { epoch }) => {
+                effects.push(Effect::RemoveCommittee { epoch: *epoch })
+            }
+            _ => {}
+        }
+    }
+    for message in messages.iter().flatten() {
+        let (Destination::Recipient(recipient), Message::System(system_message)) =
+            (&message.destination, &message.message)
+        else {
+            continue;
+        };
+        // Bouncing messages return tokens that this chain never received.
+        if *recipient == chain_id || message.kind == MessageKind::Bouncing {
+            continue;
+        }
+        match system_message {
+            SystemMessage::Credit { target, amount, .. } => effects.push(Effect::Credit {
+                recipient: *recipient,
+                target: *target,
+                amount: *amount,
+            }),
+            SystemMessage::OpenChain(config) => effects.push(Effect::OpenChain {
+                chain_id: *recipient,
+                balance: config.balance,
+            }),
+            SystemMessage::Withdraw {
+                owner,
+                amount,
+                recipient: claim_recipient,
+            } => effects.push(Effect::Claim {
+                target_id: *recipient,
+                owner: Some(*owner),
+                amount: *amount,
+                recipient: *claim_recipient,
+            }),
+            SystemMessage::WithdrawChainBalance {
+                amount,
+                recipient: claim_recipient,
+                ..
+            } => effects.push(Effect::Claim {
+                target_id: *recipient,
+                owner: None,
+                amount: *amount,
+                recipient: *claim_recipient,
+            }),
+            _ => {}
+        }
+    }
+    effects
 }
 
 impl From<Block> for ExecutedBlock {
@@ -545,6 +772,7 @@ impl From<Block> for ExecutedBlock {
                     messages_hash: _,
                     oracle_responses_hash: _,
                     events_hash: _,
+                    effects_hash: _,
                 },
             body:
                 BlockBody {
@@ -553,6 +781,7 @@ impl From<Block> for ExecutedBlock {
                     messages,
                     oracle_responses,
                     events,
+                    effects: _,
                 },
         } = block;
 
@@ -580,6 +809,13 @@ impl From<Block> for ExecutedBlock {
 
 impl<'de> BcsHashable<'de> for Block {}
 
+impl<'de> BcsHashable<'de> for Effect {}
+
+doc_scalar!(
+    Effect,
+    "An effect of a block on balances, ownership or committees."
+);
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename = "BlockHeader")]
 struct SerializedHeader {
@@ -592,6 +828,24 @@ struct SerializedHeader {
     authenticated_signer: Option<Owner>,
 }
 
+/// A block in the format of protocol version 1, before the effects were added.
+#[derive(Deserialize)]
+#[serde(rename = "Block")]
+struct LegacyBlock {
+    header: SerializedHeader,
+    body: LegacyBlockBody,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "BlockBody")]
+struct LegacyBlockBody {
+    incoming_bundles: Vec<IncomingBundle>,
+    operations: Vec<Operation>,
+    messages: Vec<Vec<OutgoingMessage>>,
+    oracle_responses: Vec<Vec<OracleResponse>>,
+    events: Vec<Vec<EventRecord>>,
+}
+
 mod hashing {
     use linera_base::crypto::{BcsHashable, CryptoHash, CryptoHashVec};
 
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use linera_base::{
+    balance_proof::{BalancePath, BalanceProof, CertifiedBlockHeader},
     crypto::Signature,
     data_types::Round,
     hashed::Hashed,
@@ -13,7 +14,7 @@ use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize};
 
 use super::{generic::GenericCertificate, Certificate};
 use crate::{
-    block::{Block, ConfirmedBlock, ConversionError},
+    block::{Block, ConfirmedBlock, ConversionError, Effect},
     data_types::{Medium, MessageBundle},
 };
 
@@ -46,6 +47,36 @@ impl GenericCertificate<ConfirmedBlock> {
         self.block().requires_blob(blob_id)
     }
 
+    /// Returns the certified effects of the block on balances, ownership and committees.
+    pub fn effects(&self) -> &[Effect] {
+        self.block().effects()
+    }
+
+    /// Returns a proof of the chain's balance after this block, given the path from the
+    /// balance to the block's state hash.
+    pub fn balance_proof(&self, path: BalancePath) -> BalanceProof {
+        let header = &self.block().header;
+        BalanceProof {
+            header: CertifiedBlockHeader {
+                chain_id: header.chain_id,
+                epoch: header.epoch.0,
+                height: header.height,
+                timestamp: header.timestamp,
+                state_hash: header.state_hash,
+                previous_block_hash: header.previous_block_hash,
+                authenticated_signer: header.authenticated_signer,
+            },
+            body: bcs::to_bytes(&self.block().body).expect("Serialization should not fail"),
+            round: self.round,
+            signatures: self
+                .signatures()
+                .iter()
+                .map(|(validator, signature)| (validator.0, *signature))
+                .collect(),
+            path,
+        }
+    }
+
     #[cfg(with_testing)]
     pub fn outgoing_message_count(&self) -> usize {
         self.block().messages().iter().map(Vec::len).sum()
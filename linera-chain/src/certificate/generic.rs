@@ -110,6 +110,18 @@ impl<T> GenericCertificate<T> {
         Ok(())
     }
 
+    /// Verifies a batch of certificates signed by the same `committee`.
+    ///
+    /// The signatures of all certificates are verified together, which is much faster than
+    /// checking each certificate on its own. If the batch is invalid, the returned error
+    /// identifies the first offending certificate.
+    pub fn verify_batch(certificates: &[Self], committee: &Committee) -> Result<(), ChainError>
+    where
+        T: CertificateValue,
+    {
+        crate::data_types::check_signatures_of_batch(certificates, committee)
+    }
+
     pub fn lite_certificate(&self) -> crate::certificate::LiteCertificate<'_>
     where
         T: CertificateValue,
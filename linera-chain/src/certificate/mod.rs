@@ -11,6 +11,7 @@ mod validated;
 use std::collections::BTreeSet;
 
 pub use generic::GenericCertificate;
+pub use linera_base::data_types::CertificateKind;
 use linera_base::{
     crypto::Signature,
     data_types::{BlockHeight, Round},
@@ -40,6 +41,10 @@ pub type TimeoutCertificate = GenericCertificate<Timeout>;
 /// Enum wrapping all types of certificates that can be created.
 /// A certified statement from the committee.
 /// Every certificate is a statement signed by the quorum of the committee.
+///
+/// Certificates serialize to compact binary with BCS, and to stable JSON with
+/// `serde_json`: there, hashes, public keys and signatures are hex strings and amounts
+/// are decimal strings, so that no precision is lost in JavaScript.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
 pub enum Certificate {
@@ -85,14 +90,6 @@ impl Certificate {
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, Hash, Eq, PartialEq)]
-#[repr(u8)]
-pub enum CertificateKind {
-    Timeout = 0,
-    Validated = 1,
-    Confirmed = 2,
-}
-
 pub trait CertificateValue: Clone {
     const KIND: CertificateKind;
 
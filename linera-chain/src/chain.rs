@@ -5,12 +5,14 @@
 use std::sync::LazyLock;
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
+    mem,
     sync::Arc,
 };
 
 use async_graphql::SimpleObject;
 use futures::stream::{self, StreamExt, TryStreamExt};
 use linera_base::{
+    balance_proof::BalancePath,
     crypto::CryptoHash,
     data_types::{
         Amount, ArithmeticError, BlockHeight, OracleResponse, Timestamp, UserApplicationDescription,
@@ -33,6 +35,7 @@ use linera_execution::{
 use linera_views::{
     context::Context,
     log_view::LogView,
+    map_view::MapView,
     queue_view::QueueView,
     reentrant_collection_view::ReentrantCollectionView,
     register_view::RegisterView,
@@ -212,8 +215,10 @@ where
     pub confirmed_log: LogView<C, CryptoHash>,
     /// Sender chain and height of all certified blocks known as a receiver (local ordering).
     pub received_log: LogView<C, ChainAndHeight>,
-    /// The number of `received_log` entries we have synchronized, for each validator.
-    pub received_certificate_trackers: RegisterView<C, HashMap<ValidatorName, u64>>,
+    /// The number of entries of each validator's `received_log` that we have already scanned.
+    /// The next synchronization only queries the entries after that.
+    #[graphql(skip)]
+    pub received_log_indices: RegisterView<C, HashMap<ValidatorName, u64>>,
 
     /// Mailboxes used to receive messages indexed by their origin.
     pub inboxes: ReentrantCollectionView<C, Origin, InboxStateView<C>>,
@@ -228,6 +233,16 @@ where
     pub outbox_counters: RegisterView<C, BTreeMap<BlockHeight, u32>>,
     /// Channels able to multicast messages to subscribers.
     pub channels: ReentrantCollectionView<C, ChannelFullName, ChannelStateView<C>>,
+    /// The highest block height of each sender chain in `received_log` whose certificate
+    /// we have synchronized.
+    pub received_certificate_trackers: RegisterView<C, BTreeMap<ChainId, BlockHeight>>,
+    /// The certificates of the blocks below this height were deleted from storage. Their
+    /// hashes are still in `confirmed_log`.
+    pub pruned_height: RegisterView<C, BlockHeight>,
+    /// The chain balance after each confirmed block, with the hashes needed to prove it
+    /// against the block's state hash. Pruned together with the certificates.
+    #[graphql(skip)]
+    pub balance_paths: MapView<C, BlockHeight, BalancePath>,
 }
 
 /// Block-chaining state.
@@ -545,15 +560,19 @@ where
         Ok(new_outbox_entries)
     }
 
-    /// Updates the `received_log` trackers.
+    /// Updates the `received_log` trackers and the indices into the validators'
+    /// `received_log`s.
     pub fn update_received_certificate_trackers(
         &mut self,
-        new_trackers: BTreeMap<ValidatorName, u64>,
+        new_trackers: BTreeMap<ChainId, BlockHeight>,
+        new_log_indices: BTreeMap<ValidatorName, u64>,
     ) {
-        for (name, tracker) in new_trackers {
+        // Unlike the trackers, an index can go backward if a validator's log was reset.
+        self.received_log_indices.get_mut().extend(new_log_indices);
+        for (sender_chain_id, tracker) in new_trackers {
             self.received_certificate_trackers
                 .get_mut()
-                .entry(name)
+                .entry(sender_chain_id)
                 .and_modify(|t| {
                     // Because several synchronizations could happen in parallel, we need to make
                     // sure to never go backward.
@@ -681,6 +700,21 @@ where
         round: Option<u32>,
         replaying_oracle_responses: Option<Vec<Vec<OracleResponse>>>,
     ) -> Result<BlockExecutionOutcome, ChainError> {
+        let (outcome, _) = self
+            .execute_block_with_resources(block, local_time, round, replaying_oracle_responses)
+            .await?;
+        Ok(outcome)
+    }
+
+    /// Executes a block like [`ChainStateView::execute_block`], and also returns the resources
+    /// it used, e.g. the fuel consumed by the contracts.
+    pub async fn execute_block_with_resources(
+        &mut self,
+        block: &ProposedBlock,
+        local_time: Timestamp,
+        round: Option<u32>,
+        replaying_oracle_responses: Option<Vec<Vec<OracleResponse>>>,
+    ) -> Result<(BlockExecutionOutcome, ResourceTracker), ChainError> {
         #[cfg(with_metrics)]
         let _execution_latency = BLOCK_EXECUTION_LATENCY.measure_latency();
 
@@ -774,6 +808,8 @@ where
         let mut oracle_responses = Vec::new();
         let mut events = Vec::new();
         let mut messages = Vec::new();
+        // The owners' spending limits apply to each block separately.
+        let mut owner_spending = BTreeMap::new();
         for (txn_index, transaction) in block.transactions() {
             let chain_execution_context = match transaction {
                 Transaction::ReceiveMessages(_) => ChainExecutionContext::IncomingBundle(txn_index),
@@ -784,7 +820,8 @@ where
                 Some(None) => return Err(ChainError::MissingOracleResponseList),
                 None => None,
             };
-            let mut txn_tracker = TransactionTracker::new(next_message_index, maybe_responses);
+            let mut txn_tracker = TransactionTracker::new(next_message_index, maybe_responses)
+                .with_owner_spending(mem::take(&mut owner_spending));
             match transaction {
                 Transaction::ReceiveMessages(incoming_bundle) => {
                     resource_controller
@@ -840,6 +877,7 @@ where
                 .update_execution_outcomes_with_app_registrations(&mut txn_tracker)
                 .await
                 .with_execution_context(chain_execution_context)?;
+            owner_spending = mem::take(txn_tracker.owner_spending_mut());
             let (txn_outcomes, txn_oracle_responses, new_next_message_index) = txn_tracker
                 .destructure()
                 .with_execution_context(chain_execution_context)?;
@@ -937,7 +975,7 @@ where
             oracle_responses,
             events,
         };
-        Ok(outcome)
+        Ok((outcome, resource_controller.tracker))
     }
 
     /// Executes a message as part of an incoming bundle in a block.
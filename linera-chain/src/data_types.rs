@@ -11,8 +11,11 @@ use async_graphql::SimpleObject;
 use custom_debug_derive::Debug;
 use linera_base::{
     bcs,
-    crypto::{BcsHashable, BcsSignable, CryptoError, CryptoHash, KeyPair, PublicKey, Signature},
-    data_types::{Amount, BlockHeight, OracleResponse, Round, Timestamp},
+    crypto::{
+        AccountPublicKey, AccountSecretKey, AccountSignature, BcsHashable, BcsSignable,
+        CryptoError, CryptoHash, KeyPair, Signature,
+    },
+    data_types::{Amount, BlockHeight, OracleResponse, Round, Timestamp, VoteValue},
     doc_scalar, ensure,
     hashed::Hashed,
     hex_debug,
@@ -300,6 +303,9 @@ pub enum Medium {
 }
 
 /// An authenticated proposal for a new block.
+///
+/// The binary format changed in major protocol version 2, so proposals stored or sent by
+/// older versions can't be decoded.
 // TODO(#456): the signature of the block owner is currently lost but it would be useful
 // to have it for auditing purposes.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -307,10 +313,15 @@ pub enum Medium {
 pub struct BlockProposal {
     pub content: ProposalContent,
     pub owner: Owner,
-    pub public_key: PublicKey,
-    pub signature: Signature,
+    pub public_key: AccountPublicKey,
+    pub signature: AccountSignature,
     #[debug(skip_if = Option::is_none)]
     pub validated_block_certificate: Option<LiteCertificate<'static>>,
+    /// Signatures of the proposed block by other owners, if the chain requires several
+    /// approvals. They don't depend on the round, so they remain valid if the same block is
+    /// proposed again.
+    #[debug(skip_if = Vec::is_empty)]
+    pub cosignatures: Vec<(AccountPublicKey, AccountSignature)>,
 }
 
 /// A posted message together with routing information.
@@ -451,9 +462,6 @@ impl LiteValue {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
-struct VoteValue(CryptoHash, Round, CertificateKind);
-
 /// A vote on a statement from a validator.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(bound(deserialize = "T: BcsHashable<'de>"))]
@@ -771,28 +779,22 @@ pub struct ProposalContent {
     pub outcome: Option<BlockExecutionOutcome>,
 }
 
-impl BlockProposal {
-    pub fn new_initial(round: Round, block: ProposedBlock, secret: &KeyPair) -> Self {
-        let content = ProposalContent {
+impl ProposalContent {
+    /// Returns the content of a proposal of a new block.
+    pub fn new_initial(round: Round, block: ProposedBlock) -> Self {
+        ProposalContent {
             round,
             block,
             outcome: None,
-        };
-        let signature = Signature::new(&content, secret);
-        Self {
-            content,
-            public_key: secret.public(),
-            owner: secret.public().into(),
-            signature,
-            validated_block_certificate: None,
         }
     }
 
+    /// Returns the content of a proposal that retries a validated block from an earlier
+    /// round, together with the certificate the proposal must carry.
     pub fn new_retry(
         round: Round,
         validated_block_certificate: ValidatedBlockCertificate,
-        secret: &KeyPair,
-    ) -> Self {
+    ) -> (Self, LiteCertificate<'static>) {
         let lite_cert = validated_block_certificate.lite_certificate().cloned();
         let block = validated_block_certificate.into_inner().into_inner();
         let executed_block: ExecutedBlock = block.into();
@@ -801,13 +803,42 @@ impl BlockProposal {
             round,
             outcome: Some(executed_block.outcome),
         };
-        let signature = Signature::new(&content, secret);
+        (content, lite_cert)
+    }
+}
+
+impl BlockProposal {
+    pub fn new_initial(round: Round, block: ProposedBlock, secret: &AccountSecretKey) -> Self {
+        let content = ProposalContent::new_initial(round, block);
+        let signature = AccountSignature::new(&content, secret);
+        Self::new_signed(content, None, secret.public(), signature)
+    }
+
+    pub fn new_retry(
+        round: Round,
+        validated_block_certificate: ValidatedBlockCertificate,
+        secret: &AccountSecretKey,
+    ) -> Self {
+        let (content, lite_cert) = ProposalContent::new_retry(round, validated_block_certificate);
+        let signature = AccountSignature::new(&content, secret);
+        Self::new_signed(content, Some(lite_cert), secret.public(), signature)
+    }
+
+    /// Creates a proposal from its content and a signature of it that was computed
+    /// elsewhere, e.g. by a hardware wallet.
+    pub fn new_signed(
+        content: ProposalContent,
+        validated_block_certificate: Option<LiteCertificate<'static>>,
+        public_key: AccountPublicKey,
+        signature: AccountSignature,
+    ) -> Self {
         Self {
             content,
-            public_key: secret.public(),
-            owner: secret.public().into(),
+            public_key,
+            owner: public_key.into(),
             signature,
-            validated_block_certificate: Some(lite_cert),
+            validated_block_certificate,
+            cosignatures: Vec::new(),
         }
     }
 
@@ -815,6 +846,17 @@ impl BlockProposal {
         self.signature.check(&self.content, self.public_key)
     }
 
+    /// Checks the co-signatures and returns the distinct owners that approved the block,
+    /// including the proposer.
+    pub fn approving_owners(&self) -> Result<BTreeSet<Owner>, CryptoError> {
+        let mut owners = BTreeSet::from([self.owner]);
+        for (public_key, signature) in &self.cosignatures {
+            signature.check(&self.content.block, *public_key)?;
+            owners.insert(Owner::from(public_key));
+        }
+        Ok(owners)
+    }
+
     pub fn required_blob_ids(&self) -> impl Iterator<Item = BlobId> + '_ {
         self.content.block.published_blob_ids().into_iter().chain(
             self.content
@@ -937,7 +979,64 @@ pub(crate) fn check_signatures(
     signatures: &[(ValidatorName, Signature)],
     committee: &Committee,
 ) -> Result<(), ChainError> {
-    // Check the quorum.
+    check_quorum(signatures, committee)?;
+    // All that is left is checking signatures!
+    let hash_and_round = VoteValue(value_hash, round, certificate_kind);
+    Signature::verify_batch(&hash_and_round, signatures.iter().map(|(v, s)| (&v.0, s)))?;
+    Ok(())
+}
+
+/// Verifies the signatures of several certificates signed by the same committee.
+///
+/// All signatures are checked together in a single batch. If the batch is rejected, the
+/// signatures are checked one by one to report which certificate and validator are at fault.
+pub(crate) fn check_signatures_of_batch<T: CertificateValue>(
+    certificates: &[GenericCertificate<T>],
+    committee: &Committee,
+) -> Result<(), ChainError> {
+    for certificate in certificates {
+        check_quorum(certificate.signatures(), committee).map_err(|error| {
+            ChainError::InvalidCertificate {
+                hash: certificate.hash(),
+                error: Box::new(error),
+            }
+        })?;
+    }
+    let values = certificates
+        .iter()
+        .map(|certificate| VoteValue(certificate.hash(), certificate.round, T::KIND))
+        .collect::<Vec<_>>();
+    let votes = values
+        .iter()
+        .zip(certificates)
+        .flat_map(|(value, certificate)| {
+            certificate
+                .signatures()
+                .iter()
+                .map(move |(v, s)| (value, &v.0, s))
+        });
+    if Signature::verify_batch_of_values(votes).is_ok() {
+        return Ok(());
+    }
+    // Find the culprit.
+    for (value, certificate) in values.iter().zip(certificates) {
+        for (validator, signature) in certificate.signatures() {
+            signature.check(value, validator.0).map_err(|error| {
+                ChainError::InvalidCertificate {
+                    hash: certificate.hash(),
+                    error: Box::new(error.into()),
+                }
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the signatures are from distinct validators of the committee, forming a quorum.
+fn check_quorum(
+    signatures: &[(ValidatorName, Signature)],
+    committee: &Committee,
+) -> Result<(), ChainError> {
     let mut weight = 0;
     let mut used_validators = HashSet::new();
     for (validator, _) in signatures {
@@ -956,15 +1055,12 @@ pub(crate) fn check_signatures(
         weight >= committee.quorum_threshold(),
         ChainError::CertificateRequiresQuorum
     );
-    // All that is left is checking signatures!
-    let hash_and_round = VoteValue(value_hash, round, certificate_kind);
-    Signature::verify_batch(&hash_and_round, signatures.iter().map(|(v, s)| (&v.0, s)))?;
     Ok(())
 }
 
 impl<'de> BcsSignable<'de> for ProposalContent {}
 
-impl<'de> BcsSignable<'de> for VoteValue {}
+impl<'de> BcsSignable<'de> for ProposedBlock {}
 
 doc_scalar!(
     MessageAction,
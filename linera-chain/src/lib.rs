@@ -138,6 +138,11 @@ pub enum ChainError {
     CertificateValidatorReuse,
     #[error("Signatures in a certificate must form a quorum")]
     CertificateRequiresQuorum,
+    #[error("Invalid certificate {hash}: {error}")]
+    InvalidCertificate {
+        hash: CryptoHash,
+        error: Box<ChainError>,
+    },
     #[error("Certificate signature verification failed: {error}")]
     CertificateSignatureVerificationFailed { error: String },
     #[error("Internal error {0}")]
@@ -125,7 +125,7 @@ impl BlockTestExt for ProposedBlock {
     }
 
     fn into_proposal_with_round(self, key_pair: &KeyPair, round: Round) -> BlockProposal {
-        BlockProposal::new_initial(round, self, key_pair)
+        BlockProposal::new_initial(round, self, &key_pair.copy().into())
     }
 }
 
@@ -143,7 +143,8 @@ impl<T: CertificateValue> VoteTestExt<T> for Vote<T> {
         let committee = Committee::new(
             vec![(self.validator, state)].into_iter().collect(),
             ResourceControlPolicy::only_fuel(),
-        );
+        )
+        .unwrap();
         SignatureAggregator::new(self.value, self.round, &committee)
             .append(self.validator, self.signature)
             .unwrap()
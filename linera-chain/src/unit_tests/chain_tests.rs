@@ -130,7 +130,8 @@ async fn test_block_size_limit() {
                 maximum_executed_block_size,
                 ..ResourceControlPolicy::default()
             },
-        ),
+        )
+        .unwrap(),
     );
 
     chain
@@ -2,12 +2,19 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use linera_base::data_types::Amount;
+use assert_matches::assert_matches;
+use linera_base::{
+    data_types::Amount,
+    identifiers::{AccountOwner, UserApplicationId},
+    ownership::{ChainOwnership, TimeoutConfig},
+};
+use linera_execution::system::Recipient;
 
 use super::*;
 use crate::{
-    block::{ConfirmedBlock, ValidatedBlock},
-    test::{make_first_block, BlockTestExt},
+    block::{ConfirmedBlock, Effect, ValidatedBlock},
+    test::{make_first_block, BlockTestExt, MessageTestExt},
+    types::ConfirmedBlockCertificate,
 };
 
 #[test]
@@ -121,3 +128,310 @@ fn test_certificates() {
         .is_none());
     assert!(builder.append(v3.validator, v3.signature).is_err());
 }
+
+#[test]
+fn test_certificate_batch_verification() {
+    let keys = (0..4).map(|_| KeyPair::generate()).collect::<Vec<_>>();
+    let committee =
+        Committee::make_simple(keys.iter().map(|key| ValidatorName(key.public())).collect());
+
+    let mut certificates = (1..=5)
+        .map(|index| {
+            let block = make_first_block(ChainId::root(index))
+                .with_simple_transfer(ChainId::root(0), Amount::ONE);
+            let executed_block = BlockExecutionOutcome {
+                messages: vec![Vec::new()],
+                state_hash: CryptoHash::test_hash("state"),
+                oracle_responses: vec![Vec::new()],
+                events: vec![Vec::new()],
+            }
+            .with(block);
+            let value = Hashed::new(ConfirmedBlock::new(executed_block));
+            let mut builder = SignatureAggregator::new(value.clone(), Round::Fast, &committee);
+            keys.iter()
+                .find_map(|key| {
+                    let vote = LiteVote::new(LiteValue::new(&value), Round::Fast, key);
+                    builder.append(vote.validator, vote.signature).unwrap()
+                })
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert!(ConfirmedBlockCertificate::verify_batch(&certificates, &committee).is_ok());
+
+    // Replace one signature of the third certificate with one for another value.
+    let bad_hash = certificates[2].hash();
+    let wrong_signature = certificates[1].signatures()[0].1;
+    certificates[2].signatures_mut()[1].1 = wrong_signature;
+    assert_matches!(
+        ConfirmedBlockCertificate::verify_batch(&certificates, &committee),
+        Err(ChainError::InvalidCertificate { hash, error })
+            if hash == bad_hash && matches!(*error, ChainError::CryptoError(_))
+    );
+    for (index, certificate) in certificates.iter().enumerate() {
+        assert_eq!(certificate.check(&committee).is_ok(), index != 2);
+    }
+}
+
+#[test]
+fn test_certificate_json_round_trip() {
+    let key = KeyPair::generate();
+    let name = ValidatorName(key.public());
+    let committee = Committee::make_simple(vec![name]);
+
+    let block = make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(3));
+    let executed_block = BlockExecutionOutcome {
+        messages: vec![Vec::new()],
+        state_hash: CryptoHash::test_hash("state"),
+        oracle_responses: vec![Vec::new()],
+        events: vec![Vec::new()],
+    }
+    .with(block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+    let vote = LiteVote::new(LiteValue::new(&value), Round::Fast, &key);
+    let mut builder = SignatureAggregator::new(value, Round::Fast, &committee);
+    let certificate = builder
+        .append(vote.validator, vote.signature)
+        .unwrap()
+        .unwrap();
+
+    let json = serde_json::to_value(&certificate).unwrap();
+    let header = &json["value"]["header"];
+    assert_eq!(
+        header["state_hash"],
+        CryptoHash::test_hash("state").to_string()
+    );
+    assert_eq!(header["chain_id"], ChainId::root(1).to_string());
+    let transfer = &json["value"]["body"]["operations"][0]["System"]["Transfer"];
+    assert_eq!(transfer["amount"], Amount::from_tokens(3).to_string());
+    let (json_name, json_signature) = (&json["signatures"][0][0], &json["signatures"][0][1]);
+    assert_eq!(*json_name, name.to_string());
+    assert_eq!(
+        *json_signature,
+        serde_json::to_value(vote.signature).unwrap()
+    );
+    assert!(json_signature.is_string());
+
+    let round_trip: ConfirmedBlockCertificate = serde_json::from_value(json).unwrap();
+    assert_eq!(round_trip, certificate);
+    assert_eq!(round_trip.hash(), certificate.hash());
+    assert!(round_trip.check(&committee).is_ok());
+}
+
+#[test]
+fn test_certificate_effects() {
+    let key = KeyPair::generate();
+    let committee = Committee::make_simple(vec![ValidatorName(key.public())]);
+    let owner = Owner::from(KeyPair::generate().public());
+
+    let block = make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(3))
+        .with_transfer(Some(owner), Recipient::Burn, Amount::ONE)
+        .with_operation(SystemOperation::ChangeOwnership {
+            super_owners: Vec::new(),
+            owners: vec![(owner, 100)],
+            multi_leader_rounds: 2,
+            open_multi_leader_rounds: false,
+            timeout_config: TimeoutConfig::default(),
+        });
+    let credit = |recipient: ChainId, amount: Amount| OutgoingMessage {
+        destination: Destination::Recipient(recipient),
+        authenticated_signer: None,
+        grant: Amount::ZERO,
+        refund_grant_to: None,
+        kind: MessageKind::Tracked,
+        message: Message::System(SystemMessage::Credit {
+            target: None,
+            amount,
+            source: None,
+        }),
+    };
+    let executed_block = BlockExecutionOutcome {
+        messages: vec![
+            vec![credit(ChainId::root(2), Amount::from_tokens(3))],
+            // Credits to the chain itself are not effects on other chains.
+            vec![credit(ChainId::root(1), Amount::ONE)],
+            Vec::new(),
+        ],
+        state_hash: CryptoHash::test_hash("state"),
+        oracle_responses: vec![Vec::new(); 3],
+        events: vec![Vec::new(); 3],
+    }
+    .with(block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+    let vote = LiteVote::new(LiteValue::new(&value), Round::Fast, &key);
+    let mut builder = SignatureAggregator::new(value, Round::Fast, &committee);
+    let certificate = builder
+        .append(vote.validator, vote.signature)
+        .unwrap()
+        .unwrap();
+
+    // The effects are part of the signed value.
+    let bytes = bcs::to_bytes(&certificate).unwrap();
+    let certificate: ConfirmedBlockCertificate = bcs::from_bytes(&bytes).unwrap();
+    assert!(certificate.check(&committee).is_ok());
+    let mut tampered = certificate.block().clone();
+    tampered.body.effects.pop();
+    assert_ne!(CryptoHash::new(&tampered), certificate.hash());
+    assert_eq!(
+        certificate.effects(),
+        vec![
+            Effect::Burn {
+                owner: Some(AccountOwner::User(owner)),
+                amount: Amount::ONE,
+            },
+            Effect::OwnershipChange {
+                super_owners: Vec::new(),
+                owners: vec![(owner, 100)],
+            },
+            Effect::Credit {
+                recipient: ChainId::root(2),
+                target: None,
+                amount: Amount::from_tokens(3),
+            },
+        ]
+    );
+
+    // A certificate signed before the effects were added to blocks still verifies, and its
+    // effects are computed when it is read.
+    let block_bytes = bcs::to_bytes(certificate.block()).unwrap();
+    let effects_bytes = bcs::to_bytes(certificate.effects()).unwrap();
+    let legacy_bytes = &block_bytes[..block_bytes.len() - effects_bytes.len()];
+    let legacy_value = ConfirmedBlock::from_legacy_bytes(legacy_bytes).unwrap();
+    let legacy_hash = legacy_value.inner().hash();
+    assert_ne!(legacy_hash, certificate.hash());
+    let legacy_value = legacy_value.with_hash_unchecked(legacy_hash);
+    let vote = LiteVote::new(LiteValue::new(&legacy_value), Round::Fast, &key);
+    let mut builder = SignatureAggregator::new(legacy_value, Round::Fast, &committee);
+    let legacy_certificate = builder
+        .append(vote.validator, vote.signature)
+        .unwrap()
+        .unwrap();
+    assert!(legacy_certificate.check(&committee).is_ok());
+    assert_eq!(legacy_certificate.effects(), certificate.effects());
+}
+
+#[test]
+fn test_effects_of_messages() {
+    let chain_id = ChainId::root(1);
+    let owner = Owner::from(KeyPair::generate().public());
+    let bundle =
+        |message: SystemMessage, kind: MessageKind, action: MessageAction| IncomingBundle {
+            origin: Origin::chain(ChainId::root(2)),
+            bundle: MessageBundle {
+                certificate_hash: CryptoHash::test_hash("certificate"),
+                height: BlockHeight(0),
+                transaction_index: 0,
+                timestamp: Timestamp::from(0),
+                messages: vec![message.to_posted(0, kind)],
+            },
+            action,
+        };
+    let burn = SystemMessage::Withdraw {
+        owner: AccountOwner::User(owner),
+        amount: Amount::ONE,
+        recipient: Recipient::Burn,
+    };
+    let credit = SystemMessage::Credit {
+        target: None,
+        amount: Amount::from_tokens(2),
+        source: None,
+    };
+    let claim = SystemMessage::WithdrawChainBalance {
+        owner,
+        amount: Amount::from_tokens(3),
+        recipient: Recipient::chain(chain_id),
+    };
+    let config = OpenChainConfig {
+        ownership: ChainOwnership::single(owner),
+        admin_id: chain_id,
+        epoch: Epoch::ZERO,
+        committees: Default::default(),
+        balance: Amount::from_tokens(4),
+        application_permissions: Default::default(),
+    };
+    let outgoing =
+        |recipient: ChainId, message: SystemMessage, kind: MessageKind| OutgoingMessage {
+            destination: Destination::Recipient(recipient),
+            authenticated_signer: None,
+            grant: Amount::ZERO,
+            refund_grant_to: None,
+            kind,
+            message: Message::System(message),
+        };
+
+    let block = make_first_block(chain_id)
+        .with_incoming_bundle(bundle(
+            burn.clone(),
+            MessageKind::Protected,
+            MessageAction::Accept,
+        ))
+        // Rejected messages are not executed, and bouncing them returns the tokens.
+        .with_incoming_bundle(bundle(burn, MessageKind::Protected, MessageAction::Reject))
+        .with_incoming_bundle(bundle(
+            credit.clone(),
+            MessageKind::Tracked,
+            MessageAction::Reject,
+        ))
+        .with_operation(SystemOperation::ClaimChainBalance {
+            owner,
+            target_id: ChainId::root(3),
+            recipient: Recipient::chain(chain_id),
+            amount: Amount::from_tokens(3),
+        })
+        .with_operation(SystemOperation::OpenChain(config.clone()))
+        // Burns by applications are not recorded in the block.
+        .with_operation(Operation::User {
+            application_id: UserApplicationId::default(),
+            bytes: Vec::new(),
+        });
+    let executed_block = BlockExecutionOutcome {
+        messages: vec![
+            Vec::new(),
+            Vec::new(),
+            vec![outgoing(
+                ChainId::root(2),
+                credit.clone(),
+                MessageKind::Bouncing,
+            )],
+            vec![outgoing(ChainId::root(3), claim, MessageKind::Simple)],
+            vec![outgoing(
+                ChainId::root(4),
+                SystemMessage::OpenChain(config),
+                MessageKind::Protected,
+            )],
+            // Credits sent by applications are reported.
+            vec![outgoing(ChainId::root(5), credit, MessageKind::Tracked)],
+        ],
+        state_hash: CryptoHash::test_hash("state"),
+        oracle_responses: vec![Vec::new(); 6],
+        events: vec![Vec::new(); 6],
+    }
+    .with(block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+
+    assert_eq!(
+        value.inner().block().effects(),
+        vec![
+            Effect::Burn {
+                owner: Some(AccountOwner::User(owner)),
+                amount: Amount::ONE,
+            },
+            Effect::Claim {
+                target_id: ChainId::root(3),
+                owner: None,
+                amount: Amount::from_tokens(3),
+                recipient: Recipient::chain(chain_id),
+            },
+            Effect::OpenChain {
+                chain_id: ChainId::root(4),
+                balance: Amount::from_tokens(4),
+            },
+            Effect::Credit {
+                recipient: ChainId::root(5),
+                target: None,
+                amount: Amount::from_tokens(2),
+            },
+        ]
+    );
+}
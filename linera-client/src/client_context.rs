@@ -20,6 +20,7 @@ use linera_core::{
     data_types::ClientOutcome,
     join_set_ext::JoinSet,
     node::CrossChainMessageDelivery,
+    rate_limit::RateLimit,
     JoinSetExt,
 };
 use linera_rpc::node_provider::{NodeOptions, NodeProvider};
@@ -86,6 +87,7 @@ where
     pub recv_timeout: Duration,
     pub retry_delay: Duration,
     pub max_retries: u32,
+    pub rate_limit: Option<RateLimit>,
     pub chain_listeners: JoinSet,
     pub blanket_message_policy: BlanketMessagePolicy,
     pub restrict_chain_ids_to: Option<HashSet<ChainId>>,
@@ -156,6 +158,7 @@ where
             recv_timeout: options.recv_timeout,
             retry_delay: options.retry_delay,
             max_retries: options.max_retries,
+            rate_limit: options.rate_limit(),
         };
         let node_provider = NodeProvider::new(node_options);
         let delivery = CrossChainMessageDelivery::new(options.wait_for_outgoing_messages);
@@ -176,6 +179,7 @@ where
             options.max_loaded_chains,
             options.grace_period,
             options.blob_download_timeout,
+            options.request_timeout,
         );
 
         ClientContext {
@@ -185,6 +189,7 @@ where
             recv_timeout: options.recv_timeout,
             retry_delay: options.retry_delay,
             max_retries: options.max_retries,
+            rate_limit: options.rate_limit(),
             chain_listeners: JoinSet::default(),
             blanket_message_policy: options.blanket_message_policy,
             restrict_chain_ids_to: options.restrict_chain_ids_to,
@@ -204,6 +209,7 @@ where
             recv_timeout: send_recv_timeout,
             retry_delay,
             max_retries,
+            rate_limit: None,
         };
         let node_provider = NodeProvider::new(node_options);
         let delivery = CrossChainMessageDelivery::new(true);
@@ -224,6 +230,7 @@ where
             NonZeroUsize::new(20).expect("Chain worker limit should not be zero"),
             DEFAULT_GRACE_PERIOD,
             Duration::from_secs(1),
+            Duration::from_secs(60),
         );
 
         ClientContext {
@@ -233,6 +240,7 @@ where
             recv_timeout: send_recv_timeout,
             retry_delay,
             max_retries,
+            rate_limit: None,
             chain_listeners: JoinSet::default(),
             blanket_message_policy: BlanketMessagePolicy::Accept,
             restrict_chain_ids_to: None,
@@ -260,8 +268,9 @@ where
         let known_key_pairs = chain
             .key_pair
             .as_ref()
-            .map(|kp| kp.copy())
+            .map(|kp| kp.copy().into())
             .into_iter()
+            .chain(chain.secp256k1_key_pair.as_ref().map(|kp| kp.copy().into()))
             .collect();
         let mut chain_client = self.client.create_chain_client(
             chain_id,
@@ -271,6 +280,7 @@ where
             chain.timestamp,
             chain.next_block_height,
             chain.pending_proposal.clone(),
+            chain.scheduled_blocks.clone(),
         );
         chain_client.options_mut().message_policy = MessagePolicy::new(
             self.blanket_message_policy,
@@ -289,6 +299,7 @@ where
             recv_timeout: self.recv_timeout,
             retry_delay: self.retry_delay,
             max_retries: self.max_retries,
+            rate_limit: self.rate_limit,
         }
     }
 
@@ -338,10 +349,12 @@ where
                 w.insert(UserChain {
                     chain_id,
                     key_pair: key_pair.as_ref().map(|kp| kp.copy()),
+                    secp256k1_key_pair: None,
                     block_hash: None,
                     timestamp,
                     next_block_height: BlockHeight::ZERO,
                     pending_proposal: None,
+                    scheduled_blocks: Vec::new(),
                 })
             })
             .await?;
@@ -680,7 +693,9 @@ where
                 let message_id = block
                     .message_id_for_operation(i, OPEN_CHAIN_MESSAGE_INDEX)
                     .expect("failed to create new chain");
-                let chain_id = ChainId::child(message_id);
+                let chain_id = block
+                    .opened_chain_id(&message_id)
+                    .expect("failed to create new chain");
                 benchmark_chains.insert(chain_id, key_pair.copy());
                 self.client.track_chain(chain_id);
                 self.update_wallet_for_new_chain(chain_id, Some(key_pair.copy()), timestamp)
@@ -885,7 +900,7 @@ where
             let proposal = BlockProposal::new_initial(
                 linera_base::data_types::Round::Fast,
                 block.clone(),
-                key_pair,
+                &key_pair.copy().into(),
             );
             proposals.push(proposal);
             previous_chain_id = chain.chain_id;
@@ -901,7 +916,11 @@ where
     where
         T: std::fmt::Debug + CertificateValue,
     {
-        let committee = self.wallet.genesis_config().create_committee();
+        let committee = self
+            .wallet
+            .genesis_config()
+            .create_committee()
+            .expect("the genesis committee should be valid");
         let mut aggregators = HashMap::new();
         let mut certificates = Vec::new();
         let mut done_senders = HashSet::new();
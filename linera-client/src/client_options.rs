@@ -18,7 +18,7 @@ use linera_base::{
     ownership::{ChainOwnership, TimeoutConfig},
     time::Duration,
 };
-use linera_core::{client::BlanketMessagePolicy, DEFAULT_GRACE_PERIOD};
+use linera_core::{client::BlanketMessagePolicy, rate_limit::RateLimit, DEFAULT_GRACE_PERIOD};
 use linera_execution::{
     committee::ValidatorName, ResourceControlPolicy, WasmRuntime, WithWasmDefault as _,
 };
@@ -134,6 +134,16 @@ pub struct ClientOptions {
     #[arg(long, default_value = "10")]
     pub max_retries: u32,
 
+    /// Maximum number of requests awaiting a response from each validator. Further
+    /// requests wait for their turn.
+    #[arg(long)]
+    pub max_in_flight_per_validator: Option<usize>,
+
+    /// Maximum number of requests per second sent to each validator. Further requests wait
+    /// for their turn.
+    #[arg(long)]
+    pub max_requests_per_second_per_validator: Option<f64>,
+
     /// Whether to wait until a quorum of validators has confirmed that all sent cross-chain
     /// messages have been delivered.
     #[arg(long)]
@@ -169,6 +179,20 @@ pub struct ClientOptions {
         value_parser = util::parse_millis
     )]
     pub blob_download_timeout: Duration,
+
+    /// The maximum time to wait for each request to a validator while updating it, in
+    /// milliseconds. Validators that don't respond in time are treated as failed.
+    #[arg(
+        long = "request-timeout-ms",
+        default_value = "60000",
+        value_parser = util::parse_millis
+    )]
+    pub request_timeout: Duration,
+
+    /// Configuration of the Wasmtime runtime.
+    #[cfg(with_wasmtime)]
+    #[command(flatten)]
+    pub wasmtime_config: WasmtimeConfig,
 }
 
 impl ClientOptions {
@@ -189,6 +213,21 @@ impl ClientOptions {
         Ok(options)
     }
 
+    /// Returns the limits on the requests sent to each validator, if any.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        if self.max_in_flight_per_validator.is_none()
+            && self.max_requests_per_second_per_validator.is_none()
+        {
+            return None;
+        }
+        Some(RateLimit {
+            max_in_flight: self.max_in_flight_per_validator.unwrap_or(usize::MAX),
+            max_per_second: self
+                .max_requests_per_second_per_validator
+                .unwrap_or(f64::INFINITY),
+        })
+    }
+
     fn common_config(&self) -> CommonStoreConfig {
         CommonStoreConfig {
             max_concurrent_queries: self.max_concurrent_queries,
@@ -490,6 +529,10 @@ pub enum ClientCommand {
         /// Skip the version and genesis config checks.
         #[arg(long)]
         skip_online_check: bool,
+
+        /// Allow a single validator to have a quorum on its own in the new committee.
+        #[arg(long)]
+        force: bool,
     },
 
     /// Remove a validator (admin only)
@@ -497,6 +540,10 @@ pub enum ClientCommand {
         /// The public key of the validator.
         #[arg(long)]
         name: ValidatorName,
+
+        /// Allow a single validator to have a quorum on its own in the new committee.
+        #[arg(long)]
+        force: bool,
     },
 
     /// Deprecates all committees except the last one.
@@ -580,6 +627,22 @@ pub enum ClientCommand {
         /// Set the maximum write data per block.
         #[arg(long)]
         maximum_bytes_written_per_block: Option<u64>,
+
+        /// Set the maximum fuel a service can consume to answer a query.
+        #[arg(long)]
+        maximum_service_fuel_per_query: Option<u64>,
+
+        /// Set the maximum size of the linear memory of a Wasm contract or service, in bytes.
+        #[arg(long)]
+        maximum_wasm_memory_bytes: Option<u64>,
+
+        /// Set the maximum number of elements in a table of a Wasm contract or service.
+        #[arg(long)]
+        maximum_wasm_table_elements: Option<u64>,
+
+        /// Set the maximum number of Wasm instances a contract or service can create.
+        #[arg(long)]
+        maximum_wasm_instances: Option<u64>,
     },
 
     /// Send one transfer per chain in bulk mode
@@ -708,6 +771,22 @@ pub enum ClientCommand {
         #[arg(long)]
         maximum_bytes_written_per_block: Option<u64>,
 
+        /// Set the maximum fuel a service can consume to answer a query.
+        #[arg(long)]
+        maximum_service_fuel_per_query: Option<u64>,
+
+        /// Set the maximum size of the linear memory of a Wasm contract or service, in bytes.
+        #[arg(long)]
+        maximum_wasm_memory_bytes: Option<u64>,
+
+        /// Set the maximum number of elements in a table of a Wasm contract or service.
+        #[arg(long)]
+        maximum_wasm_table_elements: Option<u64>,
+
+        /// Set the maximum number of Wasm instances a contract or service can create.
+        #[arg(long)]
+        maximum_wasm_instances: Option<u64>,
+
         /// Force this wallet to generate keys using a PRNG and a given seed. USE FOR
         /// TESTING ONLY.
         #[arg(long)]
@@ -1387,3 +1466,61 @@ impl From<ApplicationPermissionsConfig> for ApplicationPermissions {
         }
     }
 }
+
+/// Options to configure the Wasmtime runtime, installed once at startup.
+#[cfg(with_wasmtime)]
+#[derive(Debug, Clone, clap::Args)]
+pub struct WasmtimeConfig {
+    /// Executes again the operations that trap with an unoptimized build of the contract, to
+    /// report the backtrace of the trap. This is slow and meant for debugging applications.
+    #[arg(long)]
+    pub wasm_trap_diagnostics: bool,
+
+    /// A directory where the compiled Wasm modules are stored, so that they don't have to be
+    /// compiled again after a restart. It must only be writable by this process, since the
+    /// native code stored in it is executed. Old modules are never removed, but the directory
+    /// can be cleared at any time.
+    #[arg(long)]
+    pub wasm_module_artifact_directory: Option<PathBuf>,
+
+    /// The maximum number of compiled modules kept in each of the contract and service caches.
+    #[arg(long)]
+    pub wasm_module_cache_max_entries: Option<usize>,
+
+    /// The maximum total size, in bytes, of the bytecodes of the modules kept in each of the
+    /// contract and service caches.
+    #[arg(long)]
+    pub wasm_module_cache_max_bytes: Option<u64>,
+
+    /// The maximum number of idle contract instances kept for reuse per module, or zero to
+    /// instantiate every contract from scratch.
+    #[arg(long, default_value = "0")]
+    pub wasm_contract_instance_pool_size: usize,
+
+    /// The maximum number of idle service instances kept for reuse per module, or zero to
+    /// instantiate every service from scratch.
+    #[arg(long, default_value = "0")]
+    pub wasm_service_instance_pool_size: usize,
+}
+
+#[cfg(with_wasmtime)]
+impl WasmtimeConfig {
+    /// Installs the Wasmtime configuration for the rest of the process.
+    pub fn install(&self) -> Result<(), linera_execution::WasmExecutionError> {
+        let default = linera_execution::WasmRuntimeConfig::default();
+        linera_execution::WasmRuntimeConfig {
+            trap_diagnostics: self.wasm_trap_diagnostics,
+            module_artifact_directory: self.wasm_module_artifact_directory.clone(),
+            module_cache_max_entries: self
+                .wasm_module_cache_max_entries
+                .unwrap_or(default.module_cache_max_entries),
+            module_cache_max_bytes: self
+                .wasm_module_cache_max_bytes
+                .unwrap_or(default.module_cache_max_bytes),
+            contract_instance_pool_size: self.wasm_contract_instance_pool_size,
+            service_instance_pool_size: self.wasm_service_instance_pool_size,
+            ..default
+        }
+        .install()
+    }
+}
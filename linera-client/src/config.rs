@@ -13,7 +13,7 @@ use linera_base::{
     identifiers::{ChainDescription, ChainId},
 };
 use linera_execution::{
-    committee::{Committee, ValidatorName, ValidatorState},
+    committee::{Committee, CommitteeError, ValidatorName, ValidatorState},
     ResourceControlPolicy,
 };
 use linera_rpc::config::{ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig};
@@ -28,6 +28,8 @@ pub enum Error {
     Chain(#[from] linera_chain::ChainError),
     #[error("persistence error: {0}")]
     Persistence(Box<dyn std::error::Error + Send + Sync>),
+    #[error("committee error: {0}")]
+    Committee(#[from] CommitteeError),
 }
 
 use crate::{
@@ -70,7 +72,10 @@ pub struct CommitteeConfig {
 }
 
 impl CommitteeConfig {
-    pub fn into_committee(self, policy: ResourceControlPolicy) -> Committee {
+    pub fn into_committee(
+        self,
+        policy: ResourceControlPolicy,
+    ) -> Result<Committee, CommitteeError> {
         let validators = self
             .validators
             .into_iter()
@@ -213,7 +218,7 @@ impl GenesisConfig {
     where
         S: Storage + Clone + Send + Sync + 'static,
     {
-        let committee = self.create_committee();
+        let committee = self.create_committee()?;
         for (chain_number, (public_key, balance)) in (0..).zip(&self.chains) {
             let description = ChainDescription::Root(chain_number);
             storage
@@ -230,7 +235,7 @@ impl GenesisConfig {
         Ok(())
     }
 
-    pub fn create_committee(&self) -> Committee {
+    pub fn create_committee(&self) -> Result<Committee, CommitteeError> {
         self.committee.clone().into_committee(self.policy.clone())
     }
 
@@ -60,7 +60,7 @@ impl chain_listener::ClientContext for ClientContext {
         let known_key_pairs = chain
             .key_pair
             .as_ref()
-            .map(|kp| kp.copy())
+            .map(|kp| kp.copy().into())
             .into_iter()
             .collect();
         Ok(self.client.create_chain_client(
@@ -71,6 +71,7 @@ impl chain_listener::ClientContext for ClientContext {
             chain.timestamp,
             chain.next_block_height,
             chain.pending_proposal.clone(),
+            chain.scheduled_blocks.clone(),
         ))
     }
 
@@ -84,10 +85,12 @@ impl chain_listener::ClientContext for ClientContext {
             self.wallet.insert(UserChain {
                 chain_id,
                 key_pair: key_pair.as_ref().map(|kp| kp.copy()),
+                secp256k1_key_pair: None,
                 block_hash: None,
                 timestamp,
                 next_block_height: BlockHeight::ZERO,
                 pending_proposal: None,
+                scheduled_blocks: Vec::new(),
             });
         }
 
@@ -134,6 +137,7 @@ async fn test_chain_listener() -> anyhow::Result<()> {
             NonZeroUsize::new(20).expect("Chain worker LRU cache size must be non-zero"),
             DEFAULT_GRACE_PERIOD,
             Duration::from_secs(1),
+            Duration::from_secs(60),
         )),
     };
     let key_pair = KeyPair::generate_from(&mut rng);
@@ -7,13 +7,13 @@ use std::{
 };
 
 use linera_base::{
-    crypto::{CryptoHash, CryptoRng, KeyPair},
+    crypto::{AccountSecretKey, CryptoHash, CryptoRng, KeyPair, Secp256k1KeyPair},
     data_types::{BlockHeight, Timestamp},
     ensure,
     identifiers::{ChainDescription, ChainId, Owner},
 };
 use linera_core::{
-    client::{ChainClient, PendingProposal},
+    client::{ChainClient, PendingProposal, ScheduledBlock},
     node::ValidatorNodeProvider,
 };
 use linera_storage::Storage;
@@ -97,7 +97,10 @@ impl Wallet {
     pub fn owned_chain_ids(&self) -> Vec<ChainId> {
         self.chains
             .iter()
-            .filter_map(|(chain_id, chain)| chain.key_pair.is_some().then_some(*chain_id))
+            .filter_map(|(chain_id, chain)| {
+                (chain.key_pair.is_some() || chain.secp256k1_key_pair.is_some())
+                    .then_some(*chain_id)
+            })
             .collect()
     }
 
@@ -146,10 +149,12 @@ impl Wallet {
         let user_chain = UserChain {
             chain_id,
             key_pair: Some(key_pair),
+            secp256k1_key_pair: None,
             block_hash: None,
             timestamp,
             next_block_height: BlockHeight(0),
             pending_proposal: None,
+            scheduled_blocks: Vec::new(),
         };
         self.insert(user_chain);
         Ok(())
@@ -169,17 +174,27 @@ impl Wallet {
         P: ValidatorNodeProvider + Sync + 'static,
         S: Storage + Clone + Send + Sync + 'static,
     {
-        let key_pair = chain_client.key_pair().await.map(|k| k.copy()).ok();
+        let previous = self.chains.remove(&chain_client.chain_id());
+        let (key_pair, secp256k1_key_pair) = match chain_client.key_pair().await {
+            Ok(AccountSecretKey::Ed25519(key_pair)) => (Some(key_pair), None),
+            Ok(AccountSecretKey::Secp256k1(key_pair)) => (None, Some(key_pair)),
+            // The client doesn't know our current key, so keep the ones we had.
+            Err(_) => previous.map_or((None, None), |chain| {
+                (chain.key_pair, chain.secp256k1_key_pair)
+            }),
+        };
         let state = chain_client.state();
         self.chains.insert(
             chain_client.chain_id(),
             UserChain {
                 chain_id: chain_client.chain_id(),
                 key_pair,
+                secp256k1_key_pair,
                 block_hash: state.block_hash(),
                 next_block_height: state.next_block_height(),
                 timestamp: state.timestamp(),
                 pending_proposal: state.pending_proposal().clone(),
+                scheduled_blocks: state.scheduled_blocks().to_vec(),
             },
         );
     }
@@ -207,10 +222,16 @@ impl Wallet {
 pub struct UserChain {
     pub chain_id: ChainId,
     pub key_pair: Option<KeyPair>,
+    /// The chain's key, if it is a secp256k1 key rather than the ed25519 `key_pair`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secp256k1_key_pair: Option<Secp256k1KeyPair>,
     pub block_hash: Option<CryptoHash>,
     pub timestamp: Timestamp,
     pub next_block_height: BlockHeight,
     pub pending_proposal: Option<PendingProposal>,
+    /// The timelocked blocks that are waiting for the chain to reach their height.
+    #[serde(default)]
+    pub scheduled_blocks: Vec<ScheduledBlock>,
 }
 
 impl UserChain {
@@ -224,10 +245,12 @@ impl UserChain {
         Self {
             chain_id: description.into(),
             key_pair: Some(key_pair),
+            secp256k1_key_pair: None,
             block_hash: None,
             timestamp,
             next_block_height: BlockHeight::ZERO,
             pending_proposal: None,
+            scheduled_blocks: Vec::new(),
         }
     }
 
@@ -237,10 +260,12 @@ impl UserChain {
         Self {
             chain_id,
             key_pair: None,
+            secp256k1_key_pair: None,
             block_hash: None,
             timestamp,
             next_block_height: BlockHeight::ZERO,
             pending_proposal: None,
+            scheduled_blocks: Vec::new(),
         }
     }
 }
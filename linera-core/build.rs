@@ -5,6 +5,7 @@ fn main() {
     cfg_aliases::cfg_aliases! {
         web: { all(target_arch = "wasm32", feature = "web") },
         with_testing: { any(test, feature = "test") },
+        with_test_helpers: { any(test, feature = "test", feature = "test-helpers") },
         with_metrics: { all(not(target_arch = "wasm32"), feature = "metrics") },
     };
 }
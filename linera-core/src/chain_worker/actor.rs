@@ -6,7 +6,7 @@
 use std::{
     collections::{BTreeMap, HashSet},
     fmt,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
 };
 
 use custom_debug_derive::Debug;
@@ -23,7 +23,7 @@ use linera_chain::{
 };
 use linera_execution::{
     committee::{Epoch, ValidatorName},
-    Query, QueryContext, QueryOutcome, ServiceRuntimeEndpoint, ServiceSyncRuntime,
+    Query, QueryContext, QueryOutcome, ResourceTracker, ServiceRuntimeEndpoint, ServiceSyncRuntime,
 };
 use linera_storage::Storage;
 use tokio::sync::{mpsc, oneshot, OwnedRwLockReadGuard};
@@ -33,7 +33,7 @@ use super::{config::ChainWorkerConfig, state::ChainWorkerState, DeliveryNotifier
 use crate::{
     data_types::{ChainInfoQuery, ChainInfoResponse},
     value_cache::ValueCache,
-    worker::{NetworkActions, WorkerError},
+    worker::{ChainCounters, NetworkActions, WorkerError},
 };
 
 /// A request for the [`ChainWorkerActor`].
@@ -87,7 +87,9 @@ where
         block: ProposedBlock,
         round: Option<u32>,
         #[debug(skip)]
-        callback: oneshot::Sender<Result<(ExecutedBlock, ChainInfoResponse), WorkerError>>,
+        callback: oneshot::Sender<
+            Result<(ExecutedBlock, ChainInfoResponse, ResourceTracker), WorkerError>,
+        >,
     },
 
     /// Process a leader timeout issued for this multi-owner chain.
@@ -158,7 +160,15 @@ where
 
     /// Update the received certificate trackers to at least the given values.
     UpdateReceivedCertificateTrackers {
-        new_trackers: BTreeMap<ValidatorName, u64>,
+        new_trackers: BTreeMap<ChainId, BlockHeight>,
+        new_log_indices: BTreeMap<ValidatorName, u64>,
+        callback: oneshot::Sender<Result<(), WorkerError>>,
+    },
+
+    /// Delete the certificates of old blocks, keeping the last `keep_last` ones.
+    PruneHistory {
+        keep_last: usize,
+        keep_from: BlockHeight,
         callback: oneshot::Sender<Result<(), WorkerError>>,
     },
 }
@@ -184,6 +194,7 @@ where
         executed_block_cache: Arc<ValueCache<CryptoHash, Hashed<Block>>>,
         tracked_chains: Option<Arc<RwLock<HashSet<ChainId>>>>,
         delivery_notifier: DeliveryNotifier,
+        chain_counters: Arc<Mutex<ChainCounters>>,
         chain_id: ChainId,
     ) -> Result<Self, WorkerError> {
         let (service_runtime_thread, service_runtime_endpoint) = {
@@ -201,6 +212,7 @@ where
             executed_block_cache,
             tracked_chains,
             delivery_notifier,
+            chain_counters,
             chain_id,
             service_runtime_endpoint,
         )
@@ -351,14 +363,22 @@ where
                     .is_ok(),
                 ChainWorkerRequest::UpdateReceivedCertificateTrackers {
                     new_trackers,
+                    new_log_indices,
                     callback,
                 } => callback
                     .send(
                         self.worker
-                            .update_received_certificate_trackers(new_trackers)
+                            .update_received_certificate_trackers(new_trackers, new_log_indices)
                             .await,
                     )
                     .is_ok(),
+                ChainWorkerRequest::PruneHistory {
+                    keep_last,
+                    keep_from,
+                    callback,
+                } => callback
+                    .send(self.worker.prune_history(keep_last, keep_from).await)
+                    .is_ok(),
             };
 
             if !responded {
@@ -3,18 +3,45 @@
 
 //! Configuration parameters for the chain worker.
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
-use linera_base::{crypto::KeyPair, time::Duration};
+use linera_base::{crypto::KeyPair, data_types::Timestamp, identifiers::ChainId, time::Duration};
+
+/// Whether a worker stores the state of chains that are not active yet, e.g. the messages
+/// sent to chains that haven't been created.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum InactiveChainPolicy {
+    /// Never store the state of inactive chains.
+    #[default]
+    Reject,
+    /// Always store the state of inactive chains.
+    Allow,
+    /// Store the state of an inactive chain only during `ttl` after a cross-chain message
+    /// first referenced it as its recipient.
+    AllowRecentlyReferenced { ttl: Duration },
+}
+
+/// The default maximum number of sent certificate hashes returned in a single chain info
+/// response.
+pub const DEFAULT_MAXIMUM_SENT_CERTIFICATE_HASHES_PER_RESPONSE: usize = 10_000;
+
+/// The default maximum number of outbox statuses returned in a single chain info response.
+pub const DEFAULT_MAXIMUM_OUTBOXES_PER_RESPONSE: usize = 1_000;
 
 /// Configuration parameters for the [`ChainWorkerState`][`super::state::ChainWorkerState`].
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct ChainWorkerConfig {
     /// The signature key pair of the validator. The key may be missing for replicas
     /// without voting rights (possibly with a partial view of chains).
     pub key_pair: Option<Arc<KeyPair>>,
-    /// Whether inactive chains are allowed in storage.
-    pub allow_inactive_chains: bool,
+    /// Whether the states of inactive chains are allowed in storage.
+    pub inactive_chain_policy: InactiveChainPolicy,
+    /// When each inactive chain was first referenced by a cross-chain message, for
+    /// [`InactiveChainPolicy::AllowRecentlyReferenced`].
+    pub(crate) referenced_chains: Arc<Mutex<HashMap<ChainId, Timestamp>>>,
     /// Whether new messages from deprecated epochs are allowed.
     pub allow_messages_from_deprecated_epochs: bool,
     /// Whether the user application services should be long-lived.
@@ -22,6 +49,28 @@ pub struct ChainWorkerConfig {
     /// Blocks with a timestamp this far in the future will still be accepted, but the validator
     /// will wait until that timestamp before voting.
     pub grace_period: Duration,
+    /// The maximum number of sent certificate hashes returned in a single chain info
+    /// response. Longer ranges are truncated and have to be resumed by the client.
+    pub maximum_sent_certificate_hashes_per_response: usize,
+    /// The maximum number of outbox statuses returned in a single chain info response.
+    /// Clients request the remaining outboxes with an offset.
+    pub maximum_outboxes_per_response: usize,
+}
+
+impl Default for ChainWorkerConfig {
+    fn default() -> Self {
+        ChainWorkerConfig {
+            key_pair: None,
+            inactive_chain_policy: InactiveChainPolicy::default(),
+            referenced_chains: Arc::default(),
+            allow_messages_from_deprecated_epochs: false,
+            long_lived_services: false,
+            grace_period: Duration::default(),
+            maximum_sent_certificate_hashes_per_response:
+                DEFAULT_MAXIMUM_SENT_CERTIFICATE_HASHES_PER_RESPONSE,
+            maximum_outboxes_per_response: DEFAULT_MAXIMUM_OUTBOXES_PER_RESPONSE,
+        }
+    }
 }
 
 impl ChainWorkerConfig {
@@ -13,6 +13,9 @@ pub(super) use self::delivery_notifier::DeliveryNotifier;
 pub(crate) use self::state::CrossChainUpdateHelper;
 pub use self::{
     actor::{ChainWorkerActor, ChainWorkerRequest},
-    config::ChainWorkerConfig,
+    config::{
+        ChainWorkerConfig, InactiveChainPolicy, DEFAULT_MAXIMUM_OUTBOXES_PER_RESPONSE,
+        DEFAULT_MAXIMUM_SENT_CERTIFICATE_HASHES_PER_RESPONSE,
+    },
     state::ChainWorkerState,
 };
@@ -24,7 +24,7 @@ use linera_execution::{
     committee::{Committee, Epoch, ValidatorName},
     BlobState,
 };
-use linera_storage::{Clock as _, Storage};
+use linera_storage::{Batch, Clock as _, Storage};
 use linera_views::{
     context::Context,
     views::{RootView, View},
@@ -135,6 +135,7 @@ where
             owner,
             validated_block_certificate,
             signature: _,
+            cosignatures: _,
         } = proposal;
 
         let chain = &self.state.chain;
@@ -148,6 +149,23 @@ where
             chain.manager.verify_owner(proposal),
             WorkerError::InvalidOwner
         );
+        // Check that enough distinct owners approved the block, if required. This applies to
+        // retried blocks, too: the approvals don't depend on the round.
+        if let Some(threshold) = *chain.execution_state.system.approval_threshold.get() {
+            let ownership = chain.manager.ownership.get();
+            let approvals = proposal
+                .approving_owners()?
+                .iter()
+                .filter(|owner| ownership.verify_owner(owner))
+                .count();
+            ensure!(
+                approvals >= threshold as usize,
+                WorkerError::InsufficientApprovals {
+                    approvals,
+                    threshold,
+                }
+            );
+        }
         if let Some(lite_certificate) = validated_block_certificate {
             // Verify that this block has been validated by a quorum before.
             lite_certificate.check(committee)?;
@@ -360,11 +378,14 @@ where
             .await
             .map(|blobs| blobs.into_values().collect::<Vec<_>>());
 
+        // Write the blobs, the certificate and the blob states at once, so that a crash
+        // doesn't leave only some of them in storage.
+        let mut batch = Batch::new();
         if let Ok(blobs) = &blobs_result {
-            self.state
-                .storage
-                .write_blobs_and_certificate(blobs, &certificate)
-                .await?;
+            for blob in blobs {
+                batch.add_blob(blob.clone());
+            }
+            batch.add_certificate(certificate.clone());
         }
 
         // Update the blob state with last used certificate hash.
@@ -375,11 +396,8 @@ where
             epoch: executed_block.block.epoch,
         };
         let overwrite = blobs_result.is_ok(); // Overwrite only if we wrote the certificate.
-        let blob_ids = required_blob_ids.into_iter().collect::<Vec<_>>();
-        self.state
-            .storage
-            .maybe_write_blob_states(&blob_ids, blob_state, overwrite)
-            .await?;
+        batch.add_blob_states(required_blob_ids, blob_state, overwrite);
+        self.state.storage.write_batch(batch).await?;
         blobs_result?;
 
         // Execute the block and update inboxes.
@@ -399,13 +417,27 @@ where
         ))
         .await?;
         // We should always agree on the messages and state hash.
-        ensure!(
-            executed_block.outcome == verified_outcome,
-            WorkerError::IncorrectOutcome {
+        if executed_block.outcome != verified_outcome {
+            let only_state_hash_differs = BlockExecutionOutcome {
+                state_hash: verified_outcome.state_hash,
+                ..executed_block.outcome.clone()
+            } == verified_outcome;
+            if only_state_hash_differs {
+                return Err(WorkerError::StateHashMismatch {
+                    chain_id: executed_block.block.chain_id,
+                    height: block_height,
+                });
+            }
+            return Err(WorkerError::IncorrectOutcome {
                 submitted: Box::new(executed_block.outcome.clone()),
                 computed: Box::new(verified_outcome),
-            }
-        );
+            });
+        }
+        let balance_path = self.state.chain.execution_state.balance_path().await?;
+        self.state
+            .chain
+            .balance_paths
+            .insert(&block_height, balance_path)?;
         // Advance to next block height.
         let tip = self.state.chain.tip_state.get_mut();
         tip.block_hash = Some(certificate.hash());
@@ -431,6 +463,14 @@ where
         });
         // Persist chain.
         self.save().await?;
+        self.update_pending_message_bundles(
+            executed_block
+                .block
+                .incoming_bundles
+                .iter()
+                .map(|bundle| bundle.origin.clone()),
+        )
+        .await?;
 
         self.state
             .block_values
@@ -497,6 +537,8 @@ where
         let Some(last_updated_height) = bundles.last().map(|bundle| bundle.height) else {
             return Ok(None);
         };
+        // Whether messages were stored before, if the chain is inactive.
+        let received_messages = self.state.chain.received_log.count() > 0;
         // Process the received messages in certificates.
         let local_time = self.state.storage.clock().current_time();
         let mut previous_height = None;
@@ -514,7 +556,11 @@ where
                 new_outbox_entries = true;
             }
         }
-        if !self.state.config.allow_inactive_chains && !self.state.chain.is_active() {
+        if !self.state.chain.is_active()
+            && !self
+                .state
+                .allows_inactive_chain(local_time, received_messages)
+        {
             // Refuse to create a chain state if the chain is still inactive by
             // now. Accordingly, do not send a confirmation, so that the
             // cross-chain update is retried later.
@@ -532,6 +578,7 @@ where
         };
         // Save the chain.
         self.save().await?;
+        self.update_pending_message_bundles([origin]).await?;
         Ok(Some((last_updated_height, actions)))
     }
 
@@ -569,12 +616,46 @@ where
 
     pub async fn update_received_certificate_trackers(
         &mut self,
-        new_trackers: BTreeMap<ValidatorName, u64>,
+        new_trackers: BTreeMap<ChainId, BlockHeight>,
+        new_log_indices: BTreeMap<ValidatorName, u64>,
     ) -> Result<(), WorkerError> {
         self.state
             .chain
-            .update_received_certificate_trackers(new_trackers);
+            .update_received_certificate_trackers(new_trackers, new_log_indices);
+        self.save().await?;
+        Ok(())
+    }
+
+    pub async fn prune_history(
+        &mut self,
+        keep_last: usize,
+        keep_from: BlockHeight,
+    ) -> Result<(), WorkerError> {
+        let chain = &mut self.state.chain;
+        let start = usize::try_from(*chain.pruned_height.get())?;
+        let next_height = usize::try_from(chain.tip_state.get().next_block_height)?;
+        let mut end = next_height
+            .saturating_sub(keep_last)
+            .min(usize::try_from(keep_from).unwrap_or(usize::MAX));
+        if let Some(height) = chain.outbox_counters.get().keys().next() {
+            end = end.min(usize::try_from(*height)?);
+        }
+        if end <= start {
+            return Ok(());
+        }
+        let hashes = chain.confirmed_log.read(start..end).await?;
+        for height in start..end {
+            chain
+                .balance_paths
+                .remove(&BlockHeight::from(height as u64))?;
+        }
+        chain.pruned_height.set(BlockHeight::from(end as u64));
+        // Save first: if we stop before all certificates are deleted, some are left behind
+        // but the chain state never refers to missing ones.
         self.save().await?;
+        for hash in hashes {
+            self.state.storage.delete_certificate(hash).await?;
+        }
         Ok(())
     }
 
@@ -680,6 +761,33 @@ where
 
         self.state.chain.save().await?;
         self.succeeded = true;
+        self.state.chain_counters.lock().unwrap().update_heights(
+            self.state.chain_id(),
+            self.state.chain.tip_state.get().next_block_height,
+            *self.state.chain.pruned_height.get(),
+        );
+        Ok(())
+    }
+
+    /// Updates the worker's counters with the number of pending bundles in the inboxes from
+    /// the given origins. This must be called after saving changes to these inboxes.
+    async fn update_pending_message_bundles(
+        &self,
+        origins: impl IntoIterator<Item = Origin>,
+    ) -> Result<(), WorkerError> {
+        let mut counts = Vec::new();
+        for origin in origins {
+            let count = match self.state.chain.inboxes.try_load_entry(&origin).await? {
+                Some(inbox) => inbox.added_bundles.count(),
+                None => 0,
+            };
+            counts.push((origin, count));
+        }
+        let chain_id = self.state.chain_id();
+        let mut counters = self.state.chain_counters.lock().unwrap();
+        for (origin, count) in counts {
+            counters.update_pending_message_bundles(chain_id, origin, count);
+        }
         Ok(())
     }
 }
@@ -9,15 +9,15 @@ mod temporary_changes;
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     iter,
-    sync::{self, Arc},
+    sync::{self, Arc, Mutex},
 };
 
 use linera_base::{
     crypto::CryptoHash,
-    data_types::{Blob, BlockHeight, UserApplicationDescription},
+    data_types::{Blob, BlockHeight, Timestamp, UserApplicationDescription},
     ensure,
     hashed::Hashed,
-    identifiers::{BlobId, ChainId, UserApplicationId},
+    identifiers::{BlobId, ChainId, Destination, UserApplicationId},
 };
 use linera_chain::{
     data_types::{
@@ -28,7 +28,8 @@ use linera_chain::{
 };
 use linera_execution::{
     committee::{Epoch, ValidatorName},
-    Message, Query, QueryContext, QueryOutcome, ServiceRuntimeEndpoint, SystemMessage,
+    Message, Query, QueryContext, QueryOutcome, ResourceTracker, ServiceRuntimeEndpoint,
+    SystemMessage,
 };
 use linera_storage::{Clock as _, Storage};
 use linera_views::views::{ClonableView, ViewError};
@@ -40,11 +41,11 @@ use self::{
     attempted_changes::ChainWorkerStateWithAttemptedChanges,
     temporary_changes::ChainWorkerStateWithTemporaryChanges,
 };
-use super::{ChainWorkerConfig, DeliveryNotifier};
+use super::{ChainWorkerConfig, DeliveryNotifier, InactiveChainPolicy};
 use crate::{
     data_types::{ChainInfoQuery, ChainInfoResponse, CrossChainRequest},
     value_cache::ValueCache,
-    worker::{NetworkActions, WorkerError},
+    worker::{ChainCounters, NetworkActions, WorkerError},
 };
 
 /// The state of the chain worker.
@@ -60,6 +61,7 @@ where
     block_values: Arc<ValueCache<CryptoHash, Hashed<Block>>>,
     tracked_chains: Option<Arc<sync::RwLock<HashSet<ChainId>>>>,
     delivery_notifier: DeliveryNotifier,
+    chain_counters: Arc<Mutex<ChainCounters>>,
     knows_chain_is_active: bool,
 }
 
@@ -75,6 +77,7 @@ where
         block_values: Arc<ValueCache<CryptoHash, Hashed<Block>>>,
         tracked_chains: Option<Arc<sync::RwLock<HashSet<ChainId>>>>,
         delivery_notifier: DeliveryNotifier,
+        chain_counters: Arc<Mutex<ChainCounters>>,
         chain_id: ChainId,
         service_runtime_endpoint: Option<ServiceRuntimeEndpoint>,
     ) -> Result<Self, WorkerError> {
@@ -89,6 +92,7 @@ where
             block_values,
             tracked_chains,
             delivery_notifier,
+            chain_counters,
             knows_chain_is_active: false,
         })
     }
@@ -176,12 +180,13 @@ where
             .await
     }
 
-    /// Executes a block without persisting any changes to the state.
+    /// Executes a block without persisting any changes to the state, and returns the
+    /// resources it used.
     pub(super) async fn stage_block_execution(
         &mut self,
         block: ProposedBlock,
         round: Option<u32>,
-    ) -> Result<(ExecutedBlock, ChainInfoResponse), WorkerError> {
+    ) -> Result<(ExecutedBlock, ChainInfoResponse, ResourceTracker), WorkerError> {
         ChainWorkerStateWithTemporaryChanges::new(self)
             .await
             .stage_block_execution(block, round)
@@ -325,11 +330,45 @@ where
             .await
     }
 
+    /// Returns whether the [`InactiveChainPolicy`] allows storing the state of this chain
+    /// while it is inactive. Records the first time the chain was referenced, if needed.
+    ///
+    /// The `ttl` is not extended by later references. Expired entries are removed when a new
+    /// chain is referenced; after that, a chain that already `received_messages` is still
+    /// refused, even if the worker was restarted in the meantime.
+    fn allows_inactive_chain(&self, local_time: Timestamp, received_messages: bool) -> bool {
+        match self.config.inactive_chain_policy {
+            InactiveChainPolicy::Reject => false,
+            InactiveChainPolicy::Allow => true,
+            InactiveChainPolicy::AllowRecentlyReferenced { ttl } => {
+                let mut referenced_chains = self.config.referenced_chains.lock().unwrap();
+                let chain_id = self.chain_id();
+                if let Some(first_referenced) = referenced_chains.get(&chain_id) {
+                    return local_time.duration_since(*first_referenced) <= ttl;
+                }
+                if received_messages {
+                    return false;
+                }
+                referenced_chains.retain(|_, first_referenced| {
+                    local_time.duration_since(*first_referenced) <= ttl
+                });
+                referenced_chains.insert(chain_id, local_time);
+                true
+            }
+        }
+    }
+
     /// Ensures that the current chain is active, returning an error otherwise.
     fn ensure_is_active(&mut self) -> Result<(), WorkerError> {
         if !self.knows_chain_is_active {
             self.chain.ensure_is_active()?;
             self.knows_chain_is_active = true;
+            // An active chain doesn't need to be tracked as referenced anymore.
+            self.config
+                .referenced_chains
+                .lock()
+                .unwrap()
+                .remove(&self.chain_id());
         }
         Ok(())
     }
@@ -401,16 +440,17 @@ where
                 return; // The parent chain is not tracked; don't track the child.
             }
             let messages = executed_block.messages().iter().flatten();
-            let open_chain_message_indices =
-                messages
-                    .enumerate()
-                    .filter_map(|(index, outgoing_message)| match outgoing_message.message {
-                        Message::System(SystemMessage::OpenChain(_)) => Some(index),
-                        _ => None,
-                    });
-            let open_chain_message_ids =
-                open_chain_message_indices.map(|index| executed_block.message_id(index as u32));
-            let new_chain_ids = open_chain_message_ids.map(ChainId::child);
+            // The execution layer derived the new chain IDs; the `OpenChain` messages are
+            // addressed to them.
+            let new_chain_ids = messages.filter_map(|outgoing_message| {
+                match (&outgoing_message.message, &outgoing_message.destination) {
+                    (
+                        Message::System(SystemMessage::OpenChain(_)),
+                        Destination::Recipient(chain_id),
+                    ) => Some(*chain_id),
+                    _ => None,
+                }
+            });
 
             tracked_chains
                 .write()
@@ -553,11 +593,25 @@ where
     /// Updates the received certificate trackers to at least the given values.
     pub async fn update_received_certificate_trackers(
         &mut self,
-        new_trackers: BTreeMap<ValidatorName, u64>,
+        new_trackers: BTreeMap<ChainId, BlockHeight>,
+        new_log_indices: BTreeMap<ValidatorName, u64>,
+    ) -> Result<(), WorkerError> {
+        ChainWorkerStateWithAttemptedChanges::new(self)
+            .await
+            .update_received_certificate_trackers(new_trackers, new_log_indices)
+            .await
+    }
+
+    /// Deletes the certificates of old blocks, keeping the last `keep_last` ones, those at or
+    /// above `keep_from` and those whose outgoing messages are still in flight.
+    pub async fn prune_history(
+        &mut self,
+        keep_last: usize,
+        keep_from: BlockHeight,
     ) -> Result<(), WorkerError> {
         ChainWorkerStateWithAttemptedChanges::new(self)
             .await
-            .update_received_certificate_trackers(new_trackers)
+            .prune_history(keep_last, keep_from)
             .await
     }
 }
@@ -4,7 +4,7 @@
 //! Operations that don't persist any changes to the chain state.
 
 use linera_base::{
-    data_types::{ArithmeticError, Timestamp, UserApplicationDescription},
+    data_types::{ArithmeticError, BlockHeight, Timestamp, UserApplicationDescription},
     ensure,
     identifiers::{AccountOwner, GenericApplicationId, UserApplicationId},
 };
@@ -12,12 +12,12 @@ use linera_chain::data_types::{
     BlockExecutionOutcome, ChannelFullName, ExecutedBlock, IncomingBundle, Medium, MessageAction,
     ProposalContent, ProposedBlock,
 };
-use linera_execution::{ChannelSubscription, Query, QueryOutcome};
+use linera_execution::{ChannelSubscription, Query, QueryOutcome, ResourceTracker};
 use linera_storage::{Clock as _, Storage};
 use linera_views::views::View;
 #[cfg(with_testing)]
 use {
-    linera_base::{crypto::CryptoHash, data_types::BlockHeight},
+    linera_base::crypto::CryptoHash,
     linera_chain::{
         data_types::{MessageBundle, Origin},
         types::ConfirmedBlockCertificate,
@@ -26,7 +26,7 @@ use {
 
 use super::ChainWorkerState;
 use crate::{
-    data_types::{ChainInfo, ChainInfoQuery, ChainInfoResponse},
+    data_types::{ChainInfo, ChainInfoQuery, ChainInfoResponse, OutboxStatus},
     worker::WorkerError,
 };
 
@@ -115,18 +115,23 @@ where
         Ok(response)
     }
 
-    /// Executes a block without persisting any changes to the state.
+    /// Executes a block without persisting any changes to the state, and returns the
+    /// resources it used.
     pub(super) async fn stage_block_execution(
         &mut self,
         block: ProposedBlock,
         round: Option<u32>,
-    ) -> Result<(ExecutedBlock, ChainInfoResponse), WorkerError> {
+    ) -> Result<(ExecutedBlock, ChainInfoResponse, ResourceTracker), WorkerError> {
         let local_time = self.0.storage.clock().current_time();
         let signer = block.authenticated_signer;
 
-        let executed_block = Box::pin(self.0.chain.execute_block(&block, local_time, round, None))
-            .await?
-            .with(block);
+        let (outcome, resources) = Box::pin(
+            self.0
+                .chain
+                .execute_block_with_resources(&block, local_time, round, None),
+        )
+        .await?;
+        let executed_block = outcome.with(block);
 
         let mut response = ChainInfoResponse::new(&self.0.chain, None);
         if let Some(signer) = signer {
@@ -140,7 +145,7 @@ where
                 .await?;
         }
 
-        Ok((executed_block, response))
+        Ok((executed_block, response, resources))
     }
 
     /// Validates a block proposed to extend this chain.
@@ -219,6 +224,10 @@ where
                 MessageAction::Accept
             };
             let subscriptions = &chain.execution_state.system.subscriptions;
+            let mut skipped = 0;
+            let limit = query
+                .request_pending_message_bundles_limit
+                .unwrap_or(usize::MAX);
             for (origin, inbox) in pairs {
                 if let Medium::Channel(ChannelFullName {
                     application_id: GenericApplicationId::System,
@@ -233,7 +242,24 @@ where
                         continue; // We are not subscribed to this channel.
                     }
                 }
-                for bundle in inbox.added_bundles.elements().await? {
+                // Only read the bundles of this inbox that are in the requested page.
+                let count = inbox.added_bundles.count();
+                let start = query
+                    .request_pending_message_bundles_offset
+                    .saturating_sub(skipped);
+                skipped = skipped.saturating_add(count.min(start));
+                if start >= count {
+                    continue;
+                }
+                if messages.len() == limit {
+                    info.has_more_pending_message_bundles = true;
+                    break;
+                }
+                let end = start.saturating_add(limit - messages.len());
+                if end < count {
+                    info.has_more_pending_message_bundles = true;
+                }
+                for bundle in inbox.added_bundles.read_range(start..end).await? {
                     messages.push(IncomingBundle {
                         origin: origin.clone(),
                         bundle,
@@ -253,16 +279,72 @@ where
                     .ok_or(ArithmeticError::Overflow)?
                     .min(chain.confirmed_log.count()),
             };
-            let keys = chain.confirmed_log.read(start..end).await?;
+            let maximum = self.0.config.maximum_sent_certificate_hashes_per_response;
+            let truncated_end = end.min(start.saturating_add(maximum));
+            if truncated_end < end {
+                let next_height =
+                    u64::try_from(truncated_end).map_err(|_| ArithmeticError::Overflow)?;
+                info.requested_sent_certificate_hashes_next_height =
+                    Some(BlockHeight::from(next_height));
+            }
+            let keys = chain.confirmed_log.read(start..truncated_end).await?;
             info.requested_sent_certificate_hashes = keys;
         }
-        if let Some(start) = query.request_received_log_excluding_first_n {
+        if query.request_outboxes {
+            let targets = chain.outboxes.indices().await?;
+            let maximum = self.0.config.maximum_outboxes_per_response;
+            let start = query.request_outboxes_offset.min(targets.len());
+            let end = start.saturating_add(maximum).min(targets.len());
+            info.has_more_outboxes = end < targets.len();
+            let targets = &targets[start..end];
+            let outboxes = chain.outboxes.try_load_entries(targets).await?;
+            for (target, outbox) in targets.iter().zip(outboxes) {
+                if let Some(outbox) = outbox {
+                    let status = OutboxStatus {
+                        queued_heights: outbox.queue.elements().await?,
+                        next_height_to_schedule: *outbox.next_height_to_schedule.get(),
+                    };
+                    info.requested_outboxes.insert(target.clone(), status);
+                }
+            }
+        }
+        if query.request_balance_proof {
+            let height = query.request_balance_proof_height.or_else(|| {
+                let next_block_height = chain.tip_state.get().next_block_height;
+                next_block_height.try_sub_one().ok()
+            });
+            if let Some(height) = height {
+                if let Some(path) = chain.balance_paths.get(&height).await? {
+                    if let Some(hash) = chain.confirmed_log.get(height.try_into()?).await? {
+                        let certificate = self.0.storage.read_certificate(hash).await?;
+                        info.requested_balance_proof =
+                            Some(Box::new(certificate.balance_proof(path)));
+                    }
+                }
+            }
+        }
+        if query.request_received_log_excluding_first_n.is_some()
+            || query.request_received_log_after.is_some()
+        {
+            let start = query.request_received_log_excluding_first_n.unwrap_or(0);
             let start = usize::try_from(start).map_err(|_| ArithmeticError::Overflow)?;
-            info.requested_received_log = chain.received_log.read(start..).await?;
+            let mut received_log = chain.received_log.read(start..).await?;
+            if let Some(heights) = &query.request_received_log_after {
+                received_log.retain(|entry| {
+                    heights
+                        .get(&entry.chain_id)
+                        .map_or(true, |height| entry.height > *height)
+                });
+            }
+            info.requested_received_log = received_log;
         }
         if query.request_manager_values {
             info.manager.add_values(&chain.manager);
         }
+        if query.request_subscriptions {
+            info.requested_subscriptions =
+                chain.execution_state.system.subscriptions.indices().await?;
+        }
         Ok(ChainInfoResponse::new(info, self.0.config.key_pair()))
     }
 }
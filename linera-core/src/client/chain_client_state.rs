@@ -3,21 +3,23 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     sync::Arc,
 };
 
 use linera_base::{
-    crypto::{CryptoHash, KeyPair, PublicKey},
-    data_types::{Blob, BlockHeight, Timestamp},
+    crypto::{AccountPublicKey, AccountSecretKey, AccountSignature, CryptoHash},
+    data_types::{Amount, Blob, BlockHeight, Round, Timestamp},
     ensure,
     identifiers::Owner,
     ownership::ChainOwnership,
+    time::{Duration, Instant},
 };
 use linera_chain::data_types::ProposedBlock;
+use linera_execution::committee::ValidatorName;
 use tokio::sync::Mutex;
 
-use super::{ChainClientError, PendingProposal};
+use super::{ChainClientError, PendingProposal, QueuedOperation, ScheduledBlock};
 use crate::data_types::ChainInfo;
 
 /// The state of our interaction with a particular chain: how far we have synchronized it and
@@ -35,20 +37,44 @@ pub struct ChainClientState {
     /// This is always at the same height as `next_block_height`.
     pending_proposal: Option<PendingProposal>,
     /// Known key pairs from present and past identities.
-    known_key_pairs: BTreeMap<Owner, KeyPair>,
+    known_key_pairs: BTreeMap<Owner, AccountSecretKey>,
+    /// The validators to contact first, fastest first, if they should be queried in waves
+    /// instead of all at once.
+    preferred_validators: Vec<ValidatorName>,
+    /// The last local balance that was read, and when, if it is still at the current height.
+    cached_balance: Option<(Instant, Amount)>,
+    /// When the chain was last synchronized with the validators or advanced by a processed
+    /// certificate.
+    last_synchronized: Option<Instant>,
+    /// The round to make the next proposal in, if we advanced past the chain manager's
+    /// current round.
+    ///
+    /// Like the pending proposal, this only applies to `next_block_height`.
+    next_round: Option<Round>,
+
+    /// The operations waiting to be executed, in order.
+    operation_queue: VecDeque<QueuedOperation>,
+    /// The number of operations at the front of the queue that are being executed.
+    operations_in_flight: usize,
+    /// The timelocked blocks waiting for the chain to reach their height, in the order they
+    /// were scheduled.
+    scheduled_blocks: Vec<ScheduledBlock>,
 
     /// A mutex that is held whilst we are performing operations that should not be
     /// attempted by multiple clients at the same time.
     client_mutex: Arc<Mutex<()>>,
+    /// A mutex that is held whilst the operation queue is being processed.
+    operation_queue_mutex: Arc<Mutex<()>>,
 }
 
 impl ChainClientState {
     pub fn new(
-        known_key_pairs: Vec<KeyPair>,
+        known_key_pairs: Vec<AccountSecretKey>,
         block_hash: Option<CryptoHash>,
         timestamp: Timestamp,
         next_block_height: BlockHeight,
         pending_proposal: Option<PendingProposal>,
+        scheduled_blocks: Vec<ScheduledBlock>,
     ) -> ChainClientState {
         let known_key_pairs = known_key_pairs
             .into_iter()
@@ -60,7 +86,15 @@ impl ChainClientState {
             timestamp,
             next_block_height,
             pending_proposal,
+            preferred_validators: Vec::new(),
+            cached_balance: None,
+            last_synchronized: None,
+            next_round: None,
+            operation_queue: VecDeque::new(),
+            operations_in_flight: 0,
+            scheduled_blocks,
             client_mutex: Arc::default(),
+            operation_queue_mutex: Arc::default(),
         }
     }
 
@@ -80,14 +114,23 @@ impl ChainClientState {
         &self.pending_proposal
     }
 
-    pub(super) fn set_pending_proposal(&mut self, block: ProposedBlock, blobs: Vec<Blob>) {
+    pub(super) fn set_pending_proposal(
+        &mut self,
+        block: ProposedBlock,
+        blobs: Vec<Blob>,
+        cosignatures: Vec<(AccountPublicKey, AccountSignature)>,
+    ) {
         if block.height == self.next_block_height {
             let blobs = Vec::from_iter(blobs);
             assert_eq!(
                 block.published_blob_ids(),
                 BTreeSet::from_iter(blobs.iter().map(Blob::id))
             );
-            self.pending_proposal = Some(PendingProposal { block, blobs });
+            self.pending_proposal = Some(PendingProposal {
+                block,
+                blobs,
+                cosignatures,
+            });
         } else {
             tracing::error!(
                 "Not setting pending block at height {}, because next_block_height is {}.",
@@ -97,10 +140,55 @@ impl ChainClientState {
         }
     }
 
-    pub fn known_key_pairs(&self) -> &BTreeMap<Owner, KeyPair> {
+    pub fn known_key_pairs(&self) -> &BTreeMap<Owner, AccountSecretKey> {
         &self.known_key_pairs
     }
 
+    /// Returns the validators to contact first, in order of preference.
+    ///
+    /// If this is empty, requests are sent to all validators at once.
+    pub fn preferred_validators(&self) -> &[ValidatorName] {
+        &self.preferred_validators
+    }
+
+    pub(super) fn set_preferred_validators(&mut self, validators: Vec<ValidatorName>) {
+        self.preferred_validators = validators;
+    }
+
+    /// Returns the cached local balance, if it was read less than `max_age` ago.
+    pub fn cached_balance(&self, max_age: Duration) -> Option<Amount> {
+        let (read_at, balance) = self.cached_balance?;
+        (read_at.elapsed() < max_age).then_some(balance)
+    }
+
+    pub(super) fn set_cached_balance(&mut self, balance: Amount) {
+        self.cached_balance = Some((Instant::now(), balance));
+    }
+
+    /// Returns whether the chain was synchronized less than `max_age` ago.
+    pub fn is_synchronized_within(&self, max_age: Duration) -> bool {
+        self.last_synchronized
+            .is_some_and(|synchronized_at| synchronized_at.elapsed() < max_age)
+    }
+
+    pub(super) fn set_synchronized(&mut self) {
+        self.last_synchronized = Some(Instant::now());
+    }
+
+    pub(super) fn clear_synchronized(&mut self) {
+        self.last_synchronized = None;
+    }
+
+    /// Returns the round to make the next proposal in, if we advanced past the chain
+    /// manager's current round.
+    pub fn next_round(&self) -> Option<Round> {
+        self.next_round
+    }
+
+    pub(super) fn set_next_round(&mut self, round: Round) {
+        self.next_round = Some(round);
+    }
+
     /// Returns whether the given ownership includes anyone whose secret key we don't have.
     pub fn has_other_owners(&self, ownership: &ChainOwnership) -> bool {
         ownership
@@ -108,7 +196,13 @@ impl ChainClientState {
             .any(|owner| !self.known_key_pairs.contains_key(owner))
     }
 
-    pub(super) fn insert_known_key_pair(&mut self, key_pair: KeyPair) -> PublicKey {
+    /// Returns whether we hold the key of the chain's only owner, so that no one else can
+    /// add blocks to it.
+    pub fn is_sole_owner(&self, ownership: &ChainOwnership) -> bool {
+        ownership.all_owners().count() == 1 && !self.has_other_owners(ownership)
+    }
+
+    pub(super) fn insert_known_key_pair(&mut self, key_pair: AccountSecretKey) -> AccountPublicKey {
         let new_public_key = key_pair.public();
         self.known_key_pairs.insert(new_public_key.into(), key_pair);
         new_public_key
@@ -118,19 +212,62 @@ impl ChainClientState {
         if info.next_block_height > self.next_block_height {
             self.next_block_height = info.next_block_height;
             self.clear_pending_proposal();
+            self.cached_balance = None;
+            self.next_round = None;
             self.block_hash = info.block_hash;
             self.timestamp = info.timestamp;
         }
     }
 
-    pub(super) fn clear_pending_proposal(&mut self) {
-        self.pending_proposal = None;
+    pub(super) fn clear_pending_proposal(&mut self) -> Option<PendingProposal> {
+        self.pending_proposal.take()
     }
 
     pub(super) fn client_mutex(&self) -> Arc<Mutex<()>> {
         self.client_mutex.clone()
     }
 
+    pub(super) fn operation_queue_mut(&mut self) -> &mut VecDeque<QueuedOperation> {
+        &mut self.operation_queue
+    }
+
+    pub(super) fn operations_in_flight(&self) -> usize {
+        self.operations_in_flight
+    }
+
+    pub(super) fn set_operations_in_flight(&mut self, count: usize) {
+        self.operations_in_flight = count;
+    }
+
+    pub(super) fn operation_queue_mutex(&self) -> Arc<Mutex<()>> {
+        self.operation_queue_mutex.clone()
+    }
+
+    pub fn scheduled_blocks(&self) -> &[ScheduledBlock] {
+        &self.scheduled_blocks
+    }
+
+    pub(super) fn scheduled_blocks_mut(&mut self) -> &mut Vec<ScheduledBlock> {
+        &mut self.scheduled_blocks
+    }
+
+    /// Removes and returns the first scheduled block that can be committed at `height`.
+    pub(super) fn take_due_scheduled_block(
+        &mut self,
+        height: BlockHeight,
+    ) -> Option<ScheduledBlock> {
+        let index = self
+            .scheduled_blocks
+            .iter()
+            .position(|scheduled| scheduled.not_before <= height)?;
+        Some(self.scheduled_blocks.remove(index))
+    }
+
+    /// Puts back a scheduled block that couldn't be committed, ahead of the others.
+    pub(super) fn reschedule_block(&mut self, scheduled: ScheduledBlock) {
+        self.scheduled_blocks.insert(0, scheduled);
+    }
+
     /// Returns an error if the chain info does not match the block hash and height.
     pub(super) fn check_info_is_up_to_date(
         &self,
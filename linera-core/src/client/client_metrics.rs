@@ -0,0 +1,149 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+/// Receives events from the chain clients, e.g. to export them to a monitoring system.
+///
+/// All methods do nothing by default, so implementations only need to handle the events
+/// they are interested in. The methods are called while the client is working, so they
+/// should return quickly.
+pub trait ClientMetrics: Send + Sync {
+    /// A block proposal is being sent to the validators.
+    fn block_proposed(&self) {}
+
+    /// A request to the validators reached a quorum after `latency`.
+    fn quorum_reached(&self, _latency: Duration) {}
+
+    /// A request to the validators failed to reach a quorum after `latency`.
+    fn quorum_failed(&self, _latency: Duration) {}
+
+    /// `count` certificates of other chains were received from the validators.
+    fn certificates_received(&self, _count: usize) {}
+
+    /// A conflicting block was committed instead of ours, so its operations must be retried.
+    fn block_conflict(&self) {}
+
+    /// Certified blocks of a chain are being sent to the validators, so that they can
+    /// deliver its cross-chain messages.
+    fn chain_updates_sent(&self) {}
+
+    /// The chain's inbox is missing cross-chain updates, so they are being requested from
+    /// the validators.
+    fn cross_chain_updates_missing(&self) {}
+}
+
+/// A [`ClientMetrics`] that ignores all events.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoClientMetrics;
+
+impl ClientMetrics for NoClientMetrics {}
+
+/// A [`ClientMetrics`] that exports the events to Prometheus.
+#[cfg(with_metrics)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PrometheusClientMetrics;
+
+#[cfg(with_metrics)]
+impl ClientMetrics for PrometheusClientMetrics {
+    fn block_proposed(&self) {
+        prometheus_metrics::BLOCK_PROPOSALS
+            .with_label_values(&[])
+            .inc();
+    }
+
+    fn quorum_reached(&self, latency: Duration) {
+        prometheus_metrics::QUORUM_LATENCY
+            .with_label_values(&["reached"])
+            .observe(latency.as_secs_f64() * 1000.0);
+    }
+
+    fn quorum_failed(&self, latency: Duration) {
+        prometheus_metrics::QUORUM_LATENCY
+            .with_label_values(&["failed"])
+            .observe(latency.as_secs_f64() * 1000.0);
+    }
+
+    fn certificates_received(&self, count: usize) {
+        prometheus_metrics::CERTIFICATES_RECEIVED
+            .with_label_values(&[])
+            .inc_by(count as u64);
+    }
+
+    fn block_conflict(&self) {
+        prometheus_metrics::BLOCK_CONFLICTS
+            .with_label_values(&[])
+            .inc();
+    }
+
+    fn chain_updates_sent(&self) {
+        prometheus_metrics::CHAIN_UPDATES_SENT
+            .with_label_values(&[])
+            .inc();
+    }
+
+    fn cross_chain_updates_missing(&self) {
+        prometheus_metrics::MISSING_CROSS_CHAIN_UPDATES
+            .with_label_values(&[])
+            .inc();
+    }
+}
+
+#[cfg(with_metrics)]
+mod prometheus_metrics {
+    use std::sync::LazyLock;
+
+    use linera_base::prometheus_util::{
+        bucket_latencies, register_histogram_vec, register_int_counter_vec,
+    };
+    use prometheus::{HistogramVec, IntCounterVec};
+
+    pub static BLOCK_PROPOSALS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+        register_int_counter_vec(
+            "client_block_proposals",
+            "Number of block proposals sent by the client",
+            &[],
+        )
+    });
+
+    pub static QUORUM_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+        register_histogram_vec(
+            "client_quorum_latency",
+            "Time until a quorum of validators was reached, or failed to be",
+            &["outcome"],
+            bucket_latencies(10_000.0),
+        )
+    });
+
+    pub static CERTIFICATES_RECEIVED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+        register_int_counter_vec(
+            "client_certificates_received",
+            "Number of certificates of other chains received by the client",
+            &[],
+        )
+    });
+
+    pub static BLOCK_CONFLICTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+        register_int_counter_vec(
+            "client_block_conflicts",
+            "Number of blocks that had to be retried after a conflicting block",
+            &[],
+        )
+    });
+
+    pub static CHAIN_UPDATES_SENT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+        register_int_counter_vec(
+            "client_chain_updates_sent",
+            "Number of times the client sent certified blocks to the validators",
+            &[],
+        )
+    });
+
+    pub static MISSING_CROSS_CHAIN_UPDATES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+        register_int_counter_vec(
+            "client_missing_cross_chain_updates",
+            "Number of times the client requested missing cross-chain updates",
+            &[],
+        )
+    });
+}
@@ -0,0 +1,52 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use linera_base::crypto::{KeyPair, Seed};
+
+/// Derives the owner keys of the chains opened by a client from a single [`Seed`].
+///
+/// The key of the `N`th chain opened with
+/// [`ChainClient::open_chain_with_derived_owner`](super::ChainClient::open_chain_with_derived_owner)
+/// is derived at the path `m/N'`, so that all the keys can be recovered from the seed.
+pub struct KeyDerivation {
+    /// The seed from which the keys are derived.
+    seed: Seed,
+    /// The index of the next key to derive.
+    next_index: AtomicU32,
+}
+
+impl KeyDerivation {
+    /// Creates a [`KeyDerivation`] for a seed that wasn't used to open chains yet.
+    pub fn new(seed: Seed) -> Self {
+        Self::with_next_index(seed, 0)
+    }
+
+    /// Creates a [`KeyDerivation`] that resumes deriving keys at `next_index`.
+    pub fn with_next_index(seed: Seed, next_index: u32) -> Self {
+        KeyDerivation {
+            seed,
+            next_index: AtomicU32::new(next_index),
+        }
+    }
+
+    /// Returns the index of the next key to derive, which should be persisted to resume the
+    /// derivation later.
+    pub fn next_index(&self) -> u32 {
+        self.next_index.load(Ordering::Acquire)
+    }
+
+    /// Returns the key-pair at `index`.
+    pub fn key_pair(&self, index: u32) -> KeyPair {
+        KeyPair::derive_from_seed(&self.seed, &[index])
+    }
+
+    /// Reserves the next index and returns the key-pair derived for it.
+    ///
+    /// An index is never handed out twice, even if the chain it was reserved for fails to open.
+    pub(crate) fn reserve_key_pair(&self) -> KeyPair {
+        let index = self.next_index.fetch_add(1, Ordering::AcqRel);
+        self.key_pair(index)
+    }
+}
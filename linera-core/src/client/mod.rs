@@ -13,24 +13,30 @@ use std::{
 };
 
 use chain_client_state::ChainClientState;
+#[cfg(with_metrics)]
+pub use client_metrics::PrometheusClientMetrics;
+pub use client_metrics::{ClientMetrics, NoClientMetrics};
 use custom_debug_derive::Debug;
 use dashmap::{
     mapref::one::{MappedRef as DashMapMappedRef, Ref as DashMapRef, RefMut as DashMapRefMut},
     DashMap,
 };
 use futures::{
+    channel::mpsc,
     future::{self, try_join_all, Either, FusedFuture, Future},
-    stream::{self, AbortHandle, FusedStream, FuturesUnordered, StreamExt},
+    stream::{self, AbortHandle, FusedStream, FuturesUnordered, Stream, StreamExt},
 };
+pub use key_derivation::KeyDerivation;
 #[cfg(not(target_arch = "wasm32"))]
 use linera_base::data_types::Bytecode;
 #[cfg(with_metrics)]
 use linera_base::prometheus_util::MeasureLatency as _;
 use linera_base::{
     abi::Abi,
-    crypto::{CryptoHash, KeyPair, PublicKey},
+    crypto::{AccountPublicKey, AccountSecretKey, AccountSignature, CryptoHash},
     data_types::{
-        Amount, ApplicationPermissions, ArithmeticError, Blob, BlockHeight, Round, Timestamp,
+        Amount, ApplicationPermissions, ArithmeticError, Blob, BlockHeight, Round, TimeDelta,
+        Timestamp,
     },
     ensure,
     hashed::Hashed,
@@ -39,11 +45,12 @@ use linera_base::{
         Owner, UserApplicationId,
     },
     ownership::{ChainOwnership, TimeoutConfig},
+    time::Instant,
 };
 use linera_chain::{
     data_types::{
         BlockProposal, ChainAndHeight, ExecutedBlock, IncomingBundle, LiteVote, MessageAction,
-        ProposedBlock,
+        OutgoingMessage, ProposalContent, ProposedBlock, Target,
     },
     manager::LockingBlock,
     types::{
@@ -53,26 +60,30 @@ use linera_chain::{
     ChainError, ChainExecutionContext, ChainStateView, ExecutionResultExt as _,
 };
 use linera_execution::{
-    committee::{Committee, Epoch, ValidatorName},
+    committee::{Committee, CommitteeError, Epoch, ValidatorName},
     system::{
         AdminOperation, OpenChainConfig, Recipient, SystemChannel, SystemOperation,
         CREATE_APPLICATION_MESSAGE_INDEX, OPEN_CHAIN_MESSAGE_INDEX,
     },
-    ExecutionError, Operation, Query, QueryOutcome, QueryResponse, SystemExecutionError,
-    SystemQuery, SystemResponse,
+    ChannelSubscription, ExecutionError, Operation, Query, QueryOutcome, QueryResponse,
+    ResourceTracker, SystemExecutionError, SystemQuery, SystemResponse,
 };
-use linera_storage::{Clock as _, Storage};
+use linera_storage::{Clock as _, IdempotencyRecord, Storage};
 use linera_views::views::ViewError;
 use rand::prelude::SliceRandom as _;
 use serde::{Deserialize, Serialize};
+pub use signer::{InMemorySigner, Signer, SignerError};
 use thiserror::Error;
-use tokio::sync::OwnedRwLockReadGuard;
+use tokio::sync::{oneshot, OwnedRwLockReadGuard};
 use tokio_stream::wrappers::UnboundedReceiverStream;
-use tracing::{debug, error, info, instrument, warn, Instrument as _};
+use tracing::{debug, error, field, info, instrument, warn, Instrument as _};
+use uuid::Uuid;
 
 use crate::{
+    chain_worker::InactiveChainPolicy,
     data_types::{
-        BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse, ClientOutcome, RoundTimeout,
+        BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse, ClientOutcome,
+        OutboxStatus, RoundTimeout,
     },
     local_node::{LocalNodeClient, LocalNodeError},
     node::{
@@ -81,14 +92,20 @@ use crate::{
     },
     notifier::ChannelNotifier,
     remote_node::RemoteNode,
-    updater::{communicate_with_quorum, CommunicateAction, CommunicationError, ValidatorUpdater},
+    updater::{
+        communicate_with_quorum, communicate_with_quorum_prioritized, CommunicateAction,
+        CommunicationError, ValidatorUpdater,
+    },
     worker::{Notification, ProcessableCertificate, Reason, WorkerError, WorkerState},
 };
 
 mod chain_client_state;
+mod client_metrics;
 #[cfg(test)]
 #[path = "../unit_tests/client_tests.rs"]
 mod client_tests;
+mod key_derivation;
+mod signer;
 
 #[cfg(with_metrics)]
 mod metrics {
@@ -177,6 +194,18 @@ where
     max_loaded_chains: NonZeroUsize,
     /// The delay when downloading a blob, after which we try a second validator.
     blob_download_timeout: Duration,
+    /// The maximum time to wait for each request sent to a validator while updating it.
+    request_timeout: Duration,
+    /// How to derive the owner keys of new chains, if configured.
+    key_derivation: Option<Arc<KeyDerivation>>,
+    /// Signs proposals for owners whose keys are not among the known key pairs, if configured.
+    signer: Option<Arc<dyn Signer>>,
+    /// Receives events from the chain clients.
+    metrics: Arc<dyn ClientMetrics>,
+    /// Whether the user application services of the local node should be long-lived.
+    long_lived_services: bool,
+    /// Whether the local node stores the state of chains that are not active yet.
+    allow_inactive_chains: bool,
 }
 
 impl<P, S: Storage + Clone> Client<P, S> {
@@ -194,18 +223,17 @@ impl<P, S: Storage + Clone> Client<P, S> {
         max_loaded_chains: NonZeroUsize,
         grace_period: f64,
         blob_download_timeout: Duration,
+        request_timeout: Duration,
     ) -> Self {
         let tracked_chains = Arc::new(RwLock::new(tracked_chains.into_iter().collect()));
-        let state = WorkerState::new_for_client(
+        let local_node = Self::new_local_node(
             name.into(),
             storage.clone(),
             tracked_chains.clone(),
             max_loaded_chains,
-        )
-        .with_long_lived_services(long_lived_services)
-        .with_allow_inactive_chains(true)
-        .with_allow_messages_from_deprecated_epochs(true);
-        let local_node = LocalNodeClient::new(state);
+            long_lived_services,
+            true,
+        );
 
         Self {
             validator_node_provider,
@@ -220,9 +248,98 @@ impl<P, S: Storage + Clone> Client<P, S> {
             storage,
             max_loaded_chains,
             blob_download_timeout,
+            request_timeout,
+            key_derivation: None,
+            signer: None,
+            metrics: Arc::new(NoClientMetrics),
+            long_lived_services,
+            allow_inactive_chains: true,
         }
     }
 
+    /// Creates the local node of a client.
+    fn new_local_node(
+        name: String,
+        storage: S,
+        tracked_chains: Arc<RwLock<HashSet<ChainId>>>,
+        max_loaded_chains: NonZeroUsize,
+        long_lived_services: bool,
+        allow_inactive_chains: bool,
+    ) -> LocalNodeClient<S> {
+        let inactive_chain_policy = if allow_inactive_chains {
+            InactiveChainPolicy::Allow
+        } else {
+            InactiveChainPolicy::Reject
+        };
+        let state = WorkerState::new_for_client(name, storage, tracked_chains, max_loaded_chains)
+            .with_long_lived_services(long_lived_services)
+            .with_inactive_chain_policy(inactive_chain_policy)
+            .with_allow_messages_from_deprecated_epochs(true);
+        LocalNodeClient::new(state)
+    }
+
+    /// Returns this client, with a local node that does or doesn't store the state of chains
+    /// that are not active yet. This is allowed by default.
+    ///
+    /// Allowing inactive chains lets the local node keep the messages sent to a chain before
+    /// the block that opens it has been downloaded, so that they don't need to be fetched
+    /// again. However, anyone can send messages to any chain ID, so this lets other chains make
+    /// the local node store state for chains that may never exist.
+    ///
+    /// If inactive chains are not allowed, the local node only keeps the state of chains it
+    /// knows to be active, and the chain clients refuse to create blocks on chains that are not
+    /// active in the local node, failing early with [`LocalNodeError::InactiveChain`]. A new
+    /// chain must then be synchronized from the validators before it can be used.
+    ///
+    /// This replaces the local node, so it must be called before creating any chain clients.
+    pub fn with_allow_inactive_chains(mut self, allow_inactive_chains: bool) -> Self {
+        self.local_node = Self::new_local_node(
+            self.local_node.nickname().to_owned(),
+            self.storage.clone(),
+            self.tracked_chains.clone(),
+            self.max_loaded_chains,
+            self.long_lived_services,
+            allow_inactive_chains,
+        );
+        self.allow_inactive_chains = allow_inactive_chains;
+        self
+    }
+
+    /// Returns whether the local node stores the state of chains that are not active yet.
+    pub fn allow_inactive_chains(&self) -> bool {
+        self.allow_inactive_chains
+    }
+
+    /// Returns this client, deriving the owner keys of the chains it opens with
+    /// `key_derivation`.
+    pub fn with_key_derivation(mut self, key_derivation: KeyDerivation) -> Self {
+        self.key_derivation = Some(Arc::new(key_derivation));
+        self
+    }
+
+    /// Returns the [`KeyDerivation`] used for the owner keys of new chains, if any.
+    pub fn key_derivation(&self) -> Option<&KeyDerivation> {
+        self.key_derivation.as_deref()
+    }
+
+    /// Returns this client, using `signer` to sign proposals for the owners whose key pairs
+    /// it doesn't know.
+    pub fn with_signer(mut self, signer: impl Signer + 'static) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Returns the [`Signer`] used for owners whose key pairs are not known, if any.
+    pub fn signer(&self) -> Option<&dyn Signer> {
+        self.signer.as_deref()
+    }
+
+    /// Returns this client, reporting the events of its chain clients to `metrics`.
+    pub fn with_metrics(mut self, metrics: impl ClientMetrics + 'static) -> Self {
+        self.metrics = Arc::new(metrics);
+        self
+    }
+
     /// Returns a clone with a different set of tracked chains.
     pub fn clone_with(
         &self,
@@ -232,16 +349,14 @@ impl<P, S: Storage + Clone> Client<P, S> {
         long_lived_services: bool,
     ) -> Self {
         let tracked_chains = Arc::new(RwLock::new(tracked_chains.into_iter().collect()));
-        let state = WorkerState::new_for_client(
+        let local_node = Self::new_local_node(
             name.into(),
             self.storage.clone(),
             tracked_chains.clone(),
             self.max_loaded_chains,
-        )
-        .with_long_lived_services(long_lived_services)
-        .with_allow_inactive_chains(true)
-        .with_allow_messages_from_deprecated_epochs(true);
-        let local_node = LocalNodeClient::new(state);
+            long_lived_services,
+            self.allow_inactive_chains,
+        );
         Self {
             validator_node_provider,
             local_node,
@@ -255,6 +370,12 @@ impl<P, S: Storage + Clone> Client<P, S> {
             storage: self.storage.clone(),
             max_loaded_chains: self.max_loaded_chains,
             blob_download_timeout: self.blob_download_timeout,
+            request_timeout: self.request_timeout,
+            key_derivation: self.key_derivation.clone(),
+            signer: self.signer.clone(),
+            metrics: self.metrics.clone(),
+            long_lived_services,
+            allow_inactive_chains: self.allow_inactive_chains,
         }
     }
 
@@ -285,12 +406,13 @@ impl<P, S: Storage + Clone> Client<P, S> {
     pub fn create_chain_client(
         self: &Arc<Self>,
         chain_id: ChainId,
-        known_key_pairs: Vec<KeyPair>,
+        known_key_pairs: Vec<AccountSecretKey>,
         admin_id: ChainId,
         block_hash: Option<CryptoHash>,
         timestamp: Timestamp,
         next_block_height: BlockHeight,
         pending_proposal: Option<PendingProposal>,
+        scheduled_blocks: Vec<ScheduledBlock>,
     ) -> ChainClient<P, S> {
         // If the entry already exists we assume that the entry is more up to date than
         // the arguments: If they were read from the wallet file, they might be stale.
@@ -301,6 +423,7 @@ impl<P, S: Storage + Clone> Client<P, S> {
                 timestamp,
                 next_block_height,
                 pending_proposal,
+                scheduled_blocks,
             ));
         }
 
@@ -310,10 +433,14 @@ impl<P, S: Storage + Clone> Client<P, S> {
             admin_id,
             options: ChainClientOptions {
                 max_pending_message_bundles: self.max_pending_message_bundles,
+                pending_message_bundles_page_size: DEFAULT_PENDING_MESSAGE_BUNDLES_PAGE_SIZE,
                 message_policy: self.message_policy.clone(),
                 cross_chain_message_delivery: self.cross_chain_message_delivery,
                 grace_period: self.grace_period,
                 blob_download_timeout: self.blob_download_timeout,
+                request_timeout: self.request_timeout,
+                trust_local_state_for: Duration::ZERO,
+                max_queued_operations_per_block: 1,
             },
         }
     }
@@ -332,6 +459,31 @@ where
         chain_id: ChainId,
         target_next_block_height: BlockHeight,
     ) -> Result<Box<ChainInfo>, ChainClientError> {
+        self.download_certificates_with_progress(
+            validators,
+            chain_id,
+            target_next_block_height,
+            |_, _| {},
+        )
+        .await
+    }
+
+    /// Downloads and processes all certificates up to (excluding) the specified height, like
+    /// [`Client::download_certificates`]. Calls `progress` with the local next block height
+    /// and the target height before starting and after each processed batch.
+    #[instrument(level = "trace", skip(self, validators, progress))]
+    pub async fn download_certificates_with_progress(
+        &self,
+        validators: &[RemoteNode<impl ValidatorNode>],
+        chain_id: ChainId,
+        target_next_block_height: BlockHeight,
+        mut progress: impl FnMut(BlockHeight, BlockHeight) + Send,
+    ) -> Result<Box<ChainInfo>, ChainClientError> {
+        let info = self.local_node.chain_info(chain_id).await?;
+        progress(
+            info.next_block_height.min(target_next_block_height),
+            target_next_block_height,
+        );
         // Sequentially try each validator in random order.
         let mut validators = validators.iter().collect::<Vec<_>>();
         validators.shuffle(&mut rand::thread_rng());
@@ -345,6 +497,7 @@ where
                 chain_id,
                 info.next_block_height,
                 target_next_block_height,
+                &mut progress,
             )
             .await?;
         }
@@ -368,6 +521,7 @@ where
         chain_id: ChainId,
         mut start: BlockHeight,
         stop: BlockHeight,
+        progress: &mut (impl FnMut(BlockHeight, BlockHeight) + Send),
     ) -> Result<(), ChainClientError> {
         while start < stop {
             // TODO(#2045): Analyze network errors instead of guessing the batch size.
@@ -389,6 +543,7 @@ where
             };
             assert!(info.next_block_height > start);
             start = info.next_block_height;
+            progress(start.min(stop), stop);
         }
         Ok(())
     }
@@ -478,12 +633,20 @@ impl MessagePolicy {
 
     #[instrument(level = "trace", skip(self))]
     fn must_handle(&self, bundle: &mut IncomingBundle) -> bool {
-        if self.is_reject() {
-            if bundle.bundle.is_skippable() {
-                return false;
-            } else if bundle.bundle.is_tracked() {
-                bundle.action = MessageAction::Reject;
-            }
+        if !self.accepts(bundle) {
+            return false;
+        }
+        if self.is_reject() && bundle.bundle.is_tracked() {
+            bundle.action = MessageAction::Reject;
+        }
+        true
+    }
+
+    /// Returns whether the `bundle` is included in blocks, either accepted or rejected.
+    #[instrument(level = "trace", skip(self))]
+    fn accepts(&self, bundle: &IncomingBundle) -> bool {
+        if self.is_reject() && bundle.bundle.is_skippable() {
+            return false;
         }
         let sender = bundle.origin.sender;
         match &self.restrict_chain_ids_to {
@@ -503,11 +666,24 @@ impl MessagePolicy {
     }
 }
 
+/// The default number of pending message bundles requested from the local node at a time.
+pub const DEFAULT_PENDING_MESSAGE_BUNDLES_PAGE_SIZE: usize = 1000;
+
+/// How long the idempotency records of [`ChainClient::transfer_with_idempotency`] are kept.
+/// A key that is used again after that long is treated as a new key.
+pub const IDEMPOTENCY_RECORD_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How long [`ChainClient::listen`] waits before subscribing again to a validator whose
+/// notification stream ended.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
 #[non_exhaustive]
 #[derive(Debug, Clone)]
 pub struct ChainClientOptions {
     /// Maximum number of pending message bundles processed at a time in a block.
     pub max_pending_message_bundles: usize,
+    /// Maximum number of pending message bundles requested from the local node at a time.
+    pub pending_message_bundles_page_size: usize,
     /// The policy for automatically handling incoming messages.
     pub message_policy: MessagePolicy,
     /// Whether to block on cross-chain message delivery.
@@ -517,6 +693,16 @@ pub struct ChainClientOptions {
     pub grace_period: f64,
     /// The delay when downloading a blob, after which we try a second validator.
     pub blob_download_timeout: Duration,
+    /// The maximum time to wait for each request sent to a validator while updating it,
+    /// after which the validator is considered to have failed.
+    pub request_timeout: Duration,
+    /// How long after synchronizing a chain of which we are the only owner to trust the local
+    /// state, skipping the network requests that would otherwise synchronize it again. New
+    /// incoming messages may be noticed up to that much later.
+    pub trust_local_state_for: Duration,
+    /// The maximum number of queued operations executed together in a block by
+    /// [`ChainClient::process_operation_queue`].
+    pub max_queued_operations_per_block: usize,
 }
 
 /// Client to operate a chain by interacting with validators and the given local storage
@@ -607,6 +793,12 @@ pub enum ChainClientError {
     #[error("Found several possible identities to interact with chain {0}")]
     FoundMultipleKeysForChain(ChainId),
 
+    #[error("No key derivation is configured to derive the owner key of a new chain")]
+    MissingKeyDerivation,
+
+    #[error("Failed to sign the block proposal: {0}")]
+    SignerError(#[from] SignerError),
+
     #[error(transparent)]
     ViewError(#[from] ViewError),
 
@@ -618,6 +810,66 @@ pub enum ChainClientError {
         chain_id: ChainId,
         target_next_block_height: BlockHeight,
     },
+    #[error("Failed to download the history of chain {chain_id:?} from height {start}")]
+    CannotDownloadHistory {
+        chain_id: ChainId,
+        start: BlockHeight,
+    },
+    #[error("Invalid committee: {0}")]
+    InvalidCommittee(#[from] CommitteeError),
+
+    #[error("Cannot connect to the validators of the new committee: {0}")]
+    UnreachableCommittee(NodeError),
+
+    #[error(
+        "Unsafe committee: validator {0} would have a quorum on its own. \
+         Use the force option if this is intended"
+    )]
+    UnsafeCommittee(ValidatorName),
+
+    #[error(
+        "The given validators only have {weight} votes, but {quorum_threshold} are needed \
+         for a quorum"
+    )]
+    ValidatorSubsetBelowQuorum { weight: u64, quorum_threshold: u64 },
+
+    #[error("The chain is no longer in the given epoch with the given committee")]
+    CommitteeChanged,
+
+    #[error("The local chain is inconsistent at height {height}: {reason}")]
+    InconsistentChain {
+        height: BlockHeight,
+        reason: &'static str,
+    },
+
+    #[error(
+        "Refusing to burn {amount}, which is more than {max_fraction_bps} basis points of \
+         the balance {balance}"
+    )]
+    BurnExceedsSafetyLimit {
+        amount: Amount,
+        balance: Amount,
+        max_fraction_bps: u16,
+    },
+
+    #[error("The idempotency key {0} was already used for different operations")]
+    IdempotencyKeyReused(Uuid),
+}
+
+impl ChainClientError {
+    /// Returns whether the block we tried to commit failed to execute or to validate locally,
+    /// so that proposing it again is pointless. Other errors, e.g. when the validators are
+    /// unreachable, leave the block pending.
+    fn is_local_block_failure(&self) -> bool {
+        matches!(
+            self,
+            ChainClientError::LocalNodeError(
+                LocalNodeError::WorkerError(_) | LocalNodeError::ArithmeticError(_)
+            ) | ChainClientError::ChainError(_)
+                | ChainClientError::ArithmeticError(_)
+                | ChainClientError::BlockProposalError(_)
+        )
+    }
 }
 
 impl From<Infallible> for ChainClientError {
@@ -690,6 +942,17 @@ impl<P: 'static, S: Storage> ChainClient<P, S> {
         &mut self.options
     }
 
+    /// Sets the validators to contact first when updating validators and collecting votes,
+    /// e.g. ordered by their measured latency, fastest first.
+    ///
+    /// If `validators` is not empty, requests are sent in waves, only to as many validators as
+    /// needed to reach a quorum, in that order and followed by the other validators. Otherwise
+    /// all validators are contacted at once.
+    #[instrument(level = "trace", skip(self))]
+    pub fn set_preferred_validators(&self, validators: Vec<ValidatorName>) {
+        self.state_mut().set_preferred_validators(validators);
+    }
+
     /// Gets the ID of the associated chain.
     #[instrument(level = "trace", skip(self))]
     pub fn chain_id(&self) -> ChainId {
@@ -719,6 +982,15 @@ impl<P: 'static, S: Storage> ChainClient<P, S> {
     pub fn pending_proposal(&self) -> ChainGuardMapped<Option<PendingProposal>> {
         Unsend::new(self.state().inner.map(|state| state.pending_proposal()))
     }
+
+    /// Gets the height of the pending block, if any.
+    #[instrument(level = "trace", skip(self))]
+    pub fn pending_proposal_height(&self) -> Option<BlockHeight> {
+        self.state()
+            .pending_proposal()
+            .as_ref()
+            .map(|proposal| proposal.block.height)
+    }
 }
 
 enum ReceiveCertificateMode {
@@ -761,6 +1033,59 @@ where
         self.client.local_node.chain_state_view(self.chain_id).await
     }
 
+    /// Returns the highest block height of each sender chain whose certificates this chain
+    /// has received from the validators.
+    ///
+    /// The trackers are saved in storage with the chain state, so a client restarted with the
+    /// same storage only downloads certificates received after these heights.
+    #[instrument(level = "trace")]
+    pub async fn received_certificate_trackers(
+        &self,
+    ) -> Result<BTreeMap<ChainId, BlockHeight>, LocalNodeError> {
+        Ok(self
+            .chain_state_view()
+            .await?
+            .received_certificate_trackers
+            .get()
+            .clone())
+    }
+
+    /// Returns a snapshot of the client's view of the chain, to help diagnose a client that
+    /// is stuck, e.g. by attaching it to a bug report.
+    ///
+    /// This only reads the client state and the local storage, each of them once, and can be
+    /// called at any time. Unlike most other methods, it doesn't update the client state from
+    /// the local node.
+    #[instrument(level = "trace")]
+    pub async fn diagnostics(&self) -> Result<ChainDiagnostics, ChainClientError> {
+        let (next_round, has_pending_proposal, known_key_pairs) = {
+            let state = self.state();
+            (
+                state.next_round(),
+                state.pending_proposal().is_some(),
+                state.known_key_pairs().len(),
+            )
+        };
+        let chain = self.chain_state_view().await?;
+        let tip = chain.tip_state.get();
+        let current_round = chain.manager.current_round();
+        let system = &chain.execution_state.system;
+        let committee = system
+            .epoch
+            .get()
+            .and_then(|epoch| system.committees.get().get(&epoch).cloned());
+        Ok(ChainDiagnostics {
+            chain_id: self.chain_id,
+            block_hash: tip.block_hash,
+            next_block_height: tip.next_block_height,
+            next_round: next_round.map_or(current_round, |round| round.max(current_round)),
+            has_pending_proposal,
+            known_key_pairs,
+            received_certificate_trackers: chain.received_certificate_trackers.get().clone(),
+            committee,
+        })
+    }
+
     /// Subscribes to notifications from this client's chain.
     #[instrument(level = "trace")]
     pub async fn subscribe(&self) -> Result<NotificationStream, LocalNodeError> {
@@ -769,6 +1094,156 @@ where
         )))
     }
 
+    /// Returns a stream of this chain's confirmed block certificates, in order and starting
+    /// at height `start`.
+    ///
+    /// Certificates are yielded as soon as the local node has them. To resume after dropping
+    /// the stream, pass the height after the last certificate that was received. The stream
+    /// ends after the first error.
+    ///
+    /// This has two limits:
+    /// - Only this client's own chain is covered. For other chains, create a client for them
+    ///   or use [`download_history`](Self::download_history).
+    /// - The stream doesn't fetch anything from the validators itself. Blocks proposed by
+    ///   this client are added to the local node directly, but blocks certified by other
+    ///   owners only arrive while [`listen`](Self::listen) runs or the chain is synchronized,
+    ///   e.g. with [`synchronize_from_validators`](Self::synchronize_from_validators).
+    ///   Otherwise the stream waits indefinitely.
+    #[instrument(level = "trace")]
+    pub async fn confirmed_certificates(
+        &self,
+        start: BlockHeight,
+    ) -> Result<
+        impl Stream<Item = Result<ConfirmedBlockCertificate, ChainClientError>>,
+        ChainClientError,
+    > {
+        let notifications = self.subscribe().await?;
+        let client = self.clone();
+        Ok(stream::unfold(Some((start, notifications)), move |state| {
+            let client = client.clone();
+            async move {
+                let (height, mut notifications) = state?;
+                loop {
+                    let result = match client.read_confirmed_certificate(height).await {
+                        Ok(None) => {
+                            // Wait until a new block is added to the chain.
+                            loop {
+                                let notification = notifications.next().await?;
+                                if matches!(notification.reason, Reason::NewBlock { .. }) {
+                                    break;
+                                }
+                            }
+                            continue;
+                        }
+                        Ok(Some(certificate)) => height
+                            .try_add_one()
+                            .map(|next_height| (certificate, next_height))
+                            .map_err(ChainClientError::from),
+                        Err(error) => Err(error),
+                    };
+                    return Some(match result {
+                        Ok((certificate, next_height)) => {
+                            (Ok(certificate), Some((next_height, notifications)))
+                        }
+                        Err(error) => (Err(error), None),
+                    });
+                }
+            }
+        }))
+    }
+
+    /// Reads the certificate of this chain's block at the given height, if the local node
+    /// has it.
+    async fn read_confirmed_certificate(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<ConfirmedBlockCertificate>, ChainClientError> {
+        let index = usize::try_from(height)?;
+        let Some(hash) = self
+            .chain_state_view()
+            .await?
+            .confirmed_log
+            .get(index)
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(self.client.storage.read_certificate(hash).await?))
+    }
+
+    /// Checks that the locally stored blocks of this chain form a valid chain from genesis to
+    /// the tip: each block is at the next height and references the previous block's hash, and
+    /// each certificate is signed by a quorum of the committee of the block's epoch. If old
+    /// certificates were pruned, the check starts at the first one that is still stored.
+    ///
+    /// Returns the next block height, or
+    /// [`ChainClientError::InconsistentChain`] for the first block that fails a check. Blocks
+    /// from epochs whose committee is no longer known locally cannot be verified.
+    #[instrument(level = "trace")]
+    pub async fn verify_chain(&self) -> Result<BlockHeight, ChainClientError> {
+        // The certificates below the pruned height were deleted, so we start after them.
+        let (hashes, tip_state, mut height, mut previous_block_hash) = {
+            let chain = self.chain_state_view().await?;
+            let height = *chain.pruned_height.get();
+            let start = usize::try_from(height)?;
+            let previous_block_hash = match start.checked_sub(1) {
+                Some(index) => chain.confirmed_log.get(index).await?,
+                None => None,
+            };
+            let hashes = chain.confirmed_log.read(start..).await?;
+            (
+                hashes,
+                chain.tip_state.get().clone(),
+                height,
+                previous_block_hash,
+            )
+        };
+        let (committees, _) = self.known_committees().await?;
+        for hashes in hashes.chunks(1000) {
+            let certificates = self
+                .client
+                .storage
+                .read_certificates(hashes.to_vec())
+                .await?;
+            for (hash, certificate) in hashes.iter().zip(certificates) {
+                let inconsistency = |reason| ChainClientError::InconsistentChain { height, reason };
+                let header = &certificate.block().header;
+                ensure!(
+                    certificate.hash() == *hash,
+                    inconsistency("the certificate does not match the confirmed log")
+                );
+                ensure!(
+                    header.chain_id == self.chain_id,
+                    inconsistency("the block belongs to another chain")
+                );
+                ensure!(
+                    header.height == height,
+                    inconsistency("the block height is not consecutive")
+                );
+                ensure!(
+                    header.previous_block_hash == previous_block_hash,
+                    inconsistency("the block does not reference the previous block")
+                );
+                let committee = committees.get(&header.epoch).ok_or_else(|| {
+                    inconsistency("the committee of the block's epoch is unknown")
+                })?;
+                certificate
+                    .check(committee)
+                    .map_err(|_| inconsistency("the certificate is not signed by a quorum"))?;
+                previous_block_hash = Some(*hash);
+                height.try_add_assign_one()?;
+            }
+        }
+        ensure!(
+            tip_state.next_block_height == height && tip_state.block_hash == previous_block_hash,
+            ChainClientError::InconsistentChain {
+                height,
+                reason: "the tip does not match the last block",
+            }
+        );
+        Ok(height)
+    }
+
     /// Returns the storage client used by this client's local node.
     #[instrument(level = "trace")]
     pub fn storage_client(&self) -> S {
@@ -801,17 +1276,62 @@ where
         Ok(response.info)
     }
 
-    /// Obtains up to `self.options.max_pending_message_bundles` pending message bundles for the
-    /// local chain.
+    /// Returns the channels that the local chain is subscribed to, according to the local node.
     #[instrument(level = "trace")]
-    async fn pending_message_bundles(&self) -> Result<Vec<IncomingBundle>, ChainClientError> {
-        let query = ChainInfoQuery::new(self.chain_id).with_pending_message_bundles();
-        let info = self
+    pub async fn subscriptions(&self) -> Result<Vec<ChannelSubscription>, LocalNodeError> {
+        let query = ChainInfoQuery::new(self.chain_id).with_subscriptions();
+        let response = self
             .client
             .local_node
             .handle_chain_info_query(query)
-            .await?
-            .info;
+            .await?;
+        self.update_from_info(&response.info);
+        Ok(response.info.requested_subscriptions)
+    }
+
+    /// Obtains up to `self.options.max_pending_message_bundles` pending message bundles for the
+    /// local chain.
+    #[instrument(level = "trace")]
+    async fn pending_message_bundles(&self) -> Result<Vec<IncomingBundle>, ChainClientError> {
+        let mut pending_message_bundles = Vec::new();
+        // Bundles added to the inbox between two pages can shift the following ones, so that
+        // some bundles are returned twice.
+        let mut seen_bundles = HashSet::new();
+        let mut accepted_count = 0;
+        let mut offset = 0;
+        let info = loop {
+            let query = ChainInfoQuery::new(self.chain_id).with_pending_message_bundles_page(
+                self.options.pending_message_bundles_page_size,
+                offset,
+            );
+            let mut info = self
+                .client
+                .local_node
+                .handle_chain_info_query(query)
+                .await?
+                .info;
+            let page = std::mem::take(&mut info.requested_pending_message_bundles);
+            offset += page.len();
+            for bundle in page {
+                let key = (
+                    bundle.origin.clone(),
+                    bundle.bundle.height,
+                    bundle.bundle.transaction_index,
+                );
+                if seen_bundles.insert(key) {
+                    accepted_count += usize::from(self.options.message_policy.accepts(&bundle));
+                    pending_message_bundles.push(bundle);
+                }
+            }
+            // A new chain needs all its bundles, to find the one that opens it.
+            if !info.has_more_pending_message_bundles
+                || (info.next_block_height > BlockHeight::ZERO
+                    && (self.options.message_policy.is_ignore()
+                        || accepted_count >= self.options.max_pending_message_bundles))
+            {
+                break info;
+            }
+        };
         {
             let state = self.state();
             ensure!(
@@ -825,7 +1345,6 @@ where
         }
 
         let mut rearranged = false;
-        let mut pending_message_bundles = info.requested_pending_message_bundles;
 
         // The first incoming message of any child chain must be `OpenChain`. We must have it in
         // our inbox, and include it before all other messages.
@@ -917,12 +1436,28 @@ where
 
     #[instrument(level = "trace")]
     fn make_nodes(&self, committee: &Committee) -> Result<Vec<RemoteNode<P::Node>>, NodeError> {
-        Ok(self
+        let mut nodes = self
             .client
             .validator_node_provider
             .make_nodes(committee)?
             .map(|(name, node)| RemoteNode { name, node })
-            .collect())
+            .collect::<Vec<_>>();
+        let state = self.state();
+        let preferred_validators = state.preferred_validators();
+        if !preferred_validators.is_empty() {
+            nodes.sort_by_key(|node| {
+                preferred_validators
+                    .iter()
+                    .position(|name| *name == node.name)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        Ok(nodes)
+    }
+
+    /// Returns whether validators should be contacted in waves, in order of preference.
+    fn has_preferred_validators(&self) -> bool {
+        !self.state().preferred_validators().is_empty()
     }
 
     /// Obtains the validators trusted by the local chain.
@@ -949,8 +1484,9 @@ where
             .ok_or(LocalNodeError::InactiveChain(self.chain_id))
     }
 
-    /// Obtains the identity of the current owner of the chain. Returns an error if we have the
-    /// private key for more than one identity.
+    /// Obtains the identity of the current owner of the chain. If there is a pending proposal
+    /// made by one of our identities, this is its author. Otherwise returns an error if we have
+    /// the private key for more than one identity.
     #[instrument(level = "trace")]
     pub async fn identity(&self) -> Result<Owner, ChainClientError> {
         let manager = self.chain_info().await?.manager;
@@ -958,47 +1494,181 @@ where
             manager.ownership.is_active(),
             LocalNodeError::InactiveChain(self.chain_id)
         );
-        let state = self.state();
-        let mut our_identities = manager
+        let owners = manager
             .ownership
             .all_owners()
             .chain(&manager.leader)
-            .filter(|owner| state.known_key_pairs().contains_key(owner));
-        let Some(identity) = our_identities.next() else {
-            return Err(ChainClientError::CannotFindKeyForChain(self.chain_id));
-        };
-        ensure!(
-            our_identities.all(|id| id == identity),
-            ChainClientError::FoundMultipleKeysForChain(self.chain_id)
-        );
-        Ok(*identity)
+            .copied()
+            .collect::<BTreeSet<_>>();
+        let mut our_identities = Vec::new();
+        for owner in owners {
+            if self.our_public_key(&owner).await?.is_some() {
+                our_identities.push(owner);
+            }
+        }
+        let pending_author = self
+            .state()
+            .pending_proposal()
+            .as_ref()
+            .and_then(|proposal| proposal.block.authenticated_signer);
+        if let Some(author) = pending_author.filter(|author| our_identities.contains(author)) {
+            return Ok(author);
+        }
+        match our_identities[..] {
+            [identity] => Ok(identity),
+            [] => Err(ChainClientError::CannotFindKeyForChain(self.chain_id)),
+            _ => Err(ChainClientError::FoundMultipleKeysForChain(self.chain_id)),
+        }
+    }
+
+    /// Returns whether the chain is owned by a single owner, by several owners, or is
+    /// inactive, according to the local node.
+    #[instrument(level = "trace")]
+    pub async fn ownership_mode(&self) -> Result<OwnershipMode, ChainClientError> {
+        let ownership = self.chain_info().await?.manager.ownership;
+        if !ownership.is_active() {
+            return Ok(OwnershipMode::Inactive);
+        }
+        let owners = ownership
+            .all_owners()
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        Ok(match owners[..] {
+            [owner] => OwnershipMode::Single(owner),
+            _ => OwnershipMode::Multi(owners),
+        })
     }
 
     /// Obtains the key pair associated to the current identity.
+    ///
+    /// Fails if the identity's key is only available through the client's [`Signer`].
     #[instrument(level = "trace")]
-    pub async fn key_pair(&self) -> Result<KeyPair, ChainClientError> {
+    pub async fn key_pair(&self) -> Result<AccountSecretKey, ChainClientError> {
         let id = self.identity().await?;
-        Ok(self
-            .state()
+        self.state()
             .known_key_pairs()
             .get(&id)
-            .expect("key should be known at this point")
-            .copy())
+            .map(AccountSecretKey::copy)
+            .ok_or(ChainClientError::CannotFindKeyForChain(self.chain_id))
     }
 
     /// Obtains the public key associated to the current identity.
     #[instrument(level = "trace")]
-    pub async fn public_key(&self) -> Result<PublicKey, ChainClientError> {
-        Ok(self.key_pair().await?.public())
+    pub async fn public_key(&self) -> Result<AccountPublicKey, ChainClientError> {
+        let id = self.identity().await?;
+        self.our_public_key(&id)
+            .await?
+            .ok_or(ChainClientError::CannotFindKeyForChain(self.chain_id))
+    }
+
+    /// Returns the public key of `owner` if we can sign on its behalf, either with a known
+    /// key pair or with the [`Signer`].
+    async fn our_public_key(
+        &self,
+        owner: &Owner,
+    ) -> Result<Option<AccountPublicKey>, ChainClientError> {
+        let known_public_key = self
+            .state()
+            .known_key_pairs()
+            .get(owner)
+            .map(AccountSecretKey::public);
+        if known_public_key.is_some() {
+            return Ok(known_public_key);
+        }
+        let Some(signer) = &self.client.signer else {
+            return Ok(None);
+        };
+        Ok(signer.public_key(owner).await?)
+    }
+
+    /// Signs the proposal content on behalf of `owner`, with its key pair if it is known and
+    /// with the [`Signer`] otherwise.
+    async fn sign_proposal(
+        &self,
+        owner: &Owner,
+        content: ProposalContent,
+        validated_block_certificate: Option<LiteCertificate<'static>>,
+    ) -> Result<BlockProposal, ChainClientError> {
+        let (public_key, signature) = self.sign_content(owner, &content).await?;
+        Ok(BlockProposal::new_signed(
+            content,
+            validated_block_certificate,
+            public_key,
+            signature,
+        ))
+    }
+
+    /// Signs the proposal content on behalf of `owner`, and returns the public key and the
+    /// signature.
+    async fn sign_content(
+        &self,
+        owner: &Owner,
+        content: &ProposalContent,
+    ) -> Result<(AccountPublicKey, AccountSignature), ChainClientError> {
+        if let Some(key_pair) = self.known_key_pair(owner) {
+            return Ok((key_pair.public(), AccountSignature::new(content, &key_pair)));
+        }
+        let (signer, public_key) = self.external_signer(owner).await?;
+        Ok((public_key, signer.sign(owner, content).await?))
+    }
+
+    /// Signs a proposed block on behalf of `owner` to approve it, and returns the public key
+    /// and the signature.
+    async fn sign_block(
+        &self,
+        owner: &Owner,
+        block: &ProposedBlock,
+    ) -> Result<(AccountPublicKey, AccountSignature), ChainClientError> {
+        if let Some(key_pair) = self.known_key_pair(owner) {
+            return Ok((key_pair.public(), AccountSignature::new(block, &key_pair)));
+        }
+        let (signer, public_key) = self.external_signer(owner).await?;
+        Ok((public_key, signer.sign_block(owner, block).await?))
+    }
+
+    /// Returns the key pair of `owner`, if this client holds it.
+    fn known_key_pair(&self, owner: &Owner) -> Option<AccountSecretKey> {
+        self.state()
+            .known_key_pairs()
+            .get(owner)
+            .map(AccountSecretKey::copy)
+    }
+
+    /// Returns the external signer and the public key of `owner`, if the signer can sign on
+    /// its behalf.
+    async fn external_signer(
+        &self,
+        owner: &Owner,
+    ) -> Result<(&Arc<dyn Signer>, AccountPublicKey), ChainClientError> {
+        let signer = self
+            .client
+            .signer
+            .as_ref()
+            .ok_or(ChainClientError::CannotFindKeyForChain(self.chain_id))?;
+        let public_key = signer
+            .public_key(owner)
+            .await?
+            .ok_or(ChainClientError::CannotFindKeyForChain(self.chain_id))?;
+        Ok((signer, public_key))
     }
 
     /// Prepares the chain for the next operation, i.e. makes sure we have synchronized it up to
     /// its current height and are not missing any received messages from the inbox.
-    #[instrument(level = "trace")]
+    #[instrument(
+        level = "debug",
+        skip(self),
+        fields(chain_id = %self.chain_id, height = %self.next_block_height(), round)
+    )]
     pub async fn prepare_chain(&self) -> Result<Box<ChainInfo>, ChainClientError> {
         #[cfg(with_metrics)]
         let _latency = metrics::PREPARE_CHAIN_LATENCY.measure_latency();
 
+        if let Some(info) = self.trusted_local_info().await? {
+            return Ok(info);
+        }
+
         let mut info = self.synchronize_until(self.next_block_height()).await?;
 
         if self.state().has_other_owners(&info.manager.ownership) {
@@ -1015,12 +1685,35 @@ where
             .validate_incoming_bundles()
             .await;
         if matches!(result, Err(ChainError::MissingCrossChainUpdate { .. })) {
+            self.client.metrics.cross_chain_updates_missing();
             self.find_received_certificates().await?;
         }
+        tracing::Span::current().record("round", field::display(info.manager.current_round));
         self.update_from_info(&info);
+        self.state_mut().set_synchronized();
         Ok(info)
     }
 
+    /// Returns the local chain info if it was synchronized less than
+    /// [`ChainClientOptions::trust_local_state_for`] ago and we are the chain's only owner.
+    /// Otherwise the chain needs to be synchronized with the validators.
+    async fn trusted_local_info(&self) -> Result<Option<Box<ChainInfo>>, ChainClientError> {
+        if !self
+            .state()
+            .is_synchronized_within(self.options.trust_local_state_for)
+        {
+            return Ok(None);
+        }
+        let info = self.chain_info().await?;
+        if !self.state().is_sole_owner(&info.manager.ownership) {
+            return Ok(None);
+        }
+        // The local node can be ahead of our state if an earlier call was canceled after
+        // processing a certificate.
+        self.update_from_info(&info);
+        Ok(Some(info))
+    }
+
     // Verifies that our local storage contains enough history compared to the
     // expected block height. Otherwise, downloads the missing history from the
     // network.
@@ -1043,13 +1736,117 @@ where
         Ok(info)
     }
 
-    /// Submits a validated block for finalization and returns the confirmed block certificate.
-    #[instrument(level = "trace", skip(committee, certificate))]
-    async fn finalize_block(
+    /// Downloads the certificates of the blocks of any chain in the given height `range` from
+    /// the validators, and returns them in order of height without processing them.
+    ///
+    /// The certificates are verified against the committees known to this client. The range
+    /// is truncated at the height that validators with the validity threshold report the chain
+    /// has reached, since a single validator could report any height. Validators are asked one
+    /// after the other until one of them provides the whole truncated range. If none of them
+    /// does, [`ChainClientError::CannotDownloadHistory`] is returned.
+    #[instrument(level = "trace")]
+    pub async fn download_history(
         &self,
-        committee: &Committee,
-        certificate: ValidatedBlockCertificate,
-    ) -> Result<ConfirmedBlockCertificate, ChainClientError> {
+        chain_id: ChainId,
+        range: BlockHeightRange,
+    ) -> Result<Vec<ConfirmedBlockCertificate>, ChainClientError> {
+        let (committees, _) = self.known_committees().await?;
+        let committee = self.local_committee().await?;
+        let mut nodes = self.make_nodes(&committee)?;
+        let query = ChainInfoQuery::new(chain_id);
+        let reports = future::join_all(nodes.iter().map(|remote_node| {
+            let query = query.clone();
+            async move {
+                let info = remote_node.handle_chain_info_query(query).await.ok()?;
+                Some((remote_node.name, info.next_block_height))
+            }
+        }))
+        .await;
+        let next_block_height = committee
+            .highest_value_with_validity(reports.into_iter().flatten())
+            .ok_or(ChainClientError::CannotDownloadHistory {
+                chain_id,
+                start: range.start,
+            })?;
+        let available = next_block_height.0.saturating_sub(range.start.0);
+        let limit = range.limit.map_or(available, |limit| limit.min(available));
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let range = BlockHeightRange::multi(range.start, limit);
+
+        nodes.shuffle(&mut rand::thread_rng());
+        for remote_node in &nodes {
+            let result = remote_node
+                .download_certificates_in_range(chain_id, range.clone())
+                .await
+                .and_then(|certificates| {
+                    Self::check_history(chain_id, range.start, &certificates, &committees)?;
+                    Ok(certificates)
+                });
+            let certificates = match result {
+                Ok(certificates) => certificates,
+                Err(error) => {
+                    warn!(
+                        validator = ?remote_node.name, %error,
+                        "Failed to download the history of chain {chain_id:.8}"
+                    );
+                    continue;
+                }
+            };
+            if certificates.len() as u64 == limit {
+                return Ok(certificates);
+            }
+            warn!(
+                validator = ?remote_node.name,
+                "Validator provided {} of the {limit} requested blocks of chain {chain_id:.8}",
+                certificates.len(),
+            );
+        }
+        Err(ChainClientError::CannotDownloadHistory {
+            chain_id,
+            start: range.start,
+        })
+    }
+
+    /// Checks that the `certificates` are the consecutive blocks of the given chain from height
+    /// `start` on, and that they are signed by the committees of their epochs.
+    fn check_history(
+        chain_id: ChainId,
+        start: BlockHeight,
+        certificates: &[ConfirmedBlockCertificate],
+        committees: &BTreeMap<Epoch, Committee>,
+    ) -> Result<(), NodeError> {
+        let mut certificates_by_epoch = BTreeMap::<Epoch, Vec<_>>::new();
+        let mut previous_hash = None;
+        for (height, certificate) in (start.0..).zip(certificates) {
+            let header = &certificate.block().header;
+            ensure!(
+                header.chain_id == chain_id
+                    && header.height == BlockHeight(height)
+                    && (previous_hash.is_none() || header.previous_block_hash == previous_hash)
+                    && committees.contains_key(&header.epoch),
+                NodeError::UnexpectedCertificateValue
+            );
+            previous_hash = Some(certificate.hash());
+            certificates_by_epoch
+                .entry(header.epoch)
+                .or_default()
+                .push(certificate.clone());
+        }
+        for (epoch, epoch_certificates) in certificates_by_epoch {
+            ConfirmedBlockCertificate::verify_batch(&epoch_certificates, &committees[&epoch])?;
+        }
+        Ok(())
+    }
+
+    /// Submits a validated block for finalization and returns the confirmed block certificate.
+    #[instrument(level = "trace", skip(committee, certificate))]
+    async fn finalize_block(
+        &self,
+        committee: &Committee,
+        certificate: ValidatedBlockCertificate,
+    ) -> Result<ConfirmedBlockCertificate, ChainClientError> {
         let hashed_value = Hashed::new(ConfirmedBlock::new(
             certificate.inner().block().clone().into(),
         ));
@@ -1069,13 +1866,22 @@ where
     }
 
     /// Submits a block proposal to the validators.
-    #[instrument(level = "trace", skip(committee, proposal, value))]
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(
+            chain_id = %self.chain_id,
+            height = %proposal.content.block.height,
+            round = %proposal.content.round,
+        )
+    )]
     async fn submit_block_proposal<T: ProcessableCertificate>(
         &self,
         committee: &Committee,
         proposal: Box<BlockProposal>,
         value: Hashed<T>,
     ) -> Result<GenericCertificate<T>, ChainClientError> {
+        self.client.metrics.block_proposed();
         let submit_action = CommunicateAction::SubmitBlock {
             proposal,
             blob_ids: value.inner().required_blob_ids().into_iter().collect(),
@@ -1122,7 +1928,7 @@ where
     }
 
     /// Broadcasts certified blocks to validators.
-    #[instrument(level = "trace", skip(committee, delivery))]
+    #[instrument(level = "debug", skip_all, fields(%chain_id, %height))]
     async fn communicate_chain_updates(
         &self,
         committee: &Committee,
@@ -1135,28 +1941,56 @@ where
         let n_validators = nodes.len();
         let chain_worker_count =
             std::cmp::max(1, self.client.max_loaded_chains.get() / n_validators);
-        communicate_with_quorum(
-            &nodes,
-            committee,
-            |_: &()| (),
-            |remote_node| {
-                let mut updater = ValidatorUpdater {
-                    chain_worker_count,
-                    remote_node,
-                    local_node: local_node.clone(),
-                };
-                Box::pin(async move {
-                    updater
-                        .send_chain_information(chain_id, height, delivery)
-                        .await
-                })
-            },
-            self.options.grace_period,
-        )
-        .await?;
+        let request_timeout = self.options.request_timeout;
+        let send_chain_information = |remote_node: RemoteNode<P::Node>| {
+            let mut updater = ValidatorUpdater {
+                chain_worker_count,
+                remote_node,
+                local_node: local_node.clone(),
+                request_timeout,
+            };
+            Box::pin(async move {
+                updater
+                    .send_chain_information(chain_id, height, delivery)
+                    .await
+            })
+        };
+        self.client.metrics.chain_updates_sent();
+        let start = Instant::now();
+        let result = if self.has_preferred_validators() {
+            communicate_with_quorum_prioritized(
+                &nodes,
+                committee,
+                |_: &()| (),
+                send_chain_information,
+                self.options.grace_period,
+            )
+            .await
+        } else {
+            communicate_with_quorum(
+                &nodes,
+                committee,
+                |_: &()| (),
+                send_chain_information,
+                self.options.grace_period,
+            )
+            .await
+        };
+        self.record_quorum_result(start, &result);
+        result?;
         Ok(())
     }
 
+    /// Reports to the client's metrics whether a request that started at `start` reached a
+    /// quorum of validators.
+    fn record_quorum_result<T, E>(&self, start: Instant, result: &Result<T, E>) {
+        let latency = start.elapsed();
+        match result {
+            Ok(_) => self.client.metrics.quorum_reached(latency),
+            Err(_) => self.client.metrics.quorum_failed(latency),
+        }
+    }
+
     /// Broadcasts certified blocks and optionally a block proposal, certificate or
     /// leader timeout request.
     ///
@@ -1174,22 +2008,40 @@ where
         let n_validators = nodes.len();
         let chain_worker_count =
             std::cmp::max(1, self.client.max_loaded_chains.get() / n_validators);
-        let ((votes_hash, votes_round), votes) = communicate_with_quorum(
-            &nodes,
-            committee,
-            |vote: &LiteVote| (vote.value.value_hash, vote.round),
-            |remote_node| {
-                let mut updater = ValidatorUpdater {
-                    chain_worker_count,
-                    remote_node,
-                    local_node: local_node.clone(),
-                };
-                let action = action.clone();
-                Box::pin(async move { updater.send_chain_update(action).await })
-            },
-            self.options.grace_period,
-        )
-        .await?;
+        let request_timeout = self.options.request_timeout;
+        let group_by = |vote: &LiteVote| (vote.value.value_hash, vote.round);
+        let send_chain_update = |remote_node: RemoteNode<P::Node>| {
+            let mut updater = ValidatorUpdater {
+                chain_worker_count,
+                remote_node,
+                local_node: local_node.clone(),
+                request_timeout,
+            };
+            let action = action.clone();
+            Box::pin(async move { updater.send_chain_update(action).await })
+        };
+        let start = Instant::now();
+        let result = if self.has_preferred_validators() {
+            communicate_with_quorum_prioritized(
+                &nodes,
+                committee,
+                group_by,
+                send_chain_update,
+                self.options.grace_period,
+            )
+            .await
+        } else {
+            communicate_with_quorum(
+                &nodes,
+                committee,
+                group_by,
+                send_chain_update,
+                self.options.grace_period,
+            )
+            .await
+        };
+        self.record_quorum_result(start, &result);
+        let ((votes_hash, votes_round), votes) = result?;
         ensure!(
             (votes_hash, votes_round) == (value.hash(), action.round()),
             ChainClientError::ProtocolError("Unexpected response from validators")
@@ -1304,19 +2156,42 @@ where
         remote_node: &RemoteNode<P::Node>,
         chain_worker_limit: usize,
     ) -> Result<ReceivedCertificatesFromValidator, ChainClientError> {
-        let mut tracker = self
+        let trackers = self.received_certificate_trackers().await?;
+        let mut log_index = self
             .chain_state_view()
             .await?
-            .received_certificate_trackers
+            .received_log_indices
             .get()
             .get(&remote_node.name)
             .copied()
             .unwrap_or(0);
         let (committees, max_epoch) = self.known_committees().await?;
 
-        // Retrieve the list of newly received certificates from this validator.
-        let query = ChainInfoQuery::new(chain_id).with_received_log_excluding_first_n(tracker);
-        let info = remote_node.handle_chain_info_query(query).await?;
+        // Retrieve the list of newly received certificates from this validator, starting
+        // with the last entry we scanned last time.
+        let query = match log_index.checked_sub(1) {
+            Some(n) => ChainInfoQuery::new(chain_id).with_received_log_excluding_first_n(n),
+            None => ChainInfoQuery::new(chain_id).with_received_log_after(trackers.clone()),
+        };
+        let mut info = remote_node.handle_chain_info_query(query).await?;
+        if log_index > 0 {
+            // We downloaded everything up to the last scanned entry. If the validator's log
+            // has a different entry there, e.g. because it was reset, we scan it from the
+            // start. The trackers still filter out what we already have.
+            let is_consistent = info.requested_received_log.first().is_some_and(|entry| {
+                trackers
+                    .get(&entry.chain_id)
+                    .is_some_and(|height| *height >= entry.height)
+            });
+            if is_consistent {
+                info.requested_received_log.remove(0);
+            } else {
+                log_index = 0;
+                let query = ChainInfoQuery::new(chain_id).with_received_log_after(trackers);
+                info = remote_node.handle_chain_info_query(query).await?;
+            }
+        }
+        let count_received_log = info.count_received_log as u64;
         let remote_log = info.requested_received_log;
         let remote_max_heights = Self::max_height_per_chain(&remote_log);
 
@@ -1363,14 +2238,15 @@ where
             .download_certificates(certificate_hashes)
             .await?;
 
-        // Check the signatures and keep only the ones that are valid.
-        let mut certificates = Vec::new();
+        // Keep only the certificates from known epochs, grouped by epoch.
+        let mut certificates_by_epoch = BTreeMap::<Epoch, Vec<_>>::new();
         for confirmed_block_certificate in remote_certificates {
             let block_header = &confirmed_block_certificate.inner().block().header;
             let sender_chain_id = block_header.chain_id;
             let height = block_header.height;
             let epoch = block_header.epoch;
-            match self.check_certificate(max_epoch, &committees, &confirmed_block_certificate)? {
+            match self.check_certificate_epoch(max_epoch, &committees, &confirmed_block_certificate)
+            {
                 CheckCertificateResult::FutureEpoch => {
                     warn!(
                         "Postponing received certificate from {sender_chain_id:.8} at height \
@@ -1387,30 +2263,47 @@ where
                     warn!("Skipping received certificate from past epoch {epoch:?}");
                 }
                 CheckCertificateResult::New => {
-                    downloaded_heights
-                        .entry(sender_chain_id)
-                        .and_modify(|h| *h = height.max(*h))
-                        .or_insert(height);
-                    certificates.push(confirmed_block_certificate);
+                    certificates_by_epoch
+                        .entry(epoch)
+                        .or_default()
+                        .push(confirmed_block_certificate);
                 }
             }
         }
 
-        // Increase the tracker up to the first position we haven't downloaded.
-        for entry in remote_log {
-            if downloaded_heights
-                .get(&entry.chain_id)
-                .is_some_and(|h| *h >= entry.height)
-            {
-                tracker += 1;
-            } else {
-                break;
+        // Check the signatures of each epoch's certificates in a single batch.
+        let mut certificates = Vec::new();
+        for (epoch, epoch_certificates) in certificates_by_epoch {
+            let committee = &committees[&epoch];
+            ConfirmedBlockCertificate::verify_batch(&epoch_certificates, committee)
+                .map_err(NodeError::from)?;
+            for confirmed_block_certificate in epoch_certificates {
+                let block_header = &confirmed_block_certificate.inner().block().header;
+                downloaded_heights
+                    .entry(block_header.chain_id)
+                    .and_modify(|h| *h = block_header.height.max(*h))
+                    .or_insert(block_header.height);
+                certificates.push(confirmed_block_certificate);
             }
         }
 
+        // Only skip the scanned part of the validator's log next time if we have downloaded
+        // every entry in it. The other entries were already filtered out by the trackers.
+        if remote_log.iter().all(|entry| {
+            downloaded_heights
+                .get(&entry.chain_id)
+                .is_some_and(|height| *height >= entry.height)
+        }) {
+            log_index = count_received_log;
+        }
+
+        // Advance the tracker of each sender chain to the height we have downloaded. Since
+        // we always download all blocks up to that height, no received certificate below it
+        // can be missing, regardless of the order of the validator's log.
         Ok(ReceivedCertificatesFromValidator {
             name: remote_node.name,
-            tracker,
+            log_index,
+            trackers: downloaded_heights,
             certificates,
             other_sender_chains,
         })
@@ -1420,30 +2313,27 @@ where
         level = "trace", skip_all,
         fields(certificate_hash = ?incoming_certificate.hash()),
     )]
-    fn check_certificate(
+    fn check_certificate_epoch(
         &self,
         highest_known_epoch: Epoch,
         committees: &BTreeMap<Epoch, Committee>,
         incoming_certificate: &ConfirmedBlockCertificate,
-    ) -> Result<CheckCertificateResult, NodeError> {
+    ) -> CheckCertificateResult {
         let block = incoming_certificate.block();
-        // Check that certificates are valid w.r.t one of our trusted committees.
+        // Check that certificates belong to one of our trusted committees. Their signatures
+        // are verified by the caller.
         if block.header.epoch > highest_known_epoch {
-            return Ok(CheckCertificateResult::FutureEpoch);
-        }
-        if let Some(known_committee) = committees.get(&block.header.epoch) {
-            // This epoch is recognized by our chain. Let's verify the
-            // certificate.
-            incoming_certificate.check(known_committee)?;
-            Ok(CheckCertificateResult::New)
+            CheckCertificateResult::FutureEpoch
+        } else if committees.contains_key(&block.header.epoch) {
+            CheckCertificateResult::New
         } else {
             // We don't accept a certificate from a committee that was retired.
-            Ok(CheckCertificateResult::OldEpoch)
+            CheckCertificateResult::OldEpoch
         }
     }
 
     /// Processes the results of [`synchronize_received_certificates_from_validator`] and updates
-    /// the trackers for the validators.
+    /// the trackers for the sender chains.
     #[tracing::instrument(level = "trace", skip(received_certificates_batches))]
     async fn receive_certificates_from_validators(
         &self,
@@ -1453,10 +2343,17 @@ where
         let mut other_sender_chains = BTreeSet::new();
         let mut certificates =
             BTreeMap::<ChainId, BTreeMap<BlockHeight, ConfirmedBlockCertificate>>::new();
-        let mut new_trackers = BTreeMap::new();
+        let mut new_trackers = BTreeMap::<ChainId, BlockHeight>::new();
+        let mut new_log_indices = BTreeMap::new();
         for response in received_certificates_batches {
             other_sender_chains.extend(response.other_sender_chains);
-            new_trackers.insert(response.name, response.tracker);
+            new_log_indices.insert(response.name, response.log_index);
+            for (sender_chain_id, height) in response.trackers {
+                new_trackers
+                    .entry(sender_chain_id)
+                    .and_modify(|h| *h = height.max(*h))
+                    .or_insert(height);
+            }
             for certificate in response.certificates {
                 certificates
                     .entry(certificate.block().header.chain_id)
@@ -1465,6 +2362,7 @@ where
             }
         }
         let certificate_count = certificates.values().map(BTreeMap::len).sum::<usize>();
+        self.client.metrics.certificates_received(certificate_count);
 
         tracing::info!(
             "Received {certificate_count} certificates from {validator_count} validator(s)."
@@ -1479,16 +2377,9 @@ where
         let stream = stream::iter(certificates.into_values().map(|certificates| {
             let client = self.clone();
             async move {
-                for certificate in certificates.into_values() {
-                    let hash = certificate.hash();
-                    let mode = ReceiveCertificateMode::AlreadyChecked;
-                    if let Err(e) = client
-                        .receive_certificate_internal(certificate, mode, None)
-                        .await
-                    {
-                        warn!("Received invalid certificate {hash}: {e}");
-                    }
-                }
+                client
+                    .receive_checked_certificates(certificates.into_values().collect())
+                    .await
             }
         }))
         .buffer_unordered(chain_worker_limit);
@@ -1515,7 +2406,7 @@ where
         if let Err(error) = self
             .client
             .local_node
-            .update_received_certificate_trackers(self.chain_id, new_trackers)
+            .update_received_certificate_trackers(self.chain_id, new_trackers, new_log_indices)
             .await
         {
             error!(
@@ -1525,6 +2416,67 @@ where
         }
     }
 
+    /// Processes the already checked certificates of a single sender chain, sorted by height,
+    /// and logs the ones that are invalid.
+    ///
+    /// The first certificate of each run of consecutive heights is received on its own, which
+    /// downloads its missing ancestors. The rest of the run is handed to the local node in a
+    /// single batch. The certificates of the batch that fail are then received on their own,
+    /// e.g. to download missing blobs.
+    async fn receive_checked_certificates(&self, certificates: Vec<ConfirmedBlockCertificate>) {
+        let mode = ReceiveCertificateMode::AlreadyChecked;
+        let known_committees = self.known_committees().await.ok();
+        let mut certificates = certificates.into_iter().peekable();
+        while let Some(first) = certificates.next() {
+            let mut next_height = first.block().header.height.try_add_one().ok();
+            let mut run = Vec::new();
+            while let Some(certificate) = certificates
+                .next_if(|certificate| Some(certificate.block().header.height) == next_height)
+            {
+                next_height = certificate.block().header.height.try_add_one().ok();
+                run.push(certificate);
+            }
+            let hash = first.hash();
+            if let Err(e) = self.receive_certificate_internal(first, mode, None).await {
+                warn!("Received invalid certificate {hash}: {e}");
+            }
+
+            // Only certificates from known committees can skip the individual checks.
+            let (batch, mut individual): (Vec<_>, Vec<_>) =
+                run.into_iter().partition(|certificate| {
+                    known_committees
+                        .as_ref()
+                        .is_some_and(|(committees, max_epoch)| {
+                            let epoch = certificate.block().header.epoch;
+                            epoch <= *max_epoch && committees.contains_key(&epoch)
+                        })
+                });
+            if !batch.is_empty() {
+                let results = self
+                    .client
+                    .local_node
+                    .handle_certificates(batch.clone(), &self.client.notifier)
+                    .await;
+                for (certificate, result) in batch.into_iter().zip(results) {
+                    match result {
+                        Ok(response) => self.update_from_info(&response.info),
+                        Err(_) => individual.push(certificate),
+                    }
+                }
+                individual.sort_by_key(|certificate| certificate.block().header.height);
+            }
+            for certificate in individual {
+                let hash = certificate.hash();
+                if let Err(e) = self
+                    .receive_certificate_internal(certificate, mode, None)
+                    .await
+                {
+                    warn!("Received invalid certificate {hash}: {e}");
+                }
+            }
+        }
+    }
+
     /// Attempts to download new received certificates.
     ///
     /// This is a best effort: it will only find certificates that have been confirmed
@@ -1533,23 +2485,40 @@ where
     ///
     /// However, this should be the case whenever a sender's chain is still in use and
     /// is regularly upgraded to new committees.
-    #[instrument(level = "trace")]
+    #[instrument(
+        level = "debug",
+        skip(self),
+        fields(chain_id = %self.chain_id, height = %self.next_block_height())
+    )]
     async fn find_received_certificates(&self) -> Result<(), ChainClientError> {
         #[cfg(with_metrics)]
         let _latency = metrics::FIND_RECEIVED_CERTIFICATES_LATENCY.measure_latency();
 
         // Use network information from the local chain.
-        let chain_id = self.chain_id;
         let local_committee = self.local_committee().await?;
         let nodes = self.make_nodes(&local_committee)?;
+        self.find_received_certificates_from(&local_committee, &nodes)
+            .await
+    }
+
+    /// Attempts to download new received certificates from the given validators of the local
+    /// committee, which must be able to reach a quorum, like
+    /// [`ChainClient::find_received_certificates`].
+    async fn find_received_certificates_from(
+        &self,
+        local_committee: &Committee,
+        nodes: &[RemoteNode<P::Node>],
+    ) -> Result<(), ChainClientError> {
+        let chain_id = self.chain_id;
         let client = self.clone();
         // Proceed to downloading received certificates. Split the available chain workers so that
         // the tasks don't use more than the limit in total.
         let chain_worker_limit =
             (self.client.max_loaded_chains.get() / local_committee.validators().len()).max(1);
+        let start = Instant::now();
         let result = communicate_with_quorum(
-            &nodes,
-            &local_committee,
+            nodes,
+            local_committee,
             |_| (),
             |remote_node| {
                 let client = client.clone();
@@ -1566,6 +2535,7 @@ where
             self.options.grace_period,
         )
         .await;
+        self.record_quorum_result(start, &result);
         let received_certificate_batches = match result {
             Ok(((), received_certificate_batches)) => received_certificate_batches,
             Err(CommunicationError::Trusted(NodeError::InactiveChain(id))) if id == chain_id => {
@@ -1582,6 +2552,57 @@ where
         Ok(())
     }
 
+    /// Deletes the certificates of the sender chains that this chain has received messages
+    /// from, except the last `keep_last` blocks of each sender chain, the blocks whose
+    /// outgoing messages are still in flight and the blocks from which this chain's inboxes
+    /// still have messages to execute, which validators may need to be sent again.
+    ///
+    /// The certificates of this chain and of the other chains tracked by this client are
+    /// never pruned. The states of the sender chains are kept too, so only new blocks need
+    /// to be downloaded later.
+    #[instrument(level = "trace")]
+    pub async fn prune_history(&self, keep_last: usize) -> Result<(), ChainClientError> {
+        let tracked_chains = self
+            .client
+            .tracked_chains
+            .read()
+            .expect("Panics should not happen while holding a lock to `tracked_chains`")
+            .clone();
+        let (sender_chain_ids, keep_from) = {
+            let chain = self.chain_state_view().await?;
+            let sender_chain_ids = chain
+                .received_certificate_trackers
+                .get()
+                .keys()
+                .copied()
+                .filter(|sender_chain_id| {
+                    *sender_chain_id != self.chain_id && !tracked_chains.contains(sender_chain_id)
+                })
+                .collect::<Vec<_>>();
+            let mut keep_from = BTreeMap::<ChainId, BlockHeight>::new();
+            for (origin, inbox) in chain.inboxes.try_load_all_entries().await? {
+                if let Some(bundle) = inbox.added_bundles.front().await? {
+                    keep_from
+                        .entry(origin.sender)
+                        .and_modify(|height| *height = bundle.height.min(*height))
+                        .or_insert(bundle.height);
+                }
+            }
+            (sender_chain_ids, keep_from)
+        };
+        for sender_chain_id in sender_chain_ids {
+            let keep_from = keep_from
+                .get(&sender_chain_id)
+                .copied()
+                .unwrap_or(BlockHeight::MAX);
+            self.client
+                .local_node
+                .prune_history(sender_chain_id, keep_last, keep_from)
+                .await?;
+        }
+        Ok(())
+    }
+
     /// Sends money.
     #[instrument(level = "trace")]
     pub async fn transfer(
@@ -1599,6 +2620,176 @@ where
         .await
     }
 
+    /// Sends money to several recipients in a single block, from the account of `owner` or
+    /// from the chain account if `owner` is `None`.
+    ///
+    /// Fails with [`ArithmeticError::Overflow`] before proposing a block if the total amount
+    /// exceeds [`Amount::MAX`].
+    #[instrument(level = "trace")]
+    pub async fn transfer_batch(
+        &self,
+        owner: Option<Owner>,
+        transfers: Vec<(Recipient, Amount)>,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        Amount::try_sum(transfers.iter().map(|(_, amount)| *amount))?;
+        let operations = transfers
+            .into_iter()
+            .map(|(recipient, amount)| {
+                Operation::System(SystemOperation::Transfer {
+                    owner,
+                    recipient,
+                    amount,
+                })
+            })
+            .collect();
+        self.execute_operations(operations, vec![]).await
+    }
+
+    /// Sends money like [`ChainClient::transfer`], but at most once for each idempotency `key`.
+    ///
+    /// The hashes of the transfer and of the block are recorded for `key` in storage before the
+    /// block is proposed. If a block was already recorded for `key`, e.g. because the client was
+    /// restarted during a previous call, the pending block is processed first, and the
+    /// certificate of the recorded block is returned if it was committed. Otherwise the transfer
+    /// is proposed again, unless the recorded block gets committed in the meantime. Reusing a
+    /// key for a different transfer is an error.
+    ///
+    /// Records are deleted after [`IDEMPOTENCY_RECORD_RETENTION`], so keys must not be reused
+    /// for retries after that long.
+    #[instrument(level = "trace")]
+    pub async fn transfer_with_idempotency(
+        &self,
+        key: Uuid,
+        owner: Option<Owner>,
+        amount: Amount,
+        recipient: Recipient,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        let operation = Operation::System(SystemOperation::Transfer {
+            owner,
+            recipient,
+            amount,
+        });
+        let operations_hash = CryptoHash::new(&operation);
+        let storage = &self.client.storage;
+        let record = storage
+            .read_idempotency_record(self.chain_id, key.as_u128())
+            .await?;
+        if let Some(record) = record {
+            ensure!(
+                record.operations_hash == operations_hash,
+                ChainClientError::IdempotencyKeyReused(key)
+            );
+            if let ClientOutcome::WaitForTimeout(timeout) = self.process_pending_block().await? {
+                return Ok(ClientOutcome::WaitForTimeout(timeout));
+            }
+            if storage.contains_certificate(record.block_hash).await? {
+                let certificate = storage.read_certificate(record.block_hash).await?;
+                return Ok(ClientOutcome::Committed(certificate));
+            }
+        }
+        let make_operations = || future::ready(Ok(vec![operation.clone()]));
+        let idempotency = Some((key, operations_hash));
+        self.execute_operations_as(None, None, idempotency, make_operations, vec![])
+            .await
+    }
+
+    /// Transfers `amount` from the account of `owner`, in a block proposed and signed by
+    /// `owner`. Unlike [`ChainClient::transfer`], this works if we hold the keys of several
+    /// owners of this chain.
+    #[instrument(level = "trace")]
+    pub async fn transfer_as(
+        &self,
+        owner: Owner,
+        amount: Amount,
+        recipient: Recipient,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        let operation = Operation::System(SystemOperation::Transfer {
+            owner: Some(owner),
+            recipient,
+            amount,
+        });
+        let make_operations = move || {
+            let operations = vec![operation.clone()];
+            async move {
+                self.prepare_chain().await?;
+                Ok::<_, ChainClientError>(operations)
+            }
+        };
+        self.execute_operations_as(Some(owner), None, None, make_operations, vec![])
+            .await
+    }
+
+    /// Schedules a transfer in a block that can only be committed once the chain has reached
+    /// the height `not_before`. The block is proposed by the first call to
+    /// [`Self::submit_due_blocks`] at or after that height.
+    ///
+    /// The scheduled block is only kept in memory. To keep it across restarts, save
+    /// [`Self::scheduled_blocks`], e.g. in the wallet, and pass them to
+    /// [`Client::create_chain_client`] when the client is created again.
+    #[instrument(level = "trace")]
+    pub fn transfer_after(
+        &self,
+        not_before: BlockHeight,
+        owner: Option<Owner>,
+        amount: Amount,
+        recipient: Recipient,
+    ) {
+        let operations = vec![
+            Operation::System(SystemOperation::Timelock { not_before }),
+            Operation::System(SystemOperation::Transfer {
+                owner,
+                recipient,
+                amount,
+            }),
+        ];
+        self.state_mut()
+            .scheduled_blocks_mut()
+            .push(ScheduledBlock {
+                not_before,
+                operations,
+            });
+    }
+
+    /// Returns the blocks scheduled by [`Self::transfer_after`] that haven't been committed
+    /// yet.
+    #[instrument(level = "trace", skip(self))]
+    pub fn scheduled_blocks(&self) -> Vec<ScheduledBlock> {
+        self.state().scheduled_blocks().to_vec()
+    }
+
+    /// Proposes the scheduled blocks whose height has been reached, in the order they were
+    /// scheduled, and returns their certificates.
+    ///
+    /// Blocks that are not due yet stay scheduled. If committing a block fails or we have to
+    /// wait for a round timeout, that block and the remaining due ones stay scheduled too.
+    #[instrument(level = "trace")]
+    pub async fn submit_due_blocks(
+        &self,
+    ) -> Result<ClientOutcome<Vec<ConfirmedBlockCertificate>>, ChainClientError> {
+        let mut certificates = Vec::new();
+        loop {
+            let next_block_height = self.next_block_height();
+            let Some(scheduled) = self.state_mut().take_due_scheduled_block(next_block_height)
+            else {
+                return Ok(ClientOutcome::Committed(certificates));
+            };
+            match self
+                .execute_operations(scheduled.operations.clone(), vec![])
+                .await
+            {
+                Ok(ClientOutcome::Committed(certificate)) => certificates.push(certificate),
+                Ok(ClientOutcome::WaitForTimeout(timeout)) => {
+                    self.state_mut().reschedule_block(scheduled);
+                    return Ok(ClientOutcome::WaitForTimeout(timeout));
+                }
+                Err(error) => {
+                    self.state_mut().reschedule_block(scheduled);
+                    return Err(error);
+                }
+            }
+        }
+    }
+
     /// Verify if a data blob is readable from storage.
     // TODO(#2490): Consider removing or renaming this.
     #[instrument(level = "trace")]
@@ -1632,6 +2823,24 @@ where
         .await
     }
 
+    /// Claims money from the chain balance of a remote chain that we co-own, and sends it
+    /// back to this chain.
+    #[instrument(level = "trace")]
+    pub async fn claim_chain_balance(
+        &self,
+        target_id: ChainId,
+        amount: Amount,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        let owner = self.identity().await?;
+        self.execute_operation(Operation::System(SystemOperation::ClaimChainBalance {
+            owner,
+            target_id,
+            recipient: Recipient::chain(self.chain_id),
+            amount,
+        }))
+        .await
+    }
+
     /// Handles the certificate in the local node and the resulting notifications.
     #[instrument(level = "trace", skip(certificate))]
     async fn process_certificate<T: ProcessableCertificate>(
@@ -1640,6 +2849,9 @@ where
     ) -> Result<(), LocalNodeError> {
         let info = self.client.handle_certificate(certificate).await?.info;
         self.update_from_info(&info);
+        if info.chain_id == self.chain_id {
+            self.state_mut().set_synchronized();
+        }
         Ok(())
     }
 
@@ -1757,6 +2969,18 @@ where
         {
             return Ok(());
         };
+        if let Some(next_height) = info.requested_sent_certificate_hashes_next_height {
+            // The validator truncated its response: download the remaining certificates.
+            self.client
+                .try_download_certificates_from(
+                    remote_node,
+                    chain_id,
+                    next_height,
+                    info.next_block_height,
+                    &mut |_, _| {},
+                )
+                .await?;
+        }
         let mut proposals = Vec::new();
         if let Some(proposal) = info.manager.requested_proposed {
             proposals.push(*proposal);
@@ -1997,11 +3221,25 @@ where
         block: ProposedBlock,
         round: Option<u32>,
     ) -> Result<(ExecutedBlock, ChainInfoResponse), ChainClientError> {
-        loop {
-            let result = self
-                .client
+        let (executed_block, response, _) = self
+            .stage_block_execution_with_resources(block, round)
+            .await?;
+        Ok((executed_block, response))
+    }
+
+    /// Attempts to execute the block locally like `stage_block_execution`, and also returns
+    /// the resources used by the execution.
+    #[instrument(level = "trace", skip(block))]
+    async fn stage_block_execution_with_resources(
+        &self,
+        block: ProposedBlock,
+        round: Option<u32>,
+    ) -> Result<(ExecutedBlock, ChainInfoResponse, ResourceTracker), ChainClientError> {
+        loop {
+            let result = self
+                .client
                 .local_node
-                .stage_block_execution(block.clone(), round)
+                .stage_block_execution_with_resources(block.clone(), round)
                 .await;
             if let Err(LocalNodeError::BlobsNotFound(blob_ids)) = &result {
                 self.receive_certificates_for_blobs(blob_ids.clone())
@@ -2019,22 +3257,93 @@ where
         operations: Vec<Operation>,
         blobs: Vec<Blob>,
     ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operations_with_committee(operations, blobs, None)
+            .await
+    }
+
+    /// Executes a list of operations like `execute_operations`, but only lets `committee`
+    /// certify the block, as the committee of `epoch`.
+    ///
+    /// If the chain moves to a new epoch before the block is certified, e.g. during a committee
+    /// handover, this fails with [`ChainClientError::CommitteeChanged`] instead of proposing the
+    /// block to the new committee, even if the new committee has the same members.
+    #[instrument(level = "trace", skip(operations, blobs, committee))]
+    pub async fn propose_with_committee(
+        &self,
+        operations: Vec<Operation>,
+        blobs: Vec<Blob>,
+        epoch: Epoch,
+        committee: Committee,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operations_with_committee(operations, blobs, Some((epoch, &committee)))
+            .await
+    }
+
+    /// Executes a list of operations, retrying after conflicting blocks. If `committee` is not
+    /// `None`, only that committee may certify the blocks.
+    async fn execute_operations_with_committee(
+        &self,
+        operations: Vec<Operation>,
+        blobs: Vec<Blob>,
+        committee: Option<(Epoch, &Committee)>,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operations_as(
+            None,
+            committee,
+            None,
+            || future::ready(Ok(operations.clone())),
+            blobs,
+        )
+        .await
+    }
+
+    /// Executes a block with the operations returned by `make_operations`, retrying after
+    /// conflicting blocks. The operations are created again for every attempt, so that they
+    /// can depend on the latest state of the chain.
+    ///
+    /// The arguments `owner`, `committee` and `idempotency` are passed to `execute_block_as`.
+    /// If `idempotency` is not `None` and the conflicting block is the one recorded for its key,
+    /// its certificate is returned instead of retrying.
+    async fn execute_operations_as<F, Fut>(
+        &self,
+        owner: Option<Owner>,
+        committee: Option<(Epoch, &Committee)>,
+        idempotency: Option<(Uuid, CryptoHash)>,
+        mut make_operations: F,
+        blobs: Vec<Blob>,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<Vec<Operation>, ChainClientError>>,
+    {
         loop {
+            let operations = make_operations().await?;
             // TODO(#2066): Remove boxing once the call-stack is shallower
-            match Box::pin(self.execute_block(operations.clone(), blobs.clone())).await? {
+            let execute_block =
+                self.execute_block_as(owner, committee, idempotency, operations, blobs.clone());
+            let certificate = match Box::pin(execute_block).await? {
                 ExecuteBlockOutcome::Executed(certificate) => {
                     return Ok(ClientOutcome::Committed(certificate));
                 }
                 ExecuteBlockOutcome::WaitForTimeout(timeout) => {
                     return Ok(ClientOutcome::WaitForTimeout(timeout));
                 }
-                ExecuteBlockOutcome::Conflict(certificate) => {
-                    info!(
-                        height = %certificate.block().header.height,
-                        "Another block was committed; retrying."
-                    );
-                }
+                ExecuteBlockOutcome::Conflict(certificate) => certificate,
             };
+            if let Some((key, _)) = idempotency {
+                let record = self
+                    .client
+                    .storage
+                    .read_idempotency_record(self.chain_id, key.as_u128())
+                    .await?;
+                if record.is_some_and(|record| record.block_hash == certificate.hash()) {
+                    return Ok(ClientOutcome::Committed(certificate));
+                }
+            }
+            info!(
+                height = %certificate.block().header.height,
+                "Another block was committed; retrying."
+            );
         }
     }
 
@@ -2047,43 +3356,264 @@ where
         self.execute_operations(vec![operation], vec![]).await
     }
 
+    /// Adds an operation to the queue of this chain, to be executed in order by
+    /// [`Self::process_operation_queue`].
+    ///
+    /// Concurrent callers should each enqueue their operation, call
+    /// [`Self::process_operation_queue`] and then await the returned receiver, which yields the
+    /// certificate of the block that executed the operation, or the error that prevented it. The
+    /// receiver is closed without a result if the operation is removed by
+    /// [`Self::cancel_operation_queue`].
+    pub fn enqueue_operation(
+        &self,
+        operation: Operation,
+    ) -> oneshot::Receiver<Result<ConfirmedBlockCertificate, ChainClientError>> {
+        let (sender, receiver) = oneshot::channel();
+        self.state_mut()
+            .operation_queue_mut()
+            .push_back(QueuedOperation { operation, sender });
+        receiver
+    }
+
+    /// Executes the queued operations in order until the queue is empty, in blocks of at most
+    /// [`ChainClientOptions::max_queued_operations_per_block`] operations.
+    ///
+    /// Only one caller at a time processes the queue; the others wait for it and then find the
+    /// queue empty. Operations are only removed from the queue once their outcome is known: if
+    /// the returned future is dropped, the next call first commits their pending block, if any,
+    /// and otherwise executes them again. If a block with several operations fails, these
+    /// operations are executed again one per block, so that each caller receives the outcome of
+    /// its own operation. If we have to wait for a round timeout, the remaining operations stay
+    /// queued and the timeout is returned. If the block could not be committed for another
+    /// reason than its execution failing locally, e.g. because the validators are unreachable,
+    /// it stays pending, its operations stay queued and the error is returned.
+    #[instrument(level = "trace")]
+    pub async fn process_operation_queue(&self) -> Result<ClientOutcome<()>, ChainClientError> {
+        let mutex = self.state().operation_queue_mutex();
+        let _guard = mutex.lock_owned().await;
+        if let ClientOutcome::WaitForTimeout(timeout) = self.resume_operations_in_flight().await? {
+            return Ok(ClientOutcome::WaitForTimeout(timeout));
+        }
+        let max_per_block = self.options.max_queued_operations_per_block.max(1);
+        // The number of operations from a failed block that are still to be executed alone.
+        let mut isolated = 0;
+        loop {
+            let count = if isolated > 0 { 1 } else { max_per_block };
+            let operations = self.start_operations_in_flight(count);
+            if operations.is_empty() {
+                return Ok(ClientOutcome::Committed(()));
+            }
+            let count = operations.len();
+            match self.execute_operations(operations, vec![]).await {
+                Ok(ClientOutcome::Committed(certificate)) => {
+                    for queued in self.finish_operations_in_flight() {
+                        let _ = queued.sender.send(Ok(certificate.clone()));
+                    }
+                }
+                // The operations stay in flight, so that their pending block is committed first
+                // next time.
+                Ok(ClientOutcome::WaitForTimeout(timeout)) => {
+                    return Ok(ClientOutcome::WaitForTimeout(timeout));
+                }
+                // The validators may still accept the pending block, so it is committed first
+                // next time, like after a timeout.
+                Err(error) if !error.is_local_block_failure() => return Err(error),
+                Err(error) => {
+                    // Don't let the failed block hold up the operations behind it.
+                    self.clear_pending_proposal();
+                    if count > 1 {
+                        warn!(
+                            %error,
+                            "Failed to execute a block of queued operations; retrying them one by one"
+                        );
+                        self.state_mut().set_operations_in_flight(0);
+                        isolated = count;
+                        continue;
+                    }
+                    if let Some(queued) = self.finish_operations_in_flight().into_iter().next() {
+                        let _ = queued.sender.send(Err(error));
+                    }
+                }
+            }
+            isolated = isolated.saturating_sub(1);
+        }
+    }
+
+    /// Marks up to `count` operations at the front of the queue as in flight, and returns them.
+    fn start_operations_in_flight(&self, count: usize) -> Vec<Operation> {
+        let mut state = self.state_mut();
+        let count = count.min(state.operation_queue_mut().len());
+        state.set_operations_in_flight(count);
+        state
+            .operation_queue_mut()
+            .iter()
+            .take(count)
+            .map(|queued| queued.operation.clone())
+            .collect()
+    }
+
+    /// Removes the operations in flight from the queue, and returns them so that their outcome
+    /// can be reported.
+    fn finish_operations_in_flight(&self) -> Vec<QueuedOperation> {
+        let mut state = self.state_mut();
+        let count = state.operations_in_flight();
+        state.set_operations_in_flight(0);
+        state.operation_queue_mut().drain(..count).collect()
+    }
+
+    /// Settles the operations left in flight by a call to [`Self::process_operation_queue`]
+    /// whose future was dropped.
+    ///
+    /// If their block is still pending, it is committed first. If it was committed, the
+    /// operations are reported with its certificate; otherwise they are executed again.
+    async fn resume_operations_in_flight(&self) -> Result<ClientOutcome<()>, ChainClientError> {
+        let (operations, pending, block_hash) = {
+            let mut state = self.state_mut();
+            let count = state.operations_in_flight();
+            if count == 0 {
+                return Ok(ClientOutcome::Committed(()));
+            }
+            let operations = state
+                .operation_queue_mut()
+                .iter()
+                .take(count)
+                .map(|queued| queued.operation.clone())
+                .collect::<Vec<_>>();
+            let pending = state
+                .pending_proposal()
+                .as_ref()
+                .is_some_and(|proposal| proposal.block.operations == operations);
+            (operations, pending, state.block_hash())
+        };
+        let certificate = if pending {
+            match self.process_pending_block().await {
+                Ok(ClientOutcome::Committed(certificate)) => certificate,
+                Ok(ClientOutcome::WaitForTimeout(timeout)) => {
+                    return Ok(ClientOutcome::WaitForTimeout(timeout));
+                }
+                Err(error) if !error.is_local_block_failure() => return Err(error),
+                Err(error) => {
+                    warn!(%error, "Failed to commit the pending block of queued operations");
+                    self.clear_pending_proposal();
+                    None
+                }
+            }
+        } else if let Some(hash) = block_hash {
+            // The block may have been committed before the future was dropped.
+            self.client.storage.read_certificate(hash).await.ok()
+        } else {
+            None
+        };
+        match certificate {
+            Some(certificate) if certificate.block().body.operations == operations => {
+                for queued in self.finish_operations_in_flight() {
+                    let _ = queued.sender.send(Ok(certificate.clone()));
+                }
+            }
+            _ => self.state_mut().set_operations_in_flight(0),
+        }
+        Ok(ClientOutcome::Committed(()))
+    }
+
+    /// Removes all queued operations without executing them, closing their receivers, and
+    /// returns how many were removed. Operations in a block that is being executed are not
+    /// affected.
+    pub fn cancel_operation_queue(&self) -> usize {
+        let mut state = self.state_mut();
+        let in_flight = state.operations_in_flight();
+        let queue = state.operation_queue_mut();
+        let count = queue.len().saturating_sub(in_flight);
+        queue.truncate(in_flight);
+        count
+    }
+
     /// Executes a new block.
     ///
     /// This must be preceded by a call to `prepare_chain()`.
+    ///
+    /// This is cancellation-safe: The new block is stored as the pending proposal before it is
+    /// sent to any validator, and the next block height only advances once the certificate has
+    /// been processed by the local node. If the returned future is dropped, the pending
+    /// proposal can be committed later with `process_pending_block`.
     #[instrument(level = "trace", skip(operations, blobs))]
     async fn execute_block(
         &self,
         operations: Vec<Operation>,
         blobs: Vec<Blob>,
+    ) -> Result<ExecuteBlockOutcome, ChainClientError> {
+        self.execute_block_as(None, None, None, operations, blobs)
+            .await
+    }
+
+    /// Executes a new block like `execute_block`, proposed by `owner` if it is not `None`.
+    /// Otherwise, the block is proposed by our [`ChainClient::identity`]. If `committee` is not
+    /// `None`, only that committee may certify the block. If `idempotency` is not `None`, the
+    /// new block is recorded for its key, along with the hash of the requested operations,
+    /// before being proposed.
+    #[instrument(level = "trace", skip(committee, operations, blobs))]
+    async fn execute_block_as(
+        &self,
+        owner: Option<Owner>,
+        committee: Option<(Epoch, &Committee)>,
+        idempotency: Option<(Uuid, CryptoHash)>,
+        operations: Vec<Operation>,
+        blobs: Vec<Blob>,
     ) -> Result<ExecuteBlockOutcome, ChainClientError> {
         #[cfg(with_metrics)]
         let _latency = metrics::EXECUTE_BLOCK_LATENCY.measure_latency();
 
+        if !self.client.allow_inactive_chains {
+            let ownership = self.chain_info().await?.manager.ownership;
+            ensure!(
+                ownership.is_active(),
+                LocalNodeError::InactiveChain(self.chain_id)
+            );
+        }
+
         let mutex = self.state().client_mutex();
         let _guard = mutex.lock_owned().await;
-        match self.process_pending_block_without_prepare().await? {
+        match self
+            .process_pending_block_without_prepare(committee)
+            .await?
+        {
             ClientOutcome::Committed(Some(certificate)) => {
-                return Ok(ExecuteBlockOutcome::Conflict(certificate))
+                self.client.metrics.block_conflict();
+                return Ok(ExecuteBlockOutcome::Conflict(certificate));
             }
             ClientOutcome::WaitForTimeout(timeout) => {
                 return Ok(ExecuteBlockOutcome::WaitForTimeout(timeout))
             }
             ClientOutcome::Committed(None) => {}
         }
+        // Don't create a pending block that the given committee cannot certify.
+        self.committee_for_proposal(committee).await?;
 
         let incoming_bundles = self.pending_message_bundles().await?;
-        let identity = self.identity().await?;
+        let identity = match owner {
+            Some(owner) => {
+                ensure!(
+                    self.our_public_key(&owner).await?.is_some(),
+                    ChainClientError::CannotFindKeyForChain(self.chain_id)
+                );
+                owner
+            }
+            None => self.identity().await?,
+        };
         let confirmed_value = self
-            .new_pending_block(incoming_bundles, operations, blobs, identity)
+            .new_pending_block(incoming_bundles, operations, blobs, identity, idempotency)
             .await?;
 
-        match self.process_pending_block_without_prepare().await? {
+        match self
+            .process_pending_block_without_prepare(committee)
+            .await?
+        {
             ClientOutcome::Committed(Some(certificate))
                 if certificate.block() == confirmed_value.inner().block() =>
             {
                 Ok(ExecuteBlockOutcome::Executed(certificate))
             }
             ClientOutcome::Committed(Some(certificate)) => {
+                self.client.metrics.block_conflict();
                 Ok(ExecuteBlockOutcome::Conflict(certificate))
             }
             // Should be unreachable: We did set a pending block.
@@ -2099,6 +3629,10 @@ where
     /// Creates a new pending block and handles the proposal in the local node.
     /// Next time `process_pending_block_without_prepare` is called, this block will be proposed
     /// to the validators.
+    ///
+    /// If `idempotency` is not `None`, the hash of the block is recorded for its key in storage,
+    /// along with the given hash of the operations, before the block becomes pending. Records
+    /// older than [`IDEMPOTENCY_RECORD_RETENTION`] are deleted at the same time.
     #[instrument(level = "trace", skip(incoming_bundles, operations, blobs))]
     async fn new_pending_block(
         &self,
@@ -2106,32 +3640,18 @@ where
         operations: Vec<Operation>,
         blobs: Vec<Blob>,
         identity: Owner,
+        idempotency: Option<(Uuid, CryptoHash)>,
     ) -> Result<Hashed<ConfirmedBlock>, ChainClientError> {
-        let (previous_block_hash, height, timestamp) = {
-            let state = self.state();
-            ensure!(
-                state.pending_proposal().is_none(),
-                ChainClientError::BlockProposalError(
-                    "Client state already has a pending block; \
-                    use the `linera retry-pending-block` command to commit that first"
-                )
-            );
-            (
-                state.block_hash(),
-                state.next_block_height(),
-                self.next_timestamp(&incoming_bundles, state.timestamp()),
+        ensure!(
+            self.state().pending_proposal().is_none(),
+            ChainClientError::BlockProposalError(
+                "Client state already has a pending block; \
+                use the `linera retry-pending-block` command to commit that first"
             )
-        };
-        let block = ProposedBlock {
-            epoch: self.epoch().await?,
-            chain_id: self.chain_id,
-            incoming_bundles,
-            operations,
-            previous_block_hash,
-            height,
-            authenticated_signer: Some(identity),
-            timestamp,
-        };
+        );
+        let block = self
+            .new_proposed_block(incoming_bundles, operations, identity)
+            .await?;
         // Make sure every incoming message succeeds and otherwise remove them.
         // Also, compute the final certified hash while we're at it.
 
@@ -2141,7 +3661,9 @@ where
         // Accessing the round number in single-leader rounds where we are not the leader
         // is not currently supported.
         let published_blob_ids = block.published_blob_ids();
-        let round = match Self::round_for_new_proposal(&info, &identity, &block, true)? {
+        let next_round = self.state().next_round();
+        let round = match Self::round_for_new_proposal(&info, next_round, &identity, &block, true)?
+        {
             Either::Left(round) => round.multi_leader(),
             Either::Right(_) => None,
         };
@@ -2160,8 +3682,55 @@ where
                     .with_execution_context(ChainExecutionContext::Block)?;
             }
         }
-        self.state_mut().set_pending_proposal(block.clone(), blobs);
-        Ok(Hashed::new(ConfirmedBlock::new(executed_block)))
+        let block = block.clone();
+        let confirmed_value = Hashed::new(ConfirmedBlock::new(executed_block));
+        if let Some((key, operations_hash)) = idempotency {
+            let storage = &self.client.storage;
+            let timestamp = storage.clock().current_time();
+            let retention = TimeDelta::from_duration(IDEMPOTENCY_RECORD_RETENTION);
+            storage
+                .prune_idempotency_records(self.chain_id, timestamp.saturating_sub(retention))
+                .await?;
+            let record = IdempotencyRecord {
+                operations_hash,
+                block_hash: confirmed_value.hash(),
+                timestamp,
+            };
+            storage
+                .write_idempotency_record(self.chain_id, key.as_u128(), record)
+                .await?;
+        }
+        self.state_mut()
+            .set_pending_proposal(block, blobs, Vec::new());
+        Ok(confirmed_value)
+    }
+
+    /// Returns a new block at the next height with the given messages and operations, signed
+    /// by `identity`.
+    async fn new_proposed_block(
+        &self,
+        incoming_bundles: Vec<IncomingBundle>,
+        operations: Vec<Operation>,
+        identity: Owner,
+    ) -> Result<ProposedBlock, ChainClientError> {
+        let (previous_block_hash, height, timestamp) = {
+            let state = self.state();
+            (
+                state.block_hash(),
+                state.next_block_height(),
+                self.next_timestamp(&incoming_bundles, state.timestamp()),
+            )
+        };
+        Ok(ProposedBlock {
+            epoch: self.epoch().await?,
+            chain_id: self.chain_id,
+            incoming_bundles,
+            operations,
+            previous_block_hash,
+            height,
+            authenticated_signer: Some(identity),
+            timestamp,
+        })
     }
 
     /// Returns a suitable timestamp for the next block.
@@ -2261,6 +3830,104 @@ where
         Ok(balance)
     }
 
+    /// Obtains the balance of the chain account of any chain, including chains that this client
+    /// doesn't own.
+    ///
+    /// The chain's blocks are downloaded from the validators first. The result is the balance
+    /// after the chain's latest block: incoming messages that no block has processed yet are
+    /// not included.
+    #[instrument(level = "trace")]
+    pub async fn remote_balance(&self, chain_id: ChainId) -> Result<Amount, ChainClientError> {
+        let validators = self.validator_nodes().await?;
+        let info = self.synchronize_chain_state(&validators, chain_id).await?;
+        Ok(info.chain_balance)
+    }
+
+    /// Returns the status of this chain's outboxes on each validator, i.e. the blocks whose
+    /// messages the validator has not delivered to the recipients yet, or the error returned
+    /// by the validator. Validators return a limited number of outboxes per response, so
+    /// they are queried page by page.
+    #[instrument(level = "trace")]
+    pub async fn outbox_status(
+        &self,
+    ) -> Result<
+        BTreeMap<ValidatorName, Result<BTreeMap<Target, OutboxStatus>, NodeError>>,
+        ChainClientError,
+    > {
+        let validators = self.validator_nodes().await?;
+        let results = future::join_all(validators.iter().map(|remote_node| async move {
+            let mut outboxes = BTreeMap::new();
+            let result = loop {
+                let query = ChainInfoQuery::new(self.chain_id).with_outboxes_page(outboxes.len());
+                match remote_node.handle_chain_info_query(query).await {
+                    Ok(info) => {
+                        let is_empty = info.requested_outboxes.is_empty();
+                        outboxes.extend(info.requested_outboxes);
+                        if !info.has_more_outboxes || is_empty {
+                            break Ok(outboxes);
+                        }
+                    }
+                    Err(error) => break Err(error),
+                }
+            };
+            (remote_node.name, result)
+        }))
+        .await;
+        Ok(results.into_iter().collect())
+    }
+
+    /// Obtains the balance of the chain account that can still be spent: the result of
+    /// [`ChainClient::query_balance`], minus the amounts that the pending proposal, if any,
+    /// transfers from the chain account or gives to new chains.
+    ///
+    /// Returns zero if the pending proposal spends more than the balance, and an
+    /// [`ArithmeticError`] if the amounts it spends overflow.
+    #[instrument(level = "trace")]
+    pub async fn spendable_balance(&self) -> Result<Amount, ChainClientError> {
+        let balance = self.query_balance().await?;
+        let pending_amount = self.pending_spent_amount(None)?;
+        Ok(balance.saturating_sub(pending_amount))
+    }
+
+    /// Obtains the balance of the `owner`'s account that can still be spent: the result of
+    /// [`ChainClient::query_owner_balance`], minus the amounts that the pending proposal, if
+    /// any, transfers from that account.
+    ///
+    /// Returns zero if the pending proposal spends more than the balance, and an
+    /// [`ArithmeticError`] if the amounts it spends overflow.
+    #[instrument(level = "trace")]
+    pub async fn spendable_owner_balance(&self, owner: Owner) -> Result<Amount, ChainClientError> {
+        let balance = self.query_owner_balance(AccountOwner::User(owner)).await?;
+        let pending_amount = self.pending_spent_amount(Some(owner))?;
+        Ok(balance.saturating_sub(pending_amount))
+    }
+
+    /// Returns the total amount that the pending proposal, if any, takes from the account of
+    /// `owner`, or from the chain account if `owner` is `None`. New chains are always funded
+    /// from the chain account.
+    fn pending_spent_amount(&self, owner: Option<Owner>) -> Result<Amount, ArithmeticError> {
+        let state = self.state();
+        let Some(pending_proposal) = state.pending_proposal() else {
+            return Ok(Amount::ZERO);
+        };
+        let mut pending_amount = Amount::ZERO;
+        for operation in &pending_proposal.block.operations {
+            let amount = match operation {
+                Operation::System(SystemOperation::Transfer {
+                    owner: source,
+                    amount,
+                    ..
+                }) if *source == owner => amount,
+                Operation::System(SystemOperation::OpenChain(OpenChainConfig {
+                    balance, ..
+                })) if owner.is_none() => balance,
+                _ => continue,
+            };
+            pending_amount.try_add_assign(*amount)?;
+        }
+        Ok(pending_amount)
+    }
+
     /// Obtains the local balance of an account after staging the execution of incoming messages in
     /// a new block.
     ///
@@ -2339,6 +4006,67 @@ where
         }
     }
 
+    /// Estimates the cost of executing the given operations in a new block of this chain,
+    /// without proposing it or persisting any changes.
+    ///
+    /// Incoming messages are not included. Does not attempt to synchronize with validators.
+    #[instrument(level = "trace", skip(operations))]
+    pub async fn estimate_operation_cost(
+        &self,
+        operations: Vec<Operation>,
+    ) -> Result<FuelEstimate, ChainClientError> {
+        let block = self.make_simulated_block(Vec::new(), operations).await?;
+        let (_, _, resources) = self
+            .stage_block_execution_with_resources(block, None)
+            .await?;
+        Ok(FuelEstimate {
+            fuel: resources.fuel,
+            messages: resources.messages,
+        })
+    }
+
+    /// Executes the given operations in a new block of this chain, without proposing it or
+    /// persisting any changes, and returns the outgoing messages of each operation.
+    ///
+    /// Incoming messages are not included. Does not attempt to synchronize with validators.
+    #[instrument(level = "trace", skip(operations))]
+    pub async fn simulate_operations(
+        &self,
+        operations: Vec<Operation>,
+    ) -> Result<Vec<Vec<OutgoingMessage>>, ChainClientError> {
+        let block = self.make_simulated_block(Vec::new(), operations).await?;
+        let (executed_block, _) = self.stage_block_execution(block, None).await?;
+        Ok(executed_block.outcome.messages)
+    }
+
+    /// Creates a block with the given incoming bundles and operations at the next height of
+    /// this chain, for local simulation.
+    async fn make_simulated_block(
+        &self,
+        incoming_bundles: Vec<IncomingBundle>,
+        operations: Vec<Operation>,
+    ) -> Result<ProposedBlock, ChainClientError> {
+        let identity = self.identity().await?;
+        let (previous_block_hash, height, timestamp) = {
+            let state = self.state();
+            (
+                state.block_hash(),
+                state.next_block_height(),
+                self.next_timestamp(&incoming_bundles, state.timestamp()),
+            )
+        };
+        Ok(ProposedBlock {
+            epoch: self.epoch().await?,
+            chain_id: self.chain_id,
+            incoming_bundles,
+            operations,
+            previous_block_hash,
+            height,
+            authenticated_signer: Some(identity),
+            timestamp,
+        })
+    }
+
     /// Reads the local balance of the chain account.
     ///
     /// Does not process the inbox or attempt to synchronize with validators.
@@ -2348,6 +4076,46 @@ where
         Ok(balance)
     }
 
+    /// Reads the local balance of the chain account, like [`ChainClient::local_balance`].
+    ///
+    /// If the local node is behind the height known to the wallet, this first synchronizes
+    /// the chain with the validators once. Fails with
+    /// [`ChainClientError::WalletSynchronizationError`] if it is still behind after that.
+    #[instrument(level = "trace")]
+    pub async fn local_balance_auto_sync(&self) -> Result<Amount, ChainClientError> {
+        match self.local_balance().await {
+            Err(ChainClientError::WalletSynchronizationError) => {
+                self.prepare_chain().await?;
+                self.local_balance().await
+            }
+            result => result,
+        }
+    }
+
+    /// Returns the local balance of the chain account, reusing the last one that was read
+    /// through this method if it is younger than `max_age`.
+    ///
+    /// The cached balance is discarded whenever the chain advances to a new block, but not
+    /// when new messages arrive in the inbox.
+    #[instrument(level = "trace")]
+    pub async fn balance_with_max_age(
+        &self,
+        max_age: Duration,
+    ) -> Result<Amount, ChainClientError> {
+        let cached_balance = self.state().cached_balance(max_age);
+        if let Some(balance) = cached_balance {
+            return Ok(balance);
+        }
+        let next_block_height = self.next_block_height();
+        let balance = self.local_balance().await?;
+        let mut state = self.state_mut();
+        // Don't cache a balance if the chain advanced while we were computing it.
+        if state.next_block_height() == next_block_height {
+            state.set_cached_balance(balance);
+        }
+        Ok(balance)
+    }
+
     /// Reads the local balance of a user account.
     ///
     /// Does not process the inbox or attempt to synchronize with validators.
@@ -2417,6 +4185,31 @@ where
             .await
     }
 
+    /// Sends tokens to a chain, like [`ChainClient::transfer_to_account`], and returns the
+    /// balance of the debited account after the transfer.
+    ///
+    /// The balance is read from the local node, which already executed the new block, so
+    /// this doesn't need another round-trip to the validators.
+    #[instrument(level = "trace")]
+    pub async fn transfer_to_account_and_balance(
+        &self,
+        owner: Option<Owner>,
+        amount: Amount,
+        account: Account,
+    ) -> Result<ClientOutcome<(ConfirmedBlockCertificate, Amount)>, ChainClientError> {
+        let certificate = match self.transfer_to_account(owner, amount, account).await? {
+            ClientOutcome::Committed(certificate) => certificate,
+            ClientOutcome::WaitForTimeout(timeout) => {
+                return Ok(ClientOutcome::WaitForTimeout(timeout))
+            }
+        };
+        let balance = match owner {
+            None => self.local_balance().await?,
+            Some(owner) => self.local_owner_balance(AccountOwner::User(owner)).await?,
+        };
+        Ok(ClientOutcome::Committed((certificate, balance)))
+    }
+
     /// Burns tokens.
     #[instrument(level = "trace")]
     pub async fn burn(
@@ -2427,13 +4220,48 @@ where
         self.transfer(owner, amount, Recipient::Burn).await
     }
 
+    /// Burns tokens, unless that would destroy more than `max_fraction_bps` basis points
+    /// (hundredths of a percent) of the account's balance.
+    ///
+    /// The balance is read locally, without processing the inbox.
+    #[instrument(level = "trace")]
+    pub async fn burn_checked(
+        &self,
+        owner: Option<Owner>,
+        amount: Amount,
+        max_fraction_bps: u16,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        let balance = match owner {
+            None => self.local_balance().await?,
+            Some(owner) => self.local_owner_balance(AccountOwner::User(owner)).await?,
+        };
+        let bps = u128::from(max_fraction_bps.min(10_000));
+        let attos = u128::from(balance);
+        let limit = Amount::from_attos(attos / 10_000 * bps + attos % 10_000 * bps / 10_000);
+        ensure!(
+            amount <= limit,
+            ChainClientError::BurnExceedsSafetyLimit {
+                amount,
+                balance,
+                max_fraction_bps,
+            }
+        );
+        self.burn(owner, amount).await
+    }
+
     /// Attempts to synchronize chains that have sent us messages and populate our local
     /// inbox.
     ///
     /// To create a block that actually executes the messages in the inbox,
     /// `process_inbox` must be called separately.
+    ///
+    /// For chains of which we are the only owner, this does nothing within
+    /// [`ChainClientOptions::trust_local_state_for`] of the last synchronization.
     #[instrument(level = "trace")]
     pub async fn synchronize_from_validators(&self) -> Result<Box<ChainInfo>, ChainClientError> {
+        if let Some(info) = self.trusted_local_info().await? {
+            return Ok(info);
+        }
         if self.chain_id != self.admin_id {
             // Synchronize the state of the admin chain from the network.
             let local_committee = self.local_committee().await?;
@@ -2445,19 +4273,155 @@ where
         Ok(info)
     }
 
-    /// Processes the last pending block
+    /// Synchronizes the chain and its inbox only from the given validators, and returns the
+    /// local balance of the chain account.
+    ///
+    /// This avoids waiting for distant validators, but the result is only as complete as what
+    /// these validators know: blocks and messages that only reached the other validators are
+    /// missed. The given validators of the local committee must have enough votes for a
+    /// quorum, otherwise this fails with [`ChainClientError::ValidatorSubsetBelowQuorum`].
+    #[instrument(level = "trace")]
+    pub async fn synchronize_balance_from(
+        &self,
+        validators: &[ValidatorName],
+    ) -> Result<Amount, ChainClientError> {
+        let local_committee = self.local_committee().await?;
+        let validators = validators.iter().collect::<BTreeSet<_>>();
+        let weight = validators
+            .iter()
+            .map(|name| local_committee.weight(name))
+            .sum::<u64>();
+        let quorum_threshold = local_committee.quorum_threshold();
+        ensure!(
+            weight >= quorum_threshold,
+            ChainClientError::ValidatorSubsetBelowQuorum {
+                weight,
+                quorum_threshold,
+            }
+        );
+        let nodes = self
+            .make_nodes(&local_committee)?
+            .into_iter()
+            .filter(|node| validators.contains(&node.name))
+            .collect::<Vec<_>>();
+
+        let mut info = self
+            .client
+            .download_certificates(&nodes, self.chain_id, self.next_block_height())
+            .await?;
+        if self.state().has_other_owners(&info.manager.ownership) {
+            info = self.synchronize_chain_state(&nodes, self.chain_id).await?;
+        }
+        self.update_from_info(&info);
+        self.find_received_certificates_from(&local_committee, &nodes)
+            .await?;
+        self.local_balance().await
+    }
+
+    /// Synchronizes the chain with the validators like
+    /// [`ChainClient::synchronize_from_validators`], even if the local state would still be
+    /// trusted according to [`ChainClientOptions::trust_local_state_for`].
+    #[instrument(level = "trace")]
+    pub async fn force_synchronize(&self) -> Result<Box<ChainInfo>, ChainClientError> {
+        self.state_mut().clear_synchronized();
+        self.synchronize_from_validators().await
+    }
+
+    /// Synchronizes the chain with the validators, like
+    /// [`ChainClient::synchronize_from_validators`], and returns the local balance.
+    ///
+    /// While downloading the chain's missing blocks, calls `progress` with the local next block
+    /// height and the highest next block height that validators with the validity threshold
+    /// report, e.g. to display a progress bar.
+    #[instrument(level = "trace", skip(progress))]
+    pub async fn synchronize_with_progress(
+        &self,
+        progress: impl FnMut(BlockHeight, BlockHeight) + Send,
+    ) -> Result<Amount, ChainClientError> {
+        let nodes = self.validator_nodes().await?;
+        let mut target_next_block_height = self.next_block_height();
+        if !nodes.is_empty() {
+            // A single validator could report any height, so only trust the height that
+            // validators with the validity threshold agree the chain has reached.
+            let committee = self.local_committee().await?;
+            let query = ChainInfoQuery::new(self.chain_id);
+            let reports = future::join_all(nodes.iter().map(|remote_node| {
+                let query = query.clone();
+                async move {
+                    let info = remote_node.handle_chain_info_query(query).await.ok()?;
+                    Some((remote_node.name, info.next_block_height))
+                }
+            }))
+            .await;
+            if let Some(height) =
+                committee.highest_value_with_validity(reports.into_iter().flatten())
+            {
+                target_next_block_height = target_next_block_height.max(height);
+            }
+        }
+        self.client
+            .download_certificates_with_progress(
+                &nodes,
+                self.chain_id,
+                target_next_block_height,
+                progress,
+            )
+            .await?;
+        self.synchronize_from_validators().await?;
+        self.local_balance().await
+    }
+
+    /// Processes the last pending block, e.g. to retry a proposal whose future was dropped.
     #[instrument(level = "trace")]
     pub async fn process_pending_block(
         &self,
     ) -> Result<ClientOutcome<Option<ConfirmedBlockCertificate>>, ChainClientError> {
         self.synchronize_from_validators().await?;
-        self.process_pending_block_without_prepare().await
+        self.process_pending_block_without_prepare(None).await
     }
 
-    /// Processes the last pending block. Assumes that the local chain is up to date.
+    /// Processes the last pending block like `process_pending_block`, making up to
+    /// `max_attempts` attempts and waiting for `between` after each unsuccessful one.
+    ///
+    /// Each attempt synchronizes with the validators first, so that it uses the current round
+    /// and committee. An attempt is unsuccessful if we have to wait for a timeout or if the
+    /// validators could not be reached. Returns the number of the successful attempt, starting
+    /// at 1, with the certificate, or `None` if all attempts were unsuccessful.
     #[instrument(level = "trace")]
+    pub async fn process_pending_block_with_retries(
+        &self,
+        max_attempts: usize,
+        between: Duration,
+    ) -> Result<Option<(usize, Option<ConfirmedBlockCertificate>)>, ChainClientError> {
+        for attempt in 1..=max_attempts {
+            match self.process_pending_block().await {
+                Ok(ClientOutcome::Committed(certificate)) => {
+                    return Ok(Some((attempt, certificate)));
+                }
+                Ok(ClientOutcome::WaitForTimeout(timeout)) => {
+                    debug!(
+                        "Attempt {attempt} to process the pending block must wait for {timeout:?}"
+                    );
+                }
+                Err(error @ ChainClientError::CommunicationError(_)) if attempt < max_attempts => {
+                    warn!("Attempt {attempt} to process the pending block failed: {error}");
+                }
+                Err(error) => return Err(error),
+            }
+            if attempt < max_attempts {
+                linera_base::time::timer::sleep(between).await;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Processes the last pending block. Assumes that the local chain is up to date.
+    ///
+    /// If `committee` is not `None`, only that committee may certify the block.
+    #[instrument(level = "trace", skip(committee))]
     async fn process_pending_block_without_prepare(
         &self,
+        committee: Option<(Epoch, &Committee)>,
     ) -> Result<ClientOutcome<Option<ConfirmedBlockCertificate>>, ChainClientError> {
         let info = self.request_leader_timeout_if_needed().await?;
 
@@ -2465,13 +4429,19 @@ where
         if info.manager.has_locking_block_in_current_round()
             && !info.manager.current_round.is_fast()
         {
-            return self.finalize_locking_block(info).await;
+            return self.finalize_locking_block(info, committee).await;
         }
-        let identity = self.identity().await?;
 
         let local_node = &self.client.local_node;
         // Otherwise we have to re-propose the highest validated block, if there is one.
-        let pending_proposal = self.state().pending_proposal().clone();
+        let (pending_proposal, next_round) = {
+            let state = self.state();
+            (state.pending_proposal().clone(), state.next_round())
+        };
+        if info.manager.requested_locking.is_none() && pending_proposal.is_none() {
+            return Ok(ClientOutcome::Committed(None)); // Nothing to do.
+        }
+        let identity = self.identity().await?;
         let (executed_block, blobs) = if let Some(locking) = &info.manager.requested_locking {
             let (executed_block, blob_ids) = match &**locking {
                 LockingBlock::Regular(certificate) => (
@@ -2489,23 +4459,25 @@ where
                 .await?
                 .ok_or_else(|| ChainClientError::InternalError("Missing local locking blobs"))?;
             (executed_block, blobs)
-        } else if let Some(pending_proposal) = pending_proposal {
+        } else if let Some(pending_proposal) = &pending_proposal {
             // Otherwise we are free to propose our own pending block.
             // Use the round number assuming there are oracle responses.
             // Using the round number during execution counts as an oracle.
-            let block = pending_proposal.block;
-            let round = match Self::round_for_new_proposal(&info, &identity, &block, true)? {
-                Either::Left(round) => round.multi_leader(),
-                Either::Right(_) => None,
-            };
+            let block = pending_proposal.block.clone();
+            let round =
+                match Self::round_for_new_proposal(&info, next_round, &identity, &block, true)? {
+                    Either::Left(round) => round.multi_leader(),
+                    Either::Right(_) => None,
+                };
             let executed_block = self.stage_block_execution(block, round).await?.0;
-            (executed_block, pending_proposal.blobs)
+            (executed_block, pending_proposal.blobs.clone())
         } else {
             return Ok(ClientOutcome::Committed(None)); // Nothing to do.
         };
 
         let round = match Self::round_for_new_proposal(
             &info,
+            next_round,
             &identity,
             &executed_block.block,
             executed_block.outcome.has_oracle_responses(),
@@ -2517,19 +4489,34 @@ where
         let already_handled_locally = info
             .manager
             .already_handled_proposal(round, &executed_block.block);
-        let key_pair = self.key_pair().await?;
+        // Keep the approvals of other owners: they don't depend on the round.
+        let cosignatures = Self::known_cosignatures(
+            &executed_block.block,
+            pending_proposal.as_ref(),
+            info.manager.requested_proposed.as_deref(),
+        );
         // Create the final block proposal.
-        let proposal = if let Some(locking) = info.manager.requested_locking {
-            Box::new(match *locking {
-                LockingBlock::Regular(cert) => BlockProposal::new_retry(round, cert, &key_pair),
-                LockingBlock::Fast(proposal) => {
-                    BlockProposal::new_initial(round, proposal.content.block, &key_pair)
+        let (content, validated_block_certificate) =
+            if let Some(locking) = info.manager.requested_locking {
+                match *locking {
+                    LockingBlock::Regular(cert) => {
+                        let (content, lite_cert) = ProposalContent::new_retry(round, cert);
+                        (content, Some(lite_cert))
+                    }
+                    LockingBlock::Fast(proposal) => (
+                        ProposalContent::new_initial(round, proposal.content.block),
+                        None,
+                    ),
                 }
-            })
-        } else {
-            let block = executed_block.block.clone();
-            Box::new(BlockProposal::new_initial(round, block, &key_pair))
-        };
+            } else {
+                let block = executed_block.block.clone();
+                (ProposalContent::new_initial(round, block), None)
+            };
+        let mut proposal = self
+            .sign_proposal(&identity, content, validated_block_certificate)
+            .await?;
+        proposal.cosignatures = cosignatures;
+        let proposal = Box::new(proposal);
         if !already_handled_locally {
             // Check the final block proposal. This will be cheaper after #1401.
             if let Err(err) = local_node.handle_block_proposal(*proposal.clone()).await {
@@ -2544,9 +4531,44 @@ where
                 }
             }
         }
-        let committee = self.local_committee().await?;
+        let certificate = self
+            .submit_and_finalize(proposal, executed_block, committee)
+            .await?;
+        Ok(ClientOutcome::Committed(Some(certificate)))
+    }
+
+    /// Returns the approvals of other owners for `block` that are known from the pending
+    /// proposal or from the latest proposal handled by the local node.
+    fn known_cosignatures(
+        block: &ProposedBlock,
+        pending_proposal: Option<&PendingProposal>,
+        latest_proposal: Option<&BlockProposal>,
+    ) -> Vec<(AccountPublicKey, AccountSignature)> {
+        pending_proposal
+            .filter(|pending| pending.block == *block)
+            .map(|pending| &pending.cosignatures)
+            .or_else(|| {
+                latest_proposal
+                    .filter(|proposal| proposal.content.block == *block)
+                    .map(|proposal| &proposal.cosignatures)
+            })
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Sends a proposal that was already handled by the local node to the validators, and
+    /// finalizes the block if the proposal was not in the fast round.
+    ///
+    /// If `committee` is not `None`, only that committee may certify the block.
+    async fn submit_and_finalize(
+        &self,
+        proposal: Box<BlockProposal>,
+        executed_block: ExecutedBlock,
+        committee: Option<(Epoch, &Committee)>,
+    ) -> Result<ConfirmedBlockCertificate, ChainClientError> {
+        let committee = self.committee_for_proposal(committee).await?;
         // Send the query to validators.
-        let certificate = if round.is_fast() {
+        let certificate = if proposal.content.round.is_fast() {
             let hashed_value = Hashed::new(ConfirmedBlock::new(executed_block));
             self.submit_block_proposal(&committee, proposal, hashed_value)
                 .await?
@@ -2558,7 +4580,7 @@ where
             self.finalize_block(&committee, certificate).await?
         };
         self.update_validators(Some(&committee)).await?;
-        Ok(ClientOutcome::Committed(Some(certificate)))
+        Ok(certificate)
     }
 
     /// Checks that the current height and hash match the `ChainClientState`. Then requests a
@@ -2578,12 +4600,75 @@ where
         Ok(info)
     }
 
+    /// Returns the round in which the next block proposal will be made, unless there is a
+    /// conflicting proposal in that round.
+    #[instrument(level = "trace")]
+    pub async fn current_round(&self) -> Result<Round, ChainClientError> {
+        let info = self.chain_info_with_manager_values().await?;
+        let next_round = self.state().next_round();
+        Ok(Self::proposal_round(&info, next_round))
+    }
+
+    /// Moves on to the next round, so that the pending proposal is retried there by the next
+    /// call to `process_pending_block`, e.g. after losing the current round to another owner.
+    ///
+    /// In a multi-leader round, any owner may open the next one by proposing in it. Other rounds
+    /// can only be ended by a leader timeout, so this fails if the current round has not timed
+    /// out yet. Returns the new round.
+    #[instrument(level = "trace")]
+    pub async fn advance_round(&self) -> Result<Round, ChainClientError> {
+        let info = self.chain_info_with_manager_values().await?;
+        let next_round = self.state().next_round();
+        let current_round = Self::proposal_round(&info, next_round);
+        if current_round.is_multi_leader() {
+            let maybe_next_round = info.manager.ownership.next_round(current_round);
+            if let Some(round) = maybe_next_round.filter(Round::is_multi_leader) {
+                let mut state = self.state_mut();
+                state.check_info_is_up_to_date(&info)?;
+                state.set_next_round(round);
+                return Ok(round);
+            }
+        }
+        let info = self.request_leader_timeout_if_needed().await?;
+        ensure!(
+            info.manager.current_round > current_round,
+            ChainClientError::BlockProposalError(
+                "The current round cannot be advanced before it times out"
+            )
+        );
+        Ok(info.manager.current_round)
+    }
+
+    /// Returns the committee of the local chain's current epoch.
+    ///
+    /// If `committee` is not `None`, fails instead if the current epoch and its committee are
+    /// not the given ones.
+    async fn committee_for_proposal(
+        &self,
+        committee: Option<(Epoch, &Committee)>,
+    ) -> Result<Committee, ChainClientError> {
+        let (epoch, mut committees) = self.epoch_and_committees(self.chain_id).await?;
+        let epoch = epoch.ok_or(LocalNodeError::InactiveChain(self.chain_id))?;
+        let local_committee = committees
+            .remove(&epoch)
+            .ok_or(LocalNodeError::InactiveChain(self.chain_id))?;
+        if let Some((expected_epoch, expected_committee)) = committee {
+            ensure!(
+                epoch == expected_epoch && local_committee == *expected_committee,
+                ChainClientError::CommitteeChanged
+            );
+        }
+        Ok(local_committee)
+    }
+
     /// Finalizes the locking block.
     ///
     /// Panics if there is no locking block; fails if the locking block is not in the current round.
+    /// If `committee` is not `None`, only that committee may certify the block.
     async fn finalize_locking_block(
         &self,
         info: Box<ChainInfo>,
+        committee: Option<(Epoch, &Committee)>,
     ) -> Result<ClientOutcome<Option<ConfirmedBlockCertificate>>, ChainClientError> {
         let locking = info
             .manager
@@ -2592,7 +4677,7 @@ where
         let LockingBlock::Regular(certificate) = *locking else {
             panic!("Should have a locking validated block");
         };
-        let committee = self.local_committee().await?;
+        let committee = self.committee_for_proposal(committee).await?;
         match self.finalize_block(&committee, certificate.clone()).await {
             Ok(certificate) => Ok(ClientOutcome::Committed(Some(certificate))),
             Err(ChainClientError::CommunicationError(error)) => {
@@ -2609,26 +4694,35 @@ where
         }
     }
 
+    /// Returns the round in which the next proposal would be made, unless it conflicts with
+    /// another one: the chain manager's current round, or a later one we advanced to.
+    fn proposal_round(info: &ChainInfo, next_round: Option<Round>) -> Round {
+        let current_round = info.manager.current_round;
+        next_round.map_or(current_round, |round| round.max(current_round))
+    }
+
     /// Returns a round in which we can propose a new block or the given one, if possible.
     fn round_for_new_proposal(
         info: &ChainInfo,
+        next_round: Option<Round>,
         identity: &Owner,
         block: &ProposedBlock,
         has_oracle_responses: bool,
     ) -> Result<Either<Round, RoundTimeout>, ChainClientError> {
         let manager = &info.manager;
+        let current_round = Self::proposal_round(info, next_round);
         // If there is a conflicting proposal in the current round, we can only propose if the
         // next round can be started without a timeout, i.e. if we are in a multi-leader round.
         // Similarly, we cannot propose a block that uses oracles in the fast round.
         let conflict = manager.requested_proposed.as_ref().is_some_and(|proposal| {
-            proposal.content.round == manager.current_round && proposal.content.block != *block
-        }) || (manager.current_round.is_fast() && has_oracle_responses);
+            proposal.content.round == current_round && proposal.content.block != *block
+        }) || (current_round.is_fast() && has_oracle_responses);
         let round = if !conflict {
-            manager.current_round
+            current_round
         } else if let Some(round) = manager
             .ownership
-            .next_round(manager.current_round)
-            .filter(|_| manager.current_round.is_multi_leader() || manager.current_round.is_fast())
+            .next_round(current_round)
+            .filter(|_| current_round.is_multi_leader() || current_round.is_fast())
         {
             round
         } else if let Some(timeout) = info.round_timeout() {
@@ -2649,13 +4743,18 @@ where
         ))
     }
 
-    /// Clears the information on any operation that previously failed.
+    /// Clears the information on any operation that previously failed, and returns the
+    /// discarded pending proposal, if any.
     #[instrument(level = "trace")]
-    pub fn clear_pending_proposal(&self) {
-        self.state_mut().clear_pending_proposal();
+    pub fn clear_pending_proposal(&self) -> Option<PendingProposal> {
+        self.state_mut().clear_pending_proposal()
     }
 
     /// Processes a confirmed block for which this chain is a recipient and updates validators.
+    ///
+    /// After processing the certificate locally, this makes sure that a quorum of validators
+    /// has the sender chain up to the certificate's height, so that the messages remain
+    /// available to the recipient.
     #[instrument(
         level = "trace",
         skip(certificate),
@@ -2672,7 +4771,50 @@ where
         .await
     }
 
+    /// Makes sure a quorum of validators has all blocks of the given chain below `height`.
+    ///
+    /// The certificates are first downloaded into the local node if necessary, and then sent
+    /// to the validators of the chain's current committee. No key pair is needed for that
+    /// chain, so this can be used to relay any chain's blocks to lagging validators. If the
+    /// chain is inactive, there is nothing to relay and this does nothing.
+    #[instrument(level = "trace")]
+    pub async fn advance_chain(
+        &self,
+        chain_id: ChainId,
+        height: BlockHeight,
+    ) -> Result<(), ChainClientError> {
+        let nodes = self.validator_nodes().await?;
+        self.client
+            .download_certificates(&nodes, chain_id, height)
+            .await?;
+        let committee = match self.epoch_and_committees(chain_id).await {
+            Ok((Some(epoch), mut committees)) => committees.remove(&epoch),
+            Ok((None, _)) | Err(LocalNodeError::InactiveChain(_)) => None,
+            Err(LocalNodeError::WorkerError(WorkerError::ChainError(error)))
+                if matches!(*error, ChainError::InactiveChain(_)) =>
+            {
+                None
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let Some(committee) = committee else {
+            return Ok(());
+        };
+        self.communicate_chain_updates(
+            &committee,
+            chain_id,
+            height,
+            CrossChainMessageDelivery::Blocking,
+        )
+        .await
+    }
+
     /// Processes confirmed operation for which this chain is a recipient.
+    ///
+    /// This downloads the missing ancestors of the certificate and processes it in the local
+    /// node only. Unlike [`ChainClient::receive_certificate_and_update_validators`], it doesn't
+    /// push the sender chain to the validators. Use it only if the validators are known to
+    /// have the certificate already, e.g. when importing certificates in bulk.
     #[instrument(
         level = "trace",
         skip(certificate),
@@ -2690,9 +4832,9 @@ where
     #[instrument(level = "trace", skip(key_pair))]
     pub async fn rotate_key_pair(
         &self,
-        key_pair: KeyPair,
+        key_pair: impl Into<AccountSecretKey>,
     ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
-        let new_public_key = self.state_mut().insert_known_key_pair(key_pair);
+        let new_public_key = self.state_mut().insert_known_key_pair(key_pair.into());
         self.transfer_ownership(new_public_key.into()).await
     }
 
@@ -2769,6 +4911,161 @@ where
         .await
     }
 
+    /// Sets the maximum amount `owner` can spend in a single block, or removes the limit if
+    /// `limit` is `None`. This must be proposed by an owner without a limit.
+    #[instrument(level = "trace")]
+    pub async fn set_owner_limit(
+        &self,
+        owner: Owner,
+        limit: Option<Amount>,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        // Start from the current limits again after conflicting blocks, which may change them.
+        let make_operations = move || async move {
+            self.prepare_chain().await?;
+            let mut limits = self
+                .chain_state_view()
+                .await?
+                .execution_state
+                .system
+                .owner_limits
+                .get()
+                .clone();
+            match limit {
+                Some(limit) => limits.insert(owner, limit),
+                None => limits.remove(&owner),
+            };
+            Ok::<_, ChainClientError>(vec![Operation::System(
+                SystemOperation::ChangeOwnerLimits { limits },
+            )])
+        };
+        self.execute_operations_as(None, None, None, make_operations, vec![])
+            .await
+    }
+
+    /// Sets the number of distinct owners that must sign each new block proposal. With `None`
+    /// or 1, the proposer's signature is enough.
+    ///
+    /// If the chain already requires several approvals, the block changing the threshold must
+    /// be co-signed, too: see [`ChainClient::propose_unsigned`].
+    #[instrument(level = "trace")]
+    pub async fn change_approval_threshold(
+        &self,
+        threshold: Option<u32>,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operation(SystemOperation::ChangeApprovalThreshold { threshold }.into())
+            .await
+    }
+
+    /// Creates a proposal for a new block with the given operations, signed by this client's
+    /// owner but not yet by any other owner. It can be exported to the other owners, who add
+    /// their signatures with [`ChainClient::add_signature`], and then be submitted with
+    /// [`ChainClient::submit_cosigned`].
+    ///
+    /// Unlike `execute_operations`, this does not set a pending proposal.
+    #[instrument(level = "trace", skip(operations))]
+    pub async fn propose_unsigned(
+        &self,
+        operations: Vec<Operation>,
+    ) -> Result<ClientOutcome<BlockProposal>, ChainClientError> {
+        self.prepare_chain().await?;
+        let incoming_bundles = self.pending_message_bundles().await?;
+        let identity = self.identity().await?;
+        let block = self
+            .new_proposed_block(incoming_bundles, operations, identity)
+            .await?;
+        let info = self.chain_info_with_manager_values().await?;
+        let next_round = self.state().next_round();
+        // Use the round number assuming there are oracle responses, as in `new_pending_block`.
+        let round = match Self::round_for_new_proposal(&info, next_round, &identity, &block, true)?
+        {
+            Either::Left(round) => round.multi_leader(),
+            Either::Right(_) => None,
+        };
+        let (executed_block, _) = self
+            .stage_block_execution_and_discard_failing_messages(block, round)
+            .await?;
+        let round = match Self::round_for_new_proposal(
+            &info,
+            next_round,
+            &identity,
+            &executed_block.block,
+            executed_block.outcome.has_oracle_responses(),
+        )? {
+            Either::Left(round) => round,
+            Either::Right(timeout) => return Ok(ClientOutcome::WaitForTimeout(timeout)),
+        };
+        let content = ProposalContent::new_initial(round, executed_block.block);
+        let proposal = self.sign_proposal(&identity, content, None).await?;
+        Ok(ClientOutcome::Committed(proposal))
+    }
+
+    /// Adds this client's signature to a proposal for a new block on this chain, created by
+    /// another owner with [`ChainClient::propose_unsigned`]. The caller should inspect the
+    /// proposed block before signing it.
+    #[instrument(level = "trace", skip(proposal))]
+    pub async fn add_signature(
+        &self,
+        proposal: &mut BlockProposal,
+    ) -> Result<(), ChainClientError> {
+        ensure!(
+            proposal.content.block.chain_id == self.chain_id,
+            ChainClientError::BlockProposalError("The proposal is for a different chain")
+        );
+        let identity = self.identity().await?;
+        let cosignature = self.sign_block(&identity, &proposal.content.block).await?;
+        proposal.cosignatures.push(cosignature);
+        Ok(())
+    }
+
+    /// Submits a proposal created with [`ChainClient::propose_unsigned`] to the validators,
+    /// once it has been signed by enough owners, and returns the certificate for the new block.
+    ///
+    /// The block becomes the pending proposal, together with its approvals, so that it can be
+    /// retried with [`ChainClient::process_pending_block`] if submitting it fails.
+    #[instrument(level = "trace", skip(proposal))]
+    pub async fn submit_cosigned(
+        &self,
+        proposal: BlockProposal,
+    ) -> Result<ConfirmedBlockCertificate, ChainClientError> {
+        ensure!(
+            proposal.content.block.chain_id == self.chain_id,
+            ChainClientError::BlockProposalError("The proposal is for a different chain")
+        );
+        let mutex = self.state().client_mutex();
+        let _guard = mutex.lock_owned().await;
+        ensure!(
+            self.state().pending_proposal().is_none(),
+            ChainClientError::BlockProposalError(
+                "Client state already has a pending block; \
+                use the `linera retry-pending-block` command to commit that first"
+            )
+        );
+        self.client
+            .local_node
+            .handle_block_proposal(proposal.clone())
+            .await?;
+        let blob_ids = Vec::from_iter(proposal.content.block.published_blob_ids());
+        let blobs = self
+            .client
+            .local_node
+            .get_pending_blobs(&blob_ids, self.chain_id)
+            .await?
+            .ok_or(ChainClientError::InternalError(
+                "Missing local proposal blobs",
+            ))?;
+        self.state_mut().set_pending_proposal(
+            proposal.content.block.clone(),
+            blobs,
+            proposal.cosignatures.clone(),
+        );
+        let round = proposal.content.round.multi_leader();
+        let (executed_block, _) = self
+            .stage_block_execution(proposal.content.block.clone(), round)
+            .await?;
+        self.submit_and_finalize(Box::new(proposal), executed_block, None)
+            .await
+    }
+
     /// Changes the application permissions configuration on this chain.
     #[instrument(level = "trace", skip(application_permissions))]
     pub async fn change_application_permissions(
@@ -2811,8 +5108,12 @@ where
                 .block()
                 .message_id_for_operation(0, OPEN_CHAIN_MESSAGE_INDEX)
                 .ok_or_else(|| ChainClientError::InternalError("Failed to create new chain"))?;
+            let chain_id = certificate
+                .block()
+                .opened_chain_id(&message_id)
+                .ok_or_else(|| ChainClientError::InternalError("Failed to create new chain"))?;
             // Add the new chain to the list of tracked chains
-            self.client.track_chain(ChainId::child(message_id));
+            self.client.track_chain(chain_id);
             self.client
                 .local_node
                 .retry_pending_cross_chain_requests(self.chain_id)
@@ -2821,6 +5122,49 @@ where
         }
     }
 
+    /// Opens a new chain owned by the next key derived with the client's [`KeyDerivation`].
+    ///
+    /// The derived key is only added to the known keys of the new chain once the block opening
+    /// it is confirmed.
+    #[instrument(level = "trace", skip(self))]
+    pub async fn open_chain_with_derived_owner(
+        &self,
+        application_permissions: ApplicationPermissions,
+        balance: Amount,
+    ) -> Result<ClientOutcome<(MessageId, ConfirmedBlockCertificate)>, ChainClientError> {
+        let key_pair = self
+            .client
+            .key_derivation()
+            .ok_or(ChainClientError::MissingKeyDerivation)?
+            .reserve_key_pair();
+        let ownership = ChainOwnership::single(key_pair.public().into());
+        let outcome = self
+            .open_chain(ownership, application_permissions, balance)
+            .await?;
+        if let ClientOutcome::Committed((message_id, certificate)) = &outcome {
+            let timestamp = certificate.block().header.timestamp;
+            let chain_id = certificate
+                .block()
+                .opened_chain_id(message_id)
+                .ok_or_else(|| ChainClientError::InternalError("Failed to create new chain"))?;
+            self.client
+                .chains
+                .entry(chain_id)
+                .or_insert_with(|| {
+                    ChainClientState::new(
+                        Vec::new(),
+                        None,
+                        timestamp,
+                        BlockHeight::ZERO,
+                        None,
+                        Vec::new(),
+                    )
+                })
+                .insert_known_key_pair(key_pair.into());
+        }
+        Ok(outcome)
+    }
+
     /// Closes the chain (and loses everything in it!!).
     /// Returns `None` if the chain was already closed.
     #[instrument(level = "trace")]
@@ -2839,6 +5183,104 @@ where
         }
     }
 
+    /// Opens a new chain owned by `new_owner`, moves this chain's whole balance there and
+    /// closes this chain, all in a single block. This is meant to recover the funds of a
+    /// chain whose key was compromised.
+    ///
+    /// The chain is synchronized with the validators first. The new chain receives the
+    /// balance that is left after executing the pending incoming messages and paying the
+    /// block's fees. The block contains exactly the incoming messages that this amount was
+    /// computed with, so messages arriving in the meantime cannot be left behind on the
+    /// closed chain. Balances of individual owners on this chain are not moved.
+    ///
+    /// Returns the ID of the new chain.
+    #[instrument(level = "trace")]
+    pub async fn emergency_rotate(
+        &self,
+        new_owner: Owner,
+    ) -> Result<ClientOutcome<(ChainId, ConfirmedBlockCertificate)>, ChainClientError> {
+        loop {
+            self.synchronize_from_validators().await?;
+            let (epoch, committees) = self.epoch_and_committees(self.chain_id).await?;
+            let epoch = epoch.ok_or(LocalNodeError::InactiveChain(self.chain_id))?;
+            let operations = |balance| {
+                let config = OpenChainConfig {
+                    ownership: ChainOwnership::single(new_owner),
+                    committees: committees.clone(),
+                    admin_id: self.admin_id,
+                    epoch,
+                    balance,
+                    application_permissions: ApplicationPermissions::default(),
+                };
+                vec![
+                    Operation::System(SystemOperation::OpenChain(config)),
+                    Operation::System(SystemOperation::CloseChain),
+                ]
+            };
+
+            let mutex = self.state().client_mutex();
+            let _guard = mutex.lock_owned().await;
+            match self.process_pending_block_without_prepare(None).await? {
+                ClientOutcome::Committed(Some(_)) => continue,
+                ClientOutcome::WaitForTimeout(timeout) => {
+                    return Ok(ClientOutcome::WaitForTimeout(timeout));
+                }
+                ClientOutcome::Committed(None) => {}
+            }
+            // Execute the block without moving any tokens, to find out what is left after
+            // the incoming messages and the fees.
+            let incoming_bundles = self.pending_message_bundles().await?;
+            let block = self
+                .make_simulated_block(incoming_bundles, operations(Amount::ZERO))
+                .await?;
+            let (executed_block, response) = self
+                .stage_block_execution_and_discard_failing_messages(block, None)
+                .await?;
+            let balance = response.info.chain_balance;
+            let identity = self.identity().await?;
+            let confirmed_value = self
+                .new_pending_block(
+                    executed_block.block.incoming_bundles,
+                    operations(balance),
+                    vec![],
+                    identity,
+                    None,
+                )
+                .await?;
+            let certificate = match self.process_pending_block_without_prepare(None).await? {
+                ClientOutcome::Committed(Some(certificate))
+                    if certificate.block() == confirmed_value.inner().block() =>
+                {
+                    certificate
+                }
+                ClientOutcome::Committed(Some(_)) => continue,
+                ClientOutcome::Committed(None) => {
+                    return Err(ChainClientError::BlockProposalError(
+                        "Unexpected block proposal error",
+                    ))
+                }
+                ClientOutcome::WaitForTimeout(timeout) => {
+                    return Ok(ClientOutcome::WaitForTimeout(timeout));
+                }
+            };
+            // The first message of the first operation created the new chain.
+            let message_id = certificate
+                .block()
+                .message_id_for_operation(0, OPEN_CHAIN_MESSAGE_INDEX)
+                .ok_or_else(|| ChainClientError::InternalError("Failed to create new chain"))?;
+            let chain_id = certificate
+                .block()
+                .opened_chain_id(&message_id)
+                .ok_or_else(|| ChainClientError::InternalError("Failed to create new chain"))?;
+            self.client.track_chain(chain_id);
+            self.client
+                .local_node
+                .retry_pending_cross_chain_requests(self.chain_id)
+                .await?;
+            return Ok(ClientOutcome::Committed((chain_id, certificate)));
+        }
+    }
+
     /// Publishes some bytecode.
     #[cfg(not(target_arch = "wasm32"))]
     #[instrument(level = "trace", skip(contract, service))]
@@ -2971,11 +5413,27 @@ where
     }
 
     /// Creates a new committee and starts using it (admin chains only).
+    ///
+    /// Fails without proposing a block if the committee's voting rights are invalid or if
+    /// this client would not be able to connect to its validators. Unless `force` is set, it
+    /// also fails if a single validator would have a quorum on its own but doesn't in the
+    /// current committee.
     #[instrument(level = "trace", skip(committee))]
     pub async fn stage_new_committee(
         &self,
         committee: Committee,
+        force: bool,
     ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        // Recompute the thresholds, in case the committee wasn't created by `Committee::new`.
+        let committee = Committee::new(committee.validators().clone(), committee.policy().clone())?;
+        if let Some(name) = committee.dominant_validator() {
+            if !force && self.local_committee().await?.dominant_validator().is_none() {
+                return Err(ChainClientError::UnsafeCommittee(name));
+            }
+        }
+        if let Err(error) = self.client.validator_node_provider.make_nodes(&committee) {
+            return Err(ChainClientError::UnreachableCommittee(error));
+        }
         loop {
             let epoch = self.epoch().await?;
             match self
@@ -3275,7 +5733,10 @@ where
             }
         }
 
-        let mut senders = HashMap::new(); // Senders to cancel notification streams.
+        // Senders to cancel notification streams.
+        let mut senders = HashMap::new();
+        // Receives the names of the validators whose notification streams ended.
+        let (lost_sender, mut lost_receiver) = mpsc::unbounded();
         let notifications = self.subscribe().await?;
         let (abortable_notifications, abort) = stream::abortable(self.subscribe().await?);
         if let Err(error) = self.synchronize_from_validators().await {
@@ -3290,7 +5751,7 @@ where
 
         let mut process_notifications = FuturesUnordered::new();
 
-        match self.update_streams(&mut senders).await {
+        match self.update_streams(&mut senders, &lost_sender).await {
             Ok(handler) => process_notifications.push(handler),
             Err(error) => error!("Failed to update committee: {error}"),
         };
@@ -3299,13 +5760,25 @@ where
         let update_streams = async move {
             let mut abortable_notifications = abortable_notifications.fuse();
 
-            while let Some(notification) =
-                await_while_polling(abortable_notifications.next(), &mut process_notifications)
-                    .await
-            {
-                if let Reason::NewBlock { .. } = notification.reason {
+            loop {
+                // Update the streams after a new block, in case the committee changed, and when
+                // a validator's stream ended, to subscribe to it again.
+                let next_event =
+                    future::select(abortable_notifications.next(), lost_receiver.next()).fuse();
+                let update = match await_while_polling(next_event, &mut process_notifications).await
+                {
+                    Either::Left((Some(notification), _)) => {
+                        matches!(notification.reason, Reason::NewBlock { .. })
+                    }
+                    Either::Left((None, _)) => break,
+                    Either::Right((name, _)) => {
+                        debug!(?name, "Subscribing to validator again");
+                        true
+                    }
+                };
+                if update {
                     match await_while_polling(
-                        this.update_streams(&mut senders).fuse(),
+                        this.update_streams(&mut senders, &lost_sender).fuse(),
                         &mut process_notifications,
                     )
                     .await
@@ -3327,10 +5800,14 @@ where
         Ok((update_streams, AbortOnDrop(abort), notifications))
     }
 
-    #[instrument(level = "trace", skip(senders))]
+    /// Subscribes to the notifications of the validators of the current committee that we are
+    /// not subscribed to yet, and unsubscribes from the others. The names of the validators
+    /// whose streams end are sent to `lost_sender` after [`RESUBSCRIBE_DELAY`].
+    #[instrument(level = "trace", skip(senders, lost_sender))]
     async fn update_streams(
         &self,
         senders: &mut HashMap<ValidatorName, AbortHandle>,
+        lost_sender: &mpsc::UnboundedSender<ValidatorName>,
     ) -> Result<impl Future<Output = ()>, ChainClientError> {
         let (chain_id, nodes, local_node) = {
             let committee = self.local_committee().await?;
@@ -3372,6 +5849,8 @@ where
             let this = self.clone();
             let local_node = local_node.clone();
             let remote_node = RemoteNode { name, node };
+            let stream_abort = abort.clone();
+            let lost_sender = lost_sender.clone();
             validator_tasks.push(async move {
                 while let Some(notification) = stream.next().await {
                     this.process_notification(
@@ -3381,6 +5860,15 @@ where
                     )
                     .await;
                 }
+                // The validator closed the stream or we failed to connect: mark the entry as
+                // aborted and request an update, which subscribes to this validator again.
+                if !stream_abort.is_aborted() {
+                    warn!("Lost connection to validator {name}");
+                    stream_abort.abort();
+                    linera_base::time::timer::sleep(RESUBSCRIBE_DELAY).await;
+                    // The listener may have stopped in the meantime.
+                    let _ = lost_sender.unbounded_send(name);
+                }
             });
             entry.insert(abort);
         }
@@ -3503,8 +5991,11 @@ impl Drop for AbortOnDrop {
 struct ReceivedCertificatesFromValidator {
     /// The name of the validator we downloaded from.
     name: ValidatorName,
-    /// The new tracker value for that validator.
-    tracker: u64,
+    /// The number of entries of the validator's received log we don't need to scan again.
+    log_index: u64,
+    /// The highest block height we have downloaded and checked, for each sender chain in
+    /// the validator's received log.
+    trackers: BTreeMap<ChainId, BlockHeight>,
     /// The downloaded certificates. The signatures were already checked and they are ready
     /// to be processed.
     certificates: Vec<ConfirmedBlockCertificate>,
@@ -3513,9 +6004,73 @@ struct ReceivedCertificatesFromValidator {
     other_sender_chains: Vec<ChainId>,
 }
 
-/// A pending proposed block, together with its published blobs.
+/// The estimated cost of executing a list of operations, as returned by
+/// [`ChainClient::estimate_operation_cost`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FuelEstimate {
+    /// The fuel consumed by the applications' contracts.
+    pub fuel: u64,
+    /// The number of outgoing messages created by the operations.
+    pub messages: u32,
+}
+
+/// A snapshot of a chain client's view of its chain, as returned by
+/// [`ChainClient::diagnostics`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainDiagnostics {
+    /// The ID of the chain.
+    pub chain_id: ChainId,
+    /// The hash of the latest known block, if any.
+    pub block_hash: Option<CryptoHash>,
+    /// The height of the next block.
+    pub next_block_height: BlockHeight,
+    /// The round in which the next block will be proposed.
+    pub next_round: Round,
+    /// Whether the client is trying to propose a block at the next height.
+    pub has_pending_proposal: bool,
+    /// The number of key pairs the client knows for this chain.
+    pub known_key_pairs: usize,
+    /// The highest block height of each sender chain whose certificates this chain has
+    /// received from the validators.
+    pub received_certificate_trackers: BTreeMap<ChainId, BlockHeight>,
+    /// The committee of the chain's current epoch, or `None` if the chain is not active.
+    pub committee: Option<Committee>,
+}
+
+/// How a chain is owned, as returned by [`ChainClient::ownership_mode`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OwnershipMode {
+    /// The chain has a single owner, who can propose blocks without competing with others.
+    Single(Owner),
+    /// The chain has several owners, who may have to go through several rounds to propose a
+    /// block. This is empty for public chains, where anyone can propose blocks.
+    Multi(Vec<Owner>),
+    /// The chain has no owners and cannot be used.
+    Inactive,
+}
+
+/// An operation waiting in the queue of a chain, with the channel to report its outcome on.
+struct QueuedOperation {
+    operation: Operation,
+    sender: oneshot::Sender<Result<ConfirmedBlockCertificate, ChainClientError>>,
+}
+
+/// A block scheduled by [`ChainClient::transfer_after`], waiting for the chain to reach the
+/// height it is timelocked until.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScheduledBlock {
+    /// The height from which the block can be committed.
+    pub not_before: BlockHeight,
+    /// The operations of the block, starting with the timelock.
+    pub operations: Vec<Operation>,
+}
+
+/// A pending proposed block, together with its published blobs and the approvals of other
+/// owners, if any.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct PendingProposal {
     pub block: ProposedBlock,
     pub blobs: Vec<Blob>,
+    #[serde(default)]
+    pub cosignatures: Vec<(AccountPublicKey, AccountSignature)>,
 }
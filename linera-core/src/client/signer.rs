@@ -0,0 +1,117 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use linera_base::{
+    crypto::{AccountPublicKey, AccountSecretKey, AccountSignature},
+    identifiers::Owner,
+};
+use linera_chain::data_types::{ProposalContent, ProposedBlock};
+use thiserror::Error;
+
+/// Signs block proposals on behalf of chain owners whose keys the client doesn't hold.
+///
+/// This allows keeping the owner keys outside of the client process, e.g. in a hardware
+/// wallet or a remote key management service. An implementation for such a service would
+/// map each [`Owner`] to the identifier of a key it manages, return that key's public key
+/// in [`public_key`](Signer::public_key), and, in [`sign`](Signer::sign), send the
+/// BCS-serialized content to the service and convert its response into an
+/// [`AccountSignature`]. The signature is checked against the public key like any other,
+/// so the service must sign exactly the bytes that [`AccountSignature::new`] would.
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+pub trait Signer: Send + Sync {
+    /// Returns the public key of `owner`, or `None` if this signer can't sign on its behalf.
+    async fn public_key(&self, owner: &Owner) -> Result<Option<AccountPublicKey>, SignerError>;
+
+    /// Signs the content of a block proposal on behalf of `owner`.
+    async fn sign(
+        &self,
+        owner: &Owner,
+        content: &ProposalContent,
+    ) -> Result<AccountSignature, SignerError>;
+
+    /// Signs a proposed block on behalf of `owner`, to approve it on a chain that requires
+    /// several approvals.
+    ///
+    /// Signers that don't support approvals can keep the default implementation, which always
+    /// fails.
+    async fn sign_block(
+        &self,
+        _owner: &Owner,
+        _block: &ProposedBlock,
+    ) -> Result<AccountSignature, SignerError> {
+        Err(SignerError::Unsupported("sign_block"))
+    }
+}
+
+/// An error returned by a [`Signer`].
+#[derive(Debug, Error)]
+pub enum SignerError {
+    #[error("The signer has no key for owner {0}")]
+    MissingKey(Owner),
+
+    #[error("The signer does not support the operation {0}")]
+    Unsupported(&'static str),
+
+    #[error(transparent)]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// A [`Signer`] that keeps the secret keys in memory.
+#[derive(Default)]
+pub struct InMemorySigner {
+    keys: BTreeMap<Owner, AccountSecretKey>,
+}
+
+impl InMemorySigner {
+    /// Creates a signer for the owners of the given keys.
+    pub fn new(keys: impl IntoIterator<Item = AccountSecretKey>) -> Self {
+        let keys = keys
+            .into_iter()
+            .map(|key| (Owner::from(key.public()), key))
+            .collect();
+        InMemorySigner { keys }
+    }
+
+    /// Adds a key and returns its public key.
+    pub fn insert(&mut self, key: AccountSecretKey) -> AccountPublicKey {
+        let public_key = key.public();
+        self.keys.insert(public_key.into(), key);
+        public_key
+    }
+}
+
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+impl Signer for InMemorySigner {
+    async fn public_key(&self, owner: &Owner) -> Result<Option<AccountPublicKey>, SignerError> {
+        Ok(self.keys.get(owner).map(AccountSecretKey::public))
+    }
+
+    async fn sign(
+        &self,
+        owner: &Owner,
+        content: &ProposalContent,
+    ) -> Result<AccountSignature, SignerError> {
+        let key = self
+            .keys
+            .get(owner)
+            .ok_or(SignerError::MissingKey(*owner))?;
+        Ok(AccountSignature::new(content, key))
+    }
+
+    async fn sign_block(
+        &self,
+        owner: &Owner,
+        block: &ProposedBlock,
+    ) -> Result<AccountSignature, SignerError> {
+        let key = self
+            .keys
+            .get(owner)
+            .ok_or(SignerError::MissingKey(*owner))?;
+        Ok(AccountSignature::new(block, key))
+    }
+}
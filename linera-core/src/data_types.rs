@@ -6,18 +6,19 @@ use std::{collections::BTreeMap, ops::Not};
 
 use custom_debug_derive::Debug;
 use linera_base::{
+    balance_proof::BalanceProof,
     crypto::{BcsSignable, CryptoError, CryptoHash, KeyPair, Signature},
     data_types::{Amount, BlockHeight, Round, Timestamp},
     identifiers::{AccountOwner, ChainDescription, ChainId},
 };
 use linera_chain::{
-    data_types::{ChainAndHeight, IncomingBundle, Medium, MessageBundle},
+    data_types::{ChainAndHeight, IncomingBundle, Medium, MessageBundle, Target},
     manager::ChainManagerInfo,
     ChainStateView,
 };
 use linera_execution::{
     committee::{Committee, Epoch, ValidatorName},
-    ExecutionRuntimeContext,
+    ChannelSubscription, ExecutionRuntimeContext,
 };
 use linera_storage::ChainRuntimeContext;
 use linera_views::context::Context;
@@ -76,6 +77,11 @@ pub struct ChainInfoQuery {
     /// Query the received messages that are waiting be picked in the next block.
     #[debug(skip_if = Not::not)]
     pub request_pending_message_bundles: bool,
+    /// The number of pending message bundles to skip, if they are requested.
+    pub request_pending_message_bundles_offset: usize,
+    /// The maximum number of pending message bundles to return, if they are requested.
+    #[debug(skip_if = Option::is_none)]
+    pub request_pending_message_bundles_limit: Option<usize>,
     /// Query a range of certificate hashes sent from the chain.
     #[debug(skip_if = Option::is_none)]
     pub request_sent_certificate_hashes_in_range: Option<BlockHeightRange>,
@@ -91,6 +97,26 @@ pub struct ChainInfoQuery {
     /// Include a vote to switch to fallback mode, if appropriate.
     #[debug(skip_if = Not::not)]
     pub request_fallback: bool,
+    /// Query the channels that the chain is subscribed to.
+    #[debug(skip_if = Not::not)]
+    pub request_subscriptions: bool,
+    /// Query the received certificates, excluding those up to the given height for each
+    /// sender chain.
+    #[debug(skip_if = Option::is_none)]
+    pub request_received_log_after: Option<BTreeMap<ChainId, BlockHeight>>,
+    /// Query the status of the chain's outboxes.
+    #[debug(skip_if = Not::not)]
+    pub request_outboxes: bool,
+    /// Query a proof of the chain balance after the latest block, or after the block at
+    /// `request_balance_proof_height`.
+    #[debug(skip_if = Not::not)]
+    pub request_balance_proof: bool,
+    /// The height of the block after which to prove the balance, if not the latest one.
+    /// There is no proof for blocks that were pruned.
+    #[debug(skip_if = Option::is_none)]
+    pub request_balance_proof_height: Option<BlockHeight>,
+    /// The number of outboxes to skip, if they are requested.
+    pub request_outboxes_offset: usize,
 }
 
 impl ChainInfoQuery {
@@ -101,11 +127,19 @@ impl ChainInfoQuery {
             request_committees: false,
             request_owner_balance: None,
             request_pending_message_bundles: false,
+            request_pending_message_bundles_offset: 0,
+            request_pending_message_bundles_limit: None,
             request_sent_certificate_hashes_in_range: None,
             request_received_log_excluding_first_n: None,
             request_manager_values: false,
             request_leader_timeout: false,
             request_fallback: false,
+            request_subscriptions: false,
+            request_received_log_after: None,
+            request_outboxes: false,
+            request_balance_proof: false,
+            request_balance_proof_height: None,
+            request_outboxes_offset: 0,
         }
     }
 
@@ -129,6 +163,14 @@ impl ChainInfoQuery {
         self
     }
 
+    /// Requests at most `limit` pending message bundles, skipping the first `offset` ones.
+    pub fn with_pending_message_bundles_page(mut self, limit: usize, offset: usize) -> Self {
+        self.request_pending_message_bundles = true;
+        self.request_pending_message_bundles_limit = Some(limit);
+        self.request_pending_message_bundles_offset = offset;
+        self
+    }
+
     pub fn with_sent_certificate_hashes_in_range(mut self, range: BlockHeightRange) -> Self {
         self.request_sent_certificate_hashes_in_range = Some(range);
         self
@@ -139,6 +181,11 @@ impl ChainInfoQuery {
         self
     }
 
+    pub fn with_received_log_after(mut self, heights: BTreeMap<ChainId, BlockHeight>) -> Self {
+        self.request_received_log_after = Some(heights);
+        self
+    }
+
     pub fn with_manager_values(mut self) -> Self {
         self.request_manager_values = true;
         self
@@ -153,6 +200,34 @@ impl ChainInfoQuery {
         self.request_fallback = true;
         self
     }
+
+    pub fn with_subscriptions(mut self) -> Self {
+        self.request_subscriptions = true;
+        self
+    }
+
+    pub fn with_outboxes(mut self) -> Self {
+        self.request_outboxes = true;
+        self
+    }
+
+    /// Requests the status of the chain's outboxes, skipping the first `offset` ones.
+    pub fn with_outboxes_page(mut self, offset: usize) -> Self {
+        self.request_outboxes = true;
+        self.request_outboxes_offset = offset;
+        self
+    }
+
+    pub fn with_balance_proof(mut self) -> Self {
+        self.request_balance_proof = true;
+        self
+    }
+
+    pub fn with_balance_proof_at(mut self, height: BlockHeight) -> Self {
+        self.request_balance_proof = true;
+        self.request_balance_proof_height = Some(height);
+        self
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -189,14 +264,53 @@ pub struct ChainInfo {
     /// The received messages that are waiting be picked in the next block (if requested).
     #[debug(skip_if = Vec::is_empty)]
     pub requested_pending_message_bundles: Vec<IncomingBundle>,
+    /// Whether there are more pending message bundles after the requested ones.
+    #[debug(skip_if = Not::not)]
+    pub has_more_pending_message_bundles: bool,
     /// The response to `request_sent_certificate_hashes_in_range`
     #[debug(skip_if = Vec::is_empty)]
     pub requested_sent_certificate_hashes: Vec<CryptoHash>,
+    /// The height to resume from if the response to `request_sent_certificate_hashes_in_range`
+    /// was truncated by the validator.
+    #[debug(skip_if = Option::is_none)]
+    pub requested_sent_certificate_hashes_next_height: Option<BlockHeight>,
     /// The current number of received certificates (useful for `request_received_log_excluding_first_n`)
     pub count_received_log: usize,
     /// The response to `request_received_certificates_excluding_first_n`
     #[debug(skip_if = Vec::is_empty)]
     pub requested_received_log: Vec<ChainAndHeight>,
+    /// The channels that the chain is subscribed to (if requested).
+    #[debug(skip_if = Vec::is_empty)]
+    pub requested_subscriptions: Vec<ChannelSubscription>,
+    /// The status of the chain's outboxes (if requested).
+    #[debug(skip_if = BTreeMap::is_empty)]
+    #[serde(with = "map_as_pairs")]
+    pub requested_outboxes: BTreeMap<Target, OutboxStatus>,
+    /// A proof of the chain balance after the latest block (if requested and the chain has
+    /// blocks).
+    #[debug(skip_if = Option::is_none)]
+    pub requested_balance_proof: Option<Box<BalanceProof>>,
+    /// Whether there are more outboxes after the requested ones.
+    #[debug(skip_if = Not::not)]
+    pub has_more_outboxes: bool,
+}
+
+/// The messages in an outbox that the recipient has not acknowledged yet.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub struct OutboxStatus {
+    /// The heights of our blocks with messages that are still queued for the recipient.
+    pub queued_heights: Vec<BlockHeight>,
+    /// The height after the last block that was scheduled in the outbox. Scheduled heights
+    /// below this one that are not queued anymore were acknowledged by the recipient.
+    pub next_height_to_schedule: BlockHeight,
+}
+
+impl OutboxStatus {
+    /// Returns the oldest height that the recipient has not acknowledged yet, if any.
+    pub fn oldest_unacknowledged_height(&self) -> Option<BlockHeight> {
+        self.queued_heights.first().copied()
+    }
 }
 
 impl ChainInfo {
@@ -284,9 +398,15 @@ where
             requested_committees: None,
             requested_owner_balance: None,
             requested_pending_message_bundles: Vec::new(),
+            has_more_pending_message_bundles: false,
             requested_sent_certificate_hashes: Vec::new(),
+            requested_sent_certificate_hashes_next_height: None,
             count_received_log: view.received_log.count(),
             requested_received_log: Vec::new(),
+            requested_subscriptions: Vec::new(),
+            requested_outboxes: BTreeMap::new(),
+            requested_balance_proof: None,
+            has_more_outboxes: false,
         }
     }
 }
@@ -370,3 +490,38 @@ impl<T> ClientOutcome<T> {
         }
     }
 }
+
+/// Serializes a map as a list of key-value pairs in human-readable formats such as JSON,
+/// where map keys must be strings. Binary formats keep the map.
+mod map_as_pairs {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, S>(map: &BTreeMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.collect_seq(map)
+        } else {
+            map.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<BTreeMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Ord,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let pairs = Vec::<(K, V)>::deserialize(deserializer)?;
+            Ok(pairs.into_iter().collect())
+        } else {
+            BTreeMap::deserialize(deserializer)
+        }
+    }
+}
@@ -14,7 +14,10 @@ pub mod join_set_ext;
 pub mod local_node;
 pub mod node;
 pub mod notifier;
+pub mod rate_limit;
 pub mod remote_node;
+#[cfg(with_test_helpers)]
+pub mod test_helpers;
 #[cfg(with_testing)]
 #[path = "unit_tests/test_utils.rs"]
 pub mod test_utils;
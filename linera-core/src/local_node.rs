@@ -3,13 +3,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    collections::{BTreeMap, VecDeque},
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    io::{Read, Write},
     sync::Arc,
 };
 
 use futures::{future::Either, stream, StreamExt as _, TryStreamExt as _};
 use linera_base::{
     data_types::{ArithmeticError, Blob, BlockHeight, UserApplicationDescription},
+    ensure,
     identifiers::{BlobId, ChainId, MessageId, UserApplicationId},
 };
 use linera_chain::{
@@ -17,9 +19,10 @@ use linera_chain::{
     types::{ConfirmedBlockCertificate, GenericCertificate, LiteCertificate},
     ChainStateView,
 };
-use linera_execution::{committee::ValidatorName, Query, QueryOutcome};
-use linera_storage::Storage;
+use linera_execution::{committee::ValidatorName, Query, QueryOutcome, ResourceTracker};
+use linera_storage::{Batch, ChainExportHeader, ExportItem, Storage, CHAIN_EXPORT_VERSION};
 use linera_views::views::ViewError;
+use serde::{de::DeserializeOwned, Serialize};
 use thiserror::Error;
 use tokio::sync::OwnedRwLockReadGuard;
 use tracing::{instrument, warn};
@@ -27,9 +30,45 @@ use tracing::{instrument, warn};
 use crate::{
     data_types::{BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse},
     notifier::Notifier,
-    worker::{ProcessableCertificate, WorkerError, WorkerState},
+    worker::{ProcessableCertificate, WorkerError, WorkerMetrics, WorkerState},
 };
 
+/// Writes a length-prefixed, BCS-serialized record.
+fn write_record<W: Write>(writer: &mut W, record: &impl Serialize) -> Result<(), LocalNodeError> {
+    let bytes = bcs::to_bytes(record).map_err(ViewError::from)?;
+    let len = u32::try_from(bytes.len()).map_err(|_| ArithmeticError::Overflow)?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Reads a record written by [`write_record`], or returns `None` at the end of the input.
+fn read_record<T: DeserializeOwned>(reader: &mut impl Read) -> Result<Option<T>, LocalNodeError> {
+    let mut len_bytes = [0; 4];
+    let mut read = 0;
+    while read < len_bytes.len() {
+        match reader.read(&mut len_bytes[read..])? {
+            0 if read == 0 => return Ok(None),
+            0 => return Err(invalid_export("truncated record length")),
+            n => read += n,
+        }
+    }
+    let len = u32::from_le_bytes(len_bytes);
+    let mut bytes = Vec::new();
+    reader.take(len.into()).read_to_end(&mut bytes)?;
+    ensure!(
+        bytes.len() == len as usize,
+        invalid_export("truncated record")
+    );
+    let record = bcs::from_bytes(&bytes)
+        .map_err(|error| invalid_export(format!("invalid record: {error}")))?;
+    Ok(Some(record))
+}
+
+fn invalid_export(message: impl Into<String>) -> LocalNodeError {
+    LocalNodeError::InvalidChainExport(message.into())
+}
+
 /// A local node with a single worker, typically used by clients.
 pub struct LocalNode<S>
 where
@@ -70,6 +109,12 @@ pub enum LocalNodeError {
 
     #[error("Blobs not found: {0:?}")]
     BlobsNotFound(Vec<BlobId>),
+
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid chain export: {0}")]
+    InvalidChainExport(String),
 }
 
 impl From<WorkerError> for LocalNodeError {
@@ -133,6 +178,24 @@ where
         .await?)
     }
 
+    /// Processes the confirmed `certificates` in order, and returns the result for each one.
+    #[instrument(level = "trace", skip_all)]
+    pub async fn handle_certificates(
+        &self,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        notifier: &impl Notifier,
+    ) -> Vec<Result<ChainInfoResponse, LocalNodeError>> {
+        Box::pin(
+            self.node
+                .state
+                .fully_handle_certificates_with_notifications(certificates, notifier),
+        )
+        .await
+        .into_iter()
+        .map(|result| Ok(result?))
+        .collect()
+    }
+
     #[instrument(level = "trace", skip_all)]
     pub async fn handle_chain_info_query(
         &self,
@@ -160,6 +223,11 @@ impl<S> LocalNodeClient<S>
 where
     S: Storage + Clone,
 {
+    /// Returns the nickname of the local node's worker.
+    pub(crate) fn nickname(&self) -> &str {
+        self.node.state.nickname()
+    }
+
     #[instrument(level = "trace", skip_all)]
     pub(crate) fn storage_client(&self) -> S {
         self.node.state.storage_client().clone()
@@ -179,6 +247,21 @@ where
         Ok(self.node.state.stage_block_execution(block, round).await?)
     }
 
+    /// Executes a block like [`LocalNodeClient::stage_block_execution`], and also returns the
+    /// resources used by the execution.
+    #[instrument(level = "trace", skip_all)]
+    pub async fn stage_block_execution_with_resources(
+        &self,
+        block: ProposedBlock,
+        round: Option<u32>,
+    ) -> Result<(ExecutedBlock, ChainInfoResponse, ResourceTracker), LocalNodeError> {
+        Ok(self
+            .node
+            .state
+            .stage_block_execution_with_resources(block, round)
+            .await?)
+    }
+
     /// Reads blobs from storage.
     pub async fn read_blobs_from_storage(
         &self,
@@ -329,6 +412,219 @@ where
         Ok(())
     }
 
+    /// Returns a snapshot of the local node's chains and counters.
+    #[instrument(level = "trace", skip(self))]
+    pub async fn metrics(&self) -> Result<WorkerMetrics, LocalNodeError> {
+        Ok(self.node.state.metrics().await?)
+    }
+
+    /// Rolls forward any work that was interrupted by a crash, e.g. after starting from
+    /// existing storage.
+    ///
+    /// A chain state is saved in a single batch, and the certificate and blobs it depends on
+    /// are written before it, so the chains themselves never need to be repaired: a
+    /// certificate whose state write was lost is simply processed again. However, the
+    /// cross-chain requests created by a block are only sent after its chain was saved, so
+    /// this re-sends the pending requests of every chain in storage.
+    ///
+    /// The `linera` client calls this when it starts.
+    #[instrument(level = "trace", skip(self))]
+    pub async fn recover(&self) -> Result<(), LocalNodeError> {
+        let chain_ids = self.storage_client().list_chain_ids().await?;
+        for chain_id in chain_ids {
+            self.retry_pending_cross_chain_requests(chain_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes the given chain to `writer`: a [`ChainExportHeader`], followed by the
+    /// chain's certificates from its pruned height on, each one preceded by the blobs it
+    /// requires.
+    ///
+    /// Every record is BCS-serialized and prefixed with its length, as a little-endian `u32`.
+    #[instrument(level = "trace", skip(self, writer))]
+    pub async fn export_chain<W: Write + Send>(
+        &self,
+        chain_id: ChainId,
+        mut writer: W,
+    ) -> Result<(), LocalNodeError> {
+        let info = self.chain_info(chain_id).await?;
+        let first_block_height = *self.chain_state_view(chain_id).await?.pruned_height.get();
+        let header = ChainExportHeader {
+            version: CHAIN_EXPORT_VERSION,
+            chain_id,
+            first_block_height,
+            next_block_height: info.next_block_height,
+            block_hash: info.block_hash,
+            state_hash: info.state_hash,
+        };
+        write_record(&mut writer, &header)?;
+        let storage = self.storage_client();
+        let hashes = storage
+            .list_certificate_hashes(chain_id, first_block_height..info.next_block_height)
+            .await?;
+        let mut exported_blob_ids = BTreeSet::new();
+        for hash in hashes {
+            let certificate = storage.read_certificate(hash).await?;
+            let blob_ids = certificate
+                .block()
+                .required_blob_ids()
+                .into_iter()
+                .filter(|blob_id| !exported_blob_ids.contains(blob_id))
+                .collect::<Vec<_>>();
+            for (blob_id, maybe_blob) in blob_ids.iter().zip(storage.read_blobs(&blob_ids).await?) {
+                let blob = maybe_blob.ok_or_else(|| ViewError::BlobsNotFound(vec![*blob_id]))?;
+                write_record(&mut writer, &ExportItem::Blob(blob))?;
+                exported_blob_ids.insert(*blob_id);
+            }
+            write_record(&mut writer, &ExportItem::Certificate(certificate))?;
+        }
+        Ok(())
+    }
+
+    /// Reads a chain written by [`LocalNodeClient::export_chain`] and processes the blocks
+    /// that the local node doesn't have yet. If the export starts after the chain's genesis,
+    /// the local node must already have the blocks below it.
+    ///
+    /// Nothing is written to storage unless the whole input could be read, the certificates
+    /// form a chain that ends in the exported block and state hashes, every certificate is
+    /// signed by the committee of its epoch according to the admin chain `admin_id`, and every
+    /// blob they require is either in the input or already in storage. The blobs and
+    /// certificates are then written in a single batch, before the blocks are executed.
+    #[instrument(level = "trace", skip(self, reader))]
+    pub async fn import_chain<R: Read + Send>(
+        &self,
+        admin_id: ChainId,
+        mut reader: R,
+    ) -> Result<Box<ChainInfo>, LocalNodeError> {
+        let header = read_record::<ChainExportHeader>(&mut reader)?
+            .ok_or_else(|| invalid_export("the header is missing"))?;
+        ensure!(
+            header.version == CHAIN_EXPORT_VERSION,
+            invalid_export(format!("unsupported version {}", header.version))
+        );
+        let chain_id = header.chain_id;
+        let mut blobs = BTreeMap::new();
+        let mut certificates = Vec::new();
+        while let Some(item) = read_record::<ExportItem>(&mut reader)? {
+            match item {
+                ExportItem::Blob(blob) => {
+                    blobs.insert(blob.id(), blob);
+                }
+                ExportItem::Certificate(certificate) => certificates.push(certificate),
+            }
+        }
+
+        // Check that the certificates extend the local chain to the exported block.
+        let first_block_height = header.first_block_height;
+        let info = self.chain_info(chain_id).await?;
+        ensure!(
+            info.next_block_height >= first_block_height,
+            invalid_export(format!(
+                "the export starts at height {first_block_height}, above the local chain"
+            ))
+        );
+        let mut block_hash = match usize::try_from(first_block_height)?.checked_sub(1) {
+            Some(index) => {
+                self.chain_state_view(chain_id)
+                    .await?
+                    .confirmed_log
+                    .get(index)
+                    .await?
+            }
+            None => None,
+        };
+        for (index, certificate) in certificates.iter().enumerate() {
+            let block = &certificate.block().header;
+            ensure!(
+                block.chain_id == chain_id
+                    && block.height.0 == first_block_height.0 + index as u64
+                    && block.previous_block_hash == block_hash,
+                invalid_export(format!("certificate {index} does not extend the chain"))
+            );
+            block_hash = Some(certificate.hash());
+        }
+        ensure!(
+            first_block_height.0 + certificates.len() as u64 == header.next_block_height.0
+                && block_hash == header.block_hash,
+            invalid_export("the certificates do not end in the exported block")
+        );
+        if let Some(certificate) = certificates.last() {
+            ensure!(
+                Some(certificate.block().header.state_hash) == header.state_hash,
+                invalid_export("the last block does not have the exported state hash")
+            );
+        } else {
+            ensure!(
+                info.state_hash == header.state_hash,
+                invalid_export("the chain does not have the exported state hash")
+            );
+        }
+
+        // Check the signatures against the committees of the admin chain.
+        let query = ChainInfoQuery::new(admin_id).with_committees();
+        let committees = self
+            .handle_chain_info_query(query)
+            .await?
+            .info
+            .requested_committees
+            .unwrap_or_default();
+        let mut required_blobs = BTreeMap::new();
+        for certificate in &certificates {
+            let block = &certificate.block().header;
+            let committee = committees.get(&block.epoch).ok_or_else(|| {
+                invalid_export(format!(
+                    "block {} is from unknown epoch {}",
+                    block.height, block.epoch
+                ))
+            })?;
+            certificate
+                .check(committee)
+                .map_err(|error| invalid_export(format!("block {}: {error}", block.height)))?;
+            for blob_id in certificate.block().required_blob_ids() {
+                if let Some(blob) = blobs.remove(&blob_id) {
+                    required_blobs.insert(blob_id, blob);
+                }
+            }
+        }
+        let storage = self.storage_client();
+        let missing_blob_ids = certificates
+            .iter()
+            .flat_map(|certificate| certificate.block().required_blob_ids())
+            .filter(|blob_id| !required_blobs.contains_key(blob_id))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let missing_blob_ids = storage.missing_blobs(&missing_blob_ids).await?;
+        ensure!(
+            missing_blob_ids.is_empty(),
+            invalid_export(format!("missing blobs {missing_blob_ids:?}"))
+        );
+
+        // Write everything at once, then apply the blocks the local node doesn't have yet.
+        let new_certificates = certificates
+            .into_iter()
+            .skip(
+                usize::try_from(info.next_block_height.0 - first_block_height.0)
+                    .unwrap_or(usize::MAX),
+            )
+            .collect::<Vec<_>>();
+        let mut batch = Batch::new();
+        for blob in required_blobs.into_values() {
+            batch.add_blob(blob);
+        }
+        for certificate in &new_certificates {
+            batch.add_certificate(certificate.clone());
+        }
+        if !batch.is_empty() {
+            storage.write_batch(batch).await?;
+        }
+        for certificate in new_certificates {
+            self.handle_certificate(certificate, &()).await?;
+        }
+        self.chain_info(chain_id).await
+    }
+
     /// Given a list of chain IDs, returns a map that assigns to each of them the next block
     /// height, i.e. the lowest block height that we have not processed in the local node yet.
     ///
@@ -354,11 +650,25 @@ where
     pub async fn update_received_certificate_trackers(
         &self,
         chain_id: ChainId,
-        new_trackers: BTreeMap<ValidatorName, u64>,
+        new_trackers: BTreeMap<ChainId, BlockHeight>,
+        new_log_indices: BTreeMap<ValidatorName, u64>,
+    ) -> Result<(), LocalNodeError> {
+        self.node
+            .state
+            .update_received_certificate_trackers(chain_id, new_trackers, new_log_indices)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn prune_history(
+        &self,
+        chain_id: ChainId,
+        keep_last: usize,
+        keep_from: BlockHeight,
     ) -> Result<(), LocalNodeError> {
         self.node
             .state
-            .update_received_certificate_trackers(chain_id, new_trackers)
+            .prune_history(chain_id, keep_last, keep_from)
             .await?;
         Ok(())
     }
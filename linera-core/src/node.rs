@@ -9,7 +9,7 @@ use futures::stream::LocalBoxStream as BoxStream;
 use futures::stream::Stream;
 use linera_base::{
     crypto::{CryptoError, CryptoHash},
-    data_types::{ArithmeticError, BlobContent, BlockHeight},
+    data_types::{ArithmeticError, BlobContent, BlockHeight, ProtocolVersion},
     identifiers::{BlobId, ChainId},
 };
 use linera_chain::{
@@ -74,6 +74,29 @@ pub trait ValidatorNode {
         delivery: CrossChainMessageDelivery,
     ) -> Result<ChainInfoResponse, NodeError>;
 
+    /// Processes confirmed certificates in order, and returns the result for each one. A
+    /// certificate that fails doesn't prevent the following ones from being processed.
+    ///
+    /// The network protocols have no batched request yet, so by default the certificates are
+    /// sent one by one.
+    async fn handle_confirmed_certificates(
+        &self,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Vec<Result<ChainInfoResponse, NodeError>>
+    where
+        Self: Sync,
+    {
+        let mut results = Vec::with_capacity(certificates.len());
+        for certificate in certificates {
+            results.push(
+                self.handle_confirmed_certificate(certificate, delivery)
+                    .await,
+            );
+        }
+        results
+    }
+
     /// Processes a validated certificate.
     async fn handle_validated_certificate(
         &self,
@@ -262,6 +285,16 @@ pub enum NodeError {
     EmptyBlobsNotFound,
     #[error("Local error handling validator response")]
     ResponseHandlingError { error: String },
+    #[error("The validator did not respond in time")]
+    RequestTimeout,
+    #[error(
+        "The validator uses protocol version {validator}, which is incompatible with the \
+         client's version {client}"
+    )]
+    IncompatibleProtocolVersion {
+        validator: ProtocolVersion,
+        client: ProtocolVersion,
+    },
 }
 
 impl From<tonic::Status> for NodeError {
@@ -352,6 +385,12 @@ impl From<WorkerError> for NodeError {
             WorkerError::ChainError(error) => (*error).into(),
             WorkerError::MissingCertificateValue => Self::MissingCertificateValue,
             WorkerError::BlobsNotFound(blob_ids) => Self::BlobsNotFound(blob_ids),
+            WorkerError::IncompatibleProtocolVersion { ours, theirs } => {
+                Self::IncompatibleProtocolVersion {
+                    validator: ours,
+                    client: theirs,
+                }
+            }
             error => Self::WorkerError {
                 error: error.to_string(),
             },
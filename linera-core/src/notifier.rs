@@ -35,6 +35,13 @@ impl<N> ChannelNotifier<N> {
         }
     }
 
+    /// Returns whether any client subscribed to notifications from the given chain.
+    pub fn has_subscribers(&self, chain_id: &ChainId) -> bool {
+        self.inner
+            .get(chain_id)
+            .is_some_and(|senders| !senders.is_empty())
+    }
+
     /// Creates a subscription given a collection of ChainIds and a sender to the client.
     pub fn subscribe(&self, chain_ids: Vec<ChainId>) -> UnboundedReceiver<N> {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
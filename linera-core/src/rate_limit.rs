@@ -0,0 +1,273 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Client-side rate limiting of the requests sent to a validator.
+
+#[cfg(test)]
+#[path = "unit_tests/rate_limit_tests.rs"]
+mod unit_tests;
+
+use std::sync::{Arc, Mutex};
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::BlobContent,
+    identifiers::{BlobId, ChainId},
+    time::{timer::sleep, Duration, Instant},
+};
+use linera_chain::{
+    data_types::BlockProposal,
+    types::{
+        ConfirmedBlock, ConfirmedBlockCertificate, GenericCertificate, LiteCertificate, Timeout,
+        ValidatedBlock,
+    },
+};
+use linera_version::VersionInfo;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{
+    data_types::{ChainInfoQuery, ChainInfoResponse},
+    node::{CrossChainMessageDelivery, NodeError, ValidatorNode},
+};
+
+/// Limits on the requests a client sends to a single validator.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// The maximum number of requests awaiting a response at any given time.
+    pub max_in_flight: usize,
+    /// The maximum number of requests started per second, on average. Bursts of up to
+    /// one second's worth of requests are allowed. Use `f64::INFINITY` for no limit.
+    pub max_per_second: f64,
+}
+
+/// Enforces a [`RateLimit`]: a semaphore bounds the requests in flight and a token bucket
+/// bounds their rate. Requests over the limit wait for their turn instead of failing.
+#[derive(Debug)]
+pub struct RateLimiter {
+    semaphore: Arc<Semaphore>,
+    bucket: Option<Mutex<TokenBucket>>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Takes a token if one is available; otherwise returns how long to wait for one.
+    fn try_take(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return None;
+        }
+        Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+    }
+}
+
+impl RateLimiter {
+    /// Creates a new limiter enforcing the given limit.
+    pub fn new(limit: RateLimit) -> Self {
+        let max_in_flight = limit.max_in_flight.clamp(1, Semaphore::MAX_PERMITS);
+        let bucket = (limit.max_per_second.is_finite() && limit.max_per_second > 0.0).then(|| {
+            let capacity = limit.max_per_second.max(1.0);
+            Mutex::new(TokenBucket {
+                rate: limit.max_per_second,
+                capacity,
+                tokens: capacity,
+                last_refill: Instant::now(),
+            })
+        });
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_in_flight)),
+            bucket,
+        }
+    }
+
+    /// Waits until a new request may be sent. The request counts as in flight until the
+    /// returned permit is dropped.
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        let permit = self
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("rate limiter semaphore is never closed");
+        if let Some(bucket) = &self.bucket {
+            loop {
+                let delay = bucket
+                    .lock()
+                    .expect("token bucket lock should not be poisoned")
+                    .try_take();
+                match delay {
+                    None => break,
+                    Some(delay) => sleep(delay).await,
+                }
+            }
+        }
+        permit
+    }
+}
+
+/// A validator node whose requests are subject to an optional [`RateLimiter`].
+#[derive(Clone)]
+pub struct RateLimitedNode<N> {
+    node: N,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl<N> RateLimitedNode<N> {
+    /// Wraps `node`, sharing `limiter` with all other nodes for the same validator.
+    pub fn new(node: N, limiter: Option<Arc<RateLimiter>>) -> Self {
+        Self { node, limiter }
+    }
+
+    /// Returns the underlying node, without rate limiting.
+    pub fn into_inner(self) -> N {
+        self.node
+    }
+
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        match &self.limiter {
+            Some(limiter) => Some(limiter.acquire().await),
+            None => None,
+        }
+    }
+}
+
+impl<N: ValidatorNode + Sync> ValidatorNode for RateLimitedNode<N> {
+    type NotificationStream = N::NotificationStream;
+
+    async fn handle_block_proposal(
+        &self,
+        proposal: BlockProposal,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.handle_block_proposal(proposal).await
+    }
+
+    async fn handle_lite_certificate(
+        &self,
+        certificate: LiteCertificate<'_>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let _permit = self.acquire().await;
+        self.node
+            .handle_lite_certificate(certificate, delivery)
+            .await
+    }
+
+    async fn handle_confirmed_certificate(
+        &self,
+        certificate: GenericCertificate<ConfirmedBlock>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let _permit = self.acquire().await;
+        self.node
+            .handle_confirmed_certificate(certificate, delivery)
+            .await
+    }
+
+    async fn handle_validated_certificate(
+        &self,
+        certificate: GenericCertificate<ValidatedBlock>,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.handle_validated_certificate(certificate).await
+    }
+
+    async fn handle_timeout_certificate(
+        &self,
+        certificate: GenericCertificate<Timeout>,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.handle_timeout_certificate(certificate).await
+    }
+
+    async fn handle_chain_info_query(
+        &self,
+        query: ChainInfoQuery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.handle_chain_info_query(query).await
+    }
+
+    async fn get_version_info(&self) -> Result<VersionInfo, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.get_version_info().await
+    }
+
+    async fn get_genesis_config_hash(&self) -> Result<CryptoHash, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.get_genesis_config_hash().await
+    }
+
+    async fn subscribe(&self, chains: Vec<ChainId>) -> Result<Self::NotificationStream, NodeError> {
+        // Only establishing the subscription counts as a request; the stream itself does not.
+        let _permit = self.acquire().await;
+        self.node.subscribe(chains).await
+    }
+
+    async fn upload_blob(&self, content: BlobContent) -> Result<BlobId, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.upload_blob(content).await
+    }
+
+    async fn download_blob(&self, blob_id: BlobId) -> Result<BlobContent, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.download_blob(blob_id).await
+    }
+
+    async fn download_pending_blob(
+        &self,
+        chain_id: ChainId,
+        blob_id: BlobId,
+    ) -> Result<BlobContent, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.download_pending_blob(chain_id, blob_id).await
+    }
+
+    async fn handle_pending_blob(
+        &self,
+        chain_id: ChainId,
+        blob: BlobContent,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.handle_pending_blob(chain_id, blob).await
+    }
+
+    async fn download_certificate(
+        &self,
+        hash: CryptoHash,
+    ) -> Result<ConfirmedBlockCertificate, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.download_certificate(hash).await
+    }
+
+    async fn download_certificates(
+        &self,
+        hashes: Vec<CryptoHash>,
+    ) -> Result<Vec<ConfirmedBlockCertificate>, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.download_certificates(hashes).await
+    }
+
+    async fn blob_last_used_by(&self, blob_id: BlobId) -> Result<CryptoHash, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.blob_last_used_by(blob_id).await
+    }
+
+    async fn missing_blob_ids(&self, blob_ids: Vec<BlobId>) -> Result<Vec<BlobId>, NodeError> {
+        let _permit = self.acquire().await;
+        self.node.missing_blob_ids(blob_ids).await
+    }
+}
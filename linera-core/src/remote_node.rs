@@ -78,6 +78,31 @@ impl<N: ValidatorNode> RemoteNode<N> {
         self.check_and_return_info(response, chain_id)
     }
 
+    /// Processes confirmed certificates in order, and returns the chain information after each
+    /// one, or the error it caused.
+    pub(crate) async fn handle_confirmed_certificates(
+        &self,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Vec<Result<Box<ChainInfo>, NodeError>>
+    where
+        N: Sync,
+    {
+        let chain_ids = certificates
+            .iter()
+            .map(|certificate| certificate.inner().chain_id())
+            .collect::<Vec<_>>();
+        let responses = self
+            .node
+            .handle_confirmed_certificates(certificates, delivery)
+            .await;
+        responses
+            .into_iter()
+            .zip(chain_ids)
+            .map(|(response, chain_id)| self.check_and_return_info(response?, chain_id))
+            .collect()
+    }
+
     pub(crate) async fn handle_validated_certificate(
         &self,
         certificate: ValidatedBlockCertificate,
@@ -271,6 +296,7 @@ impl<N: ValidatorNode> RemoteNode<N> {
     }
 
     /// Returns the list of certificate hashes on the given chain in the given range of heights.
+    /// Responses truncated by the validator are resumed until the range is complete.
     /// Returns an error if the number of hashes does not match the size of the range.
     #[instrument(level = "trace")]
     pub(crate) async fn fetch_sent_certificate_hashes(
@@ -278,10 +304,32 @@ impl<N: ValidatorNode> RemoteNode<N> {
         chain_id: ChainId,
         range: BlockHeightRange,
     ) -> Result<Vec<CryptoHash>, NodeError> {
-        let query =
-            ChainInfoQuery::new(chain_id).with_sent_certificate_hashes_in_range(range.clone());
-        let response = self.handle_chain_info_query(query).await?;
-        let hashes = response.requested_sent_certificate_hashes;
+        let mut hashes = Vec::new();
+        let mut start = range.start;
+        loop {
+            let limit = range
+                .limit
+                .map(|limit| limit.saturating_sub(hashes.len() as u64));
+            let query = ChainInfoQuery::new(chain_id)
+                .with_sent_certificate_hashes_in_range(BlockHeightRange { start, limit });
+            let response = self.handle_chain_info_query(query).await?;
+            let received = response.requested_sent_certificate_hashes.len() as u64;
+            hashes.extend(response.requested_sent_certificate_hashes);
+            let Some(next_height) = response.requested_sent_certificate_hashes_next_height else {
+                break;
+            };
+            if received == 0 || start.try_add(BlockHeight::from(received)).ok() != Some(next_height)
+            {
+                warn!(
+                    ?start,
+                    ?next_height,
+                    received_num = received,
+                    "Validator sent an inconsistent truncated response."
+                );
+                return Err(NodeError::InvalidChainInfoResponse);
+            }
+            start = next_height;
+        }
 
         if range
             .limit
@@ -297,6 +345,34 @@ impl<N: ValidatorNode> RemoteNode<N> {
         Ok(hashes)
     }
 
+    /// Downloads the certificates of the blocks of the given chain in `range`, in order of
+    /// height. If the validator doesn't have all the blocks in the range, only the ones it has
+    /// are returned.
+    ///
+    /// The certificates are checked to match the hashes the validator reported, but their
+    /// signatures are not verified.
+    #[instrument(level = "trace")]
+    pub(crate) async fn download_certificates_in_range(
+        &self,
+        chain_id: ChainId,
+        range: BlockHeightRange,
+    ) -> Result<Vec<ConfirmedBlockCertificate>, NodeError> {
+        let hashes = self.fetch_sent_certificate_hashes(chain_id, range).await?;
+        if hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let certificates = self.download_certificates(hashes.clone()).await?;
+        ensure!(
+            certificates.len() == hashes.len()
+                && certificates
+                    .iter()
+                    .zip(&hashes)
+                    .all(|(certificate, hash)| certificate.hash() == *hash),
+            NodeError::InvalidChainInfoResponse
+        );
+        Ok(certificates)
+    }
+
     #[instrument(level = "trace")]
     pub async fn download_certificates(
         &self,
@@ -0,0 +1,337 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers to test clients against misbehaving validators, enabled by the `test-helpers`
+//! feature.
+//!
+//! A [`FaultyValidatorNode`] wraps any [`ValidatorNode`] and injects a configurable [`Fault`]
+//! into each [`RequestKind`]: it can delay or drop requests, fail them with an error, corrupt
+//! the signed chain information in its responses, or tamper with the certificates it serves.
+//! This makes it possible to test quorum edge cases, e.g. with one validator down, one slow
+//! and one Byzantine, without running real validators:
+//!
+//! ```ignore
+//! let node = FaultyValidatorNode::new(node);
+//! node.set_fault(RequestKind::ALL, Fault::Delay(Duration::from_millis(100)));
+//! node.set_fault([RequestKind::BlockProposal], Fault::CorruptResponse);
+//! // ... run the client against the node, then:
+//! node.clear_faults();
+//! ```
+//!
+//! The nodes of the test validators in [`test_utils`](crate::test_utils) are wrapped this way,
+//! and can be reached with `TestBuilder::faulty_node`.
+
+use std::{collections::HashMap, future::Future, sync::Arc, time::Duration};
+
+use futures::future;
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{BlobContent, BlockHeight, Round},
+    identifiers::{BlobId, ChainId},
+};
+use linera_chain::{
+    data_types::BlockProposal,
+    types::{
+        ConfirmedBlock, ConfirmedBlockCertificate, GenericCertificate, LiteCertificate, Timeout,
+        ValidatedBlock,
+    },
+};
+use linera_version::VersionInfo;
+
+use crate::{
+    data_types::{ChainInfoQuery, ChainInfoResponse},
+    node::{CrossChainMessageDelivery, NodeError, ValidatorNode},
+};
+
+/// A kind of request to a validator, for which a [`FaultyValidatorNode`] can inject a
+/// [`Fault`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum RequestKind {
+    /// Block proposals.
+    BlockProposal,
+    /// Lite, timeout, validated and confirmed certificates.
+    Certificate,
+    /// Chain info queries.
+    ChainInfoQuery,
+    /// Subscriptions to notifications.
+    Subscribe,
+    /// Uploads and downloads of blobs, and queries about blobs.
+    Blob,
+    /// Downloads of certificates.
+    DownloadCertificate,
+    /// Queries for the version and genesis configuration.
+    VersionInfo,
+}
+
+impl RequestKind {
+    /// All kinds of requests.
+    pub const ALL: [RequestKind; 7] = [
+        RequestKind::BlockProposal,
+        RequestKind::Certificate,
+        RequestKind::ChainInfoQuery,
+        RequestKind::Subscribe,
+        RequestKind::Blob,
+        RequestKind::DownloadCertificate,
+        RequestKind::VersionInfo,
+    ];
+}
+
+/// A misbehavior of a [`FaultyValidatorNode`].
+#[derive(Debug, Clone)]
+pub enum Fault {
+    /// Forwards the request after the given delay.
+    Delay(Duration),
+    /// Drops the request and never responds.
+    Drop,
+    /// Responds with the given error without forwarding the request.
+    Error(NodeError),
+    /// Forwards the request, but alters the chain information in the response after the
+    /// validator signed it, so that the response's signature is invalid. Has no effect on
+    /// requests that don't return chain information.
+    CorruptResponse,
+    /// Forwards the request, but changes the round of the returned certificates, so that
+    /// their signatures are invalid. Has no effect on requests that don't return
+    /// certificates.
+    TamperCertificates,
+}
+
+/// A [`ValidatorNode`] that forwards all requests to another node, except that it injects the
+/// configured [`Fault`] into each kind of request.
+///
+/// Clones share their configuration, so faults can be changed while a client uses the node.
+#[derive(Clone)]
+pub struct FaultyValidatorNode<N> {
+    node: N,
+    faults: Arc<std::sync::Mutex<HashMap<RequestKind, Fault>>>,
+}
+
+impl<N> FaultyValidatorNode<N> {
+    /// Wraps `node` without injecting any faults yet.
+    pub fn new(node: N) -> Self {
+        Self {
+            node,
+            faults: Arc::default(),
+        }
+    }
+
+    /// Returns the node that requests are forwarded to.
+    pub fn inner(&self) -> &N {
+        &self.node
+    }
+
+    /// Returns the node that requests are forwarded to.
+    pub fn inner_mut(&mut self) -> &mut N {
+        &mut self.node
+    }
+
+    /// Injects `fault` into all requests of the given kinds.
+    pub fn set_fault(&self, kinds: impl AsRef<[RequestKind]>, fault: Fault) {
+        let mut faults = self.faults.lock().unwrap();
+        for kind in kinds.as_ref() {
+            faults.insert(*kind, fault.clone());
+        }
+    }
+
+    /// Makes the node behave honestly again for all kinds of requests.
+    pub fn clear_faults(&self) {
+        self.faults.lock().unwrap().clear();
+    }
+
+    fn fault(&self, kind: RequestKind) -> Option<Fault> {
+        self.faults.lock().unwrap().get(&kind).cloned()
+    }
+
+    /// Runs the `request` to the inner node, unless the fault for its kind prevents that.
+    async fn forward<T>(
+        &self,
+        kind: RequestKind,
+        request: impl Future<Output = Result<T, NodeError>>,
+    ) -> Result<T, NodeError> {
+        match self.fault(kind) {
+            None | Some(Fault::CorruptResponse | Fault::TamperCertificates) => request.await,
+            Some(Fault::Delay(delay)) => {
+                tokio::time::sleep(delay).await;
+                request.await
+            }
+            Some(Fault::Drop) => future::pending().await,
+            Some(Fault::Error(error)) => Err(error),
+        }
+    }
+
+    /// Like `forward`, but also alters the returned chain information if the node is
+    /// configured to corrupt its responses.
+    async fn forward_info(
+        &self,
+        kind: RequestKind,
+        request: impl Future<Output = Result<ChainInfoResponse, NodeError>>,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let response = self.forward(kind, request).await?;
+        Ok(self.maybe_corrupt(kind, response))
+    }
+
+    fn maybe_corrupt(
+        &self,
+        kind: RequestKind,
+        mut response: ChainInfoResponse,
+    ) -> ChainInfoResponse {
+        if let Some(Fault::CorruptResponse) = self.fault(kind) {
+            let info = &mut response.info;
+            info.next_block_height = info.next_block_height.saturating_add(BlockHeight(1));
+        }
+        response
+    }
+
+    /// Like `forward`, but also tampers with the returned certificates if the node is
+    /// configured to.
+    async fn forward_certificates(
+        &self,
+        request: impl Future<Output = Result<Vec<ConfirmedBlockCertificate>, NodeError>>,
+    ) -> Result<Vec<ConfirmedBlockCertificate>, NodeError> {
+        let kind = RequestKind::DownloadCertificate;
+        let certificates = self.forward(kind, request).await?;
+        if let Some(Fault::TamperCertificates) = self.fault(kind) {
+            return Ok(certificates.into_iter().map(Self::tamper).collect());
+        }
+        Ok(certificates)
+    }
+
+    /// Moves the `certificate` to another round, which invalidates its signatures.
+    fn tamper(certificate: ConfirmedBlockCertificate) -> ConfirmedBlockCertificate {
+        let (value, round, signatures) = certificate.destructure();
+        let round = match round {
+            Round::SingleLeader(round) => Round::SingleLeader(round.wrapping_add(1)),
+            _ => Round::SingleLeader(0),
+        };
+        GenericCertificate::new(value, round, signatures)
+    }
+}
+
+impl<N> ValidatorNode for FaultyValidatorNode<N>
+where
+    N: ValidatorNode + Send + Sync,
+{
+    type NotificationStream = N::NotificationStream;
+
+    async fn handle_block_proposal(
+        &self,
+        proposal: BlockProposal,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let request = self.node.handle_block_proposal(proposal);
+        self.forward_info(RequestKind::BlockProposal, request).await
+    }
+
+    async fn handle_lite_certificate(
+        &self,
+        certificate: LiteCertificate<'_>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let request = self.node.handle_lite_certificate(certificate, delivery);
+        self.forward_info(RequestKind::Certificate, request).await
+    }
+
+    async fn handle_timeout_certificate(
+        &self,
+        certificate: GenericCertificate<Timeout>,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let request = self.node.handle_timeout_certificate(certificate);
+        self.forward_info(RequestKind::Certificate, request).await
+    }
+
+    async fn handle_validated_certificate(
+        &self,
+        certificate: GenericCertificate<ValidatedBlock>,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let request = self.node.handle_validated_certificate(certificate);
+        self.forward_info(RequestKind::Certificate, request).await
+    }
+
+    async fn handle_confirmed_certificate(
+        &self,
+        certificate: GenericCertificate<ConfirmedBlock>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let request = self
+            .node
+            .handle_confirmed_certificate(certificate, delivery);
+        self.forward_info(RequestKind::Certificate, request).await
+    }
+
+    async fn handle_chain_info_query(
+        &self,
+        query: ChainInfoQuery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let request = self.node.handle_chain_info_query(query);
+        self.forward_info(RequestKind::ChainInfoQuery, request)
+            .await
+    }
+
+    async fn subscribe(&self, chains: Vec<ChainId>) -> Result<Self::NotificationStream, NodeError> {
+        let request = self.node.subscribe(chains);
+        self.forward(RequestKind::Subscribe, request).await
+    }
+
+    async fn get_version_info(&self) -> Result<VersionInfo, NodeError> {
+        let request = self.node.get_version_info();
+        self.forward(RequestKind::VersionInfo, request).await
+    }
+
+    async fn get_genesis_config_hash(&self) -> Result<CryptoHash, NodeError> {
+        let request = self.node.get_genesis_config_hash();
+        self.forward(RequestKind::VersionInfo, request).await
+    }
+
+    async fn upload_blob(&self, content: BlobContent) -> Result<BlobId, NodeError> {
+        let request = self.node.upload_blob(content);
+        self.forward(RequestKind::Blob, request).await
+    }
+
+    async fn download_blob(&self, blob_id: BlobId) -> Result<BlobContent, NodeError> {
+        let request = self.node.download_blob(blob_id);
+        self.forward(RequestKind::Blob, request).await
+    }
+
+    async fn download_pending_blob(
+        &self,
+        chain_id: ChainId,
+        blob_id: BlobId,
+    ) -> Result<BlobContent, NodeError> {
+        let request = self.node.download_pending_blob(chain_id, blob_id);
+        self.forward(RequestKind::Blob, request).await
+    }
+
+    async fn handle_pending_blob(
+        &self,
+        chain_id: ChainId,
+        blob: BlobContent,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let request = self.node.handle_pending_blob(chain_id, blob);
+        self.forward_info(RequestKind::Blob, request).await
+    }
+
+    async fn download_certificate(
+        &self,
+        hash: CryptoHash,
+    ) -> Result<ConfirmedBlockCertificate, NodeError> {
+        let request = async { Ok(vec![self.node.download_certificate(hash).await?]) };
+        let mut certificates = self.forward_certificates(request).await?;
+        Ok(certificates.remove(0))
+    }
+
+    async fn download_certificates(
+        &self,
+        hashes: Vec<CryptoHash>,
+    ) -> Result<Vec<ConfirmedBlockCertificate>, NodeError> {
+        let request = self.node.download_certificates(hashes);
+        self.forward_certificates(request).await
+    }
+
+    async fn blob_last_used_by(&self, blob_id: BlobId) -> Result<CryptoHash, NodeError> {
+        let request = self.node.blob_last_used_by(blob_id);
+        self.forward(RequestKind::Blob, request).await
+    }
+
+    async fn missing_blob_ids(&self, blob_ids: Vec<BlobId>) -> Result<Vec<BlobId>, NodeError> {
+        let request = self.node.missing_blob_ids(blob_ids);
+        self.forward(RequestKind::Blob, request).await
+    }
+}
@@ -5,30 +5,45 @@
 #[path = "./wasm_client_tests.rs"]
 mod wasm;
 
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
 use assert_matches::assert_matches;
 use futures::StreamExt;
 use linera_base::{
     crypto::*,
     data_types::*,
-    identifiers::{Account, AccountOwner, ChainId, MessageId, Owner},
+    identifiers::{
+        Account, AccountOwner, ChainId, Destination, GenericApplicationId, MessageId, Owner,
+    },
     ownership::{ChainOwnership, TimeoutConfig},
 };
 use linera_chain::{
-    data_types::{IncomingBundle, Medium, MessageBundle, Origin, PostedMessage},
+    data_types::{
+        BlockProposal, ChannelFullName, IncomingBundle, Medium, MessageBundle, Origin,
+        PostedMessage, ProposedBlock,
+    },
     manager::LockingBlock,
-    types::Timeout,
+    types::{GenericCertificate, Timeout},
     ChainError, ChainExecutionContext,
 };
 use linera_execution::{
-    committee::{Committee, Epoch},
-    system::{Recipient, SystemOperation},
-    ExecutionError, Message, MessageKind, Operation, QueryOutcome, ResourceControlPolicy,
-    SystemExecutionError, SystemMessage, SystemQuery, SystemResponse,
+    committee::{Committee, CommitteeError, Epoch, ValidatorName, ValidatorState},
+    system::{Recipient, SystemChannel, SystemOperation},
+    ChannelSubscription, ExecutionError, Message, MessageKind, Operation, QueryOutcome,
+    ResourceControlPolicy, SystemExecutionError, SystemMessage, SystemQuery, SystemResponse,
 };
-use linera_storage::{DbStorage, TestClock};
+use linera_storage::{Batch, ChainExportHeader, DbStorage, Storage, TestClock};
 use linera_views::memory::MemoryStore;
 use rand::Rng;
 use test_case::test_case;
+use uuid::Uuid;
 
 #[cfg(feature = "dynamodb")]
 use crate::test_utils::DynamoDbStorageBuilder;
@@ -40,15 +55,18 @@ use crate::test_utils::ScyllaDbStorageBuilder;
 use crate::test_utils::ServiceStorageBuilder;
 use crate::{
     client::{
-        BlanketMessagePolicy, ChainClient, ChainClientError, ClientOutcome, MessageAction,
-        MessagePolicy,
+        BlanketMessagePolicy, ChainClient, ChainClientError, ChainDiagnostics, ClientMetrics,
+        ClientOutcome, FuelEstimate, InMemorySigner, KeyDerivation, MessageAction, MessagePolicy,
+        OwnershipMode, IDEMPOTENCY_RECORD_RETENTION,
     },
+    data_types::{BlockHeightRange, ChainInfoQuery},
     local_node::LocalNodeError,
     node::{
         CrossChainMessageDelivery,
         NodeError::{self, ClientIoError},
         ValidatorNode,
     },
+    test_helpers::{Fault, RequestKind},
     test_utils::{FaultType, MemoryStorageBuilder, NodeProvider, StorageBuilder, TestBuilder},
     updater::CommunicationError,
     worker::{Notification, Reason, WorkerError},
@@ -130,6 +148,57 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_receiving_transfer_with_listener<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    // Listen to the notifications on the receiver chain, without ever polling the validators.
+    let mut notifications = receiver.subscribe().await?;
+    let (listener, _listen_handle, _) = receiver.listen().await?;
+    tokio::spawn(listener);
+    // One validator drops its connection: the others must still deliver the transfer.
+    builder.node(0).drop_subscriptions().await;
+    assert!(!builder.node(0).has_subscribers(receiver.chain_id).await);
+
+    sender
+        .transfer_to_account(
+            None,
+            Amount::from_tokens(3),
+            Account::chain(receiver.chain_id),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Wait until the receiver's local node has received the transfer.
+    while let Some(notification) = notifications.next().await {
+        if matches!(notification.reason, Reason::NewIncomingBundle { origin, .. }
+            if origin.sender == sender.chain_id)
+        {
+            break;
+        }
+    }
+    assert_eq!(receiver.query_balance().await?, Amount::from_tokens(3));
+
+    // The listener subscribes to the dropped validator again.
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while !builder.node(0).has_subscribers(receiver.chain_id).await {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -265,34 +334,67 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_rotate_key_pair<B>(storage_builder: B) -> anyhow::Result<()>
+async fn test_claim_chain_balance<B>(storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
 {
-    let mut builder = TestBuilder::new(storage_builder, 4, 1)
-        .await?
-        .with_policy(ResourceControlPolicy::fuel_and_block());
-    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
-    let new_key_pair = KeyPair::generate();
-    let new_owner = Owner::from(new_key_pair.public());
-    let certificate = sender.rotate_key_pair(new_key_pair).await.unwrap().unwrap();
-    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
-    assert!(sender.pending_proposal().is_none());
-    assert_eq!(sender.identity().await.unwrap(), new_owner);
-    assert_eq!(
-        builder
-            .check_that_validators_have_certificate(sender.chain_id, BlockHeight::ZERO, 3)
-            .await
-            .unwrap(),
-        certificate
-    );
-    assert_eq!(
-        sender.local_balance().await.unwrap(),
-        Amount::from_millis(3999)
-    );
-    sender.synchronize_from_validators().await.unwrap();
-    // Can still use the chain.
-    sender.burn(None, Amount::from_tokens(3)).await.unwrap();
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let claimant = builder.add_root_chain(1, Amount::from_tokens(1)).await?;
+    let escrow = builder.add_root_chain(2, Amount::from_tokens(5)).await?;
+    let stranger = builder.add_root_chain(3, Amount::from_tokens(1)).await?;
+    let escrow_id = escrow.chain_id();
+    escrow
+        .share_ownership(claimant.identity().await?, 100)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // The stranger is not an owner of the escrow chain.
+    let stranger_cert = stranger
+        .claim_chain_balance(escrow_id, Amount::from_tokens(2))
+        .await
+        .unwrap()
+        .unwrap();
+    let claimant_cert = claimant
+        .claim_chain_balance(escrow_id, Amount::from_tokens(3))
+        .await
+        .unwrap()
+        .unwrap();
+
+    escrow
+        .receive_certificate_and_update_validators(stranger_cert)
+        .await?;
+    escrow
+        .receive_certificate_and_update_validators(claimant_cert)
+        .await?;
+    let cert = escrow.process_inbox().await?.0.pop().unwrap();
+    {
+        let bundles = &cert.block().body.incoming_bundles;
+        assert_eq!(bundles.len(), 2);
+        let action_from = |sender| {
+            bundles
+                .iter()
+                .find(|bundle| bundle.origin.sender == sender)
+                .unwrap()
+                .action
+        };
+        assert_eq!(action_from(stranger.chain_id()), MessageAction::Reject);
+        assert_eq!(action_from(claimant.chain_id()), MessageAction::Accept);
+    }
+    assert_eq!(escrow.local_balance().await?, Amount::from_tokens(2));
+
+    claimant
+        .receive_certificate_and_update_validators(cert.clone())
+        .await?;
+    claimant.process_inbox().await?;
+    assert_eq!(claimant.local_balance().await?, Amount::from_tokens(4));
+
+    // The rejected claim didn't credit the stranger.
+    stranger
+        .receive_certificate_and_update_validators(cert)
+        .await?;
+    stranger.process_inbox().await?;
+    assert_eq!(stranger.local_balance().await?, Amount::from_tokens(1));
     Ok(())
 }
 
@@ -302,40 +404,62 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_transfer_ownership<B>(storage_builder: B) -> anyhow::Result<()>
+async fn test_transfer_after<B>(storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
 {
-    let mut builder = TestBuilder::new(storage_builder, 4, 1)
-        .await?
-        .with_policy(ResourceControlPolicy::fuel_and_block());
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
     let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
-
-    let new_owner = KeyPair::generate().public().into();
-    let certificate = sender.transfer_ownership(new_owner).await.unwrap().unwrap();
-    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
-    assert!(sender.pending_proposal().is_none());
-    assert_matches!(
-        sender.key_pair().await.map(|kp| KeyPair::public(&kp)), // KeyPair isn't Debug; using PublicKey.
-        Err(ChainClientError::CannotFindKeyForChain(_))
-    );
-    assert_eq!(
-        builder
-            .check_that_validators_have_certificate(sender.chain_id, BlockHeight::ZERO, 3)
-            .await
-            .unwrap(),
-        certificate
-    );
-    assert_eq!(
-        sender.local_balance().await.unwrap(),
-        Amount::from_millis(3999)
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let not_before = BlockHeight::from(2);
+    sender.transfer_after(
+        not_before,
+        None,
+        Amount::from_tokens(3),
+        Recipient::chain(receiver.chain_id()),
     );
-    sender.synchronize_from_validators().await.unwrap();
-    // Cannot use the chain any more.
+
+    // Nothing is due yet.
+    let certificates = sender.submit_due_blocks().await.unwrap().unwrap();
+    assert!(certificates.is_empty());
+    assert_eq!(sender.scheduled_blocks().len(), 1);
+
+    // Proposing the timelocked block too early fails with a typed error.
+    let operations = sender.scheduled_blocks()[0].operations.clone();
     assert_matches!(
-        sender.burn(None, Amount::from_tokens(3)).await,
-        Err(ChainClientError::CannotFindKeyForChain(_))
+        sender.execute_operations(operations, vec![]).await,
+        Err(ChainClientError::LocalNodeError(LocalNodeError::WorkerError(
+            WorkerError::ChainError(error)
+        ))) if matches!(&*error, ChainError::ExecutionError(
+            execution_error, ChainExecutionContext::Operation(0)
+        ) if matches!(**execution_error, ExecutionError::SystemError(
+            SystemExecutionError::TimelockNotReached { not_before: height, .. }
+        ) if height == not_before))
     );
+    assert!(sender.pending_proposal().is_none());
+
+    sender.burn(None, Amount::ONE).await.unwrap().unwrap();
+    let certificates = sender.submit_due_blocks().await.unwrap().unwrap();
+    assert!(certificates.is_empty());
+
+    sender.burn(None, Amount::ONE).await.unwrap().unwrap();
+    assert_eq!(sender.next_block_height(), not_before);
+
+    // A restarted client that is given the saved scheduled blocks submits them.
+    let sender = builder
+        .restart_client_with_metrics(&sender, CountingMetrics::default())
+        .await?;
+    assert_eq!(sender.scheduled_blocks().len(), 1);
+    let certificates = sender.submit_due_blocks().await.unwrap().unwrap();
+    assert_eq!(certificates.len(), 1);
+    assert_eq!(certificates[0].block().header.height, not_before);
+    assert!(sender.scheduled_blocks().is_empty());
+
+    receiver
+        .receive_certificate_and_update_validators(certificates[0].clone())
+        .await?;
+    receiver.process_inbox().await?;
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(3));
     Ok(())
 }
 
@@ -345,102 +469,58 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_share_ownership<B>(storage_builder: B) -> anyhow::Result<()>
+async fn test_transfer_with_faulty_nodes<B>(storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
 {
     let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
     let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
-    let new_key_pair = KeyPair::generate();
-    let new_owner = new_key_pair.public().into();
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let receiver_id = receiver.chain_id();
+
+    // One validator is down and another one is slow.
+    builder
+        .faulty_node(0)
+        .set_fault(RequestKind::ALL, Fault::Drop);
+    builder
+        .faulty_node(1)
+        .set_fault(RequestKind::ALL, Fault::Delay(Duration::from_millis(100)));
     let certificate = sender
-        .share_ownership(new_owner, 100)
+        .transfer(None, Amount::ONE, Recipient::chain(receiver_id))
         .await
         .unwrap()
         .unwrap();
-    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
-    assert!(sender.pending_proposal().is_none());
-    assert!(sender.key_pair().await.is_ok());
-    assert_eq!(
-        builder
-            .check_that_validators_have_certificate(sender.chain_id, BlockHeight::ZERO, 3)
-            .await
-            .unwrap(),
-        certificate
-    );
-    assert_eq!(
-        sender.local_balance().await.unwrap(),
-        Amount::from_tokens(4)
-    );
-    sender.synchronize_from_validators().await.unwrap();
-    // Can still use the chain with the old client.
-    sender.burn(None, Amount::from_tokens(2)).await.unwrap();
-    assert_eq!(sender.next_block_height(), BlockHeight::from(2));
-    // Make a client to try the new key.
-    let client = builder
-        .make_client(
-            sender.chain_id,
-            new_key_pair,
-            sender.block_hash(),
-            BlockHeight::from(2),
-        )
-        .await?;
-    // Local balance fails because the client has block height 2 but we haven't downloaded
-    // the blocks yet.
-    assert_matches!(
-        client.local_balance().await,
-        Err(ChainClientError::WalletSynchronizationError)
-    );
-    client.synchronize_from_validators().await.unwrap();
-    assert_eq!(
-        client.local_balance().await.unwrap(),
-        Amount::from_tokens(2)
-    );
-
-    // We need at least three validators for making an operation.
-    builder.set_fault_type([0, 1], FaultType::Offline).await;
-    let result = client.burn(None, Amount::ONE).await;
-    assert_matches!(
-        result,
-        Err(ChainClientError::CommunicationError(
-            CommunicationError::Trusted(ClientIoError { .. }),
-        ))
-    );
-    builder.set_fault_type([0, 1], FaultType::Honest).await;
-    builder.set_fault_type([2, 3], FaultType::Offline).await;
-    assert_matches!(
-        sender.burn(None, Amount::ONE).await,
-        Err(ChainClientError::CommunicationError(
-            CommunicationError::Trusted(ClientIoError { .. })
-        ))
-    );
-
-    // Half the validators voted for one block, half for the other. We need to make a proposal in
-    // the next round to succeed.
+    assert_eq!(certificate.block().header.height, BlockHeight::ZERO);
     builder
-        .set_fault_type([0, 1, 2, 3], FaultType::Honest)
-        .await;
-    client.synchronize_from_validators().await.unwrap();
-    client.process_inbox().await.unwrap();
-    assert_eq!(
-        client.local_balance().await.unwrap(),
-        Amount::from_tokens(2)
-    );
-    client.clear_pending_proposal();
-    client.burn(None, Amount::ONE).await.unwrap().unwrap();
+        .check_that_validators_have_certificate(sender.chain_id(), BlockHeight::ZERO, 3)
+        .await
+        .unwrap();
 
-    // The other client doesn't know the new round number yet:
-    sender.synchronize_from_validators().await.unwrap();
-    sender.process_inbox().await.unwrap();
-    assert_eq!(sender.local_balance().await.unwrap(), Amount::ONE);
-    sender.clear_pending_proposal();
-    sender.burn(None, Amount::ONE).await.unwrap();
+    // The first validator is back, but another one is Byzantine. Its responses are rejected,
+    // so the client needs the slow validator for a quorum.
+    builder.faulty_node(0).clear_faults();
+    builder
+        .faulty_node(3)
+        .set_fault(RequestKind::ALL, Fault::CorruptResponse);
+    let certificate = sender
+        .transfer(None, Amount::ONE, Recipient::chain(receiver_id))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(certificate.block().header.height, BlockHeight::from(1));
+    builder
+        .check_that_validators_have_certificate(sender.chain_id(), BlockHeight::from(1), 3)
+        .await
+        .unwrap();
 
-    // That's it, we spent all our money on this test!
-    assert_eq!(sender.local_balance().await.unwrap(), Amount::ZERO);
-    client.synchronize_from_validators().await.unwrap();
-    client.process_inbox().await.unwrap();
-    assert_eq!(client.local_balance().await.unwrap(), Amount::ZERO);
+    // A validator that returns errors doesn't keep the receiver from synchronizing.
+    let offline = Fault::Error(ClientIoError {
+        error: "offline".to_string(),
+    });
+    builder.faulty_node(3).set_fault(RequestKind::ALL, offline);
+    receiver.synchronize_from_validators().await?;
+    receiver.process_inbox().await?;
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(2));
     Ok(())
 }
 
@@ -450,59 +530,64 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_open_chain_then_close_it<B>(storage_builder: B) -> anyhow::Result<()>
+async fn test_transfer_with_idempotency<B>(storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
 {
-    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
-    // New chains use the admin chain to verify their creation certificate.
-    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
     let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
-    let new_key_pair = KeyPair::generate();
-    // Open the new chain.
-    let (message_id, certificate) = sender
-        .open_chain(
-            ChainOwnership::single(new_key_pair.public().into()),
-            ApplicationPermissions::default(),
-            Amount::ZERO,
-        )
+    let receiver_id = builder.add_root_chain(2, Amount::ZERO).await?.chain_id();
+    let key = Uuid::from_u128(1);
+
+    // Without a quorum, the transfer fails after its block was recorded.
+    let offline = Fault::Error(ClientIoError {
+        error: "offline".to_string(),
+    });
+    for i in 0..2 {
+        builder
+            .faulty_node(i)
+            .set_fault(RequestKind::ALL, offline.clone());
+    }
+    assert!(sender
+        .transfer_with_idempotency(key, None, Amount::ONE, Recipient::chain(receiver_id))
+        .await
+        .is_err());
+
+    // Retrying commits the recorded block instead of proposing the transfer again.
+    for i in 0..2 {
+        builder.faulty_node(i).clear_faults();
+    }
+    let certificate = sender
+        .transfer_with_idempotency(key, None, Amount::ONE, Recipient::chain(receiver_id))
         .await
         .unwrap()
         .unwrap();
+    assert_eq!(certificate.block().header.height, BlockHeight::ZERO);
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(3));
 
-    // Regression test for #2869.
-    let sub_message_id = MessageId {
-        index: message_id.index + 1,
-        ..message_id
-    };
-    assert_matches!(
-        certificate.block().messages()[0][sub_message_id.index as usize].message,
-        Message::System(SystemMessage::Subscribe { .. })
-    );
-    assert_eq!(
-        sender
-            .client
-            .local_node()
-            .certificate_for(&sub_message_id)
-            .await
-            .unwrap(),
-        certificate
-    );
-
+    // Once committed, the same key returns the same certificate.
+    let repeated = sender
+        .transfer_with_idempotency(key, None, Amount::ONE, Recipient::chain(receiver_id))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(repeated.hash(), certificate.hash());
     assert_eq!(sender.next_block_height(), BlockHeight::from(1));
-    assert!(sender.pending_proposal().is_none());
-    assert!(sender.key_pair().await.is_ok());
-    // Make a client to try the new chain.
-    let new_id = ChainId::child(message_id);
-    let client = builder
-        .make_client(new_id, new_key_pair, None, BlockHeight::ZERO)
-        .await?;
-    client
-        .receive_certificate_and_update_validators(certificate)
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(3));
+
+    // A different key sends the money again.
+    let certificate = sender
+        .transfer_with_idempotency(
+            Uuid::from_u128(2),
+            None,
+            Amount::ONE,
+            Recipient::chain(receiver_id),
+        )
         .await
+        .unwrap()
         .unwrap();
-    assert_eq!(client.query_balance().await.unwrap(), Amount::ZERO);
-    client.close_chain().await.unwrap();
+    assert_eq!(certificate.block().header.height, BlockHeight::from(1));
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(2));
     Ok(())
 }
 
@@ -512,78 +597,127 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_transfer_then_open_chain<B>(storage_builder: B) -> anyhow::Result<()>
+async fn test_transfer_with_idempotency_after_restart<B>(storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
 {
-    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
-    // New chains use the admin chain to verify their creation certificate.
-    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let clock = storage_builder.clock().clone();
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
     let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
-    let parent = builder.add_root_chain(2, Amount::ZERO).await?;
-    let new_key_pair = KeyPair::generate();
-    let new_id = ChainId::child(MessageId {
-        chain_id: parent.chain_id(),
-        height: BlockHeight::ZERO,
-        index: 0,
+    let chain_id = sender.chain_id();
+    let recipient = Recipient::chain(builder.add_root_chain(2, Amount::ZERO).await?.chain_id());
+    let key = Uuid::from_u128(1);
+
+    // Without a quorum, the transfer fails after its block was recorded.
+    let offline = Fault::Error(ClientIoError {
+        error: "offline".to_string(),
     });
-    // Transfer before creating the chain. The validators will ignore the cross-chain messages.
-    sender
-        .transfer_to_account(None, Amount::from_tokens(2), Account::chain(new_id))
+    for i in 0..2 {
+        builder
+            .faulty_node(i)
+            .set_fault(RequestKind::ALL, offline.clone());
+    }
+    assert!(sender
+        .transfer_with_idempotency(key, None, Amount::ONE, recipient)
         .await
+        .is_err());
+    let record = sender
+        .storage_client()
+        .read_idempotency_record(chain_id, key.as_u128())
+        .await?
         .unwrap();
-    // Open the new chain.
-    let (open_chain_message_id, certificate) = parent
-        .open_chain(
-            ChainOwnership::single(new_key_pair.public().into()),
-            ApplicationPermissions::default(),
-            Amount::ZERO,
-        )
+
+    // A restarted client doesn't have the pending block, but still commits the recorded one.
+    for i in 0..2 {
+        builder.faulty_node(i).clear_faults();
+    }
+    let restarted = builder
+        .make_clients_with_storage(sender.storage_client(), &[&sender])
+        .await?
+        .remove(0);
+    let certificate = restarted
+        .transfer_with_idempotency(key, None, Amount::ONE, recipient)
         .await
         .unwrap()
         .unwrap();
-    let new_id2 = ChainId::child(open_chain_message_id);
-    assert_eq!(new_id, new_id2);
-    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
-    assert_eq!(parent.next_block_height(), BlockHeight::from(1));
-    assert!(sender.pending_proposal().is_none());
-    assert!(sender.key_pair().await.is_ok());
-    assert_matches!(
-        certificate.block().body.operations[open_chain_message_id.index as usize],
-        Operation::System(SystemOperation::OpenChain(_)),
-        "Unexpected certificate value",
-    );
-    assert_eq!(
-        builder
-            .check_that_validators_have_certificate(parent.chain_id, BlockHeight::from(0), 3)
-            .await
-            .unwrap(),
-        certificate
+    assert_eq!(certificate.hash(), record.block_hash);
+    assert_eq!(restarted.next_block_height(), BlockHeight::from(1));
+    assert_eq!(restarted.local_balance().await?, Amount::from_tokens(3));
+
+    // The key can't be used for a different transfer.
+    let result = restarted
+        .transfer_with_idempotency(key, None, Amount::from_tokens(2), recipient)
+        .await;
+    assert_matches!(result, Err(ChainClientError::IdempotencyKeyReused(reused)) if reused == key);
+    assert_eq!(restarted.local_balance().await?, Amount::from_tokens(3));
+
+    // Records older than the retention period are deleted when a new one is written.
+    let retention = TimeDelta::from_duration(IDEMPOTENCY_RECORD_RETENTION);
+    clock.set(
+        record
+            .timestamp
+            .saturating_add(retention)
+            .saturating_add_micros(1),
     );
-    // Make a client to try the new chain.
-    let client = builder
-        .make_client(new_id, new_key_pair, None, BlockHeight::ZERO)
-        .await?;
-    client
-        .receive_certificate_and_update_validators(certificate)
+    let other_key = Uuid::from_u128(2);
+    restarted
+        .transfer_with_idempotency(other_key, None, Amount::ONE, recipient)
         .await
+        .unwrap()
         .unwrap();
-    // Make another block on top of the one that sent the two tokens, so that the validators
-    // process the cross-chain messages.
-    let certificate2 = sender
-        .transfer_to_account(None, Amount::from_tokens(1), Account::chain(new_id))
+    let storage = restarted.storage_client();
+    assert!(storage
+        .read_idempotency_record(chain_id, key.as_u128())
+        .await?
+        .is_none());
+    assert!(storage
+        .read_idempotency_record(chain_id, other_key.as_u128())
+        .await?
+        .is_some());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_burn_checked<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+
+    // 10% of the balance is 0.4 tokens.
+    assert_matches!(
+        sender.burn_checked(None, Amount::ONE, 1_000).await,
+        Err(ChainClientError::BurnExceedsSafetyLimit { amount, balance, max_fraction_bps: 1_000 })
+            if amount == Amount::ONE && balance == Amount::from_tokens(4)
+    );
+    assert_eq!(sender.next_block_height(), BlockHeight::ZERO);
+
+    sender
+        .burn_checked(None, Amount::from_millis(400), 1_000)
         .await
         .unwrap()
         .unwrap();
-    client
-        .receive_certificate_and_update_validators(certificate2)
+    assert_eq!(sender.local_balance().await?, Amount::from_millis(3_600));
+
+    // Limits above 100% allow burning the whole balance, but not more.
+    assert_matches!(
+        sender
+            .burn_checked(None, Amount::from_tokens(4), u16::MAX)
+            .await,
+        Err(ChainClientError::BurnExceedsSafetyLimit { .. })
+    );
+    sender
+        .burn_checked(None, Amount::from_millis(3_600), u16::MAX)
         .await
+        .unwrap()
         .unwrap();
-    assert_eq!(
-        client.query_balance().await.unwrap(),
-        Amount::from_tokens(3)
-    );
-    client.burn(None, Amount::from_tokens(3)).await.unwrap();
+    assert_eq!(sender.local_balance().await?, Amount::ZERO);
     Ok(())
 }
 
@@ -593,70 +727,38 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_open_chain_must_be_first<B>(storage_builder: B) -> anyhow::Result<()>
+async fn test_synchronize_balance_from<B>(storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
 {
-    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
-    // New chains use the admin chain to verify their creation certificate.
-    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
     let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
-    let new_key_pair = KeyPair::generate();
-    let new_id = ChainId::child(MessageId {
-        chain_id: sender.chain_id(),
-        height: BlockHeight::from(1),
-        index: 0,
-    });
-    // Transfer before creating the chain.
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let validators = (0..4).map(|i| builder.node(i).name()).collect::<Vec<_>>();
     sender
-        .transfer_to_account(None, Amount::from_tokens(3), Account::chain(new_id))
-        .await
-        .unwrap();
-    // Open the new chain.
-    let (open_chain_message_id, certificate) = sender
-        .open_chain(
-            ChainOwnership::single(new_key_pair.public().into()),
-            ApplicationPermissions::default(),
-            Amount::ZERO,
+        .transfer_to_account(
+            None,
+            Amount::from_tokens(3),
+            Account::chain(receiver.chain_id),
         )
         .await
         .unwrap()
         .unwrap();
-    let new_id2 = ChainId::child(open_chain_message_id);
-    assert_eq!(new_id, new_id2);
-    assert_eq!(sender.next_block_height(), BlockHeight::from(2));
-    assert!(sender.pending_proposal().is_none());
-    assert!(sender.key_pair().await.is_ok());
+
+    // Two of the four validators can't reach a quorum.
     assert_matches!(
-        certificate.block().body.operations[open_chain_message_id.index as usize],
-        Operation::System(SystemOperation::OpenChain(_)),
-        "Unexpected certificate value",
+        receiver.synchronize_balance_from(&validators[..2]).await,
+        Err(ChainClientError::ValidatorSubsetBelowQuorum {
+            weight: 2,
+            quorum_threshold: 3,
+        })
     );
+
+    // The validator outside of the subset is not needed.
+    builder.set_fault_type([3], FaultType::Offline).await;
     assert_eq!(
-        builder
-            .check_that_validators_have_certificate(sender.chain_id, BlockHeight::from(1), 3)
-            .await
-            .unwrap(),
-        certificate
-    );
-    // Make a client to try the new chain.
-    let client = builder
-        .make_client(new_id, new_key_pair, None, BlockHeight::ZERO)
-        .await?;
-    client
-        .receive_certificate_and_update_validators(certificate)
-        .await
-        .unwrap();
-    let result = client.burn(None, Amount::from_tokens(3)).await;
-    assert_matches!(
-        result,
-        Err(ChainClientError::LocalNodeError(
-            LocalNodeError::WorkerError(WorkerError::ChainError(error))
-        )) if matches!(&*error, ChainError::CannotSkipMessage { bundle, .. }
-            if matches!(&**bundle, MessageBundle { messages, .. }
-            if matches!(messages[..], [PostedMessage {
-                message: Message::System(SystemMessage::Credit { .. }), ..
-        }])))
+        receiver.synchronize_balance_from(&validators[..3]).await?,
+        Amount::from_tokens(3)
     );
     Ok(())
 }
@@ -667,53 +769,34 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_open_chain_then_transfer<B>(storage_builder: B) -> anyhow::Result<()>
+async fn test_outbox_status_with_offline_validator<B>(storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
 {
-    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
-    // New chains use the admin chain to verify their creation certificate.
-    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
     let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
-    let new_key_pair = KeyPair::generate();
-    // Open the new chain. We are both regular and super owner.
-    let ownership = ChainOwnership::single(new_key_pair.public().into())
-        .with_regular_owner(new_key_pair.public().into(), 100);
-    let (message_id, creation_certificate) = sender
-        .open_chain(ownership, ApplicationPermissions::default(), Amount::ZERO)
-        .await
-        .unwrap()
-        .unwrap();
-    let new_id = ChainId::child(message_id);
-    // Transfer after creating the chain.
-    let transfer_certificate = sender
-        .transfer_to_account(None, Amount::from_tokens(3), Account::chain(new_id))
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver.chain_id()))
         .await
         .unwrap()
         .unwrap();
-    assert_eq!(sender.next_block_height(), BlockHeight::from(2));
-    assert!(sender.pending_proposal().is_none());
-    assert!(sender.key_pair().await.is_ok());
-    // Make a client to try the new chain.
-    let client = builder
-        .make_client(new_id, new_key_pair, None, BlockHeight::ZERO)
-        .await?;
-    // Must process the creation certificate before using the new chain.
-    client
-        .receive_certificate_and_update_validators(creation_certificate)
-        .await
-        .unwrap();
-    assert_eq!(client.local_balance().await.unwrap(), Amount::ZERO);
-    client
-        .receive_certificate_and_update_validators(transfer_certificate)
-        .await
-        .unwrap();
-    assert_eq!(
-        client.query_balance().await.unwrap(),
-        Amount::from_tokens(3)
-    );
-    client.burn(None, Amount::from_tokens(3)).await.unwrap();
-    assert_eq!(client.local_balance().await.unwrap(), Amount::ZERO);
+
+    // The offline validator's error doesn't hide the other validators' outboxes.
+    builder.set_fault_type([3], FaultType::Offline).await;
+    let offline_name = builder.node(3).name();
+    let statuses = sender.outbox_status().await?;
+    assert_eq!(statuses.len(), 4);
+    for (name, result) in statuses {
+        if name == offline_name {
+            assert_matches!(result, Err(ClientIoError { .. }));
+        } else {
+            let outboxes = result?;
+            assert!(outboxes
+                .keys()
+                .all(|target| target.recipient == receiver.chain_id()));
+        }
+    }
     Ok(())
 }
 
@@ -723,89 +806,2905 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_close_chain<B>(storage_builder: B) -> anyhow::Result<()>
+async fn test_rotate_key_pair<B>(storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
 {
     let mut builder = TestBuilder::new(storage_builder, 4, 1)
         .await?
-        .with_policy(ResourceControlPolicy::all_categories());
-    let client1 = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
-    let client2 = builder.add_root_chain(2, Amount::from_tokens(4)).await?;
-
-    let certificate = client1.close_chain().await.unwrap().unwrap().unwrap();
-    assert_matches!(
-        certificate.block().body.operations[..],
-        [Operation::System(SystemOperation::CloseChain)],
-        "Unexpected certificate value",
-    );
-    assert_eq!(client1.next_block_height(), BlockHeight::from(1));
-    assert!(client1.pending_proposal().is_none());
-    assert!(client1.key_pair().await.is_ok());
+        .with_policy(ResourceControlPolicy::fuel_and_block());
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let new_key_pair = KeyPair::generate();
+    let new_owner = Owner::from(new_key_pair.public());
+    let certificate = sender.rotate_key_pair(new_key_pair).await.unwrap().unwrap();
+    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
+    assert!(sender.pending_proposal().is_none());
+    assert_eq!(sender.identity().await.unwrap(), new_owner);
     assert_eq!(
         builder
-            .check_that_validators_have_certificate(client1.chain_id, BlockHeight::ZERO, 3)
+            .check_that_validators_have_certificate(sender.chain_id, BlockHeight::ZERO, 3)
             .await
             .unwrap(),
         certificate
     );
-    // Cannot use the chain for operations any more.
-    let result = client1.burn(None, Amount::from_tokens(3)).await;
-    assert!(
-        matches!(
-            &result,
-            Err(ChainClientError::LocalNodeError(
-                LocalNodeError::WorkerError(WorkerError::ChainError(err))
-            )) if matches!(**err, ChainError::ClosedChain)
-        ),
-        "Unexpected result: {:?}",
-        result,
+    assert_eq!(
+        sender.local_balance().await.unwrap(),
+        Amount::from_millis(3999)
     );
+    sender.synchronize_from_validators().await.unwrap();
+    // Can still use the chain.
+    sender.burn(None, Amount::from_tokens(3)).await.unwrap();
+    Ok(())
+}
 
-    // Incoming messages now get rejected.
-    client2
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_transfers_between_signature_schemes<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let key_pair = Secp256k1KeyPair::generate();
+    let owner = Owner::from(key_pair.public());
+    let secp256k1_client = builder
+        .add_root_chain_with_key(1, Amount::from_tokens(4), key_pair)
+        .await?;
+    let ed25519_client = builder.add_root_chain(2, Amount::ZERO).await?;
+    assert_eq!(secp256k1_client.identity().await?, owner);
+
+    // A chain owned by a secp256k1 key sends tokens to a chain owned by an ed25519 key.
+    let cert = secp256k1_client
         .transfer_to_account(
             None,
             Amount::from_tokens(3),
-            Account::chain(client1.chain_id()),
+            Account::chain(ed25519_client.chain_id()),
         )
         .await
         .unwrap()
         .unwrap();
-    client1.synchronize_from_validators().await.unwrap();
-    let (certificates, _) = client1.process_inbox().await.unwrap();
-    let block = certificates[0].block();
-    assert!(block.body.operations.is_empty());
-    assert_eq!(block.body.incoming_bundles.len(), 1);
+    ed25519_client
+        .receive_certificate_and_update_validators(cert)
+        .await?;
+    assert_eq!(ed25519_client.process_inbox().await?.0.len(), 1);
+    assert_eq!(
+        ed25519_client.local_balance().await?,
+        Amount::from_tokens(3)
+    );
+
+    // And back.
+    let cert = ed25519_client
+        .transfer_to_account(
+            None,
+            Amount::from_tokens(2),
+            Account::chain(secp256k1_client.chain_id()),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    secp256k1_client
+        .receive_certificate_and_update_validators(cert)
+        .await?;
+    assert_eq!(secp256k1_client.process_inbox().await?.0.len(), 1);
+    assert_eq!(
+        secp256k1_client.local_balance().await?,
+        Amount::from_tokens(3)
+    );
+    assert_eq!(
+        ed25519_client.local_balance().await?,
+        Amount::from_tokens(1)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_transfer_ownership<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1)
+        .await?
+        .with_policy(ResourceControlPolicy::fuel_and_block());
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+
+    let new_owner = KeyPair::generate().public().into();
+    let certificate = sender.transfer_ownership(new_owner).await.unwrap().unwrap();
+    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
+    assert!(sender.pending_proposal().is_none());
     assert_matches!(
-        &block.body.incoming_bundles[0],
-        IncomingBundle {
-            origin: Origin { sender, medium: Medium::Direct },
-            action: MessageAction::Reject,
-            bundle: MessageBundle {
-                messages,
-                ..
-            },
-        } if *sender == client2.chain_id() && matches!(messages[..],
-            [PostedMessage {
-                message: Message::System(SystemMessage::Credit { .. }),
-                kind: MessageKind::Tracked,
-                ..
-            }]
+        sender.key_pair().await.map(|kp| kp.public()), // KeyPair isn't Debug; using PublicKey.
+        Err(ChainClientError::CannotFindKeyForChain(_))
+    );
+    assert_eq!(
+        builder
+            .check_that_validators_have_certificate(sender.chain_id, BlockHeight::ZERO, 3)
+            .await
+            .unwrap(),
+        certificate
+    );
+    assert_eq!(
+        sender.local_balance().await.unwrap(),
+        Amount::from_millis(3999)
+    );
+    sender.synchronize_from_validators().await.unwrap();
+    // Cannot use the chain any more.
+    assert_matches!(
+        sender.burn(None, Amount::from_tokens(3)).await,
+        Err(ChainClientError::CannotFindKeyForChain(_))
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_share_ownership<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let new_key_pair = KeyPair::generate();
+    let new_owner = new_key_pair.public().into();
+    let certificate = sender
+        .share_ownership(new_owner, 100)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
+    assert!(sender.pending_proposal().is_none());
+    assert!(sender.key_pair().await.is_ok());
+    assert_eq!(
+        builder
+            .check_that_validators_have_certificate(sender.chain_id, BlockHeight::ZERO, 3)
+            .await
+            .unwrap(),
+        certificate
+    );
+    assert_eq!(
+        sender.local_balance().await.unwrap(),
+        Amount::from_tokens(4)
+    );
+    sender.synchronize_from_validators().await.unwrap();
+    // Can still use the chain with the old client.
+    sender.burn(None, Amount::from_tokens(2)).await.unwrap();
+    assert_eq!(sender.next_block_height(), BlockHeight::from(2));
+    // Make a client to try the new key.
+    let client = builder
+        .make_client(
+            sender.chain_id,
+            new_key_pair,
+            sender.block_hash(),
+            BlockHeight::from(2),
         )
+        .await?;
+    // Local balance fails because the client has block height 2 but we haven't downloaded
+    // the blocks yet.
+    assert_matches!(
+        client.local_balance().await,
+        Err(ChainClientError::WalletSynchronizationError)
+    );
+    client.synchronize_from_validators().await.unwrap();
+    assert_eq!(
+        client.local_balance().await.unwrap(),
+        Amount::from_tokens(2)
+    );
+
+    // We need at least three validators for making an operation.
+    builder.set_fault_type([0, 1], FaultType::Offline).await;
+    let result = client.burn(None, Amount::ONE).await;
+    assert_matches!(
+        result,
+        Err(ChainClientError::CommunicationError(
+            CommunicationError::Trusted(ClientIoError { .. }),
+        ))
+    );
+    builder.set_fault_type([0, 1], FaultType::Honest).await;
+    builder.set_fault_type([2, 3], FaultType::Offline).await;
+    assert_matches!(
+        sender.burn(None, Amount::ONE).await,
+        Err(ChainClientError::CommunicationError(
+            CommunicationError::Trusted(ClientIoError { .. })
+        ))
+    );
+
+    // Half the validators voted for one block, half for the other. We need to make a proposal in
+    // the next round to succeed.
+    builder
+        .set_fault_type([0, 1, 2, 3], FaultType::Honest)
+        .await;
+    client.synchronize_from_validators().await.unwrap();
+    client.process_inbox().await.unwrap();
+    assert_eq!(
+        client.local_balance().await.unwrap(),
+        Amount::from_tokens(2)
+    );
+    client.clear_pending_proposal();
+    client.burn(None, Amount::ONE).await.unwrap().unwrap();
+
+    // The other client doesn't know the new round number yet:
+    sender.synchronize_from_validators().await.unwrap();
+    sender.process_inbox().await.unwrap();
+    assert_eq!(sender.local_balance().await.unwrap(), Amount::ONE);
+    sender.clear_pending_proposal();
+    sender.burn(None, Amount::ONE).await.unwrap();
+
+    // That's it, we spent all our money on this test!
+    assert_eq!(sender.local_balance().await.unwrap(), Amount::ZERO);
+    client.synchronize_from_validators().await.unwrap();
+    client.process_inbox().await.unwrap();
+    assert_eq!(client.local_balance().await.unwrap(), Amount::ZERO);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_ownership_mode<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let client = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let owner = client.identity().await?;
+    assert_eq!(client.ownership_mode().await?, OwnershipMode::Single(owner));
+
+    let new_owner = Owner::from(KeyPair::generate().public());
+    client
+        .share_ownership(new_owner, 100)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut owners = vec![owner, new_owner];
+    owners.sort();
+    assert_eq!(client.ownership_mode().await?, OwnershipMode::Multi(owners));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_owner_limits<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let new_key_pair = KeyPair::generate();
+    let new_owner = new_key_pair.public().into();
+    sender
+        .share_ownership(new_owner, 100)
+        .await
+        .unwrap()
+        .unwrap();
+    sender
+        .set_owner_limit(new_owner, Some(Amount::ONE))
+        .await
+        .unwrap()
+        .unwrap();
+    let client = builder
+        .make_client(
+            sender.chain_id,
+            new_key_pair.copy(),
+            sender.block_hash(),
+            BlockHeight::from(2),
+        )
+        .await?;
+    client.synchronize_from_validators().await.unwrap();
+
+    // The limited owner cannot transfer more than its limit in a block.
+    let recipient = Account::chain(receiver.chain_id);
+    let amount = Amount::from_tokens(2);
+    assert_matches!(
+        client.transfer_to_account(None, amount, recipient).await,
+        Err(ChainClientError::LocalNodeError(LocalNodeError::WorkerError(
+            WorkerError::ChainError(error)
+        ))) if matches!(&*error, ChainError::ExecutionError(
+            execution_error, ChainExecutionContext::Operation(0)
+        ) if matches!(**execution_error, ExecutionError::SystemError(
+            SystemExecutionError::OwnerLimitExceeded { owner, limit, amount: transferred }
+        ) if owner == new_owner && limit == Amount::ONE && transferred == amount))
+    );
+    assert!(client.pending_proposal().is_none());
+
+    // The validators reject the proposal, too.
+    let info = client.chain_info().await?;
+    let block = ProposedBlock {
+        epoch: info.epoch.unwrap(),
+        chain_id: sender.chain_id,
+        incoming_bundles: vec![],
+        operations: vec![Operation::System(SystemOperation::Transfer {
+            owner: None,
+            recipient: Recipient::Account(recipient),
+            amount,
+        })],
+        previous_block_hash: info.block_hash,
+        height: info.next_block_height,
+        authenticated_signer: Some(new_owner),
+        timestamp: info.timestamp,
+    };
+    let round = builder
+        .node(0)
+        .chain_info_with_manager_values(sender.chain_id)
+        .await?
+        .manager
+        .current_round;
+    let proposal = BlockProposal::new_initial(round, block, &new_key_pair.into());
+    assert_matches!(
+        builder.node(0).handle_block_proposal(proposal).await,
+        Err(NodeError::WorkerError { error }) if error.contains("its limit is 1.")
+    );
+
+    // An owner without a limit can make the same transfer.
+    sender.synchronize_from_validators().await.unwrap();
+    sender
+        .transfer_to_account(None, amount, recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    receiver.synchronize_from_validators().await.unwrap();
+    receiver.process_inbox().await.unwrap();
+    assert_eq!(receiver.local_balance().await.unwrap(), amount);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_approval_threshold<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let new_key_pair = KeyPair::generate();
+    let new_owner = new_key_pair.public().into();
+    sender
+        .share_ownership(new_owner, 100)
+        .await
+        .unwrap()
+        .unwrap();
+    sender
+        .change_approval_threshold(Some(2))
+        .await
+        .unwrap()
+        .unwrap();
+    let client = builder
+        .make_client(
+            sender.chain_id,
+            new_key_pair,
+            sender.block_hash(),
+            BlockHeight::from(2),
+        )
+        .await?;
+    client.synchronize_from_validators().await.unwrap();
+
+    // A block signed by a single owner is not enough anymore.
+    assert_matches!(
+        sender.burn(None, Amount::ONE).await,
+        Err(ChainClientError::LocalNodeError(
+            LocalNodeError::WorkerError(WorkerError::InsufficientApprovals {
+                approvals: 1,
+                threshold: 2
+            })
+        ))
+    );
+    sender.clear_pending_proposal();
+
+    let operation = Operation::System(SystemOperation::Transfer {
+        owner: None,
+        recipient: Recipient::Burn,
+        amount: Amount::ONE,
+    });
+    let mut proposal = sender
+        .propose_unsigned(vec![operation])
+        .await
+        .unwrap()
+        .unwrap();
+    assert_matches!(
+        builder.node(0).handle_block_proposal(proposal.clone()).await,
+        Err(NodeError::WorkerError { error }) if error.contains("requires 2")
+    );
+
+    // Once the other owner signed the proposal, the block can be certified. If that fails,
+    // it remains pending together with the signatures, so that it can be retried.
+    client.add_signature(&mut proposal).await.unwrap();
+    builder.set_fault_type([2, 3], FaultType::Offline).await;
+    assert!(sender.submit_cosigned(proposal).await.is_err());
+    assert_eq!(
+        sender
+            .pending_proposal()
+            .as_ref()
+            .map(|pending| pending.cosignatures.len()),
+        Some(1)
+    );
+    builder.set_fault_type([2, 3], FaultType::Honest).await;
+    let certificate = sender
+        .process_pending_block()
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(sender.pending_proposal().is_none());
+    assert_eq!(certificate.block().header.height, BlockHeight::from(2));
+    assert_eq!(sender.next_block_height(), BlockHeight::from(3));
+    assert_eq!(
+        builder
+            .check_that_validators_have_certificate(sender.chain_id, BlockHeight::from(2), 3)
+            .await
+            .unwrap(),
+        certificate
+    );
+    assert_eq!(
+        sender.local_balance().await.unwrap(),
+        Amount::from_tokens(3)
+    );
+    Ok(())
+}
+
+/// Counts some of the events reported by a chain client.
+#[derive(Clone, Default)]
+struct CountingMetrics {
+    proposals: Arc<AtomicUsize>,
+    quorums_reached: Arc<AtomicUsize>,
+    quorums_failed: Arc<AtomicUsize>,
+    certificates_received: Arc<AtomicUsize>,
+}
+
+impl ClientMetrics for CountingMetrics {
+    fn block_proposed(&self) {
+        self.proposals.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn quorum_reached(&self, _latency: Duration) {
+        self.quorums_reached.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn quorum_failed(&self, _latency: Duration) {
+        self.quorums_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn certificates_received(&self, count: usize) {
+        self.certificates_received
+            .fetch_add(count, Ordering::Relaxed);
+    }
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_client_metrics<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let chain = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let metrics = CountingMetrics::default();
+    let client = builder
+        .make_client_with_metrics(
+            chain.chain_id,
+            chain.key_pair().await?,
+            chain.block_hash(),
+            chain.next_block_height(),
+            metrics.clone(),
+        )
+        .await?;
+
+    client.burn(None, Amount::ONE).await.unwrap().unwrap();
+    assert_eq!(metrics.proposals.load(Ordering::Relaxed), 1);
+    assert!(metrics.quorums_reached.load(Ordering::Relaxed) >= 2);
+    assert_eq!(metrics.quorums_failed.load(Ordering::Relaxed), 0);
+
+    // Without a quorum, the proposal fails.
+    builder.set_fault_type([0, 1], FaultType::Offline).await;
+    assert!(client.burn(None, Amount::ONE).await.is_err());
+    assert!(metrics.quorums_failed.load(Ordering::Relaxed) >= 1);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_transfer_to_account_and_balance<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let owner = sender.identity().await?;
+
+    let (certificate, balance) = sender
+        .transfer_to_account_and_balance(
+            None,
+            Amount::from_tokens(3),
+            Account::owner(sender.chain_id(), owner),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(certificate.block().header.height, BlockHeight::ZERO);
+    assert_eq!(balance, Amount::ONE);
+    assert_eq!(balance, sender.local_balance().await?);
+    sender.process_inbox().await?;
+
+    // With an owner, the balance of the owner's account is returned.
+    let (certificate, balance) = sender
+        .transfer_to_account_and_balance(
+            Some(owner),
+            Amount::from_tokens(2),
+            Account::chain(receiver.chain_id()),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(certificate.block().header.height, BlockHeight::from(2));
+    assert_eq!(balance, Amount::ONE);
+    assert_eq!(
+        balance,
+        sender
+            .local_owner_balance(AccountOwner::User(owner))
+            .await?
+    );
+
+    // A pending transfer from the owner's account can't be spent again, but doesn't affect
+    // the chain account.
+    builder.set_fault_type([0, 1], FaultType::Offline).await;
+    assert!(sender
+        .transfer_to_account_unsafe_unconfirmed(
+            Some(owner),
+            Amount::ONE,
+            Account::chain(receiver.chain_id()),
+        )
+        .await
+        .is_err());
+    assert!(sender.pending_proposal().is_some());
+    assert_eq!(sender.spendable_owner_balance(owner).await?, Amount::ZERO);
+    assert_eq!(sender.spendable_balance().await?, Amount::ONE);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_transfer_as<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let sender_key = sender.key_pair().await?;
+    let new_key_pair = KeyPair::generate();
+    let new_owner = new_key_pair.public().into();
+    sender
+        .share_ownership(new_owner, 100)
+        .await
+        .unwrap()
+        .unwrap();
+    let account = Account::owner(sender.chain_id, new_owner);
+    sender
+        .transfer_to_account(None, Amount::from_tokens(2), account)
+        .await
+        .unwrap()
+        .unwrap();
+    sender.process_inbox().await.unwrap();
+
+    // A client that can sign for both owners doesn't know which one to use by default.
+    let client = builder
+        .make_client_with_signer(
+            sender.chain_id,
+            InMemorySigner::new([sender_key, new_key_pair.into()]),
+            sender.block_hash(),
+            sender.next_block_height(),
+        )
+        .await?;
+    client.synchronize_from_validators().await.unwrap();
+    assert_matches!(
+        client
+            .transfer(Some(new_owner), Amount::ONE, Recipient::Burn)
+            .await,
+        Err(ChainClientError::FoundMultipleKeysForChain(_))
+    );
+    assert!(client.pending_proposal().is_none());
+
+    let certificate = client
+        .transfer_as(new_owner, Amount::ONE, Recipient::Burn)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        certificate.block().header.authenticated_signer,
+        Some(new_owner)
+    );
+    assert_eq!(
+        client
+            .local_owner_balance(AccountOwner::User(new_owner))
+            .await
+            .unwrap(),
+        Amount::ONE
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_open_chain_then_close_it<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    // New chains use the admin chain to verify their creation certificate.
+    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let new_key_pair = KeyPair::generate();
+    // Open the new chain.
+    let (message_id, certificate) = sender
+        .open_chain(
+            ChainOwnership::single(new_key_pair.public().into()),
+            ApplicationPermissions::default(),
+            Amount::ZERO,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Regression test for #2869.
+    let sub_message_id = MessageId {
+        index: message_id.index + 1,
+        ..message_id
+    };
+    assert_matches!(
+        certificate.block().messages()[0][sub_message_id.index as usize].message,
+        Message::System(SystemMessage::Subscribe { .. })
+    );
+    assert_eq!(
+        sender
+            .client
+            .local_node()
+            .certificate_for(&sub_message_id)
+            .await
+            .unwrap(),
+        certificate
+    );
+
+    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
+    assert!(sender.pending_proposal().is_none());
+    assert!(sender.key_pair().await.is_ok());
+    // Make a client to try the new chain.
+    let new_id = ChainId::child(message_id);
+    let client = builder
+        .make_client(new_id, new_key_pair, None, BlockHeight::ZERO)
+        .await?;
+    client
+        .receive_certificate_and_update_validators(certificate)
+        .await
+        .unwrap();
+    assert_eq!(client.query_balance().await.unwrap(), Amount::ZERO);
+    client.close_chain().await.unwrap();
+    Ok(())
+}
+
+/// Tests that clients and validators use the IDs of new chains derived in the execution layer,
+/// when a test replaces the derivation.
+#[test_log::test(tokio::test)]
+async fn test_open_chain_with_custom_child_id() -> anyhow::Result<()> {
+    let storage_builder = MemoryStorageBuilder::default()
+        .with_child_id_deriver(|message_id: MessageId| ChainId::root(100 + message_id.index));
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let new_key_pair = KeyPair::generate();
+    let (message_id, certificate) = sender
+        .open_chain(
+            ChainOwnership::single(new_key_pair.public().into()),
+            ApplicationPermissions::default(),
+            Amount::from_tokens(2),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    let new_id = ChainId::root(100 + message_id.index);
+    assert_ne!(new_id, ChainId::child(message_id));
+    assert_eq!(
+        certificate.block().opened_chain_id(&message_id),
+        Some(new_id)
+    );
+
+    // The new chain is usable under its derived ID, and its description maps to that ID.
+    let client = builder
+        .make_client(new_id, new_key_pair, None, BlockHeight::ZERO)
+        .await?;
+    client
+        .receive_certificate_and_update_validators(certificate)
+        .await
+        .unwrap();
+    assert_eq!(
+        client.query_balance().await.unwrap(),
+        Amount::from_tokens(2)
+    );
+    let description = client.chain_info().await?.description.unwrap();
+    assert_eq!(client.storage_client().chain_id_of(description), new_id);
+    client
+        .transfer_to_account(None, Amount::ONE, Account::chain(sender.chain_id()))
+        .await
+        .unwrap()
+        .unwrap();
+    sender.synchronize_from_validators().await.unwrap();
+    sender.process_inbox().await.unwrap();
+    assert_eq!(
+        sender.local_balance().await.unwrap(),
+        Amount::from_tokens(3)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_reject_inactive_chains<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let key_pair = KeyPair::generate();
+    let chain_id = builder
+        .add_root_chain_with_key(1, Amount::from_tokens(4), key_pair.copy())
+        .await?
+        .chain_id();
+
+    // An active chain can be used as usual.
+    let client = builder
+        .make_client_rejecting_inactive_chains(chain_id, key_pair, None, BlockHeight::ZERO)
+        .await?;
+    assert!(!client.client.allow_inactive_chains());
+    client.synchronize_from_validators().await?;
+    client.burn(None, Amount::ONE).await.unwrap().unwrap();
+
+    // A chain that doesn't exist fails before a block is created.
+    let inactive_id = ChainId::root(5);
+    let inactive = builder
+        .make_client_rejecting_inactive_chains(
+            inactive_id,
+            KeyPair::generate(),
+            None,
+            BlockHeight::ZERO,
+        )
+        .await?;
+    assert_matches!(
+        inactive.burn(None, Amount::ONE).await,
+        Err(ChainClientError::LocalNodeError(LocalNodeError::InactiveChain(id)))
+            if id == inactive_id
+    );
+    assert!(inactive.pending_proposal().is_none());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_open_chain_with_derived_owner<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    // New chains use the admin chain to verify their creation certificate.
+    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let key_pair = KeyPair::generate();
+    let chain_id = builder
+        .add_root_chain_with_key(1, Amount::from_tokens(4), key_pair.copy())
+        .await?
+        .chain_id();
+    let seed = Seed::new([1; 32])?;
+    let sender = builder
+        .make_client_with_key_derivation(
+            chain_id,
+            key_pair,
+            None,
+            BlockHeight::ZERO,
+            KeyDerivation::new(Seed::new(seed.as_bytes())?),
+        )
+        .await?;
+
+    // A chain that fails to open doesn't get the derived key.
+    assert!(sender
+        .open_chain_with_derived_owner(ApplicationPermissions::default(), Amount::from_tokens(5))
+        .await
+        .is_err());
+    assert_eq!(sender.client.chains.len(), 1);
+
+    let (message_id, certificate) = sender
+        .open_chain_with_derived_owner(ApplicationPermissions::default(), Amount::ONE)
+        .await
+        .unwrap()
+        .unwrap();
+    let new_id = ChainId::child(message_id);
+    // The first index was reserved by the failed attempt.
+    let expected_key_pair = KeyPair::derive_from_seed(&seed, &[1]);
+    let key_derivation = sender.client.key_derivation().unwrap();
+    assert_eq!(key_derivation.next_index(), 2);
+    assert_eq!(
+        key_derivation.key_pair(1).public(),
+        expected_key_pair.public()
+    );
+
+    // The new chain can be used with the derived key.
+    let client = sender.client.create_chain_client(
+        new_id,
+        Vec::new(),
+        sender.admin_id,
+        None,
+        certificate.block().header.timestamp,
+        BlockHeight::ZERO,
+        None,
+        Vec::new(),
+    );
+    client
+        .receive_certificate_and_update_validators(certificate)
+        .await?;
+    assert_eq!(
+        client.identity().await?,
+        Owner::from(expected_key_pair.public())
+    );
+    client.burn(None, Amount::from_millis(500)).await.unwrap();
+    assert_eq!(client.local_balance().await?, Amount::from_millis(500));
+
+    // Clients without a key derivation can't derive owners.
+    let other = builder.add_root_chain(2, Amount::from_tokens(4)).await?;
+    assert_matches!(
+        other
+            .open_chain_with_derived_owner(ApplicationPermissions::default(), Amount::ONE)
+            .await,
+        Err(ChainClientError::MissingKeyDerivation)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_propose_with_signer<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let key_pair = KeyPair::generate();
+    let owner = Owner::from(key_pair.public());
+    let chain_id = builder
+        .add_root_chain_with_key(1, Amount::from_tokens(4), key_pair.copy())
+        .await?
+        .chain_id();
+    let receiver_id = builder.add_root_chain(2, Amount::ZERO).await?.chain_id();
+    let sender = builder
+        .make_client_with_signer(
+            chain_id,
+            InMemorySigner::new([AccountSecretKey::from(key_pair)]),
+            None,
+            BlockHeight::ZERO,
+        )
+        .await?;
+
+    // The client doesn't hold the key itself, but can still sign with it.
+    assert_matches!(
+        sender.key_pair().await,
+        Err(ChainClientError::CannotFindKeyForChain(_))
+    );
+    assert_eq!(sender.identity().await?, owner);
+    let certificate = sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver_id))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(certificate.block().header.authenticated_signer, Some(owner));
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(3));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_emergency_rotate<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let friend = builder.add_root_chain(2, Amount::from_tokens(3)).await?;
+    // The sender hasn't received this transfer yet.
+    friend
+        .transfer_to_account(None, Amount::ONE, Account::chain(sender.chain_id()))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let new_key_pair = KeyPair::generate();
+    let (new_id, certificate) = sender
+        .emergency_rotate(new_key_pair.public().into())
+        .await
+        .unwrap()
+        .unwrap();
+    let block = certificate.block();
+    assert_eq!(block.body.incoming_bundles.len(), 1);
+    assert_eq!(block.body.operations.len(), 2);
+    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
+    assert_eq!(sender.local_balance().await?, Amount::ZERO);
+    let result = sender.burn(None, Amount::ONE).await;
+    assert!(
+        matches!(
+            &result,
+            Err(ChainClientError::LocalNodeError(
+                LocalNodeError::WorkerError(WorkerError::ChainError(err))
+            )) if matches!(**err, ChainError::ClosedChain)
+        ),
+        "Unexpected result: {:?}",
+        result,
+    );
+
+    let client = builder
+        .make_client(new_id, new_key_pair, None, BlockHeight::ZERO)
+        .await?;
+    client
+        .receive_certificate_and_update_validators(certificate)
+        .await
+        .unwrap();
+    assert_eq!(client.query_balance().await?, Amount::from_tokens(5));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_transfer_then_open_chain<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    // New chains use the admin chain to verify their creation certificate.
+    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let parent = builder.add_root_chain(2, Amount::ZERO).await?;
+    let new_key_pair = KeyPair::generate();
+    let new_id = ChainId::child(MessageId {
+        chain_id: parent.chain_id(),
+        height: BlockHeight::ZERO,
+        index: 0,
+    });
+    // Transfer before creating the chain. The validators will ignore the cross-chain messages.
+    sender
+        .transfer_to_account(None, Amount::from_tokens(2), Account::chain(new_id))
+        .await
+        .unwrap();
+    // Open the new chain.
+    let (open_chain_message_id, certificate) = parent
+        .open_chain(
+            ChainOwnership::single(new_key_pair.public().into()),
+            ApplicationPermissions::default(),
+            Amount::ZERO,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    let new_id2 = ChainId::child(open_chain_message_id);
+    assert_eq!(new_id, new_id2);
+    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
+    assert_eq!(parent.next_block_height(), BlockHeight::from(1));
+    assert!(sender.pending_proposal().is_none());
+    assert!(sender.key_pair().await.is_ok());
+    assert_matches!(
+        certificate.block().body.operations[open_chain_message_id.index as usize],
+        Operation::System(SystemOperation::OpenChain(_)),
+        "Unexpected certificate value",
+    );
+    assert_eq!(
+        builder
+            .check_that_validators_have_certificate(parent.chain_id, BlockHeight::from(0), 3)
+            .await
+            .unwrap(),
+        certificate
+    );
+    // Make a client to try the new chain.
+    let client = builder
+        .make_client(new_id, new_key_pair, None, BlockHeight::ZERO)
+        .await?;
+    client
+        .receive_certificate_and_update_validators(certificate)
+        .await
+        .unwrap();
+    // Make another block on top of the one that sent the two tokens, so that the validators
+    // process the cross-chain messages.
+    let certificate2 = sender
+        .transfer_to_account(None, Amount::from_tokens(1), Account::chain(new_id))
+        .await
+        .unwrap()
+        .unwrap();
+    client
+        .receive_certificate_and_update_validators(certificate2)
+        .await
+        .unwrap();
+    assert_eq!(
+        client.query_balance().await.unwrap(),
+        Amount::from_tokens(3)
+    );
+    client.burn(None, Amount::from_tokens(3)).await.unwrap();
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_open_chain_must_be_first<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    // New chains use the admin chain to verify their creation certificate.
+    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let new_key_pair = KeyPair::generate();
+    let new_id = ChainId::child(MessageId {
+        chain_id: sender.chain_id(),
+        height: BlockHeight::from(1),
+        index: 0,
+    });
+    // Transfer before creating the chain.
+    sender
+        .transfer_to_account(None, Amount::from_tokens(3), Account::chain(new_id))
+        .await
+        .unwrap();
+    // Open the new chain.
+    let (open_chain_message_id, certificate) = sender
+        .open_chain(
+            ChainOwnership::single(new_key_pair.public().into()),
+            ApplicationPermissions::default(),
+            Amount::ZERO,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    let new_id2 = ChainId::child(open_chain_message_id);
+    assert_eq!(new_id, new_id2);
+    assert_eq!(sender.next_block_height(), BlockHeight::from(2));
+    assert!(sender.pending_proposal().is_none());
+    assert!(sender.key_pair().await.is_ok());
+    assert_matches!(
+        certificate.block().body.operations[open_chain_message_id.index as usize],
+        Operation::System(SystemOperation::OpenChain(_)),
+        "Unexpected certificate value",
+    );
+    assert_eq!(
+        builder
+            .check_that_validators_have_certificate(sender.chain_id, BlockHeight::from(1), 3)
+            .await
+            .unwrap(),
+        certificate
+    );
+    // Make a client to try the new chain.
+    let client = builder
+        .make_client(new_id, new_key_pair, None, BlockHeight::ZERO)
+        .await?;
+    client
+        .receive_certificate_and_update_validators(certificate)
+        .await
+        .unwrap();
+    let result = client.burn(None, Amount::from_tokens(3)).await;
+    assert_matches!(
+        result,
+        Err(ChainClientError::LocalNodeError(
+            LocalNodeError::WorkerError(WorkerError::ChainError(error))
+        )) if matches!(&*error, ChainError::CannotSkipMessage { bundle, .. }
+            if matches!(&**bundle, MessageBundle { messages, .. }
+            if matches!(messages[..], [PostedMessage {
+                message: Message::System(SystemMessage::Credit { .. }), ..
+        }])))
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_open_chain_then_transfer<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    // New chains use the admin chain to verify their creation certificate.
+    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let new_key_pair = KeyPair::generate();
+    // Open the new chain. We are both regular and super owner.
+    let ownership = ChainOwnership::single(new_key_pair.public().into())
+        .with_regular_owner(new_key_pair.public().into(), 100);
+    let (message_id, creation_certificate) = sender
+        .open_chain(ownership, ApplicationPermissions::default(), Amount::ZERO)
+        .await
+        .unwrap()
+        .unwrap();
+    let new_id = ChainId::child(message_id);
+    // Transfer after creating the chain.
+    let transfer_certificate = sender
+        .transfer_to_account(None, Amount::from_tokens(3), Account::chain(new_id))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(sender.next_block_height(), BlockHeight::from(2));
+    assert!(sender.pending_proposal().is_none());
+    assert!(sender.key_pair().await.is_ok());
+    // Make a client to try the new chain.
+    let client = builder
+        .make_client(new_id, new_key_pair, None, BlockHeight::ZERO)
+        .await?;
+    // Must process the creation certificate before using the new chain.
+    client
+        .receive_certificate_and_update_validators(creation_certificate)
+        .await
+        .unwrap();
+    assert_eq!(client.local_balance().await.unwrap(), Amount::ZERO);
+    client
+        .receive_certificate_and_update_validators(transfer_certificate)
+        .await
+        .unwrap();
+    assert_eq!(
+        client.query_balance().await.unwrap(),
+        Amount::from_tokens(3)
+    );
+    client.burn(None, Amount::from_tokens(3)).await.unwrap();
+    assert_eq!(client.local_balance().await.unwrap(), Amount::ZERO);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_close_chain<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1)
+        .await?
+        .with_policy(ResourceControlPolicy::all_categories());
+    let client1 = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let client2 = builder.add_root_chain(2, Amount::from_tokens(4)).await?;
+
+    let certificate = client1.close_chain().await.unwrap().unwrap().unwrap();
+    assert_matches!(
+        certificate.block().body.operations[..],
+        [Operation::System(SystemOperation::CloseChain)],
+        "Unexpected certificate value",
+    );
+    assert_eq!(client1.next_block_height(), BlockHeight::from(1));
+    assert!(client1.pending_proposal().is_none());
+    assert!(client1.key_pair().await.is_ok());
+    assert_eq!(
+        builder
+            .check_that_validators_have_certificate(client1.chain_id, BlockHeight::ZERO, 3)
+            .await
+            .unwrap(),
+        certificate
+    );
+    // Cannot use the chain for operations any more.
+    let result = client1.burn(None, Amount::from_tokens(3)).await;
+    assert!(
+        matches!(
+            &result,
+            Err(ChainClientError::LocalNodeError(
+                LocalNodeError::WorkerError(WorkerError::ChainError(err))
+            )) if matches!(**err, ChainError::ClosedChain)
+        ),
+        "Unexpected result: {:?}",
+        result,
+    );
+
+    // Incoming messages now get rejected.
+    client2
+        .transfer_to_account(
+            None,
+            Amount::from_tokens(3),
+            Account::chain(client1.chain_id()),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    client1.synchronize_from_validators().await.unwrap();
+    let (certificates, _) = client1.process_inbox().await.unwrap();
+    let block = certificates[0].block();
+    assert!(block.body.operations.is_empty());
+    assert_eq!(block.body.incoming_bundles.len(), 1);
+    assert_matches!(
+        &block.body.incoming_bundles[0],
+        IncomingBundle {
+            origin: Origin { sender, medium: Medium::Direct },
+            action: MessageAction::Reject,
+            bundle: MessageBundle {
+                messages,
+                ..
+            },
+        } if *sender == client2.chain_id() && matches!(messages[..],
+            [PostedMessage {
+                message: Message::System(SystemMessage::Credit { .. }),
+                kind: MessageKind::Tracked,
+                ..
+            }]
+        )
+    );
+
+    // Since blocks are free of charge on closed chains, empty blocks are not allowed.
+    assert_matches!(
+        client1.execute_operations(vec![], vec![]).await,
+        Err(ChainClientError::LocalNodeError(
+            LocalNodeError::WorkerError(WorkerError::ChainError(error))
+        )) if matches!(*error, ChainError::ClosedChain)
+    );
+
+    // Trying to close the chain again returns None.
+    let maybe_certificate = client1.close_chain().await.unwrap().unwrap();
+    assert_matches!(maybe_certificate, None);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_initiating_valid_transfer_too_many_faults<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 2).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    assert_eq!(
+        sender.spendable_balance().await.unwrap(),
+        Amount::from_tokens(4)
+    );
+    let result = sender
+        .transfer_to_account_unsafe_unconfirmed(
+            None,
+            Amount::from_tokens(3),
+            Account::chain(ChainId::root(2)),
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainClientError::CommunicationError(
+            CommunicationError::Trusted(crate::node::NodeError::ArithmeticError { .. })
+        )),
+        "unexpected result"
+    );
+    assert_eq!(sender.next_block_height(), BlockHeight::ZERO);
+    assert!(sender.pending_proposal().is_some());
+    assert_eq!(
+        sender.local_balance().await.unwrap(),
+        Amount::from_tokens(4)
+    );
+    // The transfer in the pending proposal can't be spent again.
+    assert_eq!(
+        sender.spendable_balance().await.unwrap(),
+        Amount::from_tokens(1)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_transfer_quorum_not_reached<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let faulty_names = [builder.node(0).name(), builder.node(1).name()];
+    builder.set_fault_type([0], FaultType::Offline).await;
+    builder.set_fault_type([1], FaultType::Malicious).await;
+
+    // Each of the two faulty validators returns a different error, so neither is trusted.
+    let result = sender
+        .transfer_to_account_unsafe_unconfirmed(None, Amount::ONE, Account::chain(ChainId::root(2)))
+        .await;
+    let Err(ChainClientError::CommunicationError(CommunicationError::QuorumNotReached {
+        achieved_weight,
+        required_weight,
+        successes,
+        errors,
+    })) = result
+    else {
+        panic!("unexpected result");
+    };
+    assert_eq!(achieved_weight, 2);
+    assert_eq!(required_weight, 3);
+    assert_eq!(successes, 2);
+    let mut names = errors.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    names.sort();
+    let mut expected_names = faulty_names.to_vec();
+    expected_names.sort();
+    assert_eq!(names, expected_names);
+    assert_eq!(sender.next_block_height(), BlockHeight::ZERO);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_transfer_with_preferred_validators<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let validators = (0..4).map(|i| builder.node(i).name()).collect::<Vec<_>>();
+    sender.set_preferred_validators(validators);
+    let query = ChainInfoQuery::new(sender.chain_id());
+
+    // The first three validators are enough for a quorum, so the last one is not contacted.
+    sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(ChainId::root(2)))
+        .await
+        .unwrap()
+        .unwrap();
+    builder
+        .check_that_validators_have_certificate(sender.chain_id(), BlockHeight::ZERO, 3)
+        .await
+        .unwrap();
+    let info = builder
+        .node(3)
+        .handle_chain_info_query(query.clone())
+        .await?
+        .info;
+    assert_eq!(info.next_block_height, BlockHeight::ZERO);
+
+    // If one of the preferred validators fails, the next one is contacted instead.
+    builder.set_fault_type([0], FaultType::Offline).await;
+    sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(ChainId::root(2)))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(sender.next_block_height(), BlockHeight::from(2));
+    let info = builder.node(3).handle_chain_info_query(query).await?.info;
+    assert_eq!(info.next_block_height, BlockHeight::from(2));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_advance_chain<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let relayer = builder.add_root_chain(2, Amount::ZERO).await?;
+    let validators = (0..3).map(|i| builder.node(i).name()).collect::<Vec<_>>();
+    sender.set_preferred_validators(validators);
+    let query = ChainInfoQuery::new(sender.chain_id());
+
+    // The sender only contacts the first three validators, so the last one falls behind.
+    for _ in 0..2 {
+        sender
+            .transfer_to_account(None, Amount::ONE, Account::chain(relayer.chain_id()))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let info = builder
+        .node(3)
+        .handle_chain_info_query(query.clone())
+        .await?
+        .info;
+    assert_eq!(info.next_block_height, BlockHeight::ZERO);
+
+    // The relayer doesn't own the sender's chain, but can still bring the last validator up to
+    // date. With the first validator offline, the quorum can't be reached without it.
+    builder.set_fault_type([0], FaultType::Offline).await;
+    relayer
+        .advance_chain(sender.chain_id(), BlockHeight::from(2))
+        .await?;
+    let info = builder.node(3).handle_chain_info_query(query).await?.info;
+    assert_eq!(info.next_block_height, BlockHeight::from(2));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_transfer_with_unresponsive_validator<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let mut sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let validators = (0..4).map(|i| builder.node(i).name()).collect::<Vec<_>>();
+    sender.set_preferred_validators(validators);
+    sender.options_mut().request_timeout = Duration::from_millis(100);
+
+    // The first preferred validator never answers. Once its request times out, the next
+    // one is contacted instead.
+    builder.set_fault_type([0], FaultType::Unresponsive).await;
+    sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(ChainId::root(2)))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
+    let query = ChainInfoQuery::new(sender.chain_id());
+    for i in 1..4 {
+        let info = builder
+            .node(i)
+            .handle_chain_info_query(query.clone())
+            .await?
+            .info;
+        assert_eq!(info.next_block_height, BlockHeight::from(1));
+    }
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_retry_canceled_proposal<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let recipient = Account::chain(ChainId::root(2));
+
+    // Without a quorum, the proposal never completes, so we drop it.
+    builder
+        .set_fault_type([0, 1], FaultType::Unresponsive)
+        .await;
+    tokio::select! {
+        result = sender.transfer_to_account(None, Amount::ONE, recipient) => {
+            panic!("The transfer should not complete without a quorum: {result:?}");
+        }
+        () = tokio::time::sleep(Duration::from_millis(500)) => {}
+    }
+    assert_eq!(sender.next_block_height(), BlockHeight::ZERO);
+    let block = sender
+        .pending_proposal()
+        .as_ref()
+        .expect("The canceled block should still be pending")
+        .block
+        .clone();
+    assert_eq!(block.height, BlockHeight::ZERO);
+
+    // Once the validators respond again, the pending block can be committed.
+    builder.set_fault_type([0, 1], FaultType::Honest).await;
+    let certificate = sender.process_pending_block().await?.unwrap().unwrap();
+    assert_eq!(certificate.block().body.operations, block.operations);
+    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
+    assert!(sender.pending_proposal().is_none());
+    assert_eq!(
+        sender.local_balance().await.unwrap(),
+        Amount::from_tokens(3)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_bidirectional_transfer<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let client1 = builder.add_root_chain(1, Amount::from_tokens(3)).await?;
+    let client2 = builder.add_root_chain(2, Amount::ZERO).await?;
+    assert_eq!(
+        client1.local_balance().await.unwrap(),
+        Amount::from_tokens(3)
+    );
+    assert_eq!(
+        client1.query_system_application(SystemQuery).await.unwrap(),
+        QueryOutcome {
+            response: SystemResponse {
+                chain_id: client1.chain_id(),
+                balance: Amount::from_tokens(3),
+            },
+            operations: vec![],
+        }
+    );
+    let certificate = client1
+        .transfer_to_account(
+            None,
+            Amount::from_tokens(3),
+            Account::chain(client2.chain_id),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(client1.next_block_height(), BlockHeight::from(1));
+    assert!(client1.pending_proposal().is_none());
+    assert_eq!(client1.local_balance().await.unwrap(), Amount::ZERO);
+    assert_eq!(
+        client1.query_system_application(SystemQuery).await.unwrap(),
+        QueryOutcome {
+            response: SystemResponse {
+                chain_id: client1.chain_id(),
+                balance: Amount::ZERO,
+            },
+            operations: vec![],
+        },
+    );
+
+    assert_eq!(
+        builder
+            .check_that_validators_have_certificate(client1.chain_id, BlockHeight::ZERO, 3)
+            .await
+            .unwrap(),
+        certificate
+    );
+    // Local balance is lagging.
+    assert_eq!(client2.local_balance().await.unwrap(), Amount::ZERO);
+    // Obtain the certificate but do not process the inbox yet.
+    client2.synchronize_from_validators().await.unwrap();
+    assert_eq!(client2.local_balance().await.unwrap(), Amount::ZERO);
+    assert_eq!(
+        client2.query_system_application(SystemQuery).await.unwrap(),
+        QueryOutcome {
+            response: SystemResponse {
+                chain_id: client2.chain_id(),
+                balance: Amount::from_tokens(0),
+            },
+            operations: vec![],
+        },
+    );
+
+    // Process the inbox and send back some money.
+    assert_eq!(client2.next_block_height(), BlockHeight::ZERO);
+    client2
+        .transfer_to_account(None, Amount::ONE, Account::chain(client1.chain_id))
+        .await
+        .unwrap();
+    assert_eq!(client2.next_block_height(), BlockHeight::from(1));
+    assert!(client2.pending_proposal().is_none());
+    assert_eq!(
+        client2.local_balance().await.unwrap(),
+        Amount::from_tokens(2)
+    );
+    client1.synchronize_from_validators().await.unwrap();
+    client1.process_inbox().await.unwrap();
+    assert_eq!(client1.local_balance().await.unwrap(), Amount::ONE);
+    // Local balance from client2 is now consolidated.
+    assert_eq!(
+        client2.query_system_application(SystemQuery).await.unwrap(),
+        QueryOutcome {
+            response: SystemResponse {
+                chain_id: client2.chain_id(),
+                balance: Amount::from_tokens(2),
+            },
+            operations: vec![],
+        },
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_receive_after_received_log_truncation<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let receiver_id = receiver.chain_id();
+
+    sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver_id))
+        .await
+        .unwrap()
+        .unwrap();
+    receiver.synchronize_from_validators().await?;
+    receiver.process_inbox().await?;
+    assert_eq!(receiver.local_balance().await?, Amount::ONE);
+    assert_eq!(
+        receiver
+            .chain_state_view()
+            .await?
+            .received_certificate_trackers
+            .get()
+            .get(&sender.chain_id()),
+        Some(&BlockHeight::ZERO)
+    );
+    // The next synchronization skips the entries we already scanned.
+    let log_indices = receiver
+        .chain_state_view()
+        .await?
+        .received_log_indices
+        .get()
+        .clone();
+    assert!(!log_indices.is_empty());
+    assert!(log_indices.values().all(|index| *index >= 1));
+
+    // The validators lose their received logs, so the next entry is at the start again.
+    for i in 0..4 {
+        builder.node(i).clear_received_log(receiver_id).await?;
+    }
+    sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver_id))
+        .await
+        .unwrap()
+        .unwrap();
+    receiver.synchronize_from_validators().await?;
+    receiver.process_inbox().await?;
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(2));
+    assert_eq!(
+        receiver
+            .chain_state_view()
+            .await?
+            .received_certificate_trackers
+            .get()
+            .get(&sender.chain_id()),
+        Some(&BlockHeight::from(1))
+    );
+
+    // Later entries are found after the new scanned position.
+    sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver_id))
+        .await
+        .unwrap()
+        .unwrap();
+    receiver.synchronize_from_validators().await?;
+    receiver.process_inbox().await?;
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(3));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_receive_after_restart<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let receiver_id = receiver.chain_id();
+
+    for _ in 0..2 {
+        sender
+            .transfer_to_account(None, Amount::ONE, Account::chain(receiver_id))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    receiver.synchronize_from_validators().await?;
+    receiver.process_inbox().await?;
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(2));
+    let trackers = receiver.received_certificate_trackers().await?;
+    assert_eq!(
+        trackers.get(&sender.chain_id()),
+        Some(&BlockHeight::from(1))
+    );
+
+    sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver_id))
+        .await
+        .unwrap()
+        .unwrap();
+
+    // A new client with the same storage resumes from the saved trackers, so it only
+    // downloads the new certificate.
+    let metrics = CountingMetrics::default();
+    let restarted = builder
+        .restart_client_with_metrics(&receiver, metrics.clone())
+        .await?;
+    assert_eq!(restarted.received_certificate_trackers().await?, trackers);
+    restarted.synchronize_from_validators().await?;
+    assert_eq!(metrics.certificates_received.load(Ordering::Relaxed), 1);
+    restarted.process_inbox().await?;
+    assert_eq!(restarted.local_balance().await?, Amount::from_tokens(3));
+    assert_eq!(
+        restarted
+            .received_certificate_trackers()
+            .await?
+            .get(&sender.chain_id()),
+        Some(&BlockHeight::from(2))
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_chain_diagnostics<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let receiver_id = receiver.chain_id();
+
+    sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver_id))
+        .await
+        .unwrap()
+        .unwrap();
+    receiver.synchronize_from_validators().await?;
+    receiver.process_inbox().await?;
+
+    let diagnostics = receiver.diagnostics().await?;
+    assert_eq!(diagnostics.chain_id, receiver_id);
+    assert_eq!(diagnostics.block_hash, receiver.block_hash());
+    assert_eq!(diagnostics.next_block_height, BlockHeight::from(1));
+    assert_eq!(diagnostics.next_round, receiver.current_round().await?);
+    assert!(!diagnostics.has_pending_proposal);
+    assert_eq!(diagnostics.known_key_pairs, 1);
+    assert_eq!(
+        diagnostics.received_certificate_trackers,
+        BTreeMap::from([(sender.chain_id(), BlockHeight::ZERO)])
+    );
+    assert_eq!(
+        diagnostics.committee.as_ref(),
+        Some(&builder.initial_committee)
+    );
+
+    // Taking a snapshot doesn't change the state, and the snapshot can be attached to reports.
+    assert_eq!(receiver.diagnostics().await?, diagnostics);
+    let json = serde_json::to_string(&diagnostics)?;
+    assert_eq!(
+        serde_json::from_str::<ChainDiagnostics>(&json)?,
+        diagnostics
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_transfer_batch<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let recipient = Recipient::Account(Account::chain(receiver.chain_id()));
+
+    // The total overflows, so no block is proposed.
+    let almost_max = Amount::MAX.try_sub(Amount::ONE)?;
+    assert_matches!(
+        sender
+            .transfer_batch(
+                None,
+                vec![
+                    (recipient, almost_max),
+                    (Recipient::Burn, Amount::from_tokens(2))
+                ]
+            )
+            .await,
+        Err(ChainClientError::ArithmeticError(ArithmeticError::Overflow))
+    );
+    assert_eq!(sender.next_block_height(), BlockHeight::ZERO);
+    assert!(sender.pending_proposal().is_none());
+
+    sender
+        .transfer_batch(
+            None,
+            vec![
+                (recipient, Amount::ONE),
+                (Recipient::Burn, Amount::from_tokens(2)),
+            ],
+        )
+        .await?
+        .unwrap();
+    assert_eq!(sender.local_balance().await?, Amount::ONE);
+    receiver.synchronize_from_validators().await?;
+    receiver.process_inbox().await?;
+    assert_eq!(receiver.local_balance().await?, Amount::ONE);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_balance_with_max_age<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let max_age = Duration::from_secs(3600);
+    assert_eq!(receiver.balance_with_max_age(max_age).await?, Amount::ZERO);
+
+    sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver.chain_id()))
+        .await
+        .unwrap()
+        .unwrap();
+    receiver.synchronize_from_validators().await?;
+
+    // The incoming transfer doesn't invalidate the cache, but a lower maximum age does.
+    assert_eq!(receiver.balance_with_max_age(max_age).await?, Amount::ZERO);
+    assert_eq!(
+        receiver.balance_with_max_age(Duration::ZERO).await?,
+        Amount::ONE
+    );
+    assert_eq!(receiver.balance_with_max_age(max_age).await?, Amount::ONE);
+
+    // A new block always invalidates the cache.
+    receiver.burn(None, Amount::ONE).await.unwrap().unwrap();
+    assert_eq!(receiver.balance_with_max_age(max_age).await?, Amount::ZERO);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_local_balance_auto_sync<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let new_key_pair = KeyPair::generate();
+    let new_owner = new_key_pair.public().into();
+    sender
+        .share_ownership(new_owner, 100)
+        .await
+        .unwrap()
+        .unwrap();
+    sender.burn(None, Amount::ONE).await.unwrap().unwrap();
+    let client = builder
+        .make_client(
+            sender.chain_id,
+            new_key_pair,
+            sender.block_hash(),
+            BlockHeight::from(2),
+        )
+        .await?;
+    // The new client's local node hasn't downloaded the blocks yet.
+    assert_matches!(
+        client.local_balance().await,
+        Err(ChainClientError::WalletSynchronizationError)
+    );
+    assert_eq!(
+        client.local_balance_auto_sync().await?,
+        Amount::from_tokens(3)
+    );
+    assert_eq!(client.local_balance().await?, Amount::from_tokens(3));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_estimate_operation_cost<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let transfer = Operation::System(SystemOperation::Transfer {
+        owner: None,
+        recipient: Recipient::root(2),
+        amount: Amount::ONE,
+    });
+    assert_eq!(
+        sender.estimate_operation_cost(vec![]).await?,
+        FuelEstimate::default()
+    );
+    assert_eq!(
+        sender
+            .estimate_operation_cost(vec![transfer.clone(), transfer])
+            .await?,
+        FuelEstimate {
+            fuel: 0,
+            messages: 2,
+        }
+    );
+    // Nothing was executed.
+    assert_eq!(sender.next_block_height(), BlockHeight::ZERO);
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(4));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_simulate_operations<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let transfer = |index| {
+        Operation::System(SystemOperation::Transfer {
+            owner: None,
+            recipient: Recipient::root(index),
+            amount: Amount::ONE,
+        })
+    };
+    let messages = sender
+        .simulate_operations(vec![transfer(2), transfer(3)])
+        .await?;
+    let destinations = messages
+        .iter()
+        .map(|messages| {
+            messages
+                .iter()
+                .map(|message| message.destination.clone())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        destinations,
+        [
+            [Destination::Recipient(ChainId::root(2))],
+            [Destination::Recipient(ChainId::root(3))],
+        ]
+    );
+    // Nothing was executed.
+    assert_eq!(sender.next_block_height(), BlockHeight::ZERO);
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(4));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_sender_certificates_shared_by_recipients<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver1 = builder.add_root_chain(2, Amount::ZERO).await?;
+    let key_pair = KeyPair::generate();
+    let receiver2_id = builder
+        .add_root_chain_with_key(3, Amount::ZERO, key_pair.copy())
+        .await?
+        .chain_id();
+    // The second recipient chain is tracked by the same client, i.e. in the same storage.
+    let receiver2 = receiver1.client.create_chain_client(
+        receiver2_id,
+        vec![key_pair.into()],
+        receiver1.admin_id,
+        None,
+        Timestamp::from(0),
+        BlockHeight::ZERO,
+        None,
+        Vec::new(),
+    );
+
+    for receiver_id in [receiver1.chain_id(), receiver2_id] {
+        sender
+            .transfer_to_account(None, Amount::ONE, Account::chain(receiver_id))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    for receiver in [&receiver1, &receiver2] {
+        receiver.synchronize_from_validators().await?;
+        receiver.process_inbox().await?;
+        assert_eq!(receiver.local_balance().await?, Amount::ONE);
+    }
+
+    // Both recipients ingested the sender's history, but certificates are stored by hash
+    // and indexed by height in the sender chain's state, so each is present exactly once.
+    let storage = receiver1.storage_client();
+    let sender_hashes = storage
+        .list_certificate_hashes(sender.chain_id(), BlockHeight::ZERO..BlockHeight::from(10))
+        .await?;
+    let expected_hashes = sender
+        .storage_client()
+        .list_certificate_hashes(sender.chain_id(), BlockHeight::ZERO..BlockHeight::from(10))
+        .await?;
+    assert_eq!(sender_hashes, expected_hashes);
+    assert_eq!(sender_hashes.len(), 2);
+    for hash in sender_hashes {
+        assert!(storage.contains_certificate(hash).await?);
+    }
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_process_pending_block_with_retries<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let client = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    assert_eq!(
+        client
+            .process_pending_block_with_retries(3, Duration::ZERO)
+            .await?,
+        Some((1, None))
+    );
+
+    // Without a quorum, the burn fails but remains pending.
+    builder.set_fault_type([0, 1], FaultType::Offline).await;
+    assert_matches!(
+        client.burn(None, Amount::ONE).await,
+        Err(ChainClientError::CommunicationError(_))
+    );
+    assert!(client.pending_proposal().is_some());
+    assert_matches!(
+        client
+            .process_pending_block_with_retries(2, Duration::ZERO)
+            .await,
+        Err(ChainClientError::CommunicationError(_))
+    );
+
+    builder.set_fault_type([0, 1], FaultType::Honest).await;
+    let (attempt, certificate) = client
+        .process_pending_block_with_retries(3, Duration::from_millis(10))
+        .await?
+        .unwrap();
+    assert_eq!(attempt, 1);
+    assert_eq!(
+        certificate.unwrap().block().header.height,
+        BlockHeight::ZERO
+    );
+    assert!(client.pending_proposal().is_none());
+    assert_eq!(client.local_balance().await?, Amount::from_tokens(3));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_remote_balance<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let observer = builder.add_root_chain(2, Amount::ZERO).await?;
+    sender.burn(None, Amount::ONE).await.unwrap().unwrap();
+    sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(observer.chain_id()))
+        .await
+        .unwrap()
+        .unwrap();
+
+    // The observer has none of the sender's keys, and hasn't seen its blocks yet.
+    assert_eq!(
+        observer
+            .client
+            .local_node
+            .chain_info(sender.chain_id())
+            .await?
+            .next_block_height,
+        BlockHeight::ZERO
+    );
+    assert_eq!(
+        observer.remote_balance(sender.chain_id()).await?,
+        Amount::from_tokens(2)
+    );
+    assert_eq!(
+        observer.remote_balance(observer.chain_id()).await?,
+        Amount::ZERO
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_process_inbox_larger_than_page<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(6)).await?;
+    let mut receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    for _ in 0..5 {
+        sender
+            .transfer_to_account(None, Amount::ONE, Account::chain(receiver.chain_id()))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    receiver.synchronize_from_validators().await?;
+
+    receiver.options_mut().pending_message_bundles_page_size = 2;
+    receiver.options_mut().max_pending_message_bundles = 3;
+    let (certificates, _) = receiver.process_inbox().await?;
+    let bundle_counts = certificates
+        .iter()
+        .map(|certificate| certificate.block().body.incoming_bundles.len())
+        .collect::<Vec<_>>();
+    assert_eq!(bundle_counts, [3, 2]);
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(5));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_download_certificates_in_truncated_ranges<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    for i in 0..4 {
+        builder
+            .node(i)
+            .set_maximum_sent_certificate_hashes_per_response(50)
+            .await;
+    }
+    let sender = builder.add_root_chain(1, Amount::from_attos(1000)).await?;
+    let chain_id = sender.chain_id();
+    for _ in 0..1000 {
+        sender.burn(None, Amount::from_attos(1)).await.unwrap();
+    }
+    assert_eq!(sender.next_block_height(), BlockHeight::from(1000));
+
+    // The validators only return the first 50 hashes of the requested range.
+    let query =
+        ChainInfoQuery::new(chain_id).with_sent_certificate_hashes_in_range(BlockHeightRange {
+            start: BlockHeight::from(10),
+            limit: Some(100),
+        });
+    let info = builder.node(0).handle_chain_info_query(query).await?.info;
+    assert_eq!(info.requested_sent_certificate_hashes.len(), 50);
+    assert_eq!(
+        info.requested_sent_certificate_hashes_next_height,
+        Some(BlockHeight::from(60))
+    );
+
+    // A client that knows the height downloads all the certificates.
+    let client = builder
+        .make_client(
+            chain_id,
+            KeyPair::generate(),
+            sender.block_hash(),
+            BlockHeight::from(1000),
+        )
+        .await?;
+    client.synchronize_from_validators().await?;
+    assert_eq!(client.local_balance().await?, Amount::ZERO);
+
+    // A client that doesn't know the height synchronizes the whole chain.
+    let observer = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let nodes = observer.validator_nodes().await?;
+    let info = observer.synchronize_chain_state(&nodes, chain_id).await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(1000));
+    assert_eq!(info.block_hash, sender.block_hash());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_download_history<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let observer = builder.add_root_chain(2, Amount::ZERO).await?;
+    let sender_id = sender.chain_id();
+    let mut hashes = Vec::new();
+    for _ in 0..3 {
+        let certificate = sender.burn(None, Amount::ONE).await.unwrap().unwrap();
+        hashes.push(certificate.hash());
+    }
+
+    // A window of the sender's history can be downloaded by another chain's client.
+    let history = observer
+        .download_history(sender_id, BlockHeightRange::multi(BlockHeight::from(1), 2))
+        .await?;
+    assert_eq!(
+        history.iter().map(|c| c.hash()).collect::<Vec<_>>(),
+        hashes[1..]
+    );
+
+    // Ranges are truncated at the end of the chain.
+    let history = observer
+        .download_history(sender_id, BlockHeightRange::multi(BlockHeight::from(2), 10))
+        .await?;
+    assert_eq!(
+        history.iter().map(|c| c.hash()).collect::<Vec<_>>(),
+        hashes[2..]
+    );
+    let history = observer
+        .download_history(
+            sender_id,
+            BlockHeightRange {
+                start: BlockHeight::ZERO,
+                limit: None,
+            },
+        )
+        .await?;
+    assert_eq!(history.iter().map(|c| c.hash()).collect::<Vec<_>>(), hashes);
+    let history = observer
+        .download_history(sender_id, BlockHeightRange::single(BlockHeight::from(3)))
+        .await?;
+    assert!(history.is_empty());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_download_history_from_faulty_validators<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let observer = builder.add_root_chain(2, Amount::ZERO).await?;
+    let sender_id = sender.chain_id();
+
+    // Validator 1 misses all of the sender's blocks.
+    builder.set_fault_type([1], FaultType::Offline).await;
+    let mut hashes = Vec::new();
+    for _ in 0..3 {
+        let certificate = sender.burn(None, Amount::ONE).await.unwrap().unwrap();
+        hashes.push(certificate.hash());
+    }
+    builder.set_fault_type([1], FaultType::Honest).await;
+
+    // Validator 0 serves certificates with invalid signatures.
+    builder.faulty_node(0).set_fault(
+        [RequestKind::DownloadCertificate],
+        Fault::TamperCertificates,
+    );
+    let whole_history = BlockHeightRange {
+        start: BlockHeight::ZERO,
+        limit: None,
+    };
+    let history = observer
+        .download_history(sender_id, whole_history.clone())
+        .await?;
+    assert_eq!(history.iter().map(|c| c.hash()).collect::<Vec<_>>(), hashes);
+
+    // Without the honest validators, no validator provides the whole history.
+    let offline = Fault::Error(ClientIoError {
+        error: "offline".to_string(),
+    });
+    for index in [2, 3] {
+        builder
+            .faulty_node(index)
+            .set_fault([RequestKind::DownloadCertificate], offline.clone());
+    }
+    let result = observer.download_history(sender_id, whole_history).await;
+    assert_matches!(
+        result,
+        Err(ChainClientError::CannotDownloadHistory { chain_id, start })
+            if chain_id == sender_id && start == BlockHeight::ZERO
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_synchronize_with_progress<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    for i in 0..4 {
+        builder
+            .node(i)
+            .set_maximum_sent_certificate_hashes_per_response(10)
+            .await;
+    }
+    let sender = builder.add_root_chain(1, Amount::from_attos(30)).await?;
+    let chain_id = sender.chain_id();
+    for _ in 0..30 {
+        sender.burn(None, Amount::from_attos(1)).await.unwrap();
+    }
+
+    // A client that is 30 blocks behind downloads them in batches of 10, reporting each one.
+    let observer = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let mut reports = Vec::new();
+    let balance = observer
+        .synchronize_with_progress(|height, target| reports.push((height, target)))
+        .await?;
+    assert_eq!(balance, Amount::ZERO);
+    assert_eq!(observer.next_block_height(), BlockHeight::from(30));
+    let expected = [0, 10, 20, 30]
+        .map(|height| (BlockHeight::from(height), BlockHeight::from(30)))
+        .to_vec();
+    assert_eq!(reports, expected);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_download_tampered_certificates<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let chain_id = sender.chain_id();
+    for _ in 0..3 {
+        sender.burn(None, Amount::ONE).await.unwrap().unwrap();
+    }
+    let observer = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+
+    // Certificates with invalid signatures are rejected.
+    for i in 0..4 {
+        builder
+            .faulty_node(i)
+            .set_fault(RequestKind::ALL, Fault::TamperCertificates);
+    }
+    assert_matches!(
+        observer.synchronize_with_progress(|_, _| {}).await,
+        Err(ChainClientError::CannotDownloadCertificates { .. })
+    );
+    assert_eq!(
+        observer.chain_info().await?.next_block_height,
+        BlockHeight::ZERO
+    );
+
+    // A single honest validator is enough to download them.
+    builder.faulty_node(2).clear_faults();
+    observer.synchronize_with_progress(|_, _| {}).await?;
+    assert_eq!(
+        observer.chain_info().await?.next_block_height,
+        BlockHeight::from(3)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_verify_chain<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    assert_eq!(sender.verify_chain().await?, BlockHeight::ZERO);
+
+    for _ in 0..3 {
+        sender
+            .transfer_to_account(None, Amount::ONE, Account::chain(receiver.chain_id()))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    assert_eq!(sender.verify_chain().await?, BlockHeight::from(3));
+
+    // A client that downloaded the chain from the validators can verify it, too.
+    let observer = builder
+        .make_client(
+            sender.chain_id(),
+            KeyPair::generate(),
+            None,
+            BlockHeight::ZERO,
+        )
+        .await?;
+    observer.synchronize_from_validators().await?;
+    assert_eq!(observer.verify_chain().await?, BlockHeight::from(3));
+
+    // A certificate whose signatures were tampered with in storage is detected. It has the
+    // same hash, since that only covers the block, so it replaces the original.
+    let storage = observer.storage_client();
+    let hash = observer
+        .chain_state_view()
+        .await?
+        .confirmed_log
+        .get(1)
+        .await?
+        .unwrap();
+    let (value, round, signatures) = storage.read_certificate(hash).await?.destructure();
+    let round = match round {
+        Round::SingleLeader(round) => Round::SingleLeader(round + 1),
+        _ => Round::SingleLeader(0),
+    };
+    let mut batch = Batch::new();
+    batch.add_certificate(GenericCertificate::new(value, round, signatures));
+    storage.write_batch(batch).await?;
+    assert_matches!(
+        observer.verify_chain().await,
+        Err(ChainClientError::InconsistentChain { height, .. }) if height == BlockHeight::from(1)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_trust_local_state<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let mut sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let mut shared = builder.add_root_chain(2, Amount::from_tokens(4)).await?;
+    sender.options_mut().trust_local_state_for = Duration::from_secs(3600);
+    shared.options_mut().trust_local_state_for = Duration::from_secs(3600);
+    let request_count = |builder: &mut TestBuilder<B>| {
+        (0..4)
+            .map(|i| builder.node(i).request_count())
+            .sum::<usize>()
+    };
+
+    // Right after a block, the sole owner trusts its local state and sends no requests.
+    sender.burn(None, Amount::ONE).await.unwrap().unwrap();
+    let count = request_count(&mut builder);
+    sender.synchronize_from_validators().await?;
+    sender.prepare_chain().await?;
+    assert_eq!(request_count(&mut builder), count);
+
+    // Forcing a synchronization contacts the validators anyway.
+    sender.force_synchronize().await?;
+    assert!(request_count(&mut builder) > count);
+
+    // A chain with other owners is always synchronized.
+    shared
+        .share_ownership(KeyPair::generate().public().into(), 100)
+        .await
+        .unwrap()
+        .unwrap();
+    let count = request_count(&mut builder);
+    shared.synchronize_from_validators().await?;
+    assert!(request_count(&mut builder) > count);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_operation_queue<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(20)).await?;
+    let transfer = Operation::System(SystemOperation::Transfer {
+        owner: None,
+        recipient: Recipient::root(2),
+        amount: Amount::ONE,
+    });
+
+    // Concurrent callers each get the certificate of their own block.
+    let transfers = (0..10).map(|_| async {
+        let receipt = sender.enqueue_operation(transfer.clone());
+        assert_matches!(
+            sender.process_operation_queue().await,
+            Ok(ClientOutcome::Committed(()))
+        );
+        receipt.await
+    });
+    let mut hashes = BTreeSet::new();
+    for receipt in futures::future::join_all(transfers).await {
+        hashes.insert(receipt??.hash());
+    }
+    assert_eq!(hashes.len(), 10);
+    assert_eq!(sender.next_block_height(), BlockHeight::from(10));
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(10));
+
+    // Cancelled operations are dropped without being executed.
+    let receipt = sender.enqueue_operation(transfer.clone());
+    assert_eq!(sender.cancel_operation_queue(), 1);
+    assert!(receipt.await.is_err());
+    assert_matches!(
+        sender.process_operation_queue().await,
+        Ok(ClientOutcome::Committed(()))
+    );
+    assert_eq!(sender.next_block_height(), BlockHeight::from(10));
+
+    // An operation whose processing is interrupted stays queued, and is executed once.
+    let receipt = sender.enqueue_operation(transfer.clone());
+    for _ in 0..3 {
+        let _ = futures::FutureExt::now_or_never(sender.process_operation_queue());
+    }
+    assert_matches!(
+        sender.process_operation_queue().await,
+        Ok(ClientOutcome::Committed(()))
+    );
+    receipt.await??;
+    assert_eq!(sender.next_block_height(), BlockHeight::from(11));
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(9));
+
+    // A failed operation doesn't hold up the operations behind it.
+    let failed = sender.enqueue_operation(Operation::System(SystemOperation::Transfer {
+        owner: None,
+        recipient: Recipient::root(2),
+        amount: Amount::from_tokens(100),
+    }));
+    let receipt = sender.enqueue_operation(transfer.clone());
+    assert_matches!(
+        sender.process_operation_queue().await,
+        Ok(ClientOutcome::Committed(()))
+    );
+    assert!(failed.await?.is_err());
+    receipt.await??;
+    assert!(sender.pending_proposal().is_none());
+    assert_eq!(sender.next_block_height(), BlockHeight::from(12));
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(8));
+
+    // If the validators can't be reached, the block stays pending and is committed later.
+    builder.set_fault_type([0, 1], FaultType::Offline).await;
+    let receipt = sender.enqueue_operation(transfer);
+    assert!(sender.process_operation_queue().await.is_err());
+    assert!(sender.pending_proposal().is_some());
+    builder.set_fault_type([0, 1], FaultType::Honest).await;
+    assert_matches!(
+        sender.process_operation_queue().await,
+        Ok(ClientOutcome::Committed(()))
+    );
+    receipt.await??;
+    assert!(sender.pending_proposal().is_none());
+    assert_eq!(sender.next_block_height(), BlockHeight::from(13));
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(7));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_receive_certificates_in_batches<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let recipient = Account::chain(receiver.chain_id());
+
+    // The receiver gets blocks 0, 1 and 3. Block 0 is received on its own and block 1 in a
+    // batch after it. Block 3 starts a new run, so it is received on its own, which downloads
+    // block 2 first.
+    for _ in 0..2 {
+        sender
+            .transfer_to_account(None, Amount::ONE, recipient)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    sender.burn(None, Amount::ONE).await.unwrap().unwrap();
+    sender
+        .transfer_to_account(None, Amount::ONE, recipient)
+        .await
+        .unwrap()
+        .unwrap();
+
+    receiver.synchronize_from_validators().await?;
+    let (certificates, _) = receiver.process_inbox().await?;
+    assert_eq!(certificates.len(), 1);
+    assert_eq!(certificates[0].block().body.incoming_bundles.len(), 3);
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(3));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_export_and_import_chain<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    // The importer checks the signatures against the admin chain's committees.
+    let _admin = builder.add_root_chain(0, Amount::ZERO).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let chain_id = sender.chain_id();
+    for _ in 0..3 {
+        sender.burn(None, Amount::ONE).await.unwrap().unwrap();
+    }
+    let mut export = Vec::new();
+    sender
+        .client
+        .local_node
+        .export_chain(chain_id, &mut export)
+        .await?;
+
+    // Replace the state hash in the header, which is the first record.
+    let header_len = u32::from_le_bytes(export[..4].try_into()?) as usize;
+    let mut header: ChainExportHeader = bcs::from_bytes(&export[4..4 + header_len])?;
+    header.state_hash = Some(CryptoHash::test_hash("tampered"));
+    let header_bytes = bcs::to_bytes(&header)?;
+    let mut tampered = (header_bytes.len() as u32).to_le_bytes().to_vec();
+    tampered.extend(header_bytes);
+    tampered.extend(&export[4 + header_len..]);
+
+    let observer = builder.add_root_chain(2, Amount::ZERO).await?;
+    let local_node = &observer.client.local_node;
+    let admin_id = builder.admin_id();
+    assert_matches!(
+        local_node.import_chain(admin_id, &tampered[..]).await,
+        Err(LocalNodeError::InvalidChainExport(_))
+    );
+    assert_matches!(
+        local_node
+            .import_chain(admin_id, &export[..export.len() - 1])
+            .await,
+        Err(LocalNodeError::InvalidChainExport(_))
+    );
+    // Without the admin chain's committees, no certificate can be verified.
+    assert!(local_node
+        .import_chain(ChainId::root(9), &export[..])
+        .await
+        .is_err());
+    let info = local_node.chain_info(chain_id).await?;
+    assert_eq!(info.next_block_height, BlockHeight::ZERO);
+
+    let info = local_node.import_chain(admin_id, &export[..]).await?;
+    let expected_info = sender.chain_info().await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(3));
+    assert_eq!(info.block_hash, expected_info.block_hash);
+    assert_eq!(info.state_hash, expected_info.state_hash);
+    assert_eq!(info.chain_balance, Amount::ONE);
+
+    // Importing again is a no-op.
+    let info = local_node.import_chain(admin_id, &export[..]).await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(3));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_prune_history<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(5)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let receiver_id = receiver.chain_id();
+
+    for _ in 0..3 {
+        sender
+            .transfer_to_account(None, Amount::ONE, Account::chain(receiver_id))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    receiver.synchronize_from_validators().await?;
+    receiver.process_inbox().await?;
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(3));
+
+    let storage = receiver.storage_client();
+    let sender_hashes = storage
+        .load_chain(sender.chain_id())
+        .await?
+        .confirmed_log
+        .read(..)
+        .await?;
+    let own_hashes = receiver
+        .chain_state_view()
+        .await?
+        .confirmed_log
+        .read(..)
+        .await?;
+    assert_eq!(sender_hashes.len(), 3);
+    assert!(!own_hashes.is_empty());
+    let count_stored = |hashes: Vec<CryptoHash>| {
+        let storage = storage.clone();
+        async move {
+            let mut count = 0;
+            for hash in hashes {
+                if storage.contains_certificate(hash).await? {
+                    count += 1;
+                }
+            }
+            Ok::<_, anyhow::Error>(count)
+        }
+    };
+    assert_eq!(count_stored(sender_hashes.clone()).await?, 3);
+
+    // Only the last sender certificate is kept, and none of our own are deleted.
+    receiver.prune_history(1).await?;
+    assert_eq!(count_stored(sender_hashes).await?, 1);
+    assert_eq!(count_stored(own_hashes.clone()).await?, own_hashes.len());
+
+    // The sender chain's log records the pruned blocks, so it can still be verified.
+    assert_eq!(
+        *storage
+            .load_chain(sender.chain_id())
+            .await?
+            .pruned_height
+            .get(),
+        BlockHeight::from(2)
+    );
+    assert_eq!(
+        storage
+            .list_certificate_hashes(sender.chain_id(), BlockHeight::ZERO..BlockHeight::MAX)
+            .await?
+            .len(),
+        1
     );
+    let verifier = builder
+        .make_clients_with_storage(storage.clone(), &[&sender])
+        .await?
+        .remove(0);
+    assert_eq!(verifier.verify_chain().await?, BlockHeight::from(3));
 
-    // Since blocks are free of charge on closed chains, empty blocks are not allowed.
-    assert_matches!(
-        client1.execute_operations(vec![], vec![]).await,
-        Err(ChainClientError::LocalNodeError(
-            LocalNodeError::WorkerError(WorkerError::ChainError(error))
-        )) if matches!(*error, ChainError::ClosedChain)
-    );
+    // Later transfers from the pruned sender are still received.
+    sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver_id))
+        .await
+        .unwrap()
+        .unwrap();
+    receiver.synchronize_from_validators().await?;
+    receiver.process_inbox().await?;
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(4));
 
-    // Trying to close the chain again returns None.
-    let maybe_certificate = client1.close_chain().await.unwrap().unwrap();
-    assert_matches!(maybe_certificate, None);
+    // The certificates of messages that are still in the inbox are kept.
+    let certificate = sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver_id))
+        .await
+        .unwrap()
+        .unwrap();
+    receiver.synchronize_from_validators().await?;
+    receiver.prune_history(0).await?;
+    assert!(storage.contains_certificate(certificate.hash()).await?);
+    assert_eq!(
+        *storage
+            .load_chain(sender.chain_id())
+            .await?
+            .pruned_height
+            .get(),
+        BlockHeight::from(4)
+    );
+    receiver.process_inbox().await?;
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(5));
     Ok(())
 }
 
@@ -815,32 +3714,47 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_initiating_valid_transfer_too_many_faults<B>(storage_builder: B) -> anyhow::Result<()>
+async fn test_prune_history_keeps_tracked_chains<B>(storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
 {
-    let mut builder = TestBuilder::new(storage_builder, 4, 2).await?;
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
     let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
-    let result = sender
-        .transfer_to_account_unsafe_unconfirmed(
-            None,
-            Amount::from_tokens(3),
-            Account::chain(ChainId::root(2)),
-        )
-        .await;
-    assert_matches!(
-        result,
-        Err(ChainClientError::CommunicationError(
-            CommunicationError::Trusted(crate::node::NodeError::ArithmeticError { .. })
-        )),
-        "unexpected result"
-    );
-    assert_eq!(sender.next_block_height(), BlockHeight::ZERO);
-    assert!(sender.pending_proposal().is_some());
-    assert_eq!(
-        sender.local_balance().await.unwrap(),
-        Amount::from_tokens(4)
-    );
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+
+    for _ in 0..3 {
+        sender
+            .transfer_to_account(None, Amount::ONE, Account::chain(receiver.chain_id()))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    // Both chains are tracked by the same client.
+    let storage = builder.make_storage().await?;
+    let mut clients = builder
+        .make_clients_with_storage(storage.clone(), &[&sender, &receiver])
+        .await?;
+    let receiver = clients.pop().unwrap();
+    let sender = clients.pop().unwrap();
+    sender.synchronize_from_validators().await?;
+    receiver.synchronize_from_validators().await?;
+    receiver.process_inbox().await?;
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(3));
+
+    // The sender chain's certificates are still needed by its own client.
+    receiver.prune_history(0).await?;
+    let sender_hashes = sender
+        .chain_state_view()
+        .await?
+        .confirmed_log
+        .read(..)
+        .await?;
+    assert_eq!(sender_hashes.len(), 3);
+    for hash in sender_hashes {
+        assert!(storage.contains_certificate(hash).await?);
+    }
+    assert_eq!(sender.verify_chain().await?, BlockHeight::from(3));
     Ok(())
 }
 
@@ -850,100 +3764,36 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_bidirectional_transfer<B>(storage_builder: B) -> anyhow::Result<()>
+async fn test_confirmed_certificates_stream<B>(storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
 {
-    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
-    let client1 = builder.add_root_chain(1, Amount::from_tokens(3)).await?;
-    let client2 = builder.add_root_chain(2, Amount::ZERO).await?;
-    assert_eq!(
-        client1.local_balance().await.unwrap(),
-        Amount::from_tokens(3)
-    );
-    assert_eq!(
-        client1.query_system_application(SystemQuery).await.unwrap(),
-        QueryOutcome {
-            response: SystemResponse {
-                chain_id: client1.chain_id(),
-                balance: Amount::from_tokens(3),
-            },
-            operations: vec![],
-        }
-    );
-    let certificate = client1
-        .transfer_to_account(
-            None,
-            Amount::from_tokens(3),
-            Account::chain(client2.chain_id),
-        )
-        .await
-        .unwrap()
-        .unwrap();
-
-    assert_eq!(client1.next_block_height(), BlockHeight::from(1));
-    assert!(client1.pending_proposal().is_none());
-    assert_eq!(client1.local_balance().await.unwrap(), Amount::ZERO);
-    assert_eq!(
-        client1.query_system_application(SystemQuery).await.unwrap(),
-        QueryOutcome {
-            response: SystemResponse {
-                chain_id: client1.chain_id(),
-                balance: Amount::ZERO,
-            },
-            operations: vec![],
-        },
-    );
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver_id = builder.add_root_chain(2, Amount::ZERO).await?.chain_id();
 
-    assert_eq!(
-        builder
-            .check_that_validators_have_certificate(client1.chain_id, BlockHeight::ZERO, 3)
+    // The stream waits for blocks that don't exist yet.
+    let certificates = sender.confirmed_certificates(BlockHeight::ZERO).await?;
+    for _ in 0..2 {
+        sender
+            .transfer_to_account(None, Amount::ONE, Account::chain(receiver_id))
             .await
-            .unwrap(),
-        certificate
-    );
-    // Local balance is lagging.
-    assert_eq!(client2.local_balance().await.unwrap(), Amount::ZERO);
-    // Obtain the certificate but do not process the inbox yet.
-    client2.synchronize_from_validators().await.unwrap();
-    assert_eq!(client2.local_balance().await.unwrap(), Amount::ZERO);
-    assert_eq!(
-        client2.query_system_application(SystemQuery).await.unwrap(),
-        QueryOutcome {
-            response: SystemResponse {
-                chain_id: client2.chain_id(),
-                balance: Amount::from_tokens(0),
-            },
-            operations: vec![],
-        },
-    );
-
-    // Process the inbox and send back some money.
-    assert_eq!(client2.next_block_height(), BlockHeight::ZERO);
-    client2
-        .transfer_to_account(None, Amount::ONE, Account::chain(client1.chain_id))
+            .unwrap()
+            .unwrap();
+    }
+    let heights = certificates
+        .take(2)
+        .map(|certificate| certificate.map(|certificate| certificate.inner().height()))
+        .collect::<Vec<_>>()
         .await
-        .unwrap();
-    assert_eq!(client2.next_block_height(), BlockHeight::from(1));
-    assert!(client2.pending_proposal().is_none());
-    assert_eq!(
-        client2.local_balance().await.unwrap(),
-        Amount::from_tokens(2)
-    );
-    client1.synchronize_from_validators().await.unwrap();
-    client1.process_inbox().await.unwrap();
-    assert_eq!(client1.local_balance().await.unwrap(), Amount::ONE);
-    // Local balance from client2 is now consolidated.
-    assert_eq!(
-        client2.query_system_application(SystemQuery).await.unwrap(),
-        QueryOutcome {
-            response: SystemResponse {
-                chain_id: client2.chain_id(),
-                balance: Amount::from_tokens(2),
-            },
-            operations: vec![],
-        },
-    );
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(heights, vec![BlockHeight::ZERO, BlockHeight::from(1)]);
+
+    // A new stream resumes from the given height.
+    let mut certificates = Box::pin(sender.confirmed_certificates(BlockHeight::from(1)).await?);
+    let certificate = certificates.next().await.unwrap()?;
+    assert_eq!(certificate.inner().height(), BlockHeight::from(1));
     Ok(())
 }
 
@@ -1102,14 +3952,31 @@ where
     let user = builder.add_root_chain(1, Amount::ZERO).await?;
     let validators = builder.initial_committee.validators().clone();
 
-    let committee = Committee::new(validators.clone(), ResourceControlPolicy::only_fuel());
-    admin.stage_new_committee(committee).await.unwrap();
+    let committee = Committee::new(validators.clone(), ResourceControlPolicy::only_fuel())?;
+    admin.stage_new_committee(committee, false).await.unwrap();
     admin.finalize_committee().await.unwrap();
 
     // Root chain 1 receives the notification about the new epoch.
     user.synchronize_from_validators().await.unwrap();
     user.process_inbox().await.unwrap();
     assert_eq!(user.epoch().await.unwrap(), Epoch::from(1));
+    let admin_subscription = ChannelSubscription {
+        chain_id: admin.chain_id(),
+        name: SystemChannel::Admin.name(),
+    };
+    assert!(user.subscriptions().await?.contains(&admin_subscription));
+    let admin_origin = Origin::channel(
+        admin.chain_id(),
+        ChannelFullName {
+            application_id: GenericApplicationId::System,
+            name: SystemChannel::Admin.name(),
+        },
+    );
+    let admin_inbox_cursor = {
+        let chain = user.chain_state_view().await?;
+        let inbox = chain.inboxes.try_load_entry(&admin_origin).await?.unwrap();
+        *inbox.next_cursor_to_add.get()
+    };
 
     // Stop listening for new committees.
     let cert = user
@@ -1117,6 +3984,7 @@ where
         .await
         .unwrap()
         .unwrap();
+    assert!(!user.subscriptions().await?.contains(&admin_subscription));
     admin
         .receive_certificate_and_update_validators(cert)
         .await
@@ -1124,8 +3992,8 @@ where
     admin.process_inbox().await.unwrap();
 
     // Create a new committee.
-    let committee = Committee::new(validators, ResourceControlPolicy::only_fuel());
-    admin.stage_new_committee(committee).await.unwrap();
+    let committee = Committee::new(validators, ResourceControlPolicy::only_fuel())?;
+    admin.stage_new_committee(committee, false).await.unwrap();
     assert_eq!(admin.next_block_height(), BlockHeight::from(3));
     assert!(admin.pending_proposal().is_none());
     assert!(admin.key_pair().await.is_ok());
@@ -1159,9 +4027,16 @@ where
     // User is unsubscribed, so the migration message is not even in the inbox yet.
     user.process_inbox().await.unwrap();
     assert_eq!(user.epoch().await.unwrap(), Epoch::from(1));
+    {
+        let chain = user.chain_state_view().await?;
+        let inbox = chain.inboxes.try_load_entry(&admin_origin).await?.unwrap();
+        assert_eq!(*inbox.next_cursor_to_add.get(), admin_inbox_cursor);
+        assert_eq!(inbox.added_bundles.count(), 0);
+    }
 
     // Now subscribe explicitly to migrations.
     let cert = user.subscribe_to_new_committees().await.unwrap().unwrap();
+    assert!(user.subscriptions().await?.contains(&admin_subscription));
     admin
         .receive_certificate_and_update_validators(cert)
         .await
@@ -1214,6 +4089,148 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_propose_with_committee<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let admin = builder.add_root_chain(0, Amount::from_tokens(3)).await?;
+    let old_committee = builder.initial_committee.clone();
+    let transfer = Operation::System(SystemOperation::Transfer {
+        owner: None,
+        recipient: Recipient::Burn,
+        amount: Amount::ONE,
+    });
+
+    // The block is certified by the committee of the current epoch.
+    let certificate = admin
+        .propose_with_committee(
+            vec![transfer.clone()],
+            vec![],
+            Epoch::ZERO,
+            old_committee.clone(),
+        )
+        .await?
+        .unwrap();
+    assert_eq!(certificate.block().header.epoch, Epoch::ZERO);
+
+    // After a committee handover, the old committee is not used anymore.
+    let validators = old_committee.validators().clone();
+    let new_committee = Committee::new(validators, ResourceControlPolicy::only_fuel())?;
+    admin
+        .stage_new_committee(new_committee.clone(), false)
+        .await?
+        .unwrap();
+    assert_eq!(admin.epoch().await?, Epoch::from(1));
+    let next_block_height = admin.next_block_height();
+    assert_matches!(
+        admin
+            .propose_with_committee(vec![transfer.clone()], vec![], Epoch::ZERO, old_committee)
+            .await,
+        Err(ChainClientError::CommitteeChanged)
+    );
+    assert_eq!(admin.next_block_height(), next_block_height);
+    assert!(admin.pending_proposal().is_none());
+
+    // A new epoch with the same committee is a handover, too.
+    admin
+        .stage_new_committee(new_committee.clone(), false)
+        .await?
+        .unwrap();
+    assert_eq!(admin.epoch().await?, Epoch::from(2));
+    let next_block_height = admin.next_block_height();
+    assert_matches!(
+        admin
+            .propose_with_committee(
+                vec![transfer.clone()],
+                vec![],
+                Epoch::from(1),
+                new_committee.clone(),
+            )
+            .await,
+        Err(ChainClientError::CommitteeChanged)
+    );
+    assert_eq!(admin.next_block_height(), next_block_height);
+    assert!(admin.pending_proposal().is_none());
+
+    let certificate = admin
+        .propose_with_committee(vec![transfer], vec![], Epoch::from(2), new_committee)
+        .await?
+        .unwrap();
+    assert_eq!(certificate.block().header.epoch, Epoch::from(2));
+    assert_eq!(admin.local_balance().await?, Amount::ONE);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_stage_invalid_committee<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let admin = builder.add_root_chain(0, Amount::from_tokens(3)).await?;
+    let mut validators = builder.initial_committee.validators().clone();
+    let policy = ResourceControlPolicy::only_fuel();
+    let name = *validators.keys().next().unwrap();
+
+    assert_eq!(
+        Committee::new(BTreeMap::new(), policy.clone()),
+        Err(CommitteeError::NoValidators)
+    );
+    validators.get_mut(&name).unwrap().votes = 0;
+    assert_eq!(
+        Committee::new(validators.clone(), policy.clone()),
+        Err(CommitteeError::ZeroVotes(name))
+    );
+    validators.get_mut(&name).unwrap().votes = u64::MAX;
+    assert_eq!(
+        Committee::new(validators.clone(), policy.clone()),
+        Err(CommitteeError::TooManyVotes)
+    );
+
+    // A committee with a validator that the client doesn't know how to reach is rejected
+    // before any block is proposed.
+    validators.get_mut(&name).unwrap().votes = 1;
+    validators.insert(
+        ValidatorName(KeyPair::generate().public()),
+        ValidatorState {
+            network_address: "unknown".to_string(),
+            votes: 1,
+        },
+    );
+    let committee = Committee::new(validators, policy.clone())?;
+    assert_matches!(
+        admin.stage_new_committee(committee, false).await,
+        Err(ChainClientError::UnreachableCommittee(_))
+    );
+    assert_eq!(admin.next_block_height(), BlockHeight::ZERO);
+    assert_eq!(admin.epoch().await?, Epoch::ZERO);
+
+    // A committee where a single validator has a quorum is only accepted if forced.
+    let mut validators = builder.initial_committee.validators().clone();
+    validators.get_mut(&name).unwrap().votes = 10;
+    let committee = Committee::new(validators, policy)?;
+    assert_matches!(
+        admin.stage_new_committee(committee.clone(), false).await,
+        Err(ChainClientError::UnsafeCommittee(dominant)) if dominant == name
+    );
+    assert_eq!(admin.next_block_height(), BlockHeight::ZERO);
+    admin.stage_new_committee(committee, true).await?;
+    assert_eq!(admin.epoch().await?, Epoch::from(1));
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[test_log::test(tokio::test)]
@@ -1842,7 +4859,7 @@ where
 
     // The round has not timed out yet, so validators will not sign a timeout certificate.
     // If the malicious and one honest validator happen to be much faster than the other
-    // two honest validators, only those two samples may be returned. Otherwise we get
+    // two honest validators, only those two errors may be returned. Otherwise we get
     // a trusted MissingVoteInValidatorResponse, because at least two returned that.
     let result = client.request_leader_timeout().await;
     if !matches!(
@@ -1851,8 +4868,8 @@ where
             CommunicationError::Trusted(NodeError::MissingVoteInValidatorResponse)
         ))
     ) && !matches!(&result,
-        Err(ChainClientError::CommunicationError(CommunicationError::Sample(samples)))
-        if samples.iter().any(|(err, _)| matches!(err, NodeError::MissingVoteInValidatorResponse))
+        Err(ChainClientError::CommunicationError(CommunicationError::QuorumNotReached { errors, .. }))
+        if errors.iter().any(|(_, err)| matches!(err, NodeError::MissingVoteInValidatorResponse))
     ) {
         panic!("unexpected leader timeout result: {:?}", result);
     }
@@ -1894,7 +4911,18 @@ where
         ClientOutcome::Committed(_) => panic!("Committed a block where we aren't the leader."),
         ClientOutcome::WaitForTimeout(timeout) => timeout,
     };
-    client.clear_pending_proposal();
+    assert_eq!(client.pending_proposal_height(), Some(BlockHeight::from(1)));
+    let discarded = client.clear_pending_proposal().unwrap();
+    assert_eq!(
+        discarded.block.operations,
+        vec![Operation::System(SystemOperation::Transfer {
+            owner: None,
+            recipient: Recipient::root(2),
+            amount: Amount::ONE,
+        })]
+    );
+    assert_eq!(client.pending_proposal_height(), None);
+    assert!(client.clear_pending_proposal().is_none());
     assert!(client.request_leader_timeout().await.is_err());
     clock.set(timeout.timestamp);
     client.request_leader_timeout().await.unwrap();
@@ -1931,6 +4959,50 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_advance_round<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let client = builder.add_root_chain(1, Amount::from_tokens(3)).await?;
+    let owner0 = client.public_key().await.unwrap().into();
+    let owner1 = KeyPair::generate().public().into();
+
+    let owners = [(owner0, 100), (owner1, 100)];
+    let ownership = ChainOwnership::multiple(owners, 10, TimeoutConfig::default());
+    client.change_ownership(ownership).await.unwrap();
+    assert_eq!(client.current_round().await?, Round::MultiLeader(0));
+
+    // The validators vote for our proposal in round 0, but it never gets confirmed.
+    builder.set_fault_type([3], FaultType::Offline).await;
+    builder
+        .set_fault_type([0, 1, 2], FaultType::DontProcessValidated)
+        .await;
+    let result = client.transfer(None, Amount::ONE, Recipient::root(2)).await;
+    assert!(result.is_err());
+    assert!(client.pending_proposal().is_some());
+
+    // Retrying in a fresh round succeeds.
+    builder
+        .set_fault_type([0, 1, 2, 3], FaultType::Honest)
+        .await;
+    assert_eq!(client.advance_round().await?, Round::MultiLeader(1));
+    assert_eq!(client.current_round().await?, Round::MultiLeader(1));
+    let certificate = client.process_pending_block().await?.unwrap().unwrap();
+    assert_eq!(certificate.round, Round::MultiLeader(1));
+    assert_eq!(client.local_balance().await?, Amount::from_tokens(2));
+
+    // The advanced round only applied to that block height.
+    assert_eq!(client.current_round().await?, Round::MultiLeader(0));
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
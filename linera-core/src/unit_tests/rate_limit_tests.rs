@@ -0,0 +1,48 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures::future::join_all;
+use linera_base::time::{timer::sleep, Duration, Instant};
+
+use super::{RateLimit, RateLimiter};
+
+/// Tests that no more than `max_in_flight` requests run at once, and that the others queue
+/// until they can run instead of failing.
+#[tokio::test]
+async fn test_max_in_flight() {
+    let limiter = RateLimiter::new(RateLimit {
+        max_in_flight: 2,
+        max_per_second: f64::INFINITY,
+    });
+    let in_flight = AtomicUsize::new(0);
+    let max_observed = AtomicUsize::new(0);
+    let completed = AtomicUsize::new(0);
+
+    let requests = (0..10).map(|_| async {
+        let _permit = limiter.acquire().await;
+        let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        max_observed.fetch_max(current, Ordering::SeqCst);
+        sleep(Duration::from_millis(10)).await;
+        in_flight.fetch_sub(1, Ordering::SeqCst);
+        completed.fetch_add(1, Ordering::SeqCst);
+    });
+    join_all(requests).await;
+
+    assert_eq!(completed.load(Ordering::SeqCst), 10);
+    assert_eq!(max_observed.load(Ordering::SeqCst), 2);
+}
+
+/// Tests that requests beyond the initial burst are spread out according to `max_per_second`.
+#[tokio::test]
+async fn test_max_per_second() {
+    let limiter = RateLimiter::new(RateLimit {
+        max_in_flight: usize::MAX,
+        max_per_second: 20.0,
+    });
+    let start = Instant::now();
+    // The first 20 requests use up the burst; the next 10 need another half second.
+    join_all((0..30).map(|_| limiter.acquire())).await;
+    assert!(start.elapsed() >= Duration::from_millis(450));
+}
@@ -4,14 +4,17 @@
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     num::NonZeroUsize,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::Duration,
     vec,
 };
 
 use async_trait::async_trait;
 use futures::{
-    future::Either,
+    future::{self, Either},
     lock::{Mutex, MutexGuard},
     Future,
 };
@@ -29,7 +32,7 @@ use linera_chain::{
 };
 use linera_execution::{
     committee::{Committee, ValidatorName},
-    ResourceControlPolicy, WasmRuntime,
+    ChildIdDeriver, ResourceControlPolicy, WasmRuntime,
 };
 use linera_storage::{DbStorage, Storage, TestClock};
 #[cfg(all(not(target_arch = "wasm32"), feature = "storage-service"))]
@@ -40,7 +43,13 @@ use linera_views::dynamo_db::DynamoDbStore;
 #[cfg(feature = "scylladb")]
 use linera_views::scylla_db::ScyllaDbStore;
 use linera_views::{
-    memory::MemoryStore, random::generate_test_namespace, store::TestKeyValueStore as _,
+    memory::{MemoryStore, MemoryStoreConfig, MemoryStoreError, TEST_MEMORY_MAX_STREAM_QUERIES},
+    random::generate_test_namespace,
+    store::{
+        AdminKeyValueStore, KeyValueStoreError, ReadableKeyValueStore, TestKeyValueStore,
+        WithError, WritableKeyValueStore,
+    },
+    views::{RootView as _, View as _},
 };
 use tokio::sync::oneshot;
 use tokio_stream::wrappers::UnboundedReceiverStream;
@@ -51,13 +60,15 @@ use {
 };
 
 use crate::{
-    client::{ChainClient, Client},
+    chain_worker::InactiveChainPolicy,
+    client::{ChainClient, Client, ClientMetrics, KeyDerivation, Signer},
     data_types::*,
     node::{
         CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode,
         ValidatorNodeProvider,
     },
     notifier::ChannelNotifier,
+    test_helpers::FaultyValidatorNode,
     updater::DEFAULT_GRACE_PERIOD,
     worker::{NetworkActions, Notification, ProcessableCertificate, WorkerState},
 };
@@ -71,6 +82,8 @@ pub enum FaultType {
     DontSendConfirmVote,
     DontProcessValidated,
     DontSendValidateVote,
+    /// Never responds to any request.
+    Unresponsive,
 }
 
 /// A validator used for testing. "Faulty" validators ignore block proposals (but not
@@ -95,6 +108,8 @@ where
 {
     name: ValidatorName,
     client: Arc<Mutex<LocalValidator<S>>>,
+    /// The number of requests the validator has received.
+    request_count: Arc<AtomicUsize>,
 }
 
 impl<S> ValidatorNode for LocalValidatorClient<S>
@@ -260,6 +275,7 @@ where
         Self {
             name,
             client: Arc::new(Mutex::new(client)),
+            request_count: Arc::default(),
         }
     }
 
@@ -267,6 +283,11 @@ where
         self.name
     }
 
+    /// Returns the number of requests the validator has received so far.
+    pub fn request_count(&self) -> usize {
+        self.request_count.load(Ordering::SeqCst)
+    }
+
     async fn set_fault_type(&self, fault_type: FaultType) {
         self.client.lock().await.fault_type = fault_type;
     }
@@ -275,6 +296,43 @@ where
         self.client.lock().await.fault_type
     }
 
+    /// Closes the notification streams of all current subscribers, as if the validator had
+    /// dropped their connections.
+    pub async fn drop_subscriptions(&self) {
+        self.client.lock().await.notifier = Arc::new(ChannelNotifier::default());
+    }
+
+    /// Returns whether any client is subscribed to the notifications of the given chain.
+    pub async fn has_subscribers(&self, chain_id: ChainId) -> bool {
+        self.client.lock().await.notifier.has_subscribers(&chain_id)
+    }
+
+    /// Limits the number of sent certificate hashes the validator returns per chain info
+    /// response.
+    pub async fn set_maximum_sent_certificate_hashes_per_response(&self, maximum: usize) {
+        let mut validator = self.client.lock().await;
+        validator.state.clear_chain_workers();
+        validator.state = validator
+            .state
+            .clone()
+            .with_maximum_sent_certificate_hashes_per_response(maximum);
+    }
+
+    /// Removes all entries from the validator's received log of the given chain, as if it had
+    /// been truncated.
+    pub async fn clear_received_log(&self, chain_id: ChainId) -> anyhow::Result<()> {
+        let validator = self.client.lock().await;
+        validator.state.clear_chain_workers();
+        let mut chain = validator
+            .state
+            .storage_client()
+            .load_chain(chain_id)
+            .await?;
+        chain.received_log.clear();
+        chain.save().await?;
+        Ok(())
+    }
+
     /// Obtains the basic `ChainInfo` data for the local validator chain, with chain manager values.
     pub async fn chain_info_with_manager_values(
         &mut self,
@@ -293,6 +351,10 @@ where
         R: Future<Output = Result<(), T>> + Send,
         F: FnOnce(Self, oneshot::Sender<T>) -> R + Send + 'static,
     {
+        self.request_count.fetch_add(1, Ordering::SeqCst);
+        if self.fault_type().await == FaultType::Unresponsive {
+            return future::pending().await;
+        }
         let validator = self.clone();
         let (sender, receiver) = oneshot::channel();
         tokio::spawn(async move {
@@ -316,9 +378,11 @@ where
                 handle_block_proposal_result.expect("handle_block_proposal_result should be Some")
             }
             _ => match validator.fault_type {
-                FaultType::Offline | FaultType::OfflineWithInfo => Err(NodeError::ClientIoError {
-                    error: "offline".to_string(),
-                }),
+                FaultType::Offline | FaultType::OfflineWithInfo | FaultType::Unresponsive => {
+                    Err(NodeError::ClientIoError {
+                        error: "offline".to_string(),
+                    })
+                }
                 FaultType::Malicious => Err(ArithmeticError::Overflow.into()),
                 FaultType::DontSendValidateVote => Err(NodeError::ClientIoError {
                     error: "refusing to validate".to_string(),
@@ -338,7 +402,10 @@ where
         validator: &mut MutexGuard<'_, LocalValidator<S>>,
     ) -> Option<Result<(ChainInfoResponse, NetworkActions), NodeError>> {
         match validator.fault_type {
-            FaultType::Offline | FaultType::OfflineWithInfo | FaultType::Malicious => None,
+            FaultType::Offline
+            | FaultType::OfflineWithInfo
+            | FaultType::Malicious
+            | FaultType::Unresponsive => None,
             FaultType::Honest
             | FaultType::DontSendConfirmVote
             | FaultType::DontProcessValidated
@@ -369,7 +436,7 @@ where
                     .await
                     .map_err(Into::into),
             ),
-            FaultType::Offline | FaultType::OfflineWithInfo => None,
+            FaultType::Offline | FaultType::OfflineWithInfo | FaultType::Unresponsive => None,
         }
     }
 
@@ -421,9 +488,11 @@ where
                 | FaultType::DontSendValidateVote => {
                     handle_certificate_result.expect("handle_certificate_result should be Some")
                 }
-                FaultType::Offline | FaultType::OfflineWithInfo => Err(NodeError::ClientIoError {
-                    error: "offline".to_string(),
-                }),
+                FaultType::Offline | FaultType::OfflineWithInfo | FaultType::Unresponsive => {
+                    Err(NodeError::ClientIoError {
+                        error: "offline".to_string(),
+                    })
+                }
             },
         }
     }
@@ -600,7 +669,7 @@ where
 }
 
 #[derive(Clone)]
-pub struct NodeProvider<S>(BTreeMap<ValidatorName, Arc<Mutex<LocalValidator<S>>>>)
+pub struct NodeProvider<S>(BTreeMap<ValidatorName, FaultyValidatorNode<LocalValidatorClient<S>>>)
 where
     S: Storage;
 
@@ -608,7 +677,7 @@ impl<S> ValidatorNodeProvider for NodeProvider<S>
 where
     S: Storage + Clone + Send + Sync + 'static,
 {
-    type Node = LocalValidatorClient<S>;
+    type Node = FaultyValidatorNode<LocalValidatorClient<S>>;
 
     fn make_node(&self, _name: &str) -> Result<Self::Node, NodeError> {
         unimplemented!()
@@ -630,23 +699,26 @@ where
                         address: address.as_ref().to_string(),
                     })
                     .cloned()
-                    .map(|client| (name, LocalValidatorClient { name, client }))
+                    .map(|client| (name, client))
             })
             .collect::<Result<Vec<_>, _>>()?
             .into_iter())
     }
 }
 
-impl<S> FromIterator<LocalValidatorClient<S>> for NodeProvider<S>
+impl<S> FromIterator<FaultyValidatorNode<LocalValidatorClient<S>>> for NodeProvider<S>
 where
     S: Storage,
 {
     fn from_iter<T>(iter: T) -> Self
     where
-        T: IntoIterator<Item = LocalValidatorClient<S>>,
+        T: IntoIterator<Item = FaultyValidatorNode<LocalValidatorClient<S>>>,
     {
-        let destructure = |validator: LocalValidatorClient<S>| (validator.name, validator.client);
-        Self(iter.into_iter().map(destructure).collect())
+        Self(
+            iter.into_iter()
+                .map(|validator| (validator.inner().name, validator))
+                .collect(),
+        )
     }
 }
 
@@ -661,7 +733,7 @@ pub struct TestBuilder<B: StorageBuilder> {
     pub initial_committee: Committee,
     admin_id: ChainId,
     genesis_storage_builder: GenesisStorageBuilder,
-    validator_clients: Vec<LocalValidatorClient<B::Storage>>,
+    validator_clients: Vec<FaultyValidatorNode<LocalValidatorClient<B::Storage>>>,
     validator_storages: HashMap<ValidatorName, B::Storage>,
     chain_client_storages: Vec<B::Storage>,
 }
@@ -682,12 +754,17 @@ struct GenesisStorageBuilder {
 
 struct GenesisAccount {
     description: ChainDescription,
-    public_key: PublicKey,
+    public_key: AccountPublicKey,
     balance: Amount,
 }
 
 impl GenesisStorageBuilder {
-    fn add(&mut self, description: ChainDescription, public_key: PublicKey, balance: Amount) {
+    fn add(
+        &mut self,
+        description: ChainDescription,
+        public_key: AccountPublicKey,
+        balance: Amount,
+    ) {
         self.accounts.push(GenesisAccount {
             description,
             public_key,
@@ -746,14 +823,14 @@ where
                 storage.clone(),
                 NonZeroUsize::new(100).expect("Chain worker limit should not be zero"),
             )
-            .with_allow_inactive_chains(false)
+            .with_inactive_chain_policy(InactiveChainPolicy::Reject)
             .with_allow_messages_from_deprecated_epochs(false);
             let validator = LocalValidatorClient::new(name, state);
             if i < with_faulty_validators {
                 faulty_validators.insert(name);
                 validator.set_fault_type(FaultType::Malicious).await;
             }
-            validator_clients.push(validator);
+            validator_clients.push(FaultyValidatorNode::new(validator));
             validator_storages.insert(name, storage);
         }
         tracing::info!(
@@ -773,14 +850,15 @@ where
 
     pub fn with_policy(mut self, policy: ResourceControlPolicy) -> Self {
         let validators = self.initial_committee.validators().clone();
-        self.initial_committee = Committee::new(validators, policy);
+        self.initial_committee =
+            Committee::new(validators, policy).expect("the initial committee should be valid");
         self
     }
 
     pub async fn set_fault_type(&mut self, indexes: impl AsRef<[usize]>, fault_type: FaultType) {
         let mut faulty_validators = vec![];
         for index in indexes.as_ref() {
-            let validator = &mut self.validator_clients[*index];
+            let validator = self.validator_clients[*index].inner();
             validator.set_fault_type(fault_type).await;
             faulty_validators.push(validator.name);
         }
@@ -796,14 +874,26 @@ where
         &mut self,
         index: u32,
         balance: Amount,
+    ) -> Result<ChainClient<NodeProvider<B::Storage>, B::Storage>, anyhow::Error> {
+        self.add_root_chain_with_key(index, balance, KeyPair::generate())
+            .await
+    }
+
+    /// Adds a root chain owned by the given key, which can use any signature scheme.
+    pub async fn add_root_chain_with_key(
+        &mut self,
+        index: u32,
+        balance: Amount,
+        key_pair: impl Into<AccountSecretKey>,
     ) -> Result<ChainClient<NodeProvider<B::Storage>, B::Storage>, anyhow::Error> {
         let description = ChainDescription::Root(index);
-        let key_pair = KeyPair::generate();
+        let key_pair = key_pair.into();
         let public_key = key_pair.public();
         // Remember what's in the genesis store for future clients to join.
         self.genesis_storage_builder
             .add(description, public_key, balance);
         for validator in &self.validator_clients {
+            let validator = validator.inner();
             let storage = self.validator_storages.get_mut(&validator.name).unwrap();
             if validator.fault_type().await == FaultType::Malicious {
                 storage
@@ -855,7 +945,10 @@ where
                 genesis_account.description,
                 ChainDescription::Root(i as u32)
             );
-            result.push((genesis_account.public_key, genesis_account.balance));
+            let AccountPublicKey::Ed25519(public_key) = genesis_account.public_key else {
+                panic!("Genesis configurations only support ed25519 keys");
+            };
+            result.push((public_key, genesis_account.balance));
         }
         result
     }
@@ -869,7 +962,16 @@ where
     }
 
     pub fn node(&mut self, index: usize) -> &mut LocalValidatorClient<B::Storage> {
-        &mut self.validator_clients[index]
+        self.validator_clients[index].inner_mut()
+    }
+
+    /// Returns the node through which clients talk to the validator with the given `index`,
+    /// to inject faults into their requests.
+    pub fn faulty_node(
+        &self,
+        index: usize,
+    ) -> &FaultyValidatorNode<LocalValidatorClient<B::Storage>> {
+        &self.validator_clients[index]
     }
 
     pub async fn make_storage(&mut self) -> anyhow::Result<B::Storage> {
@@ -886,38 +988,203 @@ where
     pub async fn make_client(
         &mut self,
         chain_id: ChainId,
-        key_pair: KeyPair,
+        key_pair: impl Into<AccountSecretKey>,
         block_hash: Option<CryptoHash>,
         block_height: BlockHeight,
     ) -> Result<ChainClient<NodeProvider<B::Storage>, B::Storage>, anyhow::Error> {
-        // Note that new clients are only given the genesis store: they must figure out
-        // the rest by asking validators.
-        let storage = self.make_storage().await?;
-        self.chain_client_storages.push(storage.clone());
-        let provider = self.make_node_provider();
-        let builder = Arc::new(Client::new(
-            provider,
+        self.make_client_with_key_derivation(chain_id, key_pair, block_hash, block_height, None)
+            .await
+    }
+
+    /// Creates a client for the given chain that derives the owner keys of the chains it opens
+    /// with `key_derivation`.
+    pub async fn make_client_with_key_derivation(
+        &mut self,
+        chain_id: ChainId,
+        key_pair: impl Into<AccountSecretKey>,
+        block_hash: Option<CryptoHash>,
+        block_height: BlockHeight,
+        key_derivation: impl Into<Option<KeyDerivation>>,
+    ) -> Result<ChainClient<NodeProvider<B::Storage>, B::Storage>, anyhow::Error> {
+        let mut client = self.new_client(chain_id).await?;
+        if let Some(key_derivation) = key_derivation.into() {
+            client = client.with_key_derivation(key_derivation);
+        }
+        let builder = Arc::new(client);
+        Ok(builder.create_chain_client(
+            chain_id,
+            vec![key_pair.into()],
+            self.admin_id,
+            block_hash,
+            Timestamp::from(0),
+            block_height,
+            None,
+            Vec::new(),
+        ))
+    }
+
+    /// Creates a client for the given chain that doesn't know any key pairs and signs its
+    /// proposals with `signer` instead.
+    pub async fn make_client_with_signer(
+        &mut self,
+        chain_id: ChainId,
+        signer: impl Signer + 'static,
+        block_hash: Option<CryptoHash>,
+        block_height: BlockHeight,
+    ) -> Result<ChainClient<NodeProvider<B::Storage>, B::Storage>, anyhow::Error> {
+        let builder = Arc::new(self.new_client(chain_id).await?.with_signer(signer));
+        Ok(builder.create_chain_client(
+            chain_id,
+            Vec::new(),
+            self.admin_id,
+            block_hash,
+            Timestamp::from(0),
+            block_height,
+            None,
+            Vec::new(),
+        ))
+    }
+
+    /// Creates a client for the given chain that reports its events to `metrics`.
+    pub async fn make_client_with_metrics(
+        &mut self,
+        chain_id: ChainId,
+        key_pair: impl Into<AccountSecretKey>,
+        block_hash: Option<CryptoHash>,
+        block_height: BlockHeight,
+        metrics: impl ClientMetrics + 'static,
+    ) -> Result<ChainClient<NodeProvider<B::Storage>, B::Storage>, anyhow::Error> {
+        let builder = Arc::new(self.new_client(chain_id).await?.with_metrics(metrics));
+        Ok(builder.create_chain_client(
+            chain_id,
+            vec![key_pair.into()],
+            self.admin_id,
+            block_hash,
+            Timestamp::from(0),
+            block_height,
+            None,
+            Vec::new(),
+        ))
+    }
+
+    /// Creates a client for the same chain and storage as `client`, as if it was restarted,
+    /// that reports its events to `metrics`. Like a wallet, the new client keeps the scheduled
+    /// blocks of `client`.
+    pub async fn restart_client_with_metrics(
+        &mut self,
+        client: &ChainClient<NodeProvider<B::Storage>, B::Storage>,
+        metrics: impl ClientMetrics + 'static,
+    ) -> Result<ChainClient<NodeProvider<B::Storage>, B::Storage>, anyhow::Error> {
+        let chain_id = client.chain_id();
+        let builder = Arc::new(
+            self.new_client_with_storage(chain_id, client.storage_client())
+                .with_metrics(metrics),
+        );
+        Ok(builder.create_chain_client(
+            chain_id,
+            vec![client.key_pair().await?],
+            self.admin_id,
+            client.block_hash(),
+            Timestamp::from(0),
+            client.next_block_height(),
+            None,
+            client.scheduled_blocks(),
+        ))
+    }
+
+    /// Creates clients for the chains of the given `clients` that share a single local node
+    /// with the given storage, and thus track each other's chains.
+    pub async fn make_clients_with_storage(
+        &self,
+        storage: B::Storage,
+        clients: &[&ChainClient<NodeProvider<B::Storage>, B::Storage>],
+    ) -> Result<Vec<ChainClient<NodeProvider<B::Storage>, B::Storage>>, anyhow::Error> {
+        let client = Arc::new(Client::new(
+            self.make_node_provider(),
             storage,
             10,
             CrossChainMessageDelivery::NonBlocking,
             false,
-            [chain_id],
-            format!("Client node for {:.8}", chain_id),
+            clients.iter().map(|client| client.chain_id()),
+            "Shared client node".to_string(),
             NonZeroUsize::new(20).expect("Chain worker limit should not be zero"),
             DEFAULT_GRACE_PERIOD,
             Duration::from_secs(1),
+            Duration::from_secs(60),
         ));
-        Ok(builder.create_chain_client(
+        let mut chain_clients = Vec::new();
+        for chain_client in clients {
+            chain_clients.push(client.create_chain_client(
+                chain_client.chain_id(),
+                vec![chain_client.key_pair().await?],
+                self.admin_id,
+                None,
+                Timestamp::from(0),
+                BlockHeight::ZERO,
+                None,
+                Vec::new(),
+            ));
+        }
+        Ok(chain_clients)
+    }
+
+    /// Creates a client for the given chain whose local node doesn't store the state of
+    /// inactive chains.
+    pub async fn make_client_rejecting_inactive_chains(
+        &mut self,
+        chain_id: ChainId,
+        key_pair: impl Into<AccountSecretKey>,
+        block_hash: Option<CryptoHash>,
+        block_height: BlockHeight,
+    ) -> Result<ChainClient<NodeProvider<B::Storage>, B::Storage>, anyhow::Error> {
+        let client = self
+            .new_client(chain_id)
+            .await?
+            .with_allow_inactive_chains(false);
+        Ok(Arc::new(client).create_chain_client(
             chain_id,
-            vec![key_pair],
+            vec![key_pair.into()],
             self.admin_id,
             block_hash,
             Timestamp::from(0),
             block_height,
             None,
+            Vec::new(),
         ))
     }
 
+    async fn new_client(
+        &mut self,
+        chain_id: ChainId,
+    ) -> Result<Client<NodeProvider<B::Storage>, B::Storage>, anyhow::Error> {
+        // Note that new clients are only given the genesis store: they must figure out
+        // the rest by asking validators.
+        let storage = self.make_storage().await?;
+        self.chain_client_storages.push(storage.clone());
+        Ok(self.new_client_with_storage(chain_id, storage))
+    }
+
+    fn new_client_with_storage(
+        &self,
+        chain_id: ChainId,
+        storage: B::Storage,
+    ) -> Client<NodeProvider<B::Storage>, B::Storage> {
+        let provider = self.make_node_provider();
+        Client::new(
+            provider,
+            storage,
+            10,
+            CrossChainMessageDelivery::NonBlocking,
+            false,
+            [chain_id],
+            format!("Client node for {:.8}", chain_id),
+            NonZeroUsize::new(20).expect("Chain worker limit should not be zero"),
+            DEFAULT_GRACE_PERIOD,
+            Duration::from_secs(1),
+            Duration::from_secs(60),
+        )
+    }
+
     /// Tries to find a (confirmation) certificate for the given chain_id and block height.
     pub async fn check_that_validators_have_certificate(
         &self,
@@ -932,7 +1199,8 @@ where
             });
         let mut count = 0;
         let mut certificate = None;
-        for validator in self.validator_clients.clone() {
+        for validator in &self.validator_clients {
+            let validator = validator.inner();
             if let Ok(response) = validator.handle_chain_info_query(query.clone()).await {
                 if response.check(&validator.name).is_ok() {
                     let ChainInfo {
@@ -969,7 +1237,8 @@ where
     ) {
         let query = ChainInfoQuery::new(chain_id);
         let mut count = 0;
-        for validator in self.validator_clients.clone() {
+        for validator in &self.validator_clients {
+            let validator = validator.inner();
             if let Ok(response) = validator.handle_chain_info_query(query.clone()).await {
                 if response.info.manager.current_round == round
                     && response.info.next_block_height == block_height
@@ -985,7 +1254,7 @@ where
     /// Panics if any validator has a nonempty outbox for the given chain.
     pub async fn check_that_validators_have_empty_outboxes(&self, chain_id: ChainId) {
         for validator in &self.validator_clients {
-            let guard = validator.client.lock().await;
+            let guard = validator.inner().client.lock().await;
             let chain = guard.state.chain_state_view(chain_id).await.unwrap();
             assert_eq!(chain.outboxes.indices().await.unwrap(), []);
         }
@@ -1002,6 +1271,7 @@ pub struct MemoryStorageBuilder {
     instance_counter: usize,
     wasm_runtime: Option<WasmRuntime>,
     clock: TestClock,
+    child_id_deriver: Option<Arc<dyn ChildIdDeriver>>,
 }
 
 #[async_trait]
@@ -1016,14 +1286,21 @@ impl StorageBuilder for MemoryStorageBuilder {
         }
         let namespace = format!("{}_{}", self.namespace, self.instance_counter);
         let root_key = &[];
-        Ok(DbStorage::new_for_testing(
+        let storage = DbStorage::new_for_testing(
             config,
             &namespace,
             root_key,
             self.wasm_runtime,
             self.clock.clone(),
         )
-        .await?)
+        .await?;
+        Ok(match &self.child_id_deriver {
+            Some(deriver) => {
+                let deriver = deriver.clone();
+                storage.with_child_id_deriver(move |message_id| deriver.child_id(message_id))
+            }
+            None => storage,
+        })
     }
 
     fn clock(&self) -> &TestClock {
@@ -1041,6 +1318,181 @@ impl MemoryStorageBuilder {
             ..MemoryStorageBuilder::default()
         }
     }
+
+    /// Makes all storages derive the IDs of new chains with `deriver` instead of
+    /// [`ChainId::child`].
+    pub fn with_child_id_deriver(mut self, deriver: impl ChildIdDeriver + 'static) -> Self {
+        self.child_id_deriver = Some(Arc::new(deriver));
+        self
+    }
+}
+
+/// A [`MemoryStore`] that fails all writes once it has used up its budget of batches.
+#[derive(Clone)]
+pub struct FailingStore {
+    store: MemoryStore,
+    remaining_writes: Arc<AtomicUsize>,
+}
+
+/// The configuration of a [`FailingStore`].
+pub struct FailingStoreConfig {
+    /// The number of batches that can still be written. Shared by all the stores created
+    /// with this configuration.
+    pub remaining_writes: Arc<AtomicUsize>,
+}
+
+/// The errors of a [`FailingStore`].
+#[derive(Debug, thiserror::Error)]
+pub enum FailingStoreError {
+    #[error("Injected write failure")]
+    Injected,
+    #[error(transparent)]
+    Memory(#[from] MemoryStoreError),
+    #[error(transparent)]
+    Bcs(#[from] bcs::Error),
+}
+
+impl KeyValueStoreError for FailingStoreError {
+    const BACKEND: &'static str = "failing_memory";
+}
+
+impl WithError for FailingStore {
+    type Error = FailingStoreError;
+}
+
+impl ReadableKeyValueStore for FailingStore {
+    const MAX_KEY_SIZE: usize = MemoryStore::MAX_KEY_SIZE;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, FailingStoreError> {
+        Ok(self.store.read_value_bytes(key).await?)
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, FailingStoreError> {
+        Ok(self.store.contains_key(key).await?)
+    }
+
+    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, FailingStoreError> {
+        Ok(self.store.contains_keys(keys).await?)
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, FailingStoreError> {
+        Ok(self.store.read_multi_values_bytes(keys).await?)
+    }
+
+    async fn find_keys_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Vec<Vec<u8>>, FailingStoreError> {
+        Ok(self.store.find_keys_by_prefix(key_prefix).await?)
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, FailingStoreError> {
+        Ok(self.store.find_key_values_by_prefix(key_prefix).await?)
+    }
+}
+
+impl WritableKeyValueStore for FailingStore {
+    const MAX_VALUE_SIZE: usize = MemoryStore::MAX_VALUE_SIZE;
+
+    async fn write_batch(
+        &self,
+        batch: linera_views::batch::Batch,
+    ) -> Result<(), FailingStoreError> {
+        self.remaining_writes
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .map_err(|_| FailingStoreError::Injected)?;
+        Ok(self.store.write_batch(batch).await?)
+    }
+
+    async fn clear_journal(&self) -> Result<(), FailingStoreError> {
+        Ok(self.store.clear_journal().await?)
+    }
+}
+
+impl AdminKeyValueStore for FailingStore {
+    type Config = FailingStoreConfig;
+
+    fn get_name() -> String {
+        "failing memory".to_string()
+    }
+
+    async fn connect(
+        config: &FailingStoreConfig,
+        namespace: &str,
+        root_key: &[u8],
+    ) -> Result<Self, FailingStoreError> {
+        let store = MemoryStore::connect(&memory_config(), namespace, root_key).await?;
+        let remaining_writes = config.remaining_writes.clone();
+        Ok(FailingStore {
+            store,
+            remaining_writes,
+        })
+    }
+
+    fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, FailingStoreError> {
+        let store = self.store.clone_with_root_key(root_key)?;
+        let remaining_writes = self.remaining_writes.clone();
+        Ok(FailingStore {
+            store,
+            remaining_writes,
+        })
+    }
+
+    async fn list_all(_config: &FailingStoreConfig) -> Result<Vec<String>, FailingStoreError> {
+        Ok(MemoryStore::list_all(&memory_config()).await?)
+    }
+
+    async fn list_root_keys(
+        _config: &FailingStoreConfig,
+        namespace: &str,
+    ) -> Result<Vec<Vec<u8>>, FailingStoreError> {
+        Ok(MemoryStore::list_root_keys(&memory_config(), namespace).await?)
+    }
+
+    async fn exists(
+        _config: &FailingStoreConfig,
+        namespace: &str,
+    ) -> Result<bool, FailingStoreError> {
+        Ok(MemoryStore::exists(&memory_config(), namespace).await?)
+    }
+
+    async fn create(
+        _config: &FailingStoreConfig,
+        namespace: &str,
+    ) -> Result<(), FailingStoreError> {
+        Ok(MemoryStore::create(&memory_config(), namespace).await?)
+    }
+
+    async fn delete(
+        _config: &FailingStoreConfig,
+        namespace: &str,
+    ) -> Result<(), FailingStoreError> {
+        Ok(MemoryStore::delete(&memory_config(), namespace).await?)
+    }
+}
+
+impl TestKeyValueStore for FailingStore {
+    async fn new_test_config() -> Result<FailingStoreConfig, FailingStoreError> {
+        let remaining_writes = Arc::new(AtomicUsize::new(usize::MAX));
+        Ok(FailingStoreConfig { remaining_writes })
+    }
+}
+
+/// Returns the configuration of the [`MemoryStore`] underlying a [`FailingStore`].
+fn memory_config() -> MemoryStoreConfig {
+    MemoryStoreConfig::new(TEST_MEMORY_MAX_STREAM_QUERIES)
 }
 
 #[cfg(feature = "rocksdb")]
@@ -11,11 +11,12 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     iter,
     num::NonZeroUsize,
-    sync::{Arc, Mutex},
+    sync::{atomic::Ordering, Arc, Mutex},
     time::Duration,
 };
 
 use assert_matches::assert_matches;
+use futures::future;
 use linera_base::{
     crypto::{CryptoHash, *},
     data_types::*,
@@ -30,7 +31,7 @@ use linera_chain::{
     data_types::{
         BlockExecutionOutcome, BlockProposal, ChainAndHeight, ChannelFullName, ExecutedBlock,
         IncomingBundle, LiteValue, LiteVote, Medium, MessageAction, MessageBundle, Origin,
-        OutgoingMessage, PostedMessage, ProposedBlock, SignatureAggregator,
+        OutgoingMessage, PostedMessage, ProposedBlock, SignatureAggregator, Target,
     },
     manager::LockingBlock,
     test::{make_child_block, make_first_block, BlockTestExt, MessageTestExt, VoteTestExt},
@@ -49,9 +50,9 @@ use linera_execution::{
     ChannelSubscription, ExecutionError, Message, MessageKind, Query, QueryContext, QueryOutcome,
     QueryResponse, SystemExecutionError, SystemQuery, SystemResponse,
 };
-use linera_storage::{DbStorage, Storage, TestClock};
+use linera_storage::{export_all, DbStorage, ExportItem, Storage, TestClock};
 use linera_views::{
-    memory::MemoryStore,
+    memory::{MemoryStore, MemoryStoreConfig, TEST_MEMORY_MAX_STREAM_QUERIES},
     random::generate_test_namespace,
     store::TestKeyValueStore as _,
     views::{CryptoHashView, RootView},
@@ -66,13 +67,14 @@ use crate::test_utils::RocksDbStorageBuilder;
 #[cfg(feature = "scylladb")]
 use crate::test_utils::ScyllaDbStorageBuilder;
 use crate::{
-    chain_worker::CrossChainUpdateHelper,
+    chain_worker::{CrossChainUpdateHelper, InactiveChainPolicy},
     data_types::*,
-    test_utils::{MemoryStorageBuilder, StorageBuilder},
+    local_node::LocalNodeClient,
+    test_utils::{FailingStore, MemoryStorageBuilder, StorageBuilder},
     worker::{
-        Notification,
+        check_protocol_version, Notification,
         Reason::{self, NewBlock, NewIncomingBundle},
-        WorkerError, WorkerState,
+        WorkerError, WorkerMetrics, WorkerState,
     },
 };
 
@@ -97,7 +99,11 @@ where
         storage,
         NonZeroUsize::new(10).expect("Chain worker limit should not be zero"),
     )
-    .with_allow_inactive_chains(is_client)
+    .with_inactive_chain_policy(if is_client {
+        InactiveChainPolicy::Allow
+    } else {
+        InactiveChainPolicy::Reject
+    })
     .with_allow_messages_from_deprecated_epochs(is_client)
     .with_long_lived_services(has_long_lived_services)
     .with_grace_period(Duration::from_micros(TEST_GRACE_PERIOD_MICROS));
@@ -446,7 +452,7 @@ where
     let unknown_key_pair = KeyPair::generate();
     let mut bad_signature_block_proposal = block_proposal.clone();
     bad_signature_block_proposal.signature =
-        Signature::new(&block_proposal.content, &unknown_key_pair);
+        AccountSignature::new(&block_proposal.content, &unknown_key_pair.into());
     assert_matches!(
         worker
             .handle_block_proposal(bad_signature_block_proposal)
@@ -460,6 +466,32 @@ where
     Ok(())
 }
 
+#[test]
+fn test_check_protocol_version() {
+    let ours = ProtocolVersion::CURRENT;
+    let newer_major = ProtocolVersion {
+        major: ours.major + 1,
+        minor: 0,
+    };
+    let newer_minor = ProtocolVersion {
+        major: ours.major,
+        minor: ours.minor + 1,
+    };
+
+    assert_matches!(
+        check_protocol_version(Some(newer_major)),
+        Err(WorkerError::IncompatibleProtocolVersion { ours: version, theirs })
+            if version == ours && theirs == newer_major
+    );
+    assert!(check_protocol_version(Some(newer_minor)).is_ok());
+    // Peers that don't send a version predate the current binary format of block proposals.
+    assert_matches!(
+        check_protocol_version(None),
+        Err(WorkerError::IncompatibleProtocolVersion { theirs, .. })
+            if theirs == ProtocolVersion::UNVERSIONED
+    );
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
@@ -1415,7 +1447,76 @@ where
         worker
             .fully_handle_certificate_with_notifications(certificate, &())
             .await,
-        Err(WorkerError::IncorrectOutcome { .. })
+        Err(WorkerError::StateHashMismatch { chain_id, height })
+            if chain_id == ChainId::root(2) && height == BlockHeight::ZERO
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_handle_certificate_wrong_state_hash<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let sender_key_pair = KeyPair::generate();
+    let (committee, worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![
+            (
+                ChainDescription::Root(1),
+                sender_key_pair.public().into(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2).into(),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+    // The certificate is correctly signed and has the right messages, but claims that the
+    // sender's balance is unchanged after the transfer.
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(5),
+        &worker,
+        None,
+    )
+    .await;
+    assert_matches!(
+        worker
+            .fully_handle_certificate_with_notifications(certificate, &())
+            .await,
+        Err(WorkerError::StateHashMismatch { chain_id, height })
+            if chain_id == ChainId::root(1) && height == BlockHeight::ZERO
+    );
+
+    // Neither the sender nor the recipient chain was changed.
+    let sender_chain = worker.chain_state_view(ChainId::root(1)).await?;
+    assert_eq!(
+        sender_chain.tip_state.get().next_block_height,
+        BlockHeight::ZERO
+    );
+    assert_eq!(sender_chain.confirmed_log.count(), 0);
+    assert_eq!(
+        *sender_chain.execution_state.system.balance.get(),
+        Amount::from_tokens(5)
+    );
+    let recipient_chain = worker.chain_state_view(ChainId::root(2)).await?;
+    assert!(recipient_chain.inboxes.indices().await?.is_empty());
+    assert_eq!(
+        *recipient_chain.execution_state.system.balance.get(),
+        Amount::ZERO
     );
     Ok(())
 }
@@ -2036,6 +2137,16 @@ where
             height: BlockHeight::ZERO
         }
     );
+
+    // Entries are only skipped for the sender chains and heights we already have.
+    let heights = BTreeMap::from([(ChainId::root(3), BlockHeight::from(5))]);
+    let query = ChainInfoQuery::new(ChainId::root(2)).with_received_log_after(heights);
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert_eq!(response.info.requested_received_log.len(), 1);
+    let heights = BTreeMap::from([(ChainId::root(1), BlockHeight::ZERO)]);
+    let query = ChainInfoQuery::new(ChainId::root(2)).with_received_log_after(heights);
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert!(response.info.requested_received_log.is_empty());
     Ok(())
 }
 
@@ -2083,6 +2194,114 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_inactive_recipient_allowed_while_recently_referenced<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let clock = storage_builder.clock();
+    let sender_key_pair = KeyPair::generate();
+    let (committee, worker) = init_worker_with_chain(
+        storage,
+        ChainDescription::Root(1),
+        sender_key_pair.public().into(),
+        Amount::from_tokens(5),
+    )
+    .await;
+    let worker = worker.with_inactive_chain_policy(InactiveChainPolicy::AllowRecentlyReferenced {
+        ttl: Duration::from_secs(1),
+    });
+    let recipient_id = ChainId::root(2); // the recipient chain does not exist
+
+    let certificate1 = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        recipient_id,
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(4),
+        &worker,
+        None,
+    )
+    .await;
+    worker
+        .fully_handle_certificate_with_notifications(certificate1.clone(), &())
+        .await?;
+    // The recipient was referenced just now, so the message is stored in its inbox.
+    let metrics = worker.metrics().await?;
+    assert_eq!(metrics.pending_message_bundles.get(&recipient_id), Some(&1));
+
+    // After the TTL, messages to the still inactive recipient are refused.
+    clock.add(TimeDelta::from_secs(2));
+    let certificate2 = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        recipient_id,
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(3),
+        &worker,
+        Some(&certificate1),
+    )
+    .await;
+    let info = worker
+        .fully_handle_certificate_with_notifications(certificate2.clone(), &())
+        .await?
+        .info;
+    assert_eq!(info.next_block_height, BlockHeight::from(2));
+    let metrics = worker.metrics().await?;
+    assert_eq!(metrics.pending_message_bundles.get(&recipient_id), Some(&1));
+
+    // Referencing another chain forgets the expired recipient, but it is still refused because
+    // it already received messages.
+    let certificate3 = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(3),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(2),
+        &worker,
+        Some(&certificate2),
+    )
+    .await;
+    worker
+        .fully_handle_certificate_with_notifications(certificate3.clone(), &())
+        .await?;
+    let certificate4 = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        recipient_id,
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::ONE,
+        &worker,
+        Some(&certificate3),
+    )
+    .await;
+    worker
+        .fully_handle_certificate_with_notifications(certificate4, &())
+        .await?;
+    let metrics = worker.metrics().await?;
+    assert_eq!(
+        metrics.pending_message_bundles.get(&ChainId::root(3)),
+        Some(&1)
+    );
+    assert_eq!(metrics.pending_message_bundles.get(&recipient_id), Some(&1));
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
@@ -2996,6 +3215,132 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_certificate_from_removed_committee<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let owner0 = KeyPair::generate().public().into();
+    let (committee, worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        vec![(ChainDescription::Root(0), owner0, Amount::ZERO)],
+    )
+    .await;
+    let admin_id = ChainId::root(0);
+
+    // Have the admin chain rotate the committee twice and remove all the older ones.
+    let committees = BTreeMap::from_iter([(Epoch::from(2), committee.clone())]);
+    let certificate0 = make_certificate(
+        &committee,
+        &worker,
+        Hashed::new(ConfirmedBlock::new(
+            BlockExecutionOutcome {
+                messages: vec![
+                    vec![channel_admin_message(SystemMessage::CreateCommittee {
+                        epoch: Epoch::from(1),
+                        committee: committee.clone(),
+                    })],
+                    vec![channel_admin_message(SystemMessage::CreateCommittee {
+                        epoch: Epoch::from(2),
+                        committee: committee.clone(),
+                    })],
+                    vec![channel_admin_message(SystemMessage::RemoveCommittee {
+                        epoch: Epoch::ZERO,
+                    })],
+                    vec![channel_admin_message(SystemMessage::RemoveCommittee {
+                        epoch: Epoch::from(1),
+                    })],
+                ],
+                events: vec![Vec::new(); 4],
+                state_hash: SystemExecutionState {
+                    committees,
+                    ownership: ChainOwnership::single(owner0),
+                    ..SystemExecutionState::new(Epoch::from(2), ChainDescription::Root(0), admin_id)
+                }
+                .into_hash()
+                .await,
+                oracle_responses: vec![Vec::new(); 4],
+            }
+            .with(
+                make_first_block(admin_id)
+                    .with_operation(SystemOperation::Admin(AdminOperation::CreateCommittee {
+                        epoch: Epoch::from(1),
+                        committee: committee.clone(),
+                    }))
+                    .with_operation(SystemOperation::Admin(AdminOperation::CreateCommittee {
+                        epoch: Epoch::from(2),
+                        committee: committee.clone(),
+                    }))
+                    .with_operation(SystemOperation::Admin(AdminOperation::RemoveCommittee {
+                        epoch: Epoch::ZERO,
+                    }))
+                    .with_operation(SystemOperation::Admin(AdminOperation::RemoveCommittee {
+                        epoch: Epoch::from(1),
+                    })),
+            ),
+        )),
+    );
+    worker
+        .fully_handle_certificate_with_notifications(certificate0.clone(), &())
+        .await?;
+
+    // A new block certified by the committee of the first epoch is rejected.
+    let certificate1 = make_certificate(
+        &committee,
+        &worker,
+        Hashed::new(ConfirmedBlock::new(
+            BlockExecutionOutcome {
+                messages: vec![Vec::new()],
+                events: vec![Vec::new()],
+                state_hash: CryptoHash::test_hash("state"),
+                oracle_responses: vec![Vec::new()],
+            }
+            .with(
+                make_child_block(&certificate0.clone().into_value())
+                    .with_simple_transfer(ChainId::root(1), Amount::ZERO),
+            ),
+        )),
+    );
+    assert_matches!(
+        worker
+            .fully_handle_certificate_with_notifications(certificate1, &())
+            .await,
+        Err(WorkerError::InvalidEpoch { chain_id, epoch, chain_epoch })
+            if chain_id == admin_id && epoch == Epoch::ZERO && chain_epoch == Epoch::from(2)
+    );
+
+    // The history certified by the removed committee is still available.
+    assert_eq!(
+        worker.read_certificate(admin_id, BlockHeight::ZERO).await?,
+        Some(certificate0)
+    );
+    let admin_chain = worker.chain_state_view(admin_id).await?;
+    assert_eq!(
+        admin_chain.tip_state.get().next_block_height,
+        BlockHeight::from(1)
+    );
+    assert_eq!(
+        *admin_chain.execution_state.system.epoch.get(),
+        Some(Epoch::from(2))
+    );
+    // Only the committee of the last epoch is left.
+    assert_eq!(
+        admin_chain
+            .execution_state
+            .system
+            .committees
+            .get()
+            .keys()
+            .collect::<Vec<_>>(),
+        [&Epoch::from(2)]
+    );
+    Ok(())
+}
+
 #[test(tokio::test)]
 async fn test_cross_chain_helper() -> anyhow::Result<()> {
     // Make a committee and worker (only used for signing certificates)
@@ -3362,8 +3707,11 @@ where
     // But with the validated block certificate for block2, it is allowed.
     let certificate2 =
         make_certificate_with_round(&committee, &worker, value2.clone(), Round::SingleLeader(4));
-    let proposal =
-        BlockProposal::new_retry(Round::SingleLeader(5), certificate2.clone(), &key_pairs[1]);
+    let proposal = BlockProposal::new_retry(
+        Round::SingleLeader(5),
+        certificate2.clone(),
+        &key_pairs[1].copy().into(),
+    );
     let lite_value2 = LiteValue::new(&value2);
     let (_, _) = worker.handle_block_proposal(proposal).await?;
     let (response, _) = worker.handle_chain_info_query(query_values.clone()).await?;
@@ -3666,8 +4014,11 @@ where
     let value2 = Hashed::new(ValidatedBlock::new(executed_block2.clone()));
     let certificate2 =
         make_certificate_with_round(&committee, &worker, value2.clone(), Round::MultiLeader(0));
-    let proposal =
-        BlockProposal::new_retry(Round::MultiLeader(3), certificate2.clone(), &key_pairs[1]);
+    let proposal = BlockProposal::new_retry(
+        Round::MultiLeader(3),
+        certificate2.clone(),
+        &key_pairs[1].copy().into(),
+    );
     let lite_value2 = LiteValue::new(&value2);
     let (_, _) = worker.handle_block_proposal(proposal).await?;
     let query_values = ChainInfoQuery::new(chain_id).with_manager_values();
@@ -3687,18 +4038,87 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_fallback<B>(mut storage_builder: B) -> anyhow::Result<()>
+async fn test_approval_threshold_for_retried_proposals<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
 where
     B: StorageBuilder,
 {
     let storage = storage_builder.build().await?;
-    let clock = storage_builder.clock();
-    let chain_id = ChainId::root(1);
-    let key_pair = KeyPair::generate();
-    let balance = Amount::from_tokens(5);
-    let balances = vec![(ChainDescription::Root(1), key_pair.public().into(), balance)];
-    let (committee, worker) = init_worker_with_chains(storage, balances).await;
-
+    let chain_id = ChainId::root(0);
+    let key_pairs = generate_key_pairs(2);
+    let owner0 = Owner::from(key_pairs[0].public());
+    let owner1 = Owner::from(key_pairs[1].public());
+    let balances = vec![(ChainDescription::Root(0), owner0, Amount::from_tokens(2))];
+    let (committee, worker) = init_worker_with_chains(storage, balances).await;
+
+    // Add another owner and require both of them to approve each block.
+    let block0 = make_first_block(chain_id)
+        .with_operation(SystemOperation::ChangeOwnership {
+            super_owners: vec![],
+            owners: vec![(owner0, 100), (owner1, 100)],
+            multi_leader_rounds: 10,
+            open_multi_leader_rounds: false,
+            timeout_config: TimeoutConfig::default(),
+        })
+        .with_operation(SystemOperation::ChangeApprovalThreshold { threshold: Some(2) });
+    let (executed_block0, _) = worker.stage_block_execution(block0, None).await?;
+    let value0 = Hashed::new(ConfirmedBlock::new(executed_block0));
+    let certificate0 = make_certificate(&committee, &worker, value0.clone());
+    worker
+        .fully_handle_certificate_with_notifications(certificate0, &())
+        .await?;
+
+    // Retrying a validated block still requires the approval of both owners.
+    let block1 = make_child_block(&value0)
+        .with_simple_transfer(ChainId::root(1), Amount::ONE)
+        .with_authenticated_signer(Some(owner0));
+    let (executed_block1, _) = worker.stage_block_execution(block1.clone(), None).await?;
+    let value1 = Hashed::new(ValidatedBlock::new(executed_block1));
+    let certificate1 =
+        make_certificate_with_round(&committee, &worker, value1, Round::MultiLeader(0));
+    let mut proposal = BlockProposal::new_retry(
+        Round::MultiLeader(1),
+        certificate1,
+        &key_pairs[0].copy().into(),
+    );
+    let result = worker.handle_block_proposal(proposal.clone()).await;
+    assert_matches!(
+        result,
+        Err(WorkerError::InsufficientApprovals {
+            approvals: 1,
+            threshold: 2
+        })
+    );
+
+    // The other owner's signature of the block is valid in any round.
+    let secret1 = AccountSecretKey::from(key_pairs[1].copy());
+    proposal
+        .cosignatures
+        .push((secret1.public(), AccountSignature::new(&block1, &secret1)));
+    let (response, _) = worker.handle_block_proposal(proposal).await?;
+    let vote = response.info.manager.pending.as_ref().unwrap();
+    assert_eq!(vote.round, Round::MultiLeader(1));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_fallback<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let clock = storage_builder.clock();
+    let chain_id = ChainId::root(1);
+    let key_pair = KeyPair::generate();
+    let balance = Amount::from_tokens(5);
+    let balances = vec![(ChainDescription::Root(1), key_pair.public().into(), balance)];
+    let (committee, worker) = init_worker_with_chains(storage, balances).await;
+
     // At time 0 we don't vote for fallback mode.
     let query = ChainInfoQuery::new(chain_id).with_fallback();
     let (response, _) = worker.handle_chain_info_query(query.clone()).await?;
@@ -4013,3 +4433,782 @@ where
 
     Ok(())
 }
+
+#[test_log::test(tokio::test)]
+async fn test_handle_certificate_with_write_failures() -> anyhow::Result<()> {
+    let sender_key_pair = KeyPair::generate();
+    let mut succeeded = false;
+    for allowed_writes in 0..20 {
+        let namespace = generate_test_namespace();
+        let config = FailingStore::new_test_config().await?;
+        let remaining_writes = config.remaining_writes.clone();
+        let storage = DbStorage::<FailingStore, TestClock>::new_for_testing(
+            config,
+            &namespace,
+            &[],
+            None,
+            TestClock::new(),
+        )
+        .await?;
+        let (committee, worker) = init_worker_with_chain(
+            storage,
+            ChainDescription::Root(1),
+            sender_key_pair.public().into(),
+            Amount::from_tokens(5),
+        )
+        .await;
+        let certificate = make_simple_transfer_certificate(
+            ChainDescription::Root(1),
+            &sender_key_pair,
+            ChainId::root(2),
+            Amount::from_tokens(5),
+            Vec::new(),
+            &committee,
+            Amount::ZERO,
+            &worker,
+            None,
+        )
+        .await;
+
+        // Simulate a crash after `allowed_writes` batches, then restart from what was persisted.
+        remaining_writes.store(allowed_writes, Ordering::SeqCst);
+        let result = worker
+            .fully_handle_certificate_with_notifications(certificate.clone(), &())
+            .await;
+        drop(worker);
+        let config = MemoryStoreConfig::new(TEST_MEMORY_MAX_STREAM_QUERIES);
+        let storage = DbStorage::<MemoryStore, _>::new(config, &namespace, &[], None).await?;
+        let chain = storage.load_chain(ChainId::root(1)).await?;
+        let tip = chain.tip_state.get();
+        if tip.next_block_height == BlockHeight::ZERO {
+            // Nothing was applied to the chain.
+            assert!(result.is_err());
+            assert_eq!(tip.block_hash, None);
+            assert_eq!(chain.confirmed_log.count(), 0);
+            assert_eq!(
+                *chain.execution_state.system.balance.get(),
+                Amount::from_tokens(5)
+            );
+        } else {
+            // The block was applied, and everything it depends on was written.
+            assert_eq!(tip.next_block_height, BlockHeight::from(1));
+            assert_eq!(tip.block_hash, Some(certificate.hash()));
+            assert_eq!(
+                chain.confirmed_log.read(..).await?,
+                vec![certificate.hash()]
+            );
+            assert_eq!(*chain.execution_state.system.balance.get(), Amount::ZERO);
+            assert!(storage.contains_certificate(certificate.hash()).await?);
+        }
+        if result.is_ok() {
+            succeeded = true;
+            break;
+        }
+    }
+    assert!(succeeded);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_list_chains_and_certificates<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let key_pair = KeyPair::generate();
+    let owner = Owner::from(key_pair.public());
+    let (committee, worker) = init_worker_with_chains(
+        storage.clone(),
+        (1..=100).map(|i| (ChainDescription::Root(i), owner, Amount::from_tokens(5))),
+    )
+    .await;
+    // Creating the chains also wrote the admin chain.
+    let chain_ids = (0..=100).map(ChainId::root).collect::<Vec<_>>();
+    assert_eq!(storage.list_chain_ids().await?, chain_ids);
+
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &key_pair,
+        ChainId::root(2),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(4),
+        &worker,
+        None,
+    )
+    .await;
+    worker
+        .fully_handle_certificate_with_notifications(certificate.clone(), &())
+        .await?;
+    let all_heights = BlockHeight::ZERO..BlockHeight::MAX;
+    assert_eq!(
+        storage
+            .list_certificate_hashes(ChainId::root(1), all_heights.clone())
+            .await?,
+        vec![certificate.hash()]
+    );
+    assert!(storage
+        .list_certificate_hashes(ChainId::root(1), BlockHeight::from(1)..BlockHeight::MAX)
+        .await?
+        .is_empty());
+    assert!(storage
+        .list_certificate_hashes(ChainId::root(2), all_heights)
+        .await?
+        .is_empty());
+    assert_eq!(storage.list_chain_ids().await?, chain_ids);
+
+    let mut export = Vec::new();
+    export_all(&storage, &mut export).await?;
+    assert_eq!(
+        export,
+        bcs::to_bytes(&ExportItem::Certificate(certificate))?
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_recover_after_interrupted_certificate<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let sender_key_pair = KeyPair::generate();
+    let (committee, worker) = init_worker_with_chains(
+        storage.clone(),
+        vec![
+            (
+                ChainDescription::Root(1),
+                sender_key_pair.public().into(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2).into(),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(4),
+        &worker,
+        None,
+    )
+    .await;
+
+    // The process stops after writing the certificate but before saving the chain state.
+    storage
+        .write_blobs_and_certificate(&[], &certificate)
+        .await?;
+    let (_, worker) = init_worker(
+        storage.clone(),
+        /* is_client */ false,
+        /* has_long_lived_services */ false,
+    );
+    LocalNodeClient::new(worker.clone()).recover().await?;
+    let chain = storage.load_chain(ChainId::root(1)).await?;
+    assert_eq!(chain.tip_state.get().next_block_height, BlockHeight::ZERO);
+
+    // The process stops after saving the chain state but before delivering the messages.
+    let (info, _actions) = worker
+        .handle_confirmed_certificate(certificate.clone(), None)
+        .await?;
+    assert_eq!(info.info.next_block_height, BlockHeight::from(1));
+    let (_, worker) = init_worker(
+        storage.clone(),
+        /* is_client */ false,
+        /* has_long_lived_services */ false,
+    );
+    let query = ChainInfoQuery::new(ChainId::root(2)).with_pending_message_bundles();
+    let (response, _) = worker.handle_chain_info_query(query.clone()).await?;
+    assert!(response.info.requested_pending_message_bundles.is_empty());
+
+    // Recovering delivers the messages, and the sender chain can be extended.
+    LocalNodeClient::new(worker.clone()).recover().await?;
+    let (response, _) = worker.handle_chain_info_query(query).await?;
+    assert_eq!(response.info.requested_pending_message_bundles.len(), 1);
+    let next_certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(3),
+        &worker,
+        Some(&certificate),
+    )
+    .await;
+    let info = worker
+        .fully_handle_certificate_with_notifications(next_certificate, &())
+        .await?;
+    assert_eq!(info.info.next_block_height, BlockHeight::from(2));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_concurrent_certificates_on_independent_chains<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let key_pair = KeyPair::generate();
+    let owner = Owner::from(key_pair.public());
+    let recipient_id = ChainId::root(5);
+    let chains = (1..=5)
+        .map(|i| (ChainDescription::Root(i), owner, Amount::from_tokens(5)))
+        .collect::<Vec<_>>();
+    let sequential_storage = storage_builder.build().await?;
+    let (committee, sequential_worker) =
+        init_worker_with_chains(sequential_storage, chains.clone()).await;
+    // The second worker uses its own storage, with the same committee.
+    let concurrent_storage = storage_builder.build().await?;
+    for (description, owner, balance) in chains {
+        concurrent_storage
+            .create_chain(
+                committee.clone(),
+                ChainId::root(0),
+                description,
+                owner,
+                balance,
+                Timestamp::from(0),
+            )
+            .await?;
+    }
+    let (_, concurrent_worker) = init_worker(
+        concurrent_storage,
+        /* is_client */ false,
+        /* has_long_lived_services */ false,
+    );
+
+    // Each of the four sender chains has a history of three transfers to the recipient.
+    let mut histories = Vec::new();
+    for i in 1..=4 {
+        let mut history = Vec::<ConfirmedBlockCertificate>::new();
+        for balance in [4, 3, 2] {
+            let certificate = make_simple_transfer_certificate(
+                ChainDescription::Root(i),
+                &key_pair,
+                recipient_id,
+                Amount::ONE,
+                Vec::new(),
+                &committee,
+                Amount::from_tokens(balance),
+                &sequential_worker,
+                history.last(),
+            )
+            .await;
+            history.push(certificate);
+        }
+        histories.push(history);
+    }
+
+    for certificate in histories.iter().flatten() {
+        sequential_worker
+            .fully_handle_certificate_with_notifications(certificate.clone(), &())
+            .await?;
+    }
+    future::try_join_all(histories.iter().map(|history| {
+        let worker = &concurrent_worker;
+        async move {
+            for certificate in history {
+                worker
+                    .fully_handle_certificate_with_notifications(certificate.clone(), &())
+                    .await?;
+            }
+            Ok::<_, WorkerError>(())
+        }
+    }))
+    .await?;
+
+    for chain_id in (1..=5).map(ChainId::root) {
+        let query = ChainInfoQuery::new(chain_id).with_pending_message_bundles();
+        let (sequential, _) = sequential_worker
+            .handle_chain_info_query(query.clone())
+            .await?;
+        let (concurrent, _) = concurrent_worker.handle_chain_info_query(query).await?;
+        let (sequential, concurrent) = (sequential.info, concurrent.info);
+        assert_eq!(sequential.next_block_height, concurrent.next_block_height);
+        assert_eq!(sequential.block_hash, concurrent.block_hash);
+        assert_eq!(sequential.state_hash, concurrent.state_hash);
+        assert_eq!(sequential.chain_balance, concurrent.chain_balance);
+        let mut sequential_bundles = sequential.requested_pending_message_bundles;
+        let mut concurrent_bundles = concurrent.requested_pending_message_bundles;
+        sequential_bundles.sort_by_key(|bundle| bundle.origin.sender);
+        concurrent_bundles.sort_by_key(|bundle| bundle.origin.sender);
+        assert_eq!(sequential_bundles, concurrent_bundles);
+    }
+    let (response, _) = concurrent_worker
+        .handle_chain_info_query(ChainInfoQuery::new(recipient_id).with_pending_message_bundles())
+        .await?;
+    assert_eq!(response.info.requested_pending_message_bundles.len(), 12);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_pending_message_bundles_pages<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let key_pair = KeyPair::generate();
+    let owner = Owner::from(key_pair.public());
+    let recipient_id = ChainId::root(3);
+    let chains = (1..=3)
+        .map(|i| (ChainDescription::Root(i), owner, Amount::from_tokens(5)))
+        .collect::<Vec<_>>();
+    let storage = storage_builder.build().await?;
+    let (committee, worker) = init_worker_with_chains(storage, chains).await;
+
+    // Each of the two sender chains sends three bundles to the recipient.
+    for i in 1..=2 {
+        let mut previous = None;
+        for balance in [4, 3, 2] {
+            let certificate = make_simple_transfer_certificate(
+                ChainDescription::Root(i),
+                &key_pair,
+                recipient_id,
+                Amount::ONE,
+                Vec::new(),
+                &committee,
+                Amount::from_tokens(balance),
+                &worker,
+                previous.as_ref(),
+            )
+            .await;
+            worker
+                .fully_handle_certificate_with_notifications(certificate.clone(), &())
+                .await?;
+            previous = Some(certificate);
+        }
+    }
+
+    let query = ChainInfoQuery::new(recipient_id).with_pending_message_bundles();
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    let all_bundles = info.requested_pending_message_bundles;
+    assert_eq!(all_bundles.len(), 6);
+    assert!(!info.has_more_pending_message_bundles);
+
+    let query = ChainInfoQuery::new(recipient_id).with_pending_message_bundles_page(4, 0);
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    assert_eq!(info.requested_pending_message_bundles, all_bundles[..4]);
+    assert!(info.has_more_pending_message_bundles);
+
+    let query = ChainInfoQuery::new(recipient_id).with_pending_message_bundles_page(4, 4);
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    assert_eq!(info.requested_pending_message_bundles, all_bundles[4..]);
+    assert!(!info.has_more_pending_message_bundles);
+
+    let query = ChainInfoQuery::new(recipient_id).with_pending_message_bundles_page(2, 4);
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    assert_eq!(info.requested_pending_message_bundles, all_bundles[4..]);
+    assert!(!info.has_more_pending_message_bundles);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_outbox_status<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let key_pair = KeyPair::generate();
+    let owner = Owner::from(key_pair.public());
+    let sender_id = ChainId::root(1);
+    let recipient_id = ChainId::root(2);
+    let chains = vec![
+        (ChainDescription::Root(1), owner, Amount::from_tokens(5)),
+        (ChainDescription::Root(2), owner, Amount::ZERO),
+    ];
+    let storage = storage_builder.build().await?;
+    let (committee, worker) = init_worker_with_chains(storage, chains).await;
+
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &key_pair,
+        recipient_id,
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(4),
+        &worker,
+        None,
+    )
+    .await;
+    // Withhold the cross-chain requests to the recipient.
+    let (_, actions) = worker
+        .handle_confirmed_certificate(certificate, None)
+        .await?;
+
+    let query = ChainInfoQuery::new(sender_id).with_outboxes();
+    let info = worker.handle_chain_info_query(query.clone()).await?.0.info;
+    let target = Target {
+        recipient: recipient_id,
+        medium: Medium::Direct,
+    };
+    assert_eq!(info.requested_outboxes.len(), 1);
+    let status = &info.requested_outboxes[&target];
+    assert_eq!(status.queued_heights, [BlockHeight::ZERO]);
+    assert_eq!(status.next_height_to_schedule, BlockHeight::from(1));
+    assert_eq!(
+        status.oldest_unacknowledged_height(),
+        Some(BlockHeight::ZERO)
+    );
+
+    // Deliver the messages, and the recipient's confirmation.
+    let mut requests = actions.cross_chain_requests;
+    while let Some(request) = requests.pop() {
+        requests.extend(
+            worker
+                .handle_cross_chain_request(request)
+                .await?
+                .cross_chain_requests,
+        );
+    }
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    assert!(info.requested_outboxes.is_empty());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_outbox_status_pages<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let key_pair = KeyPair::generate();
+    let owner = Owner::from(key_pair.public());
+    let sender_id = ChainId::root(1);
+    let chains = vec![
+        (ChainDescription::Root(1), owner, Amount::from_tokens(5)),
+        (ChainDescription::Root(2), owner, Amount::ZERO),
+        (ChainDescription::Root(3), owner, Amount::ZERO),
+    ];
+    let storage = storage_builder.build().await?;
+    let (committee, worker) = init_worker_with_chains(storage, chains).await;
+    let worker = worker.with_maximum_outboxes_per_response(1);
+
+    // Send to two recipients, withholding the cross-chain requests.
+    let mut previous = None;
+    for (recipient, balance) in [(2, 4), (3, 3)] {
+        let certificate = make_simple_transfer_certificate(
+            ChainDescription::Root(1),
+            &key_pair,
+            ChainId::root(recipient),
+            Amount::ONE,
+            Vec::new(),
+            &committee,
+            Amount::from_tokens(balance),
+            &worker,
+            previous.as_ref(),
+        )
+        .await;
+        worker
+            .handle_confirmed_certificate(certificate.clone(), None)
+            .await?;
+        previous = Some(certificate);
+    }
+
+    let query = ChainInfoQuery::new(sender_id).with_outboxes();
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    assert_eq!(info.requested_outboxes.len(), 1);
+    assert!(info.has_more_outboxes);
+    let mut recipients = info
+        .requested_outboxes
+        .into_keys()
+        .map(|target| target.recipient)
+        .collect::<BTreeSet<_>>();
+
+    let query = ChainInfoQuery::new(sender_id).with_outboxes_page(1);
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    assert_eq!(info.requested_outboxes.len(), 1);
+    assert!(!info.has_more_outboxes);
+    recipients.extend(
+        info.requested_outboxes
+            .into_keys()
+            .map(|target| target.recipient),
+    );
+    assert_eq!(
+        recipients,
+        BTreeSet::from([ChainId::root(2), ChainId::root(3)])
+    );
+
+    let query = ChainInfoQuery::new(sender_id).with_outboxes_page(2);
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    assert!(info.requested_outboxes.is_empty());
+    assert!(!info.has_more_outboxes);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_balance_proof<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let key_pair = KeyPair::generate();
+    let owner = Owner::from(key_pair.public());
+    let sender_id = ChainId::root(1);
+    let recipient_id = ChainId::root(2);
+    let chains = vec![
+        (ChainDescription::Root(1), owner, Amount::from_tokens(5)),
+        (ChainDescription::Root(2), owner, Amount::ZERO),
+    ];
+    let storage = storage_builder.build().await?;
+    let (committee, worker) = init_worker_with_chains(storage, chains).await;
+    let weights = committee
+        .validators()
+        .iter()
+        .map(|(name, state)| (name.0, state.votes))
+        .collect();
+
+    // There is no proof for a chain without blocks.
+    let query = ChainInfoQuery::new(recipient_id).with_balance_proof();
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    assert!(info.requested_balance_proof.is_none());
+
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &key_pair,
+        recipient_id,
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(4),
+        &worker,
+        None,
+    )
+    .await;
+    worker
+        .fully_handle_certificate_with_notifications(certificate.clone(), &())
+        .await?;
+
+    let query = ChainInfoQuery::new(sender_id).with_balance_proof();
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    let proof = info.requested_balance_proof.unwrap();
+    assert_eq!(proof.block_hash(), certificate.hash());
+    assert_eq!(
+        proof.verify(&weights)?,
+        (sender_id, BlockHeight::ZERO, Amount::from_tokens(4))
+    );
+
+    let mut tampered_proof = proof.clone();
+    tampered_proof.path.balance = Amount::from_tokens(5);
+    assert_matches!(
+        tampered_proof.verify(&weights),
+        Err(linera_base::balance_proof::BalanceProofError::StateHashMismatch)
+    );
+    let mut tampered_proof = proof;
+    tampered_proof.body.push(0);
+    assert_matches!(
+        tampered_proof.verify(&weights),
+        Err(linera_base::balance_proof::BalanceProofError::InvalidSignature(_))
+    );
+
+    let certificate1 = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &key_pair,
+        recipient_id,
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(3),
+        &worker,
+        Some(&certificate),
+    )
+    .await;
+    worker
+        .fully_handle_certificate_with_notifications(certificate1.clone(), &())
+        .await?;
+
+    // The latest proof is for the new block, but the earlier balance can still be proven.
+    let query = ChainInfoQuery::new(sender_id).with_balance_proof();
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    let proof = info.requested_balance_proof.unwrap();
+    assert_eq!(proof.block_hash(), certificate1.hash());
+    assert_eq!(
+        proof.verify(&weights)?,
+        (sender_id, BlockHeight(1), Amount::from_tokens(3))
+    );
+    let query = ChainInfoQuery::new(sender_id).with_balance_proof_at(BlockHeight::ZERO);
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    let proof = info.requested_balance_proof.unwrap();
+    assert_eq!(proof.block_hash(), certificate.hash());
+    assert_eq!(
+        proof.verify(&weights)?,
+        (sender_id, BlockHeight::ZERO, Amount::from_tokens(4))
+    );
+
+    // There is no proof for a block that doesn't exist yet.
+    let query = ChainInfoQuery::new(sender_id).with_balance_proof_at(BlockHeight(2));
+    let info = worker.handle_chain_info_query(query).await?.0.info;
+    assert!(info.requested_balance_proof.is_none());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_worker_metrics<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let key_pair = KeyPair::generate();
+    let (committee, worker) = init_worker_with_chains(
+        storage,
+        vec![
+            (
+                ChainDescription::Root(1),
+                key_pair.public().into(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2).into(),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+    let chain_ids = [ChainId::root(0), ChainId::root(1), ChainId::root(2)];
+    assert_eq!(
+        worker.metrics().await?,
+        WorkerMetrics {
+            tracked_chains: None,
+            stored_certificates: 0,
+            next_block_heights: chain_ids
+                .iter()
+                .map(|id| (*id, BlockHeight::ZERO))
+                .collect(),
+            pending_message_bundles: chain_ids.iter().map(|id| (*id, 0)).collect(),
+        }
+    );
+
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &key_pair,
+        ChainId::root(2),
+        Amount::ONE,
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(4),
+        &worker,
+        None,
+    )
+    .await;
+    worker
+        .fully_handle_certificate_with_notifications(certificate.clone(), &())
+        .await?;
+    let metrics = worker.metrics().await?;
+    assert_eq!(metrics.stored_certificates, 1);
+    assert_eq!(
+        metrics.next_block_heights[&ChainId::root(1)],
+        BlockHeight::from(1)
+    );
+    assert_eq!(metrics.pending_message_bundles[&ChainId::root(2)], 1);
+
+    // Processing the same certificate again doesn't add a block.
+    worker
+        .fully_handle_certificate_with_notifications(certificate, &())
+        .await?;
+    assert_eq!(worker.metrics().await?, metrics);
+
+    let worker = worker.with_tracked_chains([ChainId::root(1)]);
+    assert_eq!(worker.metrics().await?.tracked_chains, Some(1));
+    Ok(())
+}
+
+#[cfg(feature = "rocksdb")]
+#[test_log::test(tokio::test)]
+async fn test_rocks_db_storage_reopen() -> anyhow::Result<()> {
+    use linera_views::{lru_caching::LruCachingConfig, rocks_db::RocksDbStore};
+
+    let config = RocksDbStore::new_test_config().await?;
+    let reopen_config = LruCachingConfig {
+        inner_config: config.inner_config.clone(),
+        cache_size: config.cache_size,
+    };
+    let namespace = generate_test_namespace();
+    let storage = DbStorage::<RocksDbStore, TestClock>::new_for_testing(
+        config,
+        &namespace,
+        &[],
+        None,
+        TestClock::new(),
+    )
+    .await?;
+    let sender_key_pair = KeyPair::generate();
+    let (committee, worker) = init_worker_with_chain(
+        storage.clone(),
+        ChainDescription::Root(1),
+        sender_key_pair.public().into(),
+        Amount::from_tokens(5),
+    )
+    .await;
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::from_tokens(5),
+        Vec::new(),
+        &committee,
+        Amount::ZERO,
+        &worker,
+        None,
+    )
+    .await;
+    storage
+        .write_blobs_and_certificate(&[], &certificate)
+        .await?;
+    drop(worker);
+    drop(storage);
+
+    // Everything that was written is still there after opening the database again.
+    let storage = DbStorage::<RocksDbStore, _>::new(reopen_config, &namespace, &[], None).await?;
+    let chain = storage.load_chain(ChainId::root(1)).await?;
+    assert!(chain.is_active());
+    assert_eq!(
+        *chain.execution_state.system.balance.get(),
+        Amount::from_tokens(5)
+    );
+    assert_eq!(
+        storage.read_certificate(certificate.hash()).await?,
+        certificate
+    );
+    Ok(())
+}
@@ -20,9 +20,10 @@ use linera_chain::{
     data_types::{BlockProposal, LiteVote},
     types::{ConfirmedBlock, GenericCertificate, ValidatedBlock, ValidatedBlockCertificate},
 };
-use linera_execution::committee::Committee;
+use linera_execution::committee::{Committee, ValidatorName};
 use linera_storage::Storage;
 use thiserror::Error;
+use tracing::debug;
 
 use crate::{
     client::ChainClientError,
@@ -75,6 +76,8 @@ where
     pub chain_worker_count: usize,
     pub remote_node: RemoteNode<A>,
     pub local_node: LocalNodeClient<S>,
+    /// The maximum time to wait for each individual request to the validator.
+    pub request_timeout: Duration,
 }
 
 /// An error result for requests to a stake-weighted quorum.
@@ -90,10 +93,23 @@ pub enum CommunicationError<E: fmt::Debug> {
     /// valid.
     #[error("Failed to communicate with a quorum of validators: {0}")]
     Trusted(E),
-    /// No single error reached the validity threshold so we're returning a sample of
-    /// errors for debugging purposes, together with their weight.
-    #[error("Failed to communicate with a quorum of validators:\n{:#?}", .0)]
-    Sample(Vec<(E, u64)>),
+    /// No single error reached the validity threshold so we're returning how close we got
+    /// to a quorum, together with the error returned by each failing validator.
+    #[error(
+        "Failed to communicate with a quorum of validators: the best result had a weight of \
+         {achieved_weight} out of {required_weight} required, with {successes} successful \
+         responses. Errors:\n{errors:#?}"
+    )]
+    QuorumNotReached {
+        /// The highest weight of validators that agreed on the same result.
+        achieved_weight: u64,
+        /// The weight needed for a quorum.
+        required_weight: u64,
+        /// The number of validators that responded successfully.
+        successes: usize,
+        /// The validators that returned an error, with their error.
+        errors: Vec<(ValidatorName, E)>,
+    },
 }
 
 /// Executes a sequence of actions in parallel for all validators.
@@ -117,36 +133,122 @@ where
     K: Hash + PartialEq + Eq + Clone + 'static,
     V: 'static,
 {
-    let mut responses: futures::stream::FuturesUnordered<_> = validator_clients
-        .iter()
-        .filter_map(|remote_node| {
-            if committee.weight(&remote_node.name) == 0 {
-                // This should not happen but better prevent it because certificates
-                // are not allowed to include votes with weight 0.
-                return None;
-            }
-            let execute = execute.clone();
-            let remote_node = remote_node.clone();
-            Some(async move { (remote_node.name, execute(remote_node).await) })
-        })
-        .collect();
+    communicate_with_validators(
+        validator_clients,
+        committee,
+        group_by,
+        execute,
+        grace_period,
+        false,
+    )
+    .await
+}
+
+/// Executes a sequence of actions for the validators in the given order of preference, until a
+/// quorum is reached.
+///
+/// Unlike [`communicate_with_quorum`], the validators are contacted in waves: a request is only
+/// sent to the next validator in `validator_clients` if the requests still in flight can't reach
+/// a quorum anymore, e.g. because earlier validators returned an error or a different value. No
+/// new requests are sent during the grace period.
+pub async fn communicate_with_quorum_prioritized<'a, A, V, K, F, R, G>(
+    validator_clients: &'a [RemoteNode<A>],
+    committee: &Committee,
+    group_by: G,
+    execute: F,
+    // Grace period as a fraction of time taken to reach quorum
+    grace_period: f64,
+) -> Result<(K, Vec<V>), CommunicationError<NodeError>>
+where
+    A: ValidatorNode + Clone + 'static,
+    F: Clone + Fn(RemoteNode<A>) -> R,
+    R: Future<Output = Result<V, ChainClientError>> + 'a,
+    G: Fn(&V) -> K,
+    K: Hash + PartialEq + Eq + Clone + 'static,
+    V: 'static,
+{
+    communicate_with_validators(
+        validator_clients,
+        committee,
+        group_by,
+        execute,
+        grace_period,
+        true,
+    )
+    .await
+}
+
+/// Executes a sequence of actions for the validators, either all at once or in waves, and
+/// collects the results until a quorum is reached.
+async fn communicate_with_validators<'a, A, V, K, F, R, G>(
+    validator_clients: &'a [RemoteNode<A>],
+    committee: &Committee,
+    group_by: G,
+    execute: F,
+    grace_period: f64,
+    in_waves: bool,
+) -> Result<(K, Vec<V>), CommunicationError<NodeError>>
+where
+    A: ValidatorNode + Clone + 'static,
+    F: Clone + Fn(RemoteNode<A>) -> R,
+    R: Future<Output = Result<V, ChainClientError>> + 'a,
+    G: Fn(&V) -> K,
+    K: Hash + PartialEq + Eq + Clone + 'static,
+    V: 'static,
+{
+    let mut pending_nodes = validator_clients.iter().filter(|remote_node| {
+        // This should not happen but better prevent it because certificates
+        // are not allowed to include votes with weight 0.
+        committee.weight(&remote_node.name) > 0
+    });
+    let send_request = |remote_node: &RemoteNode<A>| {
+        let execute = execute.clone();
+        let remote_node = remote_node.clone();
+        async move {
+            let name = remote_node.name;
+            let start = Instant::now();
+            let result = execute(remote_node).await;
+            (name, result, start.elapsed())
+        }
+    };
+    let mut responses = futures::stream::FuturesUnordered::new();
 
     let start_time = Instant::now();
     let mut end_time: Option<Instant> = None;
     let mut remaining_votes = committee.total_votes();
+    let mut in_flight_votes = 0;
     let mut highest_key_score = 0;
     let mut value_scores = HashMap::new();
     let mut error_scores = HashMap::new();
+    let mut errors = Vec::new();
+    let mut successes = 0;
 
-    'vote_wait: while let Ok(Some((name, result))) = timeout(
-        end_time.map_or(MAX_TIMEOUT, |t| t.saturating_duration_since(Instant::now())),
-        responses.next(),
-    )
-    .await
-    {
+    'vote_wait: loop {
+        // Send requests to all validators at once, or only to as many as needed for the
+        // requests in flight to possibly reach a quorum.
+        if end_time.is_none() {
+            while !in_waves || highest_key_score + in_flight_votes < committee.quorum_threshold() {
+                let Some(remote_node) = pending_nodes.next() else {
+                    break;
+                };
+                in_flight_votes += committee.weight(&remote_node.name);
+                responses.push(send_request(remote_node));
+            }
+        }
+        let Ok(Some((name, result, latency))) = timeout(
+            end_time.map_or(MAX_TIMEOUT, |t| t.saturating_duration_since(Instant::now())),
+            responses.next(),
+        )
+        .await
+        else {
+            break 'vote_wait;
+        };
+        in_flight_votes -= committee.weight(&name);
         remaining_votes -= committee.weight(&name);
         match result {
             Ok(value) => {
+                debug!(validator = %name, ?latency, "Validator responded");
+                successes += 1;
                 let key = group_by(&value);
                 let entry = value_scores.entry(key.clone()).or_insert((0, Vec::new()));
                 entry.0 += committee.weight(&name);
@@ -161,6 +263,7 @@ where
                         error: err.to_string(),
                     },
                 };
+                debug!(validator = %name, ?latency, error = %err, "Validator returned an error");
                 let entry = error_scores.entry(err.clone()).or_insert(0);
                 *entry += committee.weight(&name);
                 if *entry >= committee.validity_threshold() {
@@ -168,6 +271,7 @@ where
                     // No quorum can be reached, so return early.
                     return Err(CommunicationError::Trusted(err));
                 }
+                errors.push((name, err));
             }
         }
         // If it becomes clear that no key can reach a quorum, break early.
@@ -194,7 +298,7 @@ where
         return Ok((key, values));
     }
 
-    if error_scores.is_empty() {
+    if errors.is_empty() {
         return Err(CommunicationError::NoConsensus(
             committee.quorum_threshold(),
             scores,
@@ -202,25 +306,40 @@ where
     }
 
     // No specific error is available to report reliably.
-    let mut sample = error_scores.into_iter().collect::<Vec<_>>();
-    sample.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
-    sample.truncate(4);
-    Err(CommunicationError::Sample(sample))
+    Err(CommunicationError::QuorumNotReached {
+        achieved_weight: highest_key_score,
+        required_weight: committee.quorum_threshold(),
+        successes,
+        errors,
+    })
 }
 
 impl<A, S> ValidatorUpdater<A, S>
 where
-    A: ValidatorNode + Clone + 'static,
+    A: ValidatorNode + Clone + Sync + 'static,
     S: Storage + Clone + Send + Sync + 'static,
 {
+    /// Waits for the response to a `request` to the validator, failing with
+    /// [`NodeError::RequestTimeout`] if it takes longer than `request_timeout`.
+    async fn with_timeout<T>(
+        &self,
+        request: impl Future<Output = Result<T, NodeError>>,
+    ) -> Result<T, NodeError> {
+        timeout(self.request_timeout, request)
+            .await
+            .unwrap_or(Err(NodeError::RequestTimeout))
+    }
+
     async fn send_confirmed_certificate(
         &mut self,
         certificate: GenericCertificate<ConfirmedBlock>,
         delivery: CrossChainMessageDelivery,
     ) -> Result<Box<ChainInfo>, ChainClientError> {
         let result = self
-            .remote_node
-            .handle_optimized_confirmed_certificate(&certificate, delivery)
+            .with_timeout(
+                self.remote_node
+                    .handle_optimized_confirmed_certificate(&certificate, delivery),
+            )
             .await;
 
         Ok(match &result {
@@ -230,10 +349,13 @@ where
                 // The certificate is confirmed, so the blobs must be in storage.
                 let maybe_blobs = self.local_node.read_blobs_from_storage(blob_ids).await?;
                 let blobs = maybe_blobs.ok_or_else(|| original_err.clone())?;
-                self.remote_node.upload_blobs(blobs.clone()).await?;
-                self.remote_node
-                    .handle_confirmed_certificate(certificate, delivery)
-                    .await
+                self.with_timeout(self.remote_node.upload_blobs(blobs.clone()))
+                    .await?;
+                self.with_timeout(
+                    self.remote_node
+                        .handle_confirmed_certificate(certificate, delivery),
+                )
+                .await
             }
             _ => result,
         }?)
@@ -245,8 +367,10 @@ where
         delivery: CrossChainMessageDelivery,
     ) -> Result<Box<ChainInfo>, ChainClientError> {
         let result = self
-            .remote_node
-            .handle_optimized_validated_certificate(&certificate, delivery)
+            .with_timeout(
+                self.remote_node
+                    .handle_optimized_validated_certificate(&certificate, delivery),
+            )
             .await;
 
         Ok(match &result {
@@ -261,9 +385,9 @@ where
                     .get_locking_blobs(blob_ids, chain_id)
                     .await?
                     .ok_or_else(|| original_err.clone())?;
-                self.remote_node.send_pending_blobs(chain_id, blobs).await?;
-                self.remote_node
-                    .handle_validated_certificate(certificate)
+                self.with_timeout(self.remote_node.send_pending_blobs(chain_id, blobs))
+                    .await?;
+                self.with_timeout(self.remote_node.handle_validated_certificate(certificate))
                     .await
             }
             _ => result,
@@ -279,8 +403,7 @@ where
         let mut sent_cross_chain_updates = false;
         loop {
             match self
-                .remote_node
-                .handle_block_proposal(proposal.clone())
+                .with_timeout(self.remote_node.handle_block_proposal(proposal.clone()))
                 .await
             {
                 Ok(info) => return Ok(info),
@@ -309,13 +432,17 @@ where
                                 .extend(chain.manager.proposed_blobs.get(&blob_id).await?);
                         }
                     }
-                    self.remote_node
-                        .send_pending_blobs(chain_id, published_blobs)
-                        .await?;
+                    self.with_timeout(
+                        self.remote_node
+                            .send_pending_blobs(chain_id, published_blobs),
+                    )
+                    .await?;
                     let missing_blob_ids = self
-                        .remote_node
-                        .node
-                        .missing_blob_ids(mem::take(&mut blob_ids))
+                        .with_timeout(
+                            self.remote_node
+                                .node
+                                .missing_blob_ids(mem::take(&mut blob_ids)),
+                        )
                         .await?;
                     let local_storage = self.local_node.storage_client();
                     let blob_states = local_storage.read_blob_states(&missing_blob_ids).await?;
@@ -349,23 +476,45 @@ where
     ) -> Result<(), ChainClientError> {
         // Figure out which certificates this validator is missing.
         let query = ChainInfoQuery::new(chain_id);
-        let remote_info = self.remote_node.handle_chain_info_query(query).await?;
+        let remote_info = self
+            .with_timeout(self.remote_node.handle_chain_info_query(query))
+            .await?;
         let initial_block_height = remote_info.next_block_height;
         // Obtain the missing blocks and the manager state from the local node.
         let range: Range<usize> =
             initial_block_height.try_into()?..target_block_height.try_into()?;
         let (keys, timeout) = {
             let chain = self.local_node.chain_state_view(chain_id).await?;
+            // The certificates below the pruned height were deleted, so we can't send them.
+            let start = range
+                .start
+                .max(usize::try_from(*chain.pruned_height.get())?);
             (
-                chain.confirmed_log.read(range).await?,
+                chain.confirmed_log.read(start..range.end).await?,
                 chain.manager.timeout.get().clone(),
             )
         };
         if !keys.is_empty() {
-            // Send the requested certificates in order.
+            // Send the requested certificates in order, in a single batch. From the first one
+            // that fails, e.g. because the validator is missing some blobs, they are sent again
+            // one by one.
             let storage = self.local_node.storage_client();
             let certs = storage.read_certificates(keys.into_iter()).await?;
-            for cert in certs {
+            let batch_timeout = self
+                .request_timeout
+                .saturating_mul(u32::try_from(certs.len()).unwrap_or(u32::MAX));
+            let results = timeout(
+                batch_timeout,
+                self.remote_node
+                    .handle_confirmed_certificates(certs.clone(), delivery),
+            )
+            .await
+            .map_err(|_| NodeError::RequestTimeout)?;
+            let sent = results
+                .iter()
+                .position(Result::is_err)
+                .unwrap_or(results.len());
+            for cert in certs.into_iter().skip(sent) {
                 self.send_confirmed_certificate(cert, delivery).await?;
             }
         }
@@ -373,7 +522,8 @@ where
             if cert.inner().chain_id == chain_id {
                 // Timeouts are small and don't have blobs, so we can call `handle_certificate`
                 // directly.
-                self.remote_node.handle_timeout_certificate(cert).await?;
+                self.with_timeout(self.remote_node.handle_timeout_certificate(cert))
+                    .await?;
             }
         }
         Ok(())
@@ -458,7 +608,9 @@ where
             }
             CommunicateAction::RequestTimeout { .. } => {
                 let query = ChainInfoQuery::new(chain_id).with_timeout();
-                let info = self.remote_node.handle_chain_info_query(query).await?;
+                let info = self
+                    .with_timeout(self.remote_node.handle_chain_info_query(query))
+                    .await?;
                 info.manager.timeout_vote
             }
         };
@@ -15,7 +15,8 @@ use linera_base::crypto::PublicKey;
 use linera_base::{
     crypto::{CryptoError, CryptoHash, KeyPair},
     data_types::{
-        ArithmeticError, Blob, BlockHeight, DecompressionError, Round, UserApplicationDescription,
+        ArithmeticError, Blob, BlockHeight, DecompressionError, ProtocolVersion, Round,
+        UserApplicationDescription,
     },
     doc_scalar,
     hashed::Hashed,
@@ -35,7 +36,7 @@ use linera_chain::{
 };
 use linera_execution::{
     committee::{Epoch, ValidatorName},
-    ExecutionError, Query, QueryOutcome,
+    ExecutionError, Query, QueryOutcome, ResourceTracker,
 };
 use linera_storage::Storage;
 use linera_views::views::ViewError;
@@ -47,14 +48,17 @@ use tracing::{error, instrument, trace, warn, Instrument as _};
 #[cfg(with_metrics)]
 use {
     linera_base::prometheus_util::{
-        bucket_interval, register_histogram_vec, register_int_counter_vec,
+        bucket_interval, register_histogram_vec, register_int_counter_vec, register_int_gauge_vec,
     },
-    prometheus::{HistogramVec, IntCounterVec},
+    prometheus::{HistogramVec, IntCounterVec, IntGaugeVec},
     std::sync::LazyLock,
 };
 
 use crate::{
-    chain_worker::{ChainWorkerActor, ChainWorkerConfig, ChainWorkerRequest, DeliveryNotifier},
+    chain_worker::{
+        ChainWorkerActor, ChainWorkerConfig, ChainWorkerRequest, DeliveryNotifier,
+        InactiveChainPolicy,
+    },
     data_types::{ChainInfoQuery, ChainInfoResponse, CrossChainRequest},
     join_set_ext::{JoinSet, JoinSetExt},
     notifier::Notifier,
@@ -103,6 +107,42 @@ static CERTIFICATES_SIGNED: LazyLock<IntCounterVec> = LazyLock::new(|| {
     )
 });
 
+#[cfg(with_metrics)]
+static NUM_TRACKED_CHAINS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec(
+        "num_tracked_chains",
+        "Number of chains tracked by a worker",
+        &[],
+    )
+});
+
+#[cfg(with_metrics)]
+static NUM_STORED_CHAINS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec(
+        "num_stored_chains",
+        "Number of chains in a worker's storage",
+        &[],
+    )
+});
+
+#[cfg(with_metrics)]
+static NUM_STORED_CERTIFICATES: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec(
+        "num_stored_certificates",
+        "Number of certificates of the chains in a worker's storage",
+        &[],
+    )
+});
+
+#[cfg(with_metrics)]
+static NUM_PENDING_MESSAGE_BUNDLES: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec(
+        "num_pending_message_bundles",
+        "Number of incoming message bundles that no block has processed yet",
+        &[],
+    )
+});
+
 /// Instruct the networking layer to send cross-chain requests and/or push notifications.
 #[derive(Default, Debug)]
 pub struct NetworkActions {
@@ -170,6 +210,9 @@ pub enum WorkerError {
     #[error("Operations in the block are not authenticated by the proper signer: {0}")]
     InvalidSigner(Owner),
 
+    #[error("Block proposal was approved by {approvals} owners but requires {threshold}")]
+    InsufficientApprovals { approvals: usize, threshold: u32 },
+
     // Chaining
     #[error(
         "Was expecting block height {expected_block_height} but found {found_block_height} instead"
@@ -188,6 +231,11 @@ pub enum WorkerError {
     },
 
     // Other server-side errors
+    #[error("Peer uses protocol version {theirs}, which is incompatible with our version {ours}")]
+    IncompatibleProtocolVersion {
+        ours: ProtocolVersion,
+        theirs: ProtocolVersion,
+    },
     #[error("Invalid cross-chain request")]
     InvalidCrossChainRequest,
     #[error("The block does contain the hash that we expected for the previous block")]
@@ -203,6 +251,14 @@ pub enum WorkerError {
         computed: Box<BlockExecutionOutcome>,
         submitted: Box<BlockExecutionOutcome>,
     },
+    #[error(
+        "The state hash of block {height} of chain {chain_id} is not what we computed after \
+        executing the block"
+    )]
+    StateHashMismatch {
+        chain_id: ChainId,
+        height: BlockHeight,
+    },
     #[error("The timestamp of a Tick operation is in the future.")]
     InvalidTimestamp,
     #[error("We don't have the value for the certificate.")]
@@ -227,6 +283,19 @@ pub enum WorkerError {
     Decompression(#[from] DecompressionError),
 }
 
+/// Returns an error if messages from a peer with the given protocol version cannot be
+/// handled. Newer minor versions are accepted, and `None` stands for a peer that predates
+/// version negotiation.
+pub fn check_protocol_version(theirs: Option<ProtocolVersion>) -> Result<(), WorkerError> {
+    let ours = ProtocolVersion::CURRENT;
+    let theirs = theirs.unwrap_or(ProtocolVersion::UNVERSIONED);
+    if ours.is_compatible_with(&theirs) {
+        Ok(())
+    } else {
+        Err(WorkerError::IncompatibleProtocolVersion { ours, theirs })
+    }
+}
+
 impl From<ChainError> for WorkerError {
     #[instrument(level = "trace", skip(chain_error))]
     fn from(chain_error: ChainError) -> Self {
@@ -256,6 +325,157 @@ impl From<ViewError> for WorkerError {
     }
 }
 
+/// A snapshot of the state of a worker, as returned by [`WorkerState::metrics`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct WorkerMetrics {
+    /// The number of chains the worker is tracking, or `None` if it tracks all chains.
+    pub tracked_chains: Option<usize>,
+    /// The number of certificates of the chains in storage, not counting pruned ones.
+    pub stored_certificates: u64,
+    /// The next block height of each chain in storage.
+    pub next_block_heights: BTreeMap<ChainId, BlockHeight>,
+    /// The number of incoming message bundles that no block has processed yet, for each chain
+    /// in storage.
+    pub pending_message_bundles: BTreeMap<ChainId, usize>,
+}
+
+/// The per-chain values of [`WorkerMetrics`]. The chain workers update them whenever they
+/// save a chain.
+///
+/// Nothing is recorded until the metrics are first requested: after that there is one entry
+/// per chain in storage, plus one per inbox with pending bundles. The totals are kept up to
+/// date with each change, so updating the gauges doesn't iterate over the chains.
+#[derive(Debug, Default)]
+pub(crate) struct ChainCounters {
+    /// Whether the chains in storage are being or have been loaded.
+    state: ChainCountersState,
+    /// The next block height and the pruned height of each chain.
+    heights: BTreeMap<ChainId, (BlockHeight, BlockHeight)>,
+    /// The number of pending bundles in each inbox of each chain. While loading, inboxes
+    /// that became empty are kept with a count of zero, so that the loaded count doesn't
+    /// replace them.
+    pending_message_bundles: BTreeMap<ChainId, BTreeMap<Origin, usize>>,
+    /// The sum of the chains' next block heights minus their pruned heights.
+    total_stored_certificates: u64,
+    /// The sum of the pending bundles of all inboxes.
+    total_pending_message_bundles: usize,
+}
+
+/// Whether [`ChainCounters`] include the chains that were in storage before.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ChainCountersState {
+    /// The metrics were never requested, so changes are not recorded.
+    #[default]
+    NotLoaded,
+    /// The chains in storage are being loaded. Changes are recorded and take precedence over
+    /// the loaded values.
+    Loading,
+    /// The chains in storage are included.
+    Loaded,
+}
+
+impl ChainCounters {
+    /// Records the next block height and the pruned height of a chain that was just saved.
+    pub(crate) fn update_heights(
+        &mut self,
+        chain_id: ChainId,
+        next_block_height: BlockHeight,
+        pruned_height: BlockHeight,
+    ) {
+        if self.state == ChainCountersState::NotLoaded {
+            return;
+        }
+        let new_heights = (next_block_height, pruned_height);
+        if let Some((old_next_block_height, old_pruned_height)) =
+            self.heights.insert(chain_id, new_heights)
+        {
+            self.total_stored_certificates -= old_next_block_height.0 - old_pruned_height.0;
+        }
+        self.total_stored_certificates += next_block_height.0 - pruned_height.0;
+        self.update_gauges();
+    }
+
+    /// Records the number of pending bundles in a chain's inbox that was just saved.
+    pub(crate) fn update_pending_message_bundles(
+        &mut self,
+        chain_id: ChainId,
+        origin: Origin,
+        count: usize,
+    ) {
+        if self.state == ChainCountersState::NotLoaded {
+            return;
+        }
+        let inboxes = self.pending_message_bundles.entry(chain_id).or_default();
+        let old_count = if count == 0 && self.state == ChainCountersState::Loaded {
+            inboxes.remove(&origin)
+        } else {
+            inboxes.insert(origin, count)
+        };
+        if inboxes.is_empty() {
+            self.pending_message_bundles.remove(&chain_id);
+        }
+        self.total_pending_message_bundles -= old_count.unwrap_or(0);
+        self.total_pending_message_bundles += count;
+        self.update_gauges();
+    }
+
+    /// Adds the loaded values of the chains in storage. Values that were recorded since the
+    /// loading started are newer and are kept.
+    fn merge_loaded(
+        &mut self,
+        heights: BTreeMap<ChainId, (BlockHeight, BlockHeight)>,
+        pending_message_bundles: BTreeMap<ChainId, BTreeMap<Origin, usize>>,
+    ) {
+        for (chain_id, chain_heights) in heights {
+            self.heights.entry(chain_id).or_insert(chain_heights);
+        }
+        for (chain_id, loaded_inboxes) in pending_message_bundles {
+            let inboxes = self.pending_message_bundles.entry(chain_id).or_default();
+            for (origin, count) in loaded_inboxes {
+                inboxes.entry(origin).or_insert(count);
+            }
+        }
+        for inboxes in self.pending_message_bundles.values_mut() {
+            inboxes.retain(|_, count| *count > 0);
+        }
+        self.pending_message_bundles
+            .retain(|_, inboxes| !inboxes.is_empty());
+        self.total_stored_certificates = self
+            .heights
+            .values()
+            .map(|(next_block_height, pruned_height)| next_block_height.0 - pruned_height.0)
+            .sum();
+        self.total_pending_message_bundles = self
+            .pending_message_bundles
+            .values()
+            .flat_map(BTreeMap::values)
+            .sum();
+        self.state = ChainCountersState::Loaded;
+        self.update_gauges();
+    }
+
+    fn pending_message_bundles(&self, chain_id: &ChainId) -> usize {
+        self.pending_message_bundles
+            .get(chain_id)
+            .map_or(0, |inboxes| inboxes.values().sum())
+    }
+
+    fn update_gauges(&self) {
+        #[cfg(with_metrics)]
+        {
+            NUM_STORED_CHAINS
+                .with_label_values(&[])
+                .set(self.heights.len() as i64);
+            NUM_STORED_CERTIFICATES
+                .with_label_values(&[])
+                .set(self.total_stored_certificates as i64);
+            NUM_PENDING_MESSAGE_BUNDLES
+                .with_label_values(&[])
+                .set(self.total_pending_message_bundles as i64);
+        }
+    }
+}
+
 /// State of a worker in a validator or a local node.
 #[derive(Clone)]
 pub struct WorkerState<StorageClient>
@@ -278,6 +498,8 @@ where
     chain_worker_tasks: Arc<Mutex<JoinSet>>,
     /// The cache of running [`ChainWorkerActor`]s.
     chain_workers: Arc<Mutex<LruCache<ChainId, ChainActorEndpoint<StorageClient>>>>,
+    /// The per-chain values of the worker's metrics.
+    chain_counters: Arc<Mutex<ChainCounters>>,
 }
 
 /// The sender endpoint for [`ChainWorkerRequest`]s.
@@ -306,6 +528,7 @@ where
             delivery_notifiers: Arc::default(),
             chain_worker_tasks: Arc::default(),
             chain_workers: Arc::new(Mutex::new(LruCache::new(chain_worker_limit))),
+            chain_counters: Arc::default(),
         }
     }
 
@@ -325,12 +548,13 @@ where
             delivery_notifiers: Arc::default(),
             chain_worker_tasks: Arc::default(),
             chain_workers: Arc::new(Mutex::new(LruCache::new(chain_worker_limit))),
+            chain_counters: Arc::default(),
         }
     }
 
     #[instrument(level = "trace", skip(self, value))]
-    pub fn with_allow_inactive_chains(mut self, value: bool) -> Self {
-        self.chain_worker_config.allow_inactive_chains = value;
+    pub fn with_inactive_chain_policy(mut self, value: InactiveChainPolicy) -> Self {
+        self.chain_worker_config.inactive_chain_policy = value;
         self
     }
 
@@ -347,6 +571,23 @@ where
         self
     }
 
+    /// Returns an instance that returns at most `value` sent certificate hashes per chain
+    /// info response.
+    #[instrument(level = "trace", skip(self, value))]
+    pub fn with_maximum_sent_certificate_hashes_per_response(mut self, value: usize) -> Self {
+        self.chain_worker_config
+            .maximum_sent_certificate_hashes_per_response = value;
+        self
+    }
+
+    /// Returns an instance that returns at most `value` outbox statuses per chain info
+    /// response.
+    #[instrument(level = "trace", skip(self, value))]
+    pub fn with_maximum_outboxes_per_response(mut self, value: usize) -> Self {
+        self.chain_worker_config.maximum_outboxes_per_response = value;
+        self
+    }
+
     #[instrument(level = "trace", skip(self, tracked_chains))]
     /// Configures the subset of chains that this worker is tracking.
     pub fn with_tracked_chains(
@@ -387,6 +628,94 @@ where
         &self.storage
     }
 
+    /// Returns the number of tracked chains, the number of stored certificates, and the
+    /// heights and pending incoming messages of the chains in storage.
+    ///
+    /// The first call enumerates the chains in storage. After that, the values are kept up to
+    /// date by the chain workers whenever they save a chain, so this doesn't read storage.
+    #[instrument(level = "trace", skip(self))]
+    pub async fn metrics(&self) -> Result<WorkerMetrics, WorkerError> {
+        let state = {
+            let mut counters = self.chain_counters.lock().unwrap();
+            let state = counters.state;
+            if state == ChainCountersState::NotLoaded {
+                counters.state = ChainCountersState::Loading;
+            }
+            state
+        };
+        if state != ChainCountersState::Loaded {
+            if let Err(error) = self.load_chain_counters().await {
+                let mut counters = self.chain_counters.lock().unwrap();
+                if counters.state == ChainCountersState::Loading {
+                    *counters = ChainCounters::default();
+                }
+                return Err(error);
+            }
+        }
+        let tracked_chains = self
+            .tracked_chains
+            .as_ref()
+            .map(|tracked_chains| tracked_chains.read().unwrap().len());
+        #[cfg(with_metrics)]
+        if let Some(tracked_chains) = tracked_chains {
+            NUM_TRACKED_CHAINS
+                .with_label_values(&[])
+                .set(tracked_chains as i64);
+        }
+        let counters = self.chain_counters.lock().unwrap();
+        Ok(WorkerMetrics {
+            tracked_chains,
+            stored_certificates: counters.total_stored_certificates,
+            next_block_heights: counters
+                .heights
+                .iter()
+                .map(|(chain_id, (next_block_height, _))| (*chain_id, *next_block_height))
+                .collect(),
+            pending_message_bundles: counters
+                .heights
+                .keys()
+                .map(|chain_id| (*chain_id, counters.pending_message_bundles(chain_id)))
+                .collect(),
+        })
+    }
+
+    /// Adds the chains in storage to the metrics' counters. Chains and inboxes that a chain
+    /// worker has saved in the meantime keep their newer values.
+    async fn load_chain_counters(&self) -> Result<(), WorkerError> {
+        let mut heights = BTreeMap::new();
+        let mut pending_message_bundles = BTreeMap::new();
+        for chain_id in self.storage.list_chain_ids().await? {
+            let chain = self.storage.load_chain(chain_id).await?;
+            heights.insert(
+                chain_id,
+                (
+                    chain.tip_state.get().next_block_height,
+                    *chain.pruned_height.get(),
+                ),
+            );
+            let mut inboxes = BTreeMap::new();
+            for (origin, inbox) in chain.inboxes.try_load_all_entries().await? {
+                let count = inbox.added_bundles.count();
+                if count > 0 {
+                    inboxes.insert(origin, count);
+                }
+            }
+            pending_message_bundles.insert(chain_id, inboxes);
+        }
+        self.chain_counters
+            .lock()
+            .unwrap()
+            .merge_loaded(heights, pending_message_bundles);
+        Ok(())
+    }
+
+    /// Stops all chain workers, so that the chain states are loaded from storage again the
+    /// next time they are needed. This allows tests to modify the stored chain states.
+    #[cfg(with_testing)]
+    pub(crate) fn clear_chain_workers(&self) {
+        self.chain_workers.lock().unwrap().clear();
+    }
+
     #[instrument(level = "trace", skip(self, key_pair))]
     #[cfg(test)]
     pub(crate) async fn with_key_pair(mut self, key_pair: Option<Arc<KeyPair>>) -> Self {
@@ -480,21 +809,63 @@ where
         let notifications = (*notifier).clone();
         let this = self.clone();
         linera_base::task::spawn(async move {
-            let (response, actions) =
-                ProcessableCertificate::process_certificate(&this, certificate).await?;
-            notifications.notify(&actions.notifications);
-            let mut requests = VecDeque::from(actions.cross_chain_requests);
-            while let Some(request) = requests.pop_front() {
-                let actions = this.handle_cross_chain_request(request).await?;
-                requests.extend(actions.cross_chain_requests);
-                notifications.notify(&actions.notifications);
-            }
-            Ok(response)
+            this.process_certificate_and_actions(certificate, notifications)
+                .await
         })
         .await
         .unwrap_or_else(|_| Err(WorkerError::JoinError))
     }
 
+    /// Processes the confirmed `certificates` in order, along with the cross-chain requests they
+    /// trigger, in a single task.
+    ///
+    /// Returns the result for each certificate, in the same order: a certificate that fails
+    /// doesn't prevent the following ones from being processed.
+    #[instrument(level = "trace", skip(self, certificates, notifier))]
+    pub async fn fully_handle_certificates_with_notifications(
+        &self,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        notifier: &impl Notifier,
+    ) -> Vec<Result<ChainInfoResponse, WorkerError>> {
+        let count = certificates.len();
+        let notifications = (*notifier).clone();
+        let this = self.clone();
+        linera_base::task::spawn(async move {
+            let mut results = Vec::with_capacity(certificates.len());
+            for certificate in certificates {
+                results.push(
+                    this.process_certificate_and_actions(certificate, notifications.clone())
+                        .await,
+                );
+            }
+            results
+        })
+        .await
+        .unwrap_or_else(|_| (0..count).map(|_| Err(WorkerError::JoinError)).collect())
+    }
+
+    /// Processes a certificate and then the cross-chain requests it triggers, sending the
+    /// resulting notifications with the `notifier`.
+    async fn process_certificate_and_actions<T>(
+        &self,
+        certificate: GenericCertificate<T>,
+        notifier: impl Notifier,
+    ) -> Result<ChainInfoResponse, WorkerError>
+    where
+        T: ProcessableCertificate,
+    {
+        let (response, actions) =
+            ProcessableCertificate::process_certificate(self, certificate).await?;
+        notifier.notify(&actions.notifications);
+        let mut requests = VecDeque::from(actions.cross_chain_requests);
+        while let Some(request) = requests.pop_front() {
+            let actions = self.handle_cross_chain_request(request).await?;
+            requests.extend(actions.cross_chain_requests);
+            notifier.notify(&actions.notifications);
+        }
+        Ok(response)
+    }
+
     /// Tries to execute a block proposal without any verification other than block execution.
     #[instrument(level = "trace", skip(self, block))]
     pub async fn stage_block_execution(
@@ -502,6 +873,20 @@ where
         block: ProposedBlock,
         round: Option<u32>,
     ) -> Result<(ExecutedBlock, ChainInfoResponse), WorkerError> {
+        let (executed_block, response, _) = self
+            .stage_block_execution_with_resources(block, round)
+            .await?;
+        Ok((executed_block, response))
+    }
+
+    /// Tries to execute a block proposal like [`WorkerState::stage_block_execution`], and also
+    /// returns the resources used by the execution.
+    #[instrument(level = "trace", skip(self, block))]
+    pub async fn stage_block_execution_with_resources(
+        &self,
+        block: ProposedBlock,
+        round: Option<u32>,
+    ) -> Result<(ExecutedBlock, ChainInfoResponse, ResourceTracker), WorkerError> {
         self.query_chain_worker(block.chain_id, move |callback| {
             ChainWorkerRequest::StageBlockExecution {
                 block,
@@ -703,6 +1088,7 @@ where
                 self.executed_block_cache.clone(),
                 self.tracked_chains.clone(),
                 delivery_notifier,
+                self.chain_counters.clone(),
                 chain_id,
             )
             .await?;
@@ -1038,16 +1424,35 @@ where
     pub async fn update_received_certificate_trackers(
         &self,
         chain_id: ChainId,
-        new_trackers: BTreeMap<ValidatorName, u64>,
+        new_trackers: BTreeMap<ChainId, BlockHeight>,
+        new_log_indices: BTreeMap<ValidatorName, u64>,
     ) -> Result<(), WorkerError> {
         self.query_chain_worker(chain_id, move |callback| {
             ChainWorkerRequest::UpdateReceivedCertificateTrackers {
                 new_trackers,
+                new_log_indices,
                 callback,
             }
         })
         .await
     }
+
+    /// Deletes the certificates of the given chain's old blocks, keeping the last
+    /// `keep_last` ones, those at or above `keep_from` and those whose outgoing messages are
+    /// still in flight.
+    pub async fn prune_history(
+        &self,
+        chain_id: ChainId,
+        keep_last: usize,
+        keep_from: BlockHeight,
+    ) -> Result<(), WorkerError> {
+        self.query_chain_worker(chain_id, move |callback| ChainWorkerRequest::PruneHistory {
+            keep_last,
+            keep_from,
+            callback,
+        })
+        .await
+    }
 }
 
 #[cfg(with_testing)]
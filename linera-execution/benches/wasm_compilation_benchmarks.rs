@@ -0,0 +1,94 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compares the time needed to compile one module per available core when the modules are loaded
+//! one after the other with the time needed to compile them all with a single warm-up.
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use linera_base::data_types::Bytecode;
+use linera_execution::{WasmContractModule, WasmRuntime};
+use tokio::runtime::Runtime;
+
+/// A counter used to make every generated bytecode unique, so that none of them is cached.
+static NEXT_BYTECODE_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Returns `count` distinct copies of `bytecode` that haven't been compiled yet.
+///
+/// Each copy has an extra custom section holding a unique identifier, which doesn't change the
+/// behavior of the module but forces it to be compiled again.
+fn unique_bytecodes(bytecode: &Bytecode, count: usize) -> Vec<Bytecode> {
+    const SECTION_NAME: &[u8] = b"benchmark";
+
+    (0..count)
+        .map(|_| {
+            let id = NEXT_BYTECODE_ID
+                .fetch_add(1, Ordering::Relaxed)
+                .to_le_bytes();
+            let mut bytes = bytecode.as_ref().to_vec();
+            // Custom section ID, section size, then the name and the contents of the section.
+            bytes.push(0);
+            bytes.push((1 + SECTION_NAME.len() + id.len()) as u8);
+            bytes.push(SECTION_NAME.len() as u8);
+            bytes.extend_from_slice(SECTION_NAME);
+            bytes.extend_from_slice(&id);
+            Bytecode::new(bytes)
+        })
+        .collect()
+}
+
+fn bench_contract_compilation(criterion: &mut Criterion) {
+    let runtime = Runtime::new().expect("Failed to create Tokio runtime");
+    let bytecode = runtime
+        .block_on(Bytecode::load_from_file(
+            "tests/fixtures/counter_contract.wasm",
+        ))
+        .expect("Failed to load the counter contract");
+    let module_count = thread::available_parallelism().map_or(1, usize::from);
+
+    criterion.bench_function("serial_contract_compilation", |bencher| {
+        bencher.to_async(&runtime).iter_custom(|iterations| {
+            let bytecode = bytecode.clone();
+            async move {
+                let mut total_time = Duration::ZERO;
+                for _ in 0..iterations {
+                    let bytecodes = unique_bytecodes(&bytecode, module_count);
+                    let measurement = Instant::now();
+                    for bytecode in bytecodes {
+                        WasmContractModule::new(bytecode, WasmRuntime::Wasmtime)
+                            .await
+                            .expect("Failed to compile the counter contract");
+                    }
+                    total_time += measurement.elapsed();
+                }
+                total_time
+            }
+        })
+    });
+
+    criterion.bench_function("parallel_contract_warm_up", |bencher| {
+        bencher.to_async(&runtime).iter_custom(|iterations| {
+            let bytecode = bytecode.clone();
+            async move {
+                let mut total_time = Duration::ZERO;
+                for _ in 0..iterations {
+                    let bytecodes = unique_bytecodes(&bytecode, module_count);
+                    let measurement = Instant::now();
+                    WasmContractModule::warm_up(bytecodes, WasmRuntime::Wasmtime)
+                        .await
+                        .expect("Failed to compile the counter contract");
+                    total_time += measurement.elapsed();
+                }
+                total_time
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_contract_compilation);
+criterion_main!(benches);
@@ -0,0 +1,255 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Benchmarks contract operations executed with Wasmtime.
+//!
+//! The first benchmark compares the latency of operations when instantiating the module for
+//! every transaction with the latency when reusing pooled instances.
+//!
+//! The second one measures the latency and the fuel consumption of operations for each
+//! benchmarked module and fuel budget. The modules are loaded with
+//! [`WasmContractModule::from_wasmtime`] before every operation, so the measured latency
+//! includes looking up the compiled module in the contract cache and preparing an instance. The
+//! fuel consumed by an operation is deterministic, so it is measured once per benchmark and
+//! reported by Criterion as the throughput, in fuel per second. It is also checked to be the same
+//! for every operation.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use linera_base::{
+    data_types::{BlockHeight, Bytecode, Timestamp},
+    identifiers::{ChainDescription, ChainId, UserApplicationId},
+};
+use linera_execution::{
+    test_utils::{create_dummy_user_application_description, SystemExecutionState},
+    ExecutionRuntimeConfig, ExecutionRuntimeContext, ExecutionStateView, Operation,
+    OperationContext, ResourceControlPolicy, ResourceController, ResourceTracker,
+    TestExecutionRuntimeContext, TransactionTracker, WasmContractModule, WasmRuntime,
+};
+use linera_views::{context::Context as _, memory::MemoryContext};
+use tokio::runtime::Runtime;
+
+/// The number of idle instances kept by the pooled contract.
+const POOL_CAPACITY: usize = 4;
+
+/// The contracts benchmarked for their fuel consumption: their names, the files with their
+/// bytecodes, and the operation to execute, serialized with BCS.
+const MODULES: &[(&str, &str, fn() -> Vec<u8>)] = &[(
+    "counter",
+    "tests/fixtures/counter_contract.wasm",
+    counter_increment,
+)];
+
+/// The fuel budgets available to each operation.
+const FUEL_BUDGETS: &[u64] = &[1_000_000, 100_000_000];
+
+/// Returns the operation incrementing the counter application by one.
+fn counter_increment() -> Vec<u8> {
+    bcs::to_bytes(&1_u64).unwrap()
+}
+
+/// Returns the context of the benchmarked operations.
+fn operation_context() -> OperationContext {
+    OperationContext {
+        chain_id: ChainId::root(0),
+        height: BlockHeight(0),
+        round: Some(0),
+        index: Some(0),
+        authenticated_signer: None,
+        authenticated_caller_id: None,
+    }
+}
+
+/// Creates an execution state with a single application, without a contract yet.
+async fn setup_application() -> (
+    ExecutionStateView<MemoryContext<TestExecutionRuntimeContext>>,
+    UserApplicationId,
+) {
+    let state = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        ..Default::default()
+    };
+    let mut view = state
+        .into_view_with(ChainId::root(0), ExecutionRuntimeConfig::default())
+        .await;
+    let (app_desc, contract_blob, service_blob) = create_dummy_user_application_description(1);
+    let app_id = view
+        .system
+        .registry
+        .register_application(app_desc)
+        .await
+        .unwrap();
+    view.context()
+        .extra()
+        .add_blobs([contract_blob, service_blob])
+        .await
+        .unwrap();
+
+    (view, app_id)
+}
+
+/// Executes `iterations` increment operations of the counter application, instantiating the
+/// contract with the provided `contract` module.
+async fn increment_counter(contract: WasmContractModule, iterations: u64) -> Duration {
+    let (mut view, app_id) = setup_application().await;
+    view.context()
+        .extra()
+        .user_contracts()
+        .insert(app_id, contract.into());
+    let operation = Operation::user_without_abi(app_id, &1_u64).unwrap();
+    let mut controller = ResourceController {
+        policy: Arc::new(ResourceControlPolicy::default()),
+        tracker: ResourceTracker::default(),
+        account: None,
+    };
+
+    let mut total_time = Duration::ZERO;
+    for _ in 0..iterations {
+        let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+        let measurement = Instant::now();
+        view.execute_operation(
+            operation_context(),
+            Timestamp::from(0),
+            operation.clone(),
+            &mut txn_tracker,
+            &mut controller,
+        )
+        .await
+        .unwrap();
+        total_time += measurement.elapsed();
+        black_box(txn_tracker);
+    }
+
+    total_time
+}
+
+/// Executes `iterations` operations with the contract in `bytecode`, each with at most
+/// `fuel_budget` fuel, and returns the total time and the fuel consumed by each operation.
+async fn execute_operations(
+    bytecode: Bytecode,
+    operation: Vec<u8>,
+    fuel_budget: u64,
+    iterations: u64,
+) -> (Duration, u64) {
+    let (mut view, app_id) = setup_application().await;
+    let operation = Operation::User {
+        application_id: app_id,
+        bytes: operation,
+    };
+    let policy = Arc::new(ResourceControlPolicy {
+        maximum_fuel_per_block: fuel_budget,
+        ..ResourceControlPolicy::default()
+    });
+
+    let mut total_time = Duration::ZERO;
+    let mut fuel_per_operation = None;
+    for _ in 0..iterations {
+        let mut controller = ResourceController {
+            policy: policy.clone(),
+            tracker: ResourceTracker::default(),
+            account: None,
+        };
+        let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+        let measurement = Instant::now();
+        let contract = WasmContractModule::from_wasmtime(bytecode.clone())
+            .await
+            .expect("Failed to load the contract");
+        view.context()
+            .extra()
+            .user_contracts()
+            .insert(app_id, contract.into());
+        view.execute_operation(
+            operation_context(),
+            Timestamp::from(0),
+            operation.clone(),
+            &mut txn_tracker,
+            &mut controller,
+        )
+        .await
+        .expect("Operation failed within the fuel budget");
+        total_time += measurement.elapsed();
+        black_box(txn_tracker);
+
+        let fuel = controller.tracker.fuel;
+        assert_eq!(
+            *fuel_per_operation.get_or_insert(fuel),
+            fuel,
+            "Fuel consumption should be deterministic"
+        );
+    }
+
+    (total_time, fuel_per_operation.unwrap_or_default())
+}
+
+fn bench_contract_instantiation(criterion: &mut Criterion) {
+    let runtime = Runtime::new().expect("Failed to create Tokio runtime");
+    let contract = runtime
+        .block_on(WasmContractModule::from_file(
+            "tests/fixtures/counter_contract.wasm",
+            WasmRuntime::Wasmtime,
+        ))
+        .expect("Failed to load the counter contract");
+
+    criterion.bench_function("fresh_contract_instance_operation", |bencher| {
+        let contract = contract.clone().with_instance_pool_capacity(0);
+        bencher
+            .to_async(&runtime)
+            .iter_custom(|iterations| increment_counter(contract.clone(), iterations))
+    });
+
+    criterion.bench_function("pooled_contract_instance_operation", |bencher| {
+        let contract = contract.clone().with_instance_pool_capacity(POOL_CAPACITY);
+        bencher
+            .to_async(&runtime)
+            .iter_custom(|iterations| increment_counter(contract.clone(), iterations))
+    });
+}
+
+fn bench_contract_fuel(criterion: &mut Criterion) {
+    let runtime = Runtime::new().expect("Failed to create Tokio runtime");
+    let mut group = criterion.benchmark_group("contract_operation");
+
+    for (name, path, operation) in MODULES {
+        let bytecode = runtime
+            .block_on(Bytecode::load_from_file(path))
+            .expect("Failed to load the contract bytecode");
+        // Compile the module once, so that every measured operation hits the contract cache.
+        runtime
+            .block_on(WasmContractModule::from_wasmtime(bytecode.clone()))
+            .expect("Failed to compile the contract");
+
+        for &fuel_budget in FUEL_BUDGETS {
+            let (_, fuel) = runtime.block_on(execute_operations(
+                bytecode.clone(),
+                operation(),
+                fuel_budget,
+                1,
+            ));
+            group.throughput(Throughput::Elements(fuel));
+
+            group.bench_with_input(
+                BenchmarkId::new(*name, fuel_budget),
+                &fuel_budget,
+                |bencher, &fuel_budget| {
+                    bencher.to_async(&runtime).iter_custom(|iterations| {
+                        let bytecode = bytecode.clone();
+                        async move {
+                            execute_operations(bytecode, operation(), fuel_budget, iterations)
+                                .await
+                                .0
+                        }
+                    })
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_contract_instantiation, bench_contract_fuel);
+criterion_main!(benches);
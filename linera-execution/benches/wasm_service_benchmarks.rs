@@ -0,0 +1,108 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compares the latency of service queries when instantiating the module for every query with
+//! the latency when reusing pooled instances.
+
+use std::time::{Duration, Instant};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use linera_base::{
+    data_types::{BlockHeight, Timestamp},
+    identifiers::{ChainDescription, ChainId, UserApplicationId},
+};
+use linera_execution::{
+    test_utils::{create_dummy_user_application_description, SystemExecutionState},
+    ExecutionRuntimeConfig, ExecutionRuntimeContext, ExecutionStateView, Query, QueryContext,
+    TestExecutionRuntimeContext, WasmRuntime, WasmServiceModule,
+};
+use linera_views::{context::Context as _, memory::MemoryContext};
+use tokio::runtime::Runtime;
+
+/// The number of idle instances kept by the pooled service.
+const POOL_CAPACITY: usize = 4;
+
+/// Creates an execution state with the counter application, using the given `service`.
+async fn setup_counter_application(
+    service: WasmServiceModule,
+) -> (
+    ExecutionStateView<MemoryContext<TestExecutionRuntimeContext>>,
+    UserApplicationId,
+) {
+    let state = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        ..Default::default()
+    };
+    let mut view = state
+        .into_view_with(ChainId::root(0), ExecutionRuntimeConfig::default())
+        .await;
+    let (app_desc, contract_blob, service_blob) = create_dummy_user_application_description(1);
+    let app_id = view
+        .system
+        .registry
+        .register_application(app_desc)
+        .await
+        .unwrap();
+
+    view.context()
+        .extra()
+        .user_services()
+        .insert(app_id, service.into());
+    view.context()
+        .extra()
+        .add_blobs([contract_blob, service_blob])
+        .await
+        .unwrap();
+
+    (view, app_id)
+}
+
+/// Runs `iterations` queries of the counter application, instantiating the service with the
+/// provided `service` module.
+async fn query_counter(service: WasmServiceModule, iterations: u64) -> Duration {
+    let (mut view, app_id) = setup_counter_application(service).await;
+    let context = QueryContext {
+        chain_id: ChainId::root(0),
+        next_block_height: BlockHeight(0),
+        local_time: Timestamp::from(0),
+    };
+    let request = async_graphql::Request::new("query { value }");
+    let query = Query::user_without_abi(app_id, &request).unwrap();
+
+    let mut total_time = Duration::ZERO;
+    for _ in 0..iterations {
+        let query = query.clone();
+        let measurement = Instant::now();
+        black_box(view.query_application(context, query, None).await.unwrap());
+        total_time += measurement.elapsed();
+    }
+
+    total_time
+}
+
+fn bench_service_instantiation(criterion: &mut Criterion) {
+    let runtime = Runtime::new().expect("Failed to create Tokio runtime");
+    let service = runtime
+        .block_on(WasmServiceModule::from_file(
+            "tests/fixtures/counter_service.wasm",
+            WasmRuntime::Wasmtime,
+        ))
+        .expect("Failed to load the counter service");
+
+    criterion.bench_function("fresh_service_instance_query", |bencher| {
+        let service = service.clone().with_instance_pool_capacity(0);
+        bencher
+            .to_async(&runtime)
+            .iter_custom(|iterations| query_counter(service.clone(), iterations))
+    });
+
+    criterion.bench_function("pooled_service_instance_query", |bencher| {
+        let service = service.clone().with_instance_pool_capacity(POOL_CAPACITY);
+        bencher
+            .to_async(&runtime)
+            .iter_custom(|iterations| query_counter(service.clone(), iterations))
+    });
+}
+
+criterion_group!(benches, bench_service_instantiation);
+criterion_main!(benches);
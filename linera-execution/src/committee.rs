@@ -2,6 +2,10 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(test)]
+#[path = "./unit_tests/committee_tests.rs"]
+mod tests;
+
 use std::{borrow::Cow, collections::BTreeMap, str::FromStr};
 
 use async_graphql::InputObject;
@@ -10,6 +14,7 @@ use linera_base::{
     data_types::ArithmeticError,
 };
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::policy::ResourceControlPolicy;
 
@@ -94,11 +99,27 @@ impl<'de> Deserialize<'de> for ValidatorName {
 #[derive(Eq, PartialEq, Hash, Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ValidatorState {
     /// The network address (in a string format understood by the networking layer).
+    /// Committees serialized before addresses were recorded default to an empty string.
+    #[serde(default)]
     pub network_address: String,
     /// The voting power.
     pub votes: u64,
 }
 
+/// An error that makes a set of validators unusable as a committee.
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+pub enum CommitteeError {
+    #[error("A committee must have at least one validator")]
+    NoValidators,
+    #[error("Validator {0} has no voting rights")]
+    ZeroVotes(ValidatorName),
+    #[error(
+        "The total voting rights must not exceed {}",
+        Committee::MAXIMUM_TOTAL_VOTES
+    )]
+    TooManyVotes,
+}
+
 /// A set of validators (identified by their public keys) and their voting rights.
 #[derive(Eq, PartialEq, Hash, Clone, Debug, Default, InputObject)]
 pub struct Committee {
@@ -170,7 +191,8 @@ impl TryFrom<CommitteeFull<'static>> for Committee {
             validity_threshold,
             policy,
         } = committee_full;
-        let committee = Committee::new(validators.into_owned(), policy.into_owned());
+        let committee = Committee::new(validators.into_owned(), policy.into_owned())
+            .map_err(|error| format!("invalid committee: {error}"))?;
         if total_votes != committee.total_votes {
             Err(format!(
                 "invalid committee: total_votes is {}; should be {}",
@@ -214,7 +236,7 @@ impl<'a> From<&'a Committee> for CommitteeFull<'a> {
 impl From<CommitteeMinimal<'static>> for Committee {
     fn from(committee_min: CommitteeMinimal) -> Committee {
         let CommitteeMinimal { validators, policy } = committee_min;
-        Committee::new(validators.into_owned(), policy.into_owned())
+        Committee::with_thresholds(validators.into_owned(), policy.into_owned())
     }
 }
 
@@ -289,11 +311,43 @@ impl Epoch {
 }
 
 impl Committee {
+    /// The maximum sum of all voting rights. This guarantees that the thresholds can be
+    /// computed without overflowing.
+    pub const MAXIMUM_TOTAL_VOTES: u64 = u64::MAX / 3;
+
+    /// Creates a new committee, checking that it has at least one validator, that every
+    /// validator has some voting rights and that the total is at most
+    /// [`Committee::MAXIMUM_TOTAL_VOTES`].
     pub fn new(
         validators: BTreeMap<ValidatorName, ValidatorState>,
         policy: ResourceControlPolicy,
+    ) -> Result<Self, CommitteeError> {
+        if validators.is_empty() {
+            return Err(CommitteeError::NoValidators);
+        }
+        let mut total_votes = 0u64;
+        for (name, state) in &validators {
+            if state.votes == 0 {
+                return Err(CommitteeError::ZeroVotes(*name));
+            }
+            total_votes = total_votes
+                .checked_add(state.votes)
+                .filter(|total| *total <= Self::MAXIMUM_TOTAL_VOTES)
+                .ok_or(CommitteeError::TooManyVotes)?;
+        }
+        Ok(Self::with_thresholds(validators, policy))
+    }
+
+    /// Creates a committee without checking the voting rights. This is used when
+    /// deserializing, so that committees that are already stored can always be read.
+    fn with_thresholds(
+        validators: BTreeMap<ValidatorName, ValidatorState>,
+        policy: ResourceControlPolicy,
     ) -> Self {
-        let total_votes = validators.values().fold(0, |sum, state| sum + state.votes);
+        let total_votes = validators
+            .values()
+            .fold(0u64, |sum, state| sum.saturating_add(state.votes))
+            .min(Self::MAXIMUM_TOTAL_VOTES);
         // Let N = 3f + 1 + k such that 0 <= k <= 2. (Notably ⌊k / 3⌋ = 0 and ⌊(2 - k) / 3⌋ = 0.)
         // The following thresholds verify:
         // * ⌊2 N / 3⌋ + 1 = ⌊(6f + 2 + 2k) / 3⌋ + 1 = 2f + 1 + k + ⌊(2 - k) / 3⌋ = N - f
@@ -325,6 +379,7 @@ impl Committee {
             })
             .collect();
         Committee::new(map, ResourceControlPolicy::default())
+            .expect("a committee of validators with one vote each should be valid")
     }
 
     pub fn weight(&self, author: &ValidatorName) -> u64 {
@@ -354,6 +409,32 @@ impl Committee {
         self.validity_threshold
     }
 
+    /// Returns the highest value that validators with at least the validity threshold report
+    /// reaching, i.e. a value that at least one honest validator reached, or `None` if the
+    /// reporting validators are below the validity threshold.
+    ///
+    /// Only the last report of each validator is counted.
+    pub fn highest_value_with_validity<T: Ord>(
+        &self,
+        reports: impl IntoIterator<Item = (ValidatorName, T)>,
+    ) -> Option<T> {
+        let mut reports = reports
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(name, value)| (value, self.weight(&name)))
+            .collect::<Vec<_>>();
+        reports.sort_by(|(value1, _), (value2, _)| value2.cmp(value1));
+        let mut weight = 0u64;
+        for (value, votes) in reports {
+            weight = weight.saturating_add(votes);
+            if weight >= self.validity_threshold {
+                return Some(value);
+            }
+        }
+        None
+    }
+
     pub fn validators(&self) -> &BTreeMap<ValidatorName, ValidatorState> {
         &self.validators
     }
@@ -368,6 +449,14 @@ impl Committee {
         self.total_votes
     }
 
+    /// Returns a validator whose votes alone reach the quorum threshold, if any.
+    pub fn dominant_validator(&self) -> Option<ValidatorName> {
+        self.validators
+            .iter()
+            .find(|(_, state)| state.votes >= self.quorum_threshold)
+            .map(|(name, _)| *name)
+    }
+
     pub fn policy(&self) -> &ResourceControlPolicy {
         &self.policy
     }
@@ -8,6 +8,7 @@ use std::{
 
 use futures::{stream::FuturesOrdered, FutureExt, StreamExt, TryStreamExt};
 use linera_base::{
+    balance_proof::BalancePath,
     data_types::{Amount, BlockHeight, Timestamp},
     identifiers::{Account, AccountOwner, ChainId, Destination, Owner},
 };
@@ -15,14 +16,12 @@ use linera_views::{
     context::Context,
     key_value_store_view::KeyValueStoreView,
     reentrant_collection_view::HashedReentrantCollectionView,
-    views::{ClonableView, View},
+    views::{ClonableView, HashableView as _, View, ViewError},
 };
 use linera_views_derive::CryptoHashView;
 #[cfg(with_testing)]
 use {
-    crate::{
-        ResourceControlPolicy, ResourceTracker, TestExecutionRuntimeContext, UserContractCode,
-    },
+    crate::{ResourceTracker, TestExecutionRuntimeContext, UserContractCode},
     linera_base::data_types::Blob,
     linera_views::context::MemoryContext,
     std::sync::Arc,
@@ -33,8 +32,8 @@ use crate::{
     resources::ResourceController, system::SystemExecutionStateView, ContractSyncRuntime,
     ExecutionError, ExecutionOutcome, ExecutionRuntimeConfig, ExecutionRuntimeContext, Message,
     MessageContext, MessageKind, Operation, OperationContext, Query, QueryContext, QueryOutcome,
-    RawExecutionOutcome, RawOutgoingMessage, ServiceSyncRuntime, SystemMessage, TransactionTracker,
-    UserApplicationDescription, UserApplicationId,
+    RawExecutionOutcome, RawOutgoingMessage, ResourceControlPolicy, ServiceSyncRuntime,
+    SystemMessage, TransactionTracker, UserApplicationDescription, UserApplicationId,
 };
 
 /// A view accessing the execution state of a chain.
@@ -164,6 +163,26 @@ where
     C: Context + Clone + Send + Sync + 'static,
     C::Extra: ExecutionRuntimeContext,
 {
+    /// Returns the chain balance, with the hashes needed to check it against the hash of this
+    /// execution state.
+    pub async fn balance_path(&self) -> Result<BalancePath, ViewError> {
+        let other_system_field_hashes = self
+            .system
+            .field_hashes()
+            .await?
+            .into_iter()
+            .filter(|(name, _)| *name != "balance")
+            .map(|(_, hash)| {
+                <[u8; 32]>::try_from(hash.as_slice()).expect("SHA3-256 hashes have 32 bytes")
+            })
+            .collect();
+        Ok(BalancePath {
+            balance: *self.system.balance.get(),
+            other_system_field_hashes,
+            users_hash: self.users.hash().await?.into(),
+        })
+    }
+
     #[expect(clippy::too_many_arguments)]
     async fn run_user_action(
         &mut self,
@@ -496,19 +515,26 @@ where
                 bytes,
             } => {
                 let ExecutionRuntimeConfig {} = self.context().extra().execution_runtime_config();
+                let policy = self
+                    .system
+                    .current_committee()
+                    .map_or_else(ResourceControlPolicy::default, |(_, committee)| {
+                        committee.policy().clone()
+                    });
                 let outcome = match endpoint {
                     Some(endpoint) => {
                         self.query_user_application_with_long_lived_service(
                             application_id,
                             context,
                             bytes,
+                            policy,
                             &mut endpoint.incoming_execution_requests,
                             &mut endpoint.runtime_request_sender,
                         )
                         .await?
                     }
                     None => {
-                        self.query_user_application(application_id, context, bytes)
+                        self.query_user_application(application_id, context, bytes, policy)
                             .await?
                     }
                 };
@@ -522,6 +548,7 @@ where
         application_id: UserApplicationId,
         context: QueryContext,
         query: Vec<u8>,
+        policy: ResourceControlPolicy,
     ) -> Result<QueryOutcome<Vec<u8>>, ExecutionError> {
         let (execution_state_sender, mut execution_state_receiver) =
             futures::channel::mpsc::unbounded();
@@ -533,7 +560,7 @@ where
             async move {
                 let code = codes.next().await.expect("we send this immediately below");
                 runtime.preload_service(application_id, code, description)?;
-                runtime.run_query(application_id, query)
+                runtime.run_query(application_id, query, &policy)
             }
         })
         .await;
@@ -552,6 +579,7 @@ where
         application_id: UserApplicationId,
         context: QueryContext,
         query: Vec<u8>,
+        policy: ResourceControlPolicy,
         incoming_execution_requests: &mut futures::channel::mpsc::UnboundedReceiver<
             ExecutionRequest,
         >,
@@ -565,6 +593,7 @@ where
                 application_id,
                 context,
                 query,
+                policy,
                 callback: outcome_sender,
             })
             .expect("Service runtime thread should only stop when `request_sender` is dropped");
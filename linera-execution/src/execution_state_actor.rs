@@ -3,6 +3,7 @@
 
 //! Handle requests from the synchronous execution thread of user applications.
 
+use std::collections::BTreeMap;
 #[cfg(with_metrics)]
 use std::sync::LazyLock;
 
@@ -127,6 +128,7 @@ where
                 amount,
                 signer,
                 application_id,
+                mut owner_spending,
                 callback,
             } => {
                 let mut execution_outcome = RawExecutionOutcome::default();
@@ -138,13 +140,14 @@ where
                         source,
                         Recipient::Account(destination),
                         amount,
+                        &mut owner_spending,
                     )
                     .await?;
 
                 if let Some(message) = message {
                     execution_outcome.messages.push(message);
                 }
-                callback.respond(execution_outcome);
+                callback.respond((execution_outcome, owner_spending));
             }
 
             Claim {
@@ -260,6 +263,8 @@ where
                 balance,
                 next_message_id,
                 application_permissions,
+                signer,
+                mut owner_spending,
                 callback,
             } => {
                 let inactive_err = || SystemExecutionError::InactiveChain;
@@ -271,8 +276,11 @@ where
                     balance,
                     application_permissions,
                 };
-                let messages = self.system.open_chain(config, next_message_id).await?;
-                callback.respond(messages)
+                let messages = self
+                    .system
+                    .open_chain(config, signer, next_message_id, &mut owner_spending)
+                    .await?;
+                callback.respond((messages, owner_spending))
             }
 
             CloseChain {
@@ -408,8 +416,13 @@ pub enum ExecutionRequest {
         #[debug(skip_if = Option::is_none)]
         signer: Option<Owner>,
         application_id: UserApplicationId,
+        #[debug(skip_if = BTreeMap::is_empty)]
+        owner_spending: BTreeMap<Owner, Amount>,
         #[debug(skip)]
-        callback: Sender<RawExecutionOutcome<SystemMessage, Amount>>,
+        callback: Sender<(
+            RawExecutionOutcome<SystemMessage, Amount>,
+            BTreeMap<Owner, Amount>,
+        )>,
     },
 
     Claim {
@@ -492,8 +505,15 @@ pub enum ExecutionRequest {
         balance: Amount,
         next_message_id: MessageId,
         application_permissions: ApplicationPermissions,
+        #[debug(skip_if = Option::is_none)]
+        signer: Option<Owner>,
+        #[debug(skip_if = BTreeMap::is_empty)]
+        owner_spending: BTreeMap<Owner, Amount>,
         #[debug(skip)]
-        callback: Sender<[RawOutgoingMessage<SystemMessage, Amount>; 2]>,
+        callback: Sender<(
+            [RawOutgoingMessage<SystemMessage, Amount>; 2],
+            BTreeMap<Owner, Amount>,
+        )>,
     },
 
     CloseChain {
@@ -545,3 +565,39 @@ pub enum ExecutionRequest {
         callback: Sender<bool>,
     },
 }
+
+impl ExecutionRequest {
+    /// Returns `true` if the request only reads the execution state, without changing it or
+    /// having effects outside of it.
+    #[cfg(with_wasmtime)]
+    pub(crate) fn is_read_only(&self) -> bool {
+        match self {
+            #[cfg(not(web))]
+            ExecutionRequest::LoadContract { .. } | ExecutionRequest::LoadService { .. } => true,
+            ExecutionRequest::ChainBalance { .. }
+            | ExecutionRequest::OwnerBalance { .. }
+            | ExecutionRequest::OwnerBalances { .. }
+            | ExecutionRequest::BalanceOwners { .. }
+            | ExecutionRequest::SystemTimestamp { .. }
+            | ExecutionRequest::ChainOwnership { .. }
+            | ExecutionRequest::ReadValueBytes { .. }
+            | ExecutionRequest::ContainsKey { .. }
+            | ExecutionRequest::ContainsKeys { .. }
+            | ExecutionRequest::ReadMultiValuesBytes { .. }
+            | ExecutionRequest::FindKeysByPrefix { .. }
+            | ExecutionRequest::FindKeyValuesByPrefix { .. } => true,
+            // Reading blobs records them as used by the block.
+            ExecutionRequest::ReadBlobContent { .. }
+            | ExecutionRequest::AssertBlobExists { .. }
+            | ExecutionRequest::Transfer { .. }
+            | ExecutionRequest::Claim { .. }
+            | ExecutionRequest::WriteBatch { .. }
+            | ExecutionRequest::OpenChain { .. }
+            | ExecutionRequest::CloseChain { .. }
+            | ExecutionRequest::ChangeApplicationPermissions { .. }
+            | ExecutionRequest::CreateApplication { .. }
+            | ExecutionRequest::FetchUrl { .. }
+            | ExecutionRequest::HttpPost { .. } => false,
+        }
+    }
+}
@@ -57,10 +57,17 @@ pub use crate::applications::ApplicationRegistry;
 use crate::runtime::ContractSyncRuntime;
 #[cfg(all(with_testing, with_wasm_runtime))]
 pub use crate::wasm::test as wasm_test;
+#[cfg(all(with_wasmtime, with_testing))]
+pub use crate::wasm::FuelTracingContractModule;
 #[cfg(with_wasm_runtime)]
 pub use crate::wasm::{
-    ContractEntrypoints, ContractSystemApi, ServiceEntrypoints, ServiceSystemApi, SystemApiData,
-    ViewSystemApi, WasmContractModule, WasmExecutionError, WasmServiceModule,
+    BytecodeKey, ContractEntrypoints, ContractSystemApi, ModuleCache, ServiceEntrypoints,
+    ServiceSystemApi, SystemApiData, ViewSystemApi, WasmContractModule, WasmExecutionError,
+    WasmServiceModule,
+};
+#[cfg(with_wasmtime)]
+pub use crate::wasm::{
+    EngineProfile, FuelTrace, FuelTraceEntry, ResourceLimits, WasmRuntimeConfig,
 };
 pub use crate::{
     applications::ApplicationRegistryView,
@@ -229,6 +236,8 @@ pub enum ExecutionError {
     ExcessiveWrite,
     #[error("Block execution required too much fuel")]
     MaximumFuelExceeded,
+    #[error("Wasm module has {remaining} fuel left, more than the {initial} it started with")]
+    FuelAccounting { initial: u64, remaining: u64 },
     #[error("Serialized size of the executed block exceeds limit")]
     ExecutedBlockTooLarge,
     #[error("Runtime failed to respond to application")]
@@ -382,6 +391,12 @@ pub trait ExecutionRuntimeContext {
 
     async fn contains_blob(&self, blob_id: BlobId) -> Result<bool, ViewError>;
 
+    /// Returns the ID of the chain opened by the message with the given ID. This is
+    /// [`ChainId::child`] except where tests override it.
+    fn child_chain_id(&self, message_id: MessageId) -> ChainId {
+        ChainId::child(message_id)
+    }
+
     #[cfg(with_testing)]
     async fn add_blobs(
         &self,
@@ -389,6 +404,27 @@ pub trait ExecutionRuntimeContext {
     ) -> Result<(), ViewError>;
 }
 
+/// Derives the ID of a new chain from the ID of the message that opens it.
+///
+/// Only the execution layer derives child chain IDs; everyone else reads them from the
+/// destination of the [`SystemMessage::OpenChain`] message. Tests can therefore replace the
+/// derivation with [`TestExecutionRuntimeContext::set_child_id_deriver`], or for all chains
+/// in a storage with `DbStorage::with_child_id_deriver`. The ID of a chain with the
+/// description `ChainDescription::Child(message_id)` is then the deriver's `child_id`, not
+/// the ID converted from the description: use `Storage::chain_id_of` to look it up.
+pub trait ChildIdDeriver: Send + Sync {
+    fn child_id(&self, message_id: MessageId) -> ChainId;
+}
+
+impl<F> ChildIdDeriver for F
+where
+    F: Fn(MessageId) -> ChainId + Send + Sync,
+{
+    fn child_id(&self, message_id: MessageId) -> ChainId {
+        self(message_id)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct OperationContext {
     /// The current chain ID.
@@ -633,6 +669,9 @@ pub trait BaseRuntime {
 
     /// Asserts the existence of a data blob with the given hash.
     fn assert_data_blob_exists(&mut self, hash: &CryptoHash) -> Result<(), ExecutionError>;
+
+    /// Returns the resource control policy that limits the current execution.
+    fn resource_control_policy(&mut self) -> Result<Arc<ResourceControlPolicy>, ExecutionError>;
 }
 
 pub trait ServiceRuntime: BaseRuntime {
@@ -648,6 +687,12 @@ pub trait ServiceRuntime: BaseRuntime {
 
     /// Schedules an operation to be included in the block proposed after execution.
     fn schedule_operation(&mut self, operation: Vec<u8>) -> Result<(), ExecutionError>;
+
+    /// Returns the amount of fuel remaining before the query is aborted.
+    fn remaining_fuel(&mut self) -> Result<u64, ExecutionError>;
+
+    /// Consumes some of the fuel of the query.
+    fn consume_fuel(&mut self, fuel: u64) -> Result<(), ExecutionError>;
 }
 
 pub trait ContractRuntime: BaseRuntime {
@@ -1057,6 +1102,7 @@ pub struct TestExecutionRuntimeContext {
     user_contracts: Arc<DashMap<UserApplicationId, UserContractCode>>,
     user_services: Arc<DashMap<UserApplicationId, UserServiceCode>>,
     blobs: Arc<DashMap<BlobId, Blob>>,
+    child_id_deriver: Arc<std::sync::OnceLock<Box<dyn ChildIdDeriver>>>,
 }
 
 #[cfg(with_testing)]
@@ -1068,8 +1114,21 @@ impl TestExecutionRuntimeContext {
             user_contracts: Arc::default(),
             user_services: Arc::default(),
             blobs: Arc::default(),
+            child_id_deriver: Arc::default(),
         }
     }
+
+    /// Makes new chains get their IDs from `deriver` instead of [`ChainId::child`].
+    ///
+    /// # Panics
+    ///
+    /// If a deriver was already set.
+    pub fn set_child_id_deriver(&self, deriver: impl ChildIdDeriver + 'static) {
+        assert!(
+            self.child_id_deriver.set(Box::new(deriver)).is_ok(),
+            "The child ID deriver can only be set once"
+        );
+    }
 }
 
 #[cfg(with_testing)]
@@ -1132,6 +1191,13 @@ impl ExecutionRuntimeContext for TestExecutionRuntimeContext {
         Ok(self.blobs.contains_key(&blob_id))
     }
 
+    fn child_chain_id(&self, message_id: MessageId) -> ChainId {
+        match self.child_id_deriver.get() {
+            Some(deriver) => deriver.child_id(message_id),
+            None => ChainId::child(message_id),
+        }
+    }
+
     #[cfg(with_testing)]
     async fn add_blobs(
         &self,
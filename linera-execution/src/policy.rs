@@ -13,7 +13,16 @@ use linera_base::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::ExecutionError;
+use crate::{runtime::SERVICE_QUERY_FUEL, ExecutionError};
+
+/// The default maximum size of the linear memory of a Wasm contract or service: 256 MiB.
+const DEFAULT_MAXIMUM_WASM_MEMORY_BYTES: u64 = 256 * 1024 * 1024;
+
+/// The default maximum number of elements in a table of a Wasm contract or service.
+const DEFAULT_MAXIMUM_WASM_TABLE_ELEMENTS: u64 = 100_000;
+
+/// The default maximum number of Wasm instances a contract or service can create.
+const DEFAULT_MAXIMUM_WASM_INSTANCES: u64 = 10;
 
 /// A collection of prices and limits associated with block execution.
 #[derive(Eq, PartialEq, Hash, Clone, Debug, Serialize, Deserialize, InputObject)]
@@ -61,6 +70,15 @@ pub struct ResourceControlPolicy {
     pub maximum_bytes_read_per_block: u64,
     /// The maximum data to write per block
     pub maximum_bytes_written_per_block: u64,
+    /// The maximum amount of fuel a service can consume to answer a query, including the
+    /// services it queries in turn.
+    pub maximum_service_fuel_per_query: u64,
+    /// The maximum size of the linear memory of a Wasm contract or service, in bytes.
+    pub maximum_wasm_memory_bytes: u64,
+    /// The maximum number of elements in a table of a Wasm contract or service.
+    pub maximum_wasm_table_elements: u64,
+    /// The maximum number of Wasm instances a contract or service can create.
+    pub maximum_wasm_instances: u64,
 }
 
 impl fmt::Display for ResourceControlPolicy {
@@ -85,6 +103,10 @@ impl fmt::Display for ResourceControlPolicy {
             maximum_block_proposal_size,
             maximum_bytes_read_per_block,
             maximum_bytes_written_per_block,
+            maximum_service_fuel_per_query,
+            maximum_wasm_memory_bytes,
+            maximum_wasm_table_elements,
+            maximum_wasm_instances,
         } = self;
         write!(
             f,
@@ -107,7 +129,11 @@ impl fmt::Display for ResourceControlPolicy {
             {maximum_bytecode_size} maximum size of service and contract bytecode\n\
             {maximum_block_proposal_size} maximum size of a block proposal\n\
             {maximum_bytes_read_per_block} maximum number bytes read per block\n\
-            {maximum_bytes_written_per_block} maximum number bytes written per block",
+            {maximum_bytes_written_per_block} maximum number bytes written per block\n\
+            {maximum_service_fuel_per_query} maximum fuel per service query\n\
+            {maximum_wasm_memory_bytes} maximum bytes of Wasm memory\n\
+            {maximum_wasm_table_elements} maximum elements in a Wasm table\n\
+            {maximum_wasm_instances} maximum number of Wasm instances",
         )
     }
 }
@@ -134,6 +160,12 @@ impl Default for ResourceControlPolicy {
             maximum_block_proposal_size: u64::MAX,
             maximum_bytes_read_per_block: u64::MAX,
             maximum_bytes_written_per_block: u64::MAX,
+            // Queries are not paid for, so they are always limited.
+            maximum_service_fuel_per_query: SERVICE_QUERY_FUEL,
+            // Wasm instances use the validators' memory, so they are always limited, too.
+            maximum_wasm_memory_bytes: DEFAULT_MAXIMUM_WASM_MEMORY_BYTES,
+            maximum_wasm_table_elements: DEFAULT_MAXIMUM_WASM_TABLE_ELEMENTS,
+            maximum_wasm_instances: DEFAULT_MAXIMUM_WASM_INSTANCES,
         }
     }
 }
@@ -216,6 +248,19 @@ impl ResourceControlPolicy {
         }
         Ok(())
     }
+
+    /// Returns the policy for service queries. Queries are not paid for, so only their fuel
+    /// budget and the limits of Wasm instances apply.
+    pub fn for_queries(&self) -> Self {
+        Self {
+            maximum_fuel_per_block: self.maximum_service_fuel_per_query,
+            maximum_service_fuel_per_query: self.maximum_service_fuel_per_query,
+            maximum_wasm_memory_bytes: self.maximum_wasm_memory_bytes,
+            maximum_wasm_table_elements: self.maximum_wasm_table_elements,
+            maximum_wasm_instances: self.maximum_wasm_instances,
+            ..Self::default()
+        }
+    }
 }
 
 impl ResourceControlPolicy {
@@ -279,6 +324,10 @@ impl ResourceControlPolicy {
             maximum_block_proposal_size: 13_000_000,
             maximum_bytes_read_per_block: 100_000_000,
             maximum_bytes_written_per_block: 10_000_000,
+            maximum_service_fuel_per_query: 100_000_000,
+            maximum_wasm_memory_bytes: DEFAULT_MAXIMUM_WASM_MEMORY_BYTES,
+            maximum_wasm_table_elements: DEFAULT_MAXIMUM_WASM_TABLE_ELEMENTS,
+            maximum_wasm_instances: DEFAULT_MAXIMUM_WASM_INSTANCES,
         }
     }
 }
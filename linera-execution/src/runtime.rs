@@ -17,8 +17,8 @@ use linera_base::{
     },
     ensure,
     identifiers::{
-        Account, AccountOwner, ApplicationId, BlobId, BlobType, ChainId, ChannelName, MessageId,
-        Owner, StreamName,
+        Account, AccountOwner, ApplicationId, BlobId, BlobType, ChainId, ChannelName, Destination,
+        MessageId, Owner, StreamName,
     },
     ownership::ChainOwnership,
 };
@@ -28,14 +28,14 @@ use oneshot::Receiver;
 use crate::{
     execution::UserAction,
     execution_state_actor::{ExecutionRequest, ExecutionStateSender},
-    resources::ResourceController,
+    resources::{ResourceController, ResourceTracker},
     system::CreateApplicationResult,
     util::{ReceiverExt, UnboundedSenderExt},
     BaseRuntime, BytecodeId, ContractRuntime, ExecutionError, FinalizeContext, MessageContext,
-    Operation, OperationContext, QueryContext, QueryOutcome, RawExecutionOutcome, ServiceRuntime,
-    TransactionTracker, UserApplicationDescription, UserApplicationId, UserContractCode,
-    UserContractInstance, UserServiceCode, UserServiceInstance, MAX_EVENT_KEY_LEN,
-    MAX_STREAM_NAME_LEN,
+    Operation, OperationContext, QueryContext, QueryOutcome, RawExecutionOutcome,
+    ResourceControlPolicy, ServiceRuntime, TransactionTracker, UserApplicationDescription,
+    UserApplicationId, UserContractCode, UserContractInstance, UserServiceCode,
+    UserServiceInstance, MAX_EVENT_KEY_LEN, MAX_STREAM_NAME_LEN,
 };
 
 #[cfg(test)]
@@ -52,6 +52,12 @@ pub struct ServiceSyncRuntime {
     current_context: QueryContext,
 }
 
+/// The fuel available to each query by default, shared with the services it queries in turn.
+///
+/// The committees set the actual budget with
+/// [`ResourceControlPolicy::maximum_service_fuel_per_query`].
+pub const SERVICE_QUERY_FUEL: u64 = 100_000_000;
+
 #[derive(Debug)]
 pub struct SyncRuntimeHandle<UserInstance>(Arc<Mutex<SyncRuntimeInternal<UserInstance>>>);
 
@@ -725,6 +731,10 @@ impl<UserInstance> BaseRuntime for SyncRuntimeHandle<UserInstance> {
     fn assert_data_blob_exists(&mut self, hash: &CryptoHash) -> Result<(), ExecutionError> {
         self.inner().assert_data_blob_exists(hash)
     }
+
+    fn resource_control_policy(&mut self) -> Result<Arc<ResourceControlPolicy>, ExecutionError> {
+        self.inner().resource_control_policy()
+    }
 }
 
 impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
@@ -976,11 +986,16 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
                     local_time: self.local_time,
                 };
                 let sender = self.execution_state_sender.clone();
+                let policy = self.resource_controller.policy.clone();
 
                 let QueryOutcome {
                     response,
                     operations,
-                } = ServiceSyncRuntime::new(sender, context).run_query(application_id, query)?;
+                } = ServiceSyncRuntime::new(sender, context).run_query(
+                    application_id,
+                    query,
+                    &policy,
+                )?;
 
                 self.scheduled_operations.extend(operations);
                 response
@@ -1064,6 +1079,10 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
         }
         Ok(())
     }
+
+    fn resource_control_policy(&mut self) -> Result<Arc<ResourceControlPolicy>, ExecutionError> {
+        Ok(self.resource_controller.policy.clone())
+    }
 }
 
 impl<UserInstance> Clone for SyncRuntimeHandle<UserInstance> {
@@ -1143,6 +1162,50 @@ impl ContractSyncRuntime {
 }
 
 impl ContractSyncRuntimeHandle {
+    /// Creates a separate runtime to execute the current application again, in order to
+    /// diagnose a trap without affecting this runtime.
+    ///
+    /// The new runtime has the same context and a copy of the resource controller, and only the
+    /// current application in its call stack. It can only read the execution state: the requests
+    /// that would change it, or that have effects outside of it, fail. Oracle responses are not
+    /// replayed, and the outcomes of the new runtime are discarded with it.
+    #[cfg(with_wasmtime)]
+    pub(crate) fn fork_for_diagnostics(&self) -> ContractSyncRuntime {
+        let runtime = self.inner();
+        let (execution_state_sender, requests) = futures::channel::mpsc::unbounded();
+        let live_sender = runtime.execution_state_sender.clone();
+        std::thread::spawn(move || {
+            // Dropping the other requests drops their callbacks, so they fail in the fork.
+            for request in futures::executor::block_on_stream(requests) {
+                if request.is_read_only() && live_sender.unbounded_send(request).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut fork = SyncRuntimeInternal::new(
+            runtime.chain_id,
+            runtime.height,
+            runtime.round,
+            runtime.local_time,
+            runtime.authenticated_signer,
+            runtime.executing_message,
+            execution_state_sender,
+            None,
+            runtime.resource_controller.clone(),
+            TransactionTracker::default(),
+        );
+        if let Some(application) = runtime.call_stack.last() {
+            fork.push_application(ApplicationStatus {
+                caller_id: application.caller_id,
+                id: application.id,
+                parameters: application.parameters.clone(),
+                signer: application.signer,
+                outcome: RawExecutionOutcome::default(),
+            });
+        }
+        SyncRuntime(Some(SyncRuntimeHandle::from(fork)))
+    }
+
     fn run_action(
         &mut self,
         application_id: UserApplicationId,
@@ -1306,8 +1369,9 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         let current_application = this.current_application();
         let application_id = current_application.id;
         let signer = current_application.signer;
+        let owner_spending = mem::take(this.transaction_tracker.owner_spending_mut());
 
-        let execution_outcome = this
+        let (execution_outcome, owner_spending) = this
             .execution_state_sender
             .send_request(|callback| ExecutionRequest::Transfer {
                 source,
@@ -1315,10 +1379,12 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
                 amount,
                 signer,
                 application_id,
+                owner_spending,
                 callback,
             })?
             .recv_response()?;
 
+        *this.transaction_tracker.owner_spending_mut() = owner_spending;
         this.transaction_tracker
             .add_system_outcome(execution_outcome)?;
         Ok(())
@@ -1404,17 +1470,25 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
             height: this.height,
             index: this.transaction_tracker.next_message_index(),
         };
-        let chain_id = ChainId::child(message_id);
-        let [open_chain_message, subscribe_message] = this
+        let owner_spending = mem::take(this.transaction_tracker.owner_spending_mut());
+        let ([open_chain_message, subscribe_message], owner_spending) = this
             .execution_state_sender
             .send_request(|callback| ExecutionRequest::OpenChain {
                 ownership,
                 balance,
                 next_message_id: message_id,
                 application_permissions,
+                // The balance counts towards the transaction signer's limit, even if the
+                // application wasn't called with the signer's authentication.
+                signer: this.authenticated_signer,
+                owner_spending,
                 callback,
             })?
             .recv_response()?;
+        *this.transaction_tracker.owner_spending_mut() = owner_spending;
+        let Destination::Recipient(chain_id) = open_chain_message.destination else {
+            unreachable!("The `OpenChain` message is sent directly to the new chain");
+        };
         let outcome = RawExecutionOutcome::default()
             .with_message(open_chain_message)
             .with_message(subscribe_message);
@@ -1555,7 +1629,10 @@ impl ServiceSyncRuntime {
                 None,
                 execution_state_sender,
                 None,
-                ResourceController::default(),
+                ResourceController {
+                    policy: Arc::new(ResourceControlPolicy::default().for_queries()),
+                    ..ResourceController::default()
+                },
                 TransactionTracker::default(),
             )
             .into(),
@@ -1600,12 +1677,13 @@ impl ServiceSyncRuntime {
                 application_id,
                 context,
                 query,
+                policy,
                 callback,
             } = request;
 
             self.prepare_for_query(context);
 
-            let _ = callback.send(self.run_query(application_id, query));
+            let _ = callback.send(self.run_query(application_id, query, &policy));
         }
     }
 
@@ -1624,13 +1702,23 @@ impl ServiceSyncRuntime {
         }
     }
 
-    /// Queries an application specified by its [`UserApplicationId`].
+    /// Queries an application specified by its [`UserApplicationId`], with the limits that
+    /// `policy` sets for queries. See [`ResourceControlPolicy::for_queries`].
     pub(crate) fn run_query(
         &mut self,
         application_id: UserApplicationId,
         query: Vec<u8>,
+        policy: &ResourceControlPolicy,
     ) -> Result<QueryOutcome<Vec<u8>>, ExecutionError> {
         let this = self.handle_mut();
+        {
+            let mut this = this.inner();
+            let query_policy = policy.for_queries();
+            if *this.resource_controller.policy != query_policy {
+                this.resource_controller.policy = Arc::new(query_policy);
+            }
+            this.resource_controller.tracker = ResourceTracker::default();
+        }
         let response = this.try_query_application(application_id, query)?;
         let operations = mem::take(&mut this.inner().scheduled_operations);
 
@@ -1703,6 +1791,15 @@ impl ServiceRuntime for ServiceSyncRuntimeHandle {
 
         Ok(())
     }
+
+    fn remaining_fuel(&mut self) -> Result<u64, ExecutionError> {
+        Ok(self.inner().resource_controller.remaining_fuel())
+    }
+
+    fn consume_fuel(&mut self, fuel: u64) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        this.resource_controller.track_fuel(fuel)
+    }
 }
 
 /// A request to the service runtime actor.
@@ -1711,6 +1808,8 @@ pub enum ServiceRuntimeRequest {
         application_id: UserApplicationId,
         context: QueryContext,
         query: Vec<u8>,
+        /// The policy of the chain, which sets the limits of the query.
+        policy: ResourceControlPolicy,
         callback: oneshot::Sender<Result<QueryOutcome<Vec<u8>>, ExecutionError>>,
     },
 }
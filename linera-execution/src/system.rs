@@ -9,7 +9,7 @@ mod tests;
 #[cfg(with_metrics)]
 use std::sync::LazyLock;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Display, Formatter},
     iter,
 };
@@ -19,7 +19,8 @@ use custom_debug_derive::Debug;
 use linera_base::{
     crypto::CryptoHash,
     data_types::{
-        Amount, ApplicationPermissions, ArithmeticError, BlobContent, OracleResponse, Timestamp,
+        Amount, ApplicationPermissions, ArithmeticError, BlobContent, BlockHeight, OracleResponse,
+        Timestamp,
     },
     ensure, hex_debug,
     identifiers::{
@@ -98,6 +99,10 @@ pub struct SystemExecutionStateView<C> {
     pub application_permissions: HashedRegisterView<C, ApplicationPermissions>,
     /// Blobs that have been used or published on this chain.
     pub used_blobs: HashedSetView<C, BlobId>,
+    /// The maximum amount that each limited owner can spend in a single block.
+    pub owner_limits: HashedRegisterView<C, BTreeMap<Owner, Amount>>,
+    /// The number of distinct owners that must sign each new block proposal, if more than one.
+    pub approval_threshold: HashedRegisterView<C, Option<u32>>,
 }
 
 /// The configuration for a new chain.
@@ -191,6 +196,24 @@ pub enum SystemOperation {
     },
     /// Operations that are only allowed on the admin chain.
     Admin(AdminOperation),
+    /// Claims `amount` units of value from the chain balance of the remote `target` chain.
+    /// The `target` chain only accepts the claim if `owner` is one of its owners.
+    ClaimChainBalance {
+        owner: Owner,
+        target_id: ChainId,
+        recipient: Recipient,
+        amount: Amount,
+    },
+    /// Fails unless the block's height is at least `not_before`, so that the rest of the
+    /// block can only be certified once the chain has reached that height.
+    Timelock { not_before: BlockHeight },
+    /// Replaces the maximum amounts that owners can spend in a single block, from the chain
+    /// balance or their own accounts. Owners that are not in `limits` are unlimited. Only
+    /// unlimited owners can change the limits or the chain ownership.
+    ChangeOwnerLimits { limits: BTreeMap<Owner, Amount> },
+    /// Sets the number of distinct owners that must sign each new block proposal. With `None`
+    /// or 1, the proposer's signature is enough.
+    ChangeApprovalThreshold { threshold: Option<u32> },
 }
 
 /// Operations that are only allowed on the admin chain.
@@ -252,6 +275,13 @@ pub enum SystemMessage {
     /// Requests a `RegisterApplication` message from the target chain to register the specified
     /// application on the sender chain.
     RequestApplication(UserApplicationId),
+    /// Withdraws `amount` units of value from the chain balance and starts a transfer to
+    /// credit the recipient. The message must be authenticated by one of the chain's owners.
+    WithdrawChainBalance {
+        owner: Owner,
+        amount: Amount,
+        recipient: Recipient,
+    },
 }
 
 /// A query to the system state.
@@ -379,6 +409,25 @@ pub enum SystemExecutionError {
     IncorrectClaimAmount,
     #[error("Claim must be authenticated by the right signer")]
     UnauthenticatedClaimOwner,
+    #[error("Claim of the chain balance must be authenticated by one of the chain's owners")]
+    UnauthorizedChainBalanceClaim,
+    #[error("Owner limits can only be changed by an owner without a limit")]
+    UnauthorizedOwnerLimitsChange,
+    #[error("The chain ownership can only be changed by an owner without a limit")]
+    UnauthorizedOwnershipChange,
+    #[error("Owner {owner} cannot spend {amount} in a block: its limit is {limit}")]
+    OwnerLimitExceeded {
+        owner: Owner,
+        limit: Amount,
+        amount: Amount,
+    },
+    #[error("Approval threshold {threshold} must be between 1 and the number of owners {owners}")]
+    InvalidApprovalThreshold { threshold: u32, owners: usize },
+    #[error("Block at height {height} is timelocked until height {not_before}")]
+    TimelockNotReached {
+        not_before: BlockHeight,
+        height: BlockHeight,
+    },
     #[error("Admin operations are only allowed on the admin chain.")]
     AdminOperationOnNonAdminChain,
     #[error("Failed to create new committee")]
@@ -467,7 +516,14 @@ where
         match operation {
             OpenChain(config) => {
                 let next_message_id = context.next_message_id(txn_tracker.next_message_index());
-                let messages = self.open_chain(config, next_message_id).await?;
+                let messages = self
+                    .open_chain(
+                        config,
+                        context.authenticated_signer,
+                        next_message_id,
+                        txn_tracker.owner_spending_mut(),
+                    )
+                    .await?;
                 outcome.messages.extend(messages);
                 #[cfg(with_metrics)]
                 OPEN_CHAIN_COUNT.with_label_values(&[]).inc();
@@ -479,17 +535,43 @@ where
                 open_multi_leader_rounds,
                 timeout_config,
             } => {
-                self.ownership.set(ChainOwnership {
+                ensure!(
+                    !context
+                        .authenticated_signer
+                        .is_some_and(|signer| self.owner_limits.get().contains_key(&signer)),
+                    SystemExecutionError::UnauthorizedOwnershipChange
+                );
+                let ownership = ChainOwnership {
                     super_owners: super_owners.into_iter().collect(),
                     owners: owners.into_iter().collect(),
                     multi_leader_rounds,
                     open_multi_leader_rounds,
                     timeout_config,
-                });
+                };
+                if let Some(threshold) = *self.approval_threshold.get() {
+                    Self::check_approval_threshold(&ownership, threshold)?;
+                }
+                self.ownership.set(ownership);
             }
             ChangeApplicationPermissions(application_permissions) => {
                 self.application_permissions.set(application_permissions);
             }
+            ChangeOwnerLimits { limits } => {
+                ensure!(
+                    context.authenticated_signer.is_some_and(|signer| {
+                        self.ownership.get().verify_owner(&signer)
+                            && !self.owner_limits.get().contains_key(&signer)
+                    }),
+                    SystemExecutionError::UnauthorizedOwnerLimitsChange
+                );
+                self.owner_limits.set(limits);
+            }
+            ChangeApprovalThreshold { threshold } => {
+                if let Some(threshold) = threshold {
+                    Self::check_approval_threshold(self.ownership.get(), threshold)?;
+                }
+                self.approval_threshold.set(threshold);
+            }
             CloseChain => {
                 let messages = self.close_chain(context.chain_id).await?;
                 outcome.messages.extend(messages);
@@ -507,6 +589,7 @@ where
                         owner.map(AccountOwner::User),
                         recipient,
                         amount,
+                        txn_tracker.owner_spending_mut(),
                     )
                     .await?;
 
@@ -533,6 +616,41 @@ where
 
                 outcome.messages.push(message)
             }
+            ClaimChainBalance {
+                owner,
+                target_id,
+                recipient,
+                amount,
+            } => {
+                ensure!(
+                    context.authenticated_signer == Some(owner),
+                    SystemExecutionError::UnauthenticatedClaimOwner
+                );
+                ensure!(
+                    amount > Amount::ZERO,
+                    SystemExecutionError::IncorrectClaimAmount
+                );
+                outcome.messages.push(RawOutgoingMessage {
+                    destination: Destination::Recipient(target_id),
+                    authenticated: true,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Simple,
+                    message: SystemMessage::WithdrawChainBalance {
+                        owner,
+                        amount,
+                        recipient,
+                    },
+                });
+            }
+            Timelock { not_before } => {
+                ensure!(
+                    context.height >= not_before,
+                    SystemExecutionError::TimelockNotReached {
+                        not_before,
+                        height: context.height,
+                    }
+                );
+            }
             Admin(admin_operation) => {
                 ensure!(
                     *self.admin_id.get() == Some(context.chain_id),
@@ -544,6 +662,9 @@ where
                             epoch == self.epoch.get().expect("chain is active").try_add_one()?,
                             SystemExecutionError::InvalidCommitteeCreation
                         );
+                        // The voting rights are checked by the client that stages the
+                        // committee, not here: rejecting blocks that earlier versions accepted
+                        // would break the replay of existing admin chains.
                         self.committees.get_mut().insert(epoch, committee.clone());
                         self.epoch.set(Some(epoch));
                         let message = RawOutgoingMessage {
@@ -692,6 +813,7 @@ where
         source: Option<AccountOwner>,
         recipient: Recipient,
         amount: Amount,
+        owner_spending: &mut BTreeMap<Owner, Amount>,
     ) -> Result<Option<RawOutgoingMessage<SystemMessage, Amount>>, SystemExecutionError> {
         match (source, authenticated_signer, authenticated_application_id) {
             (Some(AccountOwner::User(owner)), Some(signer), _) => ensure!(
@@ -716,6 +838,13 @@ where
             amount > Amount::ZERO,
             SystemExecutionError::IncorrectTransferAmount
         );
+        match source {
+            None => self.track_spending(authenticated_signer, amount, owner_spending)?,
+            Some(AccountOwner::User(owner)) => {
+                self.track_spending(Some(owner), amount, owner_spending)?
+            }
+            Some(AccountOwner::Application(_)) => {}
+        }
         self.debit(source.as_ref(), amount).await?;
         match recipient {
             Recipient::Account(account) => {
@@ -774,6 +903,48 @@ where
         })
     }
 
+    /// Checks that the `ownership` has at least `threshold` distinct owners, and that the
+    /// threshold is at least 1.
+    fn check_approval_threshold(
+        ownership: &ChainOwnership,
+        threshold: u32,
+    ) -> Result<(), SystemExecutionError> {
+        let owners = ownership.all_owners().collect::<BTreeSet<_>>().len();
+        ensure!(
+            threshold >= 1 && threshold as usize <= owners,
+            SystemExecutionError::InvalidApprovalThreshold { threshold, owners }
+        );
+        Ok(())
+    }
+
+    /// Records in `owner_spending` that `spender` spent an [`Amount`] of tokens from the chain
+    /// balance or from its own account in the current block, and checks that this doesn't
+    /// exceed its limit.
+    fn track_spending(
+        &self,
+        spender: Option<Owner>,
+        amount: Amount,
+        owner_spending: &mut BTreeMap<Owner, Amount>,
+    ) -> Result<(), SystemExecutionError> {
+        let Some(owner) = spender else {
+            return Ok(());
+        };
+        let Some(limit) = self.owner_limits.get().get(&owner).copied() else {
+            return Ok(());
+        };
+        let spent = owner_spending.entry(owner).or_default();
+        spent.try_add_assign(amount)?;
+        ensure!(
+            *spent <= limit,
+            SystemExecutionError::OwnerLimitExceeded {
+                owner,
+                limit,
+                amount: *spent,
+            }
+        );
+        Ok(())
+    }
+
     /// Debits an [`Amount`] of tokens from an account's balance.
     async fn debit(
         &mut self,
@@ -835,6 +1006,9 @@ where
                 owner,
                 recipient,
             } => {
+                if let AccountOwner::User(owner) = owner {
+                    self.track_spending(Some(owner), amount, txn_tracker.owner_spending_mut())?;
+                }
                 self.debit(Some(&owner), amount).await?;
                 match recipient {
                     Recipient::Account(account) => {
@@ -854,6 +1028,36 @@ where
                     Recipient::Burn => (),
                 }
             }
+            WithdrawChainBalance {
+                owner,
+                amount,
+                recipient,
+            } => {
+                ensure!(
+                    context.authenticated_signer == Some(owner)
+                        && self.ownership.get().verify_owner(&owner),
+                    SystemExecutionError::UnauthorizedChainBalanceClaim
+                );
+                self.track_spending(Some(owner), amount, txn_tracker.owner_spending_mut())?;
+                self.debit(None, amount).await?;
+                match recipient {
+                    Recipient::Account(account) => {
+                        let message = RawOutgoingMessage {
+                            destination: Destination::Recipient(account.chain_id),
+                            authenticated: false,
+                            grant: Amount::ZERO,
+                            kind: MessageKind::Tracked,
+                            message: SystemMessage::Credit {
+                                amount,
+                                source: None,
+                                target: account.owner,
+                            },
+                        };
+                        outcome.messages.push(message);
+                    }
+                    Recipient::Burn => (),
+                }
+            }
             CreateCommittee { epoch, committee } => {
                 let chain_next_epoch = self.epoch.get().expect("chain is active").try_add_one()?;
                 ensure!(
@@ -949,13 +1153,15 @@ where
     }
 
     /// Returns the messages to open a new chain, and subtracts the new chain's balance
-    /// from this chain's.
+    /// from this chain's. The balance counts towards the `spender`'s limit, if any.
     pub async fn open_chain(
         &mut self,
         config: OpenChainConfig,
+        spender: Option<Owner>,
         next_message_id: MessageId,
+        owner_spending: &mut BTreeMap<Owner, Amount>,
     ) -> Result<[RawOutgoingMessage<SystemMessage, Amount>; 2], SystemExecutionError> {
-        let child_id = ChainId::child(next_message_id);
+        let child_id = self.context().extra().child_chain_id(next_message_id);
         ensure!(
             self.admin_id.get().as_ref() == Some(&config.admin_id),
             SystemExecutionError::InvalidNewChainAdminId(child_id)
@@ -972,6 +1178,9 @@ where
                 epoch: config.epoch,
             }
         );
+        if config.balance > Amount::ZERO {
+            self.track_spending(spender, config.balance, owner_spending)?;
+        }
         self.debit(None, config.balance).await?;
         let open_chain_message = RawOutgoingMessage {
             destination: Destination::Recipient(child_id),
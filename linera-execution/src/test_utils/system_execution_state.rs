@@ -50,6 +50,10 @@ pub struct SystemExecutionState {
     #[debug(skip_if = Not::not)]
     pub closed: bool,
     pub application_permissions: ApplicationPermissions,
+    #[debug(skip_if = BTreeMap::is_empty)]
+    pub owner_limits: BTreeMap<Owner, Amount>,
+    #[debug(skip_if = Option::is_none)]
+    pub approval_threshold: Option<u32>,
     #[debug(skip_if = Vec::is_empty)]
     pub extra_blobs: Vec<Blob>,
     #[debug(skip_if = BTreeMap::is_empty)]
@@ -112,6 +116,8 @@ impl SystemExecutionState {
             used_blobs,
             closed,
             application_permissions,
+            owner_limits,
+            approval_threshold,
             extra_blobs,
             mock_applications,
         } = self;
@@ -172,6 +178,8 @@ impl SystemExecutionState {
         view.system
             .application_permissions
             .set(application_permissions);
+        view.system.owner_limits.set(owner_limits);
+        view.system.approval_threshold.set(approval_threshold);
         view
     }
 }
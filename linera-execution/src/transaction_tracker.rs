@@ -1,13 +1,13 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::vec;
+use std::{collections::BTreeMap, vec};
 
 use custom_debug_derive::Debug;
 use linera_base::{
     data_types::{Amount, ArithmeticError, OracleResponse},
     ensure,
-    identifiers::ApplicationId,
+    identifiers::{ApplicationId, Owner},
 };
 
 use crate::{
@@ -25,6 +25,10 @@ pub struct TransactionTracker {
     #[debug(skip_if = Vec::is_empty)]
     outcomes: Vec<ExecutionOutcome>,
     next_message_index: u32,
+    /// The amounts that owners with a spending limit have spent in the block so far, including
+    /// in this transaction.
+    #[debug(skip_if = BTreeMap::is_empty)]
+    owner_spending: BTreeMap<Owner, Amount>,
 }
 
 impl TransactionTracker {
@@ -34,9 +38,22 @@ impl TransactionTracker {
             next_message_index,
             oracle_responses: Vec::new(),
             outcomes: Vec::new(),
+            owner_spending: BTreeMap::new(),
         }
     }
 
+    /// Continues tracking the amounts spent by limited owners in the previous transactions of
+    /// the block.
+    pub fn with_owner_spending(mut self, owner_spending: BTreeMap<Owner, Amount>) -> Self {
+        self.owner_spending = owner_spending;
+        self
+    }
+
+    /// Returns the amounts that owners with a spending limit have spent in the block so far.
+    pub fn owner_spending_mut(&mut self) -> &mut BTreeMap<Owner, Amount> {
+        &mut self.owner_spending
+    }
+
     pub fn next_message_index(&self) -> u32 {
         self.next_message_index
     }
@@ -120,6 +137,7 @@ impl TransactionTracker {
             oracle_responses,
             outcomes,
             next_message_index,
+            owner_spending: _,
         } = self;
         if let Some(mut responses) = replaying_oracle_responses {
             ensure!(
@@ -0,0 +1,51 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::crypto::PublicKey;
+
+use super::*;
+
+#[test]
+fn test_committee_without_network_addresses() {
+    let committee = Committee::make_simple(vec![
+        PublicKey::test_key(0).into(),
+        PublicKey::test_key(1).into(),
+    ]);
+    let mut json = serde_json::to_value(&committee).unwrap();
+    for state in json["validators"].as_object_mut().unwrap().values_mut() {
+        state.as_object_mut().unwrap().remove("network_address");
+    }
+
+    let old_committee = serde_json::from_value::<Committee>(json).unwrap();
+    assert_eq!(old_committee.total_votes(), committee.total_votes());
+    for (name, address) in old_committee.validator_addresses() {
+        assert_eq!(committee.weight(&name), old_committee.weight(&name));
+        assert_eq!(address, "");
+    }
+}
+
+#[test]
+fn test_highest_value_with_validity() {
+    let names = (0..4)
+        .map(|index| ValidatorName::from(PublicKey::test_key(index)))
+        .collect::<Vec<_>>();
+    let committee = Committee::make_simple(names.clone());
+    assert_eq!(committee.validity_threshold(), 2);
+
+    // A single validator can't vouch for a value on its own.
+    let reports = names.iter().copied().zip([1_000, 5, 4, 3]);
+    assert_eq!(committee.highest_value_with_validity(reports), Some(5));
+    let reports = names.iter().copied().zip([1_000, 5, 5, 3]);
+    assert_eq!(committee.highest_value_with_validity(reports), Some(5));
+    // Repeated reports from the same validator are not counted twice.
+    let reports = [(names[0], 1_000), (names[0], 1_000), (names[1], 3)];
+    assert_eq!(committee.highest_value_with_validity(reports), Some(3));
+    assert_eq!(
+        committee.highest_value_with_validity([(names[0], 1_000)]),
+        None
+    );
+    // Values from unknown validators have no weight.
+    let stranger = ValidatorName::from(PublicKey::test_key(9));
+    let reports = [(names[0], 1_000), (stranger, 1_000)];
+    assert_eq!(committee.highest_value_with_validity(reports), None);
+}
@@ -18,11 +18,15 @@ use linera_base::{
 };
 use linera_views::batch::Batch;
 
-use super::{ApplicationStatus, SyncRuntimeHandle, SyncRuntimeInternal};
+use super::{
+    ApplicationStatus, ServiceSyncRuntime, SyncRuntimeHandle, SyncRuntimeInternal,
+    SERVICE_QUERY_FUEL,
+};
 use crate::{
     execution_state_actor::ExecutionRequest,
     runtime::{LoadedApplication, ResourceController, SyncRuntime},
-    ContractRuntime, RawExecutionOutcome, TransactionTracker, UserContractInstance,
+    ContractRuntime, ExecutionError, QueryContext, RawExecutionOutcome, ResourceControlPolicy,
+    ServiceRuntime, TransactionTracker, UserContractInstance,
 };
 
 /// Test if dropping [`SyncRuntime`] does not leak memory.
@@ -149,6 +153,53 @@ async fn test_write_batch() {
     );
 }
 
+/// Tests that each query gets the full fuel budget, and can't consume more than that.
+#[test_log::test(tokio::test)]
+async fn test_service_query_fuel_budget() {
+    let (execution_state_sender, execution_state_receiver) = mpsc::unbounded();
+    let context = QueryContext {
+        chain_id: ChainDescription::Root(0).into(),
+        next_block_height: BlockHeight(0),
+        local_time: Timestamp::from(0),
+    };
+    let mut runtime = ServiceSyncRuntime::new(execution_state_sender, context);
+
+    let handle = runtime.handle_mut();
+    assert_eq!(handle.remaining_fuel().unwrap(), SERVICE_QUERY_FUEL);
+    handle.consume_fuel(SERVICE_QUERY_FUEL).unwrap();
+    assert_eq!(handle.remaining_fuel().unwrap(), 0);
+    assert!(matches!(
+        handle.consume_fuel(1),
+        Err(ExecutionError::MaximumFuelExceeded)
+    ));
+
+    // The next query starts with a new budget, even if it fails to load the application.
+    drop(execution_state_receiver);
+    let policy = ResourceControlPolicy::default();
+    assert!(runtime
+        .run_query(create_dummy_application_id(), vec![], &policy)
+        .is_err());
+    assert_eq!(
+        runtime.handle_mut().remaining_fuel().unwrap(),
+        SERVICE_QUERY_FUEL
+    );
+
+    // The budget can be changed for each query.
+    let policy = ResourceControlPolicy {
+        maximum_service_fuel_per_query: 1_000,
+        ..ResourceControlPolicy::default()
+    };
+    assert!(runtime
+        .run_query(create_dummy_application_id(), vec![], &policy)
+        .is_err());
+    let handle = runtime.handle_mut();
+    assert_eq!(handle.remaining_fuel().unwrap(), 1_000);
+    assert!(matches!(
+        handle.consume_fuel(1_001),
+        Err(ExecutionError::MaximumFuelExceeded)
+    ));
+}
+
 /// Creates a [`SyncRuntimeInternal`] instance for contracts, and returns it and the receiver
 /// endpoint for the requests the runtime sends to the [`ExecutionStateView`] actor.
 fn create_contract_runtime() -> (
@@ -1,6 +1,7 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use assert_matches::assert_matches;
 use linera_base::{
     data_types::{Blob, BlockHeight, Bytecode},
     identifiers::ApplicationId,
@@ -114,6 +115,50 @@ async fn open_chain_message_index() {
     );
 }
 
+/// Tests that the child chain IDs come from the runtime context, including when a single
+/// transaction opens several chains.
+#[tokio::test]
+async fn open_chain_with_custom_child_ids() -> anyhow::Result<()> {
+    let (mut view, context) = new_view_and_context().await;
+    view.context()
+        .extra()
+        .set_child_id_deriver(|message_id: MessageId| ChainId::root(100 + message_id.index));
+    let owner = linera_base::crypto::PublicKey::test_key(0).into();
+    let config = OpenChainConfig {
+        ownership: ChainOwnership::single(owner),
+        committees: view.system.committees.get().clone(),
+        epoch: view.system.epoch.get().unwrap(),
+        admin_id: view.system.admin_id.get().unwrap(),
+        balance: Amount::ZERO,
+        application_permissions: Default::default(),
+    };
+    let mut txn_tracker = TransactionTracker::default();
+    for _ in 0..2 {
+        let operation = SystemOperation::OpenChain(config.clone());
+        view.system
+            .execute_operation(context, operation, &mut txn_tracker)
+            .await?;
+    }
+    let child_ids = txn_tracker
+        .destructure()?
+        .0
+        .into_iter()
+        .flat_map(|outcome| {
+            let ExecutionOutcome::System(result) = outcome else {
+                panic!("Unexpected outcome");
+            };
+            result.messages
+        })
+        .filter_map(|message| match (message.message, message.destination) {
+            (SystemMessage::OpenChain(_), Destination::Recipient(chain_id)) => Some(chain_id),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(child_ids, [ChainId::root(100), ChainId::root(102)]);
+
+    Ok(())
+}
+
 /// Tests if an account is removed from storage if it is drained.
 #[tokio::test]
 async fn empty_accounts_are_removed() -> anyhow::Result<()> {
@@ -134,3 +179,192 @@ async fn empty_accounts_are_removed() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Returns an execution state view and a matching operation context as in
+/// [`new_view_and_context`], with 10 tokens in the chain balance and in the account of an owner
+/// who can spend at most 2 tokens per block and who signs the operations.
+async fn new_view_and_context_with_limited_owner() -> (
+    ExecutionStateView<MemoryContext<TestExecutionRuntimeContext>>,
+    OperationContext,
+    Owner,
+) {
+    let (mut view, mut context) = new_view_and_context().await;
+    let limited_owner = Owner::from(linera_base::crypto::PublicKey::test_key(1));
+    let owner = Owner::from(linera_base::crypto::PublicKey::test_key(0));
+    view.system.ownership.set(ChainOwnership::multiple(
+        [(owner, 100), (limited_owner, 100)],
+        10,
+        TimeoutConfig::default(),
+    ));
+    view.system
+        .owner_limits
+        .set(BTreeMap::from([(limited_owner, Amount::from_tokens(2))]));
+    view.system.balance.set(Amount::from_tokens(10));
+    view.system
+        .balances
+        .insert(&AccountOwner::User(limited_owner), Amount::from_tokens(10))
+        .unwrap();
+    context.authenticated_signer = Some(limited_owner);
+    (view, context, limited_owner)
+}
+
+/// Returns a [`MessageContext`] for a message to the chain of the operation `context`.
+fn message_context(context: &OperationContext) -> MessageContext {
+    MessageContext {
+        chain_id: context.chain_id,
+        is_bouncing: false,
+        authenticated_signer: context.authenticated_signer,
+        refund_grant_to: None,
+        height: context.height,
+        round: context.round,
+        certificate_hash: CryptoHash::test_hash("block sending the message"),
+        message_id: MessageId {
+            chain_id: context.chain_id,
+            height: context.height,
+            index: 0,
+        },
+    }
+}
+
+/// Tests that the balance of new chains counts towards the limit of the owner opening them,
+/// together with its transfers.
+#[tokio::test]
+async fn owner_limit_applies_to_open_chain() -> anyhow::Result<()> {
+    let (mut view, context, limited_owner) = new_view_and_context_with_limited_owner().await;
+    let mut txn_tracker = TransactionTracker::default();
+    let operation = SystemOperation::Transfer {
+        owner: None,
+        recipient: Recipient::chain(ChainId::root(1)),
+        amount: Amount::ONE,
+    };
+    view.system
+        .execute_operation(context, operation, &mut txn_tracker)
+        .await?;
+    let config = OpenChainConfig {
+        ownership: ChainOwnership::single(limited_owner),
+        committees: view.system.committees.get().clone(),
+        epoch: view.system.epoch.get().unwrap(),
+        admin_id: view.system.admin_id.get().unwrap(),
+        balance: Amount::from_tokens(2),
+        application_permissions: Default::default(),
+    };
+    let operation = SystemOperation::OpenChain(config);
+    assert_matches!(
+        view.system
+            .execute_operation(context, operation, &mut txn_tracker)
+            .await,
+        Err(SystemExecutionError::OwnerLimitExceeded { owner, limit, amount })
+            if owner == limited_owner
+                && limit == Amount::from_tokens(2)
+                && amount == Amount::from_tokens(3)
+    );
+    assert_eq!(*view.system.balance.get(), Amount::from_tokens(9));
+
+    Ok(())
+}
+
+/// Tests that a limited owner can't claim more than its limit from its own account.
+#[tokio::test]
+async fn owner_limit_applies_to_claims() -> anyhow::Result<()> {
+    let (mut view, context, limited_owner) = new_view_and_context_with_limited_owner().await;
+    let mut txn_tracker = TransactionTracker::default();
+    let message = SystemMessage::Withdraw {
+        owner: AccountOwner::User(limited_owner),
+        amount: Amount::from_tokens(3),
+        recipient: Recipient::Burn,
+    };
+    assert_matches!(
+        view.system
+            .execute_message(message_context(&context), message, &mut txn_tracker)
+            .await,
+        Err(SystemExecutionError::OwnerLimitExceeded { owner, .. }) if owner == limited_owner
+    );
+    let balance = view
+        .system
+        .balances
+        .get(&AccountOwner::User(limited_owner))
+        .await?;
+    assert_eq!(balance, Some(Amount::from_tokens(10)));
+
+    Ok(())
+}
+
+/// Tests that a limited owner can't withdraw more than its limit from the chain balance.
+#[tokio::test]
+async fn owner_limit_applies_to_chain_balance_withdrawals() -> anyhow::Result<()> {
+    let (mut view, context, limited_owner) = new_view_and_context_with_limited_owner().await;
+    let mut txn_tracker = TransactionTracker::default();
+    let message = SystemMessage::WithdrawChainBalance {
+        owner: limited_owner,
+        amount: Amount::from_tokens(3),
+        recipient: Recipient::Burn,
+    };
+    assert_matches!(
+        view.system
+            .execute_message(message_context(&context), message, &mut txn_tracker)
+            .await,
+        Err(SystemExecutionError::OwnerLimitExceeded { owner, .. }) if owner == limited_owner
+    );
+    assert_eq!(*view.system.balance.get(), Amount::from_tokens(10));
+
+    Ok(())
+}
+
+/// Tests that a limited owner can't change the chain ownership, e.g. to lift its own limit by
+/// becoming the only owner.
+#[tokio::test]
+async fn limited_owner_cannot_change_ownership() -> anyhow::Result<()> {
+    let (mut view, context, limited_owner) = new_view_and_context_with_limited_owner().await;
+    let mut txn_tracker = TransactionTracker::default();
+    let operation = SystemOperation::ChangeOwnership {
+        super_owners: vec![limited_owner],
+        owners: vec![],
+        multi_leader_rounds: 10,
+        open_multi_leader_rounds: false,
+        timeout_config: TimeoutConfig::default(),
+    };
+    assert_matches!(
+        view.system
+            .execute_operation(context, operation, &mut txn_tracker)
+            .await,
+        Err(SystemExecutionError::UnauthorizedOwnershipChange)
+    );
+    assert!(view.system.ownership.get().super_owners.is_empty());
+
+    Ok(())
+}
+
+/// Tests that the ownership can't be changed to fewer owners than the approval threshold.
+#[tokio::test]
+async fn ownership_change_respects_approval_threshold() -> anyhow::Result<()> {
+    let (mut view, mut context) = new_view_and_context().await;
+    let owner0 = Owner::from(linera_base::crypto::PublicKey::test_key(0));
+    let owner1 = Owner::from(linera_base::crypto::PublicKey::test_key(1));
+    view.system.ownership.set(ChainOwnership::multiple(
+        [(owner0, 100), (owner1, 100)],
+        10,
+        TimeoutConfig::default(),
+    ));
+    view.system.approval_threshold.set(Some(2));
+    context.authenticated_signer = Some(owner0);
+    let mut txn_tracker = TransactionTracker::default();
+    let operation = SystemOperation::ChangeOwnership {
+        super_owners: vec![],
+        owners: vec![(owner0, 100)],
+        multi_leader_rounds: 10,
+        open_multi_leader_rounds: false,
+        timeout_config: TimeoutConfig::default(),
+    };
+    assert_matches!(
+        view.system
+            .execute_operation(context, operation, &mut txn_tracker)
+            .await,
+        Err(SystemExecutionError::InvalidApprovalThreshold {
+            threshold: 2,
+            owners: 1
+        })
+    );
+    assert_eq!(view.system.ownership.get().all_owners().count(), 2);
+
+    Ok(())
+}
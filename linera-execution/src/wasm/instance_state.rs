@@ -0,0 +1,259 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Saving and restoring the state of Wasmtime instances outside of their linear memory, so that
+//! pooled instances start every use like freshly instantiated ones.
+//!
+//! Wasmtime only gives access to the globals and tables that an instance exports, but modules
+//! compiled from Rust keep their shadow stack pointer in a global and their function pointers in
+//! a table, neither of which is exported. [`export_instance_state`] adds an export for every
+//! mutable global and every table defined by a module, which [`InstanceState`] then saves right
+//! after instantiation and restores before each reuse.
+
+use linera_base::data_types::Bytecode;
+use wasm_encoder::{Encode, ExportKind, ExportSection, Section};
+use wasmparser::{
+    BinaryReader, BinaryReaderError, ExportSectionReader, ExternalKind, GlobalSectionReader,
+    ImportSectionReader, TableSectionReader, TypeRef,
+};
+use wasmtime::{AsContextMut, ExternType, Global, Module, Mutability, Ref, Table, Val};
+
+/// The prefix of the names of the exports added by [`export_instance_state`].
+const STATE_EXPORT_PREFIX: &str = "linera:instance-state/";
+
+/// The IDs of the sections that must come after the Export section.
+const SECTIONS_AFTER_EXPORTS: [u8; 5] = [
+    8,  // Start
+    9,  // Element
+    10, // Code
+    11, // Data
+    12, // DataCount
+];
+
+/// Returns the `bytecode` with an additional export for each mutable global and each table
+/// that it defines.
+///
+/// The exports don't change how the module executes, nor the fuel it consumes.
+pub fn export_instance_state(bytecode: &Bytecode) -> Result<Bytecode, BinaryReaderError> {
+    let bytes = bytecode.as_ref();
+    let mut imported_globals = 0;
+    let mut imported_tables = 0;
+    let mut new_exports = Vec::new();
+    let mut exports = ExportSection::new();
+    let mut export_section = None;
+    let mut insertion_offset = bytes.len();
+
+    // Skip the magic number and version.
+    let mut reader = BinaryReader::new_with_offset(bytes.get(8..).unwrap_or_default(), 8);
+    while !reader.eof() {
+        let start = reader.original_position();
+        let id = reader.read_u8()?;
+        let size = reader.read_var_u32()? as usize;
+        let offset = reader.original_position();
+        let contents = reader.read_bytes(size)?;
+        let section = start..reader.original_position();
+        match id {
+            2 => {
+                for import in ImportSectionReader::new(contents, offset)? {
+                    match import?.ty {
+                        TypeRef::Global(_) => imported_globals += 1,
+                        TypeRef::Table(_) => imported_tables += 1,
+                        _ => {}
+                    }
+                }
+            }
+            4 => {
+                let tables = TableSectionReader::new(contents, offset)?;
+                for index in imported_tables..(imported_tables + tables.count()) {
+                    let name = format!("{STATE_EXPORT_PREFIX}table-{index}");
+                    new_exports.push((name, ExportKind::Table, index));
+                }
+            }
+            6 => {
+                for (index, global) in
+                    (imported_globals..).zip(GlobalSectionReader::new(contents, offset)?)
+                {
+                    if global?.ty.mutable {
+                        let name = format!("{STATE_EXPORT_PREFIX}global-{index}");
+                        new_exports.push((name, ExportKind::Global, index));
+                    }
+                }
+            }
+            7 => {
+                for export in ExportSectionReader::new(contents, offset)? {
+                    let export = export?;
+                    exports.export(export.name, export_kind(export.kind), export.index);
+                }
+                export_section = Some(section);
+            }
+            id if SECTIONS_AFTER_EXPORTS.contains(&id) => {
+                insertion_offset = insertion_offset.min(section.start);
+            }
+            _ => {}
+        }
+    }
+
+    if new_exports.is_empty() {
+        return Ok(bytecode.clone());
+    }
+    for (name, kind, index) in new_exports {
+        exports.export(&name, kind, index);
+    }
+
+    let replaced = export_section.unwrap_or(insertion_offset..insertion_offset);
+    let mut output = Vec::with_capacity(bytes.len() + exports.len() as usize * 32);
+    output.extend(&bytes[..replaced.start]);
+    output.push(exports.id());
+    exports.encode(&mut output);
+    output.extend(&bytes[replaced.end..]);
+    Ok(Bytecode::new(output))
+}
+
+/// Converts the kind of an export parsed by [`wasmparser`] into a kind encodable with
+/// [`wasm_encoder`].
+fn export_kind(kind: ExternalKind) -> ExportKind {
+    match kind {
+        ExternalKind::Func => ExportKind::Func,
+        ExternalKind::Table => ExportKind::Table,
+        ExternalKind::Memory => ExportKind::Memory,
+        ExternalKind::Global => ExportKind::Global,
+        ExternalKind::Tag => ExportKind::Tag,
+    }
+}
+
+/// The exported mutable globals and tables of an instance, along with their contents when the
+/// state was saved.
+pub struct InstanceState {
+    globals: Vec<(Global, Val)>,
+    tables: Vec<(Table, Vec<Ref>)>,
+}
+
+impl InstanceState {
+    /// Saves the exported mutable globals and tables of an `instance` of the `module`.
+    ///
+    /// The `load_export` function returns the export of the instance with a given name.
+    pub fn save<Store: AsContextMut>(
+        module: &Module,
+        store: &mut Store,
+        mut load_export: impl FnMut(&mut Store, &str) -> Option<wasmtime::Extern>,
+    ) -> Self {
+        let mut globals = Vec::new();
+        let mut tables = Vec::new();
+        for export in module.exports() {
+            match export.ty() {
+                ExternType::Global(global_type) if global_type.mutability() == Mutability::Var => {
+                    if let Some(global) =
+                        load_export(store, export.name()).and_then(|export| export.into_global())
+                    {
+                        globals.push((global, global.get(&mut *store)));
+                    }
+                }
+                ExternType::Table(_) => {
+                    if let Some(table) =
+                        load_export(store, export.name()).and_then(|export| export.into_table())
+                    {
+                        let size = table.size(&mut *store);
+                        let elements = (0..size)
+                            .map(|index| {
+                                table
+                                    .get(&mut *store, index)
+                                    .expect("Index should be within the table")
+                            })
+                            .collect();
+                        tables.push((table, elements));
+                    }
+                }
+                _ => {}
+            }
+        }
+        InstanceState { globals, tables }
+    }
+
+    /// Restores the saved globals and tables in the `store` of their instance.
+    ///
+    /// Returns `false` if a table can't be restored because its size changed, in which case
+    /// the instance must not be reused.
+    pub fn restore(&self, mut store: impl AsContextMut) -> bool {
+        for (global, value) in &self.globals {
+            if global.set(&mut store, value.clone()).is_err() {
+                return false;
+            }
+        }
+        for (table, elements) in &self.tables {
+            let size = table.size(&mut store);
+            if usize::try_from(size).ok() != Some(elements.len()) {
+                return false;
+            }
+            for (index, element) in (0..size).zip(elements) {
+                if table.set(&mut store, index, element.clone()).is_err() {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::data_types::Bytecode;
+    use wasmtime::{Engine, Instance, Module, Store, Val};
+
+    use super::{export_instance_state, InstanceState};
+
+    /// Tests that the internal global and table of a module are restored to their initial
+    /// contents, and that existing exports are kept.
+    #[test]
+    fn restores_internal_globals_and_tables() {
+        let wat = r#"
+            (module
+              (type $get (func (result i32)))
+              (table $functions 2 funcref)
+              (global $counter (mut i32) (i32.const 7))
+              (global $constant i32 (i32.const 1))
+              (func $zero (type $get) i32.const 0)
+              (func $one (type $get) i32.const 1)
+              (func (export "bump") (result i32)
+                global.get $counter
+                i32.const 1
+                i32.add
+                global.set $counter
+                i32.const 0
+                ref.func $one
+                table.set $functions
+                global.get $counter)
+              (func (export "call") (result i32)
+                i32.const 0
+                call_indirect $functions (type $get))
+              (elem (table $functions) (i32.const 0) func $zero $one)
+            )
+        "#;
+        let bytecode = Bytecode::new(wasmer::wat2wasm(wat.as_bytes()).unwrap().into());
+        let bytecode = export_instance_state(&bytecode).unwrap();
+        let engine = Engine::default();
+        let module = Module::new(&engine, &bytecode).unwrap();
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &module, &[]).unwrap();
+        let bump = instance
+            .get_typed_func::<(), i32>(&mut store, "bump")
+            .unwrap();
+        let call = instance
+            .get_typed_func::<(), i32>(&mut store, "call")
+            .unwrap();
+
+        let state = InstanceState::save(&module, &mut store, |store, name| {
+            instance.get_export(store, name)
+        });
+        assert_eq!(state.globals.len(), 1);
+        assert_eq!(state.tables.len(), 1);
+        assert_eq!(call.call(&mut store, ()).unwrap(), 0);
+        for _ in 0..2 {
+            assert_eq!(bump.call(&mut store, ()).unwrap(), 8);
+            assert_eq!(call.call(&mut store, ()).unwrap(), 1);
+            assert!(state.restore(&mut store));
+            assert_eq!(call.call(&mut store, ()).unwrap(), 0);
+        }
+        let (counter, _) = &state.globals[0];
+        assert!(matches!(counter.get(&mut store), Val::I32(7)));
+    }
+}
@@ -11,6 +11,10 @@
 #![cfg(with_wasm_runtime)]
 
 mod entrypoints;
+#[cfg(with_wasmtime)]
+mod instance_state;
+#[cfg(with_wasmtime)]
+mod module_artifacts;
 mod module_cache;
 mod sanitizer;
 #[macro_use]
@@ -24,8 +28,12 @@ use linera_base::data_types::Bytecode;
 use thiserror::Error;
 #[cfg(with_wasmer)]
 use wasmer::{WasmerContractInstance, WasmerServiceInstance};
+#[cfg(all(with_wasmtime, with_testing))]
+pub use wasmtime::FuelTracingContractModule;
 #[cfg(with_wasmtime)]
-use wasmtime::{WasmtimeContractInstance, WasmtimeServiceInstance};
+use wasmtime::{ContractInstancePool, ServiceInstancePool};
+#[cfg(with_wasmtime)]
+pub use wasmtime::{EngineProfile, FuelTrace, FuelTraceEntry, ResourceLimits, WasmRuntimeConfig};
 #[cfg(with_metrics)]
 use {
     linera_base::prometheus_util::{bucket_latencies, register_histogram_vec, MeasureLatency},
@@ -36,6 +44,7 @@ use {
 use self::sanitizer::sanitize;
 pub use self::{
     entrypoints::{ContractEntrypoints, ServiceEntrypoints},
+    module_cache::{BytecodeKey, ModuleCache},
     system_api::{ContractSystemApi, ServiceSystemApi, SystemApiData, ViewSystemApi},
 };
 use crate::{
@@ -72,7 +81,9 @@ pub enum WasmContractModule {
         module: ::wasmer::Module,
     },
     #[cfg(with_wasmtime)]
-    Wasmtime { module: ::wasmtime::Module },
+    Wasmtime {
+        instances: std::sync::Arc<ContractInstancePool>,
+    },
 }
 
 impl WasmContractModule {
@@ -100,6 +111,56 @@ impl WasmContractModule {
         }
     }
 
+    /// Compiles the contract `bytecodes` ahead of time, so that the first calls to
+    /// [`WasmContractModule::new`] with them don't have to wait for their compilation.
+    ///
+    /// With Wasmtime, the bytecodes are compiled in parallel. Wasmer modules are compiled one
+    /// after the other.
+    pub async fn warm_up(
+        bytecodes: Vec<Bytecode>,
+        runtime: WasmRuntime,
+    ) -> Result<(), WasmExecutionError> {
+        let bytecodes = if runtime.needs_sanitizer() {
+            bytecodes
+                .into_iter()
+                .map(sanitize)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(WasmExecutionError::LoadContractModule)?
+        } else {
+            bytecodes
+        };
+        match runtime {
+            #[cfg(with_wasmer)]
+            WasmRuntime::Wasmer | WasmRuntime::WasmerWithSanitizer => {
+                for bytecode in bytecodes {
+                    Self::from_wasmer(bytecode).await?;
+                }
+                Ok(())
+            }
+            #[cfg(with_wasmtime)]
+            WasmRuntime::Wasmtime | WasmRuntime::WasmtimeWithSanitizer => {
+                Self::warm_up_wasmtime(bytecodes).await
+            }
+        }
+    }
+
+    /// Returns `true` if the contract bytecode with the given `key` is already compiled and
+    /// cached for `runtime`, so that it doesn't need to be warmed up.
+    ///
+    /// If the `runtime` needs the sanitizer, `key` must be that of the sanitized bytecode.
+    pub async fn is_cached(key: &BytecodeKey, runtime: WasmRuntime) -> bool {
+        match runtime {
+            #[cfg(with_wasmer)]
+            WasmRuntime::Wasmer | WasmRuntime::WasmerWithSanitizer => {
+                Self::is_cached_wasmer(key).await
+            }
+            #[cfg(with_wasmtime)]
+            WasmRuntime::Wasmtime | WasmRuntime::WasmtimeWithSanitizer => {
+                Self::is_cached_wasmtime(key).await
+            }
+        }
+    }
+
     /// Creates a new [`WasmContractModule`] using the WebAssembly module in `bytecode_file`.
     #[cfg(with_fs)]
     pub async fn from_file(
@@ -115,6 +176,56 @@ impl WasmContractModule {
         )
         .await
     }
+
+    /// Returns a [`WasmContractModule`] that keeps at most `capacity` idle instances for reuse
+    /// in each thread, or that instantiates the module for every transaction if `capacity` is
+    /// zero.
+    ///
+    /// The new module does not share its idle instances with `self`. Only Wasmtime modules keep
+    /// idle instances, so Wasmer modules are returned unchanged.
+    pub fn with_instance_pool_capacity(self, capacity: usize) -> Self {
+        match self {
+            #[cfg(with_wasmtime)]
+            WasmContractModule::Wasmtime { instances } => WasmContractModule::Wasmtime {
+                instances: std::sync::Arc::new(instances.with_capacity(capacity)),
+            },
+            #[cfg(with_wasmer)]
+            module @ WasmContractModule::Wasmer { .. } => {
+                let _ = capacity;
+                module
+            }
+        }
+    }
+
+    /// Returns a [`WasmContractModule`] that reports the backtraces of the operations that trap
+    /// by executing them again with the original `bytecode` of the module, as configured with
+    /// [`WasmRuntimeConfig::trap_diagnostics`]. Wasmer modules are returned unchanged.
+    #[cfg(with_testing)]
+    pub fn with_trap_diagnostics(self, bytecode: Bytecode) -> Self {
+        match self {
+            #[cfg(with_wasmtime)]
+            WasmContractModule::Wasmtime { instances } => WasmContractModule::Wasmtime {
+                instances: std::sync::Arc::new(instances.with_trap_diagnostics(bytecode)),
+            },
+            #[cfg(with_wasmer)]
+            module @ WasmContractModule::Wasmer { .. } => {
+                let _ = bytecode;
+                module
+            }
+        }
+    }
+
+    /// Returns the number of times an idle instance of this module was reused, which is always
+    /// zero for Wasmer modules.
+    #[cfg(with_testing)]
+    pub fn reused_instance_count(&self) -> u64 {
+        match self {
+            #[cfg(with_wasmtime)]
+            WasmContractModule::Wasmtime { instances } => instances.reused_instance_count(),
+            #[cfg(with_wasmer)]
+            WasmContractModule::Wasmer { .. } => 0,
+        }
+    }
 }
 
 impl UserContractModule for WasmContractModule {
@@ -127,9 +238,7 @@ impl UserContractModule for WasmContractModule {
 
         let instance: UserContractInstance = match self {
             #[cfg(with_wasmtime)]
-            WasmContractModule::Wasmtime { module } => {
-                Box::new(WasmtimeContractInstance::prepare(module, runtime)?)
-            }
+            WasmContractModule::Wasmtime { instances } => Box::new(instances.get(runtime)?),
             #[cfg(with_wasmer)]
             WasmContractModule::Wasmer { engine, module } => Box::new(
                 WasmerContractInstance::prepare(engine.clone(), module, runtime)?,
@@ -146,7 +255,9 @@ pub enum WasmServiceModule {
     #[cfg(with_wasmer)]
     Wasmer { module: ::wasmer::Module },
     #[cfg(with_wasmtime)]
-    Wasmtime { module: ::wasmtime::Module },
+    Wasmtime {
+        instances: std::sync::Arc<ServiceInstancePool>,
+    },
 }
 
 impl WasmServiceModule {
@@ -167,6 +278,45 @@ impl WasmServiceModule {
         }
     }
 
+    /// Compiles the service `bytecodes` ahead of time, so that the first calls to
+    /// [`WasmServiceModule::new`] with them don't have to wait for their compilation.
+    ///
+    /// With Wasmtime, the bytecodes are compiled in parallel. Wasmer modules are compiled one
+    /// after the other.
+    pub async fn warm_up(
+        bytecodes: Vec<Bytecode>,
+        runtime: WasmRuntime,
+    ) -> Result<(), WasmExecutionError> {
+        match runtime {
+            #[cfg(with_wasmer)]
+            WasmRuntime::Wasmer | WasmRuntime::WasmerWithSanitizer => {
+                for bytecode in bytecodes {
+                    Self::from_wasmer(bytecode).await?;
+                }
+                Ok(())
+            }
+            #[cfg(with_wasmtime)]
+            WasmRuntime::Wasmtime | WasmRuntime::WasmtimeWithSanitizer => {
+                Self::warm_up_wasmtime(bytecodes).await
+            }
+        }
+    }
+
+    /// Returns `true` if the service bytecode with the given `key` is already compiled and
+    /// cached for `runtime`, so that it doesn't need to be warmed up.
+    pub async fn is_cached(key: &BytecodeKey, runtime: WasmRuntime) -> bool {
+        match runtime {
+            #[cfg(with_wasmer)]
+            WasmRuntime::Wasmer | WasmRuntime::WasmerWithSanitizer => {
+                Self::is_cached_wasmer(key).await
+            }
+            #[cfg(with_wasmtime)]
+            WasmRuntime::Wasmtime | WasmRuntime::WasmtimeWithSanitizer => {
+                Self::is_cached_wasmtime(key).await
+            }
+        }
+    }
+
     /// Creates a new [`WasmServiceModule`] using the WebAssembly module in `bytecode_file`.
     #[cfg(with_fs)]
     pub async fn from_file(
@@ -182,6 +332,25 @@ impl WasmServiceModule {
         )
         .await
     }
+
+    /// Returns a [`WasmServiceModule`] that keeps at most `capacity` idle instances for reuse
+    /// in each thread, or that instantiates the module for every query if `capacity` is zero.
+    ///
+    /// The new module does not share its idle instances with `self`. Only Wasmtime modules keep
+    /// idle instances, so Wasmer modules are returned unchanged.
+    pub fn with_instance_pool_capacity(self, capacity: usize) -> Self {
+        match self {
+            #[cfg(with_wasmtime)]
+            WasmServiceModule::Wasmtime { instances } => WasmServiceModule::Wasmtime {
+                instances: std::sync::Arc::new(instances.with_capacity(capacity)),
+            },
+            #[cfg(with_wasmer)]
+            module @ WasmServiceModule::Wasmer { .. } => {
+                let _ = capacity;
+                module
+            }
+        }
+    }
 }
 
 impl UserServiceModule for WasmServiceModule {
@@ -194,9 +363,7 @@ impl UserServiceModule for WasmServiceModule {
 
         let instance: UserServiceInstance = match self {
             #[cfg(with_wasmtime)]
-            WasmServiceModule::Wasmtime { module } => {
-                Box::new(WasmtimeServiceInstance::prepare(module, runtime)?)
-            }
+            WasmServiceModule::Wasmtime { instances } => Box::new(instances.get(runtime)?),
             #[cfg(with_wasmer)]
             WasmServiceModule::Wasmer { module } => {
                 Box::new(WasmerServiceInstance::prepare(module, runtime)?)
@@ -282,6 +449,12 @@ pub enum WasmExecutionError {
     #[cfg(with_wasmtime)]
     #[error("Failed to create and configure Wasmtime runtime: {_0}")]
     CreateWasmtimeEngine(#[source] anyhow::Error),
+    #[cfg(with_wasmtime)]
+    #[error("Wasmtime engines have already been configured")]
+    WasmtimeEnginesAlreadyConfigured,
+    #[cfg(with_wasmtime)]
+    #[error("Wasm module imports host functions that are not available: {}", names.join(", "))]
+    MissingHostImports { names: Vec<String> },
     #[cfg(with_wasmer)]
     #[error(
         "Failed to execute Wasm module in Wasmer. This may be caused by panics or insufficient fuel. {0}"
@@ -289,15 +462,62 @@ pub enum WasmExecutionError {
     ExecuteModuleInWasmer(#[from] ::wasmer::RuntimeError),
     #[cfg(with_wasmtime)]
     #[error("Failed to execute Wasm module in Wasmtime: {0}")]
-    ExecuteModuleInWasmtime(#[from] ::wasmtime::Trap),
+    ExecuteModuleInWasmtime(::wasmtime::Trap),
     #[error("Failed to execute Wasm module: {0}")]
-    ExecuteModule(#[from] linera_witty::RuntimeError),
+    ExecuteModule(linera_witty::RuntimeError),
+    #[error("Wasm module exhausted its call stack")]
+    StackOverflow,
+    #[error("Wasm module reached unreachable code, which usually means that it panicked")]
+    UnreachableCodeReached,
+    #[error("Wasm module attempted an integer division by zero")]
+    IntegerDivisionByZero,
+    #[error("Wasm module ran out of fuel")]
+    OutOfFuel,
+    #[error("Service query exceeded its fuel budget")]
+    QueryBudgetExceeded,
+    #[error("Wasm module exceeded the limits on its memory, tables or instances")]
+    MemoryLimitExceeded,
+    #[cfg(with_wasmtime)]
+    #[error(
+        "{error}\nBacktrace of the operation executed again to diagnose the trap:\n{backtrace}"
+    )]
+    TrapWithBacktrace {
+        error: Box<WasmExecutionError>,
+        backtrace: String,
+    },
+    /// Only reported by [`FuelTracingContractModule::verify`], in tests.
+    #[cfg(all(with_wasmtime, with_testing))]
+    #[error("Wasm module consumed {actual:?} fuel instead of the expected {expected:?}")]
+    NonDeterministicFuel {
+        expected: FuelTrace,
+        actual: FuelTrace,
+    },
     #[error("Attempt to wait for an unknown promise")]
     UnknownPromise,
     #[error("Attempt to call incorrect `wait` function for a promise")]
     IncorrectPromise,
 }
 
+impl WasmExecutionError {
+    /// Returns `true` if the error is a trap raised while executing the Wasm module.
+    pub fn is_trap(&self) -> bool {
+        match self {
+            #[cfg(with_wasmtime)]
+            WasmExecutionError::ExecuteModuleInWasmtime(_)
+            | WasmExecutionError::TrapWithBacktrace { .. } => true,
+            #[cfg(with_wasmer)]
+            WasmExecutionError::ExecuteModuleInWasmer(_) => true,
+            WasmExecutionError::StackOverflow
+            | WasmExecutionError::UnreachableCodeReached
+            | WasmExecutionError::IntegerDivisionByZero
+            | WasmExecutionError::OutOfFuel
+            | WasmExecutionError::QueryBudgetExceeded
+            | WasmExecutionError::MemoryLimitExceeded => true,
+            _ => false,
+        }
+    }
+}
+
 #[cfg(with_wasmer)]
 impl From<::wasmer::InstantiationError> for WasmExecutionError {
     fn from(instantiation_error: ::wasmer::InstantiationError) -> Self {
@@ -305,6 +525,45 @@ impl From<::wasmer::InstantiationError> for WasmExecutionError {
     }
 }
 
+#[cfg(with_wasmtime)]
+impl From<::wasmtime::Trap> for WasmExecutionError {
+    fn from(trap: ::wasmtime::Trap) -> Self {
+        match trap {
+            ::wasmtime::Trap::StackOverflow => WasmExecutionError::StackOverflow,
+            ::wasmtime::Trap::UnreachableCodeReached => WasmExecutionError::UnreachableCodeReached,
+            ::wasmtime::Trap::IntegerDivisionByZero => WasmExecutionError::IntegerDivisionByZero,
+            ::wasmtime::Trap::OutOfFuel => WasmExecutionError::OutOfFuel,
+            trap => WasmExecutionError::ExecuteModuleInWasmtime(trap),
+        }
+    }
+}
+
+impl From<linera_witty::RuntimeError> for WasmExecutionError {
+    fn from(error: linera_witty::RuntimeError) -> Self {
+        match error {
+            #[cfg(with_wasmtime)]
+            linera_witty::RuntimeError::WasmtimeTrap(trap) => trap.into(),
+            // Wasmtime reports traps raised while executing a guest function as an opaque
+            // error, so the trap has to be recovered from it. Errors raised by the host, such as
+            // exceeding the resource limits, are reported the same way.
+            #[cfg(with_wasmtime)]
+            linera_witty::RuntimeError::Wasmtime(error) => {
+                let error = match error.downcast::<WasmExecutionError>() {
+                    Ok(error) => return error,
+                    Err(error) => error,
+                };
+                match error.downcast_ref::<::wasmtime::Trap>() {
+                    Some(&trap) => trap.into(),
+                    None => WasmExecutionError::ExecuteModule(
+                        linera_witty::RuntimeError::Wasmtime(error),
+                    ),
+                }
+            }
+            error => WasmExecutionError::ExecuteModule(error),
+        }
+    }
+}
+
 /// This assumes that the current directory is one of the crates.
 #[cfg(with_testing)]
 pub mod test {
@@ -353,3 +612,55 @@ pub mod test {
         Ok((contract, service))
     }
 }
+
+#[cfg(all(test, with_wasmtime))]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::WasmExecutionError;
+
+    /// Tests that the traps that callers need to tell apart are mapped to their own variants,
+    /// whether they are reported directly or wrapped in a Wasmtime error.
+    #[test]
+    fn classifies_wasmtime_traps() {
+        let classify = |trap: ::wasmtime::Trap| {
+            let direct = WasmExecutionError::from(trap);
+            let wrapped = WasmExecutionError::from(linera_witty::RuntimeError::Wasmtime(
+                anyhow::Error::from(trap).context("backtrace"),
+            ));
+            assert_eq!(direct.to_string(), wrapped.to_string());
+            direct
+        };
+
+        assert_matches!(
+            classify(::wasmtime::Trap::StackOverflow),
+            WasmExecutionError::StackOverflow
+        );
+        assert_matches!(
+            classify(::wasmtime::Trap::UnreachableCodeReached),
+            WasmExecutionError::UnreachableCodeReached
+        );
+        assert_matches!(
+            classify(::wasmtime::Trap::IntegerDivisionByZero),
+            WasmExecutionError::IntegerDivisionByZero
+        );
+        assert_matches!(
+            classify(::wasmtime::Trap::OutOfFuel),
+            WasmExecutionError::OutOfFuel
+        );
+        assert_matches!(
+            classify(::wasmtime::Trap::MemoryOutOfBounds),
+            WasmExecutionError::ExecuteModuleInWasmtime(::wasmtime::Trap::MemoryOutOfBounds)
+        );
+        assert_matches!(
+            WasmExecutionError::from(linera_witty::RuntimeError::Wasmtime(
+                anyhow::Error::from(WasmExecutionError::MemoryLimitExceeded).context("backtrace"),
+            )),
+            WasmExecutionError::MemoryLimitExceeded
+        );
+        assert_matches!(
+            WasmExecutionError::from(linera_witty::RuntimeError::AllocationTooLarge),
+            WasmExecutionError::ExecuteModule(linera_witty::RuntimeError::AllocationTooLarge)
+        );
+    }
+}
@@ -0,0 +1,238 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An on-disk cache of compiled Wasmtime modules, so that a restarted node doesn't have to
+//! compile all the application bytecodes again.
+//!
+//! Each artifact is stored in a file named after the compatibility hash of the [`Engine`] that
+//! compiled it and the [`BytecodeKey`] of its bytecode. The file starts with a checksum of the
+//! serialized module, which is verified before the module is deserialized. Artifacts that are
+//! missing, corrupted or incompatible are ignored: the module is compiled again, and its
+//! artifact replaced.
+//!
+//! The checksum only protects against accidental corruption. Loading an artifact runs the
+//! native code in it, so the directory must not be writable by anyone who isn't trusted to run
+//! code on the node.
+//!
+//! Artifacts are never removed. Several engines with different configurations share the
+//! directory, so the artifacts of other engines can't be told apart from stale ones, e.g. those
+//! left behind by an older Wasmtime version. Deleting the directory, or any file in it, is
+//! always safe: the missing modules are compiled again.
+
+use std::{
+    fs,
+    hash::{Hash as _, Hasher},
+    path::{Path, PathBuf},
+};
+
+use linera_base::crypto::CryptoHash;
+use wasmtime::{Engine, Module};
+
+use super::module_cache::BytecodeKey;
+
+/// The type name used to compute the checksums of the artifacts.
+const CHECKSUM_TYPE_NAME: &str = "ModuleArtifact";
+
+/// The type name used to compute the hashes of the engines in the names of the artifacts.
+const ENGINE_HASH_TYPE_NAME: &str = "ModuleArtifactEngine";
+
+/// The size of the checksum at the start of an artifact file.
+const CHECKSUM_SIZE: usize = 32;
+
+/// A directory storing the serialized modules compiled by Wasmtime.
+#[derive(Clone, Debug)]
+pub(crate) struct ModuleArtifacts {
+    directory: PathBuf,
+}
+
+impl ModuleArtifacts {
+    /// Creates a [`ModuleArtifacts`] storing its files in `directory`. The directory is
+    /// created when the first artifact is stored.
+    pub(crate) fn new(directory: PathBuf) -> Self {
+        ModuleArtifacts { directory }
+    }
+
+    /// Returns the module of the bytecode with the given `key` from its artifact, or compiles
+    /// it with `compile` and stores its artifact if there is no valid artifact for `engine`.
+    ///
+    /// Failing to store the artifact is only logged, since the module can still be used.
+    pub(crate) fn load_or_compile<E>(
+        &self,
+        engine: &Engine,
+        key: &BytecodeKey,
+        compile: impl FnOnce() -> Result<Module, E>,
+    ) -> Result<Module, E> {
+        let path = self.path(engine, key);
+        if let Some(module) = Self::load(engine, &path) {
+            return Ok(module);
+        }
+        let module = compile()?;
+        if let Err(error) = self.store(&path, &module) {
+            tracing::warn!(
+                %error,
+                path = %path.display(),
+                "Failed to store the compiled module"
+            );
+        }
+        Ok(module)
+    }
+
+    /// Returns the path of the artifact of the bytecode with the given `key`, compiled with
+    /// `engine`.
+    fn path(&self, engine: &Engine, key: &BytecodeKey) -> PathBuf {
+        let mut hasher = StableHasher::default();
+        engine.precompile_compatibility_hash().hash(&mut hasher);
+        let engine_hash = hasher.crypto_hash();
+        self.directory
+            .join(format!("{engine_hash}-{}-{}.cwasm", key.hash(), key.size()))
+    }
+
+    /// Loads the module in the artifact at `path`, if it exists and is valid for `engine`.
+    fn load(engine: &Engine, path: &Path) -> Option<Module> {
+        let contents = fs::read(path).ok()?;
+        if contents.len() < CHECKSUM_SIZE {
+            tracing::warn!(path = %path.display(), "Ignoring truncated module artifact");
+            return None;
+        }
+        let (checksum, artifact) = contents.split_at(CHECKSUM_SIZE);
+        if checksum
+            != CryptoHash::from_bcs_bytes(CHECKSUM_TYPE_NAME, artifact)
+                .as_bytes()
+                .0
+        {
+            tracing::warn!(path = %path.display(), "Ignoring corrupted module artifact");
+            return None;
+        }
+        // SAFETY: The artifact was created by `Module::serialize` with an engine with the same
+        // compatibility hash, and its checksum shows that it wasn't modified since. Wasmtime
+        // also checks that the artifact is compatible with `engine`.
+        match unsafe { Module::deserialize(engine, artifact) } {
+            Ok(module) => Some(module),
+            Err(error) => {
+                tracing::warn!(
+                    %error,
+                    path = %path.display(),
+                    "Ignoring incompatible module artifact"
+                );
+                None
+            }
+        }
+    }
+
+    /// Stores the artifact of `module` at `path`.
+    ///
+    /// The artifact is written to a temporary file first, so that other processes never read a
+    /// partially written artifact.
+    fn store(&self, path: &Path, module: &Module) -> anyhow::Result<()> {
+        let artifact = module.serialize()?;
+        let checksum = CryptoHash::from_bcs_bytes(CHECKSUM_TYPE_NAME, &artifact);
+        let mut contents = Vec::with_capacity(CHECKSUM_SIZE + artifact.len());
+        contents.extend_from_slice(&checksum.as_bytes().0);
+        contents.extend_from_slice(&artifact);
+        fs::create_dir_all(&self.directory)?;
+        let temporary_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temporary_path, contents)?;
+        fs::rename(&temporary_path, path)?;
+        Ok(())
+    }
+}
+
+/// A [`Hasher`] based on [`CryptoHash`], whose output, unlike that of the standard library
+/// hashers, is stable across Rust versions and processes.
+#[derive(Default)]
+struct StableHasher(Vec<u8>);
+
+impl StableHasher {
+    /// Returns the hash of the bytes written so far.
+    fn crypto_hash(&self) -> CryptoHash {
+        CryptoHash::from_bcs_bytes(ENGINE_HASH_TYPE_NAME, &self.0)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        <[u64; 4]>::from(self.crypto_hash())[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, fs, path::PathBuf};
+
+    use linera_base::data_types::Bytecode;
+    use wasmtime::{Engine, Module};
+
+    use super::ModuleArtifacts;
+    use crate::wasm::module_cache::BytecodeKey;
+
+    /// Returns an empty directory for the test with the given `name`.
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "linera-module-artifacts-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    /// Loads the module of `bytecode` from the `artifacts`, and returns whether it had to be
+    /// compiled.
+    fn load(artifacts: &ModuleArtifacts, engine: &Engine, bytecode: &Bytecode) -> bool {
+        let compiled = Cell::new(false);
+        artifacts
+            .load_or_compile(engine, &BytecodeKey::new(bytecode), || {
+                compiled.set(true);
+                Module::new(engine, bytecode)
+            })
+            .unwrap();
+        compiled.get()
+    }
+
+    #[test]
+    fn round_trips_module_artifacts() {
+        let directory = test_directory("round-trip");
+        let artifacts = ModuleArtifacts::new(directory.clone());
+        let engine = Engine::default();
+        let bytecode = Bytecode::new(b"\0asm\x01\0\0\0".to_vec());
+
+        assert!(load(&artifacts, &engine, &bytecode));
+        assert!(!load(&artifacts, &engine, &bytecode));
+
+        // Artifacts are only used by compatible engines.
+        let mut config = wasmtime::Config::default();
+        config.consume_fuel(true);
+        let other_engine = Engine::new(&config).unwrap();
+        assert!(load(&artifacts, &other_engine, &bytecode));
+        assert!(!load(&artifacts, &other_engine, &bytecode));
+        assert!(!load(&artifacts, &engine, &bytecode));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn recompiles_corrupted_module_artifacts() {
+        let directory = test_directory("corrupted");
+        let artifacts = ModuleArtifacts::new(directory.clone());
+        let engine = Engine::default();
+        let bytecode = Bytecode::new(b"\0asm\x01\0\0\0".to_vec());
+        let path = artifacts.path(&engine, &BytecodeKey::new(&bytecode));
+
+        assert!(load(&artifacts, &engine, &bytecode));
+        let mut contents = fs::read(&path).unwrap();
+        let last = contents.len() - 1;
+        contents[last] ^= 0xff;
+        fs::write(&path, contents).unwrap();
+        assert!(load(&artifacts, &engine, &bytecode));
+        assert!(!load(&artifacts, &engine, &bytecode));
+
+        fs::write(&path, b"truncated").unwrap();
+        assert!(load(&artifacts, &engine, &bytecode));
+        assert!(!load(&artifacts, &engine, &bytecode));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -3,33 +3,121 @@
 
 //! A cache of compiled WebAssembly modules.
 //!
-//! The cache is limited by the total size of cached bytecodes. Note that this is a heuristic to
-//! estimate the total memory usage by the cache, since it's currently not possible to determine
-//! the size of a generic `Module`.
+//! The cache is limited by the number of cached modules and by the total size of cached
+//! bytecodes. Note that the latter is a heuristic to estimate the total memory usage by the
+//! cache, since it's currently not possible to determine the size of a generic `Module`.
+//!
+//! Modules are looked up by a [`BytecodeKey`] instead of their bytecode, so that the cache
+//! doesn't keep the bytecodes in memory next to their compiled modules.
+
+#[cfg(with_wasmtime)]
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
-use linera_base::data_types::Bytecode;
+use linera_base::{crypto::CryptoHash, data_types::Bytecode};
 use lru::LruCache;
+#[cfg(with_wasmtime)]
+use tokio::sync::{Mutex, OnceCell};
+
+#[cfg(with_wasmtime)]
+use super::WasmExecutionError;
 
 /// The default maximum size of the bytecodes stored in cache.
-const DEFAULT_MAX_CACHE_SIZE: u64 = 512 /* MiB */ * 1024 /* KiB */ * 1024 /* bytes */;
+pub const DEFAULT_MAX_CACHE_SIZE: u64 = 512 /* MiB */ * 1024 /* KiB */ * 1024 /* bytes */;
+
+/// The default maximum number of modules stored in cache.
+pub const DEFAULT_MAX_CACHE_ENTRIES: usize = usize::MAX;
+
+/// Identifies a bytecode by its hash and its size.
+///
+/// Both must match for two keys to be equal, so that a module can't be confused with the
+/// module of another bytecode even in the event of a hash collision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BytecodeKey {
+    hash: CryptoHash,
+    size: u64,
+}
+
+impl BytecodeKey {
+    /// Computes the key of `bytecode`.
+    pub fn new(bytecode: &Bytecode) -> Self {
+        BytecodeKey {
+            hash: CryptoHash::new(bytecode),
+            size: bytecode.as_ref().len() as u64,
+        }
+    }
+
+    /// Returns the hash of the bytecode.
+    pub fn hash(&self) -> CryptoHash {
+        self.hash
+    }
+
+    /// Returns the size of the bytecode in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
 
 /// A cache of compiled WebAssembly modules.
 ///
-/// The cache prioritizes entries based on their [`Metadata`].
+/// When the cache is full, the least recently used modules are evicted. They are compiled again
+/// the next time they are requested with [`ModuleCache::get_or_insert_with`].
 pub struct ModuleCache<Module> {
-    modules: LruCache<Bytecode, Module>,
+    modules: LruCache<BytecodeKey, Module>,
     total_size: u64,
     max_size: u64,
+    max_entries: usize,
+    hits: u64,
+    misses: u64,
 }
 
 impl<Module> Default for ModuleCache<Module> {
     fn default() -> Self {
+        ModuleCache::new(DEFAULT_MAX_CACHE_ENTRIES, DEFAULT_MAX_CACHE_SIZE)
+    }
+}
+
+impl<Module> ModuleCache<Module> {
+    /// Creates an empty cache holding at most `max_entries` modules, whose bytecodes have a
+    /// total size of at most `max_size` bytes.
+    pub fn new(max_entries: usize, max_size: u64) -> Self {
         ModuleCache {
             modules: LruCache::unbounded(),
             total_size: 0,
-            max_size: DEFAULT_MAX_CACHE_SIZE,
+            max_size,
+            max_entries,
+            hits: 0,
+            misses: 0,
         }
     }
+
+    /// Returns the number of modules in the cache.
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    /// Returns `true` if there are no modules in the cache.
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Returns the number of lookups that found their module in the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the number of lookups that didn't find their module in the cache.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Returns `true` if the module for the bytecode with the given `key` is in the cache,
+    /// without counting it as a lookup or as a use of the module.
+    pub fn contains(&self, key: &BytecodeKey) -> bool {
+        self.modules.contains(key)
+    }
 }
 
 impl<Module: Clone> ModuleCache<Module> {
@@ -40,42 +128,373 @@ impl<Module: Clone> ModuleCache<Module> {
         bytecode: Bytecode,
         module_builder: impl FnOnce(Bytecode) -> Result<Module, E>,
     ) -> Result<Module, E> {
-        if let Some(module) = self.get(&bytecode) {
+        let key = BytecodeKey::new(&bytecode);
+        if let Some(module) = self.get(&key) {
             Ok(module)
         } else {
-            let module = module_builder(bytecode.clone())?;
-            self.insert(bytecode, module.clone());
+            let module = module_builder(bytecode)?;
+            self.insert(key, module.clone());
             Ok(module)
         }
     }
 
-    /// Returns a `Module` for the requested `bytecode` if it's in the cache.
-    pub fn get(&mut self, bytecode: &Bytecode) -> Option<Module> {
-        self.modules.get(bytecode).cloned()
+    /// Returns the `Module` for the bytecode with the given `key` if it's in the cache.
+    pub fn get(&mut self, key: &BytecodeKey) -> Option<Module> {
+        let module = self.modules.get(key).cloned();
+        if module.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        module
+    }
+
+    /// Inserts the compiled `module` of the bytecode with the given `key` in the cache,
+    /// evicting the least recently used modules if needed to stay within the limits.
+    pub fn insert(&mut self, key: BytecodeKey, module: Module) {
+        if let Some(previous_module) = self.modules.get_mut(&key) {
+            *previous_module = module;
+            return;
+        }
+
+        self.evict_until_fits(key.size);
+        self.total_size += key.size;
+        self.modules.put(key, module);
+    }
+
+    /// Evicts entries from the cache until there is room for one more entry with a bytecode of
+    /// `bytecode_size` bytes, or the cache is empty.
+    fn evict_until_fits(&mut self, bytecode_size: u64) {
+        while self.modules.len() >= self.max_entries
+            || self.total_size + bytecode_size > self.max_size
+        {
+            let Some((key, _module)) = self.modules.pop_lru() else {
+                break;
+            };
+            self.total_size -= key.size;
+        }
     }
+}
+
+/// A [`ModuleCache`] shared by concurrent tasks, which compiles the missing modules without
+/// holding its lock, so that a slow compilation doesn't block loading the other modules.
+///
+/// Concurrent requests for the same bytecode wait for a single compilation.
+#[cfg(with_wasmtime)]
+pub(crate) struct SharedModuleCache<Module> {
+    state: Mutex<SharedModuleCacheState<Module>>,
+    compile: fn(BytecodeKey, Bytecode) -> Result<Module, WasmExecutionError>,
+}
 
-    /// Inserts a `bytecode` and its compiled `module` in the cache.
-    pub fn insert(&mut self, bytecode: Bytecode, module: Module) {
-        let bytecode_size = bytecode.as_ref().len() as u64;
+/// The modules of a [`SharedModuleCache`], and the compilations in progress.
+#[cfg(with_wasmtime)]
+struct SharedModuleCacheState<Module> {
+    modules: ModuleCache<Module>,
+    compilations: HashMap<BytecodeKey, Arc<OnceCell<Module>>>,
+}
 
-        if self.total_size + bytecode_size > self.max_size {
-            self.reduce_size_to(self.max_size - bytecode_size);
+#[cfg(with_wasmtime)]
+impl<Module> SharedModuleCache<Module>
+where
+    Module: Clone + Send + Sync + 'static,
+{
+    /// Creates a [`SharedModuleCache`] storing its modules in `modules`, and compiling the
+    /// missing ones with `compile`.
+    pub(crate) fn new(
+        modules: ModuleCache<Module>,
+        compile: fn(BytecodeKey, Bytecode) -> Result<Module, WasmExecutionError>,
+    ) -> Self {
+        SharedModuleCache {
+            state: Mutex::new(SharedModuleCacheState {
+                modules,
+                compilations: HashMap::new(),
+            }),
+            compile,
         }
+    }
+
+    /// Returns the `Module` for the requested `bytecode` with the given `key`, compiling it in
+    /// a blocking task and adding it to the cache if it isn't cached yet.
+    ///
+    /// The cache is only locked to look up and insert modules, and to join the compilation of
+    /// the same bytecode if one is already in progress.
+    pub(crate) async fn get_or_compile(
+        &self,
+        key: BytecodeKey,
+        bytecode: Bytecode,
+    ) -> Result<Module, WasmExecutionError> {
+        let compilation = {
+            let mut state = self.state.lock().await;
+            if let Some(module) = state.modules.get(&key) {
+                return Ok(module);
+            }
+            state.compilations.entry(key).or_default().clone()
+        };
+        self.join_compilation(key, bytecode, compilation).await
+    }
 
-        self.modules.put(bytecode, module);
+    /// Compiles the `bytecode` with the given `key` and adds it to the cache, unless it is
+    /// cached already.
+    ///
+    /// Unlike [`Self::get_or_compile`], this doesn't count as a lookup, and doesn't make a
+    /// cached module more recently used.
+    async fn compile_if_missing(
+        &self,
+        key: BytecodeKey,
+        bytecode: Bytecode,
+    ) -> Result<(), WasmExecutionError> {
+        let compilation = {
+            let mut state = self.state.lock().await;
+            if state.modules.contains(&key) {
+                return Ok(());
+            }
+            state.compilations.entry(key).or_default().clone()
+        };
+        self.join_compilation(key, bytecode, compilation).await?;
+        Ok(())
     }
 
-    /// Evicts entries from the cache so that the total size of cached bytecodes is less than
-    /// `new_size`.
-    fn reduce_size_to(&mut self, new_size: u64) {
-        while self.total_size > new_size {
-            let (bytecode, _module) = self
-                .modules
-                .pop_lru()
-                .expect("Empty cache should have a `total_size` of zero");
-            let bytecode_size = bytecode.as_ref().len() as u64;
+    /// Runs or joins the `compilation` of the `bytecode` with the given `key`, and adds the
+    /// module to the cache.
+    async fn join_compilation(
+        &self,
+        key: BytecodeKey,
+        bytecode: Bytecode,
+        compilation: Arc<OnceCell<Module>>,
+    ) -> Result<Module, WasmExecutionError> {
+        let compile = self.compile;
+        let result = compilation
+            .get_or_try_init(|| async move {
+                tokio::task::spawn_blocking(move || compile(key, bytecode))
+                    .await
+                    // Blocking tasks can't be cancelled, so the task can only have failed by
+                    // panicking.
+                    .unwrap_or_else(|error| std::panic::resume_unwind(error.into_panic()))
+            })
+            .await
+            .cloned();
+
+        let mut state = self.state.lock().await;
+        if state
+            .compilations
+            .get(&key)
+            .is_some_and(|current| Arc::ptr_eq(current, &compilation))
+        {
+            state.compilations.remove(&key);
+        }
+        if let Ok(module) = &result {
+            if !state.modules.contains(&key) {
+                state.modules.insert(key, module.clone());
+            }
+        }
+        result
+    }
+
+    /// Returns `true` if the module for the bytecode with the given `key` is in the cache.
+    pub(crate) async fn contains(&self, key: &BytecodeKey) -> bool {
+        self.state.lock().await.modules.contains(key)
+    }
+
+    /// Compiles the `bytecodes` that aren't cached yet in parallel, and adds them to the cache.
+    ///
+    /// The modules that are already cached are neither counted as hits nor made more recently
+    /// used.
+    pub(crate) async fn warm_up(&self, bytecodes: Vec<Bytecode>) -> Result<(), WasmExecutionError> {
+        let mut keys = HashSet::new();
+        futures::future::try_join_all(
+            bytecodes
+                .into_iter()
+                .map(|bytecode| (BytecodeKey::new(&bytecode), bytecode))
+                .filter(|(key, _)| keys.insert(*key))
+                .map(|(key, bytecode)| self.compile_if_missing(key, bytecode)),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::{crypto::CryptoHash, data_types::Bytecode};
+
+    use super::{BytecodeKey, ModuleCache};
+
+    /// Returns a bytecode of `size` bytes, all equal to `byte`.
+    fn bytecode(byte: u8, size: usize) -> Bytecode {
+        Bytecode::new(vec![byte; size])
+    }
+
+    /// Returns the module for `bytecode` from the `cache`, and whether it had to be compiled.
+    fn load(cache: &mut ModuleCache<u8>, bytecode: &Bytecode) -> (u8, bool) {
+        let mut compiled = false;
+        let module = cache
+            .get_or_insert_with(bytecode.clone(), |bytecode| {
+                compiled = true;
+                Ok::<_, ()>(bytecode.as_ref()[0])
+            })
+            .unwrap();
+        (module, compiled)
+    }
+
+    #[test]
+    fn evicts_least_recently_used_module_beyond_max_entries() {
+        let mut cache = ModuleCache::new(3, u64::MAX);
+        let bytecodes = (0..4).map(|byte| bytecode(byte, 10)).collect::<Vec<_>>();
+
+        for bytecode in &bytecodes[..3] {
+            assert!(load(&mut cache, bytecode).1);
+        }
+        // Use the first module again, so that the second one is the least recently used.
+        assert_eq!(load(&mut cache, &bytecodes[0]), (0, false));
+        assert_eq!(load(&mut cache, &bytecodes[3]), (3, true));
+        assert_eq!(cache.len(), 3);
+        assert!(cache.contains(&BytecodeKey::new(&bytecodes[0])));
+        assert!(!cache.contains(&BytecodeKey::new(&bytecodes[1])));
+
+        // The evicted module is compiled again on demand, evicting the next one.
+        assert_eq!(load(&mut cache, &bytecodes[1]), (1, true));
+        assert!(!cache.contains(&BytecodeKey::new(&bytecodes[2])));
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 5);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_module_beyond_max_size() {
+        let mut cache = ModuleCache::new(usize::MAX, 30);
+        let bytecodes = (0..4).map(|byte| bytecode(byte, 10)).collect::<Vec<_>>();
+
+        for bytecode in &bytecodes {
+            assert!(load(&mut cache, bytecode).1);
+        }
+        assert_eq!(cache.len(), 3);
+        assert!(!cache.contains(&BytecodeKey::new(&bytecodes[0])));
+        assert_eq!(load(&mut cache, &bytecodes[0]), (0, true));
+        assert!(!cache.contains(&BytecodeKey::new(&bytecodes[1])));
+
+        // A bytecode larger than the cache evicts everything else.
+        let large = bytecode(4, 40);
+        assert_eq!(load(&mut cache, &large), (4, true));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(load(&mut cache, &large), (4, false));
+    }
+
+    #[test]
+    fn keys_identify_bytecodes_without_retaining_them() {
+        // A key has a fixed size, however large its bytecode is.
+        assert_eq!(
+            std::mem::size_of::<BytecodeKey>(),
+            std::mem::size_of::<CryptoHash>() + std::mem::size_of::<u64>()
+        );
+        let large = bytecode(1, 1 << 20);
+        let key = BytecodeKey::new(&large);
+        assert_eq!(key.size(), 1 << 20);
+        assert_eq!(key, BytecodeKey::new(&large.clone()));
+        assert_ne!(key, BytecodeKey::new(&bytecode(1, (1 << 20) + 1)));
+
+        // The cache only needs the key once the module is compiled.
+        let mut cache = ModuleCache::new(usize::MAX, u64::MAX);
+        assert_eq!(load(&mut cache, &large), (1, true));
+        drop(large);
+        assert!(cache.contains(&key));
+        assert_eq!(cache.get(&key), Some(1));
+    }
+
+    #[cfg(with_wasmtime)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn compiles_different_modules_concurrently() {
+        use std::{
+            sync::{Barrier, LazyLock},
+            time::Duration,
+        };
+
+        use super::SharedModuleCache;
+        use crate::wasm::WasmExecutionError;
+
+        static BARRIER: LazyLock<Barrier> = LazyLock::new(|| Barrier::new(2));
+
+        /// Waits for another compilation to run at the same time.
+        fn compile(_key: BytecodeKey, bytecode: Bytecode) -> Result<u8, WasmExecutionError> {
+            BARRIER.wait();
+            Ok(bytecode.as_ref()[0])
+        }
+
+        let cache = SharedModuleCache::new(ModuleCache::default(), compile);
+        let load = |byte| {
+            let bytecode = bytecode(byte, 10);
+            cache.get_or_compile(BytecodeKey::new(&bytecode), bytecode)
+        };
+        // The compilations only finish if they run concurrently.
+        let (first, second) = tokio::time::timeout(Duration::from_secs(60), async {
+            futures::join!(load(1), load(2))
+        })
+        .await
+        .expect("Compilations should run concurrently");
+        assert_eq!((first.unwrap(), second.unwrap()), (1, 2));
+    }
+
+    #[cfg(with_wasmtime)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn compiles_the_same_module_once() {
+        use std::{
+            sync::atomic::{AtomicUsize, Ordering},
+            thread,
+            time::Duration,
+        };
+
+        use super::SharedModuleCache;
+        use crate::wasm::WasmExecutionError;
+
+        static COMPILATIONS: AtomicUsize = AtomicUsize::new(0);
+
+        fn compile(_key: BytecodeKey, bytecode: Bytecode) -> Result<u8, WasmExecutionError> {
+            COMPILATIONS.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(100));
+            Ok(bytecode.as_ref()[0])
+        }
+
+        let cache = SharedModuleCache::new(ModuleCache::default(), compile);
+        let load = |byte| {
+            let bytecode = bytecode(byte, 10);
+            cache.get_or_compile(BytecodeKey::new(&bytecode), bytecode)
+        };
+        let (first, second) = futures::join!(load(1), load(1));
+        assert_eq!((first.unwrap(), second.unwrap()), (1, 1));
+        assert_eq!(COMPILATIONS.load(Ordering::SeqCst), 1);
+        assert_eq!(load(1).await.unwrap(), 1);
+        assert_eq!(COMPILATIONS.load(Ordering::SeqCst), 1);
+        assert!(cache.contains(&BytecodeKey::new(&bytecode(1, 10))).await);
+    }
+
+    #[cfg(with_wasmtime)]
+    #[tokio::test]
+    async fn warm_up_does_not_use_cached_modules() {
+        use super::SharedModuleCache;
+        use crate::wasm::WasmExecutionError;
+
+        fn compile(_key: BytecodeKey, bytecode: Bytecode) -> Result<u8, WasmExecutionError> {
+            Ok(bytecode.as_ref()[0])
+        }
+
+        let cache = SharedModuleCache::new(ModuleCache::new(2, u64::MAX), compile);
+        let bytecodes = (0..3).map(|byte| bytecode(byte, 10)).collect::<Vec<_>>();
+        for bytecode in &bytecodes[..2] {
+            let key = BytecodeKey::new(bytecode);
+            cache.get_or_compile(key, bytecode.clone()).await.unwrap();
+        }
 
-            self.total_size -= bytecode_size;
+        // Warming up the least recently used module neither counts as a lookup nor keeps it
+        // from being evicted next.
+        cache.warm_up(vec![bytecodes[0].clone()]).await.unwrap();
+        {
+            let state = cache.state.lock().await;
+            assert_eq!((state.modules.hits(), state.modules.misses()), (0, 2));
         }
+        cache.warm_up(vec![bytecodes[2].clone()]).await.unwrap();
+        assert!(!cache.contains(&BytecodeKey::new(&bytecodes[0])).await);
+        assert!(cache.contains(&BytecodeKey::new(&bytecodes[1])).await);
+        assert!(cache.contains(&BytecodeKey::new(&bytecodes[2])).await);
+        let state = cache.state.lock().await;
+        assert_eq!((state.modules.hits(), state.modules.misses()), (0, 2));
     }
 }
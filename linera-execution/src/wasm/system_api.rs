@@ -21,26 +21,74 @@ use crate::{
     ServiceRuntime, ServiceSyncRuntimeHandle,
 };
 
+/// The `tracing` target of the log of the storage work done by each host call, which can be
+/// enabled with `RUST_LOG=linera_execution::host_calls=trace`.
+const HOST_CALLS_LOG_TARGET: &str = "linera_execution::host_calls";
+
 /// Common host data used as the `UserData` of the system API implementations.
 pub struct SystemApiData<Runtime> {
-    runtime: Runtime,
+    runtime: Option<Runtime>,
     active_promises: HashMap<u32, Box<dyn Any + Send + Sync>>,
     promise_counter: u32,
+    #[cfg(with_wasmtime)]
+    resource_limits: super::wasmtime::ResourceLimits,
 }
 
 impl<Runtime> SystemApiData<Runtime> {
     /// Creates a new [`SystemApiData`] using the provided `runtime` to execute the system APIs.
     pub fn new(runtime: Runtime) -> Self {
         SystemApiData {
-            runtime,
+            runtime: Some(runtime),
+            active_promises: HashMap::new(),
+            promise_counter: 0,
+            #[cfg(with_wasmtime)]
+            resource_limits: Default::default(),
+        }
+    }
+
+    /// Creates a [`SystemApiData`] without a runtime, which can only be used to inspect the
+    /// exported system APIs.
+    #[cfg(with_wasmtime)]
+    pub(crate) fn detached() -> Self {
+        SystemApiData {
+            runtime: None,
             active_promises: HashMap::new(),
             promise_counter: 0,
+            #[cfg(with_wasmtime)]
+            resource_limits: Default::default(),
         }
     }
 
     /// Returns a mutable reference the system API `Runtime`.
+    ///
+    /// # Panics
+    ///
+    /// If the runtime was detached with [`Self::detach_runtime`] and not attached again.
     pub fn runtime_mut(&mut self) -> &mut Runtime {
-        &mut self.runtime
+        self.runtime
+            .as_mut()
+            .expect("System APIs should not be called while the runtime is detached")
+    }
+
+    /// Returns a mutable reference to the limits on the resources of the Wasm instance.
+    #[cfg(with_wasmtime)]
+    pub(crate) fn resource_limits_mut(&mut self) -> &mut super::wasmtime::ResourceLimits {
+        &mut self.resource_limits
+    }
+
+    /// Attaches a new `runtime` to execute the system APIs.
+    #[cfg(with_wasmtime)]
+    pub(crate) fn attach_runtime(&mut self, runtime: Runtime) {
+        self.runtime = Some(runtime);
+    }
+
+    /// Drops the current runtime and any pending promises, so that no references to the
+    /// runtime are kept while the instance is idle.
+    #[cfg(with_wasmtime)]
+    pub(crate) fn detach_runtime(&mut self) {
+        self.runtime = None;
+        self.active_promises.clear();
+        self.promise_counter = 0;
     }
 
     /// Registers a `promise` internally, returning an ID that is unique for the lifetime of this
@@ -88,7 +136,7 @@ where
     fn get_chain_id(caller: &mut Caller) -> Result<ChainId, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .chain_id()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -97,7 +145,7 @@ where
     fn get_block_height(caller: &mut Caller) -> Result<BlockHeight, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .block_height()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -106,7 +154,7 @@ where
     fn get_application_id(caller: &mut Caller) -> Result<ApplicationId, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .application_id()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -115,7 +163,7 @@ where
     fn get_application_creator_chain_id(caller: &mut Caller) -> Result<ChainId, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .application_creator_chain_id()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -124,7 +172,7 @@ where
     fn application_parameters(caller: &mut Caller) -> Result<Vec<u8>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .application_parameters()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -133,7 +181,7 @@ where
     fn authenticated_signer(caller: &mut Caller) -> Result<Option<Owner>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .authenticated_signer()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -142,7 +190,7 @@ where
     fn read_system_timestamp(caller: &mut Caller) -> Result<Timestamp, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .read_system_timestamp()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -152,7 +200,7 @@ where
     fn get_message_id(caller: &mut Caller) -> Result<Option<MessageId>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .message_id()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -163,7 +211,7 @@ where
     fn message_is_bouncing(caller: &mut Caller) -> Result<Option<bool>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .message_is_bouncing()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -172,7 +220,7 @@ where
     fn authenticated_caller_id(caller: &mut Caller) -> Result<Option<ApplicationId>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .authenticated_caller_id()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -181,7 +229,7 @@ where
     fn read_chain_balance(caller: &mut Caller) -> Result<Amount, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .read_chain_balance()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -193,7 +241,7 @@ where
     ) -> Result<Amount, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .read_owner_balance(owner)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -205,7 +253,7 @@ where
     ) -> Result<(), RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .send_message(message)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -218,7 +266,7 @@ where
     ) -> Result<(), RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .subscribe(chain, channel)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -231,7 +279,7 @@ where
     ) -> Result<(), RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .unsubscribe(chain, channel)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -246,7 +294,7 @@ where
     ) -> Result<(), RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .transfer(source, destination, amount)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -260,7 +308,7 @@ where
     ) -> Result<(), RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .claim(source, destination, amount)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -269,7 +317,7 @@ where
     fn get_chain_ownership(caller: &mut Caller) -> Result<ChainOwnership, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .chain_ownership()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -284,7 +332,7 @@ where
     ) -> Result<(MessageId, ChainId), RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .open_chain(chain_ownership, application_permissions, balance)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -292,7 +340,7 @@ where
     /// Closes the current chain. Returns an error if the application doesn't have
     /// permission to do so.
     fn close_chain(caller: &mut Caller) -> Result<Result<(), CloseChainError>, RuntimeError> {
-        match caller.user_data_mut().runtime.close_chain() {
+        match caller.user_data_mut().runtime_mut().close_chain() {
             Ok(()) => Ok(Ok(())),
             Err(ExecutionError::UnauthorizedApplication(_)) => {
                 Ok(Err(CloseChainError::NotPermitted))
@@ -309,7 +357,7 @@ where
     ) -> Result<Result<(), ChangeApplicationPermissionsError>, RuntimeError> {
         match caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .change_application_permissions(application_permissions)
         {
             Ok(()) => Ok(Ok(())),
@@ -331,7 +379,7 @@ where
     ) -> Result<ApplicationId, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .create_application(bytecode_id, parameters, argument, required_application_ids)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -345,7 +393,7 @@ where
     ) -> Result<Vec<u8>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .try_call_application(authenticated, callee_id, argument)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -359,7 +407,7 @@ where
     ) -> Result<(), RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .emit(name, key, value)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -372,7 +420,7 @@ where
     ) -> Result<Vec<u8>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .query_service(application_id, query)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -386,7 +434,7 @@ where
     ) -> Result<Vec<u8>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .http_post(&query, content_type, payload)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -397,7 +445,7 @@ where
     fn assert_before(caller: &mut Caller, timestamp: Timestamp) -> Result<(), RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .assert_before(timestamp)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -406,7 +454,7 @@ where
     fn read_data_blob(caller: &mut Caller, hash: CryptoHash) -> Result<Vec<u8>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .read_data_blob(&hash)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -415,7 +463,7 @@ where
     fn assert_data_blob_exists(caller: &mut Caller, hash: CryptoHash) -> Result<(), RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .assert_data_blob_exists(&hash)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -468,7 +516,7 @@ where
     fn get_chain_id(caller: &mut Caller) -> Result<ChainId, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .chain_id()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -477,7 +525,7 @@ where
     fn get_next_block_height(caller: &mut Caller) -> Result<BlockHeight, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .block_height()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -486,7 +534,7 @@ where
     fn get_application_id(caller: &mut Caller) -> Result<ApplicationId, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .application_id()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -495,7 +543,7 @@ where
     fn get_application_creator_chain_id(caller: &mut Caller) -> Result<ChainId, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .application_creator_chain_id()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -504,7 +552,7 @@ where
     fn get_application_parameters(caller: &mut Caller) -> Result<Vec<u8>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .application_parameters()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -513,7 +561,7 @@ where
     fn read_chain_balance(caller: &mut Caller) -> Result<Amount, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .read_chain_balance()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -525,7 +573,7 @@ where
     ) -> Result<Amount, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .read_owner_balance(owner)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -534,7 +582,7 @@ where
     fn read_system_timestamp(caller: &mut Caller) -> Result<Timestamp, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .read_system_timestamp()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -545,7 +593,7 @@ where
     ) -> Result<Vec<(AccountOwner, Amount)>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .read_owner_balances()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -554,7 +602,7 @@ where
     fn read_balance_owners(caller: &mut Caller) -> Result<Vec<AccountOwner>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .read_balance_owners()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -563,7 +611,7 @@ where
     fn schedule_operation(caller: &mut Caller, operation: Vec<u8>) -> Result<(), RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .schedule_operation(operation)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -576,7 +624,7 @@ where
     ) -> Result<Vec<u8>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .try_query_application(application, argument)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -585,7 +633,7 @@ where
     fn fetch_url(caller: &mut Caller, url: String) -> Result<Vec<u8>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .fetch_url(&url)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -598,7 +646,7 @@ where
     ) -> Result<Vec<u8>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .query_service(application_id, query)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -612,7 +660,7 @@ where
     ) -> Result<Vec<u8>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .http_post(&query, content_type, payload)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -621,7 +669,7 @@ where
     fn read_data_blob(caller: &mut Caller, hash: CryptoHash) -> Result<Vec<u8>, RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .read_data_blob(&hash)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -630,7 +678,7 @@ where
     fn assert_data_blob_exists(caller: &mut Caller, hash: CryptoHash) -> Result<(), RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .assert_data_blob_exists(&hash)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -640,7 +688,7 @@ where
     fn assert_before(caller: &mut Caller, timestamp: Timestamp) -> Result<(), RuntimeError> {
         caller
             .user_data_mut()
-            .runtime
+            .runtime_mut()
             .assert_before(timestamp)
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
@@ -673,7 +721,7 @@ where
     fn contains_key_new(caller: &mut Caller, key: Vec<u8>) -> Result<u32, RuntimeError> {
         let mut data = caller.user_data_mut();
         let promise = data
-            .runtime
+            .runtime_mut()
             .contains_key_new(key)
             .map_err(|error| RuntimeError::Custom(error.into()))?;
 
@@ -685,16 +733,20 @@ where
         let mut data = caller.user_data_mut();
         let promise = data.take_promise(promise_id)?;
 
-        data.runtime
+        let contains_key = data
+            .runtime_mut()
             .contains_key_wait(&promise)
-            .map_err(|error| RuntimeError::Custom(error.into()))
+            .map_err(|error| RuntimeError::Custom(error.into()))?;
+
+        tracing::trace!(target: HOST_CALLS_LOG_TARGET, call = "contains-key", keys = 1);
+        Ok(contains_key)
     }
 
     /// Creates a new promise to check if the `keys` are in storage.
     fn contains_keys_new(caller: &mut Caller, keys: Vec<Vec<u8>>) -> Result<u32, RuntimeError> {
         let mut data = caller.user_data_mut();
         let promise = data
-            .runtime
+            .runtime_mut()
             .contains_keys_new(keys)
             .map_err(|error| RuntimeError::Custom(error.into()))?;
 
@@ -706,9 +758,17 @@ where
         let mut data = caller.user_data_mut();
         let promise = data.take_promise(promise_id)?;
 
-        data.runtime
+        let contains_keys = data
+            .runtime_mut()
             .contains_keys_wait(&promise)
-            .map_err(|error| RuntimeError::Custom(error.into()))
+            .map_err(|error| RuntimeError::Custom(error.into()))?;
+
+        tracing::trace!(
+            target: HOST_CALLS_LOG_TARGET,
+            call = "contains-keys",
+            keys = contains_keys.len(),
+        );
+        Ok(contains_keys)
     }
 
     /// Creates a new promise to read multiple entries from storage.
@@ -718,7 +778,7 @@ where
     ) -> Result<u32, RuntimeError> {
         let mut data = caller.user_data_mut();
         let promise = data
-            .runtime
+            .runtime_mut()
             .read_multi_values_bytes_new(keys)
             .map_err(|error| RuntimeError::Custom(error.into()))?;
 
@@ -733,16 +793,25 @@ where
         let mut data = caller.user_data_mut();
         let promise = data.take_promise(promise_id)?;
 
-        data.runtime
+        let values = data
+            .runtime_mut()
             .read_multi_values_bytes_wait(&promise)
-            .map_err(|error| RuntimeError::Custom(error.into()))
+            .map_err(|error| RuntimeError::Custom(error.into()))?;
+
+        tracing::trace!(
+            target: HOST_CALLS_LOG_TARGET,
+            call = "read-multi-values-bytes",
+            keys = values.len(),
+            bytes_read = values.iter().flatten().map(Vec::len).sum::<usize>(),
+        );
+        Ok(values)
     }
 
     /// Creates a new promise to read a single entry from storage.
     fn read_value_bytes_new(caller: &mut Caller, key: Vec<u8>) -> Result<u32, RuntimeError> {
         let mut data = caller.user_data_mut();
         let promise = data
-            .runtime
+            .runtime_mut()
             .read_value_bytes_new(key)
             .map_err(|error| RuntimeError::Custom(error.into()))?;
 
@@ -757,16 +826,25 @@ where
         let mut data = caller.user_data_mut();
         let promise = data.take_promise(promise_id)?;
 
-        data.runtime
+        let value = data
+            .runtime_mut()
             .read_value_bytes_wait(&promise)
-            .map_err(|error| RuntimeError::Custom(error.into()))
+            .map_err(|error| RuntimeError::Custom(error.into()))?;
+
+        tracing::trace!(
+            target: HOST_CALLS_LOG_TARGET,
+            call = "read-value-bytes",
+            keys = 1,
+            bytes_read = value.as_ref().map_or(0, Vec::len),
+        );
+        Ok(value)
     }
 
     /// Creates a new promise to search for keys that start with the `key_prefix`.
     fn find_keys_new(caller: &mut Caller, key_prefix: Vec<u8>) -> Result<u32, RuntimeError> {
         let mut data = caller.user_data_mut();
         let promise = data
-            .runtime
+            .runtime_mut()
             .find_keys_by_prefix_new(key_prefix)
             .map_err(|error| RuntimeError::Custom(error.into()))?;
 
@@ -778,16 +856,25 @@ where
         let mut data = caller.user_data_mut();
         let promise = data.take_promise(promise_id)?;
 
-        data.runtime
+        let keys = data
+            .runtime_mut()
             .find_keys_by_prefix_wait(&promise)
-            .map_err(|error| RuntimeError::Custom(error.into()))
+            .map_err(|error| RuntimeError::Custom(error.into()))?;
+
+        tracing::trace!(
+            target: HOST_CALLS_LOG_TARGET,
+            call = "find-keys",
+            keys = keys.len(),
+            bytes_read = keys.iter().map(Vec::len).sum::<usize>(),
+        );
+        Ok(keys)
     }
 
     /// Creates a new promise to search for entries whose keys that start with the `key_prefix`.
     fn find_key_values_new(caller: &mut Caller, key_prefix: Vec<u8>) -> Result<u32, RuntimeError> {
         let mut data = caller.user_data_mut();
         let promise = data
-            .runtime
+            .runtime_mut()
             .find_key_values_by_prefix_new(key_prefix)
             .map_err(|error| RuntimeError::Custom(error.into()))?;
 
@@ -803,9 +890,21 @@ where
         let mut data = caller.user_data_mut();
         let promise = data.take_promise(promise_id)?;
 
-        data.runtime
+        let key_values = data
+            .runtime_mut()
             .find_key_values_by_prefix_wait(&promise)
-            .map_err(|error| RuntimeError::Custom(error.into()))
+            .map_err(|error| RuntimeError::Custom(error.into()))?;
+
+        tracing::trace!(
+            target: HOST_CALLS_LOG_TARGET,
+            call = "find-key-values",
+            keys = key_values.len(),
+            bytes_read = key_values
+                .iter()
+                .map(|(key, value)| key.len() + value.len())
+                .sum::<usize>(),
+        );
+        Ok(key_values)
     }
 
     /// Writes a batch of `operations` to storage.
@@ -813,8 +912,19 @@ where
         caller: &mut Caller,
         operations: Vec<WriteOperation>,
     ) -> Result<(), RuntimeError> {
-        WriteBatch::write_batch(&mut caller.user_data_mut().runtime, Batch { operations })
-            .map_err(|error| RuntimeError::Custom(error.into()))
+        let batch = Batch { operations };
+        let (operation_count, bytes_written) = (batch.operations.len(), batch.size());
+
+        WriteBatch::write_batch(caller.user_data_mut().runtime_mut(), batch)
+            .map_err(|error| RuntimeError::Custom(error.into()))?;
+
+        tracing::trace!(
+            target: HOST_CALLS_LOG_TARGET,
+            call = "write-batch",
+            operations = operation_count,
+            bytes_written,
+        );
+        Ok(())
     }
 }
 
@@ -14,7 +14,7 @@ use tokio::sync::Mutex;
 use wasm_instrument::{gas_metering, parity_wasm};
 
 use super::{
-    module_cache::ModuleCache,
+    module_cache::{BytecodeKey, ModuleCache},
     system_api::{ContractSystemApi, ServiceSystemApi, SystemApiData, ViewSystemApi, WriteBatch},
     ContractEntrypoints, ServiceEntrypoints, WasmExecutionError,
 };
@@ -67,6 +67,12 @@ impl WasmContractModule {
             .map_err(WasmExecutionError::LoadContractModule)?;
         Ok(WasmContractModule::Wasmer { engine, module })
     }
+
+    /// Returns `true` if the contract bytecode with the given `key` is already compiled and
+    /// cached by Wasmer.
+    pub async fn is_cached_wasmer(key: &BytecodeKey) -> bool {
+        CONTRACT_CACHE.lock().await.contains(key)
+    }
 }
 
 impl<Runtime> WasmerContractInstance<Runtime>
@@ -102,6 +108,12 @@ impl WasmServiceModule {
             .map_err(WasmExecutionError::LoadServiceModule)?;
         Ok(WasmServiceModule::Wasmer { module })
     }
+
+    /// Returns `true` if the service bytecode with the given `key` is already compiled and
+    /// cached by Wasmer.
+    pub async fn is_cached_wasmer(key: &BytecodeKey) -> bool {
+        SERVICE_CACHE.lock().await.contains(key)
+    }
 }
 
 impl<Runtime> WasmerServiceInstance<Runtime>
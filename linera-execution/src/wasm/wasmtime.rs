@@ -3,42 +3,407 @@
 
 //! Code specific to the usage of the [Wasmtime](https://wasmtime.dev/) runtime.
 
-use std::sync::LazyLock;
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::HashMap,
+    marker::PhantomData,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, LazyLock, OnceLock, Weak,
+    },
+};
 
 use linera_base::data_types::Bytecode;
 use linera_witty::{wasmtime::EntrypointInstance, ExportTo, Instance};
-use tokio::sync::Mutex;
-use wasmtime::{AsContextMut, Config, Engine, Linker, Module, Store};
+use wasmparser::{Validator, WasmFeatures};
+use wasmtime::{
+    AsContextMut, Config, Engine, InstanceAllocationStrategy, Linker, Memory, Module, OptLevel,
+    ResourceLimiter, Store, WasmBacktrace, WasmBacktraceDetails,
+};
 
 use super::{
-    module_cache::ModuleCache,
+    instance_state::{export_instance_state, InstanceState},
+    module_artifacts::ModuleArtifacts,
+    module_cache::{
+        BytecodeKey, ModuleCache, SharedModuleCache, DEFAULT_MAX_CACHE_ENTRIES,
+        DEFAULT_MAX_CACHE_SIZE,
+    },
     system_api::{ContractSystemApi, ServiceSystemApi, SystemApiData, ViewSystemApi, WriteBatch},
     ContractEntrypoints, ServiceEntrypoints, WasmExecutionError,
 };
 use crate::{
+    runtime::ContractSyncRuntime,
     wasm::{WasmContractModule, WasmServiceModule},
-    ContractRuntime, ExecutionError, FinalizeContext, MessageContext, OperationContext,
-    QueryContext, ServiceRuntime,
+    BaseRuntime, ContractRuntime, ContractSyncRuntimeHandle, ExecutionError, FinalizeContext,
+    MessageContext, OperationContext, QueryContext, ResourceControlPolicy, ServiceRuntime,
+    ServiceSyncRuntimeHandle,
 };
 
-/// An [`Engine`] instance configured to run application contracts.
-static CONTRACT_ENGINE: LazyLock<Engine> = LazyLock::new(|| {
-    let mut config = Config::default();
-    config
-        .consume_fuel(true)
-        .cranelift_nan_canonicalization(true);
+/// The [`Engine`] instances used to run applications, created from a [`WasmRuntimeConfig`]
+/// for each [`EngineProfile`].
+static ENGINES: OnceLock<Engines> = OnceLock::new();
+
+/// Settings used to build the Wasmtime [`Engine`]s that run application contracts and services.
+///
+/// The configuration can be supplied once at startup with [`WasmRuntimeConfig::install`],
+/// otherwise the [`Default`] configuration is used. Settings that are required for
+/// deterministic contract execution are always enforced, regardless of the values here.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WasmRuntimeConfig {
+    /// Enables the WebAssembly SIMD proposal for the modules that use it, and the relaxed SIMD
+    /// proposal for the services that use it.
+    pub simd: bool,
+    /// Uses the pooling instance allocator instead of allocating instances on demand.
+    pub pooling_allocator: bool,
+    /// The optimization level used by Cranelift when compiling modules.
+    pub optimization_level: OptLevel,
+    /// The maximum number of idle service instances kept for reuse per module, or zero to
+    /// instantiate every service from scratch. See [`ServiceInstancePool`].
+    pub service_instance_pool_size: usize,
+    /// The maximum number of idle contract instances kept for reuse per module, or zero to
+    /// instantiate every contract from scratch. See [`ContractInstancePool`].
+    pub contract_instance_pool_size: usize,
+    /// Executes again the operations that trap, with an unoptimized build of the contract that
+    /// reports detailed backtraces, and attaches the backtrace to the error. This only happens
+    /// on failure, but is slow and keeps the bytecode of every contract in memory, so it is
+    /// meant for debugging applications.
+    pub trap_diagnostics: bool,
+    /// The maximum number of compiled modules kept in each of the contract and service caches.
+    /// The least recently used modules are evicted, and compiled again when they are loaded.
+    pub module_cache_max_entries: usize,
+    /// The maximum total size of the bytecodes of the modules kept in each of the contract and
+    /// service caches, in bytes.
+    pub module_cache_max_bytes: u64,
+    /// A directory where the compiled modules are stored, so that they are loaded from disk
+    /// instead of being compiled again after a restart. Each artifact is checked against its
+    /// checksum before being loaded, and compiled again if it is missing or invalid.
+    ///
+    /// Loading an artifact runs the native code in it, so this directory must only be writable
+    /// by the node itself.
+    pub module_artifact_directory: Option<PathBuf>,
+}
+
+impl Default for WasmRuntimeConfig {
+    fn default() -> Self {
+        WasmRuntimeConfig {
+            simd: true,
+            pooling_allocator: false,
+            optimization_level: OptLevel::Speed,
+            service_instance_pool_size: 0,
+            contract_instance_pool_size: 0,
+            trap_diagnostics: false,
+            module_cache_max_entries: DEFAULT_MAX_CACHE_ENTRIES,
+            module_cache_max_bytes: DEFAULT_MAX_CACHE_SIZE,
+            module_artifact_directory: None,
+        }
+    }
+}
+
+impl WasmRuntimeConfig {
+    /// Builds the engines from this configuration and installs them for the rest of the
+    /// process, along with the sizes of the module caches.
+    ///
+    /// Fails if the engines can't be created, or if they have already been created, either by
+    /// a previous call or because an application was already loaded with the default
+    /// configuration.
+    pub fn install(self) -> Result<(), WasmExecutionError> {
+        let engines = Engines::new(&self)?;
+        ENGINES
+            .set(engines)
+            .map_err(|_| WasmExecutionError::WasmtimeEnginesAlreadyConfigured)
+    }
+
+    /// Creates an empty [`ModuleCache`] with the configured capacity.
+    fn module_cache<CachedModule>(&self) -> ModuleCache<CachedModule> {
+        ModuleCache::new(self.module_cache_max_entries, self.module_cache_max_bytes)
+    }
 
-    Engine::new(&config).expect("Failed to create Wasmtime `Engine` for contracts")
-});
+    /// Creates the [`Config`] shared by contracts and services with the `profile`.
+    fn base_config(&self, profile: EngineProfile) -> Config {
+        let mut config = Config::default();
+        config
+            .wasm_simd(self.simd && profile.simd)
+            .cranelift_opt_level(self.optimization_level.clone());
 
-/// An [`Engine`] instance configured to run application services.
-static SERVICE_ENGINE: LazyLock<Engine> = LazyLock::new(Engine::default);
+        if self.pooling_allocator {
+            config.allocation_strategy(InstanceAllocationStrategy::pooling());
+        }
+
+        config
+    }
+
+    /// Creates the [`Config`] for contracts with the `profile`, enforcing the settings needed
+    /// for determinism.
+    fn contract_config(&self, profile: EngineProfile) -> Config {
+        let mut config = self.base_config(profile);
+        config
+            .consume_fuel(true)
+            .cranelift_nan_canonicalization(true)
+            .wasm_relaxed_simd(false);
+        config
+    }
+
+    /// Creates the [`Config`] for re-executing contracts with the `profile` to diagnose traps.
+    ///
+    /// Only code generation differs from [`Self::contract_config`], so the fuel consumed is
+    /// the same and a contract running out of fuel traps at the same place.
+    fn debug_contract_config(&self, profile: EngineProfile) -> Config {
+        let mut config = self.contract_config(profile);
+        config
+            .cranelift_opt_level(OptLevel::None)
+            .wasm_backtrace(true)
+            .wasm_backtrace_details(WasmBacktraceDetails::Enable);
+        config
+    }
+
+    /// Creates the [`Config`] for services with the `profile`, metering fuel so that queries
+    /// are bounded by the budget of their runtime.
+    fn service_config(&self, profile: EngineProfile) -> Config {
+        let mut config = self.base_config(profile);
+        config
+            .consume_fuel(true)
+            .wasm_relaxed_simd(self.simd && profile.relaxed_simd);
+        config
+    }
+}
+
+/// Limits on the resources that the Wasm instances of a contract or service can use, set by the
+/// [`ResourceControlPolicy`] of the chain.
+///
+/// The limits are installed in the [`Store`] of every instance before it is instantiated, and
+/// updated from the policy of the runtime before each entrypoint call. Growing a memory or a
+/// table beyond its limit traps with [`WasmExecutionError::MemoryLimitExceeded`] instead of
+/// failing, so that a module can't keep trying until it runs out of fuel. A module whose initial
+/// memories or tables exceed the limits, or creating too many instances, fails to instantiate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// The maximum size of a linear memory, in bytes.
+    pub max_memory_bytes: u64,
+    /// The maximum number of elements in a table.
+    pub max_table_elements: u64,
+    /// The maximum number of instances in a [`Store`].
+    pub max_instances: u64,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        ResourceLimits::from(&ResourceControlPolicy::default())
+    }
+}
+
+impl From<&ResourceControlPolicy> for ResourceLimits {
+    fn from(policy: &ResourceControlPolicy) -> Self {
+        ResourceLimits {
+            max_memory_bytes: policy.maximum_wasm_memory_bytes,
+            max_table_elements: policy.maximum_wasm_table_elements,
+            max_instances: policy.maximum_wasm_instances,
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Allows growing to the `desired` size if it is at most `limit`, and traps otherwise.
+    fn check_growth(desired: usize, limit: u64) -> anyhow::Result<bool> {
+        if u64::try_from(desired).is_ok_and(|desired| desired <= limit) {
+            Ok(true)
+        } else {
+            Err(WasmExecutionError::MemoryLimitExceeded.into())
+        }
+    }
+}
+
+impl ResourceLimiter for ResourceLimits {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        Self::check_growth(desired, self.max_memory_bytes)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        Self::check_growth(desired, self.max_table_elements)
+    }
+
+    fn instances(&self) -> usize {
+        usize::try_from(self.max_instances).unwrap_or(usize::MAX)
+    }
+}
+
+/// The WebAssembly features used by an application module, which select the [`Engine`] that
+/// compiles and runs it.
+///
+/// Profiles only toggle features that a module either uses or doesn't, so they don't change
+/// how a module executes. The settings needed for deterministic contract execution are the
+/// same for every profile, which is why contracts can't use relaxed SIMD.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct EngineProfile {
+    /// The module uses the WebAssembly SIMD proposal.
+    pub simd: bool,
+    /// The module uses the WebAssembly relaxed SIMD proposal, whose results may differ between
+    /// hosts. Only services may use it.
+    pub relaxed_simd: bool,
+}
+
+impl EngineProfile {
+    /// The profiles to try for a module, from the fewest to the most features.
+    const CANDIDATES: [EngineProfile; 3] = [
+        EngineProfile {
+            simd: false,
+            relaxed_simd: false,
+        },
+        EngineProfile {
+            simd: true,
+            relaxed_simd: false,
+        },
+        EngineProfile {
+            simd: true,
+            relaxed_simd: true,
+        },
+    ];
+
+    /// Returns the profile with the fewest features that the module in `bytecode` needs.
+    ///
+    /// A module that isn't valid with any profile gets the SIMD profile, which was the
+    /// configuration used for all modules before profiles existed, so that compiling it
+    /// reports the error.
+    pub fn for_bytecode(bytecode: &Bytecode) -> Self {
+        Self::CANDIDATES
+            .into_iter()
+            .find(|profile| profile.validates(bytecode))
+            .unwrap_or(Self::CANDIDATES[1])
+    }
+
+    /// Returns `true` if the module in `bytecode` only uses the features of this profile.
+    fn validates(&self, bytecode: &Bytecode) -> bool {
+        let features = WasmFeatures {
+            simd: self.simd,
+            relaxed_simd: self.relaxed_simd,
+            ..WasmFeatures::default()
+        };
+        Validator::new_with_features(features)
+            .validate_all(bytecode.as_ref())
+            .is_ok()
+    }
+}
+
+/// The [`Engine`]s used to run application contracts and services, created on demand for each
+/// [`EngineProfile`].
+struct Engines {
+    config: WasmRuntimeConfig,
+    contract: std::sync::Mutex<HashMap<EngineProfile, Engine>>,
+    debug_contract: std::sync::Mutex<HashMap<EngineProfile, Engine>>,
+    service: std::sync::Mutex<HashMap<EngineProfile, Engine>>,
+    artifacts: Option<ModuleArtifacts>,
+}
+
+impl Engines {
+    /// Creates the [`Engines`] described by the provided `config`.
+    ///
+    /// The engines for the default [`EngineProfile`] are created immediately, so that an
+    /// invalid `config` is reported here.
+    fn new(config: &WasmRuntimeConfig) -> Result<Self, WasmExecutionError> {
+        let profile = EngineProfile::default();
+        let contract = Engine::new(&config.contract_config(profile))
+            .map_err(WasmExecutionError::CreateWasmtimeEngine)?;
+        let service = Engine::new(&config.service_config(profile))
+            .map_err(WasmExecutionError::CreateWasmtimeEngine)?;
+
+        Ok(Engines {
+            config: config.clone(),
+            contract: std::sync::Mutex::new(HashMap::from([(profile, contract)])),
+            debug_contract: std::sync::Mutex::default(),
+            service: std::sync::Mutex::new(HashMap::from([(profile, service)])),
+            artifacts: config
+                .module_artifact_directory
+                .clone()
+                .map(ModuleArtifacts::new),
+        })
+    }
+
+    /// Returns the [`Engine`] for contracts with the `profile`.
+    fn contract(&self, profile: EngineProfile) -> Result<Engine, WasmExecutionError> {
+        Self::get_or_create(
+            &self.contract,
+            self.config.contract_config(profile),
+            profile,
+        )
+    }
+
+    /// Returns the [`Engine`] for re-executing contracts with the `profile` to diagnose traps.
+    fn debug_contract(&self, profile: EngineProfile) -> Result<Engine, WasmExecutionError> {
+        Self::get_or_create(
+            &self.debug_contract,
+            self.config.debug_contract_config(profile),
+            profile,
+        )
+    }
+
+    /// Returns the [`Engine`] for services with the `profile`.
+    fn service(&self, profile: EngineProfile) -> Result<Engine, WasmExecutionError> {
+        Self::get_or_create(&self.service, self.config.service_config(profile), profile)
+    }
+
+    /// Returns the [`Engine`] for the `profile` from `engines`, creating it with `config` if
+    /// it doesn't exist yet.
+    fn get_or_create(
+        engines: &std::sync::Mutex<HashMap<EngineProfile, Engine>>,
+        config: Config,
+        profile: EngineProfile,
+    ) -> Result<Engine, WasmExecutionError> {
+        let mut engines = engines.lock().expect("Engine lock should not be poisoned");
+        if let Some(engine) = engines.get(&profile) {
+            return Ok(engine.clone());
+        }
+        let engine = Engine::new(&config).map_err(WasmExecutionError::CreateWasmtimeEngine)?;
+        engines.insert(profile, engine.clone());
+        Ok(engine)
+    }
+
+    /// Returns the [`Module`] compiled from the `bytecode` with the given `key` for `engine`,
+    /// loading it from its artifact if a [`WasmRuntimeConfig::module_artifact_directory`] is
+    /// configured.
+    fn load_or_compile(
+        &self,
+        engine: &Engine,
+        key: &BytecodeKey,
+        bytecode: Bytecode,
+    ) -> anyhow::Result<Module> {
+        match &self.artifacts {
+            Some(artifacts) => {
+                artifacts.load_or_compile(engine, key, || Module::new(engine, bytecode))
+            }
+            None => Module::new(engine, bytecode),
+        }
+    }
+
+    /// Returns the installed [`Engines`], creating them with the default configuration if none
+    /// was installed.
+    fn get() -> &'static Self {
+        ENGINES.get_or_init(|| {
+            Engines::new(&WasmRuntimeConfig::default())
+                .expect("Failed to create Wasmtime `Engine`s with the default configuration")
+        })
+    }
+}
 
-/// A cache of compiled contract modules.
-static CONTRACT_CACHE: LazyLock<Mutex<ModuleCache<Module>>> = LazyLock::new(Mutex::default);
+/// A cache of compiled contract modules, along with their pools of idle instances.
+static CONTRACT_CACHE: LazyLock<SharedModuleCache<Arc<ContractInstancePool>>> =
+    LazyLock::new(|| {
+        SharedModuleCache::new(Engines::get().config.module_cache(), compile_contract)
+    });
 
-/// A cache of compiled service modules.
-static SERVICE_CACHE: LazyLock<Mutex<ModuleCache<Module>>> = LazyLock::new(Mutex::default);
+/// A cache of compiled service modules, along with their pools of idle instances.
+static SERVICE_CACHE: LazyLock<SharedModuleCache<Arc<ServiceInstancePool>>> =
+    LazyLock::new(|| SharedModuleCache::new(Engines::get().config.module_cache(), compile_service));
 
 /// Type representing a running [Wasmtime](https://wasmtime.dev/) contract.
 ///
@@ -51,8 +416,8 @@ where
     /// The Wasm module instance.
     instance: EntrypointInstance<SystemApiData<Runtime>>,
 
-    /// The starting amount of fuel.
-    initial_fuel: u64,
+    /// Where to record the fuel consumed by each entrypoint call, if it is being traced.
+    fuel_trace: Option<Arc<std::sync::Mutex<FuelTrace>>>,
 }
 
 // TODO(#1967): Remove once fuel consumption is instrumented in the bytecode
@@ -60,31 +425,122 @@ impl<Runtime> WasmtimeContractInstance<Runtime>
 where
     Runtime: ContractRuntime,
 {
-    fn configure_initial_fuel(&mut self) -> Result<(), ExecutionError> {
-        let runtime = &mut self.instance.user_data_mut().runtime_mut();
+    /// Gives the instance all the fuel remaining in the runtime and the resource limits of its
+    /// policy, before an entrypoint call. Returns the fuel.
+    fn configure_call(&mut self) -> Result<u64, ExecutionError> {
+        let user_data = self.instance.user_data_mut();
+        let runtime = user_data.runtime_mut();
         let fuel = runtime.remaining_fuel()?;
-        let mut context = self.instance.as_context_mut();
-
-        self.initial_fuel = fuel;
-
-        context
+        let limits = ResourceLimits::from(&*runtime.resource_control_policy()?);
+        *user_data.resource_limits_mut() = limits;
+        self.instance
+            .as_context_mut()
             .set_fuel(fuel)
             .expect("Fuel consumption should be enabled");
-
-        Ok(())
+        Ok(fuel)
     }
 
-    fn persist_remaining_fuel(&mut self) -> Result<(), ExecutionError> {
-        let remaining_fuel = self
-            .instance
-            .as_context_mut()
-            .get_fuel()
-            .expect("Failed to read remaining fuel");
+    /// Charges the runtime for the fuel consumed by a call to the `entrypoint`, which started
+    /// with `initial_fuel`.
+    fn persist_remaining_fuel(
+        &mut self,
+        initial_fuel: u64,
+        entrypoint: &'static str,
+    ) -> Result<(), ExecutionError> {
+        let fuel = consumed_fuel(&mut self.instance, initial_fuel)?;
         let runtime = &mut self.instance.user_data_mut().runtime_mut();
 
-        assert!(self.initial_fuel >= remaining_fuel);
+        if let Some(fuel_trace) = &self.fuel_trace {
+            fuel_trace
+                .lock()
+                .expect("Fuel trace lock should not be poisoned")
+                .calls
+                .push(FuelTraceEntry { entrypoint, fuel });
+        }
 
-        runtime.consume_fuel(self.initial_fuel - remaining_fuel)
+        runtime.consume_fuel(fuel)
+    }
+}
+
+/// The fuel consumed by each call into the entrypoints of a contract, in the order of the calls.
+///
+/// Executing the same calls must consume the same fuel on every node, so two traces of the same
+/// execution that differ indicate a source of non-determinism.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FuelTrace {
+    /// The recorded entrypoint calls.
+    pub calls: Vec<FuelTraceEntry>,
+}
+
+/// The fuel consumed by a single call into a contract entrypoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuelTraceEntry {
+    /// The name of the entrypoint that was called.
+    pub entrypoint: &'static str,
+    /// The fuel consumed by the call.
+    pub fuel: u64,
+}
+
+/// A contract module that records a [`FuelTrace`] of the Wasmtime instances it creates.
+///
+/// This is a testing harness to catch non-determinism introduced for instance by changes to the
+/// engine configuration: record a trace while executing some operations, then execute the same
+/// operations with a new [`FuelTracingContractModule`] and [`verify`][Self::verify] that its
+/// trace matches the first one.
+#[cfg(with_testing)]
+#[derive(Clone)]
+pub struct FuelTracingContractModule {
+    module: Module,
+    fuel_trace: Arc<std::sync::Mutex<FuelTrace>>,
+}
+
+#[cfg(with_testing)]
+impl FuelTracingContractModule {
+    /// Creates a [`FuelTracingContractModule`] for a contract compiled with Wasmtime.
+    ///
+    /// Returns `None` if `contract_module` does not use Wasmtime.
+    pub fn new(contract_module: &WasmContractModule) -> Option<Self> {
+        #[allow(unreachable_patterns)]
+        match contract_module {
+            WasmContractModule::Wasmtime { instances } => Some(FuelTracingContractModule {
+                module: instances.module.clone(),
+                fuel_trace: Arc::default(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the fuel recorded so far for the instances created by this module.
+    pub fn fuel_trace(&self) -> FuelTrace {
+        self.fuel_trace
+            .lock()
+            .expect("Fuel trace lock should not be poisoned")
+            .clone()
+    }
+
+    /// Checks that the fuel recorded so far is exactly the `expected` trace.
+    pub fn verify(&self, expected: &FuelTrace) -> Result<(), WasmExecutionError> {
+        let actual = self.fuel_trace();
+        if actual == *expected {
+            Ok(())
+        } else {
+            Err(WasmExecutionError::NonDeterministicFuel {
+                expected: expected.clone(),
+                actual,
+            })
+        }
+    }
+}
+
+#[cfg(with_testing)]
+impl crate::UserContractModule for FuelTracingContractModule {
+    fn instantiate(
+        &self,
+        runtime: ContractSyncRuntimeHandle,
+    ) -> Result<crate::UserContractInstance, ExecutionError> {
+        let mut instance = WasmtimeContractInstance::prepare(&self.module, runtime)?;
+        instance.fuel_trace = Some(self.fuel_trace.clone());
+        Ok(Box::new(instance))
     }
 }
 
@@ -94,39 +550,138 @@ pub struct WasmtimeServiceInstance<Runtime> {
     instance: EntrypointInstance<SystemApiData<Runtime>>,
 }
 
+impl<Runtime> WasmtimeServiceInstance<Runtime>
+where
+    Runtime: ServiceRuntime,
+{
+    /// Gives the instance all the fuel remaining in the runtime and the resource limits of its
+    /// policy, before a query. Returns the fuel.
+    fn configure_call(&mut self) -> Result<u64, ExecutionError> {
+        let user_data = self.instance.user_data_mut();
+        let runtime = user_data.runtime_mut();
+        let fuel = runtime.remaining_fuel()?;
+        let limits = ResourceLimits::from(&*runtime.resource_control_policy()?);
+        *user_data.resource_limits_mut() = limits;
+        self.instance
+            .as_context_mut()
+            .set_fuel(fuel)
+            .expect("Fuel consumption should be enabled");
+        Ok(fuel)
+    }
+
+    /// Charges the runtime for the fuel consumed by a query, which started with
+    /// `initial_fuel`.
+    fn persist_remaining_fuel(&mut self, initial_fuel: u64) -> Result<(), ExecutionError> {
+        let fuel = consumed_fuel(&mut self.instance, initial_fuel)?;
+        self.instance
+            .user_data_mut()
+            .runtime_mut()
+            .consume_fuel(fuel)
+    }
+}
+
+/// Returns the fuel consumed by an `instance` since it was given `initial_fuel`.
+///
+/// Fails instead of panicking if the instance has more fuel left than it started with, so that
+/// an accounting error only aborts the transaction.
+fn consumed_fuel<UserData>(
+    instance: &mut EntrypointInstance<UserData>,
+    initial_fuel: u64,
+) -> Result<u64, ExecutionError> {
+    let remaining_fuel = instance
+        .as_context_mut()
+        .get_fuel()
+        .expect("Fuel consumption should be enabled");
+    initial_fuel
+        .checked_sub(remaining_fuel)
+        .ok_or(ExecutionError::FuelAccounting {
+            initial: initial_fuel,
+            remaining: remaining_fuel,
+        })
+}
+
 impl WasmContractModule {
     /// Creates a new [`WasmContractModule`] using Wasmtime with the provided bytecodes.
     pub async fn from_wasmtime(contract_bytecode: Bytecode) -> Result<Self, WasmExecutionError> {
-        let mut contract_cache = CONTRACT_CACHE.lock().await;
-        let module = contract_cache
-            .get_or_insert_with(contract_bytecode, |bytecode| {
-                Module::new(&CONTRACT_ENGINE, bytecode)
-            })
-            .map_err(WasmExecutionError::LoadContractModule)?;
-        Ok(WasmContractModule::Wasmtime { module })
+        let key = BytecodeKey::new(&contract_bytecode);
+        let instances = CONTRACT_CACHE
+            .get_or_compile(key, contract_bytecode)
+            .await?;
+        Ok(WasmContractModule::Wasmtime { instances })
+    }
+
+    /// Returns `true` if the contract bytecode with the given `key` is already compiled and
+    /// cached by Wasmtime.
+    pub async fn is_cached_wasmtime(key: &BytecodeKey) -> bool {
+        CONTRACT_CACHE.contains(key).await
+    }
+
+    /// Compiles the contract `bytecodes` that aren't cached yet in parallel, and adds them to
+    /// the cache.
+    pub async fn warm_up_wasmtime(bytecodes: Vec<Bytecode>) -> Result<(), WasmExecutionError> {
+        CONTRACT_CACHE.warm_up(bytecodes).await
     }
 }
 
+/// Compiles a contract `bytecode` with the engine of its [`EngineProfile`], checking that all its
+/// imports are provided by the system API, and creates the pool for its instances.
+fn compile_contract(
+    key: BytecodeKey,
+    bytecode: Bytecode,
+) -> Result<Arc<ContractInstancePool>, WasmExecutionError> {
+    let engines = Engines::get();
+    let profile = EngineProfile::for_bytecode(&bytecode);
+    let engine = engines.contract(profile)?;
+    let diagnostics = engines
+        .config
+        .trap_diagnostics
+        .then(|| Arc::new(TrapDiagnostics::new(bytecode.clone())));
+    // Export the globals and tables, so that pooled instances can restore them.
+    let bytecode = export_instance_state(&bytecode)
+        .map_err(|error| WasmExecutionError::LoadContractModule(error.into()))?;
+    let module = engines
+        .load_or_compile(&engine, &key, bytecode)
+        .map_err(WasmExecutionError::LoadContractModule)?;
+    let linker = WasmtimeContractInstance::<ContractSyncRuntimeHandle>::linker(&engine)?;
+    check_imports(&linker, &module, SystemApiData::detached())?;
+    let mut pool = ContractInstancePool::new(module, engines.config.contract_instance_pool_size);
+    pool.diagnostics = diagnostics;
+    Ok(Arc::new(pool))
+}
+
 impl<Runtime> WasmtimeContractInstance<Runtime>
 where
     Runtime: ContractRuntime + WriteBatch + 'static,
 {
-    /// Prepares a runtime instance to call into the Wasm contract.
-    pub fn prepare(contract_module: &Module, runtime: Runtime) -> Result<Self, WasmExecutionError> {
-        let mut linker = Linker::new(&CONTRACT_ENGINE);
+    /// Creates a [`Linker`] for the `engine` with the system APIs available to contracts.
+    fn linker(engine: &Engine) -> Result<Linker<SystemApiData<Runtime>>, WasmExecutionError> {
+        let mut linker = Linker::new(engine);
 
         ContractSystemApi::export_to(&mut linker)?;
         ViewSystemApi::export_to(&mut linker)?;
 
-        let user_data = SystemApiData::new(runtime);
-        let mut store = Store::new(&CONTRACT_ENGINE, user_data);
+        Ok(linker)
+    }
+
+    /// Prepares a runtime instance to call into the Wasm contract.
+    pub fn prepare(contract_module: &Module, runtime: Runtime) -> Result<Self, WasmExecutionError> {
+        let linker = Self::linker(contract_module.engine())?;
+        let mut user_data = SystemApiData::new(runtime);
+        // The limits already apply to the instantiation, e.g. to the initial memory size.
+        let policy = user_data
+            .runtime_mut()
+            .resource_control_policy()
+            .map_err(|error| WasmExecutionError::LoadContractModule(error.into()))?;
+        *user_data.resource_limits_mut() = ResourceLimits::from(&*policy);
+        let mut store = Store::new(contract_module.engine(), user_data);
+        store.limiter(|user_data| user_data.resource_limits_mut());
         let instance = linker
             .instantiate(&mut store, contract_module)
-            .map_err(WasmExecutionError::LoadContractModule)?;
+            .map_err(|error| instantiation_error(error, WasmExecutionError::LoadContractModule))?;
 
         Ok(Self {
             instance: EntrypointInstance::new(instance, store),
-            initial_fuel: 0,
+            fuel_trace: None,
         })
     }
 }
@@ -134,13 +689,77 @@ where
 impl WasmServiceModule {
     /// Creates a new [`WasmServiceModule`] using Wasmtime with the provided bytecodes.
     pub async fn from_wasmtime(service_bytecode: Bytecode) -> Result<Self, WasmExecutionError> {
-        let mut service_cache = SERVICE_CACHE.lock().await;
-        let module = service_cache
-            .get_or_insert_with(service_bytecode, |bytecode| {
-                Module::new(&SERVICE_ENGINE, bytecode)
-            })
-            .map_err(WasmExecutionError::LoadServiceModule)?;
-        Ok(WasmServiceModule::Wasmtime { module })
+        let key = BytecodeKey::new(&service_bytecode);
+        let instances = SERVICE_CACHE.get_or_compile(key, service_bytecode).await?;
+        Ok(WasmServiceModule::Wasmtime { instances })
+    }
+
+    /// Returns `true` if the service bytecode with the given `key` is already compiled and
+    /// cached by Wasmtime.
+    pub async fn is_cached_wasmtime(key: &BytecodeKey) -> bool {
+        SERVICE_CACHE.contains(key).await
+    }
+
+    /// Compiles the service `bytecodes` that aren't cached yet in parallel, and adds them to
+    /// the cache.
+    pub async fn warm_up_wasmtime(bytecodes: Vec<Bytecode>) -> Result<(), WasmExecutionError> {
+        SERVICE_CACHE.warm_up(bytecodes).await
+    }
+}
+
+/// Compiles a service `bytecode` with the engine of its [`EngineProfile`], checking that all its
+/// imports are provided by the system API, and creates the pool for its instances.
+fn compile_service(
+    key: BytecodeKey,
+    bytecode: Bytecode,
+) -> Result<Arc<ServiceInstancePool>, WasmExecutionError> {
+    let engines = Engines::get();
+    let engine = engines.service(EngineProfile::for_bytecode(&bytecode))?;
+    // Export the globals and tables, so that pooled instances can restore them.
+    let bytecode = export_instance_state(&bytecode)
+        .map_err(|error| WasmExecutionError::LoadServiceModule(error.into()))?;
+    let module = engines
+        .load_or_compile(&engine, &key, bytecode)
+        .map_err(WasmExecutionError::LoadServiceModule)?;
+    let linker = WasmtimeServiceInstance::<ServiceSyncRuntimeHandle>::linker(&engine)?;
+    check_imports(&linker, &module, SystemApiData::detached())?;
+    Ok(Arc::new(ServiceInstancePool::new(
+        module,
+        engines.config.service_instance_pool_size,
+    )))
+}
+
+/// Converts an `error` from instantiating a module, keeping the error raised by the
+/// [`ResourceLimits`] if the module's initial memories or tables exceed them, and using
+/// `load_error` otherwise.
+fn instantiation_error(
+    error: anyhow::Error,
+    load_error: fn(anyhow::Error) -> WasmExecutionError,
+) -> WasmExecutionError {
+    error
+        .downcast::<WasmExecutionError>()
+        .unwrap_or_else(load_error)
+}
+
+/// Checks that the `linker` provides all the functions imported by the `module`, so that a
+/// module using an unavailable host function is rejected when it's loaded rather than when
+/// it's instantiated.
+fn check_imports<UserData>(
+    linker: &Linker<UserData>,
+    module: &Module,
+    user_data: UserData,
+) -> Result<(), WasmExecutionError> {
+    let mut store = Store::new(module.engine(), user_data);
+    let names = module
+        .imports()
+        .filter(|import| linker.get_by_import(&mut store, import).is_none())
+        .map(|import| format!("{}#{}", import.module(), import.name()))
+        .collect::<Vec<_>>();
+
+    if names.is_empty() {
+        Ok(())
+    } else {
+        Err(WasmExecutionError::MissingHostImports { names })
     }
 }
 
@@ -148,18 +767,31 @@ impl<Runtime> WasmtimeServiceInstance<Runtime>
 where
     Runtime: ServiceRuntime + WriteBatch + 'static,
 {
-    /// Prepares a runtime instance to call into the Wasm service.
-    pub fn prepare(service_module: &Module, runtime: Runtime) -> Result<Self, WasmExecutionError> {
-        let mut linker = Linker::new(&SERVICE_ENGINE);
+    /// Creates a [`Linker`] for the `engine` with the system APIs available to services.
+    fn linker(engine: &Engine) -> Result<Linker<SystemApiData<Runtime>>, WasmExecutionError> {
+        let mut linker = Linker::new(engine);
 
         ServiceSystemApi::export_to(&mut linker)?;
         ViewSystemApi::export_to(&mut linker)?;
 
-        let user_data = SystemApiData::new(runtime);
-        let mut store = Store::new(&SERVICE_ENGINE, user_data);
+        Ok(linker)
+    }
+
+    /// Prepares a runtime instance to call into the Wasm service.
+    pub fn prepare(service_module: &Module, runtime: Runtime) -> Result<Self, WasmExecutionError> {
+        let linker = Self::linker(service_module.engine())?;
+        let mut user_data = SystemApiData::new(runtime);
+        // The limits already apply to the instantiation, e.g. to the initial memory size.
+        let policy = user_data
+            .runtime_mut()
+            .resource_control_policy()
+            .map_err(|error| WasmExecutionError::LoadServiceModule(error.into()))?;
+        *user_data.resource_limits_mut() = ResourceLimits::from(&*policy);
+        let mut store = Store::new(service_module.engine(), user_data);
+        store.limiter(|user_data| user_data.resource_limits_mut());
         let instance = linker
             .instantiate(&mut store, service_module)
-            .map_err(WasmExecutionError::LoadServiceModule)?;
+            .map_err(|error| instantiation_error(error, WasmExecutionError::LoadServiceModule))?;
 
         Ok(Self {
             instance: EntrypointInstance::new(instance, store),
@@ -167,6 +799,485 @@ where
     }
 }
 
+/// A Wasmtime instance that an [`InstancePool`] can keep idle and reuse.
+pub(crate) trait PoolableInstance: Sized + 'static {
+    /// The runtime attached to the instance while it is used.
+    type Runtime: 'static;
+
+    /// Links and instantiates the `module` with the provided `runtime`.
+    fn instantiate_module(
+        module: &Module,
+        runtime: Self::Runtime,
+    ) -> Result<Self, WasmExecutionError>;
+
+    /// Returns the underlying Wasm instance.
+    fn wasm_instance(&mut self) -> &mut EntrypointInstance<SystemApiData<Self::Runtime>>;
+}
+
+impl PoolableInstance for WasmtimeContractInstance<ContractSyncRuntimeHandle> {
+    type Runtime = ContractSyncRuntimeHandle;
+
+    fn instantiate_module(
+        module: &Module,
+        runtime: Self::Runtime,
+    ) -> Result<Self, WasmExecutionError> {
+        Self::prepare(module, runtime)
+    }
+
+    fn wasm_instance(&mut self) -> &mut EntrypointInstance<SystemApiData<Self::Runtime>> {
+        &mut self.instance
+    }
+}
+
+impl PoolableInstance for WasmtimeServiceInstance<ServiceSyncRuntimeHandle> {
+    type Runtime = ServiceSyncRuntimeHandle;
+
+    fn instantiate_module(
+        module: &Module,
+        runtime: Self::Runtime,
+    ) -> Result<Self, WasmExecutionError> {
+        Self::prepare(module, runtime)
+    }
+
+    fn wasm_instance(&mut self) -> &mut EntrypointInstance<SystemApiData<Self::Runtime>> {
+        &mut self.instance
+    }
+}
+
+/// Returns the exported linear memory of an `instance`, if there is one.
+fn memory<UserData>(instance: &mut EntrypointInstance<UserData>) -> Option<Memory> {
+    instance.load_export("memory")?.into_memory()
+}
+
+/// A pool of idle instances of a module, reused across transactions or queries to avoid
+/// linking and instantiating the module every time.
+///
+/// Instances refer to the runtime of the thread using them, so idle instances are kept in the
+/// thread where they were used, and each thread keeps at most `capacity` of them for every pool.
+///
+/// Reused contract instances must execute exactly like fresh ones, otherwise validators would
+/// disagree on the outcome and fuel of blocks, and a service query must not observe what a
+/// previous query left behind, e.g. in statics. Before an instance is returned to the pool, it
+/// is reset to the state of a freshly instantiated module:
+///
+/// - the runtime is detached, along with any pending promises;
+/// - the linear memory is restored to its contents right after instantiation, which covers
+///   the heap, the shadow stack and all static data;
+/// - the mutable globals and the tables are restored as well. Modules are compiled with
+///   exports for all their globals and tables for that purpose (see
+///   [`export_instance_state`]).
+///
+/// The fuel is set from the runtime before every call. Instances are discarded instead of
+/// reused if a call failed (a trap may interrupt the guest at any point) or if their memory or
+/// a table grew, since they can't shrink back to their initial size.
+pub struct InstancePool<Instance> {
+    id: u64,
+    module: Module,
+    capacity: usize,
+    initial_memory: OnceLock<Vec<u8>>,
+    /// The number of times an idle instance was reused.
+    reused_instances: AtomicU64,
+    _instance: PhantomData<fn() -> Instance>,
+}
+
+/// A pool of idle service instances.
+pub type ServiceInstancePool = InstancePool<WasmtimeServiceInstance<ServiceSyncRuntimeHandle>>;
+
+/// The source of unique [`InstancePool`] IDs.
+static NEXT_INSTANCE_POOL_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// The idle instances kept in the current thread, indexed by the ID of their pool.
+    static IDLE_INSTANCES: RefCell<HashMap<u64, Box<dyn IdleInstanceList>>> =
+        RefCell::default();
+}
+
+/// The idle instances of an [`InstancePool`] kept in a thread, along with the globals and
+/// tables to restore before each reuse.
+struct IdleInstances<Instance> {
+    pool: Weak<InstancePool<Instance>>,
+    instances: Vec<(Instance, InstanceState)>,
+}
+
+/// The [`IdleInstances`] of a pool of any type of instance.
+trait IdleInstanceList {
+    /// Returns `true` if the pool of these instances no longer exists.
+    fn is_orphaned(&self) -> bool;
+
+    /// Returns `self` as [`Any`], to recover the type of the instances.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<Instance: 'static> IdleInstanceList for IdleInstances<Instance> {
+    fn is_orphaned(&self) -> bool {
+        self.pool.strong_count() == 0
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<Instance: PoolableInstance> InstancePool<Instance> {
+    /// Creates a pool for the `module`, which keeps at most `capacity` idle instances per
+    /// thread.
+    pub(crate) fn new(module: Module, capacity: usize) -> Self {
+        InstancePool {
+            id: NEXT_INSTANCE_POOL_ID.fetch_add(1, Ordering::Relaxed),
+            module,
+            capacity,
+            initial_memory: OnceLock::new(),
+            reused_instances: AtomicU64::new(0),
+            _instance: PhantomData,
+        }
+    }
+
+    /// Creates a new pool for the same module, which keeps at most `capacity` idle instances
+    /// per thread.
+    pub(crate) fn with_capacity(&self, capacity: usize) -> Self {
+        InstancePool::new(self.module.clone(), capacity)
+    }
+
+    /// Returns an instance ready to be used with the provided `runtime`, reusing an idle
+    /// instance if available.
+    pub(crate) fn get(
+        self: &Arc<Self>,
+        runtime: Instance::Runtime,
+    ) -> Result<PooledInstance<Instance>, WasmExecutionError> {
+        let idle_instance = IDLE_INSTANCES.with_borrow_mut(|idle_instances| {
+            idle_instances
+                .get_mut(&self.id)
+                .and_then(|idle| idle.as_any_mut().downcast_mut::<IdleInstances<Instance>>())
+                .and_then(|idle| idle.instances.pop())
+        });
+
+        let (instance, initial_state) = match idle_instance {
+            Some((mut instance, initial_state)) => {
+                self.reused_instances.fetch_add(1, Ordering::Relaxed);
+                instance
+                    .wasm_instance()
+                    .user_data_mut()
+                    .attach_runtime(runtime);
+                (instance, Some(initial_state))
+            }
+            None => {
+                let mut instance = Instance::instantiate_module(&self.module, runtime)?;
+                let initial_state = (self.capacity > 0).then(|| {
+                    let wasm_instance = instance.wasm_instance();
+                    if self.initial_memory.get().is_none() {
+                        if let Some(memory) = memory(wasm_instance) {
+                            let _ = self
+                                .initial_memory
+                                .set(memory.data(&*wasm_instance).to_vec());
+                        }
+                    }
+                    InstanceState::save(&self.module, wasm_instance, |instance, name| {
+                        instance.load_export(name)
+                    })
+                });
+                (instance, initial_state)
+            }
+        };
+
+        Ok(PooledInstance {
+            instance: Some(instance),
+            initial_state,
+            pool: Arc::clone(self),
+            reusable: true,
+        })
+    }
+
+    /// Returns the number of times an idle instance was reused.
+    #[cfg(with_testing)]
+    pub(crate) fn reused_instance_count(&self) -> u64 {
+        self.reused_instances.load(Ordering::Relaxed)
+    }
+
+    /// Resets an `instance` and keeps it for reuse in the current thread if possible, or drops
+    /// it otherwise.
+    fn release(self: &Arc<Self>, mut instance: Instance, initial_state: InstanceState) {
+        if !self.reset(&mut instance, &initial_state) {
+            return;
+        }
+
+        // The thread-local storage is unavailable while the thread is exiting, in which case
+        // the instance is simply dropped.
+        let _ = IDLE_INSTANCES.try_with(|idle_instances| {
+            let mut idle_instances = idle_instances.borrow_mut();
+            // Forget the instances of pools that no longer exist.
+            idle_instances.retain(|_, idle| !idle.is_orphaned());
+
+            let idle = idle_instances.entry(self.id).or_insert_with(|| {
+                Box::new(IdleInstances {
+                    pool: Arc::downgrade(self),
+                    instances: Vec::new(),
+                })
+            });
+            let Some(idle) = idle.as_any_mut().downcast_mut::<IdleInstances<Instance>>() else {
+                return;
+            };
+            if idle.instances.len() < self.capacity {
+                idle.instances.push((instance, initial_state));
+            }
+        });
+    }
+
+    /// Detaches the runtime and restores the linear memory of an `instance` to its contents
+    /// right after instantiation, and the globals and tables to their `initial_state`.
+    ///
+    /// Returns `false` if the memory or a table can't be restored because its size changed, in
+    /// which case the instance must not be reused.
+    fn reset(&self, instance: &mut Instance, initial_state: &InstanceState) -> bool {
+        let wasm_instance = instance.wasm_instance();
+        wasm_instance.user_data_mut().detach_runtime();
+
+        if let Some(memory) = memory(wasm_instance) {
+            let initial_memory = self.initial_memory.get().map_or(&[][..], Vec::as_slice);
+            let contents = memory.data_mut(&mut *wasm_instance);
+            if contents.len() != initial_memory.len() {
+                return false;
+            }
+            contents.copy_from_slice(initial_memory);
+        }
+        initial_state.restore(wasm_instance)
+    }
+}
+
+/// An instance borrowed from an [`InstancePool`], returned to the pool when dropped.
+pub(crate) struct PooledInstance<Instance: PoolableInstance> {
+    instance: Option<Instance>,
+    /// The globals and tables to restore before the instance is reused, or `None` if the pool
+    /// doesn't keep idle instances.
+    initial_state: Option<InstanceState>,
+    pool: Arc<InstancePool<Instance>>,
+    reusable: bool,
+}
+
+impl<Instance: PoolableInstance> PooledInstance<Instance> {
+    /// Calls into the instance with `call`, and marks the instance as not reusable if it
+    /// failed.
+    fn call<Output>(
+        &mut self,
+        call: impl FnOnce(&mut Instance) -> Result<Output, ExecutionError>,
+    ) -> Result<Output, ExecutionError> {
+        let instance = self
+            .instance
+            .as_mut()
+            .expect("Pooled instance is only taken when dropped");
+        let result = call(instance);
+        self.reusable &= result.is_ok();
+        result
+    }
+}
+
+impl<Instance: PoolableInstance> Drop for PooledInstance<Instance> {
+    fn drop(&mut self) {
+        if let (Some(instance), Some(initial_state)) =
+            (self.instance.take(), self.initial_state.take())
+        {
+            if self.reusable {
+                self.pool.release(instance, initial_state);
+            }
+        }
+    }
+}
+
+impl crate::UserService for PooledInstance<WasmtimeServiceInstance<ServiceSyncRuntimeHandle>> {
+    fn handle_query(
+        &mut self,
+        context: QueryContext,
+        argument: Vec<u8>,
+    ) -> Result<Vec<u8>, ExecutionError> {
+        self.call(|instance| crate::UserService::handle_query(instance, context, argument))
+    }
+}
+
+/// The pool of idle instances of a contract module, which can also report the backtraces of
+/// the operations that trap. See [`InstancePool`].
+pub struct ContractInstancePool {
+    instances: Arc<InstancePool<WasmtimeContractInstance<ContractSyncRuntimeHandle>>>,
+    diagnostics: Option<Arc<TrapDiagnostics>>,
+}
+
+/// What is needed to re-execute the operations of a contract that trap, in order to report a
+/// detailed backtrace. See [`WasmRuntimeConfig::trap_diagnostics`].
+struct TrapDiagnostics {
+    bytecode: Bytecode,
+    profile: EngineProfile,
+    /// The contract compiled for diagnostics, or `None` if it failed to compile. Only compiled
+    /// when an operation first traps.
+    module: OnceLock<Option<Module>>,
+}
+
+impl TrapDiagnostics {
+    /// Creates the [`TrapDiagnostics`] for the contract with the original `bytecode`.
+    fn new(bytecode: Bytecode) -> Self {
+        TrapDiagnostics {
+            profile: EngineProfile::for_bytecode(&bytecode),
+            bytecode,
+            module: OnceLock::new(),
+        }
+    }
+
+    /// Executes the `operation` again with at most `fuel` in the `runtime` forked from the one
+    /// that trapped, and returns the backtrace of the trap, if it traps again.
+    ///
+    /// The forked runtime can't change the execution state (see
+    /// [`ContractSyncRuntimeHandle::fork_for_diagnostics`]), so an operation that traps after
+    /// such a change may fail differently the second time, and then no backtrace is reported.
+    fn backtrace(
+        &self,
+        runtime: ContractSyncRuntime,
+        fuel: u64,
+        operation: Vec<u8>,
+    ) -> Option<String> {
+        let module = self
+            .module
+            .get_or_init(|| {
+                let engine = Engines::get().debug_contract(self.profile).ok()?;
+                Module::new(&engine, &self.bytecode)
+                    .inspect_err(|error| {
+                        tracing::warn!("Failed to compile contract to diagnose traps: {error}")
+                    })
+                    .ok()
+            })
+            .as_ref()?;
+        let mut instance =
+            WasmtimeContractInstance::prepare(module, ContractSyncRuntimeHandle::clone(&runtime))
+                .ok()?;
+        instance
+            .instance
+            .as_context_mut()
+            .set_fuel(fuel)
+            .expect("Fuel consumption should be enabled");
+        let error = ContractEntrypoints::new(&mut instance.instance)
+            .execute_operation(operation)
+            .err()?;
+        match error {
+            linera_witty::RuntimeError::Wasmtime(error) => error
+                .downcast_ref::<WasmBacktrace>()
+                .map(ToString::to_string),
+            _ => None,
+        }
+    }
+}
+
+impl ContractInstancePool {
+    /// Creates a pool for the contract `module`, which keeps at most `capacity` idle instances
+    /// per thread.
+    pub(crate) fn new(module: Module, capacity: usize) -> Self {
+        ContractInstancePool {
+            instances: Arc::new(InstancePool::new(module, capacity)),
+            diagnostics: None,
+        }
+    }
+
+    /// Creates a new pool for the same module, which keeps at most `capacity` idle instances
+    /// per thread.
+    pub(crate) fn with_capacity(&self, capacity: usize) -> Self {
+        ContractInstancePool {
+            instances: Arc::new(self.instances.with_capacity(capacity)),
+            diagnostics: self.diagnostics.clone(),
+        }
+    }
+
+    /// Creates a new pool for the same module, which reports the backtraces of the operations
+    /// that trap by executing them again with the original `bytecode` of the module.
+    #[cfg(with_testing)]
+    pub(crate) fn with_trap_diagnostics(&self, bytecode: Bytecode) -> Self {
+        ContractInstancePool {
+            instances: Arc::new(self.instances.with_capacity(self.instances.capacity)),
+            diagnostics: Some(Arc::new(TrapDiagnostics::new(bytecode))),
+        }
+    }
+
+    /// Returns an instance ready to execute a transaction with the provided `runtime`, reusing
+    /// an idle instance if available.
+    pub(crate) fn get(
+        &self,
+        runtime: ContractSyncRuntimeHandle,
+    ) -> Result<PooledContractInstance, WasmExecutionError> {
+        Ok(PooledContractInstance {
+            instance: self.instances.get(runtime)?,
+            diagnostics: self.diagnostics.clone(),
+        })
+    }
+
+    /// Returns the number of times an idle instance was reused.
+    #[cfg(with_testing)]
+    pub(crate) fn reused_instance_count(&self) -> u64 {
+        self.instances.reused_instance_count()
+    }
+}
+
+/// A contract instance borrowed from a [`ContractInstancePool`], returned to the pool when
+/// dropped.
+pub(crate) struct PooledContractInstance {
+    instance: PooledInstance<WasmtimeContractInstance<ContractSyncRuntimeHandle>>,
+    diagnostics: Option<Arc<TrapDiagnostics>>,
+}
+
+impl crate::UserContract for PooledContractInstance {
+    fn instantiate(
+        &mut self,
+        context: OperationContext,
+        argument: Vec<u8>,
+    ) -> Result<(), ExecutionError> {
+        self.instance
+            .call(|instance| crate::UserContract::instantiate(instance, context, argument))
+    }
+
+    fn execute_operation(
+        &mut self,
+        context: OperationContext,
+        operation: Vec<u8>,
+    ) -> Result<Vec<u8>, ExecutionError> {
+        let Some(diagnostics) = self.diagnostics.clone() else {
+            return self.instance.call(|instance| {
+                crate::UserContract::execute_operation(instance, context, operation)
+            });
+        };
+        let mut retry = None;
+        let result = self.instance.call(|instance| {
+            let mut runtime = instance.instance.user_data_mut().runtime_mut().clone();
+            let fuel = runtime.remaining_fuel()?;
+            let result =
+                crate::UserContract::execute_operation(instance, context, operation.clone());
+            if matches!(&result, Err(ExecutionError::WasmError(error)) if error.is_trap()) {
+                retry = Some((runtime.fork_for_diagnostics(), fuel));
+            }
+            result
+        });
+        match (result, retry) {
+            (Err(ExecutionError::WasmError(error)), Some((runtime, fuel))) => {
+                let error = match diagnostics.backtrace(runtime, fuel, operation) {
+                    Some(backtrace) => WasmExecutionError::TrapWithBacktrace {
+                        error: Box::new(error),
+                        backtrace,
+                    },
+                    None => error,
+                };
+                Err(error.into())
+            }
+            (result, _) => result,
+        }
+    }
+
+    fn execute_message(
+        &mut self,
+        context: MessageContext,
+        message: Vec<u8>,
+    ) -> Result<(), ExecutionError> {
+        self.instance
+            .call(|instance| crate::UserContract::execute_message(instance, context, message))
+    }
+
+    fn finalize(&mut self, context: FinalizeContext) -> Result<(), ExecutionError> {
+        self.instance
+            .call(|instance| crate::UserContract::finalize(instance, context))
+    }
+}
+
 impl<Runtime> crate::UserContract for WasmtimeContractInstance<Runtime>
 where
     Runtime: ContractRuntime + 'static,
@@ -176,9 +1287,9 @@ where
         _context: OperationContext,
         argument: Vec<u8>,
     ) -> Result<(), ExecutionError> {
-        self.configure_initial_fuel()?;
+        let initial_fuel = self.configure_call()?;
         let result = ContractEntrypoints::new(&mut self.instance).instantiate(argument);
-        self.persist_remaining_fuel()?;
+        self.persist_remaining_fuel(initial_fuel, "instantiate")?;
         result.map_err(WasmExecutionError::from)?;
         Ok(())
     }
@@ -188,9 +1299,9 @@ where
         _context: OperationContext,
         operation: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
-        self.configure_initial_fuel()?;
+        let initial_fuel = self.configure_call()?;
         let result = ContractEntrypoints::new(&mut self.instance).execute_operation(operation);
-        self.persist_remaining_fuel()?;
+        self.persist_remaining_fuel(initial_fuel, "execute_operation")?;
         Ok(result.map_err(WasmExecutionError::from)?)
     }
 
@@ -199,17 +1310,17 @@ where
         _context: MessageContext,
         message: Vec<u8>,
     ) -> Result<(), ExecutionError> {
-        self.configure_initial_fuel()?;
+        let initial_fuel = self.configure_call()?;
         let result = ContractEntrypoints::new(&mut self.instance).execute_message(message);
-        self.persist_remaining_fuel()?;
+        self.persist_remaining_fuel(initial_fuel, "execute_message")?;
         result.map_err(WasmExecutionError::from)?;
         Ok(())
     }
 
     fn finalize(&mut self, _context: FinalizeContext) -> Result<(), ExecutionError> {
-        self.configure_initial_fuel()?;
+        let initial_fuel = self.configure_call()?;
         let result = ContractEntrypoints::new(&mut self.instance).finalize();
-        self.persist_remaining_fuel()?;
+        self.persist_remaining_fuel(initial_fuel, "finalize")?;
         result.map_err(WasmExecutionError::from)?;
         Ok(())
     }
@@ -224,8 +1335,13 @@ where
         _context: QueryContext,
         argument: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
-        Ok(ServiceEntrypoints::new(&mut self.instance)
-            .handle_query(argument)
-            .map_err(WasmExecutionError::from)?)
+        let initial_fuel = self.configure_call()?;
+        let result = ServiceEntrypoints::new(&mut self.instance).handle_query(argument);
+        self.persist_remaining_fuel(initial_fuel)?;
+        let response = result.map_err(|error| match WasmExecutionError::from(error) {
+            WasmExecutionError::OutOfFuel => WasmExecutionError::QueryBudgetExceeded,
+            error => error,
+        })?;
+        Ok(response)
     }
 }
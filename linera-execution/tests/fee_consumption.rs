@@ -151,6 +151,10 @@ async fn test_fee_consumption(
         maximum_block_proposal_size: 53,
         maximum_bytes_read_per_block: 59,
         maximum_bytes_written_per_block: 61,
+        maximum_service_fuel_per_query: 67,
+        maximum_wasm_memory_bytes: 71,
+        maximum_wasm_table_elements: 73,
+        maximum_wasm_instances: 79,
     };
 
     let consumed_fees = spends
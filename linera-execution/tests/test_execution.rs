@@ -1593,6 +1593,67 @@ async fn test_close_chain() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Tests that the tokens an application spends from the chain balance count towards the limit
+/// of the owner who signed the block.
+#[tokio::test]
+async fn test_owner_limit_in_application() -> anyhow::Result<()> {
+    let committee = Committee::make_simple(vec![PublicKey::test_key(0).into()]);
+    let committees = BTreeMap::from([(Epoch::ZERO, committee)]);
+    let limited_owner = Owner::from(PublicKey::test_key(1));
+    let state = SystemExecutionState {
+        committees,
+        ownership: ChainOwnership::single(limited_owner),
+        owner_limits: BTreeMap::from([(limited_owner, Amount::from_tokens(2))]),
+        balance: Amount::from_tokens(5),
+        ..SystemExecutionState::new(Epoch::ZERO, ChainDescription::Root(0), ChainId::root(0))
+    };
+    let mut view = state.into_view().await;
+    let (application_id, application) = view.register_mock_application().await?;
+
+    let context = OperationContext {
+        authenticated_signer: Some(limited_owner),
+        ..create_dummy_operation_context()
+    };
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.transfer(None, Account::chain(ChainId::root(2)), Amount::ONE)?;
+            let child_ownership = ChainOwnership::single(PublicKey::test_key(2).into());
+            let application_permissions = ApplicationPermissions::default();
+            runtime.open_chain(
+                child_ownership,
+                application_permissions,
+                Amount::from_tokens(2),
+            )?;
+            Ok(vec![])
+        },
+    ));
+
+    let mut controller = ResourceController::default();
+    let operation = Operation::User {
+        application_id,
+        bytes: vec![],
+    };
+    let result = view
+        .execute_operation(
+            context,
+            Timestamp::from(0),
+            operation,
+            &mut TransactionTracker::new(0, Some(Vec::new())),
+            &mut controller,
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::SystemError(SystemExecutionError::OwnerLimitExceeded {
+            owner,
+            amount,
+            ..
+        })) if owner == limited_owner && amount == Amount::from_tokens(3)
+    );
+
+    Ok(())
+}
+
 /// Tests an application attempting to transfer the tokens in the chain's balance while executing
 /// messages.
 #[test_case(
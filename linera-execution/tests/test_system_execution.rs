@@ -4,6 +4,7 @@
 #![allow(clippy::field_reassign_with_default)]
 
 use linera_base::{
+    balance_proof::BALANCE_FIELD_INDEX,
     crypto::{CryptoHash, KeyPair},
     data_types::{Amount, BlockHeight, Timestamp},
     identifiers::{Account, AccountOwner, ChainDescription, ChainId, MessageId, Owner},
@@ -15,6 +16,7 @@ use linera_execution::{
     RawExecutionOutcome, ResourceController, SystemMessage, SystemOperation, SystemQuery,
     SystemResponse, TransactionTracker,
 };
+use linera_views::views::CryptoHashView as _;
 
 #[tokio::test]
 async fn test_simple_system_operation() -> anyhow::Result<()> {
@@ -144,3 +146,29 @@ async fn test_simple_system_query() -> anyhow::Result<()> {
     assert!(operations.is_empty());
     Ok(())
 }
+
+#[tokio::test]
+async fn test_balance_path() -> anyhow::Result<()> {
+    let state = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        balance: Amount::from_tokens(4),
+        ..SystemExecutionState::default()
+    };
+    let view = state.into_view().await;
+    // The verifier can't see the view, so its balance position must match the layout.
+    let field_names = view
+        .system
+        .field_hashes()
+        .await?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        field_names.iter().position(|name| *name == "balance"),
+        Some(BALANCE_FIELD_INDEX)
+    );
+    let path = view.balance_path().await?;
+    assert_eq!(path.balance, Amount::from_tokens(4));
+    assert_eq!(path.state_hash(), view.crypto_hash().await?);
+    Ok(())
+}
@@ -6,7 +6,7 @@
 use std::sync::Arc;
 
 use linera_base::{
-    data_types::{Amount, BlockHeight, Timestamp},
+    data_types::{Amount, BlockHeight, Bytecode, Timestamp},
     identifiers::{Account, ChainDescription, ChainId},
 };
 use linera_execution::{
@@ -156,3 +156,858 @@ async fn test_fuel_for_counter_wasm_application(
     assert!(operations.is_empty());
     Ok(())
 }
+
+/// Test that a service instance reused from the pool answers queries like a fresh instance.
+#[cfg(with_wasmtime)]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_pooled_wasmtime_service_instances() -> anyhow::Result<()> {
+    let state = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        ..Default::default()
+    };
+    let mut view = state
+        .into_view_with(ChainId::root(0), ExecutionRuntimeConfig::default())
+        .await;
+    let (app_desc, contract_blob, service_blob) = create_dummy_user_application_description(1);
+    let app_id = view.system.registry.register_application(app_desc).await?;
+
+    let service =
+        WasmServiceModule::from_file("tests/fixtures/counter_service.wasm", WasmRuntime::Wasmtime)
+            .await?
+            .with_instance_pool_capacity(1);
+    view.context()
+        .extra()
+        .user_services()
+        .insert(app_id, service.into());
+    view.context()
+        .extra()
+        .add_blobs([contract_blob, service_blob])
+        .await?;
+
+    let context = QueryContext {
+        chain_id: ChainId::root(0),
+        next_block_height: BlockHeight(0),
+        local_time: Timestamp::from(0),
+    };
+    let mut service_runtime_endpoint = context.spawn_service_runtime_actor();
+    let expected_value = async_graphql::Response::new(
+        async_graphql::Value::from_json(json!({"value" : 0})).unwrap(),
+    );
+    let request = async_graphql::Request::new("query { value }");
+    for use_endpoint in [false, false, true, false] {
+        let endpoint = use_endpoint.then_some(&mut service_runtime_endpoint);
+        let outcome = view
+            .query_application(
+                context,
+                Query::user_without_abi(app_id, &request).unwrap(),
+                endpoint,
+            )
+            .await?;
+        let QueryOutcome {
+            response: QueryResponse::User(serialized_value),
+            operations,
+        } = outcome
+        else {
+            panic!("unexpected response")
+        };
+        assert_eq!(
+            serde_json::from_slice::<async_graphql::Response>(&serialized_value).unwrap(),
+            expected_value
+        );
+        assert!(operations.is_empty());
+    }
+    Ok(())
+}
+
+/// Test that contract instances are reused from the pool, and consume the same fuel as fresh
+/// instances.
+#[cfg(with_wasmtime)]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_pooled_wasmtime_contract_instances() -> anyhow::Result<()> {
+    let state = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        ..Default::default()
+    };
+    let mut view = state
+        .into_view_with(ChainId::root(0), ExecutionRuntimeConfig::default())
+        .await;
+    let (app_desc, contract_blob, service_blob) = create_dummy_user_application_description(1);
+    let app_id = view.system.registry.register_application(app_desc).await?;
+
+    let contract = WasmContractModule::from_file(
+        "tests/fixtures/counter_contract.wasm",
+        WasmRuntime::Wasmtime,
+    )
+    .await?
+    .with_instance_pool_capacity(1);
+    view.context()
+        .extra()
+        .user_contracts()
+        .insert(app_id, contract.clone().into());
+    view.context()
+        .extra()
+        .add_blobs([contract_blob, service_blob])
+        .await?;
+
+    let context = OperationContext {
+        chain_id: ChainId::root(0),
+        height: BlockHeight(0),
+        round: Some(0),
+        index: Some(0),
+        authenticated_signer: None,
+        authenticated_caller_id: None,
+    };
+    let mut controller = ResourceController {
+        policy: Arc::new(ResourceControlPolicy::default()),
+        tracker: ResourceTracker::default(),
+        account: None,
+    };
+    for increment in [2_u64, 9, 7, 1000] {
+        let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+        view.execute_operation(
+            context,
+            Timestamp::from(0),
+            Operation::user_without_abi(app_id, &increment).unwrap(),
+            &mut txn_tracker,
+            &mut controller,
+        )
+        .await?;
+    }
+    // The same fuel as in `test_fuel_for_counter_wasm_application`, with fresh instances.
+    assert_eq!(controller.tracker.fuel, 90_454);
+    assert!(contract.reused_instance_count() > 0);
+    Ok(())
+}
+
+/// Tests that bytecodes can be compiled ahead of time, and that invalid bytecodes are reported.
+#[cfg_attr(with_wasmer, test_case(WasmRuntime::Wasmer; "wasmer"))]
+#[cfg_attr(with_wasmer, test_case(WasmRuntime::WasmerWithSanitizer; "wasmer_with_sanitizer"))]
+#[cfg_attr(with_wasmtime, test_case(WasmRuntime::Wasmtime; "wasmtime"))]
+#[cfg_attr(with_wasmtime, test_case(WasmRuntime::WasmtimeWithSanitizer; "wasmtime_with_sanitizer"))]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_warm_up_modules(wasm_runtime: WasmRuntime) -> anyhow::Result<()> {
+    let contract_bytecode =
+        Bytecode::load_from_file("tests/fixtures/counter_contract.wasm").await?;
+    let service_bytecode = Bytecode::load_from_file("tests/fixtures/counter_service.wasm").await?;
+
+    WasmContractModule::warm_up(vec![contract_bytecode.clone()], wasm_runtime).await?;
+    WasmServiceModule::warm_up(vec![service_bytecode.clone()], wasm_runtime).await?;
+    WasmContractModule::new(contract_bytecode, wasm_runtime).await?;
+    WasmServiceModule::new(service_bytecode, wasm_runtime).await?;
+
+    let invalid_bytecode = Bytecode::new(b"not a Wasm module".to_vec());
+    assert!(
+        WasmContractModule::warm_up(vec![invalid_bytecode.clone()], wasm_runtime)
+            .await
+            .is_err()
+    );
+    assert!(
+        WasmServiceModule::warm_up(vec![invalid_bytecode], wasm_runtime)
+            .await
+            .is_err()
+    );
+    Ok(())
+}
+
+/// Tests that modules importing host functions that aren't exported are rejected when they are
+/// loaded.
+#[cfg(with_wasmtime)]
+#[test_log::test(tokio::test)]
+async fn test_missing_host_imports() {
+    use linera_execution::WasmExecutionError;
+    use wasm_encoder::{EntityType, ImportSection, Module, TypeSection};
+
+    let mut types = TypeSection::new();
+    types.function([], []);
+    let mut imports = ImportSection::new();
+    imports.import(
+        "linera:app/contract-system-api",
+        "does-not-exist",
+        EntityType::Function(0),
+    );
+    imports.import("unknown:package/api", "function", EntityType::Function(0));
+    let mut module = Module::new();
+    module.section(&types).section(&imports);
+    let bytecode = Bytecode::new(module.finish());
+    let expected_names = vec![
+        "linera:app/contract-system-api#does-not-exist".to_owned(),
+        "unknown:package/api#function".to_owned(),
+    ];
+
+    let Err(WasmExecutionError::MissingHostImports { names }) =
+        WasmContractModule::new(bytecode.clone(), WasmRuntime::Wasmtime).await
+    else {
+        panic!("Contract with missing host imports should be rejected");
+    };
+    assert_eq!(names, expected_names);
+
+    let Err(WasmExecutionError::MissingHostImports { names }) =
+        WasmServiceModule::new(bytecode, WasmRuntime::Wasmtime).await
+    else {
+        panic!("Service with missing host imports should be rejected");
+    };
+    assert_eq!(names, expected_names);
+}
+
+/// Tests that modules are compiled with the engine profile matching the features they use, and
+/// that contracts can't use relaxed SIMD.
+#[cfg(with_wasmtime)]
+#[test_log::test(tokio::test)]
+async fn test_engine_profiles() -> anyhow::Result<()> {
+    use linera_execution::{EngineProfile, WasmExecutionError};
+    use wasm_encoder::{
+        CodeSection, Function, FunctionSection, Instruction, Module, TypeSection, ValType,
+    };
+
+    fn module_with(instructions: &[Instruction]) -> Bytecode {
+        let mut types = TypeSection::new();
+        types.function([], [ValType::V128]);
+        let mut functions = FunctionSection::new();
+        functions.function(0);
+        let mut function = Function::new([]);
+        for instruction in instructions {
+            function.instruction(instruction);
+        }
+        function.instruction(&Instruction::End);
+        let mut code = CodeSection::new();
+        code.function(&function);
+        let mut module = Module::new();
+        module.section(&types).section(&functions).section(&code);
+        Bytecode::new(module.finish())
+    }
+
+    let plain = Bytecode::new(wasm_encoder::Module::new().finish());
+    let simd = module_with(&[Instruction::V128Const(0)]);
+    let relaxed_simd = module_with(&[
+        Instruction::V128Const(0),
+        Instruction::V128Const(0),
+        Instruction::I8x16RelaxedSwizzle,
+    ]);
+
+    assert_eq!(
+        EngineProfile::for_bytecode(&plain),
+        EngineProfile::default()
+    );
+    assert_eq!(
+        EngineProfile::for_bytecode(&simd),
+        EngineProfile {
+            simd: true,
+            relaxed_simd: false,
+        }
+    );
+    assert_eq!(
+        EngineProfile::for_bytecode(&relaxed_simd),
+        EngineProfile {
+            simd: true,
+            relaxed_simd: true,
+        }
+    );
+
+    for bytecode in [&plain, &simd] {
+        WasmContractModule::new(bytecode.clone(), WasmRuntime::Wasmtime).await?;
+        WasmServiceModule::new(bytecode.clone(), WasmRuntime::Wasmtime).await?;
+    }
+    WasmServiceModule::new(relaxed_simd.clone(), WasmRuntime::Wasmtime).await?;
+    assert!(matches!(
+        WasmContractModule::new(relaxed_simd, WasmRuntime::Wasmtime).await,
+        Err(WasmExecutionError::LoadContractModule(_))
+    ));
+    Ok(())
+}
+
+/// Tests that executing the same operations twice consumes the same fuel in every entrypoint
+/// call, and that a diverging trace is reported.
+#[cfg(with_wasmtime)]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_fuel_trace_is_deterministic() -> anyhow::Result<()> {
+    use linera_execution::{FuelTracingContractModule, WasmExecutionError};
+
+    async fn execute_increments(module: FuelTracingContractModule) -> anyhow::Result<()> {
+        let state = SystemExecutionState {
+            description: Some(ChainDescription::Root(0)),
+            ..Default::default()
+        };
+        let mut view = state
+            .into_view_with(ChainId::root(0), ExecutionRuntimeConfig::default())
+            .await;
+        let (app_desc, contract_blob, service_blob) = create_dummy_user_application_description(1);
+        let app_id = view.system.registry.register_application(app_desc).await?;
+        view.context()
+            .extra()
+            .user_contracts()
+            .insert(app_id, module.into());
+        view.context()
+            .extra()
+            .add_blobs([contract_blob, service_blob])
+            .await?;
+
+        let context = OperationContext {
+            chain_id: ChainId::root(0),
+            height: BlockHeight(0),
+            round: Some(0),
+            index: Some(0),
+            authenticated_signer: None,
+            authenticated_caller_id: None,
+        };
+        let mut controller = ResourceController::default();
+        for increment in [2_u64, 9, 7, 1000] {
+            let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+            view.execute_operation(
+                context,
+                Timestamp::from(0),
+                Operation::user_without_abi(app_id, &increment).unwrap(),
+                &mut txn_tracker,
+                &mut controller,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    let contract = WasmContractModule::from_file(
+        "tests/fixtures/counter_contract.wasm",
+        WasmRuntime::Wasmtime,
+    )
+    .await?;
+
+    let recording = FuelTracingContractModule::new(&contract).unwrap();
+    execute_increments(recording.clone()).await?;
+    let trace = recording.fuel_trace();
+    assert_eq!(trace.calls.len(), 8);
+    assert_eq!(
+        trace
+            .calls
+            .iter()
+            .map(|call| call.entrypoint)
+            .collect::<Vec<_>>(),
+        ["execute_operation", "finalize"].repeat(4)
+    );
+
+    let replay = FuelTracingContractModule::new(&contract).unwrap();
+    execute_increments(replay.clone()).await?;
+    replay.verify(&trace)?;
+
+    let mut diverging_trace = trace.clone();
+    diverging_trace.calls[0].fuel += 1;
+    let Err(WasmExecutionError::NonDeterministicFuel { expected, actual }) =
+        replay.verify(&diverging_trace)
+    else {
+        panic!("A diverging fuel trace should be reported");
+    };
+    assert_eq!(expected, diverging_trace);
+    assert_eq!(actual, trace);
+    Ok(())
+}
+
+/// Tests that a service stuck in an infinite loop is stopped once it consumed the fuel budget
+/// set by the committee's policy.
+#[cfg(with_wasmtime)]
+#[test_case(false; "short_lived_runtime")]
+#[test_case(true; "long_lived_runtime")]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_service_query_budget(use_endpoint: bool) -> anyhow::Result<()> {
+    use linera_base::crypto::PublicKey;
+    use linera_execution::{
+        committee::{Committee, Epoch},
+        ExecutionError, WasmExecutionError,
+    };
+
+    let bytecode = test_bytecode(
+        r#"(memory (export "memory") 1)
+           (func (export "linera:app/service-entrypoints#handle-query")
+             (param i32 i32) (result i32)
+             (loop $spin
+               (br $spin))
+             unreachable)"#,
+    )?;
+
+    let policy = ResourceControlPolicy {
+        maximum_service_fuel_per_query: 1_000_000,
+        ..ResourceControlPolicy::default()
+    };
+    let validators = Committee::make_simple(vec![PublicKey::test_key(0).into()])
+        .validators()
+        .clone();
+    let state = SystemExecutionState {
+        committees: [(Epoch::ZERO, Committee::new(validators, policy)?)].into(),
+        ..SystemExecutionState::new(Epoch::ZERO, ChainDescription::Root(0), ChainId::root(0))
+    };
+    let mut view = state
+        .into_view_with(ChainId::root(0), ExecutionRuntimeConfig::default())
+        .await;
+    let (app_desc, contract_blob, service_blob) = create_dummy_user_application_description(1);
+    let app_id = view.system.registry.register_application(app_desc).await?;
+    let service = WasmServiceModule::new(bytecode, WasmRuntime::Wasmtime).await?;
+    view.context()
+        .extra()
+        .user_services()
+        .insert(app_id, service.into());
+    view.context()
+        .extra()
+        .add_blobs([contract_blob, service_blob])
+        .await?;
+
+    let context = QueryContext {
+        chain_id: ChainId::root(0),
+        next_block_height: BlockHeight(0),
+        local_time: Timestamp::from(0),
+    };
+    let mut service_runtime_endpoint = context.spawn_service_runtime_actor();
+    let endpoint = use_endpoint.then_some(&mut service_runtime_endpoint);
+    let result = view
+        .query_application(
+            context,
+            Query::User {
+                application_id: app_id,
+                bytes: vec![],
+            },
+            endpoint,
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(ExecutionError::WasmError(
+            WasmExecutionError::QueryBudgetExceeded
+        ))
+    ));
+    Ok(())
+}
+
+/// Tests that the fuel charged for a contract call is exactly the fuel consumed by the Wasm
+/// instance, whether the call runs out of fuel or has some left.
+#[cfg(with_wasmtime)]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_contract_fuel_accounting() -> anyhow::Result<()> {
+    use linera_execution::{FuelTraceEntry, FuelTracingContractModule};
+
+    /// Returns a contract whose operations either loop forever or return right away.
+    fn contract(looping: bool) -> anyhow::Result<Bytecode> {
+        let operation = if looping {
+            "(loop $spin
+               (br $spin))
+             unreachable"
+        } else {
+            "i32.const 0"
+        };
+        test_bytecode(&format!(
+            r#"(memory (export "memory") 1)
+               (func (export "linera:app/contract-entrypoints#execute-operation")
+                 (param i32 i32) (result i32)
+                 {operation})
+               (func (export "linera:app/contract-entrypoints#finalize"))"#
+        ))
+    }
+
+    /// Executes an operation with at most `fuel_budget` fuel, and returns whether it succeeded,
+    /// the fuel trace of the contract and the fuel charged to the block.
+    async fn execute_operation(
+        bytecode: Bytecode,
+        fuel_budget: u64,
+    ) -> anyhow::Result<(bool, Vec<FuelTraceEntry>, u64)> {
+        let state = SystemExecutionState {
+            description: Some(ChainDescription::Root(0)),
+            ..Default::default()
+        };
+        let mut view = state
+            .into_view_with(ChainId::root(0), ExecutionRuntimeConfig::default())
+            .await;
+        let (app_desc, contract_blob, service_blob) = create_dummy_user_application_description(1);
+        let app_id = view.system.registry.register_application(app_desc).await?;
+        let contract = WasmContractModule::new(bytecode, WasmRuntime::Wasmtime).await?;
+        let module = FuelTracingContractModule::new(&contract).unwrap();
+        view.context()
+            .extra()
+            .user_contracts()
+            .insert(app_id, module.clone().into());
+        view.context()
+            .extra()
+            .add_blobs([contract_blob, service_blob])
+            .await?;
+
+        let context = OperationContext {
+            chain_id: ChainId::root(0),
+            height: BlockHeight(0),
+            round: Some(0),
+            index: Some(0),
+            authenticated_signer: None,
+            authenticated_caller_id: None,
+        };
+        let mut controller = ResourceController {
+            policy: Arc::new(ResourceControlPolicy {
+                maximum_fuel_per_block: fuel_budget,
+                ..ResourceControlPolicy::default()
+            }),
+            tracker: ResourceTracker::default(),
+            account: None,
+        };
+        let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+        let result = view
+            .execute_operation(
+                context,
+                Timestamp::from(0),
+                Operation::User {
+                    application_id: app_id,
+                    bytes: vec![],
+                },
+                &mut txn_tracker,
+                &mut controller,
+            )
+            .await;
+        Ok((
+            result.is_ok(),
+            module.fuel_trace().calls,
+            controller.tracker.fuel,
+        ))
+    }
+
+    let looping = contract(true)?;
+    for fuel_budget in [0, 1_000] {
+        let (succeeded, calls, _) = execute_operation(looping.clone(), fuel_budget).await?;
+        assert!(!succeeded);
+        assert_eq!(
+            calls,
+            [FuelTraceEntry {
+                entrypoint: "execute_operation",
+                fuel: fuel_budget,
+            }]
+        );
+    }
+
+    let returning = contract(false)?;
+    let fuel_budget = 1_000_000;
+    let (succeeded, calls, charged_fuel) =
+        execute_operation(returning.clone(), fuel_budget).await?;
+    assert!(succeeded);
+    assert_eq!(
+        calls.iter().map(|call| call.entrypoint).collect::<Vec<_>>(),
+        ["execute_operation", "finalize"]
+    );
+    let consumed_fuel = calls.iter().map(|call| call.fuel).sum::<u64>();
+    assert!(consumed_fuel > 0);
+    assert!(consumed_fuel < fuel_budget);
+    assert_eq!(charged_fuel, consumed_fuel);
+
+    let (succeeded, _, charged_fuel) = execute_operation(returning.clone(), consumed_fuel).await?;
+    assert!(succeeded);
+    assert_eq!(charged_fuel, consumed_fuel);
+    let (succeeded, _, _) = execute_operation(returning, consumed_fuel - 1).await?;
+    assert!(!succeeded);
+    Ok(())
+}
+
+/// Returns the bytecode of a module with the WAT `definitions` and an allocator that always
+/// returns address zero.
+#[cfg(with_wasmtime)]
+fn test_bytecode(definitions: &str) -> anyhow::Result<Bytecode> {
+    let wat = format!(
+        r#"
+        (module
+          {definitions}
+          (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+            i32.const 0)
+        )
+        "#
+    );
+    Ok(Bytecode::new(wasmer::wat2wasm(wat.as_bytes())?.into()))
+}
+
+/// Returns a contract with the memory and tables in `definitions`, whose operations execute
+/// the `operation` instructions.
+#[cfg(with_wasmtime)]
+async fn limits_test_contract(
+    definitions: &str,
+    operation: &str,
+) -> anyhow::Result<WasmContractModule> {
+    let bytecode = test_bytecode(&format!(
+        r#"{definitions}
+           (func (export "linera:app/contract-entrypoints#execute-operation")
+             (param i32 i32) (result i32)
+             {operation})"#
+    ))?;
+    Ok(WasmContractModule::new(bytecode, WasmRuntime::Wasmtime).await?)
+}
+
+/// The policy of the resource limit tests: 16 MiB of memory, 10 000 table elements and 10
+/// instances.
+#[cfg(with_wasmtime)]
+fn limits_test_policy() -> ResourceControlPolicy {
+    ResourceControlPolicy {
+        maximum_fuel_per_block: 10_000_000,
+        maximum_wasm_memory_bytes: 16 * 1024 * 1024,
+        maximum_wasm_table_elements: 10_000,
+        maximum_wasm_instances: 10,
+        ..ResourceControlPolicy::default()
+    }
+}
+
+/// Executes an operation of the `contract` with the given `policy`.
+#[cfg(with_wasmtime)]
+async fn execute_operation_with_policy(
+    contract: WasmContractModule,
+    operation: Vec<u8>,
+    policy: ResourceControlPolicy,
+) -> anyhow::Result<Result<(), linera_execution::ExecutionError>> {
+    let state = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        ..Default::default()
+    };
+    let mut view = state
+        .into_view_with(ChainId::root(0), ExecutionRuntimeConfig::default())
+        .await;
+    let (app_desc, contract_blob, service_blob) = create_dummy_user_application_description(1);
+    let app_id = view.system.registry.register_application(app_desc).await?;
+    view.context()
+        .extra()
+        .user_contracts()
+        .insert(app_id, contract.into());
+    view.context()
+        .extra()
+        .add_blobs([contract_blob, service_blob])
+        .await?;
+
+    let context = OperationContext {
+        chain_id: ChainId::root(0),
+        height: BlockHeight(0),
+        round: Some(0),
+        index: Some(0),
+        authenticated_signer: None,
+        authenticated_caller_id: None,
+    };
+    let mut controller = ResourceController {
+        policy: Arc::new(policy),
+        tracker: ResourceTracker::default(),
+        account: None,
+    };
+    let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+    Ok(view
+        .execute_operation(
+            context,
+            Timestamp::from(0),
+            Operation::User {
+                application_id: app_id,
+                bytes: operation,
+            },
+            &mut txn_tracker,
+            &mut controller,
+        )
+        .await)
+}
+
+/// Tests that a contract growing its memory beyond the limit set by the policy traps, while a
+/// contract within the limits is unaffected.
+#[cfg(with_wasmtime)]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_wasm_memory_limit() -> anyhow::Result<()> {
+    use linera_execution::{ExecutionError, WasmExecutionError};
+
+    let growing = limits_test_contract(
+        r#"(memory (export "memory") 1)"#,
+        "(loop $grow
+           (drop (memory.grow (i32.const 1)))
+           (br $grow))
+         unreachable",
+    )
+    .await?;
+    let result = execute_operation_with_policy(growing, vec![], limits_test_policy()).await?;
+    assert!(matches!(
+        result,
+        Err(ExecutionError::WasmError(
+            WasmExecutionError::MemoryLimitExceeded
+        ))
+    ));
+
+    let counter = WasmContractModule::from_file(
+        "tests/fixtures/counter_contract.wasm",
+        WasmRuntime::Wasmtime,
+    )
+    .await?;
+    let result =
+        execute_operation_with_policy(counter, bcs::to_bytes(&1_u64)?, limits_test_policy())
+            .await?;
+    assert!(result.is_ok());
+    Ok(())
+}
+
+/// Tests that a contract whose initial memory exceeds the limit set by the policy can't be
+/// instantiated.
+#[cfg(with_wasmtime)]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_wasm_initial_memory_limit() -> anyhow::Result<()> {
+    use linera_execution::{ExecutionError, WasmExecutionError};
+
+    // 512 pages of 64 KiB are 32 MiB.
+    let contract = limits_test_contract(r#"(memory (export "memory") 512)"#, "i32.const 0").await?;
+    let result = execute_operation_with_policy(contract, vec![], limits_test_policy()).await?;
+    assert!(matches!(
+        result,
+        Err(ExecutionError::WasmError(
+            WasmExecutionError::MemoryLimitExceeded
+        ))
+    ));
+    Ok(())
+}
+
+/// Tests that a contract can neither start with nor grow a table beyond the limit set by the
+/// policy.
+#[cfg(with_wasmtime)]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_wasm_table_limit() -> anyhow::Result<()> {
+    use linera_execution::{ExecutionError, WasmExecutionError};
+
+    let large = limits_test_contract(
+        r#"(memory (export "memory") 1)
+           (table 20000 funcref)"#,
+        "i32.const 0",
+    )
+    .await?;
+    let result = execute_operation_with_policy(large, vec![], limits_test_policy()).await?;
+    assert!(matches!(
+        result,
+        Err(ExecutionError::WasmError(
+            WasmExecutionError::MemoryLimitExceeded
+        ))
+    ));
+
+    let growing = limits_test_contract(
+        r#"(memory (export "memory") 1)
+           (table 1 funcref)"#,
+        "(loop $grow
+           (drop (table.grow (ref.null func) (i32.const 1000)))
+           (br $grow))
+         unreachable",
+    )
+    .await?;
+    let result = execute_operation_with_policy(growing, vec![], limits_test_policy()).await?;
+    assert!(matches!(
+        result,
+        Err(ExecutionError::WasmError(
+            WasmExecutionError::MemoryLimitExceeded
+        ))
+    ));
+    Ok(())
+}
+
+/// Tests that a contract can't be instantiated in a store that already reached the limit on
+/// instances set by the policy.
+#[cfg(with_wasmtime)]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_wasm_instance_limit() -> anyhow::Result<()> {
+    use linera_execution::{ExecutionError, WasmExecutionError};
+
+    let contract = limits_test_contract(r#"(memory (export "memory") 1)"#, "i32.const 0").await?;
+    let policy = ResourceControlPolicy {
+        maximum_wasm_instances: 0,
+        ..limits_test_policy()
+    };
+    let result = execute_operation_with_policy(contract, vec![], policy).await?;
+    assert!(matches!(
+        result,
+        Err(ExecutionError::WasmError(
+            WasmExecutionError::LoadContractModule(_)
+        ))
+    ));
+
+    let counter = WasmContractModule::from_file(
+        "tests/fixtures/counter_contract.wasm",
+        WasmRuntime::Wasmtime,
+    )
+    .await?;
+    let policy = ResourceControlPolicy {
+        maximum_wasm_instances: 1,
+        ..limits_test_policy()
+    };
+    let result = execute_operation_with_policy(counter, bcs::to_bytes(&1_u64)?, policy).await?;
+    assert!(result.is_ok());
+    Ok(())
+}
+
+/// Tests that the operations that trap are executed again to report a backtrace, when trap
+/// diagnostics are enabled.
+#[cfg(with_wasmtime)]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_trap_diagnostics() -> anyhow::Result<()> {
+    use linera_execution::{ExecutionError, WasmExecutionError};
+
+    let bytecode = test_bytecode(
+        r#"(memory (export "memory") 1)
+           (func $failing_helper
+             unreachable)
+           (func (export "linera:app/contract-entrypoints#execute-operation")
+             (param i32 i32) (result i32)
+             call $failing_helper
+             i32.const 0)"#,
+    )?;
+
+    /// Executes an operation of the `contract`, which is expected to trap.
+    async fn execute_operation(contract: WasmContractModule) -> anyhow::Result<ExecutionError> {
+        let state = SystemExecutionState {
+            description: Some(ChainDescription::Root(0)),
+            ..Default::default()
+        };
+        let mut view = state
+            .into_view_with(ChainId::root(0), ExecutionRuntimeConfig::default())
+            .await;
+        let (app_desc, contract_blob, service_blob) = create_dummy_user_application_description(1);
+        let app_id = view.system.registry.register_application(app_desc).await?;
+        view.context()
+            .extra()
+            .user_contracts()
+            .insert(app_id, contract.into());
+        view.context()
+            .extra()
+            .add_blobs([contract_blob, service_blob])
+            .await?;
+
+        let context = OperationContext {
+            chain_id: ChainId::root(0),
+            height: BlockHeight(0),
+            round: Some(0),
+            index: Some(0),
+            authenticated_signer: None,
+            authenticated_caller_id: None,
+        };
+        let mut controller = ResourceController {
+            policy: Arc::new(ResourceControlPolicy {
+                maximum_fuel_per_block: 10_000_000,
+                ..ResourceControlPolicy::default()
+            }),
+            tracker: ResourceTracker::default(),
+            account: None,
+        };
+        let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+        let result = view
+            .execute_operation(
+                context,
+                Timestamp::from(0),
+                Operation::User {
+                    application_id: app_id,
+                    bytes: vec![],
+                },
+                &mut txn_tracker,
+                &mut controller,
+            )
+            .await;
+        Ok(result.expect_err("The operation should trap"))
+    }
+
+    let contract = WasmContractModule::new(bytecode.clone(), WasmRuntime::Wasmtime).await?;
+    let error = execute_operation(contract.clone()).await?;
+    assert!(matches!(
+        error,
+        ExecutionError::WasmError(ref error) if error.is_trap()
+    ));
+    assert!(!matches!(
+        error,
+        ExecutionError::WasmError(WasmExecutionError::TrapWithBacktrace { .. })
+    ));
+
+    let error = execute_operation(contract.with_trap_diagnostics(bytecode)).await?;
+    let ExecutionError::WasmError(WasmExecutionError::TrapWithBacktrace { error, backtrace }) =
+        error
+    else {
+        panic!("Expected a backtrace, got {error:?}");
+    };
+    assert!(error.is_trap());
+    assert!(backtrace.contains("failing_helper"), "{backtrace}");
+    Ok(())
+}
@@ -6,7 +6,7 @@ use std::{fmt, future::Future, iter};
 use futures::{future, stream, StreamExt};
 use linera_base::{
     crypto::CryptoHash,
-    data_types::BlobContent,
+    data_types::{BlobContent, ProtocolVersion},
     ensure,
     identifiers::{BlobId, ChainId},
     time::Duration,
@@ -147,7 +147,18 @@ impl GrpcClient {
             error: "missing body from response".to_string(),
         })?;
         match inner {
-            api::chain_info_result::Inner::ChainInfoResponse(response) => {
+            api::chain_info_result::Inner::ChainInfoResponse(mut response) => {
+                let validator = response
+                    .protocol_version
+                    .take()
+                    .map_or(ProtocolVersion::UNVERSIONED, ProtocolVersion::from);
+                ensure!(
+                    ProtocolVersion::CURRENT.is_compatible_with(&validator),
+                    NodeError::IncompatibleProtocolVersion {
+                        validator,
+                        client: ProtocolVersion::CURRENT,
+                    }
+                );
                 Ok(response.try_into().map_err(|err| NodeError::GrpcError {
                     error: format!("failed to unmarshal response: {}", err),
                 })?)
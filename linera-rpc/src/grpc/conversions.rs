@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use linera_base::{
-    crypto::{CryptoError, CryptoHash, PublicKey, Signature},
-    data_types::{BlobContent, BlockHeight},
+    crypto::{
+        AccountPublicKey, AccountSignature, CryptoError, CryptoHash, PublicKey, Secp256k1Signature,
+        Signature,
+    },
+    data_types::{BlobContent, BlockHeight, ProtocolVersion},
     ensure,
     hashed::Hashed,
     identifiers::{AccountOwner, BlobId, ChainId, Owner},
@@ -44,6 +47,8 @@ pub enum GrpcProtoConversionError {
     InconsistentChainId,
     #[error("Unrecognized certificate type")]
     InvalidCertificateType,
+    #[error("Integer out of range: {0}")]
+    IntegerOutOfRange(#[from] std::num::TryFromIntError),
 }
 
 impl From<ed25519_dalek::SignatureError> for GrpcProtoConversionError {
@@ -203,6 +208,10 @@ impl TryFrom<BlockProposal> for api::BlockProposal {
                 .validated_block_certificate
                 .map(|cert| bincode::serialize(&cert))
                 .transpose()?,
+            cosignatures: (!block_proposal.cosignatures.is_empty())
+                .then(|| bincode::serialize(&block_proposal.cosignatures))
+                .transpose()?,
+            protocol_version: Some(ProtocolVersion::CURRENT.into()),
         })
     }
 }
@@ -225,6 +234,11 @@ impl TryFrom<api::BlockProposal> for BlockProposal {
                 .validated_block_certificate
                 .map(|bytes| bincode::deserialize(&bytes))
                 .transpose()?,
+            cosignatures: block_proposal
+                .cosignatures
+                .map(|bytes| bincode::deserialize(&bytes))
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
@@ -331,6 +345,7 @@ impl<'a> TryFrom<HandleLiteCertRequest<'a>> for api::LiteCertificate {
             signatures: bincode::serialize(&request.certificate.signatures)?,
             wait_for_outgoing_messages: request.wait_for_outgoing_messages,
             kind: request.certificate.value.kind as i32,
+            protocol_version: Some(ProtocolVersion::CURRENT.into()),
         })
     }
 }
@@ -412,6 +427,7 @@ impl TryFrom<HandleConfirmedCertificateRequest> for api::HandleConfirmedCertific
             chain_id: Some(request.certificate.inner().chain_id().into()),
             certificate: Some(request.certificate.try_into()?),
             wait_for_outgoing_messages: request.wait_for_outgoing_messages,
+            protocol_version: Some(ProtocolVersion::CURRENT.into()),
         })
     }
 }
@@ -423,6 +439,7 @@ impl TryFrom<HandleValidatedCertificateRequest> for api::HandleValidatedCertific
         Ok(Self {
             chain_id: Some(request.certificate.inner().chain_id().into()),
             certificate: Some(request.certificate.try_into()?),
+            protocol_version: Some(ProtocolVersion::CURRENT.into()),
         })
     }
 }
@@ -434,6 +451,7 @@ impl TryFrom<HandleTimeoutCertificateRequest> for api::HandleTimeoutCertificateR
         Ok(Self {
             chain_id: Some(request.certificate.inner().chain_id().into()),
             certificate: Some(request.certificate.try_into()?),
+            protocol_version: Some(ProtocolVersion::CURRENT.into()),
         })
     }
 }
@@ -551,6 +569,10 @@ impl TryFrom<api::ChainInfoQuery> for ChainInfoQuery {
             .request_sent_certificate_hashes_in_range
             .map(|range| bincode::deserialize(&range))
             .transpose()?;
+        let request_received_log_after = chain_info_query
+            .request_received_log_after
+            .map(|heights| bincode::deserialize(&heights))
+            .transpose()?;
 
         Ok(Self {
             request_committees: chain_info_query.request_committees,
@@ -559,6 +581,13 @@ impl TryFrom<api::ChainInfoQuery> for ChainInfoQuery {
                 .map(TryInto::try_into)
                 .transpose()?,
             request_pending_message_bundles: chain_info_query.request_pending_message_bundles,
+            request_pending_message_bundles_offset: chain_info_query
+                .request_pending_message_bundles_offset
+                .try_into()?,
+            request_pending_message_bundles_limit: chain_info_query
+                .request_pending_message_bundles_limit
+                .map(usize::try_from)
+                .transpose()?,
             chain_id: try_proto_convert(chain_info_query.chain_id)?,
             request_sent_certificate_hashes_in_range,
             request_received_log_excluding_first_n: chain_info_query
@@ -567,6 +596,14 @@ impl TryFrom<api::ChainInfoQuery> for ChainInfoQuery {
             request_manager_values: chain_info_query.request_manager_values,
             request_leader_timeout: chain_info_query.request_leader_timeout,
             request_fallback: chain_info_query.request_fallback,
+            request_subscriptions: chain_info_query.request_subscriptions,
+            request_received_log_after,
+            request_outboxes: chain_info_query.request_outboxes,
+            request_balance_proof: chain_info_query.request_balance_proof,
+            request_balance_proof_height: chain_info_query
+                .request_balance_proof_height
+                .map(Into::into),
+            request_outboxes_offset: chain_info_query.request_outboxes_offset.try_into()?,
         })
     }
 }
@@ -579,6 +616,10 @@ impl TryFrom<ChainInfoQuery> for api::ChainInfoQuery {
             .request_sent_certificate_hashes_in_range
             .map(|range| bincode::serialize(&range))
             .transpose()?;
+        let request_received_log_after = chain_info_query
+            .request_received_log_after
+            .map(|heights| bincode::serialize(&heights))
+            .transpose()?;
         let request_owner_balance = chain_info_query
             .request_owner_balance
             .map(|owner| owner.try_into())
@@ -589,6 +630,12 @@ impl TryFrom<ChainInfoQuery> for api::ChainInfoQuery {
             request_committees: chain_info_query.request_committees,
             request_owner_balance,
             request_pending_message_bundles: chain_info_query.request_pending_message_bundles,
+            request_pending_message_bundles_offset: chain_info_query
+                .request_pending_message_bundles_offset
+                as u64,
+            request_pending_message_bundles_limit: chain_info_query
+                .request_pending_message_bundles_limit
+                .map(|limit| limit as u64),
             test_next_block_height: chain_info_query.test_next_block_height.map(Into::into),
             request_sent_certificate_hashes_in_range,
             request_received_log_excluding_first_n: chain_info_query
@@ -596,6 +643,15 @@ impl TryFrom<ChainInfoQuery> for api::ChainInfoQuery {
             request_manager_values: chain_info_query.request_manager_values,
             request_leader_timeout: chain_info_query.request_leader_timeout,
             request_fallback: chain_info_query.request_fallback,
+            request_subscriptions: chain_info_query.request_subscriptions,
+            request_received_log_after,
+            request_outboxes: chain_info_query.request_outboxes,
+            request_balance_proof: chain_info_query.request_balance_proof,
+            request_balance_proof_height: chain_info_query
+                .request_balance_proof_height
+                .map(Into::into),
+            request_outboxes_offset: chain_info_query.request_outboxes_offset as u64,
+            protocol_version: Some(ProtocolVersion::CURRENT.into()),
         })
     }
 }
@@ -664,6 +720,54 @@ impl TryFrom<api::Signature> for Signature {
     }
 }
 
+impl From<AccountPublicKey> for api::PublicKey {
+    fn from(public_key: AccountPublicKey) -> Self {
+        Self {
+            bytes: public_key.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<api::PublicKey> for AccountPublicKey {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(public_key: api::PublicKey) -> Result<Self, Self::Error> {
+        Ok(AccountPublicKey::try_from(public_key.bytes.as_slice())?)
+    }
+}
+
+impl From<AccountSignature> for api::AccountSignature {
+    fn from(signature: AccountSignature) -> Self {
+        let inner = match signature {
+            AccountSignature::Ed25519(signature) => {
+                api::account_signature::Inner::Ed25519(signature.0.to_vec())
+            }
+            AccountSignature::Secp256k1(signature) => {
+                api::account_signature::Inner::Secp256k1(signature.to_bytes())
+            }
+        };
+        Self { inner: Some(inner) }
+    }
+}
+
+impl TryFrom<api::AccountSignature> for AccountSignature {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(signature: api::AccountSignature) -> Result<Self, Self::Error> {
+        match signature
+            .inner
+            .ok_or(GrpcProtoConversionError::MissingField)?
+        {
+            api::account_signature::Inner::Ed25519(bytes) => Ok(AccountSignature::Ed25519(
+                Signature(bytes.as_slice().try_into()?),
+            )),
+            api::account_signature::Inner::Secp256k1(bytes) => Ok(AccountSignature::Secp256k1(
+                Secp256k1Signature::try_from(bytes.as_slice())?,
+            )),
+        }
+    }
+}
+
 impl TryFrom<ChainInfoResponse> for api::ChainInfoResponse {
     type Error = GrpcProtoConversionError;
 
@@ -671,6 +775,7 @@ impl TryFrom<ChainInfoResponse> for api::ChainInfoResponse {
         Ok(Self {
             chain_info: bincode::serialize(&chain_info_response.info)?,
             signature: chain_info_response.signature.map(Into::into),
+            protocol_version: Some(ProtocolVersion::CURRENT.into()),
         })
     }
 }
@@ -767,6 +872,24 @@ impl From<api::BlockHeight> for BlockHeight {
     }
 }
 
+impl From<ProtocolVersion> for api::ProtocolVersion {
+    fn from(version: ProtocolVersion) -> Self {
+        Self {
+            major: version.major,
+            minor: version.minor,
+        }
+    }
+}
+
+impl From<api::ProtocolVersion> for ProtocolVersion {
+    fn from(version: api::ProtocolVersion) -> Self {
+        Self {
+            major: version.major,
+            minor: version.minor,
+        }
+    }
+}
+
 impl TryFrom<AccountOwner> for api::AccountOwner {
     type Error = GrpcProtoConversionError;
 
@@ -967,10 +1090,10 @@ impl TryFrom<api::CertificatesBatchResponse> for Vec<Certificate> {
 
 #[cfg(test)]
 pub mod tests {
-    use std::{borrow::Cow, fmt::Debug};
+    use std::{borrow::Cow, collections::BTreeMap, fmt::Debug};
 
     use linera_base::{
-        crypto::{BcsSignable, CryptoHash, KeyPair},
+        crypto::{AccountSecretKey, BcsSignable, CryptoHash, KeyPair, Secp256k1KeyPair},
         data_types::{Amount, Blob, Round, Timestamp},
     };
     use linera_chain::{
@@ -979,6 +1102,7 @@ pub mod tests {
         types::CertificateKind,
     };
     use linera_core::data_types::ChainInfo;
+    use prost::Message as _;
     use serde::{Deserialize, Serialize};
 
     use super::*;
@@ -1060,9 +1184,15 @@ pub mod tests {
             requested_committees: None,
             requested_owner_balance: None,
             requested_pending_message_bundles: vec![],
+            has_more_pending_message_bundles: false,
             requested_sent_certificate_hashes: vec![],
+            requested_sent_certificate_hashes_next_height: None,
             count_received_log: 0,
             requested_received_log: vec![],
+            requested_subscriptions: vec![],
+            requested_outboxes: BTreeMap::new(),
+            requested_balance_proof: None,
+            has_more_outboxes: false,
         });
 
         let chain_info_response_none = ChainInfoResponse {
@@ -1090,7 +1220,9 @@ pub mod tests {
             test_next_block_height: Some(BlockHeight::from(10)),
             request_committees: false,
             request_owner_balance: None,
-            request_pending_message_bundles: false,
+            request_pending_message_bundles: true,
+            request_pending_message_bundles_offset: 20,
+            request_pending_message_bundles_limit: Some(10),
             request_sent_certificate_hashes_in_range: Some(
                 linera_core::data_types::BlockHeightRange {
                     start: BlockHeight::from(3),
@@ -1101,10 +1233,29 @@ pub mod tests {
             request_manager_values: false,
             request_leader_timeout: false,
             request_fallback: true,
+            request_subscriptions: true,
+            request_received_log_after: Some(BTreeMap::from([(
+                ChainId::root(1),
+                BlockHeight::from(4),
+            )])),
+            request_outboxes: true,
+            request_balance_proof: true,
+            request_balance_proof_height: Some(BlockHeight::from(2)),
+            request_outboxes_offset: 5,
         };
         round_trip_check::<_, api::ChainInfoQuery>(chain_info_query_some);
     }
 
+    #[test]
+    pub fn test_protocol_version() {
+        let query = ChainInfoQuery::new(ChainId::root(0));
+        let mut proto = api::ChainInfoQuery::try_from(query.clone()).unwrap();
+        let version = proto.protocol_version.take().map(ProtocolVersion::from);
+        assert_eq!(version, Some(ProtocolVersion::CURRENT));
+        // Peers that predate version negotiation leave out the version.
+        assert_eq!(ChainInfoQuery::try_from(proto).unwrap(), query);
+    }
+
     #[test]
     pub fn test_pending_blob_request() {
         let chain_id = ChainId::root(2);
@@ -1212,7 +1363,7 @@ pub mod tests {
         )
         .lite_certificate()
         .cloned();
-        let public_key = KeyPair::generate().public();
+        let public_key = AccountPublicKey::from(KeyPair::generate().public());
         let block_proposal = BlockProposal {
             content: ProposalContent {
                 block: get_block(),
@@ -1221,13 +1372,39 @@ pub mod tests {
             },
             owner: Owner::from(public_key),
             public_key,
-            signature: Signature::new(&Foo("test".into()), &KeyPair::generate()),
+            signature: AccountSignature::new(&Foo("test".into()), &KeyPair::generate().into()),
             validated_block_certificate: Some(cert),
+            cosignatures: vec![(
+                public_key,
+                AccountSignature::new(&Foo("cosigned".into()), &KeyPair::generate().into()),
+            )],
         };
 
         round_trip_check::<_, api::BlockProposal>(block_proposal);
     }
 
+    #[test]
+    pub fn test_account_signature() {
+        let ed25519_key = AccountSecretKey::from(KeyPair::generate());
+        let ed25519_signature = AccountSignature::new(&Foo("test".into()), &ed25519_key);
+        round_trip_check::<_, api::AccountSignature>(ed25519_signature);
+        round_trip_check::<_, api::PublicKey>(ed25519_key.public());
+
+        let secp256k1_key = AccountSecretKey::from(Secp256k1KeyPair::generate());
+        let secp256k1_signature = AccountSignature::new(&Foo("test".into()), &secp256k1_key);
+        round_trip_check::<_, api::AccountSignature>(secp256k1_signature);
+        round_trip_check::<_, api::PublicKey>(secp256k1_key.public());
+
+        // Ed25519 signatures keep the encoding of a plain `Signature`.
+        let AccountSignature::Ed25519(signature) = ed25519_signature else {
+            unreachable!()
+        };
+        assert_eq!(
+            api::AccountSignature::from(ed25519_signature).encode_to_vec(),
+            api::Signature::from(signature).encode_to_vec()
+        );
+    }
+
     #[test]
     pub fn test_notification() {
         let notification = Notification {
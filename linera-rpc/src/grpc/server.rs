@@ -18,7 +18,7 @@ use futures::{
 use linera_base::{data_types::Blob, identifiers::ChainId};
 use linera_core::{
     node::NodeError,
-    worker::{NetworkActions, Notification, WorkerError, WorkerState},
+    worker::{check_protocol_version, NetworkActions, Notification, WorkerError, WorkerState},
     JoinSetExt as _, TaskHandle,
 };
 use linera_storage::Storage;
@@ -446,7 +446,12 @@ where
         request: Request<BlockProposal>,
     ) -> Result<Response<ChainInfoResult>, Status> {
         let start = Instant::now();
-        let proposal = request.into_inner().try_into()?;
+        let mut request = request.into_inner();
+        let protocol_version = request.protocol_version.take().map(Into::into);
+        if let Err(error) = check_protocol_version(protocol_version) {
+            return Ok(Response::new(NodeError::from(error).try_into()?));
+        }
+        let proposal = request.try_into()?;
         trace!(?proposal, "Handling block proposal");
         Ok(Response::new(
             match self.state.clone().handle_block_proposal(proposal).await {
@@ -484,10 +489,15 @@ where
         request: Request<LiteCertificate>,
     ) -> Result<Response<ChainInfoResult>, Status> {
         let start = Instant::now();
+        let mut request = request.into_inner();
+        let protocol_version = request.protocol_version.take().map(Into::into);
+        if let Err(error) = check_protocol_version(protocol_version) {
+            return Ok(Response::new(NodeError::from(error).try_into()?));
+        }
         let HandleLiteCertRequest {
             certificate,
             wait_for_outgoing_messages,
-        } = request.into_inner().try_into()?;
+        } = request.try_into()?;
         trace!(?certificate, "Handling lite certificate");
         let (sender, receiver) = wait_for_outgoing_messages.then(oneshot::channel).unzip();
         match self
@@ -538,10 +548,15 @@ where
         request: Request<api::HandleConfirmedCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
         let start = Instant::now();
+        let mut request = request.into_inner();
+        let protocol_version = request.protocol_version.take().map(Into::into);
+        if let Err(error) = check_protocol_version(protocol_version) {
+            return Ok(Response::new(NodeError::from(error).try_into()?));
+        }
         let HandleConfirmedCertificateRequest {
             certificate,
             wait_for_outgoing_messages,
-        } = request.into_inner().try_into()?;
+        } = request.try_into()?;
         trace!(?certificate, "Handling certificate");
         let (sender, receiver) = wait_for_outgoing_messages.then(oneshot::channel).unzip();
         match self
@@ -588,7 +603,12 @@ where
         request: Request<api::HandleValidatedCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
         let start = Instant::now();
-        let HandleValidatedCertificateRequest { certificate } = request.into_inner().try_into()?;
+        let mut request = request.into_inner();
+        let protocol_version = request.protocol_version.take().map(Into::into);
+        if let Err(error) = check_protocol_version(protocol_version) {
+            return Ok(Response::new(NodeError::from(error).try_into()?));
+        }
+        let HandleValidatedCertificateRequest { certificate } = request.try_into()?;
         trace!(?certificate, "Handling certificate");
         match self
             .state
@@ -629,7 +649,12 @@ where
         request: Request<api::HandleTimeoutCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
         let start = Instant::now();
-        let HandleTimeoutCertificateRequest { certificate } = request.into_inner().try_into()?;
+        let mut request = request.into_inner();
+        let protocol_version = request.protocol_version.take().map(Into::into);
+        if let Err(error) = check_protocol_version(protocol_version) {
+            return Ok(Response::new(NodeError::from(error).try_into()?));
+        }
+        let HandleTimeoutCertificateRequest { certificate } = request.try_into()?;
         trace!(?certificate, "Handling Timeout certificate");
         match self
             .state
@@ -669,7 +694,12 @@ where
         request: Request<ChainInfoQuery>,
     ) -> Result<Response<ChainInfoResult>, Status> {
         let start = Instant::now();
-        let query = request.into_inner().try_into()?;
+        let mut request = request.into_inner();
+        let protocol_version = request.protocol_version.take().map(Into::into);
+        if let Err(error) = check_protocol_version(protocol_version) {
+            return Ok(Response::new(NodeError::from(error).try_into()?));
+        }
+        let query = request.try_into()?;
         trace!(?query, "Handling chain info query");
         match self.state.clone().handle_chain_info_query(query).await {
             Ok((info, actions)) => {
@@ -1,8 +1,16 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
 use linera_base::time::Duration;
-use linera_core::node::{NodeError, ValidatorNodeProvider};
+use linera_core::{
+    node::{NodeError, ValidatorNodeProvider},
+    rate_limit::{RateLimit, RateLimitedNode, RateLimiter},
+};
 
 #[cfg(with_simple_network)]
 use crate::simple::SimpleNodeProvider;
@@ -15,6 +23,9 @@ pub struct NodeProvider {
     grpc: GrpcNodeProvider,
     #[cfg(with_simple_network)]
     simple: SimpleNodeProvider,
+    rate_limit: Option<RateLimit>,
+    /// The rate limiter of each validator, by address, shared by all nodes made for it.
+    rate_limiters: Arc<Mutex<HashMap<String, Arc<RateLimiter>>>>,
 }
 
 impl NodeProvider {
@@ -23,23 +34,39 @@ impl NodeProvider {
             grpc: GrpcNodeProvider::new(options),
             #[cfg(with_simple_network)]
             simple: SimpleNodeProvider::new(options),
+            rate_limit: options.rate_limit,
+            rate_limiters: Arc::default(),
         }
     }
+
+    fn rate_limiter(&self, address: &str) -> Option<Arc<RateLimiter>> {
+        let limit = self.rate_limit?;
+        let mut rate_limiters = self
+            .rate_limiters
+            .lock()
+            .expect("rate limiter lock should not be poisoned");
+        let limiter = rate_limiters
+            .entry(address.to_owned())
+            .or_insert_with(|| Arc::new(RateLimiter::new(limit)));
+        Some(limiter.clone())
+    }
 }
 
 impl ValidatorNodeProvider for NodeProvider {
-    type Node = Client;
+    type Node = RateLimitedNode<Client>;
 
     fn make_node(&self, address: &str) -> anyhow::Result<Self::Node, NodeError> {
         let address = address.to_lowercase();
 
         #[cfg(with_simple_network)]
         if address.starts_with("tcp") || address.starts_with("udp") {
-            return Ok(Client::Simple(self.simple.make_node(&address)?));
+            let client = Client::Simple(self.simple.make_node(&address)?);
+            return Ok(RateLimitedNode::new(client, self.rate_limiter(&address)));
         }
 
         if address.starts_with("grpc") {
-            return Ok(Client::Grpc(self.grpc.make_node(&address)?));
+            let client = Client::Grpc(self.grpc.make_node(&address)?);
+            return Ok(RateLimitedNode::new(client, self.rate_limiter(&address)));
         }
 
         Err(NodeError::CannotResolveValidatorAddress { address })
@@ -52,4 +79,6 @@ pub struct NodeOptions {
     pub recv_timeout: Duration,
     pub retry_delay: Duration,
     pub max_retries: u32,
+    /// The limits on the requests sent to each validator, if any.
+    pub rate_limit: Option<RateLimit>,
 }
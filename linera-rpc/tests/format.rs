@@ -3,12 +3,16 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use linera_base::{
+    crypto::{
+        AccountPublicKey, AccountSignature, Secp256k1KeyPair, Secp256k1Signature, TestString,
+    },
     data_types::{BlobContent, OracleResponse, Round},
     hashed::Hashed,
     identifiers::{AccountOwner, BlobType, ChainDescription, Destination, GenericApplicationId},
     ownership::ChainOwnership,
 };
 use linera_chain::{
+    block::Effect,
     data_types::{Medium, MessageAction},
     manager::{ChainManagerInfo, LockingBlock},
     types::{Certificate, CertificateKind, ConfirmedBlock, Timeout, ValidatedBlock},
@@ -27,9 +31,17 @@ fn get_registry() -> Result<Registry> {
             .record_samples_for_newtype_structs(true)
             .record_samples_for_tuple_structs(true),
     );
-    let samples = Samples::new();
+    let mut samples = Samples::new();
     // 1. Record samples for types with custom deserializers.
+    {
+        let key_pair = Secp256k1KeyPair::generate();
+        let signature = Secp256k1Signature::new(&TestString::new("sample"), &key_pair);
+        tracer.trace_value(&mut samples, &key_pair.public())?;
+        tracer.trace_value(&mut samples, &signature)?;
+    }
     // 2. Trace the main entry point(s) + every enum separately.
+    tracer.trace_type::<AccountPublicKey>(&samples)?;
+    tracer.trace_type::<AccountSignature>(&samples)?;
     tracer.trace_type::<Round>(&samples)?;
     tracer.trace_type::<OracleResponse>(&samples)?;
     tracer.trace_type::<Recipient>(&samples)?;
@@ -41,6 +53,7 @@ fn get_registry() -> Result<Registry> {
     tracer.trace_type::<Message>(&samples)?;
     tracer.trace_type::<MessageAction>(&samples)?;
     tracer.trace_type::<MessageKind>(&samples)?;
+    tracer.trace_type::<Effect>(&samples)?;
     tracer.trace_type::<CertificateKind>(&samples)?;
     tracer.trace_type::<Certificate>(&samples)?;
     tracer.trace_type::<Hashed<ConfirmedBlock>>(&samples)?;
@@ -0,0 +1,390 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks the BCS serialization of the values that validators sign against fixed byte
+//! sequences, so that any change to their wire format is noticed, and that the JSON
+//! representations of the RPC messages preserve their BCS serialization.
+//!
+//! The fixtures in `tests/fixtures` are hex-encoded and may contain whitespace.
+
+use std::{collections::BTreeMap, fmt::Debug};
+
+use linera_base::{
+    crypto::{AccountSecretKey, BcsHashable, CryptoHash, KeyPair, PublicKey, Signature},
+    data_types::{Amount, Blob, BlobContent, BlockHeight, Round, Timestamp},
+    hashed::Hashed,
+    identifiers::{Account, ChainDescription, ChainId, Owner},
+};
+use linera_chain::{
+    data_types::{
+        BlockExecutionOutcome, BlockProposal, ExecutedBlock, LiteValue, LiteVote, Medium,
+        ProposedBlock, Target,
+    },
+    manager::ChainManagerInfo,
+    types::{
+        Block, CertificateKind, ConfirmedBlock, ConfirmedBlockCertificate, Timeout,
+        TimeoutCertificate, ValidatedBlock, ValidatedBlockCertificate,
+    },
+};
+use linera_core::{
+    data_types::{ChainInfo, ChainInfoQuery, ChainInfoResponse, CrossChainRequest, OutboxStatus},
+    node::NodeError,
+};
+use linera_execution::{
+    committee::{Committee, Epoch, ValidatorName, ValidatorState},
+    system::{Recipient, SystemOperation},
+    Operation, ResourceControlPolicy,
+};
+use linera_rpc::{
+    HandleConfirmedCertificateRequest, HandleLiteCertRequest, HandleTimeoutCertificateRequest,
+    HandleValidatedCertificateRequest, RpcMessage,
+};
+use linera_version::VersionInfo;
+use serde::{de::DeserializeOwned, Serialize};
+use test_strategy::proptest;
+
+const BLOCK: &str = include_str!("fixtures/block.hex");
+const LEGACY_BLOCK: &str = include_str!("fixtures/legacy_block.hex");
+const LITE_VOTE: &str = include_str!("fixtures/lite_vote.hex");
+const CONFIRMED_BLOCK_CERTIFICATE: &str = include_str!("fixtures/confirmed_block_certificate.hex");
+const COMMITTEE: &str = include_str!("fixtures/committee.hex");
+const CHAIN_INFO_RESPONSE: &str = include_str!("fixtures/chain_info_response.hex");
+
+fn test_hash(n: u64) -> CryptoHash {
+    CryptoHash::from([n; 4])
+}
+
+fn test_signature(n: u8) -> Signature {
+    Signature(ed25519_dalek::Signature::from_bytes(&[n; 64]))
+}
+
+fn test_validator(n: u8) -> ValidatorName {
+    ValidatorName(PublicKey::test_key(n))
+}
+
+fn decode_fixture(fixture: &str) -> Vec<u8> {
+    let hex = fixture.split_whitespace().collect::<String>();
+    hex::decode(hex).expect("Fixtures should be valid hex")
+}
+
+/// Checks that `value` is serialized to the bytes in `fixture`, and deserialized back from
+/// them.
+fn check_fixture<T>(value: &T, fixture: &str)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let bytes = decode_fixture(fixture);
+    assert_eq!(
+        hex::encode(bcs::to_bytes(value).unwrap()),
+        hex::encode(&bytes)
+    );
+    assert_eq!(&bcs::from_bytes::<T>(&bytes).unwrap(), value);
+}
+
+/// Checks that any value that can be read from `bytes` is written back to the same bytes.
+fn check_decoding<T>(bytes: &[u8])
+where
+    T: Serialize + DeserializeOwned,
+{
+    if let Ok(value) = bcs::from_bytes::<T>(bytes) {
+        assert_eq!(bcs::to_bytes(&value).unwrap(), bytes);
+    }
+}
+
+fn check_decoding_all(bytes: &[u8]) {
+    check_decoding::<Block>(bytes);
+    check_decoding::<LiteVote>(bytes);
+    check_decoding::<ConfirmedBlockCertificate>(bytes);
+    check_decoding::<Committee>(bytes);
+    check_decoding::<ChainInfoResponse>(bytes);
+}
+
+/// Checks that `value` is written to JSON and read back as a value with the same BCS
+/// serialization.
+fn check_json_round_trip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned,
+{
+    let json = serde_json::to_string(value).unwrap();
+    let round_trip = serde_json::from_str::<T>(&json).unwrap();
+    assert_eq!(
+        hex::encode(bcs::to_bytes(&round_trip).unwrap()),
+        hex::encode(bcs::to_bytes(value).unwrap()),
+        "JSON: {json}"
+    );
+}
+
+fn test_signatures() -> Vec<(ValidatorName, Signature)> {
+    vec![
+        (test_validator(1), test_signature(11)),
+        (test_validator(2), test_signature(12)),
+    ]
+}
+
+fn executed_block() -> ExecutedBlock {
+    let transfer = SystemOperation::Transfer {
+        owner: None,
+        recipient: Recipient::Account(Account {
+            chain_id: ChainId(test_hash(3)),
+            owner: None,
+        }),
+        amount: Amount::from_tokens(5),
+    };
+    ExecutedBlock {
+        block: ProposedBlock {
+            chain_id: ChainId(test_hash(1)),
+            epoch: Epoch(2),
+            incoming_bundles: Vec::new(),
+            operations: vec![Operation::System(transfer)],
+            height: BlockHeight(4),
+            timestamp: Timestamp::from(1_000_000),
+            authenticated_signer: Some(Owner(test_hash(5))),
+            previous_block_hash: Some(test_hash(6)),
+        },
+        outcome: BlockExecutionOutcome {
+            messages: vec![Vec::new()],
+            state_hash: test_hash(7),
+            oracle_responses: vec![Vec::new()],
+            events: vec![Vec::new()],
+        },
+    }
+}
+
+fn block() -> Block {
+    let ExecutedBlock { block, outcome } = executed_block();
+    Block::new(block, outcome)
+}
+
+fn confirmed_block_certificate() -> ConfirmedBlockCertificate {
+    let value = Hashed::new(ConfirmedBlock::new(executed_block()));
+    let signatures = vec![
+        (test_validator(2), test_signature(12)),
+        (test_validator(1), test_signature(11)),
+    ];
+    ConfirmedBlockCertificate::new(value, Round::SingleLeader(1), signatures)
+}
+
+fn committee() -> Committee {
+    let validators = [1, 2]
+        .into_iter()
+        .map(|n| {
+            let state = ValidatorState {
+                network_address: format!("tcp:validator-{n}:9100"),
+                votes: n.into(),
+            };
+            (test_validator(n), state)
+        })
+        .collect::<BTreeMap<_, _>>();
+    let policy = ResourceControlPolicy {
+        block: Amount::from_attos(1),
+        fuel_unit: Amount::from_attos(2),
+        maximum_fuel_per_block: 3,
+        maximum_bytes_written_per_block: 4,
+        ..ResourceControlPolicy::default()
+    };
+    Committee::new(validators, policy).unwrap()
+}
+
+fn chain_info_response() -> ChainInfoResponse {
+    let info = ChainInfo {
+        chain_id: ChainId(test_hash(1)),
+        epoch: Some(Epoch(2)),
+        description: Some(ChainDescription::Root(0)),
+        manager: Box::new(ChainManagerInfo::default()),
+        chain_balance: Amount::from_tokens(7),
+        block_hash: Some(test_hash(6)),
+        timestamp: Timestamp::from(1_000_000),
+        next_block_height: BlockHeight(5),
+        state_hash: Some(test_hash(7)),
+        requested_owner_balance: None,
+        requested_committees: None,
+        requested_pending_message_bundles: Vec::new(),
+        has_more_pending_message_bundles: false,
+        requested_sent_certificate_hashes: Vec::new(),
+        requested_sent_certificate_hashes_next_height: None,
+        count_received_log: 0,
+        requested_received_log: Vec::new(),
+        requested_subscriptions: Vec::new(),
+        requested_outboxes: BTreeMap::new(),
+        requested_balance_proof: None,
+        has_more_outboxes: false,
+    };
+    ChainInfoResponse {
+        info: Box::new(info),
+        signature: Some(test_signature(13)),
+    }
+}
+
+#[test]
+fn test_block_fixture() {
+    let block = block();
+    check_fixture(&block, BLOCK);
+    let bytes = decode_fixture(BLOCK);
+    assert_eq!(block.to_bytes(), bytes);
+    // A confirmed block is hashed and signed with the same bytes as the block itself.
+    let value = ConfirmedBlock::new(executed_block());
+    assert_eq!(value.to_bytes(), bytes);
+    assert_eq!(ConfirmedBlock::from_bytes(&bytes).unwrap(), value);
+}
+
+#[test]
+fn test_legacy_block_fixture() {
+    // Blocks certified before their effects were added keep the hash they were signed with.
+    let bytes = decode_fixture(LEGACY_BLOCK);
+    assert!(bcs::from_bytes::<ConfirmedBlock>(&bytes).is_err());
+    let value = ConfirmedBlock::from_legacy_bytes(&bytes).unwrap();
+    assert_eq!(value.block(), &block());
+    assert_eq!(
+        value.inner().hash(),
+        CryptoHash::from_bcs_bytes("Block", &bytes)
+    );
+}
+
+#[test]
+fn test_lite_vote_fixture() {
+    let vote = LiteVote {
+        value: LiteValue {
+            value_hash: test_hash(8),
+            chain_id: ChainId(test_hash(1)),
+            kind: CertificateKind::Confirmed,
+        },
+        round: Round::MultiLeader(3),
+        validator: test_validator(9),
+        signature: test_signature(10),
+    };
+    check_fixture(&vote, LITE_VOTE);
+}
+
+#[test]
+fn test_confirmed_block_certificate_fixture() {
+    check_fixture(&confirmed_block_certificate(), CONFIRMED_BLOCK_CERTIFICATE);
+}
+
+#[test]
+fn test_committee_fixture() {
+    check_fixture(&committee(), COMMITTEE);
+}
+
+#[test]
+fn test_chain_info_response_fixture() {
+    let response = chain_info_response();
+    check_fixture(&response, CHAIN_INFO_RESPONSE);
+    // The validators sign the serialized `ChainInfo`, without the signature.
+    let bytes = decode_fixture(CHAIN_INFO_RESPONSE);
+    assert_eq!(response.info.to_bytes(), bytes[..bytes.len() - 65]);
+}
+
+#[test]
+fn test_rpc_message_json_round_trips() {
+    let chain_id = ChainId(test_hash(1));
+    let certificate = confirmed_block_certificate();
+    let blob = Blob::new_data(b"blob".to_vec());
+    let mut response = chain_info_response();
+    response.info.requested_committees = Some([(Epoch(2), committee())].into());
+    response.info.requested_outboxes = [(
+        Target {
+            recipient: ChainId(test_hash(3)),
+            medium: Medium::Direct,
+        },
+        OutboxStatus {
+            queued_heights: vec![BlockHeight(1), BlockHeight(2)],
+            next_height_to_schedule: BlockHeight(3),
+        },
+    )]
+    .into();
+    let secret = AccountSecretKey::from(KeyPair::generate());
+    let proposal = BlockProposal::new_initial(Round::Fast, executed_block().block, &secret);
+    let timeout = Hashed::new(Timeout::new(chain_id, BlockHeight(4), Epoch(2)));
+    let validated = Hashed::new(ValidatedBlock::new(executed_block()));
+
+    let messages = [
+        RpcMessage::BlockProposal(Box::new(proposal)),
+        RpcMessage::TimeoutCertificate(Box::new(HandleTimeoutCertificateRequest {
+            certificate: TimeoutCertificate::new(
+                timeout,
+                Round::SingleLeader(1),
+                test_signatures(),
+            ),
+        })),
+        RpcMessage::ValidatedCertificate(Box::new(HandleValidatedCertificateRequest {
+            certificate: ValidatedBlockCertificate::new(validated, Round::Fast, test_signatures()),
+        })),
+        RpcMessage::LiteCertificate(Box::new(HandleLiteCertRequest {
+            certificate: certificate.lite_certificate().cloned(),
+            wait_for_outgoing_messages: true,
+        })),
+        RpcMessage::ConfirmedCertificate(Box::new(HandleConfirmedCertificateRequest {
+            certificate: certificate.clone(),
+            wait_for_outgoing_messages: false,
+        })),
+        RpcMessage::ChainInfoQuery(Box::new(
+            ChainInfoQuery::new(chain_id)
+                .with_pending_message_bundles()
+                .with_outboxes(),
+        )),
+        RpcMessage::UploadBlob(Box::new(BlobContent::new_data(b"blob".to_vec()))),
+        RpcMessage::DownloadBlob(Box::new(blob.id())),
+        RpcMessage::DownloadCertificates(vec![test_hash(6)]),
+        RpcMessage::VersionInfoQuery,
+        RpcMessage::Vote(Box::new(LiteVote::new(
+            LiteValue::new(certificate.value()),
+            Round::Fast,
+            &KeyPair::generate(),
+        ))),
+        RpcMessage::ChainInfoResponse(Box::new(response)),
+        RpcMessage::Error(Box::new(NodeError::InactiveChain(chain_id))),
+        RpcMessage::VersionInfoResponse(Box::new(VersionInfo::default())),
+        RpcMessage::DownloadConfirmedBlockResponse(Box::new(certificate.inner().clone())),
+        RpcMessage::DownloadCertificatesResponse(vec![certificate]),
+        RpcMessage::CrossChainRequest(Box::new(CrossChainRequest::ConfirmUpdatedRecipient {
+            sender: chain_id,
+            recipient: ChainId(test_hash(3)),
+            latest_heights: vec![(Medium::Direct, BlockHeight(4))],
+        })),
+    ];
+    for message in &messages {
+        check_json_round_trip(message);
+    }
+    check_json_round_trip(&committee());
+    check_json_round_trip(&block());
+}
+
+#[test]
+fn test_json_encodings() {
+    let json = serde_json::to_value(chain_info_response()).unwrap();
+    assert_eq!(json["info"]["chain_id"], ChainId(test_hash(1)).to_string());
+    assert_eq!(
+        json["info"]["chain_balance"],
+        Amount::from_tokens(7).to_string()
+    );
+    assert_eq!(json["info"]["manager"]["current_round"], "Fast");
+    assert!(json["signature"].is_string());
+    let json = serde_json::to_value(committee()).unwrap();
+    let validator = test_validator(1).to_string();
+    assert_eq!(json["validators"][&validator]["votes"], 1);
+}
+
+/// Decoding random bytes must fail gracefully, or produce a value that is serialized back to
+/// the same bytes.
+#[proptest]
+fn decodes_random_bytes(bytes: Vec<u8>) {
+    check_decoding_all(&bytes);
+}
+
+/// Same as `decodes_random_bytes`, with a corrupted byte in a valid serialization.
+#[proptest]
+fn decodes_corrupted_fixtures(index: usize, byte: u8) {
+    for fixture in [
+        BLOCK,
+        LITE_VOTE,
+        CONFIRMED_BLOCK_CERTIFICATE,
+        COMMITTEE,
+        CHAIN_INFO_RESPONSE,
+    ] {
+        let mut bytes = decode_fixture(fixture);
+        let index = index % bytes.len();
+        bytes[index] = byte;
+        check_decoding_all(&bytes);
+        check_decoding_all(&bytes[..index]);
+    }
+}
@@ -24,6 +24,7 @@ mod types {
     pub type ChainManager = Value;
     pub type ChainOwnership = Value;
     pub type ChannelFullName = Value;
+    pub type Effect = Value;
     pub type Epoch = Value;
     pub type MessageBundle = Value;
     pub type MessageKind = Value;
@@ -62,6 +63,7 @@ mod types {
 mod types {
     pub use linera_base::{data_types::UserApplicationDescription, ownership::ChainOwnership};
     pub use linera_chain::{
+        block::Effect,
         data_types::{ChannelFullName, MessageAction, MessageBundle, Origin, Target},
         manager::ChainManager,
     };
@@ -235,6 +237,7 @@ mod from {
                 operations_hash,
                 oracle_responses_hash,
                 events_hash,
+                effects_hash,
             } = header;
             let block::BlockBlockValueBlockBody {
                 incoming_bundles,
@@ -242,6 +245,7 @@ mod from {
                 operations,
                 oracle_responses,
                 events,
+                effects,
             } = body;
 
             let block_header = BlockHeader {
@@ -257,6 +261,7 @@ mod from {
                 operations_hash,
                 oracle_responses_hash,
                 events_hash,
+                effects_hash,
             };
             let block_body = BlockBody {
                 incoming_bundles: incoming_bundles
@@ -273,6 +278,7 @@ mod from {
                     .into_iter()
                     .map(|events| events.into_iter().map(Into::into).collect())
                     .collect(),
+                effects,
             };
 
             Block {
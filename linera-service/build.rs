@@ -5,5 +5,6 @@ fn main() {
     cfg_aliases::cfg_aliases! {
         with_testing: { any(test, feature = "test") },
         with_metrics: { all(not(target_arch = "wasm32"), feature = "metrics") },
+        with_wasmtime: { all(not(target_arch = "wasm32"), feature = "wasmtime") },
     };
 }
@@ -643,9 +643,12 @@ impl LocalNet {
             recv_timeout: Duration::from_secs(1),
             retry_delay: Duration::ZERO,
             max_retries: 0,
+            rate_limit: None,
         });
 
-        Ok(node_provider.make_node(&self.validator_address(validator))?)
+        Ok(node_provider
+            .make_node(&self.validator_address(validator))?
+            .into_inner())
     }
 
     /// Returns the address to connect to a validator's proxy.
@@ -254,6 +254,10 @@ impl ClientWrapper {
             maximum_block_proposal_size,
             maximum_bytes_read_per_block,
             maximum_bytes_written_per_block,
+            maximum_service_fuel_per_query,
+            maximum_wasm_memory_bytes,
+            maximum_wasm_table_elements,
+            maximum_wasm_instances,
         } = policy;
         let mut command = self.command().await?;
         command
@@ -303,6 +307,22 @@ impl ClientWrapper {
             .args([
                 "--maximum-bytes-written-per-block",
                 &maximum_bytes_written_per_block.to_string(),
+            ])
+            .args([
+                "--maximum-service-fuel-per-query",
+                &maximum_service_fuel_per_query.to_string(),
+            ])
+            .args([
+                "--maximum-wasm-memory-bytes",
+                &maximum_wasm_memory_bytes.to_string(),
+            ])
+            .args([
+                "--maximum-wasm-table-elements",
+                &maximum_wasm_table_elements.to_string(),
+            ])
+            .args([
+                "--maximum-wasm-instances",
+                &maximum_wasm_instances.to_string(),
             ]);
         if let Some(seed) = self.testing_prng_seed {
             command.arg("--testing-prng-seed").arg(seed.to_string());
@@ -904,6 +924,18 @@ impl ClientWrapper {
         Ok(())
     }
 
+    /// Removes a validator even if another one would then have a quorum on its own.
+    pub async fn force_remove_validator(&self, name: &str) -> Result<()> {
+        self.command()
+            .await?
+            .arg("remove-validator")
+            .args(["--name", name])
+            .arg("--force")
+            .spawn_and_wait_for_stdout()
+            .await?;
+        Ok(())
+    }
+
     pub async fn finalize_committee(&self) -> Result<()> {
         self.command()
             .await?
@@ -151,7 +151,10 @@ where
                 )));
             }
         };
-        let chain_id = ChainId::child(message_id);
+        let chain_id = certificate
+            .block()
+            .opened_chain_id(&message_id)
+            .ok_or_else(|| Error::new("The block does not open a chain"))?;
         Ok(ClaimOutcome {
             message_id,
             chain_id,
@@ -110,6 +110,8 @@ impl Runnable for Job {
         let Job(options) = self;
         let wallet = options.wallet().await?;
         let mut context = ClientContext::new(storage.clone(), options.clone(), wallet);
+        // Resend the cross-chain requests that a previous run may not have delivered.
+        context.client.local_node().recover().await?;
         let command = options.command;
 
         use ClientCommand::*;
@@ -176,7 +178,10 @@ impl Runnable for Job {
                     })
                     .await
                     .context("Failed to open chain")?;
-                let id = ChainId::child(message_id);
+                let id = certificate
+                    .block()
+                    .opened_chain_id(&message_id)
+                    .context("The block does not open a chain")?;
                 let timestamp = certificate.block().header.timestamp;
                 context
                     .update_wallet_for_new_chain(id, key_pair, timestamp)
@@ -189,7 +194,7 @@ impl Runnable for Job {
                 debug!("{:?}", certificate);
                 // Print the new chain ID and message ID on stdout for scripting purposes.
                 println!("{}", message_id);
-                println!("{}", ChainId::child(message_id));
+                println!("{}", id);
             }
 
             OpenMultiOwnerChain {
@@ -223,7 +228,10 @@ impl Runnable for Job {
                     .context("Failed to open chain")?;
                 // No key pair. This chain can be assigned explicitly using the assign command.
                 let key_pair = None;
-                let id = ChainId::child(message_id);
+                let id = certificate
+                    .block()
+                    .opened_chain_id(&message_id)
+                    .context("The block does not open a chain")?;
                 let timestamp = certificate.block().header.timestamp;
                 context
                     .update_wallet_for_new_chain(id, key_pair, timestamp)
@@ -236,7 +244,7 @@ impl Runnable for Job {
                 debug!("{:?}", certificate);
                 // Print the new chain ID and message ID on stdout for scripting purposes.
                 println!("{}", message_id);
-                println!("{}", ChainId::child(message_id));
+                println!("{}", id);
             }
 
             ChangeOwnership {
@@ -549,6 +557,7 @@ impl Runnable for Job {
                     address,
                     votes: _,
                     skip_online_check: false,
+                    force: _,
                 } = &command
                 {
                     let node = context.make_node_provider().make_node(address)?;
@@ -596,12 +605,18 @@ impl Runnable for Job {
                             let mut committee = chain_client.local_committee().await.unwrap();
                             let mut policy = committee.policy().clone();
                             let mut validators = committee.validators().clone();
+                            let force = matches!(
+                                command,
+                                SetValidator { force: true, .. }
+                                    | RemoveValidator { force: true, .. }
+                            );
                             match command {
                                 SetValidator {
                                     name,
                                     address,
                                     votes,
                                     skip_online_check: _,
+                                    force: _,
                                 } => {
                                     validators.insert(
                                         name,
@@ -611,7 +626,7 @@ impl Runnable for Job {
                                         },
                                     );
                                 }
-                                RemoveValidator { name } => {
+                                RemoveValidator { name, force: _ } => {
                                     if validators.remove(&name).is_none() {
                                         warn!("Skipping removal of nonexistent validator");
                                         return Ok(ClientOutcome::Committed(None));
@@ -637,6 +652,10 @@ impl Runnable for Job {
                                     maximum_block_proposal_size,
                                     maximum_bytes_read_per_block,
                                     maximum_bytes_written_per_block,
+                                    maximum_service_fuel_per_query,
+                                    maximum_wasm_memory_bytes,
+                                    maximum_wasm_table_elements,
+                                    maximum_wasm_instances,
                                 } => {
                                     if let Some(block) = block {
                                         policy.block = block;
@@ -707,6 +726,27 @@ impl Runnable for Job {
                                         policy.maximum_bytes_written_per_block =
                                             maximum_bytes_written_per_block;
                                     }
+                                    if let Some(maximum_service_fuel_per_query) =
+                                        maximum_service_fuel_per_query
+                                    {
+                                        policy.maximum_service_fuel_per_query =
+                                            maximum_service_fuel_per_query;
+                                    }
+                                    if let Some(maximum_wasm_memory_bytes) =
+                                        maximum_wasm_memory_bytes
+                                    {
+                                        policy.maximum_wasm_memory_bytes =
+                                            maximum_wasm_memory_bytes;
+                                    }
+                                    if let Some(maximum_wasm_table_elements) =
+                                        maximum_wasm_table_elements
+                                    {
+                                        policy.maximum_wasm_table_elements =
+                                            maximum_wasm_table_elements;
+                                    }
+                                    if let Some(maximum_wasm_instances) = maximum_wasm_instances {
+                                        policy.maximum_wasm_instances = maximum_wasm_instances;
+                                    }
                                     info!("{policy}");
                                     if committee.policy() == &policy {
                                         return Ok(ClientOutcome::Committed(None));
@@ -714,9 +754,9 @@ impl Runnable for Job {
                                 }
                                 _ => unreachable!(),
                             }
-                            committee = Committee::new(validators, policy);
+                            committee = Committee::new(validators, policy)?;
                             chain_client
-                                .stage_new_committee(committee)
+                                .stage_new_committee(committee, force)
                                 .await
                                 .map(|outcome| outcome.map(Some))
                         }
@@ -1104,13 +1144,12 @@ impl Runnable for Job {
 
             Assign { owner, message_id } => {
                 let start_time = Instant::now();
-                let chain_id = ChainId::child(message_id);
                 info!(
-                    "Linking chain {chain_id} to its corresponding key in the wallet, owned by \
-                    {owner}",
+                    "Linking the chain opened by message {message_id} to its corresponding key \
+                    in the wallet, owned by {owner}",
                 );
-                Self::assign_new_chain_to_key(chain_id, message_id, owner, None, &mut context)
-                    .await?;
+                let chain_id =
+                    Self::assign_new_chain_to_key(message_id, owner, None, &mut context).await?;
                 println!("{}", chain_id);
                 context.save_wallet().await?;
                 info!(
@@ -1220,14 +1259,19 @@ impl Runnable for Job {
                 println!("{}", outcome.chain_id);
                 println!("{}", outcome.message_id);
                 println!("{}", outcome.certificate_hash);
-                Self::assign_new_chain_to_key(
-                    outcome.chain_id,
+                let chain_id = Self::assign_new_chain_to_key(
                     outcome.message_id,
                     owner,
                     Some(validators),
                     &mut context,
                 )
                 .await?;
+                anyhow::ensure!(
+                    chain_id == outcome.chain_id,
+                    "The faucet returned the chain ID {}, but the message opens chain {chain_id}. \
+                    Please make sure you are connecting to a genuine faucet.",
+                    outcome.chain_id
+                );
                 let admin_id = context.wallet().genesis_admin_chain();
                 let chains = with_other_chains
                     .into_iter()
@@ -1258,13 +1302,14 @@ impl Runnable for Job {
 }
 
 impl Job {
+    /// Assigns the chain opened by the message with the given ID to `owner` in the wallet,
+    /// and returns the new chain's ID.
     async fn assign_new_chain_to_key<S>(
-        chain_id: ChainId,
         message_id: MessageId,
         owner: Owner,
         validators: Option<Vec<(ValidatorName, String)>>,
         context: &mut ClientContext<S, impl Persist<Target = Wallet>>,
-    ) -> anyhow::Result<()>
+    ) -> anyhow::Result<ChainId>
     where
         S: Storage + Clone + Send + Sync + 'static,
     {
@@ -1272,7 +1317,7 @@ impl Job {
         let client = context.client.clone_with(
             node_provider.clone(),
             "Temporary client for fetching the parent chain",
-            vec![message_id.chain_id, chain_id],
+            vec![message_id.chain_id],
             false,
         );
 
@@ -1323,6 +1368,15 @@ impl Job {
             "The chain with the ID returned by the faucet is not owned by you. \
             Please make sure you are connecting to a genuine faucet."
         );
+        // The message is addressed to the new chain.
+        let chain_id = executed_block
+            .opened_chain_id(&message_id)
+            .context("could not find the ID of the new chain")?;
+        client.track_chain(chain_id);
+        client
+            .local_node()
+            .retry_pending_cross_chain_requests(message_id.chain_id)
+            .await?;
         context
             .wallet_mut()
             .mutate(|w| {
@@ -1330,7 +1384,7 @@ impl Job {
             })
             .await?
             .context("could not assign the new chain")?;
-        Ok(())
+        Ok(chain_id)
     }
 
     /// Prints a warning message to explain that the wallet has been initialized using data from
@@ -1398,6 +1452,9 @@ fn main() -> anyhow::Result<()> {
 
     linera_base::tracing::init(&log_file_name_for(&options.command));
 
+    #[cfg(with_wasmtime)]
+    options.wasmtime_config.install()?;
+
     let mut runtime = if options.tokio_threads == Some(1) {
         tokio::runtime::Builder::new_current_thread()
     } else {
@@ -1528,6 +1585,10 @@ async fn run(options: &ClientOptions) -> Result<i32, anyhow::Error> {
             maximum_block_proposal_size,
             maximum_bytes_read_per_block,
             maximum_bytes_written_per_block,
+            maximum_service_fuel_per_query,
+            maximum_wasm_memory_bytes,
+            maximum_wasm_table_elements,
+            maximum_wasm_instances,
             testing_prng_seed,
             network_name,
         } => {
@@ -1543,6 +1604,14 @@ async fn run(options: &ClientOptions) -> Result<i32, anyhow::Error> {
             let maximum_published_blobs = maximum_published_blobs.unwrap_or(u64::MAX);
             let maximum_bytecode_size = maximum_bytecode_size.unwrap_or(u64::MAX);
             let maximum_block_proposal_size = maximum_block_proposal_size.unwrap_or(u64::MAX);
+            let maximum_service_fuel_per_query = maximum_service_fuel_per_query
+                .unwrap_or(ResourceControlPolicy::default().maximum_service_fuel_per_query);
+            let maximum_wasm_memory_bytes = maximum_wasm_memory_bytes
+                .unwrap_or(ResourceControlPolicy::default().maximum_wasm_memory_bytes);
+            let maximum_wasm_table_elements = maximum_wasm_table_elements
+                .unwrap_or(ResourceControlPolicy::default().maximum_wasm_table_elements);
+            let maximum_wasm_instances = maximum_wasm_instances
+                .unwrap_or(ResourceControlPolicy::default().maximum_wasm_instances);
             let policy = ResourceControlPolicy {
                 block: *block_price,
                 fuel_unit: *fuel_unit_price,
@@ -1563,6 +1632,10 @@ async fn run(options: &ClientOptions) -> Result<i32, anyhow::Error> {
                 maximum_block_proposal_size,
                 maximum_bytes_read_per_block,
                 maximum_bytes_written_per_block,
+                maximum_service_fuel_per_query,
+                maximum_wasm_memory_bytes,
+                maximum_wasm_table_elements,
+                maximum_wasm_instances,
             };
             let timestamp = start_timestamp
                 .map(|st| {
@@ -18,12 +18,12 @@ use linera_base::{
     data_types::{Amount, ApplicationPermissions, Bytecode, TimeDelta, UserApplicationDescription},
     ensure,
     hashed::Hashed,
-    identifiers::{ApplicationId, BytecodeId, ChainId, Owner, UserApplicationId},
+    identifiers::{ApplicationId, BytecodeId, ChainId, MessageId, Owner, UserApplicationId},
     ownership::{ChainOwnership, TimeoutConfig},
     BcsHexParseError,
 };
 use linera_chain::{
-    types::{ConfirmedBlock, GenericCertificate},
+    types::{ConfirmedBlock, ConfirmedBlockCertificate, GenericCertificate},
     ChainStateView,
 };
 use linera_client::chain_listener::{ChainListener, ChainListenerConfig, ClientContext};
@@ -332,20 +332,18 @@ where
     ) -> Result<ChainId, Error> {
         let ownership = ChainOwnership::single(owner);
         let balance = balance.unwrap_or(Amount::ZERO);
-        let message_id = self
-            .apply_client_command(&chain_id, move |client| {
-                let ownership = ownership.clone();
-                async move {
-                    let result = client
-                        .open_chain(ownership, ApplicationPermissions::default(), balance)
-                        .await
-                        .map_err(Error::from)
-                        .map(|outcome| outcome.map(|(message_id, _)| message_id));
-                    (result, client)
-                }
-            })
-            .await?;
-        Ok(ChainId::child(message_id))
+        self.apply_client_command(&chain_id, move |client| {
+            let ownership = ownership.clone();
+            async move {
+                let result = client
+                    .open_chain(ownership, ApplicationPermissions::default(), balance)
+                    .await
+                    .and_then(|outcome| outcome.try_map(opened_chain_id))
+                    .map_err(Error::from);
+                (result, client)
+            }
+        })
+        .await
     }
 
     /// Creates (or activates) a new chain by installing the given authentication keys.
@@ -403,21 +401,19 @@ where
         };
         let ownership = ChainOwnership::multiple(owners, multi_leader_rounds, timeout_config);
         let balance = balance.unwrap_or(Amount::ZERO);
-        let message_id = self
-            .apply_client_command(&chain_id, move |client| {
-                let ownership = ownership.clone();
-                let application_permissions = application_permissions.clone().unwrap_or_default();
-                async move {
-                    let result = client
-                        .open_chain(ownership, application_permissions, balance)
-                        .await
-                        .map_err(Error::from)
-                        .map(|outcome| outcome.map(|(message_id, _)| message_id));
-                    (result, client)
-                }
-            })
-            .await?;
-        Ok(ChainId::child(message_id))
+        self.apply_client_command(&chain_id, move |client| {
+            let ownership = ownership.clone();
+            let application_permissions = application_permissions.clone().unwrap_or_default();
+            async move {
+                let result = client
+                    .open_chain(ownership, application_permissions, balance)
+                    .await
+                    .and_then(|outcome| outcome.try_map(opened_chain_id))
+                    .map_err(Error::from);
+                (result, client)
+            }
+        })
+        .await
     }
 
     /// Closes the chain. Returns `None` if it was already closed.
@@ -514,6 +510,8 @@ where
         epoch: Epoch,
         committee: Committee,
     ) -> Result<CryptoHash, Error> {
+        // Recompute the thresholds instead of trusting the ones in the input.
+        let committee = Committee::new(committee.validators().clone(), committee.policy().clone())?;
         let operation =
             SystemOperation::Admin(AdminOperation::CreateCommittee { epoch, committee });
         self.execute_system_operation(operation, chain_id).await
@@ -838,6 +836,18 @@ impl ApplicationOverview {
     }
 }
 
+/// Returns the ID of the chain opened by the message with the given ID in the certificate.
+fn opened_chain_id(
+    (message_id, certificate): (MessageId, ConfirmedBlockCertificate),
+) -> Result<ChainId, ChainClientError> {
+    certificate
+        .block()
+        .opened_chain_id(&message_id)
+        .ok_or(ChainClientError::InternalError(
+            "Failed to create new chain",
+        ))
+}
+
 /// Given a parsed GraphQL query (or `ExecutableDocument`), returns the `OperationType`.
 ///
 /// Errors:
@@ -15,12 +15,21 @@ use anyhow::{bail, Context};
 use async_trait::async_trait;
 use futures::{stream::FuturesUnordered, FutureExt as _, StreamExt, TryFutureExt as _};
 use linera_base::crypto::{CryptoRng, KeyPair};
+#[cfg(with_wasmtime)]
+use linera_client::client_options::WasmtimeConfig;
 use linera_client::{
     config::{CommitteeConfig, GenesisConfig, ValidatorConfig, ValidatorServerConfig},
     persistent::{self, Persist},
     storage::{full_initialize_storage, run_with_storage, Runnable, StorageConfigNamespace},
 };
-use linera_core::{worker::WorkerState, JoinSetExt as _};
+use linera_core::{
+    chain_worker::{
+        InactiveChainPolicy, DEFAULT_MAXIMUM_OUTBOXES_PER_RESPONSE,
+        DEFAULT_MAXIMUM_SENT_CERTIFICATE_HASHES_PER_RESPONSE,
+    },
+    worker::WorkerState,
+    JoinSetExt as _,
+};
 use linera_execution::{committee::ValidatorName, WasmRuntime, WithWasmDefault};
 use linera_rpc::{
     config::{
@@ -46,6 +55,9 @@ struct ServerContext {
     shard: Option<usize>,
     grace_period: Duration,
     max_loaded_chains: NonZeroUsize,
+    max_sent_certificate_hashes_per_response: usize,
+    max_outboxes_per_response: usize,
+    inactive_chain_ttl: Option<Duration>,
 }
 
 impl ServerContext {
@@ -67,9 +79,16 @@ impl ServerContext {
             storage,
             self.max_loaded_chains,
         )
-        .with_allow_inactive_chains(false)
+        .with_inactive_chain_policy(match self.inactive_chain_ttl {
+            Some(ttl) => InactiveChainPolicy::AllowRecentlyReferenced { ttl },
+            None => InactiveChainPolicy::Reject,
+        })
         .with_allow_messages_from_deprecated_epochs(false)
-        .with_grace_period(self.grace_period);
+        .with_grace_period(self.grace_period)
+        .with_maximum_sent_certificate_hashes_per_response(
+            self.max_sent_certificate_hashes_per_response,
+        )
+        .with_maximum_outboxes_per_response(self.max_outboxes_per_response);
         (state, shard_id, shard.clone())
     }
 
@@ -349,6 +368,21 @@ enum ServerCommand {
         #[arg(long, default_value = "400")]
         max_loaded_chains: NonZeroUsize,
 
+        /// The maximal number of sent certificate hashes returned in a single chain info
+        /// response. Clients request the rest of longer ranges separately.
+        #[arg(long, default_value_t = DEFAULT_MAXIMUM_SENT_CERTIFICATE_HASHES_PER_RESPONSE)]
+        max_sent_certificate_hashes_per_response: usize,
+
+        /// The maximal number of outbox statuses returned in a single chain info response.
+        /// Clients request the remaining outboxes separately.
+        #[arg(long, default_value_t = DEFAULT_MAXIMUM_OUTBOXES_PER_RESPONSE)]
+        max_outboxes_per_response: usize,
+
+        /// If set, messages to a chain that is not active yet are stored during this time
+        /// after the chain was first referenced, instead of being refused until it is active.
+        #[arg(long = "inactive-chain-ttl-ms", value_parser = util::parse_millis)]
+        inactive_chain_ttl: Option<Duration>,
+
         /// The maximal number of simultaneous queries to the database
         #[arg(long)]
         max_concurrent_queries: Option<usize>,
@@ -360,6 +394,11 @@ enum ServerCommand {
         /// The maximal number of entries in the storage cache.
         #[arg(long, default_value = "1000")]
         cache_size: usize,
+
+        /// Configuration of the Wasmtime runtime.
+        #[cfg(with_wasmtime)]
+        #[command(flatten)]
+        wasmtime_config: WasmtimeConfig,
     },
 
     /// Act as a trusted third-party and generate all server configurations
@@ -499,12 +538,22 @@ async fn run(options: ServerOptions) {
             grace_period,
             wasm_runtime,
             max_loaded_chains,
+            max_sent_certificate_hashes_per_response,
+            max_outboxes_per_response,
+            inactive_chain_ttl,
             max_concurrent_queries,
             max_stream_queries,
             cache_size,
+            #[cfg(with_wasmtime)]
+            wasmtime_config,
         } => {
             linera_version::VERSION_INFO.log();
 
+            #[cfg(with_wasmtime)]
+            wasmtime_config
+                .install()
+                .expect("Failed to configure the Wasmtime runtime");
+
             let genesis_config: GenesisConfig =
                 util::read_json(&genesis_config_path).expect("Failed to read initial chain config");
             let server_config: ValidatorServerConfig =
@@ -524,6 +573,9 @@ async fn run(options: ServerOptions) {
                 shard,
                 grace_period,
                 max_loaded_chains,
+                max_sent_certificate_hashes_per_response,
+                max_outboxes_per_response,
+                inactive_chain_ttl,
             };
             let wasm_runtime = wasm_runtime.with_wasm_default();
             let common_config = CommonStoreConfig {
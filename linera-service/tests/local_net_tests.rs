@@ -176,7 +176,11 @@ async fn test_end_to_end_reconfiguration(config: LocalNetConfig) -> Result<()> {
     // Remove the first 4 validators, so only the last one remains.
     for i in 0..4 {
         let name = net.validator_name(i).unwrap();
-        client.remove_validator(name).await?;
+        if i < 3 {
+            client.remove_validator(name).await?;
+        } else {
+            client.force_remove_validator(name).await?;
+        }
         client.finalize_committee().await?;
         if let Some(service) = &node_service_2 {
             service.process_inbox(&chain_2).await?;
@@ -0,0 +1,59 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{data_types::Blob, identifiers::BlobId};
+use linera_chain::types::ConfirmedBlockCertificate;
+use linera_execution::BlobState;
+
+/// A set of blob and certificate writes that [`Storage::write_batch`](crate::Storage::write_batch)
+/// applies atomically: after a crash, either all of them or none of them are persisted.
+///
+/// Chain states are not part of the batch: they are written by saving the
+/// [`ChainStateView`](linera_chain::ChainStateView), which is itself atomic.
+#[derive(Default)]
+pub struct Batch {
+    /// The blobs to write.
+    pub(crate) blobs: Vec<Blob>,
+    /// The certificates to write.
+    pub(crate) certificates: Vec<ConfirmedBlockCertificate>,
+    /// The blob states to write, unless a newer one exists. If the flag is `false`, an
+    /// existing blob state is never overwritten.
+    pub(crate) blob_states: Vec<(BlobId, BlobState, bool)>,
+}
+
+impl Batch {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns whether the batch contains no writes.
+    pub fn is_empty(&self) -> bool {
+        self.blobs.is_empty() && self.certificates.is_empty() && self.blob_states.is_empty()
+    }
+
+    /// Adds a blob to write.
+    pub fn add_blob(&mut self, blob: Blob) {
+        self.blobs.push(blob);
+    }
+
+    /// Adds a certificate to write.
+    pub fn add_certificate(&mut self, certificate: ConfirmedBlockCertificate) {
+        self.certificates.push(certificate);
+    }
+
+    /// Adds the state of the given blobs, with the same semantics as
+    /// [`Storage::maybe_write_blob_states`](crate::Storage::maybe_write_blob_states).
+    pub fn add_blob_states(
+        &mut self,
+        blob_ids: impl IntoIterator<Item = BlobId>,
+        blob_state: BlobState,
+        overwrite: bool,
+    ) {
+        self.blob_states.extend(
+            blob_ids
+                .into_iter()
+                .map(|blob_id| (blob_id, blob_state.clone(), overwrite)),
+        );
+    }
+}
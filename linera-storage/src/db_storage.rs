@@ -3,7 +3,7 @@
 
 #[cfg(with_metrics)]
 use std::sync::LazyLock;
-use std::{fmt::Debug, sync::Arc};
+use std::{collections::BTreeSet, fmt::Debug, sync::Arc};
 
 use async_trait::async_trait;
 use dashmap::DashMap;
@@ -11,22 +11,22 @@ use linera_base::{
     crypto::CryptoHash,
     data_types::{Blob, TimeDelta, Timestamp},
     hashed::Hashed,
-    identifiers::{BlobId, ChainId, UserApplicationId},
+    identifiers::{BlobId, ChainDescription, ChainId, UserApplicationId},
 };
 use linera_chain::{
     types::{ConfirmedBlock, ConfirmedBlockCertificate, LiteCertificate},
     ChainStateView,
 };
 use linera_execution::{
-    committee::Epoch, BlobState, ExecutionRuntimeConfig, UserContractCode, UserServiceCode,
-    WasmRuntime,
+    committee::Epoch, BlobState, ChildIdDeriver, ExecutionRuntimeConfig, UserContractCode,
+    UserServiceCode, WasmRuntime,
 };
 use linera_views::{
     backends::dual::{DualStoreRootKeyAssignment, StoreInUse},
     batch::Batch,
     context::ViewContext,
-    store::{KeyIterable as _, KeyValueStore},
-    views::{View, ViewError},
+    store::{AdminKeyValueStore, KeyIterable as _, KeyValueIterable as _, KeyValueStore},
+    views::{RootView, View, ViewError},
 };
 use serde::{Deserialize, Serialize};
 #[cfg(with_testing)]
@@ -43,7 +43,7 @@ use {
     prometheus::{HistogramVec, IntCounterVec},
 };
 
-use crate::{ChainRuntimeContext, Clock, Storage};
+use crate::{ChainRuntimeContext, Clock, IdempotencyRecord, Storage};
 
 /// The metric counting how often a blob is tested for existence from storage
 #[cfg(with_metrics)]
@@ -226,13 +226,22 @@ impl BatchExt for Batch {
 
 /// Main implementation of the [`Storage`] trait.
 #[derive(Clone)]
-pub struct DbStorage<Store, Clock> {
+pub struct DbStorage<Store, Clock>
+where
+    Store: AdminKeyValueStore,
+{
     store: Arc<Store>,
+    /// The configuration the store was opened with, to list its root keys.
+    config: Arc<Store::Config>,
+    /// The namespace the store was opened in.
+    namespace: Arc<str>,
     clock: Clock,
     wasm_runtime: Option<WasmRuntime>,
     user_contracts: Arc<DashMap<UserApplicationId, UserContractCode>>,
     user_services: Arc<DashMap<UserApplicationId, UserServiceCode>>,
     execution_runtime_config: ExecutionRuntimeConfig,
+    /// Replaces [`ChainId::child`] to derive the IDs of new chains, in tests.
+    child_id_deriver: Option<Arc<dyn ChildIdDeriver>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -242,6 +251,7 @@ enum BaseKey {
     ConfirmedBlock(CryptoHash),
     Blob(BlobId),
     BlobState(BlobId),
+    IdempotencyRecord(ChainId, u128),
 }
 
 const INDEX_BLOB: u8 = 3;
@@ -480,12 +490,14 @@ where
         hash: CryptoHash,
     ) -> Result<Hashed<ConfirmedBlock>, ViewError> {
         let value_key = bcs::to_bytes(&BaseKey::ConfirmedBlock(hash))?;
-        let maybe_value = self.store.read_value::<ConfirmedBlock>(&value_key).await?;
+        let maybe_value_bytes = self.store.read_value_bytes(&value_key).await?;
         #[cfg(with_metrics)]
         READ_HASHED_CONFIRMED_BLOCK_COUNTER
             .with_label_values(&[])
             .inc();
-        let value = maybe_value.ok_or_else(|| ViewError::not_found("value for hash", hash))?;
+        let value_bytes =
+            maybe_value_bytes.ok_or_else(|| ViewError::not_found("value for hash", hash))?;
+        let value = Self::deserialize_confirmed_block(&value_bytes)?;
         Ok(value.with_hash_unchecked(hash))
     }
 
@@ -576,7 +588,7 @@ where
     async fn write_blob(&self, blob: &Blob) -> Result<(), ViewError> {
         let mut batch = Batch::new();
         batch.add_blob(blob)?;
-        self.write_batch(batch).await?;
+        self.write_store_batch(batch).await?;
         Ok(())
     }
 
@@ -637,7 +649,7 @@ where
             latest_epochs.push(latest_epoch);
         }
         if need_write {
-            self.write_batch(batch).await?;
+            self.write_store_batch(batch).await?;
         }
         Ok(latest_epochs)
     }
@@ -649,7 +661,7 @@ where
     ) -> Result<(), ViewError> {
         let mut batch = Batch::new();
         batch.add_blob_state(blob_id, blob_state)?;
-        self.write_batch(batch).await?;
+        self.write_store_batch(batch).await?;
         Ok(())
     }
 
@@ -668,7 +680,7 @@ where
                 batch.add_blob(blob)?;
             }
         }
-        self.write_batch(batch).await?;
+        self.write_store_batch(batch).await?;
         Ok(blob_states)
     }
 
@@ -680,7 +692,7 @@ where
         for blob in blobs {
             batch.add_blob(blob)?;
         }
-        self.write_batch(batch).await
+        self.write_store_batch(batch).await
     }
 
     async fn write_blobs_and_certificate(
@@ -693,7 +705,46 @@ where
             batch.add_blob(blob)?;
         }
         batch.add_certificate(certificate)?;
-        self.write_batch(batch).await
+        self.write_store_batch(batch).await
+    }
+
+    async fn write_batch(&self, storage_batch: crate::Batch) -> Result<(), ViewError> {
+        let crate::Batch {
+            blobs,
+            certificates,
+            blob_states,
+        } = storage_batch;
+        let mut batch = Batch::new();
+        for blob in &blobs {
+            batch.add_blob(blob)?;
+        }
+        for certificate in &certificates {
+            batch.add_certificate(certificate)?;
+        }
+        if !blob_states.is_empty() {
+            let blob_state_keys = blob_states
+                .iter()
+                .map(|(blob_id, _, _)| bcs::to_bytes(&BaseKey::BlobState(*blob_id)))
+                .collect::<Result<_, _>>()?;
+            let current_blob_states = self
+                .store
+                .read_multi_values::<BlobState>(blob_state_keys)
+                .await?;
+            for ((blob_id, blob_state, overwrite), current_blob_state) in
+                blob_states.iter().zip(current_blob_states)
+            {
+                let should_write = match current_blob_state {
+                    None => true,
+                    Some(current_blob_state) => {
+                        *overwrite && current_blob_state.epoch < blob_state.epoch
+                    }
+                };
+                if should_write {
+                    batch.add_blob_state(*blob_id, blob_state)?;
+                }
+            }
+        }
+        self.write_store_batch(batch).await
     }
 
     async fn contains_certificate(&self, hash: CryptoHash) -> Result<bool, ViewError> {
@@ -741,9 +792,91 @@ where
         Ok(certificates)
     }
 
+    async fn delete_certificate(&self, hash: CryptoHash) -> Result<(), ViewError> {
+        let mut batch = Batch::new();
+        for key in Self::get_keys_for_certificates(&[hash])? {
+            batch.delete_key(key);
+        }
+        self.write_store_batch(batch).await
+    }
+
+    async fn read_idempotency_record(
+        &self,
+        chain_id: ChainId,
+        key: u128,
+    ) -> Result<Option<IdempotencyRecord>, ViewError> {
+        let record_key = bcs::to_bytes(&BaseKey::IdempotencyRecord(chain_id, key))?;
+        Ok(self
+            .store
+            .read_value::<IdempotencyRecord>(&record_key)
+            .await?)
+    }
+
+    async fn write_idempotency_record(
+        &self,
+        chain_id: ChainId,
+        key: u128,
+        record: IdempotencyRecord,
+    ) -> Result<(), ViewError> {
+        let mut batch = Batch::new();
+        let record_key = bcs::to_bytes(&BaseKey::IdempotencyRecord(chain_id, key))?;
+        batch.put_key_value(record_key, &record)?;
+        self.write_store_batch(batch).await
+    }
+
+    async fn prune_idempotency_records(
+        &self,
+        chain_id: ChainId,
+        time: Timestamp,
+    ) -> Result<(), ViewError> {
+        // The records of the chain are the keys that start like this one, without its `u128`.
+        let mut prefix = bcs::to_bytes(&BaseKey::IdempotencyRecord(chain_id, 0))?;
+        prefix.truncate(prefix.len() - std::mem::size_of::<u128>());
+        let mut batch = Batch::new();
+        for entry in self
+            .store
+            .find_key_values_by_prefix(&prefix)
+            .await?
+            .iterator()
+        {
+            let (suffix, value) = entry?;
+            let record = bcs::from_bytes::<IdempotencyRecord>(value)?;
+            if record.timestamp < time {
+                batch.delete_key([prefix.as_slice(), suffix].concat());
+            }
+        }
+        self.write_store_batch(batch).await
+    }
+
+    async fn delete_chain(&self, chain_id: ChainId) -> Result<(), ViewError> {
+        let mut chain = self.load_chain(chain_id).await?;
+        chain.clear();
+        chain.save().await
+    }
+
+    async fn list_chain_ids(&self) -> Result<Vec<ChainId>, ViewError> {
+        let root_keys = Store::list_root_keys(&self.config, &self.namespace).await?;
+        // Other root keys, e.g. the one of `self.store`, don't deserialize as a chain state.
+        let chain_ids = root_keys
+            .iter()
+            .filter_map(|root_key| match bcs::from_bytes(root_key) {
+                Ok(BaseKey::ChainState(chain_id)) => Some(chain_id),
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>();
+        Ok(chain_ids.into_iter().collect())
+    }
+
     fn wasm_runtime(&self) -> Option<WasmRuntime> {
         self.wasm_runtime
     }
+
+    fn chain_id_of(&self, description: ChainDescription) -> ChainId {
+        match (description, &self.child_id_deriver) {
+            (ChainDescription::Child(message_id), Some(deriver)) => deriver.child_id(message_id),
+            _ => description.into(),
+        }
+    }
 }
 
 impl<Store, C> DbStorage<Store, C>
@@ -774,26 +907,41 @@ where
             .as_ref()
             .ok_or_else(|| ViewError::not_found("value bytes for hash", hash))?;
         let cert = bcs::from_bytes::<LiteCertificate>(cert_bytes)?;
-        let value = bcs::from_bytes::<ConfirmedBlock>(value_bytes)?;
+        let value = Self::deserialize_confirmed_block(value_bytes)?;
         let certificate = cert
             .with_value(value.with_hash_unchecked(hash))
             .ok_or(ViewError::InconsistentEntries)?;
         Ok(certificate)
     }
 
-    async fn write_batch(&self, batch: Batch) -> Result<(), ViewError> {
+    fn deserialize_confirmed_block(bytes: &[u8]) -> Result<ConfirmedBlock, ViewError> {
+        // Blocks written before the effects were certified are still readable.
+        Ok(bcs::from_bytes::<ConfirmedBlock>(bytes)
+            .or_else(|_| ConfirmedBlock::from_legacy_bytes(bytes))?)
+    }
+
+    async fn write_store_batch(&self, batch: Batch) -> Result<(), ViewError> {
         self.store.write_batch(batch).await?;
         Ok(())
     }
 
-    fn create(store: Store, wasm_runtime: Option<WasmRuntime>, clock: C) -> Self {
+    fn create(
+        store: Store,
+        config: Store::Config,
+        namespace: &str,
+        wasm_runtime: Option<WasmRuntime>,
+        clock: C,
+    ) -> Self {
         Self {
             store: Arc::new(store),
+            config: Arc::new(config),
+            namespace: Arc::from(namespace),
             clock,
             wasm_runtime,
             user_contracts: Arc::new(DashMap::new()),
             user_services: Arc::new(DashMap::new()),
             execution_runtime_config: ExecutionRuntimeConfig::default(),
+            child_id_deriver: None,
         }
     }
 }
@@ -810,7 +958,13 @@ where
         wasm_runtime: Option<WasmRuntime>,
     ) -> Result<Self, Store::Error> {
         let store = Store::maybe_create_and_connect(&config, namespace, root_key).await?;
-        Ok(Self::create(store, wasm_runtime, WallClock))
+        Ok(Self::create(
+            store,
+            config,
+            namespace,
+            wasm_runtime,
+            WallClock,
+        ))
     }
 
     pub async fn new(
@@ -820,7 +974,13 @@ where
         wasm_runtime: Option<WasmRuntime>,
     ) -> Result<Self, Store::Error> {
         let store = Store::connect(&config, namespace, root_key).await?;
-        Ok(Self::create(store, wasm_runtime, WallClock))
+        Ok(Self::create(
+            store,
+            config,
+            namespace,
+            wasm_runtime,
+            WallClock,
+        ))
     }
 }
 
@@ -853,6 +1013,15 @@ where
         clock: TestClock,
     ) -> Result<Self, Store::Error> {
         let store = Store::recreate_and_connect(&config, namespace, root_key).await?;
-        Ok(Self::create(store, wasm_runtime, clock))
+        Ok(Self::create(store, config, namespace, wasm_runtime, clock))
+    }
+
+    /// Makes new chains get their IDs from `deriver` instead of [`ChainId::child`].
+    ///
+    /// All the storages of a network, i.e. of the validators and clients, must use the same
+    /// deriver.
+    pub fn with_child_id_deriver(mut self, deriver: impl ChildIdDeriver + 'static) -> Self {
+        self.child_id_deriver = Some(Arc::new(deriver));
+        self
     }
 }
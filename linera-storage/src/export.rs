@@ -0,0 +1,82 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::BTreeSet, io::Write};
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Blob, BlockHeight},
+    identifiers::{BlobId, ChainId},
+};
+use linera_chain::types::ConfirmedBlockCertificate;
+use linera_views::views::ViewError;
+use serde::{Deserialize, Serialize};
+
+use crate::Storage;
+
+/// The version of the chain export format described by [`ChainExportHeader`].
+pub const CHAIN_EXPORT_VERSION: u32 = 2;
+
+/// The first record of the export of a single chain. It is followed by the chain's
+/// certificates as [`ExportItem`]s, in the same order as in [`export_all`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChainExportHeader {
+    /// The version of the format, i.e. [`CHAIN_EXPORT_VERSION`].
+    pub version: u32,
+    /// The exported chain.
+    pub chain_id: ChainId,
+    /// The height of the first exported block. The blocks below it were pruned.
+    pub first_block_height: BlockHeight,
+    /// The number of exported blocks.
+    pub next_block_height: BlockHeight,
+    /// The hash of the last exported block.
+    pub block_hash: Option<CryptoHash>,
+    /// The hash of the chain's execution state after the last exported block.
+    pub state_hash: Option<CryptoHash>,
+}
+
+/// An item written by [`export_all`].
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ExportItem {
+    /// A blob that is required by the next certificate.
+    Blob(Blob),
+    /// The confirmed certificate of a block.
+    Certificate(ConfirmedBlockCertificate),
+}
+
+/// Writes the confirmed certificates of all chains to `writer`, ordered by chain ID and block
+/// height, each one preceded by the blobs it requires that were not written before. The
+/// certificates of pruned blocks are not stored any more, so each chain starts at its pruned
+/// height.
+///
+/// Every [`ExportItem`] is BCS-serialized on its own, as soon as it is read, so the export is
+/// never held in memory as a whole. This can be called while chains are being written: the
+/// certificates are only ever appended, so the export contains each chain up to some height.
+pub async fn export_all<S, W>(storage: &S, mut writer: W) -> Result<(), ViewError>
+where
+    S: Storage,
+    W: Write + Send,
+{
+    let mut exported_blob_ids = BTreeSet::<BlobId>::new();
+    for chain_id in storage.list_chain_ids().await? {
+        let hashes = storage
+            .list_certificate_hashes(chain_id, BlockHeight::ZERO..BlockHeight::MAX)
+            .await?;
+        for hash in hashes {
+            let certificate = storage.read_certificate(hash).await?;
+            let blob_ids = certificate
+                .block()
+                .required_blob_ids()
+                .into_iter()
+                .filter(|blob_id| !exported_blob_ids.contains(blob_id))
+                .collect::<Vec<_>>();
+            for (blob_id, maybe_blob) in blob_ids.iter().zip(storage.read_blobs(&blob_ids).await?) {
+                let blob = maybe_blob.ok_or_else(|| ViewError::BlobsNotFound(vec![*blob_id]))?;
+                bcs::serialize_into(&mut writer, &ExportItem::Blob(blob))?;
+                exported_blob_ids.insert(*blob_id);
+            }
+            bcs::serialize_into(&mut writer, &ExportItem::Certificate(certificate))?;
+        }
+    }
+    Ok(())
+}
@@ -5,9 +5,11 @@
 
 #![deny(clippy::large_futures)]
 
+mod batch;
 mod db_storage;
+mod export;
 
-use std::sync::Arc;
+use std::{ops::Range, sync::Arc};
 
 use async_trait::async_trait;
 use dashmap::{mapref::entry::Entry, DashMap};
@@ -16,7 +18,8 @@ use linera_base::{
     data_types::{Amount, Blob, BlockHeight, TimeDelta, Timestamp, UserApplicationDescription},
     hashed::Hashed,
     identifiers::{
-        BlobId, ChainDescription, ChainId, GenericApplicationId, Owner, UserApplicationId,
+        BlobId, ChainDescription, ChainId, GenericApplicationId, MessageId, Owner,
+        UserApplicationId,
     },
     ownership::ChainOwnership,
 };
@@ -35,12 +38,14 @@ use linera_views::{
     context::Context,
     views::{CryptoHashView, RootView, ViewError},
 };
+use serde::{Deserialize, Serialize};
 #[cfg(with_wasm_runtime)]
 use {
     linera_base::{data_types::CompressedBytecode, identifiers::BlobType},
     linera_execution::{WasmContractModule, WasmServiceModule},
 };
 
+pub use crate::batch::Batch;
 #[cfg(with_testing)]
 pub use crate::db_storage::TestClock;
 pub use crate::db_storage::{list_all_blob_ids, ChainStatesFirstAssignment, DbStorage, WallClock};
@@ -48,6 +53,7 @@ pub use crate::db_storage::{list_all_blob_ids, ChainStatesFirstAssignment, DbSto
 pub use crate::db_storage::{
     READ_CERTIFICATE_COUNTER, READ_HASHED_CONFIRMED_BLOCK_COUNTER, WRITE_CERTIFICATE_COUNTER,
 };
+pub use crate::export::{export_all, ChainExportHeader, ExportItem, CHAIN_EXPORT_VERSION};
 
 /// Communicate with a persistent storage using the "views" abstraction.
 #[cfg_attr(not(web), async_trait)]
@@ -148,6 +154,9 @@ pub trait Storage: Sized {
     /// Writes several blobs.
     async fn write_blobs(&self, blobs: &[Blob]) -> Result<(), ViewError>;
 
+    /// Applies all the writes in the batch atomically.
+    async fn write_batch(&self, batch: Batch) -> Result<(), ViewError>;
+
     /// Tests existence of the certificate with the given hash.
     async fn contains_certificate(&self, hash: CryptoHash) -> Result<bool, ViewError>;
 
@@ -163,6 +172,66 @@ pub trait Storage: Sized {
         hashes: I,
     ) -> Result<Vec<ConfirmedBlockCertificate>, ViewError>;
 
+    /// Deletes the certificate with the given hash, if it exists.
+    async fn delete_certificate(&self, hash: CryptoHash) -> Result<(), ViewError>;
+
+    /// Reads the record of the block that was last proposed on the given chain for the
+    /// client-chosen idempotency `key`, if any.
+    async fn read_idempotency_record(
+        &self,
+        chain_id: ChainId,
+        key: u128,
+    ) -> Result<Option<IdempotencyRecord>, ViewError>;
+
+    /// Records the block that is being proposed on the given chain for the idempotency `key`,
+    /// replacing any previous record for that key.
+    async fn write_idempotency_record(
+        &self,
+        chain_id: ChainId,
+        key: u128,
+        record: IdempotencyRecord,
+    ) -> Result<(), ViewError>;
+
+    /// Deletes the idempotency records of the given chain that were written before `time`.
+    async fn prune_idempotency_records(
+        &self,
+        chain_id: ChainId,
+        time: Timestamp,
+    ) -> Result<(), ViewError>;
+
+    /// Deletes the state of the given chain, if it exists.
+    ///
+    /// # Notes
+    ///
+    /// The chain must not be loaded anywhere else at the same time, otherwise the deleted
+    /// state may be written again.
+    async fn delete_chain(&self, chain_id: ChainId) -> Result<(), ViewError>;
+
+    /// Lists the IDs of the chains whose state is stored, in ascending order.
+    ///
+    /// This can be called while chains are being written. Chains that are created in the
+    /// meantime may or may not be included, and some backends also list chains that were
+    /// loaded but never saved.
+    async fn list_chain_ids(&self) -> Result<Vec<ChainId>, ViewError>;
+
+    /// Returns the hashes of the confirmed certificates of the given chain at the block
+    /// heights in `range`, in ascending order of height. Heights the chain has not reached
+    /// yet are skipped, and so are heights below the chain's pruned height, whose
+    /// certificates may have been deleted.
+    async fn list_certificate_hashes(
+        &self,
+        chain_id: ChainId,
+        range: Range<BlockHeight>,
+    ) -> Result<Vec<CryptoHash>, ViewError> {
+        let to_index = |height: BlockHeight| usize::try_from(height.0).unwrap_or(usize::MAX);
+        let chain = self.load_chain(chain_id).await?;
+        let start = range.start.max(*chain.pruned_height.get());
+        chain
+            .confirmed_log
+            .read(to_index(start)..to_index(range.end))
+            .await
+    }
+
     /// Loads the view of a chain state and checks that it is active.
     ///
     /// # Notes
@@ -207,7 +276,7 @@ pub trait Storage: Sized {
     where
         ChainRuntimeContext<Self>: ExecutionRuntimeContext,
     {
-        let id = description.into();
+        let id = self.chain_id_of(description);
         let mut chain = self.load_chain(id).await?;
         assert!(!chain.is_active(), "Attempting to create a chain twice");
         chain.manager.reset(
@@ -255,6 +324,12 @@ pub trait Storage: Sized {
     /// Selects the WebAssembly runtime to use for applications (if any).
     fn wasm_runtime(&self) -> Option<WasmRuntime>;
 
+    /// Returns the ID of the chain with the given description. For child chains, this is
+    /// [`ChainId::child`] unless a test replaced the derivation.
+    fn chain_id_of(&self, description: ChainDescription) -> ChainId {
+        description.into()
+    }
+
     /// Creates a [`UserContractCode`] instance using the bytecode in storage referenced
     /// by the `application_description`.
     #[cfg(with_wasm_runtime)]
@@ -341,6 +416,18 @@ pub trait Storage: Sized {
     }
 }
 
+/// The block last proposed by a client for an idempotency key, see
+/// [`Storage::write_idempotency_record`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    /// The hash of the operations that were requested with the key.
+    pub operations_hash: CryptoHash,
+    /// The hash of the confirmed block that was proposed for the key.
+    pub block_hash: CryptoHash,
+    /// When the record was written.
+    pub timestamp: Timestamp,
+}
+
 #[derive(Clone)]
 pub struct ChainRuntimeContext<S> {
     storage: S,
@@ -408,6 +495,11 @@ where
         self.storage.contains_blob(blob_id).await
     }
 
+    fn child_chain_id(&self, message_id: MessageId) -> ChainId {
+        self.storage
+            .chain_id_of(ChainDescription::Child(message_id))
+    }
+
     #[cfg(with_testing)]
     async fn add_blobs(
         &self,
@@ -324,6 +324,43 @@ fn generate_hash_view_code(input: ItemStruct) -> TokenStream2 {
     }
 }
 
+fn generate_field_hashes_code(input: ItemStruct) -> TokenStream2 {
+    let struct_name = input.ident;
+    let (impl_generics, type_generics, maybe_where_clause) = input.generics.split_for_impl();
+    let template_vect = get_seq_parameter(input.generics.clone());
+
+    let (_, context_constraints) = context_and_constraints(&input.attrs, &template_vect);
+
+    let mut where_clause = maybe_where_clause
+        .cloned()
+        .unwrap_or_else(empty_where_clause);
+    where_clause
+        .predicates
+        .extend(context_constraints.predicates);
+
+    let field_hashes = input.fields.iter().map(|field| {
+        let name = field.ident.clone().unwrap();
+        let name_str = name.to_string();
+        quote! { (#name_str, self.#name.hash().await?.as_ref().to_vec()), }
+    });
+
+    quote! {
+        impl #impl_generics #struct_name #type_generics
+        #where_clause
+        {
+            /// Returns the names and hashes of the fields, in the order in which they are
+            /// combined into the hash of the view.
+            #[allow(dead_code)]
+            pub async fn field_hashes(
+                &self,
+            ) -> Result<Vec<(&'static str, Vec<u8>)>, linera_views::views::ViewError> {
+                use linera_views::views::HashableView;
+                Ok(vec![#(#field_hashes)*])
+            }
+        }
+    }
+}
+
 fn generate_crypto_hash_code(input: ItemStruct) -> TokenStream2 {
     let struct_name = input.ident;
     let (impl_generics, type_generics, maybe_where_clause) = input.generics.split_for_impl();
@@ -415,7 +452,8 @@ pub fn derive_view(input: TokenStream) -> TokenStream {
 pub fn derive_hash_view(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as ItemStruct);
     let mut stream = generate_view_code(input.clone(), false);
-    stream.extend(generate_hash_view_code(input));
+    stream.extend(generate_hash_view_code(input.clone()));
+    stream.extend(generate_field_hashes_code(input));
     stream.into()
 }
 
@@ -432,6 +470,7 @@ pub fn derive_crypto_hash_view(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as ItemStruct);
     let mut stream = generate_view_code(input.clone(), false);
     stream.extend(generate_hash_view_code(input.clone()));
+    stream.extend(generate_field_hashes_code(input.clone()));
     stream.extend(generate_crypto_hash_code(input));
     stream.into()
 }
@@ -442,6 +481,7 @@ pub fn derive_crypto_hash_root_view(input: TokenStream) -> TokenStream {
     let mut stream = generate_view_code(input.clone(), true);
     stream.extend(generate_save_delete_view_code(input.clone()));
     stream.extend(generate_hash_view_code(input.clone()));
+    stream.extend(generate_field_hashes_code(input.clone()));
     stream.extend(generate_crypto_hash_code(input));
     stream.into()
 }
@@ -452,7 +492,8 @@ pub fn derive_hashable_root_view(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as ItemStruct);
     let mut stream = generate_view_code(input.clone(), true);
     stream.extend(generate_save_delete_view_code(input.clone()));
-    stream.extend(generate_hash_view_code(input));
+    stream.extend(generate_hash_view_code(input.clone()));
+    stream.extend(generate_field_hashes_code(input));
     stream.into()
 }
 
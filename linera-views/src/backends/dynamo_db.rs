@@ -10,11 +10,13 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    time::Duration,
 };
 
 use async_lock::{Semaphore, SemaphoreGuard};
 use async_trait::async_trait;
 use aws_sdk_dynamodb::{
+    config::retry::RetryConfig,
     error::SdkError,
     operation::{
         batch_write_item::BatchWriteItemError,
@@ -60,12 +62,31 @@ const LOCALSTACK_ENDPOINT: &str = "LOCALSTACK_ENDPOINT";
 /// The configuration to connect to DynamoDB.
 pub type Config = aws_sdk_dynamodb::Config;
 
+/// The maximum number of attempts for each request, including the first one.
+const MAX_REQUEST_ATTEMPTS: u32 = 8;
+
+/// The delay before the first retry of a request. It grows exponentially with each retry.
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Returns how to retry requests that failed with a transient error.
+///
+/// In standard mode, throttling errors such as `ProvisionedThroughputExceededException`
+/// are retried with exponential backoff and jitter.
+fn get_retry_config() -> RetryConfig {
+    RetryConfig::standard()
+        .with_max_attempts(MAX_REQUEST_ATTEMPTS)
+        .with_initial_backoff(INITIAL_RETRY_BACKOFF)
+}
+
 /// Gets the AWS configuration from the environment
 async fn get_base_config() -> Result<aws_sdk_dynamodb::Config, DynamoDbStoreInternalError> {
     let base_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest())
         .boxed()
         .await;
-    Ok((&base_config).into())
+    let config = aws_sdk_dynamodb::config::Builder::from(&base_config)
+        .retry_config(get_retry_config())
+        .build();
+    Ok(config)
 }
 
 fn get_endpoint_address() -> Option<String> {
@@ -84,6 +105,7 @@ async fn get_localstack_config() -> Result<aws_sdk_dynamodb::Config, DynamoDbSto
     let endpoint_address = get_endpoint_address().unwrap();
     let config = aws_sdk_dynamodb::config::Builder::from(&base_config)
         .endpoint_url(endpoint_address)
+        .retry_config(get_retry_config())
         .build();
     Ok(config)
 }
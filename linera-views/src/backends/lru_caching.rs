@@ -48,6 +48,9 @@ struct LruPrefixCache {
     map: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
     queue: LinkedHashMap<Vec<u8>, (), RandomState>,
     max_cache_size: usize,
+    /// The number of batches written so far. A value read from the store is only cached if
+    /// no batch was written in the meantime, since it may be outdated otherwise.
+    generation: u64,
 }
 
 impl<'a> LruPrefixCache {
@@ -57,6 +60,7 @@ impl<'a> LruPrefixCache {
             map: BTreeMap::new(),
             queue: LinkedHashMap::new(),
             max_cache_size,
+            generation: 0,
         }
     }
 
@@ -96,10 +100,15 @@ impl<'a> LruPrefixCache {
 }
 
 /// We take a store, a maximum size and build a LRU-based system.
+///
+/// The cache is shared by all clones of the store, including those obtained with
+/// `clone_with_root_key`, so the cache size bounds the total number of entries.
 #[derive(Clone)]
 pub struct LruCachingStore<K> {
     /// The inner store that is called by the LRU cache one
     store: K,
+    /// The prefix of the keys in the cache: the serialized root key of the store.
+    cache_prefix: Vec<u8>,
     lru_read_values: Option<Arc<Mutex<LruPrefixCache>>>,
 }
 
@@ -127,27 +136,31 @@ where
         let Some(lru_read_values) = &self.lru_read_values else {
             return self.store.read_value_bytes(key).await;
         };
+        let cache_key = self.cache_key(key);
         // First inquiring in the read_value_bytes LRU
-        {
+        let generation = {
             let lru_read_values_container = lru_read_values.lock().unwrap();
-            if let Some(value) = lru_read_values_container.query(key) {
+            if let Some(value) = lru_read_values_container.query(&cache_key) {
                 #[cfg(with_metrics)]
                 NUM_CACHE_SUCCESS.with_label_values(&[]).inc();
                 return Ok(value.clone());
             }
-        }
+            lru_read_values_container.generation
+        };
         #[cfg(with_metrics)]
         NUM_CACHE_FAULT.with_label_values(&[]).inc();
         let value = self.store.read_value_bytes(key).await?;
         let mut lru_read_values = lru_read_values.lock().unwrap();
-        lru_read_values.insert(key.to_vec(), value.clone());
+        if lru_read_values.generation == generation {
+            lru_read_values.insert(cache_key, value.clone());
+        }
         Ok(value)
     }
 
     async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
         if let Some(values) = &self.lru_read_values {
             let values = values.lock().unwrap();
-            if let Some(value) = values.query(key) {
+            if let Some(value) = values.query(&self.cache_key(key)) {
                 return Ok(value.is_some());
             }
         }
@@ -165,7 +178,7 @@ where
         {
             let values = values.lock().unwrap();
             for i in 0..size {
-                if let Some(value) = values.query(&keys[i]) {
+                if let Some(value) = values.query(&self.cache_key(&keys[i])) {
                     results[i] = value.is_some();
                 } else {
                     indices.push(i);
@@ -193,10 +206,10 @@ where
         let mut result = Vec::with_capacity(keys.len());
         let mut cache_miss_indices = Vec::new();
         let mut miss_keys = Vec::new();
-        {
+        let generation = {
             let lru_read_values_container = lru_read_values.lock().unwrap();
             for (i, key) in keys.into_iter().enumerate() {
-                if let Some(value) = lru_read_values_container.query(&key) {
+                if let Some(value) = lru_read_values_container.query(&self.cache_key(&key)) {
                     #[cfg(with_metrics)]
                     NUM_CACHE_SUCCESS.with_label_values(&[]).inc();
                     result.push(value.clone());
@@ -208,18 +221,22 @@ where
                     miss_keys.push(key);
                 }
             }
-        }
+            lru_read_values_container.generation
+        };
         if !miss_keys.is_empty() {
             let values = self
                 .store
                 .read_multi_values_bytes(miss_keys.clone())
                 .await?;
             let mut lru_read_values = lru_read_values.lock().unwrap();
+            let is_current = lru_read_values.generation == generation;
             for (i, (key, value)) in cache_miss_indices
                 .into_iter()
                 .zip(miss_keys.into_iter().zip(values))
             {
-                lru_read_values.insert(key, value.clone());
+                if is_current {
+                    lru_read_values.insert(self.cache_key(&key), value.clone());
+                }
                 result[i] = value;
             }
         }
@@ -252,16 +269,17 @@ where
 
         {
             let mut lru_read_values = lru_read_values.lock().unwrap();
+            lru_read_values.generation += 1;
             for operation in &batch.operations {
                 match operation {
                     WriteOperation::Put { key, value } => {
-                        lru_read_values.insert(key.to_vec(), Some(value.to_vec()));
+                        lru_read_values.insert(self.cache_key(key), Some(value.to_vec()));
                     }
                     WriteOperation::Delete { key } => {
-                        lru_read_values.insert(key.to_vec(), None);
+                        lru_read_values.insert(self.cache_key(key), None);
                     }
                     WriteOperation::DeletePrefix { key_prefix } => {
-                        lru_read_values.delete_prefix(key_prefix);
+                        lru_read_values.delete_prefix(&self.cache_key(key_prefix));
                     }
                 }
             }
@@ -299,13 +317,20 @@ where
     ) -> Result<Self, Self::Error> {
         let store = K::connect(&config.inner_config, namespace, root_key).await?;
         let cache_size = config.cache_size;
-        Ok(LruCachingStore::new(store, cache_size))
+        Ok(LruCachingStore {
+            store,
+            cache_prefix: cache_prefix(root_key),
+            lru_read_values: new_lru_prefix_cache(cache_size),
+        })
     }
 
     fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, Self::Error> {
         let store = self.store.clone_with_root_key(root_key)?;
-        let cache_size = self.cache_size();
-        Ok(LruCachingStore::new(store, cache_size))
+        Ok(LruCachingStore {
+            store,
+            cache_prefix: cache_prefix(root_key),
+            lru_read_values: self.lru_read_values.clone(),
+        })
     }
 
     async fn list_all(config: &Self::Config) -> Result<Vec<String>, Self::Error> {
@@ -359,16 +384,31 @@ fn new_lru_prefix_cache(cache_size: usize) -> Option<Arc<Mutex<LruPrefixCache>>>
     }
 }
 
+/// Returns the prefix of the cache keys for a store with the given root key.
+///
+/// The root key is serialized with its length, so that no prefix is a prefix of another one.
+fn cache_prefix(root_key: &[u8]) -> Vec<u8> {
+    bcs::to_bytes(root_key).expect("serializing a byte slice should not fail")
+}
+
 impl<K> LruCachingStore<K> {
     /// Creates a new key-value store that provides LRU caching at top of the given store.
+    ///
+    /// The given store is assumed to use the empty root key.
     pub fn new(store: K, cache_size: usize) -> Self {
         let lru_read_values = new_lru_prefix_cache(cache_size);
         Self {
             store,
+            cache_prefix: cache_prefix(&[]),
             lru_read_values,
         }
     }
 
+    /// Returns the key under which the value of `key` is cached.
+    fn cache_key(&self, key: &[u8]) -> Vec<u8> {
+        [self.cache_prefix.as_slice(), key].concat()
+    }
+
     /// Gets the `cache_size`
     pub fn cache_size(&self) -> usize {
         match &self.lru_read_values {
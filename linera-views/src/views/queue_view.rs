@@ -349,6 +349,52 @@ where
         Ok(values)
     }
 
+    /// Reads the values at the positions in `range`, counted from the front of the queue
+    /// (including staged ones). The range is truncated to the length of the queue.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::queue_view::QueueView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut queue = QueueView::load(context).await.unwrap();
+    /// queue.push_back(34);
+    /// queue.push_back(37);
+    /// queue.push_back(42);
+    /// assert_eq!(queue.read_range(1..5).await.unwrap(), vec![37, 42]);
+    /// # })
+    /// ```
+    pub async fn read_range(&self, range: Range<usize>) -> Result<Vec<T>, ViewError> {
+        let end = range.end.min(self.count());
+        let start = range.start.min(end);
+        if start == end {
+            return Ok(Vec::new());
+        }
+        let mut values = Vec::with_capacity(end - start);
+        if self.delete_storage_first {
+            values.extend(self.new_back_values.range(start..end).cloned());
+            return Ok(values);
+        }
+        let stored_remainder = self.stored_count();
+        let stored_start = self.stored_indices.end - stored_remainder;
+        if start < stored_remainder {
+            let stored_end = end.min(stored_remainder);
+            values.extend(
+                self.read_context((stored_start + start)..(stored_start + stored_end))
+                    .await?,
+            );
+        }
+        if end > stored_remainder {
+            let new_start = start.saturating_sub(stored_remainder);
+            values.extend(
+                self.new_back_values
+                    .range(new_start..(end - stored_remainder))
+                    .cloned(),
+            );
+        }
+        Ok(values)
+    }
+
     /// Reads the `count` last values in the queue (including staged ones).
     /// ```rust
     /// # tokio_test::block_on(async {
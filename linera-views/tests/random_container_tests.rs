@@ -588,6 +588,14 @@ async fn queue_view_mutability_check() -> Result<()> {
                 assert_ne!(new_hash, hash);
             }
             assert_eq!(new_elements, new_vector);
+            for _ in 0..3 {
+                let end = rng.gen_range(0..new_vector.len() + 2);
+                let start = rng.gen_range(0..end + 1);
+                let vec1 = view.queue.read_range(start..end).await?;
+                let vec2 =
+                    new_vector[start.min(new_vector.len())..end.min(new_vector.len())].to_vec();
+                assert_eq!(vec1, vec2);
+            }
         }
         if save {
             if vector != new_vector {
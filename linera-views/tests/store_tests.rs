@@ -5,9 +5,13 @@ use linera_views::{
     batch::Batch,
     context::{create_test_memory_context, Context as _},
     key_value_store_view::ViewContainer,
+    lru_caching::LruCachingMemoryStore,
     memory::MemoryStore,
     random::make_deterministic_rng,
-    store::TestKeyValueStore as _,
+    store::{
+        AdminKeyValueStore as _, ReadableKeyValueStore as _, TestKeyValueStore as _,
+        WritableKeyValueStore as _,
+    },
     test_utils::{
         big_read_multi_values, get_random_test_scenarios, run_big_write_read, run_reads,
         run_writes_from_blank, run_writes_from_state,
@@ -256,6 +260,34 @@ async fn test_dynamo_db_big_write_read() {
     run_big_write_read(store, target_size, value_sizes).await;
 }
 
+#[tokio::test]
+async fn test_lru_caching_store_shared_by_clones() {
+    let store = LruCachingMemoryStore::new_test_store().await.unwrap();
+    let store1 = store.clone_with_root_key(&[1]).unwrap();
+    let store2 = store.clone_with_root_key(&[1]).unwrap();
+    let other_store = store.clone_with_root_key(&[]).unwrap();
+    // Both clones now have the missing value in the cache.
+    assert_eq!(store1.read_value_bytes(&[2]).await.unwrap(), None);
+    assert_eq!(store2.read_value_bytes(&[2]).await.unwrap(), None);
+    assert_eq!(other_store.read_value_bytes(&[1, 2]).await.unwrap(), None);
+
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![2], vec![3]);
+    store1.write_batch(batch).await.unwrap();
+    assert_eq!(store2.read_value_bytes(&[2]).await.unwrap(), Some(vec![3]));
+    assert_eq!(
+        store2.read_multi_values_bytes(vec![vec![2]]).await.unwrap(),
+        vec![Some(vec![3])]
+    );
+    // The same key under another root key is unaffected.
+    assert_eq!(other_store.read_value_bytes(&[1, 2]).await.unwrap(), None);
+
+    let mut batch = Batch::new();
+    batch.delete_key_prefix(vec![2]);
+    store2.write_batch(batch).await.unwrap();
+    assert!(!store1.contains_key(&[2]).await.unwrap());
+}
+
 #[tokio::test]
 async fn test_memory_writes_from_state() {
     let store = MemoryStore::new_test_store().await.unwrap();